@@ -4,8 +4,10 @@ use std::{path::PathBuf, sync::Arc};
 
 use cap_editor::create_segments;
 use cap_media::sources::get_target_fps;
-use cap_project::{RecordingMeta, XY};
-use cap_rendering::RenderVideoConstants;
+use cap_project::{
+    ProjectConfiguration, RecordingMeta, Take, TimelineConfiguration, TimelineSegment, XY,
+};
+use cap_rendering::{FrameRateConversion, RenderVideoConstants};
 use clap::{Args, Parser, Subcommand};
 use record::RecordStart;
 use serde_json::json;
@@ -21,8 +23,16 @@ struct Cli {
 enum Commands {
     /// Export a '.cap' project to an mp4 file
     Export(Export),
+    /// Render a '.cap' project with a preset, on a machine with no display or GPU
+    Render(Render),
     /// Start a recording or list available capture targets and devices
     Record(RecordArgs),
+    /// Combine several '.cap' projects into one, played back to back
+    Merge(Merge),
+    /// Play another '.cap' project within one, without copying it in
+    Splice(Splice),
+    /// Style '.cap' projects like a template, keeping their own timelines
+    ApplyTemplate(ApplyTemplate),
 }
 
 #[derive(Args)]
@@ -44,7 +54,13 @@ enum RecordCommands {
     Windows,
     /// List cameras available for capturing
     Cameras,
-    // Mics,
+    /// List microphones available for capturing
+    Mics,
+    /// Stop a recording running in another process
+    Stop {
+        /// Path of the '.cap' project being recorded
+        path: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -53,6 +69,10 @@ async fn main() -> Result<(), String> {
 
     match cli.command {
         Commands::Export(e) => e.run().await,
+        Commands::Render(r) => r.run().await?,
+        Commands::Merge(m) => m.run().await?,
+        Commands::Splice(s) => s.run()?,
+        Commands::ApplyTemplate(t) => t.run()?,
         Commands::Record(RecordArgs { command, args }) => match command {
             Some(RecordCommands::Screens) => {
                 let screens = cap_media::sources::list_screens();
@@ -122,10 +142,30 @@ window {}:
 
                 println!("{}", serde_json::to_string_pretty(&info).unwrap());
             }
+            Some(RecordCommands::Mics) => {
+                let mics = cap_media::feeds::AudioInputFeed::list_devices();
+
+                for (i, (name, (_, config))) in mics.iter().enumerate() {
+                    println!(
+                        "
+mic {}:
+  name: {}
+  sample rate: {}
+  channels: {}",
+                        i,
+                        name,
+                        config.sample_rate().0,
+                        config.channels()
+                    );
+                }
+            }
+            Some(RecordCommands::Stop { path }) => {
+                record::request_stop(&path)?;
+                println!("Asked the recording at '{}' to stop", path.display());
+            }
             None => {
                 args.run().await?;
             }
-            _ => {}
         },
     }
 
@@ -136,18 +176,88 @@ window {}:
 struct Export {
     project_path: PathBuf,
     output_path: Option<PathBuf>,
+    /// Export the screen and camera as separate synchronized files
+    #[arg(long)]
+    split: bool,
+    /// Burn a timecode and project revision into every frame
+    #[arg(long)]
+    draft: bool,
+    /// Run the effect in this folder over every frame, in the order given
+    #[arg(long = "effect")]
+    effects: Vec<PathBuf>,
+    /// Export only this part of the composition, as normalized "x,y,width,height"
+    #[arg(long)]
+    region: Option<cap_export::ExportRegion>,
+    /// Output size: 720p, 1080p, 4k, vertical, square or WIDTHxHEIGHT
+    #[arg(long, default_value = "1080p")]
+    resolution: cap_export::ExportResolution,
+    /// Frame rate to export at, defaulting to the recording's
+    #[arg(long)]
+    fps: Option<u32>,
+    /// Blend recorded frames instead of repeating them when conforming to the export frame rate
+    #[arg(long)]
+    blend_frames: bool,
+    /// h264, hevc, prores422, prores4444, ffv1, utvideo, vp9 or av1
+    #[arg(long, default_value = "h264")]
+    codec: cap_export::ExportCodec,
+    /// Target bit rate for HEVC, VP9 and AV1, in bits per second
+    #[arg(long)]
+    bit_rate: Option<u32>,
+    /// Quality from 0 to 100 for HEVC, VP9 and AV1, used instead of a bit rate
+    #[arg(long)]
+    quality: Option<u8>,
+    /// Encode twice to land close to this many bytes, instead of using a bit rate or quality
+    #[arg(long)]
+    target_size: Option<u64>,
+    /// Encode HEVC as HDR10 (PQ, BT.2020). Experimental: SDR content is mapped into HDR10
+    /// at reference white, and HDR recordings are still tone-mapped first.
+    #[arg(long)]
+    hdr10: bool,
+    /// Leave out frames that repeat the last, making a smaller, variable frame rate file.
+    /// Some editors can't handle those.
+    #[arg(long)]
+    skip_static_frames: bool,
+    /// Export an animated gif or webp instead of a video, at up to 15 fps
+    #[arg(long)]
+    animated: Option<cap_export::AnimatedFormat>,
+    /// Render the animation smaller until it's under this many bytes
+    #[arg(long, requires = "animated")]
+    max_size: Option<u64>,
+    /// Export every frame as a numbered image instead of a video
+    #[arg(long)]
+    frames: bool,
+    /// Export only the frame this many seconds in, as an image
+    #[arg(long, conflicts_with = "frames")]
+    still: Option<f64>,
+    /// png or jpeg, for --frames and --still
+    #[arg(long, default_value = "png")]
+    image_format: cap_export::StillFormat,
+    /// Render in chunks kept beside the output, so running the same export again after an
+    /// interruption continues where it stopped
+    #[arg(long, conflicts_with_all = ["animated", "split"])]
+    resume: bool,
+    /// Render this many chunks of the timeline at once, which implies --resume
+    #[arg(long, default_value_t = 1, conflicts_with_all = ["animated", "split"])]
+    workers: usize,
+    /// Print what would be rendered as JSON instead of exporting
+    #[arg(long)]
+    dry_run: bool,
 }
 
 impl Export {
     async fn run(self) {
-        let project = serde_json::from_reader(
-            std::fs::File::open(self.project_path.join("project-config.json")).unwrap(),
-        )
-        .unwrap();
+        let mut project = ProjectConfiguration::load(&self.project_path).unwrap();
 
-        let meta = RecordingMeta::load_for_project(&self.project_path).unwrap();
+        let meta = RecordingMeta::load_for_project(&self.project_path)
+            .unwrap()
+            .with_takes(&project.takes)
+            .unwrap();
         let recordings = cap_rendering::ProjectRecordings::new(&meta);
 
+        project.timeline.get_or_insert_with(|| {
+            TimelineConfiguration::from_segment_durations(&recordings.segment_durations())
+        });
+
         let render_options = cap_rendering::RenderOptions {
             screen_size: XY::new(
                 recordings.segments[0].display.width,
@@ -157,6 +267,13 @@ impl Export {
                 .camera
                 .as_ref()
                 .map(|c| XY::new(c.width, c.height)),
+            deterministic: project.determinism.enabled,
+            // the exporter turns this on for its own renders
+            high_quality_scaling: false,
+            gpu: None,
+            screen_color: recordings.segments[0].display.color,
+            // the exporter turns this on for HDR10 exports
+            hdr10: false,
         };
         let render_constants = Arc::new(
             RenderVideoConstants::new(render_options, &meta)
@@ -164,11 +281,11 @@ impl Export {
                 .unwrap(),
         );
 
-        let segments = create_segments(&meta).await.unwrap();
+        let segments = create_segments(&meta, false).await.unwrap();
 
-        let fps = meta.content.max_fps();
+        let fps = self.fps.unwrap_or_else(|| meta.content.max_fps());
         let project_output_path = self.project_path.join("output/result.mp4");
-        let exporter = cap_export::Exporter::new(
+        let mut exporter = cap_export::Exporter::new(
             project,
             project_output_path.clone(),
             |_| {},
@@ -177,13 +294,97 @@ impl Export {
             render_constants,
             &segments,
             fps,
-            XY::new(1920, 1080),
+            self.resolution.resolution_base(),
             true,
         )
         .await
-        .unwrap();
+        .unwrap()
+        .with_draft(self.draft)
+        .with_frame_rate_conversion(if self.blend_frames {
+            FrameRateConversion::Blend
+        } else {
+            FrameRateConversion::Duplicate
+        })
+        .with_settings(cap_export::ExportSettings {
+            codec: self.codec,
+            bit_rate: self.bit_rate,
+            quality: self.quality,
+            target_size_kib: self.target_size.map(|bytes| (bytes / 1024) as u32),
+            hdr10: self.hdr10,
+            skip_static_frames: self.skip_static_frames,
+        })
+        .with_workers(self.workers);
+
+        if let Some(aspect_ratio) = self.resolution.aspect_ratio() {
+            exporter = exporter.with_aspect_ratio(aspect_ratio);
+        }
+
+        if let Some(region) = self.region {
+            exporter = exporter.with_region(region);
+        }
+
+        for dir in &self.effects {
+            exporter = exporter.with_effect(cap_export::load_effect(dir).unwrap());
+        }
+
+        if self.dry_run {
+            let plan = exporter.dry_run();
+            println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+
+            return;
+        }
+
+        if self.frames {
+            let output_dir = exporter.export_frames(self.image_format).await.unwrap();
+            println!("Exported frames to '{}'", output_dir.display());
+
+            return;
+        }
+
+        if let Some(time) = self.still {
+            let project_output_path = exporter
+                .export_still(time, self.image_format)
+                .await
+                .unwrap();
+
+            let output_path = if let Some(output_path) = self.output_path {
+                std::fs::copy(&project_output_path, &output_path).unwrap();
+                output_path
+            } else {
+                project_output_path
+            };
+
+            println!("Exported frame to '{}'", output_path.display());
+
+            return;
+        }
+
+        if self.split {
+            let manifest = exporter.export_split().await.unwrap();
 
-        exporter.export_with_custom_muxer().await.unwrap();
+            println!("Exported screen to '{}'", manifest.screen.path.display());
+            if let Some(camera) = manifest.camera {
+                println!("Exported camera to '{}'", camera.path.display());
+            }
+
+            return;
+        }
+
+        let project_output_path = if let Some(format) = self.animated {
+            exporter
+                .export_animated(cap_export::AnimatedExportSettings {
+                    format,
+                    quality: self.quality.unwrap_or(80),
+                    max_size_kib: self.max_size.map(|bytes| (bytes / 1024) as u32),
+                    ..Default::default()
+                })
+                .await
+                .unwrap()
+        } else if self.resume || self.workers > 1 {
+            exporter.export_resumable().await.unwrap()
+        } else {
+            exporter.export_with_custom_muxer().await.unwrap()
+        };
 
         let output_path = if let Some(output_path) = self.output_path {
             std::fs::copy(&project_output_path, &output_path).unwrap();
@@ -196,6 +397,177 @@ impl Export {
     }
 }
 
+#[derive(Args)]
+struct Render {
+    project_path: PathBuf,
+    /// Where to write the video, with the codec's extension. Defaults to the project's output.
+    output_path: Option<PathBuf>,
+    /// JSON file with the resolution, fps and codec settings to export with
+    #[arg(long)]
+    preset: Option<PathBuf>,
+    /// Render on the CPU even when there's a GPU
+    #[arg(long)]
+    software: bool,
+}
+
+impl Render {
+    async fn run(self) -> Result<(), String> {
+        let mut preset = match &self.preset {
+            Some(path) => cap_export::RenderPreset::load(path)
+                .map_err(|e| format!("Failed to read preset '{}': {e}", path.display()))?,
+            None => cap_export::RenderPreset::default(),
+        };
+        preset.software |= self.software;
+
+        let output_path =
+            cap_export::render_project(&self.project_path, &preset, self.output_path, |progress| {
+                if progress.frames_done % 100 == 0 {
+                    eprintln!(
+                        "{:?}: {}/{} frames",
+                        progress.stage, progress.frames_done, progress.total_frames
+                    );
+                }
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        println!("Rendered video to '{}'", output_path.display());
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct Merge {
+    /// Projects to combine, in playback order
+    #[arg(required = true)]
+    project_paths: Vec<PathBuf>,
+    /// Where to create the combined project
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+impl Merge {
+    async fn run(self) -> Result<(), String> {
+        let pretty_name = self
+            .output
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Merged recording".to_string());
+
+        cap_editor::merge_projects(&self.project_paths, self.output.clone(), pretty_name).await?;
+
+        println!("Merged project written to '{}'", self.output.display());
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct Splice {
+    /// Project to splice into
+    project_path: PathBuf,
+    /// Project to play from, like a retake of part of the first
+    take_path: PathBuf,
+    /// Where on the timeline to play it, in seconds
+    #[arg(long, default_value_t = 0.0)]
+    at: f64,
+}
+
+impl Splice {
+    fn run(self) -> Result<(), String> {
+        let mut project =
+            ProjectConfiguration::load(&self.project_path).map_err(|e| e.to_string())?;
+        let meta =
+            RecordingMeta::load_for_project(&self.project_path)?.with_takes(&project.takes)?;
+        let take_meta = RecordingMeta::load_for_project(&self.take_path)?;
+
+        let recordings = cap_rendering::ProjectRecordings::new(&meta);
+        // the take's segments are numbered after those already there
+        let first_segment = recordings.segments.len() as u32;
+        let take_durations = cap_rendering::ProjectRecordings::new(&take_meta).segment_durations();
+
+        let timeline = project.timeline.get_or_insert_with(|| {
+            TimelineConfiguration::from_segment_durations(&recordings.segment_durations())
+        });
+        if self.at < 0.0 || self.at > timeline.duration() {
+            return Err(format!(
+                "{} is outside the project's {:.2}s timeline",
+                self.at,
+                timeline.duration()
+            ));
+        }
+
+        let mut time = self.at;
+        for (i, duration) in take_durations.iter().enumerate() {
+            timeline.insert_segment(
+                time,
+                TimelineSegment {
+                    recording_segment: first_segment + i as u32,
+                    timescale: 1.0,
+                    start: 0.0,
+                    end: *duration,
+                    display: 0,
+                    camera_position: None,
+                    transition: None,
+                },
+            );
+            time += duration;
+        }
+
+        project
+            .takes
+            .push(Take::new(&self.project_path, &self.take_path)?);
+        project
+            .write(&self.project_path)
+            .map_err(|e| e.to_string())?;
+
+        println!("Spliced '{}' in at {}s", self.take_path.display(), self.at);
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct ApplyTemplate {
+    /// A 'project-config.json', or a '.cap' project to copy the config of
+    template: PathBuf,
+    /// Projects to apply it to
+    #[arg(required = true)]
+    project_paths: Vec<PathBuf>,
+}
+
+impl ApplyTemplate {
+    fn run(self) -> Result<(), String> {
+        let template = if self.template.is_dir() {
+            ProjectConfiguration::load(&self.template)
+        } else {
+            std::fs::read_to_string(&self.template)
+                .map_err(Into::into)
+                .and_then(|json| ProjectConfiguration::from_json(&json))
+        }
+        .map_err(|e| format!("Failed to read template '{}': {e}", self.template.display()))?;
+
+        for project_path in &self.project_paths {
+            let project = ProjectConfiguration::load(project_path)
+                .map_err(|e| format!("Failed to read project '{}': {e}", project_path.display()))?;
+
+            // what's cut and spliced in belongs to the recording, not the style
+            ProjectConfiguration {
+                timeline: project.timeline,
+                takes: project.takes,
+                ..template.clone()
+            }
+            .write(project_path)
+            .map_err(|e| e.to_string())?;
+
+            println!("Applied template to '{}'", project_path.display());
+        }
+
+        Ok(())
+    }
+}
+
 // fn ffmpeg_callback_experiment() {
 //     unsafe {
 //         unsafe extern "C" fn ffmpeg_log_callback(
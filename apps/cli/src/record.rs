@@ -1,12 +1,25 @@
-use std::{env::current_dir, hash::Hash, path::PathBuf, sync::Arc};
+use std::{
+    env::current_dir,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use cap_media::{feeds::CameraFeed, sources::ScreenCaptureTarget};
-use cap_recording::RecordingOptions;
+use cap_media::{
+    feeds::{AudioInputFeed, CameraFeed},
+    sources::{CaptureWindow, ScreenCaptureTarget},
+};
+use cap_recording::{ActorEvent, RecordingOptions};
 use clap::Args;
 use nokhwa::utils::{ApiBackend, CameraIndex};
 use tokio::{io::AsyncBufReadExt, sync::Mutex};
 use uuid::Uuid;
 
+// dropped into a project by `cap record stop` to stop the process recording it
+const STOP_REQUEST_FILE: &str = ".stop-requested";
+const STOP_REQUEST_POLL: Duration = Duration::from_millis(250);
+
 #[derive(Args)]
 pub struct RecordStart {
     #[command(flatten)]
@@ -14,15 +27,37 @@ pub struct RecordStart {
     /// Index of the camera to record
     #[arg(long)]
     camera: Option<u32>,
-    /// ID of the microphone to record
+    /// Name of the microphone to record, as listed by `cap record mics`
     #[arg(long)]
-    mic: Option<u32>,
+    mic: Option<String>,
     #[arg(long)]
     /// Path to save the '.cap' project to
     path: Option<PathBuf>,
     /// Maximum fps to record at (max 60)
     #[arg(long)]
     fps: Option<u32>,
+    /// ID of another screen to record into its own track, can be repeated
+    #[arg(long = "also-screen")]
+    also_screens: Vec<u32>,
+    /// Only keep the last this many seconds, which are saved once recording is stopped
+    #[arg(long)]
+    replay_buffer: Option<u32>,
+    /// Seconds to wait before recording starts
+    #[arg(long)]
+    start_in: Option<u32>,
+    /// Stop recording after this many seconds
+    #[arg(long)]
+    max_duration: Option<u32>,
+    /// Record what the system plays into its own audio track
+    #[arg(long)]
+    system_audio: bool,
+    /// Record the bounds of the UI element under the cursor, so Auto zoom can frame the whole
+    /// control. Needs accessibility permission on macOS.
+    #[arg(long)]
+    ui_elements: bool,
+    /// Record key presses for the editor's keystroke overlay
+    #[arg(long)]
+    keyboard: bool,
 }
 
 impl RecordStart {
@@ -42,12 +77,32 @@ impl RecordStart {
                     cap_media::sources::list_windows()
                         .into_iter()
                         .find(|s| s.0.id == id)
-                        .map(|(s, t)| (ScreenCaptureTarget::Window(s), t))
+                        .map(|(s, t)| {
+                            (
+                                ScreenCaptureTarget::Window(CaptureWindow {
+                                    isolated: self.target.isolate_window,
+                                    ..s
+                                }),
+                                t,
+                            )
+                        })
                         .ok_or(format!("Window with id '{id}' not found"))
                 })
             })
             .ok_or("No target specified".to_string())??;
 
+        let additional_displays = self
+            .also_screens
+            .iter()
+            .map(|&id| {
+                cap_media::sources::list_screens()
+                    .into_iter()
+                    .find(|s| s.0.id == id)
+                    .map(|(s, _)| s)
+                    .ok_or(format!("Screen with id '{id}' not found"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         let camera = if let Some(camera_index) = self.camera {
             if let Some(camera_info) = nokhwa::query(ApiBackend::Auto)
                 .unwrap()
@@ -64,38 +119,127 @@ impl RecordStart {
             None
         };
 
+        let audio_input = match &self.mic {
+            Some(name) => Some(
+                AudioInputFeed::init(name)
+                    .await
+                    .map_err(|e| format!("Failed to open mic '{name}': {e}"))?,
+            ),
+            None => None,
+        };
+
         let id = Uuid::new_v4().to_string();
         let path = self
             .path
             .unwrap_or_else(|| current_dir().unwrap().join(format!("{id}.cap")));
+        let stop_request = path.join(STOP_REQUEST_FILE);
+        // left over from an earlier recording into the same place
+        std::fs::remove_file(&stop_request).ok();
 
-        let actor = cap_recording::spawn_recording_actor(
+        let prepared = cap_recording::prepare_recording(
             id,
-            path,
+            path.clone(),
             RecordingOptions {
                 capture_target: target_info,
                 camera_label: camera.as_ref().map(|c| c.camera_info.human_name()),
-                audio_input_name: None,
+                audio_input_name: self.mic.clone(),
+                sample_ui_elements: self.ui_elements,
+                record_keyboard: self.keyboard,
+                additional_displays,
+                replay_buffer_secs: self.replay_buffer,
+                max_duration_secs: self.max_duration,
+                capture_system_audio: self.system_audio,
             },
             camera.map(|c| Arc::new(Mutex::new(c))),
-            None,
+            audio_input,
         )
         .await
         .map_err(|e| e.to_string())?;
 
-        println!("Recording starting, press Enter to stop");
+        let (actor, _) = match self.start_in {
+            Some(secs) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+                prepared.schedule(now.as_secs_f64() + secs as f64).await
+            }
+            None => prepared.start().await,
+        }
+        .map_err(|e| e.to_string())?;
 
-        tokio::io::BufReader::new(tokio::io::stdin())
-            .read_line(&mut String::new())
-            .await
-            .unwrap();
+        println!("Recording to '{}'", path.display());
+        match self.replay_buffer {
+            Some(secs) => println!("Replay buffer starting, press Enter to save the last {secs}s"),
+            None => println!("Recording starting, press Enter to stop"),
+        }
+
+        let mut stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        // scripts and CI usually run without one, which reads as closed straight away
+        let mut stdin_open = true;
+        let mut poll_stop_request = tokio::time::interval(STOP_REQUEST_POLL);
+        let terminated = terminated();
+        tokio::pin!(terminated);
+        loop {
+            tokio::select! {
+                read = stdin.read_line(&mut String::new()), if stdin_open => match read {
+                    Ok(0) | Err(_) => stdin_open = false,
+                    Ok(_) => break,
+                },
+                _ = &mut terminated => break,
+                _ = poll_stop_request.tick() => {
+                    if stop_request.exists() {
+                        std::fs::remove_file(&stop_request).ok();
+                        break;
+                    }
+                }
+                event = actor.events.recv_async() => match event {
+                    Ok(ActorEvent::StartsIn(secs)) => println!("Starting in {secs}s"),
+                    Ok(ActorEvent::Started) => println!("Recording started"),
+                    Ok(ActorEvent::StopsIn(_)) => {}
+                    Ok(ActorEvent::LowDiskSpace(bytes)) => {
+                        println!("Low disk space, {} MB left", bytes / 1_000_000)
+                    }
+                    Ok(ActorEvent::DiskFull) => println!("Disk almost full, stopping recording"),
+                    Ok(ActorEvent::AutoStopped) | Err(_) => break,
+                },
+            }
+        }
 
-        actor.0.stop().await.unwrap();
+        actor.stop().await.map_err(|e| e.to_string())?;
+        println!("Recording saved to '{}'", path.display());
 
         Ok(())
     }
 }
 
+// Asks the process recording into `path` to stop, which it notices within a moment
+pub fn request_stop(path: &Path) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err(format!("No recording at '{}'", path.display()));
+    }
+
+    std::fs::write(path.join(STOP_REQUEST_FILE), []).map_err(|e| e.to_string())
+}
+
+// Resolves on Ctrl+C, or when the process is asked to terminate
+async fn terminated() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+            tokio::signal::ctrl_c().await.ok();
+            return;
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+}
+
 #[derive(Args)]
 struct RecordTargets {
     /// ID of the screen to capture
@@ -104,4 +248,7 @@ struct RecordTargets {
     /// ID of the window to capture
     #[arg(long, group = "target")]
     window: Option<u32>,
+    /// Capture only the window's contents, even while other windows cover it
+    #[arg(long, requires = "window")]
+    isolate_window: bool,
 }
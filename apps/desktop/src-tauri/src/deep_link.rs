@@ -0,0 +1,404 @@
+// Lets other apps and launcher workflows drive Cap through links like
+// `cap://record/start?screen=1&mic=MacBook%20Pro%20Microphone`. The actions are:
+//
+//   record/start           screen, window (an id or name), camera, mic ("none" turns one off),
+//                          systemAudio. Asks the user before it starts.
+//   record/stop
+//   record/stop-and-export output, a new file in the recordings folder, outside any project, to
+//                          export to instead of the project's output file
+//   record/marker          marks the current moment on the recording's timeline
+//   project/open           id, or path to a project in the recordings folder
+//
+// Links for anything else, like signing in, are left to the windows listening for them.
+
+use cap_export::ExportSettings;
+use cap_media::sources::{list_screens, list_windows, ScreenCaptureTarget};
+use cap_project::{RecordingMeta, XY};
+use cap_rendering::FrameRateConversion;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::{oneshot, RwLock};
+
+use crate::{
+    export_queue::{self, ExportJobRequest},
+    recording, recordings_path, set_recording_options, show_editor, App,
+};
+
+// what exports started from a link are scaled to fit
+const EXPORT_RESOLUTION: XY<u32> = XY { x: 1920, y: 1080 };
+
+#[derive(Debug)]
+enum DeepLinkAction {
+    StartRecording {
+        screen: Option<String>,
+        window: Option<String>,
+        // Some(None) turns the device off
+        camera: Option<Option<String>>,
+        mic: Option<Option<String>>,
+        system_audio: Option<bool>,
+    },
+    StopRecording {
+        export: bool,
+        output: Option<PathBuf>,
+    },
+    AddMarker,
+    OpenProject {
+        id: String,
+    },
+}
+
+impl DeepLinkAction {
+    // None for links that aren't actions
+    fn parse(url: &Url) -> Option<Self> {
+        if !matches!(url.scheme(), "cap" | "cap-desktop") {
+            return None;
+        }
+
+        let action = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+        let params = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+        let device = |name: &str| {
+            params
+                .get(name)
+                .map(|v| (!v.eq_ignore_ascii_case("none") && !v.is_empty()).then(|| v.clone()))
+        };
+
+        Some(match action.trim_end_matches('/') {
+            "record/start" => Self::StartRecording {
+                screen: params.get("screen").cloned(),
+                window: params.get("window").cloned(),
+                camera: device("camera"),
+                mic: device("mic"),
+                system_audio: params
+                    .get("systemAudio")
+                    .map(|v| matches!(v.as_str(), "1" | "true")),
+            },
+            "record/stop" => Self::StopRecording {
+                export: false,
+                output: None,
+            },
+            "record/stop-and-export" => Self::StopRecording {
+                export: true,
+                output: params.get("output").map(PathBuf::from),
+            },
+            "record/marker" => Self::AddMarker,
+            "project/open" => Self::OpenProject {
+                id: params.get("id").cloned().or_else(|| {
+                    let path = PathBuf::from(params.get("path")?);
+                    Some(path.file_stem()?.to_string_lossy().to_string())
+                })?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    // Linux and Windows only know about the schemes once the app has registered them
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    app.deep_link().register_all().ok();
+
+    // a link that launched the app
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        dispatch(app, urls);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        dispatch(&handle, event.urls());
+    });
+}
+
+fn dispatch(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        let Some(action) = DeepLinkAction::parse(&url) else {
+            continue;
+        };
+
+        tracing::info!("Deep link: {action:?}");
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = run(&app, action).await {
+                tracing::error!("Deep link '{url}' failed: {e}");
+            }
+        });
+    }
+}
+
+async fn run(app: &AppHandle, action: DeepLinkAction) -> Result<(), String> {
+    let state = app.state::<Arc<RwLock<App>>>();
+
+    match action {
+        DeepLinkAction::StartRecording {
+            screen,
+            window,
+            camera,
+            mic,
+            system_audio,
+        } => {
+            let mut options = {
+                let state = state.read().await;
+                if state.current_recording.is_some() {
+                    return Err("A recording is already in progress".to_string());
+                }
+                state.start_recording_options.clone()
+            };
+
+            if let Some(screen) = screen {
+                options.capture_target = ScreenCaptureTarget::Screen(
+                    list_screens()
+                        .into_iter()
+                        .map(|(s, _)| s)
+                        .find(|s| s.id.to_string() == screen || s.name == screen)
+                        .ok_or(format!("Screen '{screen}' not found"))?,
+                );
+            } else if let Some(window) = window {
+                options.capture_target = ScreenCaptureTarget::Window(
+                    list_windows()
+                        .into_iter()
+                        .map(|(w, _)| w)
+                        .find(|w| {
+                            w.id.to_string() == window || w.name == window || w.owner_name == window
+                        })
+                        .ok_or(format!("Window '{window}' not found"))?,
+                );
+            }
+            if let Some(camera) = camera {
+                options.camera_label = camera;
+            }
+            if let Some(mic) = mic {
+                options.audio_input_name = mic;
+            }
+            if let Some(system_audio) = system_audio {
+                options.capture_system_audio = system_audio;
+            }
+
+            // any website or app can open a link, so nothing's recorded until the user says so
+            let mut details = vec![match &options.capture_target {
+                ScreenCaptureTarget::Screen(screen) => format!("Screen: {}", screen.name),
+                ScreenCaptureTarget::Window(window) => {
+                    format!("Window: {} ({})", window.name, window.owner_name)
+                }
+                ScreenCaptureTarget::Area(area) => format!("Part of {}", area.screen.name),
+            }];
+            if let Some(camera) = &options.camera_label {
+                details.push(format!("Camera: {camera}"));
+            }
+            if let Some(mic) = &options.audio_input_name {
+                details.push(format!("Microphone: {mic}"));
+            }
+            if options.capture_system_audio {
+                details.push("System audio".to_string());
+            }
+
+            if !confirm(
+                app,
+                "Start Recording?",
+                format!(
+                    "A link opened from another app or website wants Cap to start recording.\n\n{}",
+                    details.join("\n")
+                ),
+                "Start Recording",
+            )
+            .await
+            {
+                return Err("The user didn't allow the recording".to_string());
+            }
+
+            set_recording_options(app.clone(), app.state(), options).await?;
+            recording::start_recording(app.clone(), app.state()).await
+        }
+        DeepLinkAction::StopRecording { export, output } => {
+            let output = output.map(|output| export_path(app, &output)).transpose()?;
+
+            let project_path = {
+                let mut state = state.write().await;
+                recording::stop_current_recording(app.clone(), &mut state).await?
+            };
+
+            if !export {
+                return Ok(());
+            }
+
+            let meta = RecordingMeta::load_for_project(&project_path)?;
+            let video_id = project_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .ok_or("The recording has no id")?;
+
+            export_queue::queue_exports(
+                app.clone(),
+                app.state(),
+                vec![ExportJobRequest {
+                    video_id,
+                    output_path: output.unwrap_or_else(|| meta.output_path()),
+                    fps: meta.content.max_fps(),
+                    resolution_base: EXPORT_RESOLUTION,
+                    aspect_ratio: None,
+                    frame_rate_conversion: FrameRateConversion::default(),
+                    settings: ExportSettings::default(),
+                    draft: false,
+                }],
+            )
+            .await
+            .map(|_| ())
+        }
+        DeepLinkAction::AddMarker => {
+            if state.read().await.current_recording.is_none() {
+                return Err("There's no recording in progress".to_string());
+            }
+
+            recording::add_recording_marker(app.state()).await
+        }
+        DeepLinkAction::OpenProject { id } => {
+            check_project_id(&recordings_path(app), &id)?;
+            show_editor(app, id)
+        }
+    }
+}
+
+// Asks the user to go ahead with something a link asked for, false if they didn't
+async fn confirm(app: &AppHandle, title: &str, message: String, ok: &str) -> bool {
+    let (tx, rx) = oneshot::channel();
+
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            ok.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            tx.send(confirmed).ok();
+        });
+
+    rx.await.unwrap_or(false)
+}
+
+// Where to export to for an `output` a link gave
+fn export_path(app: &AppHandle, output: &Path) -> Result<PathBuf, String> {
+    let path = resolve_export_path(
+        &recordings_path(app),
+        output,
+        ExportSettings::default().codec.extension(),
+    )?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    Ok(path)
+}
+
+// `output` has to be a relative path that stays inside the recordings folder, so a link can't
+// write anywhere else. It can't go inside a project either, or replace a file that's already
+// there. The export's written with `extension`, whatever `output` has.
+fn resolve_export_path(
+    recordings_dir: &Path,
+    output: &Path,
+    extension: &str,
+) -> Result<PathBuf, String> {
+    let inside = output.components().count() > 0
+        && output
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !inside {
+        return Err(format!(
+            "Export paths from links must be relative to the recordings folder, not '{}'",
+            output.display()
+        ));
+    }
+
+    let in_project = output.components().any(|component| {
+        Path::new(&component)
+            .extension()
+            .is_some_and(|e| e == "cap")
+    });
+    if in_project {
+        return Err(format!(
+            "Links can't export into a project, like '{}' does",
+            output.display()
+        ));
+    }
+
+    let path = recordings_dir.join(output).with_extension(extension);
+    if path.exists() {
+        return Err(format!(
+            "Links can't export over '{}', which already exists",
+            path.display()
+        ));
+    }
+
+    Ok(path)
+}
+
+// A project a link asked to open has to be one in the recordings folder, named by its id
+fn check_project_id(recordings_dir: &Path, id: &str) -> Result<(), String> {
+    let plain = !id.is_empty() && !id.contains("..") && !id.contains(['/', '\\', ':']);
+    if !plain {
+        return Err(format!("'{id}' isn't a project id"));
+    }
+
+    if !recordings_dir.join(format!("{id}.cap")).is_dir() {
+        return Err(format!("There's no project '{id}'"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn export_path_inside_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            resolve_export_path(dir.path(), Path::new("exports/demo.mov"), "mp4"),
+            Ok(dir.path().join("exports/demo.mp4"))
+        );
+        assert!(resolve_export_path(dir.path(), Path::new("../demo.mp4"), "mp4").is_err());
+        assert!(resolve_export_path(dir.path(), Path::new("/tmp/demo.mp4"), "mp4").is_err());
+    }
+
+    #[test]
+    fn export_path_not_in_projects() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(
+            resolve_export_path(dir.path(), Path::new("other.cap/output/result"), "mp4").is_err()
+        );
+        assert!(resolve_export_path(dir.path(), Path::new("other.cap"), "mp4").is_err());
+    }
+
+    #[test]
+    fn export_path_not_over_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("demo.mp4"), b"").unwrap();
+
+        assert!(resolve_export_path(dir.path(), Path::new("demo.mp4"), "mp4").is_err());
+        assert!(resolve_export_path(dir.path(), Path::new("demo"), "mp4").is_err());
+        assert!(resolve_export_path(dir.path(), Path::new("demo"), "webm").is_ok());
+    }
+
+    #[test]
+    fn project_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("demo.cap")).unwrap();
+
+        assert!(check_project_id(dir.path(), "demo").is_ok());
+        assert!(check_project_id(dir.path(), "missing").is_err());
+        assert!(check_project_id(dir.path(), "").is_err());
+        assert!(check_project_id(dir.path(), "../demo").is_err());
+        assert!(check_project_id(dir.path(), "..").is_err());
+        assert!(check_project_id(dir.path(), "nested/demo").is_err());
+        assert!(check_project_id(dir.path(), "nested\\demo").is_err());
+    }
+}
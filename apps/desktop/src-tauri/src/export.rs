@@ -1,11 +1,53 @@
 use crate::{
-    create_editor_instance_impl, get_video_metadata, recordings_path, windows::ShowCapWindow,
+    create_editor_instance_impl, get_video_metadata, recordings_path,
+    webhooks::{self, WebhookEvent},
+    windows::ShowCapWindow,
     AuthStore, RenderProgress, VideoType,
 };
 use cap_editor::EditorInstance;
-use cap_project::{ProjectConfiguration, RecordingMeta, XY};
-use std::path::PathBuf;
-use tauri::AppHandle;
+use cap_export::{
+    AnimatedExportSettings, ExportCancellation, ExportProgress, ExportSettings,
+    SplitExportManifest, StreamTarget,
+};
+use cap_project::{AspectRatio, ProjectConfiguration, RecordingMeta, XY};
+use cap_rendering::FrameRateConversion;
+use serde_json::json;
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager};
+
+// Exports in progress by video id, so they can be cancelled
+#[derive(Default)]
+pub struct RunningExports(Mutex<HashMap<String, ExportCancellation>>);
+
+impl RunningExports {
+    fn start(&self, video_id: &str) -> ExportCancellation {
+        let cancellation = ExportCancellation::new();
+        self.0
+            .lock()
+            .unwrap()
+            .insert(video_id.to_string(), cancellation.clone());
+        cancellation
+    }
+
+    fn finish(&self, video_id: &str) {
+        self.0.lock().unwrap().remove(video_id);
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    app.manage(RunningExports::default());
+}
+
+fn send_progress(channel: &tauri::ipc::Channel<RenderProgress>, progress: ExportProgress) {
+    channel
+        .send(RenderProgress::FrameRendered {
+            current_frame: progress.frames_done,
+            total_frames: progress.total_frames,
+            stage: progress.stage,
+            eta_secs: progress.eta_secs,
+        })
+        .ok();
+}
 
 #[tauri::command]
 #[specta::specta]
@@ -16,27 +58,259 @@ pub async fn export_video(
     force: bool,
     fps: u32,
     resolution_base: XY<u32>,
+    // set by the vertical and square resolution presets
+    aspect_ratio: Option<AspectRatio>,
+    frame_rate_conversion: FrameRateConversion,
+    settings: ExportSettings,
+    // burns the timecode and project revision into every frame, for review copies
+    draft: bool,
 ) -> Result<PathBuf, String> {
     let editor_instance = create_editor_instance_impl(&app, &video_id).await?;
 
-    let screen_metadata =
-        match get_video_metadata(app.clone(), video_id.clone(), Some(VideoType::Screen)).await {
-            Ok(meta) => meta,
-            Err(e) => {
-                sentry::capture_message(
-                    &format!("Failed to get video metadata: {}", e),
-                    sentry::Level::Error,
-                );
-                return Err(
+    let total_frames = editor_instance.get_total_frames(fps);
+
+    let output_path = editor_instance.meta().output_path();
+
+    // If the file exists and we're not forcing a re-render, return it
+    if output_path.exists() && !force {
+        return Ok(output_path);
+    }
+
+    progress
+        .send(RenderProgress::EstimatedTotalFrames { total_frames })
+        .ok();
+
+    let exporter = create_exporter(
+        &app,
+        &video_id,
+        &editor_instance,
+        output_path,
+        move |p| send_progress(&progress, p),
+        fps,
+        resolution_base,
+        aspect_ratio,
+    )
+    .await?
+    .with_frame_rate_conversion(frame_rate_conversion)
+    .with_settings(settings)
+    .with_draft(draft);
+
+    let running_exports = app.state::<RunningExports>();
+    let result = exporter
+        .with_cancellation(running_exports.start(&video_id))
+        .export_with_custom_muxer()
+        .await;
+    running_exports.finish(&video_id);
+
+    match result {
+        // the extension depends on the codec
+        Ok(output_path) => {
+            ShowCapWindow::PrevRecordings.show(&app).ok();
+            webhooks::notify(
+                &app,
+                WebhookEvent::ExportFinished,
+                json!({ "id": video_id, "path": output_path }),
+            );
+            Ok(output_path)
+        }
+        Err(e @ cap_export::ExportError::Cancelled) => Err(e.to_string()),
+        Err(e) => {
+            sentry::capture_message(&e.to_string(), sentry::Level::Error);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn export_animated(
+    app: AppHandle,
+    video_id: String,
+    progress: tauri::ipc::Channel<RenderProgress>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    aspect_ratio: Option<AspectRatio>,
+    settings: AnimatedExportSettings,
+) -> Result<PathBuf, String> {
+    let editor_instance = create_editor_instance_impl(&app, &video_id).await?;
+
+    // the exporter caps the frame rate the same way
+    let total_frames = editor_instance.get_total_frames(fps.min(settings.max_fps).max(1));
+
+    progress
+        .send(RenderProgress::EstimatedTotalFrames { total_frames })
+        .ok();
+
+    let exporter = create_exporter(
+        &app,
+        &video_id,
+        &editor_instance,
+        editor_instance.meta().output_path(),
+        // frames are rendered again when shrinking to fit a size limit, which starts
+        // the progress over
+        move |p| send_progress(&progress, p),
+        fps,
+        resolution_base,
+        aspect_ratio,
+    )
+    .await?;
+
+    let running_exports = app.state::<RunningExports>();
+    let result = exporter
+        .with_cancellation(running_exports.start(&video_id))
+        .export_animated(settings)
+        .await;
+    running_exports.finish(&video_id);
+
+    result.map_err(|e| {
+        if !matches!(e, cap_export::ExportError::Cancelled) {
+            sentry::capture_message(&e.to_string(), sentry::Level::Error);
+        }
+        e.to_string()
+    })
+}
+
+// Exports the screen and the camera as separate files next to `output_path`, with a
+// `-sync.json` manifest lining them up, for editing the two angles elsewhere
+#[tauri::command]
+#[specta::specta]
+pub async fn export_split(
+    app: AppHandle,
+    video_id: String,
+    progress: tauri::ipc::Channel<RenderProgress>,
+    output_path: PathBuf,
+    fps: u32,
+    resolution_base: XY<u32>,
+    aspect_ratio: Option<AspectRatio>,
+    frame_rate_conversion: FrameRateConversion,
+    settings: ExportSettings,
+    draft: bool,
+) -> Result<SplitExportManifest, String> {
+    let editor_instance = create_editor_instance_impl(&app, &video_id).await?;
+
+    progress
+        .send(RenderProgress::EstimatedTotalFrames {
+            total_frames: editor_instance.get_total_frames(fps),
+        })
+        .ok();
+
+    let exporter = create_exporter(
+        &app,
+        &video_id,
+        &editor_instance,
+        output_path,
+        // the camera is rendered before the screen, each counting its own frames
+        move |p| send_progress(&progress, p),
+        fps,
+        resolution_base,
+        aspect_ratio,
+    )
+    .await?
+    .with_frame_rate_conversion(frame_rate_conversion)
+    .with_settings(settings)
+    .with_draft(draft);
+
+    let running_exports = app.state::<RunningExports>();
+    let result = exporter
+        .with_cancellation(running_exports.start(&video_id))
+        .export_split()
+        .await;
+    running_exports.finish(&video_id);
+
+    result.map_err(|e| {
+        if !matches!(e, cap_export::ExportError::Cancelled) {
+            sentry::capture_message(&e.to_string(), sentry::Level::Error);
+        }
+        e.to_string()
+    })
+}
+
+// Streams the project live to an RTMP ingest, finishing once it's played through or
+// cancelled with `cancel_export`
+#[tauri::command]
+#[specta::specta]
+pub async fn export_to_stream(
+    app: AppHandle,
+    video_id: String,
+    progress: tauri::ipc::Channel<RenderProgress>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    aspect_ratio: Option<AspectRatio>,
+    target: StreamTarget,
+) -> Result<(), String> {
+    let editor_instance = create_editor_instance_impl(&app, &video_id).await?;
+
+    let total_frames = editor_instance.get_total_frames(fps);
+
+    progress
+        .send(RenderProgress::EstimatedTotalFrames { total_frames })
+        .ok();
+
+    let exporter = create_exporter(
+        &app,
+        &video_id,
+        &editor_instance,
+        // nothing is written here, but the exporter wants somewhere
+        editor_instance.meta().output_path(),
+        move |p| send_progress(&progress, p),
+        fps,
+        resolution_base,
+        aspect_ratio,
+    )
+    .await?;
+
+    let running_exports = app.state::<RunningExports>();
+    let result = exporter
+        .with_cancellation(running_exports.start(&video_id))
+        .export_to_stream(target)
+        .await;
+    running_exports.finish(&video_id);
+
+    result.map_err(|e| {
+        if !matches!(e, cap_export::ExportError::Cancelled) {
+            sentry::capture_message(&e.to_string(), sentry::Level::Error);
+        }
+        e.to_string()
+    })
+}
+
+// Sets up an exporter for the project as it's being edited, written to `output_path`
+pub(crate) async fn create_exporter<TOnProgress>(
+    app: &AppHandle,
+    video_id: &str,
+    editor_instance: &EditorInstance,
+    output_path: PathBuf,
+    on_progress: TOnProgress,
+    fps: u32,
+    resolution_base: XY<u32>,
+    aspect_ratio: Option<AspectRatio>,
+) -> Result<cap_export::Exporter<TOnProgress>, String>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    let screen_metadata = match get_video_metadata(
+        app.clone(),
+        video_id.to_string(),
+        Some(VideoType::Screen),
+    )
+    .await
+    {
+        Ok(meta) => meta,
+        Err(e) => {
+            sentry::capture_message(
+                &format!("Failed to get video metadata: {}", e),
+                sentry::Level::Error,
+            );
+            return Err(
                 "Failed to read video metadata. The recording may be from an incompatible version."
                     .to_string(),
             );
-            }
-        };
+        }
+    };
 
     // Get camera metadata if it exists
     let camera_metadata =
-        get_video_metadata(app.clone(), video_id.clone(), Some(VideoType::Camera))
+        get_video_metadata(app.clone(), video_id.to_string(), Some(VideoType::Camera))
             .await
             .ok();
 
@@ -47,19 +321,6 @@ pub async fn export_video(
             .unwrap_or(screen_metadata.duration),
     );
 
-    let total_frames = editor_instance.get_total_frames(fps);
-
-    let output_path = editor_instance.meta().output_path();
-
-    // If the file exists and we're not forcing a re-render, return it
-    if output_path.exists() && !force {
-        return Ok(output_path);
-    }
-
-    progress
-        .send(RenderProgress::EstimatedTotalFrames { total_frames })
-        .ok();
-
     // Create a modified project configuration that accounts for different video lengths
     let mut modified_project = editor_instance.project_config.1.borrow().clone();
     if let Some(timeline) = &mut modified_project.timeline {
@@ -71,22 +332,16 @@ pub async fn export_video(
         }
     }
 
-    let is_upgraded = AuthStore::get(&app)
+    let is_upgraded = AuthStore::get(app)
         .ok()
         .flatten()
         .map(|auth| auth.is_upgraded())
         .unwrap_or(false);
 
-    let exporter = cap_export::Exporter::new(
+    let mut exporter = cap_export::Exporter::new(
         modified_project,
-        output_path.clone(),
-        move |frame_index| {
-            // Ensure progress never exceeds total frames
-            let current_frame = (frame_index + 1).min(total_frames);
-            progress
-                .send(RenderProgress::FrameRendered { current_frame })
-                .ok();
-        },
+        output_path,
+        on_progress,
         editor_instance.project_path.clone(),
         editor_instance.meta(),
         editor_instance.render_constants.clone(),
@@ -101,18 +356,27 @@ pub async fn export_video(
         e.to_string()
     })?;
 
-    let result = exporter.export_with_custom_muxer().await;
+    if let Some(aspect_ratio) = aspect_ratio {
+        exporter = exporter.with_aspect_ratio(aspect_ratio);
+    }
 
-    match result {
-        Ok(_) => {
-            ShowCapWindow::PrevRecordings.show(&app).ok();
-            Ok(output_path)
-        }
-        Err(e) => {
-            sentry::capture_message(&e.to_string(), sentry::Level::Error);
-            Err(e.to_string())
-        }
+    Ok(exporter)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_export(app: AppHandle, video_id: String) -> Result<(), String> {
+    if let Some(cancellation) = app
+        .state::<RunningExports>()
+        .0
+        .lock()
+        .unwrap()
+        .get(&video_id)
+    {
+        cancellation.cancel();
     }
+
+    Ok(())
 }
 
 #[derive(Debug, serde::Serialize, specta::Type)]
@@ -130,6 +394,7 @@ pub async fn get_export_estimates(
     video_id: String,
     resolution: XY<u32>,
     fps: u32,
+    settings: ExportSettings,
 ) -> Result<ExportEstimates, String> {
     let screen_metadata =
         get_video_metadata(app.clone(), video_id.clone(), Some(VideoType::Screen)).await?;
@@ -147,36 +412,33 @@ pub async fn get_export_estimates(
     let project_path = EditorInstance::project_path(&recordings_path(&app), &video_id);
     let meta = RecordingMeta::load_for_project(&project_path).unwrap();
     let project_config = meta.project_config();
+    let meta = meta.with_takes(&project_config.takes)?;
     let duration_seconds = if let Some(timeline) = &project_config.timeline {
-        timeline
-            .segments
-            .iter()
-            .map(|s| (s.end - s.start) / s.timescale)
-            .sum()
+        timeline.with_speed_applied().duration()
     } else {
         raw_duration
     };
 
     let (width, height) = (resolution.x, resolution.y);
 
-    let base_bitrate = if width <= 1280 && height <= 720 {
-        4_000_000.0
-    } else if width <= 1920 && height <= 1080 {
-        8_000_000.0
-    } else if width <= 2560 && height <= 1440 {
-        14_000_000.0
-    } else {
-        20_000_000.0
-    };
-
-    let fps_factor = (fps as f64) / 30.0;
-    let video_bitrate = base_bitrate * fps_factor;
-
-    let audio_bitrate = 192_000.0;
+    let has_audio = match &meta.content {
+        cap_project::Content::SingleSegment { segment } => segment.audio.is_some(),
+        cap_project::Content::MultipleSegments { inner } => inner
+            .segments
+            .iter()
+            .any(|s| s.audio.is_some() || s.system_audio.is_some()),
+    } && !project_config.audio.mute;
 
-    let total_bitrate = video_bitrate + audio_bitrate;
+    let estimated_size_mb = cap_export::estimate_export_size(
+        &settings,
+        (width, height),
+        fps,
+        duration_seconds,
+        has_audio,
+    ) as f64
+        / (1024.0 * 1024.0);
 
-    let estimated_size_mb = (total_bitrate * duration_seconds) / (8.0 * 1024.0 * 1024.0);
+    let fps_factor = (fps as f64) / 30.0;
 
     let base_factor = match (width, height) {
         (w, h) if w <= 1280 && h <= 720 => 0.43,
@@ -185,7 +447,14 @@ pub async fn get_export_estimates(
         _ => 0.86,
     };
 
-    let processing_time = duration_seconds * base_factor * fps_factor;
+    // two-pass exports render everything twice
+    let passes = if settings.target_size_kib.is_some() {
+        2.0
+    } else {
+        1.0
+    };
+
+    let processing_time = duration_seconds * base_factor * fps_factor * passes;
     let overhead_time = 0.0;
 
     let estimated_time_seconds = processing_time + overhead_time;
@@ -0,0 +1,422 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use cap_export::{ExportCancellation, ExportError, ExportProgress, ExportSettings};
+use cap_project::{AspectRatio, XY};
+use cap_rendering::FrameRateConversion;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_store::StoreExt;
+use tauri_specta::Event;
+use tokio::sync::Notify;
+
+use crate::{
+    create_editor_instance_impl,
+    export::create_exporter,
+    webhooks::{self, WebhookEvent},
+};
+
+// An export to run in the background, like the editor's export button would
+#[derive(Serialize, Deserialize, Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportJobRequest {
+    pub video_id: String,
+    // where the export is written, with its extension set by the codec
+    pub output_path: PathBuf,
+    pub fps: u32,
+    pub resolution_base: XY<u32>,
+    pub aspect_ratio: Option<AspectRatio>,
+    pub frame_rate_conversion: FrameRateConversion,
+    pub settings: ExportSettings,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+#[derive(Serialize, Deserialize, Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportJob {
+    pub id: String,
+    pub request: ExportJobRequest,
+    pub status: ExportJobStatus,
+}
+
+#[derive(Serialize, Deserialize, Type, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportJobStatus {
+    Queued,
+    Running,
+    Done { path: PathBuf },
+    Failed { error: String },
+    Cancelled,
+}
+
+impl ExportJobStatus {
+    fn is_finished(&self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+// Sent as jobs are added, make progress and finish
+#[derive(Serialize, Deserialize, Type, tauri_specta::Event, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportJobEvent {
+    Updated {
+        job: ExportJob,
+    },
+    Progress {
+        id: String,
+        progress: ExportProgress,
+    },
+    Removed {
+        id: String,
+    },
+}
+
+// What's kept of the queue between launches
+#[derive(Serialize, Deserialize, Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportQueueStore {
+    jobs: Vec<ExportJob>,
+    // how many jobs run at once
+    max_parallel: u32,
+}
+
+impl Default for ExportQueueStore {
+    fn default() -> Self {
+        Self {
+            jobs: vec![],
+            max_parallel: 1,
+        }
+    }
+}
+
+impl ExportQueueStore {
+    fn get(app: &AppHandle<Wry>) -> Result<Option<Self>, String> {
+        match app.store("store").map(|s| s.get("export_queue")) {
+            Ok(Some(store)) => match serde_json::from_value(store) {
+                Ok(queue) => Ok(Some(queue)),
+                Err(e) => Err(format!("Failed to deserialize export queue store: {e}")),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let Ok(store) = app.store("store") else {
+            return Err("Store not found".to_string());
+        };
+
+        store.set("export_queue", json!(self));
+        store.save().map_err(|e| e.to_string())
+    }
+}
+
+// The queue in memory, saved to the store on every change
+pub struct ExportQueue {
+    store: Mutex<ExportQueueStore>,
+    running: Mutex<HashMap<String, ExportCancellation>>,
+    // woken when a job is added or finishes, or more may run at once
+    changed: Notify,
+}
+
+impl ExportQueue {
+    // Changes the queue and saves it, announcing the jobs `update` returns the ids of
+    fn update(
+        &self,
+        app: &AppHandle,
+        update: impl FnOnce(&mut ExportQueueStore) -> Vec<String>,
+    ) -> Result<(), String> {
+        let mut store = self.store.lock().unwrap();
+        let changed = update(&mut store);
+        store.save(app)?;
+
+        for job in store.jobs.iter().filter(|j| changed.contains(&j.id)) {
+            ExportJobEvent::Updated { job: job.clone() }.emit(app).ok();
+        }
+
+        Ok(())
+    }
+
+    // Marks as many queued jobs as running as there's room for, oldest first. They can be
+    // cancelled from then on, before their exports have started.
+    fn take_ready(&self, app: &AppHandle) -> Result<Vec<(ExportJob, ExportCancellation)>, String> {
+        let mut ready = vec![];
+
+        self.update(app, |store| {
+            let running = store
+                .jobs
+                .iter()
+                .filter(|j| j.status == ExportJobStatus::Running)
+                .count();
+            let room = (store.max_parallel.max(1) as usize).saturating_sub(running);
+
+            for job in store
+                .jobs
+                .iter_mut()
+                .filter(|j| j.status == ExportJobStatus::Queued)
+                .take(room)
+            {
+                job.status = ExportJobStatus::Running;
+                ready.push(job.clone());
+            }
+
+            ready.iter().map(|j| j.id.clone()).collect()
+        })?;
+
+        let mut running = self.running.lock().unwrap();
+        Ok(ready
+            .into_iter()
+            .map(|job| {
+                let cancellation = ExportCancellation::new();
+                running.insert(job.id.clone(), cancellation.clone());
+                (job, cancellation)
+            })
+            .collect())
+    }
+
+    fn set_status(&self, app: &AppHandle, id: &str, status: ExportJobStatus) {
+        let result = self.update(app, |store| {
+            store
+                .jobs
+                .iter_mut()
+                .filter(|j| j.id == id)
+                .map(|job| {
+                    job.status = status.clone();
+                    job.id.clone()
+                })
+                .collect()
+        });
+
+        if let Err(e) = result {
+            eprintln!("Failed to save export queue: {e}");
+        }
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    let mut store = match ExportQueueStore::get(app) {
+        Ok(store) => store.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{e}");
+            ExportQueueStore::default()
+        }
+    };
+
+    // jobs cut off by quitting start over
+    for job in &mut store.jobs {
+        if job.status == ExportJobStatus::Running {
+            job.status = ExportJobStatus::Queued;
+        }
+    }
+
+    let queue = Arc::new(ExportQueue {
+        store: Mutex::new(store),
+        running: Mutex::new(HashMap::new()),
+        changed: Notify::new(),
+    });
+    app.manage(queue.clone());
+
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            match queue.take_ready(&app) {
+                Ok(jobs) => {
+                    for (job, cancellation) in jobs {
+                        tokio::spawn(run_job(app.clone(), queue.clone(), job, cancellation));
+                    }
+                }
+                Err(e) => eprintln!("Failed to start queued exports: {e}"),
+            }
+
+            queue.changed.notified().await;
+        }
+    });
+}
+
+async fn run_job(
+    app: AppHandle,
+    queue: Arc<ExportQueue>,
+    job: ExportJob,
+    cancellation: ExportCancellation,
+) {
+    println!("Running export job {}", job.id);
+
+    let status = export_job(&app, &job, cancellation).await;
+
+    queue.running.lock().unwrap().remove(&job.id);
+    queue.set_status(&app, &job.id, status);
+    queue.changed.notify_one();
+}
+
+// Runs the job's export, returning how it ended
+async fn export_job(
+    app: &AppHandle,
+    job: &ExportJob,
+    cancellation: ExportCancellation,
+) -> ExportJobStatus {
+    let request = &job.request;
+
+    let editor_instance = match create_editor_instance_impl(app, &request.video_id).await {
+        Ok(editor_instance) => editor_instance,
+        Err(error) => return ExportJobStatus::Failed { error },
+    };
+
+    let exporter = match create_exporter(
+        app,
+        &request.video_id,
+        &editor_instance,
+        request.output_path.clone(),
+        {
+            let app = app.clone();
+            let id = job.id.clone();
+            move |progress| {
+                ExportJobEvent::Progress {
+                    id: id.clone(),
+                    progress,
+                }
+                .emit(&app)
+                .ok();
+            }
+        },
+        request.fps,
+        request.resolution_base,
+        request.aspect_ratio.clone(),
+    )
+    .await
+    {
+        Ok(exporter) => exporter,
+        Err(error) => return ExportJobStatus::Failed { error },
+    };
+
+    let result = exporter
+        .with_frame_rate_conversion(request.frame_rate_conversion)
+        .with_settings(request.settings)
+        .with_draft(request.draft)
+        .with_cancellation(cancellation)
+        .export_with_custom_muxer()
+        .await;
+
+    match result {
+        Ok(path) => {
+            webhooks::notify(
+                app,
+                WebhookEvent::ExportFinished,
+                json!({ "id": request.video_id, "path": path }),
+            );
+            ExportJobStatus::Done { path }
+        }
+        Err(ExportError::Cancelled) => ExportJobStatus::Cancelled,
+        Err(e) => ExportJobStatus::Failed {
+            error: e.to_string(),
+        },
+    }
+}
+
+// Adds exports to the end of the queue, returning them with their ids
+#[tauri::command]
+#[specta::specta]
+pub async fn queue_exports(
+    app: AppHandle,
+    queue: tauri::State<'_, Arc<ExportQueue>>,
+    requests: Vec<ExportJobRequest>,
+) -> Result<Vec<ExportJob>, String> {
+    let jobs = requests
+        .into_iter()
+        .map(|request| ExportJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            request,
+            status: ExportJobStatus::Queued,
+        })
+        .collect::<Vec<_>>();
+
+    queue.update(&app, |store| {
+        store.jobs.extend(jobs.iter().cloned());
+        jobs.iter().map(|j| j.id.clone()).collect()
+    })?;
+    queue.changed.notify_one();
+
+    Ok(jobs)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn list_export_jobs(
+    queue: tauri::State<'_, Arc<ExportQueue>>,
+) -> Result<Vec<ExportJob>, String> {
+    Ok(queue.store.lock().unwrap().jobs.clone())
+}
+
+// Cancels a job, stopping it if it's already running
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_export_job(
+    app: AppHandle,
+    queue: tauri::State<'_, Arc<ExportQueue>>,
+    id: String,
+) -> Result<(), String> {
+    if let Some(cancellation) = queue.running.lock().unwrap().get(&id) {
+        // the job marks itself cancelled once it's stopped
+        cancellation.cancel();
+        return Ok(());
+    }
+
+    queue.update(&app, |store| {
+        store
+            .jobs
+            .iter_mut()
+            .filter(|j| j.id == id && j.status == ExportJobStatus::Queued)
+            .map(|job| {
+                job.status = ExportJobStatus::Cancelled;
+                job.id.clone()
+            })
+            .collect()
+    })
+}
+
+// Removes the jobs that are done, failed or were cancelled
+#[tauri::command]
+#[specta::specta]
+pub async fn clear_finished_export_jobs(
+    app: AppHandle,
+    queue: tauri::State<'_, Arc<ExportQueue>>,
+) -> Result<(), String> {
+    let mut removed = vec![];
+
+    queue.update(&app, |store| {
+        store.jobs.retain(|job| {
+            if job.status.is_finished() {
+                removed.push(job.id.clone());
+            }
+            !job.status.is_finished()
+        });
+        vec![]
+    })?;
+
+    for id in removed {
+        ExportJobEvent::Removed { id }.emit(&app).ok();
+    }
+
+    Ok(())
+}
+
+// How many jobs run at once, at least one
+#[tauri::command]
+#[specta::specta]
+pub async fn set_export_queue_parallelism(
+    app: AppHandle,
+    queue: tauri::State<'_, Arc<ExportQueue>>,
+    max_parallel: u32,
+) -> Result<(), String> {
+    queue.update(&app, |store| {
+        store.max_parallel = max_parallel.max(1);
+        vec![]
+    })?;
+    queue.changed.notify_one();
+
+    Ok(())
+}
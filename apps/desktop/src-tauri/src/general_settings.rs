@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use specta::Type;
-use std::sync::Mutex;
+use std::{path::PathBuf, sync::Mutex};
 use tauri::{AppHandle, Manager, Wry};
 use tauri_plugin_store::StoreExt;
 use uuid::Uuid;
@@ -25,6 +25,9 @@ pub struct GeneralSettingsStore {
     pub enable_notifications: bool,
     #[serde(default)]
     pub disable_auto_open_links: bool,
+    // the editor previews large recordings from low resolution copies unless this is set
+    #[serde(default)]
+    pub disable_proxy_media: bool,
     // first launch: store won't exist so show startup
     #[serde(default = "true_b")]
     pub has_completed_startup: bool,
@@ -34,6 +37,15 @@ pub struct GeneralSettingsStore {
     pub commercial_license: Option<CommercialLicense>,
     #[serde(default)]
     pub last_version: Option<String>,
+    // new videos dropped into these folders are imported as projects
+    #[serde(default)]
+    pub watch_folders: Vec<PathBuf>,
+    // where projects are kept instead of the app's data folder, set through `storage`
+    #[serde(default)]
+    pub recordings_dir: Option<PathBuf>,
+    // caps how fast shareable links upload, in megabits per second
+    #[serde(default)]
+    pub upload_speed_limit_mbps: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Type, Debug)]
@@ -56,10 +68,14 @@ impl Default for GeneralSettingsStore {
             auto_create_shareable_link: false,
             enable_notifications: true,
             disable_auto_open_links: false,
+            disable_proxy_media: false,
             has_completed_startup: false,
             theme: AppTheme::System,
             commercial_license: None,
             last_version: None,
+            watch_folders: vec![],
+            recordings_dir: None,
+            upload_speed_limit_mbps: None,
         }
     }
 }
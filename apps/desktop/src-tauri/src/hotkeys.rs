@@ -1,8 +1,10 @@
 use crate::{
-    RequestNewScreenshot, RequestRestartRecording, RequestStartRecording, RequestStopRecording,
+    RequestAddMarker, RequestNewScreenshot, RequestRestartRecording, RequestStartRecording,
+    RequestStopRecording, RequestToggleCamera, RequestTogglePauseRecording,
 };
 use global_hotkey::HotKeyState;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use specta::Type;
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -42,13 +44,32 @@ impl Hotkey {
     }
 }
 
-#[derive(Serialize, Deserialize, Type, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Type, PartialEq, Eq, Hash, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 pub enum HotkeyAction {
     StartRecording,
     StopRecording,
     RestartRecording,
     TakeScreenshot,
+    // pauses the recording, or resumes it when it's paused
+    PauseRecording,
+    AddMarker,
+    // turns the camera on or off for the next recording
+    ToggleCamera,
+}
+
+impl HotkeyAction {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::StartRecording => "start recording",
+            Self::StopRecording => "stop recording",
+            Self::RestartRecording => "restart recording",
+            Self::TakeScreenshot => "take a screenshot",
+            Self::PauseRecording => "pause recording",
+            Self::AddMarker => "add a marker",
+            Self::ToggleCamera => "toggle the camera",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Type, Default)]
@@ -64,6 +85,12 @@ impl HotkeysStore {
 
         serde_json::from_value(store).map_err(|e| e.to_string())
     }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let store = app.store("store").map_err(|e| e.to_string())?;
+        store.set("hotkeys", json!(self));
+        store.save().map_err(|e| e.to_string())
+    }
 }
 
 pub type HotkeysState = Mutex<HotkeysStore>;
@@ -93,6 +120,15 @@ pub fn init(app: &AppHandle) {
                             HotkeyAction::TakeScreenshot => {
                                 let _ = RequestNewScreenshot.emit(app);
                             }
+                            HotkeyAction::PauseRecording => {
+                                let _ = RequestTogglePauseRecording.emit(app);
+                            }
+                            HotkeyAction::AddMarker => {
+                                let _ = RequestAddMarker.emit(app);
+                            }
+                            HotkeyAction::ToggleCamera => {
+                                let _ = RequestToggleCamera.emit(app);
+                            }
                         }
                     }
                 }
@@ -114,7 +150,11 @@ pub fn init(app: &AppHandle) {
 
 #[tauri::command(async)]
 #[specta::specta]
-pub fn set_hotkey(app: AppHandle, action: HotkeyAction, hotkey: Option<Hotkey>) -> Result<(), ()> {
+pub fn set_hotkey(
+    app: AppHandle,
+    action: HotkeyAction,
+    hotkey: Option<Hotkey>,
+) -> Result<(), String> {
     let global_shortcut = app.global_shortcut();
     let state = app.state::<HotkeysState>();
     let mut store = state.lock().unwrap();
@@ -122,6 +162,25 @@ pub fn set_hotkey(app: AppHandle, action: HotkeyAction, hotkey: Option<Hotkey>)
     let prev = store.hotkeys.get(&action).cloned();
 
     if let Some(hotkey) = hotkey {
+        if let Some(other) = store
+            .hotkeys
+            .iter()
+            .find(|(a, h)| **a != action && **h == hotkey)
+            .map(|(a, _)| a)
+        {
+            return Err(format!(
+                "This shortcut is already used to {}",
+                other.description()
+            ));
+        }
+
+        // another app holding on to the shortcut makes registering it fail
+        if prev != Some(hotkey) {
+            global_shortcut
+                .register(hotkey.to_shortcut())
+                .map_err(|_| "This shortcut is already used by another app".to_string())?;
+        }
+
         store.hotkeys.insert(action, hotkey);
     } else {
         store.hotkeys.remove(&action);
@@ -133,9 +192,5 @@ pub fn set_hotkey(app: AppHandle, action: HotkeyAction, hotkey: Option<Hotkey>)
         }
     }
 
-    if let Some(hotkey) = hotkey {
-        global_shortcut.register(hotkey.to_shortcut()).ok();
-    }
-
-    Ok(())
+    store.save(&app)
 }
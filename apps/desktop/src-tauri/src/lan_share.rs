@@ -0,0 +1,344 @@
+// Serves a recording's export on the local network, with a page that plays it, so it can be
+// watched from another device without uploading it anywhere.
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use cap_project::RecordingMeta;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    collections::HashMap,
+    io::SeekFrom,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::{AppHandle, Manager};
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::oneshot,
+};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Serialize, Type, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LanShare {
+    // the player page, with the token in it if there is one
+    pub url: String,
+    pub token: Option<String>,
+}
+
+// The recordings being shared, by project path
+#[derive(Default)]
+pub struct LanShares(Mutex<HashMap<PathBuf, RunningShare>>);
+
+struct RunningShare {
+    share: LanShare,
+    shutdown: oneshot::Sender<()>,
+}
+
+pub fn init(app: &AppHandle) {
+    app.manage(LanShares::default());
+}
+
+struct ShareState {
+    video_path: PathBuf,
+    title: String,
+    token: Option<String>,
+}
+
+impl ShareState {
+    fn authorized(&self, query: &TokenQuery) -> bool {
+        match (&self.token, &query.token) {
+            (None, _) => true,
+            (Some(expected), Some(given)) => constant_time_eq(expected, given),
+            (Some(_), None) => false,
+        }
+    }
+
+    fn video_src(&self) -> String {
+        match &self.token {
+            Some(token) => format!("/video?token={token}"),
+            None => "/video".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_lan_share(
+    app: AppHandle,
+    project_path: PathBuf,
+    require_token: bool,
+) -> Result<LanShare, String> {
+    let shares = app.state::<LanShares>();
+    if let Some(running) = shares.0.lock().unwrap().get(&project_path) {
+        return Ok(running.share.clone());
+    }
+
+    let meta = RecordingMeta::load_for_project(&project_path)?;
+    let video_path = meta.output_path();
+    if !video_path.exists() {
+        return Err("Export this recording before sharing it".to_string());
+    }
+
+    let token = require_token.then(|| {
+        let bytes: [u8; 16] = rand::thread_rng().gen();
+        bytes.iter().map(|b| format!("{b:02x}")).collect::<String>()
+    });
+
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+        .await
+        .map_err(|e| format!("Failed to start the share server: {e}"))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let mut url = format!("http://{}:{port}/", lan_address());
+    if let Some(token) = &token {
+        url.push_str(&format!("?token={token}"));
+    }
+    let share = LanShare { url, token };
+
+    let router = axum::Router::new()
+        .route("/", get(player_page))
+        .route("/video", get(video))
+        .with_state(Arc::new(ShareState {
+            video_path,
+            title: meta.pretty_name,
+            token: share.token.clone(),
+        }));
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    {
+        let mut shares = shares.0.lock().unwrap();
+        // another call may have started sharing it while this one was binding, in which
+        // case the listener's dropped before it serves anything
+        if let Some(running) = shares.get(&project_path) {
+            return Ok(running.share.clone());
+        }
+        shares.insert(
+            project_path.clone(),
+            RunningShare {
+                share: share.clone(),
+                shutdown: shutdown_tx,
+            },
+        );
+    }
+
+    tokio::spawn(async move {
+        let server =
+            axum::serve(listener, router.into_make_service()).with_graceful_shutdown(async {
+                shutdown_rx.await.ok();
+            });
+
+        if let Err(e) = server.await {
+            tracing::error!("LAN share server failed: {e}");
+        }
+    });
+
+    println!("Sharing {} at {}", project_path.display(), share.url);
+
+    Ok(share)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_lan_share(app: AppHandle, project_path: PathBuf) {
+    if let Some(running) = app
+        .state::<LanShares>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&project_path)
+    {
+        running.shutdown.send(()).ok();
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_lan_share(app: AppHandle, project_path: PathBuf) -> Option<LanShare> {
+    app.state::<LanShares>()
+        .0
+        .lock()
+        .unwrap()
+        .get(&project_path)
+        .map(|running| running.share.clone())
+}
+
+async fn player_page(
+    State(state): State<Arc<ShareState>>,
+    Query(query): Query<TokenQuery>,
+) -> Response {
+    if !state.authorized(&query) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let title = escape_html(&state.title);
+
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body {{ margin: 0; min-height: 100vh; display: flex; flex-direction: column; align-items: center; justify-content: center; gap: 1rem; background: #111; color: #eee; font-family: system-ui, sans-serif; }}
+video {{ max-width: 100vw; max-height: 85vh; }}
+</style>
+</head>
+<body>
+<video controls autoplay playsinline src="{src}"></video>
+<span>{title}</span>
+</body>
+</html>"#,
+        src = state.video_src()
+    ))
+    .into_response()
+}
+
+// The export itself, in whatever ranges the player asks for so it can seek
+async fn video(
+    State(state): State<Arc<ShareState>>,
+    Query(query): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !state.authorized(&query) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let mut file = match tokio::fs::File::open(&state.video_path).await {
+        Ok(file) => file,
+        Err(_) => return StatusCode::NOT_FOUND.into_response(),
+    };
+    let Ok(size) = file.metadata().await.map(|m| m.len()) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_range(v, size));
+
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, size),
+        Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end),
+        Some(None) => {
+            return (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{size}"))],
+            )
+                .into_response()
+        }
+    };
+
+    if file.seek(SeekFrom::Start(start)).await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    let body = futures::stream::unfold((file, end - start), |(mut file, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+
+        let mut chunk = vec![0; (remaining as usize).min(CHUNK_SIZE)];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(n) => {
+                chunk.truncate(n);
+                Some((Ok(chunk), (file, remaining - n as u64)))
+            }
+            Err(e) => Some((Err(e), (file, 0))),
+        }
+    });
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type(&state.video_path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, end - start);
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {start}-{}/{size}", end - 1),
+        );
+    }
+
+    response
+        .body(Body::from_stream(body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+// What the export's extension says it is
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mov") => "video/quicktime",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("webm") => "video/webm",
+        Some("mkv") => "video/x-matroska",
+        _ => "video/mp4",
+    }
+}
+
+// A single `bytes=` range as `start..end`, or `None` if it can't be served. Players only
+// ever ask for one.
+fn parse_range(value: &str, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = value.strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let (start, end) = if start.is_empty() {
+        // the last `end` bytes
+        let suffix = end.parse::<u64>().ok()?.min(size);
+        (size - suffix, size)
+    } else {
+        let start = start.parse::<u64>().ok()?;
+        let end = match end {
+            "" => size,
+            end => end.parse::<u64>().ok()?.saturating_add(1).min(size),
+        };
+        (start, end)
+    };
+
+    (start < end).then_some((start, end))
+}
+
+// The address other devices on the network reach this one at. Connecting a UDP socket
+// doesn't send anything, it only picks the interface the route goes out of.
+fn lan_address() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
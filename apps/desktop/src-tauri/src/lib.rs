@@ -1,38 +1,59 @@
 mod audio;
 mod auth;
 mod camera;
+mod deep_link;
 mod flags;
 mod general_settings;
 mod hotkeys;
+mod lan_share;
 mod notifications;
 mod permissions;
 mod platform;
+mod project_bundle;
 mod recording;
+mod recovery;
+mod s3_storage;
+mod storage;
 // mod resource;
 mod audio_meter;
 mod editor_window;
 mod export;
+mod export_queue;
 mod fake_window;
 // mod live_state;
 mod presets;
 mod tray;
 mod upload;
+mod uploader;
+mod virtual_camera;
+mod watch_folders;
 mod web_api;
+mod webhooks;
 mod windows;
+mod zoom_debug;
 
 use audio::AppSounds;
 use auth::{AuthStore, AuthenticationInvalid, Plan};
 use camera::create_camera_preview_ws;
+use cap_audio::silence::{detect_silences, suggest_cuts, SilenceCut, SilenceOptions};
+use cap_audio::waveform::Waveform;
 use cap_editor::EditorInstance;
 use cap_editor::EditorState;
+use cap_editor::{transcribe, TranscriptionProgress};
 use cap_fail::fail;
 use cap_media::feeds::RawCameraFrame;
 use cap_media::feeds::{AudioInputFeed, AudioInputSamplesSender};
 use cap_media::frame_ws::WSFrame;
-use cap_media::sources::CaptureScreen;
+use cap_media::sources::{CaptureScreen, CaptureWindow};
 use cap_media::{feeds::CameraFeed, sources::ScreenCaptureTarget};
 use cap_project::XY;
-use cap_project::{Content, ProjectConfiguration, RecordingMeta, Resolution, SharingMeta};
+use cap_project::{
+    captions_from_transcript, detect_idle, input_activity, parse_captions, suggest_idle_periods,
+    suggest_redactions, track_sensitive_text, write_captions, CaptionFormat, CaptionSegment,
+    Content, CursorEvents, EditHistory, IdleOptions, IdlePeriod, OverlayColorWarning,
+    OverlayConfiguration, ProjectCommand, ProjectConfiguration, RecordingMeta, RedactionSuggestion,
+    Resolution, SharingMeta, TimelineConfiguration, ZoomSegment,
+};
 use cap_recording::RecordingOptions;
 use cap_rendering::ProjectRecordings;
 use clipboard_rs::common::RustImage;
@@ -46,6 +67,7 @@ use notifications::NotificationType;
 use png::{ColorType, Encoder};
 use presets::PresetsStore;
 use relative_path::RelativePathBuf;
+use s3_storage::S3StorageConfig;
 use scap::capturer::Capturer;
 use scap::frame::Frame;
 use serde::{Deserialize, Serialize};
@@ -75,6 +97,7 @@ use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::Layer;
 use upload::{get_s3_config, upload_image, upload_video, S3UploadMeta};
 use web_api::ManagerExt;
+use webhooks::WebhookEvent;
 use windows::{CapWindowId, ShowCapWindow};
 
 #[derive(specta::Type, Serialize)]
@@ -94,6 +117,9 @@ pub struct App {
     handle: AppHandle,
     #[serde(skip)]
     current_recording: Option<cap_recording::ActorHandle>,
+    // the next recording, set up ahead of time while the recorder is open
+    #[serde(skip)]
+    prepared_recording: Option<cap_recording::PreparedRecording>,
     #[serde(skip)]
     pre_created_video: Option<PreCreatedVideo>,
 }
@@ -135,7 +161,10 @@ impl App {
 
         if matches!(
             current_recording.options.capture_target,
-            ScreenCaptureTarget::Window(_) | ScreenCaptureTarget::Area(_)
+            ScreenCaptureTarget::Window(CaptureWindow {
+                isolated: false,
+                ..
+            }) | ScreenCaptureTarget::Area(_)
         ) {
             let _ = ShowCapWindow::WindowCaptureOccluder.show(&self.handle);
         } else {
@@ -149,6 +178,12 @@ impl App {
         self.current_recording.take()
     }
 
+    pub async fn discard_prepared_recording(&mut self) {
+        if let Some(prepared) = self.prepared_recording.take() {
+            prepared.discard().await;
+        }
+    }
+
     fn close_occluder_window(&self) {
         if let Some(window) = CapWindowId::WindowCaptureOccluder.get(&self.handle) {
             window.close().ok();
@@ -159,6 +194,9 @@ impl App {
         &mut self,
         new_options: RecordingOptions,
     ) -> Result<(), String> {
+        // it was set up with the old capture target and devices
+        self.discard_prepared_recording().await;
+
         let options = new_options.clone();
         sentry::configure_scope(move |scope| {
             let mut ctx = std::collections::BTreeMap::new();
@@ -371,6 +409,14 @@ pub struct NewScreenshotAdded {
 #[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
 pub struct RecordingStarted;
 
+// Countdowns for recordings that start or stop by themselves
+#[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RecordingTimer {
+    StartsIn { secs: u32 },
+    StopsIn { secs: u32 },
+}
+
 #[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
 pub struct RecordingStopped {
     path: PathBuf,
@@ -388,6 +434,16 @@ pub struct RequestNewScreenshot;
 #[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
 pub struct RequestStopRecording;
 
+// handled by the in-progress recording window, which keeps track of the pauses
+#[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
+pub struct RequestTogglePauseRecording;
+
+#[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
+pub struct RequestAddMarker;
+
+#[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
+pub struct RequestToggleCamera;
+
 #[derive(Deserialize, specta::Type, Serialize, tauri_specta::Event, Debug, Clone)]
 pub struct RequestOpenSettings {
     page: String,
@@ -437,11 +493,14 @@ async fn set_recording_options(
     options: RecordingOptions,
 ) -> Result<(), String> {
     // Update in-memory state
-    state
-        .write()
-        .await
-        .set_start_recording_options(options.clone())
-        .await?;
+    let mut state = state.write().await;
+    let had_prepared_recording = state.prepared_recording.is_some();
+
+    state.set_start_recording_options(options.clone()).await?;
+
+    if had_prepared_recording {
+        recording::prepare_next_recording(&app, &mut state).await?;
+    }
 
     Ok(())
 }
@@ -960,12 +1019,7 @@ async fn get_video_metadata(
         video_id
     };
 
-    let project_path = app
-        .path()
-        .app_data_dir()
-        .unwrap()
-        .join("recordings")
-        .join(format!("{}.cap", video_id));
+    let project_path = recording_path(&app, &video_id);
 
     let meta = RecordingMeta::load_for_project(&project_path)?;
 
@@ -1038,13 +1092,20 @@ async fn get_video_metadata(
 #[tauri::command(async)]
 #[specta::specta]
 fn open_editor(app: AppHandle, id: String) {
+    show_editor(&app, id).unwrap();
+}
+
+pub(crate) fn show_editor(app: &AppHandle, id: String) -> Result<(), String> {
     println!("Opening editor for recording: {}", id);
 
-    if let Some(window) = CapWindowId::Camera.get(&app) {
+    if let Some(window) = CapWindowId::Camera.get(app) {
         window.close().ok();
     }
 
-    ShowCapWindow::Editor { project_id: id }.show(&app).unwrap();
+    ShowCapWindow::Editor { project_id: id }
+        .show(app)
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't open the editor: {e}"))
 }
 
 #[tauri::command(async)]
@@ -1075,9 +1136,19 @@ fn focus_captures_panel(app: AppHandle) {
 #[derive(Serialize, Deserialize, specta::Type, Clone)]
 #[serde(tag = "type")]
 pub enum RenderProgress {
-    Starting { total_frames: u32 },
-    EstimatedTotalFrames { total_frames: u32 },
-    FrameRendered { current_frame: u32 },
+    Starting {
+        total_frames: u32,
+    },
+    EstimatedTotalFrames {
+        total_frames: u32,
+    },
+    // frames count over the whole export, so a two-pass export goes over every frame twice
+    FrameRendered {
+        current_frame: u32,
+        total_frames: u32,
+        stage: cap_export::ExportStage,
+        eta_secs: Option<f64>,
+    },
 }
 
 #[tauri::command]
@@ -1108,6 +1179,389 @@ async fn set_project_config(
     Ok(())
 }
 
+// Edits the project through the undo history kept in its directory, returning the project
+// as it is afterwards
+#[tauri::command]
+#[specta::specta]
+async fn apply_project_command(
+    editor_instance: WindowEditorInstance,
+    command: ProjectCommand,
+) -> Result<ProjectConfiguration, String> {
+    edit_project(&editor_instance, |history, project| {
+        history.execute(project, command)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn undo_project_edit(
+    editor_instance: WindowEditorInstance,
+) -> Result<ProjectConfiguration, String> {
+    edit_project(&editor_instance, EditHistory::undo)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn redo_project_edit(
+    editor_instance: WindowEditorInstance,
+) -> Result<ProjectConfiguration, String> {
+    edit_project(&editor_instance, EditHistory::redo)
+}
+
+fn edit_project(
+    editor_instance: &EditorInstance,
+    edit: impl FnOnce(&mut EditHistory, &mut ProjectConfiguration) -> bool,
+) -> Result<ProjectConfiguration, String> {
+    let mut project = editor_instance.project_config.1.borrow().clone();
+    let mut history = EditHistory::load(&editor_instance.project_path);
+
+    if edit(&mut history, &mut project) {
+        project
+            .write(&editor_instance.project_path)
+            .map_err(|e| e.to_string())?;
+        editor_instance.project_config.0.send(project.clone()).ok();
+    }
+
+    // written either way, as commands that no longer apply are dropped
+    history
+        .write(&editor_instance.project_path)
+        .map_err(|e| e.to_string())?;
+
+    Ok(project)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn generate_zoom_segments(
+    editor_instance: WindowEditorInstance,
+) -> Result<Vec<ZoomSegment>, String> {
+    let project = editor_instance.project_config.1.borrow().clone();
+    let cursors = editor_instance
+        .segments
+        .iter()
+        .map(|s| &s.cursors)
+        .collect::<Vec<_>>();
+
+    Ok(cap_rendering::auto_zoom::generate_zoom_segments(
+        &project, &cursors,
+    ))
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn generate_silence_cuts(
+    editor_instance: WindowEditorInstance,
+) -> Result<Vec<SilenceCut>, String> {
+    let project = editor_instance.project_config.1.borrow().clone();
+    let options = SilenceOptions::default();
+    let silences = editor_instance
+        .segments
+        .iter()
+        .map(|s| match s.audio.as_ref() {
+            Some(audio) => {
+                let info = audio.info();
+                let tracks = audio
+                    .tracks()
+                    .map(|t| t.buffer.as_slice())
+                    .collect::<Vec<_>>();
+
+                detect_silences(&tracks, info.channels, info.sample_rate, &options)
+            }
+            None => vec![],
+        })
+        .collect::<Vec<_>>();
+
+    Ok(suggest_cuts(project.timeline.as_ref(), &silences))
+}
+
+// Finds where nothing happens on screen, with the mouse or on the keyboard, for cutting or
+// speeding up. Decodes every segment's display video to see what changes on screen.
+#[tauri::command]
+#[specta::specta]
+async fn generate_idle_periods(
+    editor_instance: WindowEditorInstance,
+) -> Result<Vec<IdlePeriod>, String> {
+    let project = editor_instance.project_config.1.borrow().clone();
+    let meta = editor_instance.meta();
+    let options = IdleOptions::default();
+    let durations = editor_instance.recordings.segment_durations();
+
+    let segments = match &meta.content {
+        Content::SingleSegment { segment } => {
+            vec![(meta.path(&segment.display.path), CursorEvents::default())]
+        }
+        Content::MultipleSegments { inner } => inner
+            .segments
+            .iter()
+            .map(|s| (meta.path(&s.display.path), s.cursor_events(&meta)))
+            .collect(),
+    };
+
+    let mut idle = vec![];
+    for ((display, cursor), duration) in segments.into_iter().zip(&durations) {
+        let mut activity =
+            tokio::task::spawn_blocking(move || cap_editor::detect_frame_changes(&display))
+                .await
+                .map_err(|e| e.to_string())??;
+        activity.extend(input_activity(&cursor, &options));
+
+        idle.push(detect_idle(&activity, *duration, &options));
+    }
+
+    let timeline = project
+        .timeline
+        .unwrap_or_else(|| TimelineConfiguration::from_segment_durations(&durations));
+
+    Ok(suggest_idle_periods(&timeline, &idle))
+}
+
+// Finds emails, API keys and card numbers shown on screen, suggesting blurs over them.
+// Reads the text off every segment's display video, which takes a while.
+#[tauri::command]
+#[specta::specta]
+async fn generate_redactions(
+    editor_instance: WindowEditorInstance,
+) -> Result<Vec<RedactionSuggestion>, String> {
+    let project = editor_instance.project_config.1.borrow().clone();
+    let meta = editor_instance.meta();
+    let durations = editor_instance.recordings.segment_durations();
+
+    let displays = match &meta.content {
+        Content::SingleSegment { segment } => vec![meta.path(&segment.display.path)],
+        Content::MultipleSegments { inner } => inner
+            .segments
+            .iter()
+            .map(|s| meta.path(&s.display.path))
+            .collect(),
+    };
+
+    let mut regions = vec![];
+    for display in displays {
+        let frames = tokio::task::spawn_blocking(move || cap_editor::read_frames(&display))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        regions.push(track_sensitive_text(&frames, cap_editor::OCR_INTERVAL_SECS));
+    }
+
+    let timeline = project
+        .timeline
+        .unwrap_or_else(|| TimelineConfiguration::from_segment_durations(&durations));
+
+    Ok(suggest_redactions(&timeline, &regions))
+}
+
+fn caption_format(path: &std::path::Path) -> Result<CaptionFormat, String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(CaptionFormat::from_extension)
+        .ok_or_else(|| "Captions have to be SRT or WebVTT files".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_captions(path: PathBuf) -> Result<Vec<CaptionSegment>, String> {
+    caption_format(&path)?;
+    let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+
+    parse_captions(&contents).map_err(|e| e.to_string())
+}
+
+// Writes `captions` as SRT or WebVTT, going by the path's extension
+#[tauri::command]
+#[specta::specta]
+async fn export_captions(path: PathBuf, captions: Vec<CaptionSegment>) -> Result<(), String> {
+    let format = caption_format(&path)?;
+
+    std::fs::write(&path, write_captions(&captions, format)).map_err(|e| e.to_string())
+}
+
+// The first whisper.cpp model in the app's `whisper` folder, like `ggml-base.en.bin`
+fn whisper_model(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("whisper");
+    let no_model = || format!("No whisper.cpp model found, add one to {}", dir.display());
+
+    let mut models = std::fs::read_dir(&dir)
+        .map_err(|_| no_model())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("ggml-") && n.ends_with(".bin"))
+        })
+        .collect::<Vec<_>>();
+    models.sort();
+
+    models.into_iter().next().ok_or_else(no_model)
+}
+
+// Transcribes each segment's microphone audio on this machine into captions for the
+// timeline, which takes a while
+#[tauri::command]
+#[specta::specta]
+async fn generate_captions(
+    app: AppHandle,
+    editor_instance: WindowEditorInstance,
+    progress: tauri::ipc::Channel<TranscriptionProgress>,
+) -> Result<Vec<CaptionSegment>, String> {
+    let model = whisper_model(&app)?;
+    let project = editor_instance.project_config.1.borrow().clone();
+    let durations = editor_instance.recordings.segment_durations();
+    let mics = editor_instance
+        .segments
+        .iter()
+        .map(|s| {
+            s.audio
+                .as_ref()
+                .as_ref()
+                .and_then(|audio| audio.mic.clone())
+        })
+        .collect::<Vec<_>>();
+
+    let words = tokio::task::spawn_blocking(move || {
+        let segments = mics.len() as u32;
+
+        mics.iter()
+            .enumerate()
+            .map(|(i, mic)| {
+                let Some(mic) = mic else {
+                    return Ok(vec![]);
+                };
+
+                transcribe(
+                    &mic.buffer,
+                    mic.info.channels,
+                    mic.info.sample_rate,
+                    &model,
+                    |fraction| {
+                        progress
+                            .send(TranscriptionProgress {
+                                segment: i as u32,
+                                segments,
+                                fraction,
+                            })
+                            .ok();
+                    },
+                )
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let timeline = project
+        .timeline
+        .unwrap_or_else(|| TimelineConfiguration::from_segment_durations(&durations));
+
+    Ok(captions_from_transcript(&timeline, &words))
+}
+
+#[derive(Deserialize, specta::Type, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+enum AudioTrack {
+    Mic,
+    System,
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_audio_waveform(
+    editor_instance: WindowEditorInstance,
+    recording_segment: u32,
+    track: AudioTrack,
+) -> Result<Option<Waveform>, String> {
+    let meta = editor_instance.meta();
+    let audio_meta = match &meta.content {
+        Content::SingleSegment { segment } => match track {
+            AudioTrack::Mic => segment.audio.as_ref(),
+            AudioTrack::System => None,
+        },
+        Content::MultipleSegments { inner } => inner
+            .segments
+            .get(recording_segment as usize)
+            .and_then(|s| match track {
+                AudioTrack::Mic => s.audio.as_ref(),
+                AudioTrack::System => s.system_audio.as_ref(),
+            }),
+    };
+    let Some(audio_meta) = audio_meta else {
+        return Ok(None);
+    };
+    let audio_path = meta.path(&audio_meta.path);
+
+    // the editor already decoded the track for playback, which saves decoding it again
+    // when there's no cached waveform yet
+    let Some(data) = editor_instance
+        .segments
+        .get(recording_segment as usize)
+        .and_then(|s| s.audio.as_ref().as_ref())
+        .and_then(|audio| match track {
+            AudioTrack::Mic => audio.mic.clone(),
+            AudioTrack::System => audio.system.clone(),
+        })
+    else {
+        return Ok(None);
+    };
+
+    tokio::task::spawn_blocking(move || {
+        Waveform::load_or_generate(&audio_path, || {
+            (
+                data.buffer.to_vec(),
+                data.info.channels,
+                data.info.sample_rate,
+            )
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map(Some)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_overlay_color_warnings(overlays: OverlayConfiguration) -> Vec<OverlayColorWarning> {
+    overlays.lint()
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn merge_projects(app: AppHandle, project_paths: Vec<PathBuf>) -> Result<PathBuf, String> {
+    let first =
+        RecordingMeta::load_for_project(project_paths.first().ok_or("No projects to merge")?)?;
+    let project_path = recording_path(&app, &uuid::Uuid::new_v4().to_string());
+
+    let meta = cap_editor::merge_projects(
+        &project_paths,
+        project_path.clone(),
+        format!("{} (merged)", first.pretty_name),
+    )
+    .await?;
+
+    if let Content::MultipleSegments { inner } = &meta.content {
+        let screenshots_dir = project_path.join("screenshots");
+        std::fs::create_dir_all(&screenshots_dir).ok();
+        create_screenshot(
+            meta.path(&inner.segments[0].display.path),
+            screenshots_dir.join("display.jpg"),
+            None,
+        )
+        .await?;
+    }
+
+    NewRecordingAdded {
+        path: project_path.clone(),
+    }
+    .emit(&app)
+    .ok();
+
+    Ok(project_path)
+}
+
 #[tauri::command]
 #[specta::specta]
 async fn list_audio_devices() -> Result<Vec<String>, ()> {
@@ -1142,6 +1596,19 @@ async fn upload_exported_video(
     video_id: String,
     mode: UploadMode,
 ) -> Result<UploadResult, String> {
+    // a bucket of the user's own is uploaded to directly, without needing a Cap account
+    if let Some(storage) = S3StorageConfig::get(&app)? {
+        let meta = RecordingMeta::load_for_project(&recording_path(&app, &video_id))?;
+        let output_path = meta.output_path();
+        if !output_path.exists() {
+            notifications::send_notification(&app, notifications::NotificationType::UploadFailed);
+            return Err("Failed to upload video: Rendered video not found".to_string());
+        }
+
+        let uploaded = s3_storage::upload_video(&app, storage, &video_id, &output_path).await;
+        return finish_upload(&app, meta, video_id, uploaded).await;
+    }
+
     let Ok(Some(mut auth)) = AuthStore::get(&app) else {
         AuthStore::set(&app, None).map_err(|e| e.to_string())?;
         return Ok(UploadResult::NotAuthenticated);
@@ -1182,13 +1649,7 @@ async fn upload_exported_video(
         return Ok(UploadResult::UpgradeRequired);
     }
 
-    let mut meta = RecordingMeta::load_for_project(
-        &app.path()
-            .app_data_dir()
-            .unwrap()
-            .join("recordings")
-            .join(format!("{}.cap", video_id)),
-    )?;
+    let meta = RecordingMeta::load_for_project(&recording_path(&app, &video_id))?;
 
     let output_path = meta.output_path();
     if !output_path.exists() {
@@ -1225,34 +1686,60 @@ async fn upload_exported_video(
     }
     .await?;
 
-    match upload_video(&app, video_id.clone(), output_path, false, Some(s3_config)).await {
-        Ok(uploaded_video) => {
+    let uploaded = upload_video(&app, video_id.clone(), output_path, false, Some(s3_config))
+        .await
+        .map(|uploaded_video| SharingMeta {
+            link: uploaded_video.link,
+            id: uploaded_video.id,
+        });
+
+    finish_upload(&app, meta, video_id, uploaded).await
+}
+
+async fn finish_upload(
+    app: &AppHandle,
+    mut meta: RecordingMeta,
+    video_id: String,
+    uploaded: Result<SharingMeta, String>,
+) -> Result<UploadResult, String> {
+    match uploaded {
+        Ok(sharing) => {
             // Emit upload complete
             UploadProgress {
                 progress: 1.0,
                 message: "Upload complete!".to_string(),
             }
-            .emit(&app)
+            .emit(app)
             .ok();
 
-            meta.sharing = Some(SharingMeta {
-                link: uploaded_video.link.clone(),
-                id: uploaded_video.id.clone(),
-            });
+            let link = sharing.link.clone();
+            // reuploads keep the link the first upload made
+            let link_created = !meta.sharing.as_ref().is_some_and(|s| s.link == link);
+            meta.sharing = Some(sharing);
             meta.save_for_project().ok();
-            RecordingMetaChanged { id: video_id }.emit(&app).ok();
+            RecordingMetaChanged {
+                id: video_id.clone(),
+            }
+            .emit(app)
+            .ok();
+
+            let data = json!({ "id": video_id, "link": link });
+            webhooks::notify(app, WebhookEvent::UploadComplete, data.clone());
+            if link_created {
+                webhooks::notify(app, WebhookEvent::ShareLinkCreated, data);
+            }
 
             let _ = app
                 .state::<ArcLock<ClipboardContext>>()
                 .write()
                 .await
-                .set_text(uploaded_video.link.clone());
+                .set_text(link.clone());
 
-            NotificationType::ShareableLinkCopied.send(&app);
-            Ok(UploadResult::Success(uploaded_video.link))
+            NotificationType::ShareableLinkCopied.send(app);
+            Ok(UploadResult::Success(link))
         }
         Err(e) => {
-            NotificationType::UploadFailed.send(&app);
+            NotificationType::UploadFailed.send(app);
             Err(e)
         }
     }
@@ -1297,16 +1784,18 @@ async fn upload_screenshot(
         });
         meta.save_for_project();
 
-        RecordingMetaChanged {
-            id: screenshot_path
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string(),
-        }
-        .emit(&app)
-        .ok();
+        let id = screenshot_path
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        RecordingMetaChanged { id: id.clone() }.emit(&app).ok();
+
+        let data = json!({ "id": id, "link": uploaded.link });
+        webhooks::notify(&app, WebhookEvent::UploadComplete, data.clone());
+        webhooks::notify(&app, WebhookEvent::ShareLinkCreated, data);
 
         uploaded.link
     };
@@ -1798,11 +2287,18 @@ async fn is_camera_window_open(app: AppHandle) -> bool {
 #[tauri::command]
 #[specta::specta]
 async fn seek_to(editor_instance: WindowEditorInstance, frame_number: u32) -> Result<(), String> {
-    editor_instance
-        .modify_and_emit_state(|state| {
-            state.playhead_position = frame_number;
-        })
-        .await;
+    editor_instance.seek(frame_number).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn set_playback_loop(
+    editor_instance: WindowEditorInstance,
+    enabled: bool,
+) -> Result<(), String> {
+    editor_instance.set_loop_playback(enabled).await;
 
     Ok(())
 }
@@ -1928,10 +2424,12 @@ pub async fn run() {
         .commands(tauri_specta::collect_commands![
             get_recording_options,
             set_recording_options,
+            recording::prepare_recording,
             recording::start_recording,
             recording::stop_recording,
             recording::pause_recording,
             recording::resume_recording,
+            recording::add_recording_marker,
             recording::list_cameras,
             recording::list_capture_windows,
             recording::list_capture_screens,
@@ -1943,7 +2441,29 @@ pub async fn run() {
             focus_captures_panel,
             get_current_recording,
             export::export_video,
+            export::export_split,
+            export::export_animated,
+            export::export_to_stream,
             export::get_export_estimates,
+            export::cancel_export,
+            export_queue::queue_exports,
+            export_queue::list_export_jobs,
+            export_queue::cancel_export_job,
+            export_queue::clear_finished_export_jobs,
+            export_queue::set_export_queue_parallelism,
+            storage::get_storage_info,
+            storage::set_recordings_dir,
+            project_bundle::export_project_bundle,
+            project_bundle::import_project_bundle,
+            lan_share::start_lan_share,
+            lan_share::stop_lan_share,
+            lan_share::get_lan_share,
+            s3_storage::get_s3_storage_config,
+            s3_storage::set_s3_storage_config,
+            s3_storage::test_s3_storage_config,
+            webhooks::get_webhooks,
+            webhooks::set_webhooks,
+            webhooks::test_webhook,
             copy_file_to_path,
             copy_video_to_clipboard,
             copy_screenshot_to_clipboard,
@@ -1952,8 +2472,25 @@ pub async fn run() {
             create_editor_instance,
             start_playback,
             stop_playback,
+            virtual_camera::start_virtual_camera,
+            virtual_camera::stop_virtual_camera,
+            virtual_camera::get_virtual_camera,
             set_playhead_position,
             set_project_config,
+            apply_project_command,
+            undo_project_edit,
+            redo_project_edit,
+            generate_zoom_segments,
+            zoom_debug::set_zoom_debug,
+            generate_silence_cuts,
+            generate_idle_periods,
+            generate_redactions,
+            import_captions,
+            export_captions,
+            generate_captions,
+            get_audio_waveform,
+            get_overlay_color_warnings,
+            merge_projects,
             open_editor,
             permissions::open_permission_settings,
             permissions::do_permissions_check,
@@ -1971,6 +2508,7 @@ pub async fn run() {
             reset_microphone_permissions,
             is_camera_window_open,
             seek_to,
+            set_playback_loop,
             send_feedback_request,
             windows::position_traffic_lights,
             windows::set_theme,
@@ -1992,16 +2530,22 @@ pub async fn run() {
             CurrentRecordingChanged,
             RecordingMetaChanged,
             RecordingStarted,
+            RecordingTimer,
             RecordingStopped,
             RequestStartRecording,
             RequestRestartRecording,
             RequestStopRecording,
             RequestNewScreenshot,
+            RequestTogglePauseRecording,
+            RequestAddMarker,
+            RequestToggleCamera,
             RequestOpenSettings,
             NewNotification,
             AuthenticationInvalid,
             audio_meter::AudioInputLevelChange,
             UploadProgress,
+            export_queue::ExportJobEvent,
+            zoom_debug::ZoomDebugChanged,
         ])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
         .typ::<ProjectConfiguration>()
@@ -2080,6 +2624,14 @@ pub async fn run() {
             hotkeys::init(&app);
             general_settings::init(&app);
             fake_window::init(&app);
+            export::init(&app);
+            export_queue::init(&app);
+            watch_folders::init(&app);
+            recovery::init(&app);
+            virtual_camera::init(&app);
+            lan_share::init(&app);
+            deep_link::init(&app);
+            zoom_debug::init(&app);
 
             if let Ok(Some(auth)) = AuthStore::load(&app) {
                 sentry::configure_scope(|scope| {
@@ -2107,8 +2659,15 @@ pub async fn run() {
                         }),
                         camera_label: None,
                         audio_input_name: None,
+                        sample_ui_elements: false,
+                        record_keyboard: false,
+                        additional_displays: vec![],
+                        replay_buffer_secs: None,
+                        max_duration_secs: None,
+                        capture_system_audio: false,
                     },
                     current_recording: None,
+                    prepared_recording: None,
                     pre_created_video: None,
                 })));
 
@@ -2194,6 +2753,33 @@ pub async fn run() {
                 }
             });
 
+            RequestAddMarker::listen_any_spawn(&app, |_, app| async move {
+                if let Err(e) = recording::add_recording_marker(app.state()).await {
+                    eprintln!("Failed to add marker: {}", e);
+                }
+            });
+
+            RequestToggleCamera::listen_any_spawn(&app, |_, app| async move {
+                let state = app.state::<Arc<RwLock<App>>>();
+                let mut options = {
+                    let app_state = state.read().await;
+                    // the recording keeps the camera it started with
+                    if app_state.current_recording.is_some() {
+                        return;
+                    }
+                    app_state.start_recording_options.clone()
+                };
+
+                options.camera_label = match options.camera_label {
+                    Some(_) => None,
+                    None => recording::list_cameras().into_iter().next(),
+                };
+
+                if let Err(e) = set_recording_options(app.clone(), state, options).await {
+                    eprintln!("Failed to toggle camera: {}", e);
+                }
+            });
+
             RequestOpenSettings::listen_any_spawn(&app, |payload, app| async move {
                 ShowCapWindow::Settings {
                     page: Some(payload.page),
@@ -2216,6 +2802,12 @@ pub async fn run() {
                                 if let Some(w) = CapWindowId::Camera.get(app) {
                                     w.close().ok();
                                 }
+
+                                let app = app.clone();
+                                tokio::spawn(async move {
+                                    let state = app.state::<Arc<RwLock<App>>>();
+                                    state.write().await.discard_prepared_recording().await;
+                                });
                             }
                             CapWindowId::Editor { .. } => {
                                 EditorInstances::remove(window);
@@ -2311,6 +2903,10 @@ async fn create_editor_instance_impl(
                     .unwrap_or(false)
             }
         },
+        !GeneralSettingsStore::get(&app)
+            .ok()
+            .flatten()
+            .is_some_and(|s| s.disable_proxy_media),
     )
     .await?;
 
@@ -2332,13 +2928,11 @@ async fn create_editor_instance_impl(
 
 // use EditorInstance.project_path instead of this
 fn recordings_path(app: &AppHandle) -> PathBuf {
-    let path = app.path().app_data_dir().unwrap().join("recordings");
-    std::fs::create_dir_all(&path).unwrap_or_default();
-    path
+    storage::recordings_dir(app)
 }
 
 fn recording_path(app: &AppHandle, recording_id: &str) -> PathBuf {
-    recordings_path(app).join(format!("{}.cap", recording_id))
+    storage::project_dir(app, recording_id)
 }
 
 fn screenshots_path(app: &AppHandle) -> PathBuf {
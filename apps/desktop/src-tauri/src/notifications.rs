@@ -13,6 +13,8 @@ pub enum NotificationType {
     ScreenshotCopiedToClipboard,
     ScreenshotSaveFailed,
     ScreenshotCopyFailed,
+    LowDiskSpace,
+    RecordingStoppedDiskFull,
 }
 
 impl NotificationType {
@@ -61,6 +63,16 @@ impl NotificationType {
                 "Unable to copy screenshot to clipboard. Please try again",
                 true,
             ),
+            NotificationType::LowDiskSpace => (
+                "Low Disk Space",
+                "Your disk is almost full. The recording will stop before it runs out of space",
+                true,
+            ),
+            NotificationType::RecordingStoppedDiskFull => (
+                "Recording Stopped",
+                "Your disk is almost full, so the recording was stopped and saved",
+                true,
+            ),
         }
     }
 
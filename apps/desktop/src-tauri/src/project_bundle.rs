@@ -0,0 +1,83 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use cap_project::{extract_bundle, write_project_bundle, RecordingMeta};
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{storage, NewRecordingAdded};
+
+// Packs the project into a single .capproj file that can be imported on another machine
+#[tauri::command]
+#[specta::specta]
+pub async fn export_project_bundle(
+    project_path: PathBuf,
+    destination: PathBuf,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        // written next to the destination so a failed export doesn't leave half a bundle
+        let partial = destination.with_extension("capproj.partial");
+
+        let result = File::create(&partial)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                write_project_bundle(&project_path, BufWriter::new(file)).map_err(|e| e.to_string())
+            })
+            .and_then(|_| std::fs::rename(&partial, &destination).map_err(|e| e.to_string()));
+
+        if result.is_err() {
+            std::fs::remove_file(&partial).ok();
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Unpacks a bundle into a new project in the recordings folder
+#[tauri::command]
+#[specta::specta]
+pub async fn import_project_bundle(
+    app: AppHandle,
+    bundle_path: PathBuf,
+) -> Result<PathBuf, String> {
+    let project_path = storage::project_dir(&app, &uuid::Uuid::new_v4().to_string());
+
+    let bundle_size = std::fs::metadata(&bundle_path)
+        .map_err(|e| e.to_string())?
+        .len();
+    if cap_utils::available_disk_space(&storage::recordings_dir(&app))
+        .is_ok_and(|available| available < bundle_size)
+    {
+        return Err("There isn't enough space to import this project".to_string());
+    }
+
+    let imported = tokio::task::spawn_blocking({
+        let project_path = project_path.clone();
+        move || {
+            let file = File::open(&bundle_path).map_err(|e| e.to_string())?;
+            extract_bundle(BufReader::new(file), &project_path, |_| true)
+                .map_err(|e| e.to_string())?;
+            RecordingMeta::load_for_project(&project_path)
+                .map_err(|_| "The bundle doesn't contain a recording".to_string())
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Err(e) = imported {
+        std::fs::remove_dir_all(&project_path).ok();
+        return Err(e);
+    }
+
+    NewRecordingAdded {
+        path: project_path.clone(),
+    }
+    .emit(&app)
+    .ok();
+
+    Ok(project_path)
+}
@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Instant};
+use std::{path::PathBuf, sync::Arc, time::Instant};
 
 use crate::{
     audio::AppSounds,
@@ -8,23 +8,25 @@ use crate::{
     general_settings::GeneralSettingsStore,
     notifications, open_editor, open_external_link,
     presets::PresetsStore,
+    recording_path,
     upload::get_s3_config,
     upload_exported_video, web_api,
+    webhooks::{self, WebhookEvent},
     windows::{CapWindowId, ShowCapWindow},
     App, CurrentRecordingChanged, MutableState, NewRecordingAdded, PreCreatedVideo,
-    RecordingStarted, RecordingStopped, UploadMode,
+    RecordingStarted, RecordingStopped, RecordingTimer, UploadMode,
 };
+use cap_export::ExportSettings;
 use cap_fail::fail;
 use cap_flags::FLAGS;
 use cap_media::sources::{CaptureScreen, CaptureWindow};
 use cap_media::{feeds::CameraFeed, sources::ScreenCaptureTarget};
-use cap_project::{
-    Content, ProjectConfiguration, TimelineConfiguration, TimelineSegment, ZoomSegment, XY,
-};
-use cap_recording::CompletedRecording;
-use cap_rendering::ProjectRecordings;
+use cap_project::{Content, ProjectConfiguration, TimelineConfiguration, ZoomSegment, XY};
+use cap_recording::{ActorEvent, CompletedRecording};
+use cap_rendering::{FrameRateConversion, ProjectRecordings};
 use cap_utils::spawn_actor;
 use clipboard_rs::{Clipboard, ClipboardContext};
+use serde_json::json;
 use tauri::{AppHandle, Manager};
 use tauri_specta::Event;
 
@@ -52,6 +54,42 @@ pub fn list_cameras() -> Vec<String> {
     CameraFeed::list_cameras()
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn prepare_recording(
+    app: AppHandle,
+    state_mtx: MutableState<'_, App>,
+) -> Result<(), String> {
+    let mut state = state_mtx.write().await;
+
+    prepare_next_recording(&app, &mut state).await
+}
+
+// Builds the capture and encoder pipeline without starting it, so hitting record only has
+// to start the clock. Replaces any recording that was already prepared.
+pub async fn prepare_next_recording(app: &AppHandle, state: &mut App) -> Result<(), String> {
+    if state.current_recording.is_some() {
+        return Ok(());
+    }
+
+    state.discard_prepared_recording().await;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let prepared = cap_recording::prepare_recording(
+        id.clone(),
+        recording_path(app, &id),
+        state.start_recording_options.clone(),
+        state.camera_feed.clone(),
+        state.audio_input_feed.clone(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    state.prepared_recording = Some(prepared);
+
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn start_recording(
@@ -62,12 +100,7 @@ pub async fn start_recording(
 
     let id = uuid::Uuid::new_v4().to_string();
 
-    let recording_dir = app
-        .path()
-        .app_data_dir()
-        .unwrap()
-        .join("recordings")
-        .join(format!("{id}.cap"));
+    let recording_dir = recording_path(&app, &id);
 
     // Check if auto_create_shareable_link is true and user is upgraded
     let general_settings = GeneralSettingsStore::get(&app)?;
@@ -94,24 +127,78 @@ pub async fn start_recording(
 
     if matches!(
         state.start_recording_options.capture_target,
-        ScreenCaptureTarget::Window(_) | ScreenCaptureTarget::Area(_)
+        ScreenCaptureTarget::Window(CaptureWindow {
+            isolated: false,
+            ..
+        }) | ScreenCaptureTarget::Area(_)
     ) {
         let _ = ShowCapWindow::WindowCaptureOccluder.show(&app);
     }
 
-    let (actor, actor_done_rx) = cap_recording::spawn_recording_actor(
-        id,
-        recording_dir,
-        state.start_recording_options.clone(),
-        state.camera_feed.clone(),
-        state.audio_input_feed.clone(),
-    )
-    .await
+    let (actor, actor_done_rx) = match state.prepared_recording.take() {
+        Some(prepared) => prepared.start().await,
+        None => {
+            cap_recording::spawn_recording_actor(
+                id,
+                recording_dir,
+                state.start_recording_options.clone(),
+                state.camera_feed.clone(),
+                state.audio_input_feed.clone(),
+            )
+            .await
+        }
+    }
     .map_err(|e| e.to_string())?;
 
+    let events = actor.events.clone();
+    let options = actor.options.clone();
     state.set_current_recording(actor);
     drop(state);
 
+    spawn_actor({
+        let app = app.clone();
+        let state_mtx = Arc::clone(&state_mtx);
+        async move {
+            while let Ok(event) = events.recv_async().await {
+                match event {
+                    ActorEvent::StartsIn(secs) => {
+                        RecordingTimer::StartsIn { secs }.emit(&app).ok();
+                    }
+                    ActorEvent::StopsIn(secs) => {
+                        RecordingTimer::StopsIn { secs }.emit(&app).ok();
+                    }
+                    ActorEvent::Started => {}
+                    ActorEvent::LowDiskSpace(_) => {
+                        notifications::send_notification(
+                            &app,
+                            notifications::NotificationType::LowDiskSpace,
+                        );
+                    }
+                    ActorEvent::DiskFull => {
+                        notifications::send_notification(
+                            &app,
+                            notifications::NotificationType::RecordingStoppedDiskFull,
+                        );
+                    }
+                    // the actor holds on to the finished recording until it's told to stop
+                    ActorEvent::AutoStopped => {
+                        let mut state = state_mtx.write().await;
+                        let Some(current_recording) = state.clear_current_recording() else {
+                            break;
+                        };
+
+                        if let Ok(completed_recording) = current_recording.stop().await {
+                            handle_recording_finished(app, Some(completed_recording), &mut state)
+                                .await
+                                .ok();
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
     spawn_actor({
         let app = app.clone();
         let state_mtx = Arc::clone(&state_mtx);
@@ -141,6 +228,16 @@ pub async fn start_recording(
 
     RecordingStarted.emit(&app).ok();
 
+    webhooks::notify(
+        &app,
+        WebhookEvent::RecordingStarted,
+        json!({
+            "captureTarget": options.capture_target,
+            "camera": options.camera_label,
+            "mic": options.audio_input_name,
+        }),
+    );
+
     Ok(())
 }
 
@@ -168,19 +265,40 @@ pub async fn resume_recording(state: MutableState<'_, App>) -> Result<(), String
     Ok(())
 }
 
+// Drops a marker into the recording in progress, if there is one
+#[tauri::command]
+#[specta::specta]
+pub async fn add_recording_marker(state: MutableState<'_, App>) -> Result<(), String> {
+    let state = state.read().await;
+
+    if let Some(recording) = state.current_recording.as_ref() {
+        recording.add_marker().await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn stop_recording(app: AppHandle, state: MutableState<'_, App>) -> Result<(), String> {
     let mut state = state.write().await;
+    stop_current_recording(app, &mut state).await?;
+
+    Ok(())
+}
+
+// Stops the recording in progress, returning the project it was saved to
+pub async fn stop_current_recording(app: AppHandle, state: &mut App) -> Result<PathBuf, String> {
     let Some(current_recording) = state.clear_current_recording() else {
         return Err("Recording not in progress".to_string())?;
     };
 
     let completed_recording = current_recording.stop().await.map_err(|e| e.to_string())?;
+    let recording_dir = completed_recording.recording_dir.clone();
 
-    handle_recording_finished(app, Some(completed_recording), &mut state).await?;
+    handle_recording_finished(app, Some(completed_recording), state).await?;
 
-    Ok(())
+    Ok(recording_dir)
 }
 
 async fn handle_recording_finished(
@@ -223,11 +341,21 @@ async fn handle_recording_finished(
         .ok();
 
         RecordingStopped {
-            path: recording_dir,
+            path: recording_dir.clone(),
         }
         .emit(&app)
         .ok();
 
+        webhooks::notify(
+            &app,
+            WebhookEvent::RecordingStopped,
+            json!({
+                "id": completed_recording.id,
+                "name": completed_recording.meta.pretty_name,
+                "path": recording_dir,
+            }),
+        );
+
         let recordings = ProjectRecordings::new(&completed_recording.meta);
 
         let config = project_config_from_recording(
@@ -240,6 +368,19 @@ async fn handle_recording_finished(
             .write(&completed_recording.recording_dir)
             .map_err(|e| e.to_string())?;
 
+        let use_proxies = !GeneralSettingsStore::get(&app)
+            .ok()
+            .flatten()
+            .is_some_and(|s| s.disable_proxy_media);
+        if use_proxies {
+            let meta = completed_recording.meta.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = cap_editor::generate_proxies(&meta) {
+                    tracing::error!("Failed to generate proxies: {e}");
+                }
+            });
+        }
+
         if let Some(pre_created_video) = state.pre_created_video.take() {
             spawn_actor({
                 let app = app.clone();
@@ -280,6 +421,10 @@ async fn handle_recording_finished(
                                     true,
                                     completed_recording.meta.content.max_fps(),
                                     XY::new(1920, 1080),
+                                    None,
+                                    FrameRateConversion::Duplicate,
+                                    ExportSettings::default(),
+                                    false,
                                 )
                                 .await
                                 .ok();
@@ -389,18 +534,8 @@ fn project_config_from_recording(
 ) -> ProjectConfiguration {
     ProjectConfiguration {
         timeline: Some(TimelineConfiguration {
-            segments: recordings
-                .segments
-                .iter()
-                .enumerate()
-                .map(|(i, segment)| TimelineSegment {
-                    recording_segment: i as u32,
-                    start: 0.0,
-                    end: segment.duration(),
-                    timescale: 1.0,
-                })
-                .collect(),
             zoom_segments: generate_zoom_segments_from_clicks(&completed_recording, &recordings),
+            ..completed_recording.timeline(&recordings.segment_durations())
         }),
         ..default_config.unwrap_or_default()
     }
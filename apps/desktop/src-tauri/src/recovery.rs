@@ -0,0 +1,48 @@
+use cap_project::Content;
+use cap_recording::recovery::{find_unfinished_recordings, recover_recording};
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{create_screenshot, recordings_path, NewRecordingAdded};
+
+// Recordings that were still going when the app crashed or was killed are repaired and
+// added to the recordings list, so the capture isn't lost
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+
+    tokio::spawn(async move {
+        for project_path in find_unfinished_recordings(&recordings_path(&app)) {
+            println!("Recovering unfinished recording {}", project_path.display());
+
+            let recovered = tokio::task::spawn_blocking({
+                let project_path = project_path.clone();
+                move || recover_recording(&project_path)
+            })
+            .await;
+
+            let recovered = match recovered {
+                Ok(Ok(recovered)) => recovered,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to recover {}: {e}", project_path.display());
+                    continue;
+                }
+                Err(_) => continue,
+            };
+
+            for path in &recovered.lost {
+                eprintln!("Couldn't recover {}", path.display());
+            }
+
+            if let Content::MultipleSegments { inner } = &recovered.meta.content {
+                let display_path = recovered.meta.path(&inner.segments[0].display.path);
+                let screenshots_dir = project_path.join("screenshots");
+                std::fs::create_dir_all(&screenshots_dir).ok();
+                create_screenshot(display_path, screenshots_dir.join("display.jpg"), None)
+                    .await
+                    .ok();
+            }
+
+            NewRecordingAdded { path: project_path }.emit(&app).ok();
+        }
+    });
+}
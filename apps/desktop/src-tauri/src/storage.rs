@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Manager};
+
+use crate::{general_settings::GeneralSettingsStore, App, MutableState};
+
+#[derive(Serialize, Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageInfo {
+    pub recordings_dir: PathBuf,
+    pub is_default: bool,
+    // free space on the folder's disk, as an f64 since the bindings can't take a u64
+    pub available_bytes: Option<f64>,
+}
+
+// The recordings folder in the app's data, used unless another one is set
+pub fn default_recordings_dir(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("recordings")
+}
+
+// Where projects are kept. A custom folder that's gone, like an external drive that isn't
+// plugged in, falls back to the default one.
+pub fn recordings_dir(app: &AppHandle) -> PathBuf {
+    let custom = GeneralSettingsStore::get(app)
+        .ok()
+        .flatten()
+        .and_then(|s| s.recordings_dir);
+
+    let path = match custom {
+        Some(path) if path.is_dir() => path,
+        _ => default_recordings_dir(app),
+    };
+    std::fs::create_dir_all(&path).ok();
+    path
+}
+
+pub fn project_dir(app: &AppHandle, id: &str) -> PathBuf {
+    recordings_dir(app).join(format!("{id}.cap"))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_storage_info(app: AppHandle) -> StorageInfo {
+    let recordings_dir = recordings_dir(&app);
+
+    StorageInfo {
+        is_default: recordings_dir == default_recordings_dir(&app),
+        available_bytes: cap_utils::available_disk_space(&recordings_dir)
+            .ok()
+            .map(|bytes| bytes as f64),
+        recordings_dir,
+    }
+}
+
+// Keeps new recordings in `path`, or the default folder if it's None, moving the existing
+// ones there too if `move_projects` is set
+#[tauri::command]
+#[specta::specta]
+pub async fn set_recordings_dir(
+    app: AppHandle,
+    state: MutableState<'_, App>,
+    path: Option<PathBuf>,
+    move_projects: bool,
+) -> Result<StorageInfo, String> {
+    if state.read().await.current_recording.is_some() {
+        return Err("Stop recording before changing where recordings are kept".to_string());
+    }
+
+    let from = recordings_dir(&app);
+    let to = path.clone().unwrap_or_else(|| default_recordings_dir(&app));
+
+    std::fs::create_dir_all(&to).map_err(|e| format!("Failed to create {}: {e}", to.display()))?;
+    let to = to.canonicalize().map_err(|e| e.to_string())?;
+    check_writable(&to)?;
+
+    if move_projects {
+        let from = from.canonicalize().map_err(|e| e.to_string())?;
+
+        if from != to {
+            if to.starts_with(&from) {
+                return Err("Recordings can't be moved into a folder inside their own".to_string());
+            }
+
+            tokio::task::spawn_blocking(move || move_projects_between(&from, &to))
+                .await
+                .map_err(|e| e.to_string())??;
+        }
+    }
+
+    GeneralSettingsStore::update(&app, |settings| settings.recordings_dir = path)?;
+
+    Ok(get_storage_info(app))
+}
+
+fn check_writable(dir: &Path) -> Result<(), String> {
+    let probe = dir.join(".cap-write-test");
+    std::fs::write(&probe, []).map_err(|e| format!("Can't write to {}: {e}", dir.display()))?;
+    std::fs::remove_file(&probe).ok();
+    Ok(())
+}
+
+fn move_projects_between(from: &Path, to: &Path) -> Result<(), String> {
+    let projects = std::fs::read_dir(from)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path.extension().is_some_and(|e| e == "cap"))
+        .collect::<Vec<_>>();
+
+    // checked up front so nothing is moved if some of it can't be
+    if let Some(taken) = projects
+        .iter()
+        .filter_map(|project| Some(to.join(project.file_name()?)))
+        .find(|target| target.exists())
+    {
+        return Err(format!("{} already exists", taken.display()));
+    }
+
+    for project in projects {
+        let target = to.join(project.file_name().unwrap());
+        move_project(&project, &target)?;
+        rewrite_project_paths(&target, &project, &target)?;
+    }
+
+    Ok(())
+}
+
+fn move_project(from: &Path, to: &Path) -> Result<(), String> {
+    // folders can only be renamed within a disk, and are copied to others
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    let size = dir_size(from).map_err(|e| e.to_string())?;
+    let parent = to.parent().unwrap_or(to);
+    if cap_utils::available_disk_space(parent).is_ok_and(|available| available < size) {
+        return Err(format!(
+            "There isn't enough space in {} for {}",
+            parent.display(),
+            from.display()
+        ));
+    }
+
+    if let Err(e) = copy_dir(from, to) {
+        std::fs::remove_dir_all(to).ok();
+        return Err(format!("Failed to copy {}: {e}", from.display()));
+    }
+
+    std::fs::remove_dir_all(from).map_err(|e| e.to_string())
+}
+
+fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+// Points paths in the project's config that were inside its old folder, like imported
+// images, at the new one. The recording's own files are stored relative to the project.
+fn rewrite_project_paths(project: &Path, from: &Path, to: &Path) -> Result<(), String> {
+    let config_path = project.join("project-config.json");
+    let Ok(config) = std::fs::read_to_string(&config_path) else {
+        return Ok(());
+    };
+
+    let mut config: serde_json::Value = serde_json::from_str(&config).map_err(|e| e.to_string())?;
+    let (from, to) = (from.to_string_lossy(), to.to_string_lossy());
+
+    if rewrite_strings(&mut config, &from, &to) {
+        std::fs::write(
+            &config_path,
+            serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn rewrite_strings(value: &mut serde_json::Value, from: &str, to: &str) -> bool {
+    match value {
+        serde_json::Value::String(s) => match s.strip_prefix(from) {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+                *s = format!("{to}{rest}");
+                true
+            }
+            _ => false,
+        },
+        serde_json::Value::Array(values) => values
+            .iter_mut()
+            .fold(false, |changed, v| rewrite_strings(v, from, to) || changed),
+        serde_json::Value::Object(values) => values
+            .values_mut()
+            .fold(false, |changed, v| rewrite_strings(v, from, to) || changed),
+        _ => false,
+    }
+}
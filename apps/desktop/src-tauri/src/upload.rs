@@ -1,17 +1,15 @@
 // credit @filleduchaos
 
-use futures::stream;
 use image::codecs::jpeg::JpegEncoder;
 use image::ImageReader;
 use reqwest::{multipart::Form, StatusCode};
 use std::path::PathBuf;
 use tauri::AppHandle;
-use tauri_specta::Event;
 use tokio::task;
 
+use crate::uploader::{self, CapCloud, UploadOptions};
 use crate::web_api::{self, ManagerExt};
 
-use crate::UploadProgress;
 use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -219,55 +217,10 @@ pub async fn upload_video(
         },
     )?;
 
-    let (upload_url, form) = presigned_s3_url(app, body).await?;
-
-    let file_bytes = tokio::fs::read(&file_path)
-        .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let total_size = file_bytes.len() as f64;
-
-    // Wrap file_bytes in an Arc for shared ownership
-    let file_bytes = std::sync::Arc::new(file_bytes);
-
-    // Create a stream that reports progress
-    let file_part =
-        {
-            let progress_counter = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
-            let app_handle = app.clone();
-            let file_bytes = file_bytes.clone();
-
-            let stream = stream::iter((0..file_bytes.len()).step_by(1024 * 1024).map(
-                move |start| {
-                    let end = (start + 1024 * 1024).min(file_bytes.len());
-                    let chunk = file_bytes[start..end].to_vec();
-
-                    let current = progress_counter
-                        .fetch_add(chunk.len() as u64, std::sync::atomic::Ordering::SeqCst)
-                        as f64;
-
-                    // Emit progress every chunk
-                    UploadProgress {
-                        progress: current / total_size,
-                        message: format!("{:.0}%", (current / total_size * 100.0)),
-                    }
-                    .emit(&app_handle)
-                    .ok();
-
-                    Ok::<Vec<u8>, std::io::Error>(chunk)
-                },
-            ));
-
-            reqwest::multipart::Part::stream_with_length(
-                reqwest::Body::wrap_stream(stream),
-                total_size as u64,
-            )
-            .file_name(file_name.clone())
-            .mime_str("video/mp4")
-            .map_err(|e| format!("Error setting MIME type: {}", e))?
-        };
-
-    let form = form.part("file", file_part);
+    let backend = CapCloud::new(app, serde_json::json!(body));
+    let upload_options = UploadOptions::from_settings(app);
+    let video_upload =
+        uploader::upload_multipart(app, &backend, &file_path, &file_key, &upload_options);
 
     // Prepare screenshot upload
     let screenshot_path = file_path
@@ -284,10 +237,7 @@ pub async fn upload_video(
         None
     };
 
-    let (video_upload, screenshot_result): (
-        Result<reqwest::Response, reqwest::Error>,
-        Option<Result<reqwest::Response, reqwest::Error>>,
-    ) = tokio::join!(client.post(upload_url).multipart(form).send(), async {
+    let (video_upload, screenshot_result) = tokio::join!(video_upload, async {
         if let Some((screenshot_url, screenshot_form)) = screenshot_upload {
             Some(
                 client
@@ -301,52 +251,29 @@ pub async fn upload_video(
         }
     });
 
-    let response =
-        video_upload.map_err(|e| format!("Failed to send upload file request: {}", e))?;
+    if let Err(e) = video_upload {
+        tracing::error!("Failed to upload video: {e}");
+        return Err(format!("Failed to upload video: {e}"));
+    }
 
-    if response.status().is_success() {
-        // Final progress update
-        UploadProgress {
-            progress: 1.0,
-            message: "100%".to_string(),
-        }
-        .emit(app)
-        .ok();
-
-        println!("Video uploaded successfully");
-
-        if let Some(Ok(screenshot_response)) = screenshot_result {
-            if screenshot_response.status().is_success() {
-                println!("Screenshot uploaded successfully");
-            } else {
-                println!(
-                    "Failed to upload screenshot: {}",
-                    screenshot_response.status()
-                );
-            }
-        }
+    println!("Video uploaded successfully");
 
-        return Ok(UploadedVideo {
-            link: web_api::make_url(format!("/s/{}", &s3_config.id)),
-            id: s3_config.id.clone(),
-            config: s3_config,
-        });
+    if let Some(Ok(screenshot_response)) = screenshot_result {
+        if screenshot_response.status().is_success() {
+            println!("Screenshot uploaded successfully");
+        } else {
+            println!(
+                "Failed to upload screenshot: {}",
+                screenshot_response.status()
+            );
+        }
     }
 
-    let status = response.status();
-    let error_body = response
-        .text()
-        .await
-        .unwrap_or_else(|_| "<no response body>".to_string());
-    tracing::error!(
-        "Failed to upload file. Status: {}. Body: {}",
-        status,
-        error_body
-    );
-    Err(format!(
-        "Failed to upload file. Status: {}. Body: {}",
-        status, error_body
-    ))
+    Ok(UploadedVideo {
+        link: web_api::make_url(format!("/s/{}", &s3_config.id)),
+        id: s3_config.id.clone(),
+        config: s3_config,
+    })
 }
 
 pub async fn upload_image(app: &AppHandle, file_path: PathBuf) -> Result<UploadedImage, String> {
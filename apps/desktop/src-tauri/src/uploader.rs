@@ -0,0 +1,679 @@
+// Uploads large files to S3 in parts, so a dropped connection only costs the part that was
+// in flight and an upload that's stopped partway can carry on from where it got to
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_specta::Event;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::Mutex,
+    time::Instant,
+};
+
+use crate::{
+    general_settings::GeneralSettingsStore,
+    web_api::{self, ManagerExt},
+    UploadProgress,
+};
+
+// S3 won't take parts under 5MB, other than the last one, or more than 10,000 of them
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PARTS: u64 = 10_000;
+// how much of a part is sent at once, which progress and the bandwidth limit go by
+const CHUNK_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_backoff);
+
+        // parts that failed together shouldn't all retry at the same moment
+        let jitter = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_millis() % 250)
+            .unwrap_or(0);
+
+        backoff + Duration::from_millis(jitter as u64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    pub part_size: u64,
+    // parts uploaded at the same time
+    pub concurrency: usize,
+    // bytes per second across all of the parts
+    pub bandwidth_limit: Option<u64>,
+    pub retry: RetryPolicy,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            part_size: 10 * 1024 * 1024,
+            concurrency: 4,
+            bandwidth_limit: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl UploadOptions {
+    pub fn from_settings(app: &AppHandle) -> Self {
+        let limit_mbps = GeneralSettingsStore::get(app)
+            .ok()
+            .flatten()
+            .and_then(|s| s.upload_speed_limit_mbps);
+
+        Self {
+            bandwidth_limit: limit_mbps.map(|mbps| mbps as u64 * 1_000_000 / 8),
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) enum UploadError {
+    // worth trying again, like a dropped connection or the server being busy
+    Retryable(String),
+    // S3 no longer knows the upload, usually because it was left unfinished for too long
+    Expired,
+    Fatal(String),
+}
+
+impl From<UploadError> for String {
+    fn from(e: UploadError) -> Self {
+        match e {
+            UploadError::Retryable(e) | UploadError::Fatal(e) => e,
+            UploadError::Expired => "The upload expired before it finished".to_string(),
+        }
+    }
+}
+
+// Kept next to the file until it's uploaded, so an upload of the same file can be resumed
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadState {
+    file_key: String,
+    upload_id: String,
+    part_size: u64,
+    file_size: u64,
+    modified: Option<SystemTime>,
+    completed: Vec<CompletedPart>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CompletedPart {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+// Where uploads go, which is Cap's cloud unless the user has set up a bucket of their own.
+// Each is an S3 multipart upload underneath.
+pub(crate) trait StorageBackend {
+    // Starts an upload to `key`, returning its upload id
+    async fn create_upload(&self, key: &str) -> Result<String, UploadError>;
+
+    // A URL a part can be PUT to
+    async fn presign_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+    ) -> Result<String, UploadError>;
+
+    async fn complete_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+    ) -> Result<(), UploadError>;
+
+    async fn abort_upload(&self, key: &str, upload_id: &str) -> Result<(), UploadError>;
+}
+
+// Uploads through Cap's API, which signs each request for the bucket the user's account uses
+pub struct CapCloud {
+    app: AppHandle,
+    // the video's metadata, which is sent when the upload is created
+    body: serde_json::Value,
+}
+
+impl CapCloud {
+    pub fn new(app: &AppHandle, body: serde_json::Value) -> Self {
+        Self {
+            app: app.clone(),
+            body,
+        }
+    }
+}
+
+impl StorageBackend for CapCloud {
+    async fn create_upload(&self, _key: &str) -> Result<String, UploadError> {
+        let response = api_request(&self.app, "/api/upload/multipart/initiate", &self.body).await?;
+
+        response["uploadId"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| UploadError::Fatal("Upload id is missing or not a string".to_string()))
+    }
+
+    async fn presign_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+    ) -> Result<String, UploadError> {
+        let response = api_request(
+            &self.app,
+            "/api/upload/multipart/presign-part",
+            &serde_json::json!({
+                "fileKey": key,
+                "uploadId": upload_id,
+                "partNumber": part_number,
+            }),
+        )
+        .await?;
+
+        response["presignedUrl"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| UploadError::Fatal("Presigned URL is missing".to_string()))
+    }
+
+    async fn complete_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[CompletedPart],
+    ) -> Result<(), UploadError> {
+        api_request(
+            &self.app,
+            "/api/upload/multipart/complete",
+            &serde_json::json!({
+                "fileKey": key,
+                "uploadId": upload_id,
+                "parts": parts,
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn abort_upload(&self, key: &str, upload_id: &str) -> Result<(), UploadError> {
+        api_request(
+            &self.app,
+            "/api/upload/multipart/abort",
+            &serde_json::json!({
+                "fileKey": key,
+                "uploadId": upload_id,
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+impl UploadState {
+    fn path(file_path: &Path) -> PathBuf {
+        let mut path = file_path.as_os_str().to_owned();
+        path.push(".upload.json");
+        PathBuf::from(path)
+    }
+
+    // The saved state, if it's for the same upload of the same version of the file
+    async fn load(file_path: &Path, file_key: &str, file: &FileInfo) -> Option<Self> {
+        let state = tokio::fs::read_to_string(Self::path(file_path))
+            .await
+            .ok()?;
+        let state: Self = serde_json::from_str(&state).ok()?;
+
+        (state.file_key == file_key
+            && state.part_size == file.part_size
+            && state.file_size == file.size
+            && state.modified == file.modified)
+            .then_some(state)
+    }
+
+    async fn save(&self, file_path: &Path) {
+        if let Ok(state) = serde_json::to_string(self) {
+            tokio::fs::write(Self::path(file_path), state).await.ok();
+        }
+    }
+
+    async fn remove(file_path: &Path) {
+        tokio::fs::remove_file(Self::path(file_path)).await.ok();
+    }
+}
+
+struct FileInfo {
+    size: u64,
+    modified: Option<SystemTime>,
+    part_size: u64,
+}
+
+impl FileInfo {
+    fn part_count(&self) -> u32 {
+        self.size.div_ceil(self.part_size).max(1) as u32
+    }
+
+    fn part_range(&self, part_number: u32) -> (u64, u64) {
+        let start = (part_number as u64 - 1) * self.part_size;
+        (start, self.part_size.min(self.size - start))
+    }
+}
+
+// Spaces out what's sent so that all of the parts together stay under the limit
+struct BandwidthLimiter {
+    bytes_per_second: u64,
+    next_send: Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            next_send: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self, bytes: usize) {
+        let send_at = {
+            let mut next_send = self.next_send.lock().await;
+            let send_at = (*next_send).max(Instant::now());
+            *next_send =
+                send_at + Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64);
+            send_at
+        };
+
+        tokio::time::sleep_until(send_at).await;
+    }
+}
+
+struct Progress {
+    app: AppHandle,
+    sent: AtomicU64,
+    total: u64,
+}
+
+impl Progress {
+    fn add(&self, bytes: u64) {
+        let sent = self.sent.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        self.emit(sent);
+    }
+
+    // for bytes of a part that failed, which are sent again
+    fn remove(&self, bytes: u64) {
+        let sent = self.sent.fetch_sub(bytes, Ordering::SeqCst) - bytes;
+        self.emit(sent);
+    }
+
+    fn emit(&self, sent: u64) {
+        let progress = sent as f64 / self.total.max(1) as f64;
+        UploadProgress {
+            progress,
+            message: format!("{:.0}%", progress * 100.0),
+        }
+        .emit(&self.app)
+        .ok();
+    }
+}
+
+// Uploads the file to `file_key`, resuming an earlier upload of it if one was cut off
+pub async fn upload_multipart(
+    app: &AppHandle,
+    backend: &impl StorageBackend,
+    file_path: &Path,
+    file_key: &str,
+    options: &UploadOptions,
+) -> Result<(), String> {
+    let metadata = tokio::fs::metadata(file_path)
+        .await
+        .map_err(|e| format!("Failed to read file: {e}"))?;
+    let file = FileInfo {
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+        part_size: options
+            .part_size
+            .max(MIN_PART_SIZE)
+            .max(metadata.len().div_ceil(MAX_PARTS)),
+    };
+
+    let mut state = match UploadState::load(file_path, file_key, &file).await {
+        Some(state) => {
+            println!(
+                "Resuming upload of {} with {} parts done",
+                file_path.display(),
+                state.completed.len()
+            );
+            state
+        }
+        None => {
+            abort_stale_upload(backend, file_path).await;
+            initiate(backend, file_path, file_key, &file, options).await?
+        }
+    };
+
+    let mut restarted = false;
+    loop {
+        match upload_parts(app, backend, file_path, &file, state, options).await {
+            Ok(done) => {
+                state = done;
+                break;
+            }
+            Err(UploadError::Expired) if !restarted => {
+                println!("Upload of {} expired, starting over", file_path.display());
+                restarted = true;
+                state = initiate(backend, file_path, file_key, &file, options).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let mut parts = state.completed.clone();
+    parts.sort_by_key(|part| part.part_number);
+    let result = with_retry(&options.retry, || {
+        backend.complete_upload(&state.file_key, &state.upload_id, &parts)
+    })
+    .await;
+    if let Err(UploadError::Expired) = result {
+        UploadState::remove(file_path).await;
+    }
+    result?;
+
+    UploadState::remove(file_path).await;
+
+    UploadProgress {
+        progress: 1.0,
+        message: "100%".to_string(),
+    }
+    .emit(app)
+    .ok();
+
+    Ok(())
+}
+
+// Abandons an upload of an older version of the file, so S3 doesn't keep its parts around
+async fn abort_stale_upload(backend: &impl StorageBackend, file_path: &Path) {
+    let Ok(state) = tokio::fs::read_to_string(UploadState::path(file_path)).await else {
+        return;
+    };
+    let Ok(state) = serde_json::from_str::<UploadState>(&state) else {
+        return;
+    };
+
+    backend
+        .abort_upload(&state.file_key, &state.upload_id)
+        .await
+        .ok();
+
+    UploadState::remove(file_path).await;
+}
+
+async fn initiate(
+    backend: &impl StorageBackend,
+    file_path: &Path,
+    file_key: &str,
+    file: &FileInfo,
+    options: &UploadOptions,
+) -> Result<UploadState, String> {
+    let upload_id = with_retry(&options.retry, || backend.create_upload(file_key)).await?;
+
+    let state = UploadState {
+        file_key: file_key.to_string(),
+        upload_id,
+        part_size: file.part_size,
+        file_size: file.size,
+        modified: file.modified,
+        completed: vec![],
+    };
+    state.save(file_path).await;
+
+    Ok(state)
+}
+
+async fn upload_parts(
+    app: &AppHandle,
+    backend: &impl StorageBackend,
+    file_path: &Path,
+    file: &FileInfo,
+    state: UploadState,
+    options: &UploadOptions,
+) -> Result<UploadState, UploadError> {
+    let done = state
+        .completed
+        .iter()
+        .map(|part| part.part_number)
+        .collect::<HashSet<_>>();
+
+    let progress = Arc::new(Progress {
+        app: app.clone(),
+        sent: AtomicU64::new(done.iter().map(|n| file.part_range(*n).1).sum()),
+        total: file.size,
+    });
+    let limiter = options
+        .bandwidth_limit
+        .map(|limit| Arc::new(BandwidthLimiter::new(limit)));
+
+    let (file_key, upload_id) = (state.file_key.clone(), state.upload_id.clone());
+    let state = Mutex::new(state);
+
+    stream::iter((1..=file.part_count()).filter(|n| !done.contains(n)))
+        .map(|part_number| {
+            let (progress, limiter) = (progress.clone(), limiter.clone());
+            let (file_key, upload_id) = (&file_key, &upload_id);
+
+            with_retry(&options.retry, move || {
+                upload_part(
+                    backend,
+                    file_path,
+                    file,
+                    file_key,
+                    upload_id,
+                    part_number,
+                    progress.clone(),
+                    limiter.clone(),
+                )
+            })
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .try_for_each(|part| {
+            let state = &state;
+            // saved as each part finishes, so a resumed upload doesn't send it again
+            async move {
+                let mut state = state.lock().await;
+                state.completed.push(part);
+                state.save(file_path).await;
+                Ok(())
+            }
+        })
+        .await?;
+
+    Ok(state.into_inner())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upload_part(
+    backend: &impl StorageBackend,
+    file_path: &Path,
+    file: &FileInfo,
+    file_key: &str,
+    upload_id: &str,
+    part_number: u32,
+    progress: Arc<Progress>,
+    limiter: Option<Arc<BandwidthLimiter>>,
+) -> Result<CompletedPart, UploadError> {
+    let url = backend
+        .presign_part(file_key, upload_id, part_number)
+        .await?;
+
+    let (start, len) = file.part_range(part_number);
+    let mut data = vec![0; len as usize];
+    let read = async {
+        let mut reader = tokio::fs::File::open(file_path).await?;
+        reader.seek(std::io::SeekFrom::Start(start)).await?;
+        reader.read_exact(&mut data).await
+    };
+    read.await
+        .map_err(|e| UploadError::Fatal(format!("Failed to read file: {e}")))?;
+
+    let sent = Arc::new(AtomicU64::new(0));
+    let chunks = data
+        .chunks(CHUNK_SIZE)
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+    let body = stream::iter(chunks).then({
+        let (progress, sent) = (progress.clone(), sent.clone());
+        move |chunk| {
+            let (progress, sent, limiter) = (progress.clone(), sent.clone(), limiter.clone());
+            async move {
+                if let Some(limiter) = limiter {
+                    limiter.acquire(chunk.len()).await;
+                }
+                sent.fetch_add(chunk.len() as u64, Ordering::SeqCst);
+                progress.add(chunk.len() as u64);
+                Ok::<_, std::io::Error>(chunk)
+            }
+        }
+    });
+
+    let result = reqwest::Client::new()
+        .put(url)
+        .header(reqwest::header::CONTENT_LENGTH, len)
+        .body(reqwest::Body::wrap_stream(body))
+        .send()
+        .await;
+
+    let part = match result {
+        Ok(response) if response.status().is_success() => response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| CompletedPart {
+                part_number,
+                etag: etag.to_string(),
+            })
+            .ok_or_else(|| UploadError::Fatal("Upload response has no ETag".to_string())),
+        Ok(response) => Err(part_status_error(
+            response.status(),
+            format!("Failed to upload part {part_number}"),
+        )),
+        Err(e) => Err(UploadError::Retryable(format!(
+            "Failed to upload part {part_number}: {e}"
+        ))),
+    };
+
+    if part.is_err() {
+        progress.remove(sent.load(Ordering::SeqCst));
+    }
+
+    part
+}
+
+async fn api_request(
+    app: &AppHandle,
+    path: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value, UploadError> {
+    let response = app
+        .authed_api_request(|client| client.post(web_api::make_url(path)).json(body))
+        .await
+        .map_err(|e| match e.as_str() {
+            "Unauthorized" => {
+                UploadError::Fatal("Failed to authenticate request; please log in again".into())
+            }
+            _ => UploadError::Retryable(format!("Failed to send request to {path}: {e}")),
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        // S3's NoSuchUpload comes back through the API as a failed request
+        if body.contains("NoSuchUpload") {
+            return Err(UploadError::Expired);
+        }
+        return Err(status_error(
+            status,
+            format!("Request to {path} failed: {body}"),
+        ));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| UploadError::Retryable(format!("Failed to read response from {path}: {e}")))
+}
+
+pub(crate) fn status_error(status: StatusCode, message: String) -> UploadError {
+    let message = format!("{message}. Status: {status}");
+
+    match status {
+        StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => {
+            UploadError::Retryable(message)
+        }
+        status if status.is_server_error() => UploadError::Retryable(message),
+        _ => UploadError::Fatal(message),
+    }
+}
+
+// Like `status_error`, for a part sent to a presigned URL
+fn part_status_error(status: StatusCode, message: String) -> UploadError {
+    match status {
+        // the multipart upload's gone
+        StatusCode::NOT_FOUND => UploadError::Expired,
+        // an expired presigned URL, which is signed again on the next attempt
+        StatusCode::FORBIDDEN => UploadError::Retryable(format!("{message}. Status: {status}")),
+        status => status_error(status, message),
+    }
+}
+
+async fn with_retry<T, Fut: Future<Output = Result<T, UploadError>>>(
+    policy: &RetryPolicy,
+    mut f: impl FnMut() -> Fut,
+) -> Result<T, UploadError> {
+    let mut attempt = 0;
+
+    loop {
+        match f().await {
+            Err(UploadError::Retryable(e)) if attempt + 1 < policy.max_attempts => {
+                let backoff = policy.backoff(attempt);
+                println!("{e}, retrying in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
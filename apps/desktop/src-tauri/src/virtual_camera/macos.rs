@@ -0,0 +1,353 @@
+use cap_media::frame_ws::WSFrame;
+use core_foundation::{
+    base::{CFRelease, CFType, TCFType},
+    dictionary::CFDictionary,
+    string::{CFString, CFStringRef},
+};
+use std::ffi::c_void;
+
+use super::{rgba_to_nv12, FPS};
+
+type CMIOObjectID = u32;
+type OSStatus = i32;
+type CFTypeRef = *const c_void;
+
+#[repr(C)]
+struct PropertyAddress {
+    selector: u32,
+    scope: u32,
+    element: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CMTime {
+    value: i64,
+    timescale: i32,
+    flags: u32,
+    epoch: i64,
+}
+
+#[repr(C)]
+struct CMSampleTimingInfo {
+    duration: CMTime,
+    presentation_time_stamp: CMTime,
+    decode_time_stamp: CMTime,
+}
+
+#[link(name = "CoreMediaIO", kind = "framework")]
+extern "C" {
+    fn CMIOObjectGetPropertyDataSize(
+        object: CMIOObjectID,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: *mut u32,
+    ) -> OSStatus;
+    fn CMIOObjectGetPropertyData(
+        object: CMIOObjectID,
+        address: *const PropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: u32,
+        data_used: *mut u32,
+        data: *mut c_void,
+    ) -> OSStatus;
+    fn CMIOStreamCopyBufferQueue(
+        stream: CMIOObjectID,
+        queue_altered: Option<extern "C" fn(CMIOObjectID, *mut c_void, *mut c_void)>,
+        refcon: *mut c_void,
+        queue: *mut CFTypeRef,
+    ) -> OSStatus;
+    fn CMIODeviceStartStream(device: CMIOObjectID, stream: CMIOObjectID) -> OSStatus;
+    fn CMIODeviceStopStream(device: CMIOObjectID, stream: CMIOObjectID) -> OSStatus;
+}
+
+#[link(name = "CoreMedia", kind = "framework")]
+extern "C" {
+    fn CMSimpleQueueEnqueue(queue: CFTypeRef, element: CFTypeRef) -> OSStatus;
+    fn CMSimpleQueueGetCount(queue: CFTypeRef) -> i32;
+    fn CMSimpleQueueGetCapacity(queue: CFTypeRef) -> i32;
+    fn CMVideoFormatDescriptionCreateForImageBuffer(
+        allocator: CFTypeRef,
+        image_buffer: CFTypeRef,
+        format: *mut CFTypeRef,
+    ) -> OSStatus;
+    fn CMSampleBufferCreateForImageBuffer(
+        allocator: CFTypeRef,
+        image_buffer: CFTypeRef,
+        data_ready: u8,
+        make_data_ready: *const c_void,
+        refcon: *mut c_void,
+        format: CFTypeRef,
+        timing: *const CMSampleTimingInfo,
+        sample_buffer: *mut CFTypeRef,
+    ) -> OSStatus;
+    fn CMClockGetHostTimeClock() -> CFTypeRef;
+    fn CMClockGetTime(clock: CFTypeRef) -> CMTime;
+}
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    static kCVPixelBufferIOSurfacePropertiesKey: CFStringRef;
+
+    fn CVPixelBufferCreate(
+        allocator: CFTypeRef,
+        width: usize,
+        height: usize,
+        format: u32,
+        attributes: CFTypeRef,
+        pixel_buffer: *mut CFTypeRef,
+    ) -> i32;
+    fn CVPixelBufferLockBaseAddress(pixel_buffer: CFTypeRef, flags: u64) -> i32;
+    fn CVPixelBufferUnlockBaseAddress(pixel_buffer: CFTypeRef, flags: u64) -> i32;
+    fn CVPixelBufferGetBaseAddressOfPlane(pixel_buffer: CFTypeRef, plane: usize) -> *mut u8;
+    fn CVPixelBufferGetBytesPerRowOfPlane(pixel_buffer: CFTypeRef, plane: usize) -> usize;
+}
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+const SYSTEM_OBJECT: CMIOObjectID = 1;
+const PROPERTY_DEVICES: u32 = four_cc(b"dev#");
+const PROPERTY_NAME: u32 = four_cc(b"lnam");
+const PROPERTY_STREAMS: u32 = four_cc(b"stm#");
+const SCOPE_GLOBAL: u32 = four_cc(b"glob");
+const ELEMENT_MAIN: u32 = 0;
+// NV12 in video range, what OBS sends its camera itself
+const PIXEL_FORMAT_NV12: u32 = four_cc(b"420v");
+const TIME_VALID: u32 = 1;
+
+const DEVICE_NAME: &str = "OBS Virtual Camera";
+
+// OBS's camera extension has a sink stream that takes frames from any app, which it then
+// shows on the camera as if they came from OBS
+pub struct ObsCamera {
+    device: CMIOObjectID,
+    sink: CMIOObjectID,
+    queue: CFTypeRef,
+    size: (u32, u32),
+}
+
+impl ObsCamera {
+    pub fn open(width: u32, height: u32) -> Result<Self, String> {
+        // OBS only installs the camera the first time its own virtual camera is started
+        let device = find_device().ok_or_else(|| {
+            format!("Couldn't find the {DEVICE_NAME}. Start it once from OBS Studio to set it up.")
+        })?;
+
+        let streams = property_list::<CMIOObjectID>(device, PROPERTY_STREAMS);
+        // the first stream is the camera's output, the second the sink it's fed through
+        let Some(&sink) = streams.get(1) else {
+            return Err(format!("The {DEVICE_NAME} has no stream to send to"));
+        };
+
+        let mut queue = std::ptr::null();
+        unsafe {
+            let status = CMIOStreamCopyBufferQueue(sink, None, std::ptr::null_mut(), &mut queue);
+            if status != 0 || queue.is_null() {
+                return Err(format!("Failed to open the camera's queue ({status})"));
+            }
+
+            let status = CMIODeviceStartStream(device, sink);
+            if status != 0 {
+                CFRelease(queue);
+                return Err(format!("Failed to start the camera ({status})"));
+            }
+        }
+
+        Ok(Self {
+            device,
+            sink,
+            queue,
+            size: (width & !1, height & !1),
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn send(&mut self, frame: &WSFrame) -> Result<(), String> {
+        unsafe {
+            // whoever's watching has fallen behind, so this frame is dropped
+            if CMSimpleQueueGetCount(self.queue) >= CMSimpleQueueGetCapacity(self.queue) {
+                return Ok(());
+            }
+
+            let pixel_buffer = self.pixel_buffer(frame)?;
+
+            let mut format = std::ptr::null();
+            let status = CMVideoFormatDescriptionCreateForImageBuffer(
+                std::ptr::null(),
+                pixel_buffer,
+                &mut format,
+            );
+            if status != 0 {
+                CFRelease(pixel_buffer);
+                return Err(format!("Failed to describe the camera's frames ({status})"));
+            }
+
+            let timing = CMSampleTimingInfo {
+                duration: CMTime {
+                    value: 1,
+                    timescale: FPS as i32,
+                    flags: TIME_VALID,
+                    epoch: 0,
+                },
+                presentation_time_stamp: CMClockGetTime(CMClockGetHostTimeClock()),
+                decode_time_stamp: CMTime {
+                    value: 0,
+                    timescale: 0,
+                    flags: 0,
+                    epoch: 0,
+                },
+            };
+
+            let mut sample_buffer = std::ptr::null();
+            let status = CMSampleBufferCreateForImageBuffer(
+                std::ptr::null(),
+                pixel_buffer,
+                1,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                format,
+                &timing,
+                &mut sample_buffer,
+            );
+            // the sample buffer holds on to these itself
+            CFRelease(format);
+            CFRelease(pixel_buffer);
+            if status != 0 {
+                return Err(format!("Failed to create a camera frame ({status})"));
+            }
+
+            // the queue's reader releases the buffer once it's taken it
+            let status = CMSimpleQueueEnqueue(self.queue, sample_buffer);
+            if status != 0 {
+                CFRelease(sample_buffer);
+            }
+        }
+
+        Ok(())
+    }
+
+    // An NV12 copy of `frame` in a buffer that can be shared with the camera's process
+    unsafe fn pixel_buffer(&self, frame: &WSFrame) -> Result<CFTypeRef, String> {
+        let attributes = CFDictionary::from_CFType_pairs(&[(
+            CFString::wrap_under_get_rule(kCVPixelBufferIOSurfacePropertiesKey).as_CFType(),
+            CFDictionary::<CFType, CFType>::from_CFType_pairs(&[]).as_CFType(),
+        )]);
+
+        let mut pixel_buffer = std::ptr::null();
+        let status = CVPixelBufferCreate(
+            std::ptr::null(),
+            self.size.0 as usize,
+            self.size.1 as usize,
+            PIXEL_FORMAT_NV12,
+            attributes.as_CFTypeRef(),
+            &mut pixel_buffer,
+        );
+        if status != 0 {
+            return Err(format!("Failed to create a camera frame buffer ({status})"));
+        }
+
+        CVPixelBufferLockBaseAddress(pixel_buffer, 0);
+
+        let plane = |i: usize| {
+            let stride = CVPixelBufferGetBytesPerRowOfPlane(pixel_buffer, i);
+            let rows = (if i == 0 { self.size.1 } else { self.size.1 / 2 }) as usize;
+            (
+                std::slice::from_raw_parts_mut(
+                    CVPixelBufferGetBaseAddressOfPlane(pixel_buffer, i),
+                    stride * rows,
+                ),
+                stride,
+            )
+        };
+        let (y, y_stride) = plane(0);
+        let (uv, uv_stride) = plane(1);
+        rgba_to_nv12(frame, self.size, y, y_stride, uv, uv_stride);
+
+        CVPixelBufferUnlockBaseAddress(pixel_buffer, 0);
+
+        Ok(pixel_buffer)
+    }
+}
+
+impl Drop for ObsCamera {
+    fn drop(&mut self) {
+        unsafe {
+            CMIODeviceStopStream(self.device, self.sink);
+            CFRelease(self.queue);
+        }
+    }
+}
+
+fn find_device() -> Option<CMIOObjectID> {
+    property_list::<CMIOObjectID>(SYSTEM_OBJECT, PROPERTY_DEVICES)
+        .into_iter()
+        .find(|&device| device_name(device).is_some_and(|name| name == DEVICE_NAME))
+}
+
+fn device_name(device: CMIOObjectID) -> Option<String> {
+    let address = global_address(PROPERTY_NAME);
+    let mut name: CFStringRef = std::ptr::null();
+    let mut used = 0;
+
+    let status = unsafe {
+        CMIOObjectGetPropertyData(
+            device,
+            &address,
+            0,
+            std::ptr::null(),
+            std::mem::size_of::<CFStringRef>() as u32,
+            &mut used,
+            (&mut name as *mut CFStringRef).cast(),
+        )
+    };
+    if status != 0 || name.is_null() {
+        return None;
+    }
+
+    Some(unsafe { CFString::wrap_under_create_rule(name) }.to_string())
+}
+
+// A property holding an array, like a device's streams
+fn property_list<T: Copy + Default>(object: CMIOObjectID, selector: u32) -> Vec<T> {
+    let address = global_address(selector);
+    let mut size = 0;
+
+    unsafe {
+        if CMIOObjectGetPropertyDataSize(object, &address, 0, std::ptr::null(), &mut size) != 0 {
+            return vec![];
+        }
+
+        let mut items = vec![T::default(); size as usize / std::mem::size_of::<T>()];
+        let mut used = 0;
+        if CMIOObjectGetPropertyData(
+            object,
+            &address,
+            0,
+            std::ptr::null(),
+            size,
+            &mut used,
+            items.as_mut_ptr().cast(),
+        ) != 0
+        {
+            return vec![];
+        }
+
+        items.truncate(used as usize / std::mem::size_of::<T>());
+        items
+    }
+}
+
+fn global_address(selector: u32) -> PropertyAddress {
+    PropertyAddress {
+        selector,
+        scope: SCOPE_GLOBAL,
+        element: ELEMENT_MAIN,
+    }
+}
@@ -0,0 +1,212 @@
+// Mirrors an editor's preview into a virtual camera, so the composited layout can be picked
+// as a camera in video calls. The camera device is OBS Studio's virtual camera, which calling
+// apps already accept, so OBS has to be installed for it to show up.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod win;
+
+#[cfg(target_os = "macos")]
+use macos::ObsCamera;
+#[cfg(target_os = "windows")]
+use win::ObsCamera;
+
+use cap_media::frame_ws::WSFrame;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use crate::editor_window::WindowEditorInstance;
+
+// calls expect a steady stream, so the last frame repeats while the preview sits still
+const FPS: u32 = 30;
+// how long the editor gets to show its first frame
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The editor the virtual camera is showing, while it's on
+#[derive(Default)]
+pub struct VirtualCamera(Mutex<Option<RunningCamera>>);
+
+struct RunningCamera {
+    video_id: String,
+    stop: Arc<AtomicBool>,
+}
+
+impl VirtualCamera {
+    fn stop(&self) {
+        if let Some(camera) = self.0.lock().unwrap().take() {
+            camera.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn init(app: &AppHandle) {
+    app.manage(VirtualCamera::default());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn start_virtual_camera(
+    app: AppHandle,
+    editor_instance: WindowEditorInstance,
+) -> Result<(), String> {
+    let state = app.state::<VirtualCamera>();
+    state.stop();
+
+    let mut frames = editor_instance.subscribe_preview();
+    let first = tokio::time::timeout(FIRST_FRAME_TIMEOUT, frames.wait_for(|f| f.is_some()))
+        .await
+        .map_err(|_| "The editor didn't show a frame to start the camera with".to_string())?
+        .map_err(|_| "The editor was closed".to_string())?
+        .clone()
+        .unwrap();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    *state.0.lock().unwrap() = Some(RunningCamera {
+        video_id: editor_instance.id.clone(),
+        stop: stop.clone(),
+    });
+
+    let (opened_tx, opened_rx) = tokio::sync::oneshot::channel();
+
+    // the camera is opened on the thread that feeds it, since it can't be moved between them
+    std::thread::spawn({
+        let app = app.clone();
+        move || {
+            let result = ObsCamera::open(first.width, first.height);
+            let opened = result.as_ref().map(|_| ()).map_err(Clone::clone);
+            opened_tx.send(opened).ok();
+
+            if let Err(e) = result.and_then(|camera| run(camera, first, frames, &stop)) {
+                tracing::error!("Virtual camera stopped: {e}");
+            }
+
+            // unless it was replaced by another
+            let state = app.state::<VirtualCamera>();
+            let mut running = state.0.lock().unwrap();
+            if running
+                .as_ref()
+                .is_some_and(|r| Arc::ptr_eq(&r.stop, &stop))
+            {
+                *running = None;
+            }
+        }
+    });
+
+    opened_rx
+        .await
+        .map_err(|_| "The virtual camera failed to start".to_string())?
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_virtual_camera(app: AppHandle) {
+    app.state::<VirtualCamera>().stop();
+}
+
+// The project the virtual camera is showing, if it's on
+#[tauri::command]
+#[specta::specta]
+pub fn get_virtual_camera(app: AppHandle) -> Option<String> {
+    app.state::<VirtualCamera>()
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|c| c.video_id.clone())
+}
+
+// Sends the latest preview frame to the camera at a steady rate until stopped or the
+// editor closes
+fn run(
+    mut camera: ObsCamera,
+    mut frame: Arc<WSFrame>,
+    mut frames: watch::Receiver<Option<Arc<WSFrame>>>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let interval = Duration::from_secs(1) / FPS;
+    let mut next = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        match frames.has_changed() {
+            Ok(true) => {
+                if let Some(latest) = frames.borrow_and_update().clone() {
+                    frame = latest;
+                }
+            }
+            Ok(false) => {}
+            Err(_) => return Ok(()),
+        }
+
+        // a different aspect ratio or preview quality changes the size
+        if camera.size() != (frame.width & !1, frame.height & !1) {
+            drop(camera);
+            camera = ObsCamera::open(frame.width, frame.height)?;
+        }
+
+        camera.send(&frame)?;
+
+        next += interval;
+        match next.checked_duration_since(Instant::now()) {
+            Some(wait) => std::thread::sleep(wait),
+            // fell behind, so there's no catching up to do
+            None => next = Instant::now(),
+        }
+    }
+
+    Ok(())
+}
+
+// Converts a rendered RGBA frame to NV12, the format both platforms' OBS cameras take, at
+// `width` and `height`, which must be even. Writes the luma plane to `y` and the interleaved
+// chroma plane to `uv`, each row starting `y_stride` and `uv_stride` bytes after the last.
+fn rgba_to_nv12(
+    frame: &WSFrame,
+    (width, height): (u32, u32),
+    y: &mut [u8],
+    y_stride: usize,
+    uv: &mut [u8],
+    uv_stride: usize,
+) {
+    let stride = frame.stride as usize;
+    let pixel = |col: usize, row: usize| {
+        let i = row * stride + col * 4;
+        (
+            frame.data[i] as i32,
+            frame.data[i + 1] as i32,
+            frame.data[i + 2] as i32,
+        )
+    };
+
+    // BT.601 in limited range, which cameras are assumed to send
+    for row in 0..height as usize {
+        for col in 0..width as usize {
+            let (r, g, b) = pixel(col, row);
+            y[row * y_stride + col] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+        }
+    }
+
+    for row in 0..height as usize / 2 {
+        for col in 0..width as usize / 2 {
+            let (mut r, mut g, mut b) = (0, 0, 0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let p = pixel(col * 2 + dx, row * 2 + dy);
+                r += p.0;
+                g += p.1;
+                b += p.2;
+            }
+            let (r, g, b) = (r / 4, g / 4, b / 4);
+
+            let i = row * uv_stride + col * 2;
+            uv[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            uv[i + 1] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use ::windows::{
+    core::w,
+    Win32::{
+        Foundation::{CloseHandle, BOOL, HANDLE, INVALID_HANDLE_VALUE},
+        System::Memory::{
+            CreateFileMappingW, MapViewOfFile, OpenFileMappingW, UnmapViewOfFile,
+            FILE_MAP_ALL_ACCESS, FILE_MAP_READ, MEMORY_MAPPED_VIEW_ADDRESS, PAGE_READWRITE,
+        },
+    },
+};
+use cap_media::frame_ws::WSFrame;
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::Instant,
+};
+
+use super::{rgba_to_nv12, FPS};
+
+// OBS's DirectShow camera reads frames from a queue of three in shared memory, laid out as in
+// its plugins/win-dshow/shared-memory-queue.c
+#[repr(C)]
+struct QueueHeader {
+    write_idx: AtomicU32,
+    read_idx: AtomicU32,
+    state: AtomicU32,
+    offsets: [u32; 3],
+    kind: u32,
+    cx: u32,
+    cy: u32,
+    // 100ns units
+    interval: u64,
+    reserved: [u32; 8],
+}
+
+const QUEUE_STATE_STARTING: u32 = 1;
+const QUEUE_STATE_READY: u32 = 2;
+const QUEUE_STATE_STOPPING: u32 = 3;
+// each frame is preceded by its timestamp, padded out to this
+const FRAME_HEADER_SIZE: usize = 32;
+
+pub struct ObsCamera {
+    handle: HANDLE,
+    view: MEMORY_MAPPED_VIEW_ADDRESS,
+    size: (u32, u32),
+    started: Instant,
+}
+
+impl ObsCamera {
+    pub fn open(width: u32, height: u32) -> Result<Self, String> {
+        let size = (width & !1, height & !1);
+        let frame_size = (size.0 * size.1 * 3 / 2) as usize;
+
+        let align = |n: usize| (n + 31) & !31;
+        let mut offsets = [0u32; 3];
+        let mut total = align(std::mem::size_of::<QueueHeader>());
+        for offset in &mut offsets {
+            *offset = total as u32;
+            total = align(total + FRAME_HEADER_SIZE + frame_size);
+        }
+
+        unsafe {
+            // OBS itself, or another app, has the camera
+            if let Ok(handle) =
+                OpenFileMappingW(FILE_MAP_READ.0, BOOL::from(false), w!("OBSVirtualCamVideo"))
+            {
+                CloseHandle(handle).ok();
+                return Err("OBS's virtual camera is already in use".to_string());
+            }
+
+            let handle = CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                None,
+                PAGE_READWRITE,
+                0,
+                total as u32,
+                w!("OBSVirtualCamVideo"),
+            )
+            .map_err(|e| format!("Failed to create the camera's frame queue: {e}"))?;
+
+            let view = MapViewOfFile(handle, FILE_MAP_ALL_ACCESS, 0, 0, 0);
+            if view.Value.is_null() {
+                CloseHandle(handle).ok();
+                return Err("Failed to map the camera's frame queue".to_string());
+            }
+
+            view.Value.cast::<QueueHeader>().write(QueueHeader {
+                write_idx: AtomicU32::new(0),
+                read_idx: AtomicU32::new(0),
+                state: AtomicU32::new(QUEUE_STATE_STARTING),
+                offsets,
+                kind: 0,
+                cx: size.0,
+                cy: size.1,
+                interval: 10_000_000 / FPS as u64,
+                reserved: [0; 8],
+            });
+
+            Ok(Self {
+                handle,
+                view,
+                size,
+                started: Instant::now(),
+            })
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn send(&mut self, frame: &WSFrame) -> Result<(), String> {
+        let (width, height) = self.size;
+        let luma_size = (width * height) as usize;
+
+        unsafe {
+            let base = self.view.Value.cast::<u8>();
+            let header = &*base.cast::<QueueHeader>();
+
+            let index = header.write_idx.fetch_add(1, Ordering::SeqCst) + 1;
+            let slot = base.add(header.offsets[(index % 3) as usize] as usize);
+
+            slot.cast::<u64>()
+                .write(self.started.elapsed().as_nanos() as u64);
+
+            let data =
+                std::slice::from_raw_parts_mut(slot.add(FRAME_HEADER_SIZE), luma_size * 3 / 2);
+            let (y, uv) = data.split_at_mut(luma_size);
+            rgba_to_nv12(frame, self.size, y, width as usize, uv, width as usize);
+
+            header.read_idx.store(index, Ordering::SeqCst);
+            header.state.store(QUEUE_STATE_READY, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for ObsCamera {
+    fn drop(&mut self) {
+        unsafe {
+            (*self.view.Value.cast::<QueueHeader>())
+                .state
+                .store(QUEUE_STATE_STOPPING, Ordering::SeqCst);
+            UnmapViewOfFile(self.view).ok();
+            CloseHandle(self.handle).ok();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use cap_project::{
+    AudioMeta, Content, Display, ProjectConfiguration, RecordingMeta, SingleSegment,
+    TimelineConfiguration, TimelineSegment,
+};
+use cap_rendering::Video;
+use relative_path::RelativePathBuf;
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{
+    create_screenshot, general_settings::GeneralSettingsStore, presets::PresetsStore,
+    recording_path, NewRecordingAdded,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "mkv", "webm"];
+
+// Files being written (OBS still recording, AirDrop still transferring) keep growing,
+// so a file is only imported once its size is the same on two polls in a row.
+#[derive(Default)]
+struct Watcher {
+    folders: HashSet<PathBuf>,
+    seen: HashSet<PathBuf>,
+    pending: HashMap<PathBuf, u64>,
+}
+
+impl Watcher {
+    fn poll(&mut self, folders: &[PathBuf]) -> Vec<PathBuf> {
+        let mut ready = vec![];
+
+        for folder in folders {
+            let files = list_videos(folder);
+
+            // anything already in a folder when we start watching it isn't new
+            if self.folders.insert(folder.clone()) {
+                self.seen.extend(files.into_iter().map(|(path, _)| path));
+                continue;
+            }
+
+            for (path, size) in files {
+                if self.seen.contains(&path) {
+                    continue;
+                }
+
+                match self.pending.insert(path.clone(), size) {
+                    Some(prev_size) if prev_size == size && size > 0 => {
+                        self.pending.remove(&path);
+                        self.seen.insert(path.clone());
+                        ready.push(path);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.folders.retain(|f| folders.contains(f));
+
+        ready
+    }
+}
+
+fn list_videos(folder: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (entry.path(), metadata.len()))
+        })
+        .collect()
+}
+
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+
+    tokio::spawn(async move {
+        let mut watcher = Watcher::default();
+
+        loop {
+            let folders = GeneralSettingsStore::get(&app)
+                .ok()
+                .flatten()
+                .map(|s| s.watch_folders)
+                .unwrap_or_default();
+
+            for path in watcher.poll(&folders) {
+                println!("Importing {} from watch folder", path.display());
+
+                if let Err(e) = import_video(&app, &path).await {
+                    eprintln!("Failed to import {}: {e}", path.display());
+                }
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+// Copies an external video into a new project, styled with the default preset
+async fn import_video(app: &AppHandle, source: &Path) -> Result<(), String> {
+    let project_path = recording_path(app, &uuid::Uuid::new_v4().to_string());
+    let content_dir = project_path.join("content");
+    std::fs::create_dir_all(&content_dir).map_err(|e| e.to_string())?;
+
+    let extension = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mp4")
+        .to_lowercase();
+    let display_path = content_dir.join(format!("display.{extension}"));
+
+    tokio::fs::copy(source, &display_path)
+        .await
+        .map_err(|e| format!("Failed to copy video: {e}"))?;
+
+    let video = Video::new(&display_path)?;
+    let has_audio = ffmpeg::format::input(&display_path)
+        .map(|input| input.streams().best(ffmpeg::media::Type::Audio).is_some())
+        .unwrap_or(false);
+
+    let relative_display_path =
+        RelativePathBuf::from_path(display_path.strip_prefix(&project_path).unwrap()).unwrap();
+
+    let meta = RecordingMeta {
+        project_path: project_path.clone(),
+        sharing: None,
+        pretty_name: source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Imported recording".to_string()),
+        content: Content::SingleSegment {
+            segment: SingleSegment {
+                display: Display {
+                    path: relative_display_path.clone(),
+                    fps: video.fps,
+                },
+                camera: None,
+                // the renderer picks the audio stream out of the same file
+                audio: has_audio.then_some(AudioMeta {
+                    path: relative_display_path,
+                }),
+                cursor: None,
+            },
+        },
+    };
+    meta.save_for_project().map_err(|e| format!("{e:?}"))?;
+
+    let config = ProjectConfiguration {
+        timeline: Some(TimelineConfiguration {
+            segments: vec![TimelineSegment {
+                recording_segment: 0,
+                start: 0.0,
+                end: video.duration,
+                timescale: 1.0,
+                display: 0,
+                camera_position: None,
+                transition: None,
+            }],
+            zoom_segments: vec![],
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }),
+        ..PresetsStore::get_default_preset(app)?
+            .map(|p| p.config)
+            .unwrap_or_default()
+    };
+    config.write(&project_path).map_err(|e| e.to_string())?;
+
+    let screenshots_dir = project_path.join("screenshots");
+    std::fs::create_dir_all(&screenshots_dir).ok();
+    create_screenshot(display_path, screenshots_dir.join("display.jpg"), None).await?;
+
+    NewRecordingAdded { path: project_path }.emit(app).ok();
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+// POSTs recording lifecycle events, like a recording stopping or a shareable link being
+// created, to URLs the user sets up, so they can be hooked into other tools.
+//
+// Each request carries the event's name in `X-Cap-Event`. When the webhook has a secret, the
+// body is signed with it as `X-Cap-Signature-256: sha256=<hex HMAC-SHA256 of the body>`,
+// which receivers should check before trusting the payload.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::{header, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_store::StoreExt;
+
+use crate::s3_storage::{hex, hmac_sha256};
+
+const MAX_ATTEMPTS: u32 = 5;
+// the wait before the first retry, doubling after each one that fails
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Deserialize, Type, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    RecordingStarted,
+    RecordingStopped,
+    ExportFinished,
+    UploadComplete,
+    ShareLinkCreated,
+}
+
+impl WebhookEvent {
+    // What receivers see in the payload and `X-Cap-Event`
+    fn name(&self) -> &'static str {
+        match self {
+            Self::RecordingStarted => "recording.started",
+            Self::RecordingStopped => "recording.stopped",
+            Self::ExportFinished => "export.finished",
+            Self::UploadComplete => "upload.complete",
+            Self::ShareLinkCreated => "share_link.created",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub url: String,
+    // the events sent to it, or all of them when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "true_b")]
+    pub enabled: bool,
+}
+
+impl Webhook {
+    fn wants(&self, event: WebhookEvent) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let url = Url::parse(self.url.trim())
+            .map_err(|e| format!("Invalid webhook URL '{}': {e}", self.url))?;
+
+        match url.scheme() {
+            "http" | "https" => Ok(()),
+            scheme => Err(format!(
+                "Webhook URLs must be http or https, not '{scheme}'"
+            )),
+        }
+    }
+}
+
+fn true_b() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Type, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhooksStore {
+    webhooks: Vec<Webhook>,
+}
+
+impl WebhooksStore {
+    pub fn get(app: &AppHandle<Wry>) -> Result<Option<Self>, String> {
+        match app.store("store").map(|s| s.get("webhooks")) {
+            Ok(Some(store)) => serde_json::from_value(store)
+                .map(Some)
+                .map_err(|e| format!("Failed to deserialize webhooks store: {e}")),
+            _ => Ok(None),
+        }
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let Ok(store) = app.store("store") else {
+            return Err("Store not found".to_string());
+        };
+
+        store.set("webhooks", json!(self));
+        store.save().map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    // the same for every attempt, so receivers can ignore retries they've already handled
+    id: String,
+    event: &'a str,
+    timestamp: String,
+    data: serde_json::Value,
+}
+
+impl<'a> Payload<'a> {
+    fn new(event: &'a str, data: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            event,
+            timestamp: Utc::now().to_rfc3339(),
+            data,
+        }
+    }
+}
+
+// Sends `event` to every webhook that wants it in the background, retrying ones that fail
+pub fn notify(app: &AppHandle, event: WebhookEvent, data: serde_json::Value) {
+    let webhooks = match WebhooksStore::get(app) {
+        Ok(Some(store)) => store.webhooks,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("{e}");
+            return;
+        }
+    };
+
+    let webhooks = webhooks
+        .into_iter()
+        .filter(|w| w.wants(event))
+        .collect::<Vec<_>>();
+    if webhooks.is_empty() {
+        return;
+    }
+
+    let payload = Payload::new(event.name(), data);
+    let Ok(body) = serde_json::to_vec(&payload) else {
+        return;
+    };
+
+    let client = reqwest::Client::new();
+    for webhook in webhooks {
+        let client = client.clone();
+        let body = body.clone();
+        let id = payload.id.clone();
+
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = deliver(&client, &webhook, event.name(), &id, &body).await {
+                tracing::error!("Webhook {} gave up on {}: {e}", webhook.url, event.name());
+            }
+        });
+    }
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    event: &str,
+    id: &str,
+    body: &[u8],
+) -> Result<(), String> {
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        match send(client, webhook, event, id, body).await {
+            Ok(()) => return Ok(()),
+            Err((error, true)) if attempt < MAX_ATTEMPTS => {
+                println!(
+                    "Webhook {} {error}, retrying in {}s",
+                    webhook.url,
+                    backoff.as_secs()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err((error, _)) => return Err(error),
+        }
+    }
+}
+
+// Sends the request once, failing with whether it's worth trying again. Requests the
+// receiver turned down won't go any better the next time, but timeouts and server errors might.
+async fn send(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    event: &str,
+    id: &str,
+    body: &[u8],
+) -> Result<(), (String, bool)> {
+    let mut request = client
+        .post(webhook.url.trim())
+        .timeout(REQUEST_TIMEOUT)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Cap-Event", event)
+        .header("X-Cap-Delivery", id)
+        .body(body.to_vec());
+
+    if let Some(secret) = webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header("X-Cap-Signature-256", signature(secret, body));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| (format!("couldn't be reached: {e}"), true))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    Err((
+        format!("responded with {status}"),
+        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+    ))
+}
+
+fn signature(secret: &str, body: &[u8]) -> String {
+    format!("sha256={}", hex(&hmac_sha256(secret.as_bytes(), body)))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_webhooks(app: AppHandle) -> Result<Vec<Webhook>, String> {
+    Ok(WebhooksStore::get(&app)?
+        .map(|store| store.webhooks)
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_webhooks(app: AppHandle, webhooks: Vec<Webhook>) -> Result<(), String> {
+    for webhook in &webhooks {
+        webhook.validate()?;
+    }
+
+    WebhooksStore { webhooks }.save(&app)
+}
+
+// Sends a `ping` event once, without retrying, so the user can see the webhook's set up right
+#[tauri::command]
+#[specta::specta]
+pub async fn test_webhook(webhook: Webhook) -> Result<(), String> {
+    webhook.validate()?;
+
+    let payload = Payload::new("ping", json!({}));
+    let body = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+
+    send(
+        &reqwest::Client::new(),
+        &webhook,
+        "ping",
+        &payload.id,
+        &body,
+    )
+    .await
+    .map_err(|(error, _)| format!("The webhook {error}"))
+}
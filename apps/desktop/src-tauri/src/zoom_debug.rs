@@ -0,0 +1,60 @@
+// Streams the zoom state of each frame an editor previews to its window, for the editor's
+// zoom debug overlay. The editor only collects it while a window's asked for it.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use cap_rendering::ZoomDebugInfo;
+use serde::Serialize;
+use specta::Type;
+use tauri::{async_runtime::JoinHandle, AppHandle, Manager, Window};
+use tauri_specta::Event;
+
+use crate::editor_window::WindowEditorInstance;
+
+#[derive(Serialize, Type, tauri_specta::Event, Debug, Clone)]
+pub struct ZoomDebugChanged(ZoomDebugInfo);
+
+// The streams that are on, by editor window
+#[derive(Default)]
+pub struct ZoomDebugStreams(Mutex<HashMap<String, JoinHandle<()>>>);
+
+pub fn init(app: &AppHandle) {
+    app.manage(ZoomDebugStreams::default());
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_zoom_debug(window: Window, editor_instance: WindowEditorInstance, enabled: bool) {
+    let streams = window.state::<ZoomDebugStreams>();
+    let mut streams = streams.0.lock().unwrap();
+
+    if let Some(stream) = streams.remove(window.label()) {
+        stream.abort();
+    }
+
+    if !enabled {
+        return;
+    }
+
+    let mut zoom_debug = editor_instance.subscribe_zoom_debug();
+    let app = window.app_handle().clone();
+    let label = window.label().to_string();
+
+    let stream = tauri::async_runtime::spawn(async move {
+        // ends once the editor's closed
+        while zoom_debug.changed().await.is_ok() {
+            let Some(info) = zoom_debug.borrow_and_update().clone() else {
+                continue;
+            };
+
+            if ZoomDebugChanged(info)
+                .emit_to(&app, label.as_str())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    streams.insert(window.label().to_string(), stream);
+}
@@ -57,6 +57,12 @@ export default function () {
 
   const isRecording = () => !!currentRecording.data;
 
+  // keep the next recording's pipeline warm so hitting record starts immediately
+  createEffect(() => {
+    if (currentRecording.isPending || isRecording()) return;
+    commands.prepareRecording().catch(console.error);
+  });
+
   const toggleRecording = createMutation(() => ({
     mutationFn: async () => {
       if (!isRecording()) {
@@ -240,6 +246,7 @@ export default function () {
       <TargetSelects options={options.data} setOptions={setOptions} />
       <CameraSelect options={options.data} setOptions={setOptions} />
       <MicrophoneSelect options={options.data} setOptions={setOptions} />
+      <CursorDataOptions options={options.data} setOptions={setOptions} />
       <div class="w-full flex items-center space-x-1">
         <Button
           disabled={toggleRecording.isPending}
@@ -561,9 +568,15 @@ function TargetSelects(props: {
               refresh_rate: value.refresh_rate,
             });
 
+            const { captureTarget } = props.options;
             commands.setRecordingOptions({
               ...props.options,
-              captureTarget: { ...value, variant: "window" },
+              captureTarget: {
+                ...value,
+                variant: "window",
+                isolated:
+                  captureTarget.variant === "window" && captureTarget.isolated,
+              },
             });
           }}
           value={
@@ -584,6 +597,34 @@ function TargetSelects(props: {
           )}
         />
       </div>
+      <Show
+        when={
+          props.options?.captureTarget.variant === "window" &&
+          props.options.captureTarget
+        }
+      >
+        {(target) => (
+          <label class="flex flex-row items-center gap-1.5 mt-1.5 text-xs text-gray-400">
+            <input
+              type="checkbox"
+              checked={target().isolated ?? false}
+              disabled={props.setOptions.isPending}
+              onChange={(e) => {
+                if (!props.options) return;
+
+                commands.setRecordingOptions({
+                  ...props.options,
+                  captureTarget: {
+                    ...target(),
+                    isolated: e.currentTarget.checked,
+                  },
+                });
+              }}
+            />
+            Only capture this window, even when it's covered
+          </label>
+        )}
+      </Show>
     </div>
   );
 }
@@ -860,6 +901,70 @@ function MicrophoneSelect(props: {
           </PopperContent>
         </KSelect.Portal>
       </KSelect>
+      <label class="flex flex-row items-center gap-1.5 mt-1.5 text-xs text-gray-400">
+        <input
+          type="checkbox"
+          checked={props.options?.captureSystemAudio ?? false}
+          disabled={props.setOptions.isPending || !!currentRecording.data}
+          onChange={(e) => {
+            if (!props.options) return;
+            props.setOptions.mutate({
+              ...props.options,
+              captureSystemAudio: e.currentTarget.checked,
+            });
+          }}
+        />
+        Also record system audio
+      </label>
+    </div>
+  );
+}
+
+// What gets recorded alongside the cursor for the editor to use
+function CursorDataOptions(props: {
+  options: ReturnType<typeof createOptionsQuery>["options"]["data"];
+  setOptions: ReturnType<typeof createOptionsQuery>["setOptions"];
+}) {
+  const currentRecording = createCurrentRecordingQuery();
+
+  return (
+    <div class="flex flex-col gap-1">
+      <label
+        class="flex flex-row items-center gap-1.5 text-xs text-gray-400"
+        title="Auto zoom frames the whole button or field under the cursor instead of just the cursor. Needs accessibility permission on macOS."
+      >
+        <input
+          type="checkbox"
+          checked={props.options?.sampleUiElements ?? false}
+          disabled={props.setOptions.isPending || !!currentRecording.data}
+          onChange={(e) => {
+            if (!props.options) return;
+            props.setOptions.mutate({
+              ...props.options,
+              sampleUiElements: e.currentTarget.checked,
+            });
+          }}
+        />
+        Record UI elements under the cursor for Auto zoom
+      </label>
+      <label
+        class="flex flex-row items-center gap-1.5 text-xs text-gray-400"
+        title="Lets the editor show the keys you press as an overlay. Everything typed while recording is saved with the project, so leave this off when entering passwords."
+      >
+        <input
+          type="checkbox"
+          checked={props.options?.recordKeyboard ?? false}
+          disabled={props.setOptions.isPending || !!currentRecording.data}
+          onChange={(e) => {
+            if (!props.options) return;
+            props.setOptions.mutate({
+              ...props.options,
+              recordKeyboard: e.currentTarget.checked,
+            });
+          }}
+        />
+        Record key presses for the keystroke overlay
+      </label>
     </div>
   );
 }
@@ -1,13 +1,18 @@
-import { createResource, Show, For } from "solid-js";
+import { createResource, createSignal, Show, For } from "solid-js";
 import { createStore } from "solid-js/store";
 import { generalSettingsStore } from "~/store";
-import type { AppTheme, GeneralSettingsStore } from "~/utils/tauri";
+import {
+  commands,
+  type AppTheme,
+  type GeneralSettingsStore,
+} from "~/utils/tauri";
 // import { themeStore } from "~/store/theme";
 import {
   isPermissionGranted,
   requestPermission,
 } from "@tauri-apps/plugin-notification";
 import { type OsType, type } from "@tauri-apps/plugin-os";
+import { ask, open } from "@tauri-apps/plugin-dialog";
 import themePreviewAuto from "~/assets/theme-previews/auto.jpg";
 import themePreviewLight from "~/assets/theme-previews/light.jpg";
 import themePreviewDark from "~/assets/theme-previews/dark.jpg";
@@ -60,6 +65,12 @@ const settingsList: Array<{
       "When enabled, Cap will not automatically open links in your browser (e.g. after creating a shareable link).",
     pro: true,
   },
+  {
+    key: "disableProxyMedia",
+    label: "Disable proxy media",
+    description:
+      "Recordings larger than 1080p get a 720p copy after recording that the editor previews from, which keeps scrubbing and playback smooth. Exports always use the full resolution recording.",
+  },
   {
     key: "enableNotifications",
     label: "Enable System Notifications",
@@ -137,6 +148,172 @@ function AppearanceSection(props: {
   );
 }
 
+function WatchFoldersSection(props: {
+  folders: string[];
+  onChange: (folders: string[]) => void;
+}) {
+  const addFolder = async () => {
+    const folder = await open({ directory: true, multiple: false });
+    if (typeof folder !== "string" || props.folders.includes(folder)) return;
+
+    props.onChange([...props.folders, folder]);
+  };
+
+  return (
+    <div class="space-y-2 py-3">
+      <div class="flex items-center justify-between">
+        <p class="text-[--text-primary]">Watch folders</p>
+        <button
+          type="button"
+          class="text-sm text-blue-400 hover:underline"
+          onClick={addFolder}
+        >
+          Add folder
+        </button>
+      </div>
+      <p class="text-xs text-[--text-tertiary]">
+        New videos saved to these folders, such as OBS recordings or screen
+        recordings sent from your phone, are imported as Cap projects
+        automatically.
+      </p>
+      <For each={props.folders}>
+        {(folder) => (
+          <div class="flex items-center justify-between gap-2 text-sm text-[--text-secondary]">
+            <span class="truncate" title={folder}>
+              {folder}
+            </span>
+            <button
+              type="button"
+              class="shrink-0 text-xs text-[--text-tertiary] hover:text-red-400"
+              onClick={() =>
+                props.onChange(props.folders.filter((f) => f !== folder))
+              }
+            >
+              Remove
+            </button>
+          </div>
+        )}
+      </For>
+    </div>
+  );
+}
+
+const uploadSpeedLimits = [null, 5, 10, 25, 50, 100];
+
+function UploadSpeedSection(props: {
+  limit: number | null;
+  onChange: (limit: number | null) => void;
+}) {
+  return (
+    <div class="space-y-2 py-3">
+      <div class="flex items-center justify-between">
+        <p class="text-[--text-primary]">Upload speed limit</p>
+        <select
+          class="text-sm bg-transparent text-[--text-secondary] border border-gray-300 rounded-md px-2 py-1"
+          value={props.limit ?? ""}
+          onChange={(e) =>
+            props.onChange(
+              e.currentTarget.value ? Number(e.currentTarget.value) : null
+            )
+          }
+        >
+          <For each={uploadSpeedLimits}>
+            {(limit) => (
+              <option value={limit ?? ""}>
+                {limit ? `${limit} Mbps` : "Unlimited"}
+              </option>
+            )}
+          </For>
+        </select>
+      </div>
+      <p class="text-xs text-[--text-tertiary]">
+        Leaves room on your connection for other things while shareable links
+        upload.
+      </p>
+    </div>
+  );
+}
+
+function RecordingsLocationSection() {
+  const [info, { mutate }] = createResource(() => commands.getStorageInfo());
+  const [error, setError] = createSignal<string>();
+  const [moving, setMoving] = createSignal(false);
+
+  const setLocation = async (path: string | null) => {
+    const moveProjects = await ask(
+      "Move your existing recordings to the new location too?",
+      { title: "Recordings location", okLabel: "Move", cancelLabel: "Keep" }
+    );
+
+    setError();
+    setMoving(true);
+    try {
+      mutate(await commands.setRecordingsDir(path, moveProjects));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setMoving(false);
+    }
+  };
+
+  const chooseFolder = async () => {
+    const folder = await open({ directory: true, multiple: false });
+    if (typeof folder !== "string") return;
+
+    await setLocation(folder);
+  };
+
+  return (
+    <div class="space-y-2 py-3">
+      <div class="flex items-center justify-between">
+        <p class="text-[--text-primary]">Recordings location</p>
+        <div class="flex items-center gap-3">
+          <Show when={info() && !info()!.isDefault}>
+            <button
+              type="button"
+              class="text-sm text-[--text-tertiary] hover:underline"
+              disabled={moving()}
+              onClick={() => setLocation(null)}
+            >
+              Reset
+            </button>
+          </Show>
+          <button
+            type="button"
+            class="text-sm text-blue-400 hover:underline"
+            disabled={moving()}
+            onClick={chooseFolder}
+          >
+            {moving() ? "Moving..." : "Change"}
+          </button>
+        </div>
+      </div>
+      <p class="text-xs text-[--text-tertiary]">
+        Where new recordings are saved, such as a folder on an external drive.
+        If the folder isn't available, recordings are saved to the default
+        location.
+      </p>
+      <Show when={info()}>
+        {(info) => (
+          <div class="flex items-center justify-between gap-2 text-sm text-[--text-secondary]">
+            <span class="truncate" title={info().recordingsDir}>
+              {info().recordingsDir}
+            </span>
+            <Show when={info().availableBytes !== null}>
+              <span class="shrink-0 text-xs text-[--text-tertiary]">
+                {(info().availableBytes! / 1e9).toFixed(1)} GB free
+              </span>
+            </Show>
+          </div>
+        )}
+      </Show>
+      <Show when={error()}>
+        <p class="text-xs text-red-400">{error()}</p>
+      </Show>
+    </div>
+  );
+}
+
 function Inner(props: { initialStore: GeneralSettingsStore | null }) {
   const [settings, setSettings] = createStore<GeneralSettingsStore>(
     props.initialStore ?? {
@@ -248,6 +425,21 @@ function Inner(props: { initialStore: GeneralSettingsStore | null }) {
               );
             }}
           </For>
+          <WatchFoldersSection
+            folders={settings.watchFolders ?? []}
+            onChange={(folders) => {
+              setSettings("watchFolders", folders);
+              generalSettingsStore.set({ watchFolders: folders });
+            }}
+          />
+          <UploadSpeedSection
+            limit={settings.uploadSpeedLimitMbps ?? null}
+            onChange={(limit) => {
+              setSettings("uploadSpeedLimitMbps", limit);
+              generalSettingsStore.set({ uploadSpeedLimitMbps: limit });
+            }}
+          />
+          <RecordingsLocationSection />
         </div>
       </div>
     </div>
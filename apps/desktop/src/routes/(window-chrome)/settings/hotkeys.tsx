@@ -6,11 +6,11 @@ import {
   Show,
   Switch,
   batch,
-  createEffect,
   createResource,
   createSignal,
 } from "solid-js";
 import { createStore } from "solid-js/store";
+import { message } from "@tauri-apps/plugin-dialog";
 import { hotkeysStore } from "~/store";
 
 import {
@@ -25,6 +25,9 @@ const ACTION_TEXT: Record<HotkeyAction, string> = {
   stopRecording: "Stop Recording",
   restartRecording: "Restart Recording",
   takeScreenshot: "Take Screenshot",
+  pauseRecording: "Pause/Resume Recording",
+  addMarker: "Add Marker",
+  toggleCamera: "Toggle Camera",
 };
 
 export default function () {
@@ -43,10 +46,6 @@ function Inner(props: { initialStore: HotkeysStore | null }) {
     [K in HotkeyAction]?: Hotkey;
   }>(props.initialStore?.hotkeys ?? {});
 
-  createEffect(() => {
-    hotkeysStore.set({ hotkeys: { ...hotkeys } as any });
-  });
-
   const [listening, setListening] = createSignal<{
     action: HotkeyAction;
     prev?: Hotkey;
@@ -80,7 +79,10 @@ function Inner(props: { initialStore: HotkeysStore | null }) {
               "startRecording",
               "stopRecording",
               "restartRecording",
+              "pauseRecording",
+              "addMarker",
               "takeScreenshot",
+              "toggleCamera",
             ] as Array<HotkeyAction>
           }
         >
@@ -112,11 +114,18 @@ function Inner(props: { initialStore: HotkeysStore | null }) {
                               onClick={(e) => {
                                 e.stopPropagation();
 
+                                const prev = listening()?.prev;
                                 setListening();
-                                commands.setHotkey(
-                                  item(),
-                                  hotkeys[item()] ?? null
-                                );
+                                commands
+                                  .setHotkey(item(), hotkeys[item()] ?? null)
+                                  .catch((e) => {
+                                    // the shortcut is taken, so the old one stays
+                                    setHotkeys(item(), prev);
+                                    message(String(e), {
+                                      title: "Shortcut unavailable",
+                                      kind: "warning",
+                                    });
+                                  });
                               }}
                             >
                               <IconCapCircleCheck class="size-[1.25rem] text-[--blue-400]" />
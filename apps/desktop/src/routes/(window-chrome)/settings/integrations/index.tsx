@@ -18,6 +18,22 @@ export default function AppsTab() {
       url: "/settings/integrations/s3-config",
       pro: true,
     },
+    {
+      name: "Self-hosted Storage",
+      description:
+        "Upload shareable links straight from this device to an S3-compatible bucket you run, without going through Cap's cloud.",
+      icon: IconLucideServer,
+      url: "/settings/integrations/own-storage",
+      pro: false,
+    },
+    {
+      name: "Webhooks",
+      description:
+        "Notify your own services when recordings start and stop, exports finish, and shareable links are created.",
+      icon: IconLucideWebhook,
+      url: "/settings/integrations/webhooks",
+      pro: false,
+    },
   ];
 
   const handleAppClick = async (app: (typeof apps)[number]) => {
@@ -29,7 +45,7 @@ export default function AppsTab() {
   };
 
   return (
-    <div class="p-4">
+    <div class="p-4 space-y-3">
       <For each={apps}>
         {(app) => (
           <div class="p-1.5 bg-gray-100 rounded-lg border border-gray-200">
@@ -0,0 +1,160 @@
+import { Button } from "@cap/ui-solid";
+import { createSignal, onMount } from "solid-js";
+import { createStore } from "solid-js/store";
+import { commands, type S3StorageConfig } from "~/utils/tauri";
+
+const DEFAULT_CONFIG: S3StorageConfig = {
+  endpoint: "https://s3.amazonaws.com",
+  region: "us-east-1",
+  bucket: "",
+  accessKeyId: "",
+  secretAccessKey: "",
+  forcePathStyle: false,
+  publicUrl: null,
+};
+
+export default function OwnStoragePage() {
+  const [config, setConfig] = createStore<S3StorageConfig>({
+    ...DEFAULT_CONFIG,
+  });
+  const [hasConfig, setHasConfig] = createSignal(false);
+  const [busy, setBusy] = createSignal(false);
+
+  onMount(async () => {
+    const saved = await commands.getS3StorageConfig().catch(() => null);
+    if (saved) {
+      setConfig(saved);
+      setHasConfig(true);
+    }
+  });
+
+  const run = async (action: () => Promise<unknown>, success: string) => {
+    setBusy(true);
+    try {
+      await action();
+      await commands.globalMessageDialog(success);
+    } catch (e) {
+      await commands.globalMessageDialog(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleSave = () =>
+    run(async () => {
+      await commands.setS3StorageConfig({ ...config });
+      setHasConfig(true);
+    }, "Shareable links will now be uploaded to your bucket");
+
+  const handleRemove = () =>
+    run(async () => {
+      await commands.setS3StorageConfig(null);
+      setConfig({ ...DEFAULT_CONFIG });
+      setHasConfig(false);
+    }, "Shareable links will be uploaded to Cap again");
+
+  const handleTest = () =>
+    run(
+      () => commands.testS3StorageConfig({ ...config }),
+      "Connected! Cap can upload to this bucket."
+    );
+
+  const renderInput = (
+    label: string,
+    key: "endpoint" | "region" | "bucket" | "accessKeyId" | "secretAccessKey",
+    placeholder: string,
+    type: "text" | "password" = "text"
+  ) => (
+    <div>
+      <label class="text-gray-500 text-sm">{label}</label>
+      <input
+        type={type}
+        value={config[key]}
+        onInput={(e) => setConfig(key, e.currentTarget.value)}
+        placeholder={placeholder}
+        class="w-full px-3 py-2 border border-gray-200 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500"
+        autocomplete="off"
+        autocapitalize="off"
+        autocorrect="off"
+        spellcheck={false}
+      />
+    </div>
+  );
+
+  return (
+    <div class="h-full flex flex-col">
+      <div class="flex-1 overflow-y-auto">
+        <div class="p-4 space-y-4">
+          <p class="text-gray-400 text-sm">
+            Upload shareable links straight from this device to any
+            S3-compatible bucket, such as a self-hosted MinIO server. Nothing is
+            sent to Cap, and you don't need to be signed in.
+          </p>
+          {renderInput("Endpoint", "endpoint", "https://s3.amazonaws.com")}
+          {renderInput("Region", "region", "us-east-1")}
+          {renderInput("Bucket Name", "bucket", "my-bucket")}
+          {renderInput("Access Key ID", "accessKeyId", "", "password")}
+          {renderInput("Secret Access Key", "secretAccessKey", "", "password")}
+          <div>
+            <label class="text-gray-500 text-sm">Public URL (optional)</label>
+            <input
+              type="text"
+              value={config.publicUrl ?? ""}
+              onInput={(e) =>
+                setConfig("publicUrl", e.currentTarget.value || null)
+              }
+              placeholder="https://cdn.example.com"
+              class="w-full px-3 py-2 border border-gray-200 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500"
+              autocomplete="off"
+              spellcheck={false}
+            />
+            <p class="text-xs text-[--text-tertiary] mt-1">
+              Links use this address when it's set. Otherwise they're signed
+              links to the bucket that expire after 7 days.
+            </p>
+          </div>
+          <label class="flex items-center gap-2 text-sm text-gray-500">
+            <input
+              type="checkbox"
+              checked={config.forcePathStyle ?? false}
+              onChange={(e) =>
+                setConfig("forcePathStyle", e.currentTarget.checked)
+              }
+            />
+            Use path-style URLs (needed for MinIO and most self-hosted servers)
+          </label>
+        </div>
+      </div>
+
+      <div class="flex-shrink-0 p-4 border-t">
+        <div class="flex justify-between items-center">
+          <div class="flex gap-2">
+            {hasConfig() && (
+              <Button
+                variant="destructive"
+                onClick={handleRemove}
+                disabled={busy()}
+              >
+                Remove
+              </Button>
+            )}
+            <Button
+              variant="secondary"
+              onClick={handleTest}
+              disabled={busy() || !config.bucket || !config.accessKeyId}
+            >
+              Test Connection
+            </Button>
+          </div>
+          <Button
+            variant="primary"
+            onClick={handleSave}
+            disabled={busy() || !config.bucket || !config.accessKeyId}
+          >
+            Save
+          </Button>
+        </div>
+      </div>
+    </div>
+  );
+}
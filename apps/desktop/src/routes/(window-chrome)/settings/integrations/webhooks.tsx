@@ -0,0 +1,184 @@
+import { Button } from "@cap/ui-solid";
+import { For, Show, createSignal, onMount } from "solid-js";
+import { createStore, produce } from "solid-js/store";
+import { commands, type Webhook, type WebhookEvent } from "~/utils/tauri";
+
+const EVENTS: { value: WebhookEvent; label: string }[] = [
+  { value: "recordingStarted", label: "Recording started" },
+  { value: "recordingStopped", label: "Recording stopped" },
+  { value: "exportFinished", label: "Export finished" },
+  { value: "uploadComplete", label: "Upload complete" },
+  { value: "shareLinkCreated", label: "Shareable link created" },
+];
+
+function generateSecret() {
+  const bytes = crypto.getRandomValues(new Uint8Array(24));
+  return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+export default function WebhooksPage() {
+  const [webhooks, setWebhooks] = createStore<Webhook[]>([]);
+  const [busy, setBusy] = createSignal(false);
+
+  onMount(async () => {
+    setWebhooks(await commands.getWebhooks().catch(() => []));
+  });
+
+  const run = async (action: () => Promise<unknown>, success: string) => {
+    setBusy(true);
+    try {
+      await action();
+      await commands.globalMessageDialog(success);
+    } catch (e) {
+      await commands.globalMessageDialog(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleAdd = () =>
+    setWebhooks(webhooks.length, {
+      url: "",
+      events: [],
+      secret: generateSecret(),
+      enabled: true,
+    });
+
+  const toggleEvent = (index: number, event: WebhookEvent) =>
+    setWebhooks(
+      index,
+      produce((webhook) => {
+        // no events selected means all of them
+        const events = webhook.events?.length
+          ? webhook.events
+          : EVENTS.map((e) => e.value);
+        webhook.events = events.includes(event)
+          ? events.filter((e) => e !== event)
+          : [...events, event];
+        if (webhook.events.length === EVENTS.length) webhook.events = [];
+      })
+    );
+
+  const handleSave = () =>
+    run(
+      () => commands.setWebhooks(webhooks.filter((w) => w.url.trim())),
+      "Webhooks saved"
+    );
+
+  return (
+    <div class="h-full flex flex-col">
+      <div class="flex-1 overflow-y-auto">
+        <div class="p-4 space-y-4">
+          <p class="text-gray-400 text-sm">
+            Cap sends a JSON POST request to each webhook when something
+            happens to your recordings, retrying ones that fail. Requests are
+            signed with the webhook's secret in the X-Cap-Signature-256 header.
+          </p>
+          <For each={webhooks}>
+            {(webhook, index) => (
+              <div class="p-3 space-y-3 rounded-lg border border-gray-200">
+                <div class="flex gap-2 items-center">
+                  <input
+                    type="checkbox"
+                    checked={webhook.enabled ?? true}
+                    onChange={(e) =>
+                      setWebhooks(index(), "enabled", e.currentTarget.checked)
+                    }
+                  />
+                  <input
+                    type="text"
+                    value={webhook.url}
+                    onInput={(e) =>
+                      setWebhooks(index(), "url", e.currentTarget.value)
+                    }
+                    placeholder="https://example.com/webhooks/cap"
+                    class="flex-1 px-3 py-2 border border-gray-200 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500"
+                    autocomplete="off"
+                    spellcheck={false}
+                  />
+                </div>
+                <div class="flex flex-wrap gap-x-4 gap-y-1">
+                  <For each={EVENTS}>
+                    {(event) => (
+                      <label class="flex items-center gap-2 text-sm text-gray-500">
+                        <input
+                          type="checkbox"
+                          checked={
+                            !webhook.events?.length ||
+                            webhook.events.includes(event.value)
+                          }
+                          onChange={() => toggleEvent(index(), event.value)}
+                        />
+                        {event.label}
+                      </label>
+                    )}
+                  </For>
+                </div>
+                <div>
+                  <label class="text-gray-500 text-sm">Signing secret</label>
+                  <input
+                    type="password"
+                    value={webhook.secret ?? ""}
+                    onInput={(e) =>
+                      setWebhooks(
+                        index(),
+                        "secret",
+                        e.currentTarget.value || null
+                      )
+                    }
+                    class="w-full px-3 py-2 border border-gray-200 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500"
+                    autocomplete="off"
+                    spellcheck={false}
+                  />
+                </div>
+                <div class="flex gap-2 justify-end">
+                  <Show when={webhook.secret}>
+                    {(secret) => (
+                      <Button
+                        variant="secondary"
+                        onClick={() => navigator.clipboard.writeText(secret())}
+                      >
+                        Copy Secret
+                      </Button>
+                    )}
+                  </Show>
+                  <Button
+                    variant="secondary"
+                    disabled={busy() || !webhook.url.trim()}
+                    onClick={() =>
+                      run(
+                        () => commands.testWebhook({ ...webhook }),
+                        "The webhook received a ping event"
+                      )
+                    }
+                  >
+                    Send Test
+                  </Button>
+                  <Button
+                    variant="destructive"
+                    onClick={() =>
+                      setWebhooks((w) => w.filter((_, i) => i !== index()))
+                    }
+                  >
+                    Remove
+                  </Button>
+                </div>
+              </div>
+            )}
+          </For>
+        </div>
+      </div>
+
+      <div class="flex-shrink-0 p-4 border-t">
+        <div class="flex justify-between items-center">
+          <Button variant="secondary" onClick={handleAdd}>
+            Add Webhook
+          </Button>
+          <Button variant="primary" onClick={handleSave} disabled={busy()}>
+            Save
+          </Button>
+        </div>
+      </div>
+    </div>
+  );
+}
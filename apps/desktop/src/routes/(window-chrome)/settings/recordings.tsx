@@ -1,6 +1,8 @@
 import { createQuery } from "@tanstack/solid-query";
 import { For, ParentProps, Show, Suspense, createSignal } from "solid-js";
 import { convertFileSrc } from "@tauri-apps/api/core";
+import { ask, message, open, save } from "@tauri-apps/plugin-dialog";
+import { writeText } from "@tauri-apps/plugin-clipboard-manager";
 
 import { commands, events, type RecordingMeta } from "~/utils/tauri";
 import { trackEvent } from "~/utils/analytics";
@@ -15,6 +17,7 @@ type MediaEntry = {
 };
 
 export default function Recordings() {
+  const [importing, setImporting] = createSignal(false);
   const fetchRecordings = createQuery(() => ({
     queryKey: ["recordings"],
     queryFn: async () => {
@@ -59,8 +62,77 @@ export default function Recordings() {
     commands.openEditor(fileName.replace(".cap", ""));
   };
 
+  const handleExportBundle = async (recording: MediaEntry) => {
+    const destination = await save({
+      defaultPath: `${recording.prettyName}.capproj`,
+      filters: [{ name: "Cap Project", extensions: ["capproj"] }],
+    });
+    if (!destination) return;
+
+    await commands
+      .exportProjectBundle(recording.path, destination)
+      .catch((e) =>
+        message(String(e), { title: "Export failed", kind: "error" })
+      );
+  };
+
+  const handleLanShare = async (recording: MediaEntry) => {
+    const existing = await commands.getLanShare(recording.path);
+    if (existing) {
+      const stop = await ask(
+        `${recording.prettyName} is being shared at ${existing.url}`,
+        {
+          title: "Shared on local network",
+          okLabel: "Stop sharing",
+          cancelLabel: "Keep sharing",
+        }
+      );
+      if (stop) await commands.stopLanShare(recording.path);
+      return;
+    }
+
+    try {
+      const share = await commands.startLanShare(recording.path, true);
+      await writeText(share.url);
+      await message(
+        `Anyone on your network with this link can watch it until Cap is closed. It's been copied to your clipboard.\n\n${share.url}`,
+        { title: "Shared on local network" }
+      );
+    } catch (e) {
+      await message(String(e), { title: "Sharing failed", kind: "error" });
+    }
+  };
+
+  const handleImportBundle = async () => {
+    const bundlePath = await open({
+      multiple: false,
+      filters: [{ name: "Cap Project", extensions: ["capproj"] }],
+    });
+    if (typeof bundlePath !== "string") return;
+
+    setImporting(true);
+    try {
+      await commands.importProjectBundle(bundlePath);
+      await fetchRecordings.refetch();
+    } catch (e) {
+      await message(String(e), { title: "Import failed", kind: "error" });
+    } finally {
+      setImporting(false);
+    }
+  };
+
   return (
     <div class="flex flex-col w-full h-full divide-y divide-[--gray-200] pt-1 pb-12">
+      <div class="flex justify-end px-[0.625rem] py-2">
+        <button
+          type="button"
+          class="text-sm text-blue-400 hover:underline"
+          disabled={importing()}
+          onClick={handleImportBundle}
+        >
+          {importing() ? "Importing..." : "Import project"}
+        </button>
+      </div>
       <div class="flex-1 overflow-y-auto">
         <ul class="p-[0.625rem] flex flex-col gap-[0.5rem] w-full text-[--text-primary]">
           <Show
@@ -78,6 +150,8 @@ export default function Recordings() {
                   onClick={() => handleRecordingClick(recording)}
                   onOpenFolder={() => handleOpenFolder(recording.path)}
                   onOpenEditor={() => handleOpenEditor(recording.path)}
+                  onExportBundle={() => handleExportBundle(recording)}
+                  onLanShare={() => handleLanShare(recording)}
                 />
               )}
             </For>
@@ -93,6 +167,8 @@ function RecordingItem(props: {
   onClick: () => void;
   onOpenFolder: () => void;
   onOpenEditor: () => void;
+  onExportBundle: () => void;
+  onLanShare: () => void;
 }) {
   const [imageExists, setImageExists] = createSignal(true);
 
@@ -127,6 +203,18 @@ function RecordingItem(props: {
         >
           <IconLucideEdit class="size-5" />
         </TooltipIconButton>
+        <TooltipIconButton
+          tooltipText="Export as project file"
+          onClick={() => props.onExportBundle()}
+        >
+          <IconLucidePackage class="size-5" />
+        </TooltipIconButton>
+        <TooltipIconButton
+          tooltipText="Share on local network"
+          onClick={() => props.onLanShare()}
+        >
+          <IconLucideWifi class="size-5" />
+        </TooltipIconButton>
         <TooltipIconButton
           tooltipText="Show in recordings overlay"
           onClick={() => props.onClick()}
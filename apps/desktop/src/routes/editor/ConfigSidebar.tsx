@@ -28,17 +28,34 @@ import {
   join,
   resolveResource,
 } from "@tauri-apps/api/path";
-import { convertFileSrc } from "@tauri-apps/api/core";
+import { Channel, convertFileSrc } from "@tauri-apps/api/core";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { Collapsible } from "@kobalte/core/collapsible";
 
 import {
+  type AnnotationAnimation,
+  type AnnotationKind,
+  type AudioTrackConfiguration,
   type BackgroundSource,
+  type CaptionsConfiguration,
+  type ColorGradeConfiguration,
   type CursorAnimationStyle,
+  type CursorTheme,
+  type HotkeysPosition,
+  type IdlePeriod,
+  type RedactionSuggestion,
+  type SensitiveKind,
+  type SilenceCut,
+  type SlideDirection,
+  type TextAnimation,
+  type TextFont,
+  type TranscriptionProgress,
+  type TransitionKind,
+  type WatermarkCorner,
   commands,
 } from "~/utils/tauri";
 import { useEditorContext } from "./context";
 import {
-  ComingSoonTooltip,
   EditorButton,
   Field,
   Subfield,
@@ -51,12 +68,15 @@ import { type as ostype } from "@tauri-apps/plugin-os";
 import toast from "solid-toast";
 import { createElementBounds } from "@solid-primitives/bounds";
 import { TextInput } from "./TextInput";
+import { formatTime } from "./utils";
 
 const BACKGROUND_SOURCES = {
   wallpaper: "Wallpaper",
   image: "Image",
   color: "Color",
   gradient: "Gradient",
+  screen: "Screen",
+  transparent: "Clear",
 } satisfies Record<BackgroundSource["type"], string>;
 
 const BACKGROUND_SOURCES_LIST = [
@@ -64,6 +84,8 @@ const BACKGROUND_SOURCES_LIST = [
   "image",
   "color",
   "gradient",
+  "screen",
+  "transparent",
 ] satisfies Array<BackgroundSource["type"]>;
 
 const CURSOR_ANIMATION_STYLES: Record<CursorAnimationStyle, string> = {
@@ -72,6 +94,18 @@ const CURSOR_ANIMATION_STYLES: Record<CursorAnimationStyle, string> = {
   fast: "Fast & Responsive",
 } as const;
 
+const CURSOR_THEMES: Record<CursorTheme, string> = {
+  recorded: "Recorded",
+  macos: "macOS",
+  windows: "Windows",
+} as const;
+
+const HOTKEYS_POSITIONS: Record<HotkeysPosition, string> = {
+  bottomLeft: "Left",
+  bottomCenter: "Center",
+  bottomRight: "Right",
+} as const;
+
 const WALLPAPER_NAMES = [
   // macOS wallpapers
   "macOS/sequoia-dark",
@@ -119,6 +153,150 @@ const WALLPAPER_NAMES = [
   "orange/9",
 ] as const;
 
+// how much faster idle time plays when it's sped up rather than cut
+const IDLE_SPEED_FACTOR = 8;
+
+const TEXT_FONTS: Record<TextFont, string> = {
+  sans: "Sans",
+  serif: "Serif",
+  mono: "Mono",
+} as const;
+
+const DEFAULT_CAPTIONS: CaptionsConfiguration = {
+  burnIn: false,
+  font: "sans",
+  size: 4.5,
+  color: [255, 255, 255],
+  background: [0, 0, 0],
+  backgroundOpacity: 0.6,
+};
+
+const CAPTION_FILE_EXTENSIONS = ["srt", "vtt"];
+
+// cards are only in exports, before and after the timeline
+const TITLE_CARDS = [
+  { key: "intro", name: "Intro Card" },
+  { key: "outro", name: "Outro Card" },
+] as const;
+
+const TEXT_ANIMATIONS: Record<TextAnimation, string> = {
+  none: "None",
+  fade: "Fade",
+  slideFromBottom: "Rise",
+  slideFromLeft: "Slide",
+} as const;
+
+const TRANSITION_KINDS: Record<TransitionKind["type"], string> = {
+  crossFade: "Cross-fade",
+  dip: "Dip to Color",
+  slide: "Slide",
+} as const;
+
+const SLIDE_DIRECTIONS: Record<SlideDirection, string> = {
+  left: "Left",
+  right: "Right",
+  up: "Up",
+  down: "Down",
+} as const;
+
+const DEFAULT_TRANSITION_KINDS: Record<
+  TransitionKind["type"],
+  TransitionKind
+> = {
+  crossFade: { type: "crossFade" },
+  dip: { type: "dip", color: [0, 0, 0] },
+  slide: { type: "slide", direction: "left" },
+};
+
+// how long new text and annotations stay on screen, in seconds
+const DEFAULT_TEXT_DURATION = 4;
+
+const ANNOTATION_KINDS: Record<AnnotationKind, string> = {
+  arrow: "Arrow",
+  rectangle: "Box",
+  highlight: "Highlight",
+  blur: "Blur",
+  pixelate: "Pixelate",
+} as const;
+
+const ANNOTATION_ANIMATIONS: Record<AnnotationAnimation, string> = {
+  none: "None",
+  fade: "Fade",
+  grow: "Grow",
+} as const;
+
+// line thickness for shapes, and how strongly masks hide what's under them, as a percentage
+// of the frame's height
+const DEFAULT_ANNOTATION_SIZES: Record<AnnotationKind, number> = {
+  arrow: 0.6,
+  rectangle: 0.4,
+  highlight: 0.4,
+  blur: 2,
+  pixelate: 2,
+};
+
+const isMask = (kind: AnnotationKind) => kind === "blur" || kind === "pixelate";
+
+const WATERMARK_CORNERS: Record<WatermarkCorner, string> = {
+  topLeft: "Top Left",
+  topRight: "Top Right",
+  bottomLeft: "Bottom Left",
+  bottomRight: "Bottom Right",
+} as const;
+
+const DEFAULT_COLOR_GRADE: ColorGradeConfiguration = {
+  brightness: 0,
+  contrast: 0,
+  saturation: 0,
+  lut: null,
+  lutIntensity: 100,
+};
+
+const COLOR_ADJUSTMENTS = [
+  ["brightness", "Brightness"],
+  ["contrast", "Contrast"],
+  ["saturation", "Saturation"],
+] as const;
+
+// the renderer reads PNGs, so SVG watermarks are drawn into one this wide
+const SVG_WATERMARK_WIDTH = 1024;
+
+async function svgToPng(file: File): Promise<Uint8Array> {
+  const url = URL.createObjectURL(file);
+  try {
+    const image = new Image();
+    image.src = url;
+    await image.decode();
+
+    const width = SVG_WATERMARK_WIDTH;
+    const height = Math.max(
+      1,
+      Math.round(
+        (width * (image.naturalHeight || 1)) / (image.naturalWidth || 1)
+      )
+    );
+    const canvas = document.createElement("canvas");
+    canvas.width = width;
+    canvas.height = height;
+    canvas.getContext("2d")?.drawImage(image, 0, 0, width, height);
+
+    const blob = await new Promise<Blob | null>((resolve) =>
+      canvas.toBlob(resolve, "image/png")
+    );
+    if (!blob) throw new Error("Failed to convert SVG");
+
+    return new Uint8Array(await blob.arrayBuffer());
+  } finally {
+    URL.revokeObjectURL(url);
+  }
+}
+
+const SENSITIVE_KINDS: Record<SensitiveKind, string> = {
+  email: "Email",
+  apiKey: "API key",
+  cardNumber: "Card number",
+} as const;
+
 export function ConfigSidebar() {
   const {
     selectedTab,
@@ -129,6 +307,8 @@ export function ConfigSidebar() {
     state,
     setState,
     history,
+    playbackTime,
+    prettyName,
   } = useEditorContext();
 
   const [wallpapers, { mutate }] = createResource(async () => {
@@ -292,6 +472,12 @@ export function ConfigSidebar() {
       from: DEFAULT_GRADIENT_FROM,
       to: DEFAULT_GRADIENT_TO,
     },
+    screen: {
+      type: "screen",
+    },
+    transparent: {
+      type: "transparent",
+    },
   };
 
   const [previousAngle, setPreviousAngle] = createSignal(0);
@@ -302,6 +488,7 @@ export function ConfigSidebar() {
   generalSettingsStore.listen(() => hapticsEnabledOptions.refetch());
 
   let fileInput!: HTMLInputElement;
+  let watermarkInput!: HTMLInputElement;
 
   // Optimize the debounced set project function
   const debouncedSetProject = (wallpaperPath: string) => {
@@ -339,7 +526,9 @@ export function ConfigSidebar() {
             // },
             { id: "audio" as const, icon: IconCapAudioOn },
             { id: "cursor" as const, icon: IconCapCursor },
-            // { id: "hotkeys" as const, icon: IconCapHotkeys },
+            { id: "hotkeys" as const, icon: IconCapHotkeys },
+            { id: "text" as const, icon: IconCapMessageBubble },
+            { id: "annotations" as const, icon: IconCapSquare },
           ]}
         >
           {(item) => (
@@ -402,9 +591,21 @@ export function ConfigSidebar() {
                         project.background.source.type === "gradient"
                           ? project.background.source.angle
                           : 90,
+                      kind:
+                        project.background.source.type === "gradient"
+                          ? project.background.source.kind
+                          : "linear",
                     });
                     break;
                   }
+                  case "screen": {
+                    setProject("background", "source", backgrounds.screen);
+                    break;
+                  }
+                  case "transparent": {
+                    setProject("background", "source", backgrounds.transparent);
+                    break;
+                  }
                   case "wallpaper": {
                     setProject("background", "source", {
                       type: "wallpaper",
@@ -769,11 +970,34 @@ export function ConfigSidebar() {
                         >
                           <div class="bg-blue-300 size-2 rounded-full" />
                         </div>
+                        <div class="flex flex-col items-center gap-1 text-gray-400 shrink-0">
+                          <span>Radial</span>
+                          <Toggle
+                            checked={source().kind === "radial"}
+                            onChange={(radial) =>
+                              setProject("background", "source", {
+                                type: "gradient",
+                                kind: radial ? "radial" : "linear",
+                              })
+                            }
+                          />
+                        </div>
                       </>
                     );
                   }}
                 </Show>
               </KTabs.Content>
+              <KTabs.Content value="screen">
+                <p class="text-gray-400">
+                  A blurred copy of the recording fills the background.
+                </p>
+              </KTabs.Content>
+              <KTabs.Content value="transparent">
+                <p class="text-gray-400">
+                  Kept transparent when exporting as ProRes 4444, FFV1, Ut
+                  Video or VP9, and black otherwise.
+                </p>
+              </KTabs.Content>
             </KTabs>
           </Field>
 
@@ -788,13 +1012,23 @@ export function ConfigSidebar() {
           </Field>
 
           <Field name="Padding" icon={<IconCapPadding />}>
-            <Slider
-              value={[project.background.padding]}
-              onChange={(v) => setProject("background", "padding", v[0])}
-              minValue={0}
-              maxValue={40}
-              step={0.1}
-            />
+            <div class="flex flex-col gap-[0.75rem]">
+              <Slider
+                value={[project.background.padding]}
+                onChange={(v) => setProject("background", "padding", v[0])}
+                minValue={0}
+                maxValue={40}
+                step={0.1}
+              />
+              <Subfield name="Shrink While Zoomed">
+                <Toggle
+                  checked={project.background.paddingFollowsZoom ?? false}
+                  onChange={(v) =>
+                    setProject("background", "paddingFollowsZoom", v)
+                  }
+                />
+              </Subfield>
+            </div>
           </Field>
           <Field name="Rounded Corners" icon={<IconCapCorners />}>
             <Slider
@@ -889,10 +1123,375 @@ export function ConfigSidebar() {
                       step={0.1}
                     />
                   </div>
+                  <div class="flex flex-col gap-2">
+                    <span class="text-gray-500 text-sm">Offset X</span>
+                    <Slider
+                      value={[project.background.advancedShadow?.offset_x ?? 0]}
+                      onChange={(v) => {
+                        setProject("background", "advancedShadow", {
+                          ...(project.background.advancedShadow ?? {
+                            size: 50,
+                            opacity: 18,
+                            blur: 50,
+                          }),
+                          offset_x: v[0],
+                        });
+                      }}
+                      minValue={-100}
+                      maxValue={100}
+                      step={0.1}
+                    />
+                  </div>
+                  <div class="flex flex-col gap-2">
+                    <span class="text-gray-500 text-sm">Offset Y</span>
+                    <Slider
+                      value={[project.background.advancedShadow?.offset_y ?? 0]}
+                      onChange={(v) => {
+                        setProject("background", "advancedShadow", {
+                          ...(project.background.advancedShadow ?? {
+                            size: 50,
+                            opacity: 18,
+                            blur: 50,
+                          }),
+                          offset_y: v[0],
+                        });
+                      }}
+                      minValue={-100}
+                      maxValue={100}
+                      step={0.1}
+                    />
+                  </div>
                 </Collapsible.Content>
               </Collapsible>
             </div>
           </Field>
+          <Field name="Color" icon={<IconLucidePalette />}>
+            <div class="flex flex-col gap-[0.75rem]">
+              <For each={COLOR_ADJUSTMENTS}>
+                {([key, name]) => (
+                  <Subfield name={name}>
+                    <Slider
+                      value={[project.colorGrade?.[key] ?? 0]}
+                      onChange={(v) =>
+                        setProject("colorGrade", {
+                          ...DEFAULT_COLOR_GRADE,
+                          ...project.colorGrade,
+                          [key]: v[0],
+                        })
+                      }
+                      minValue={-100}
+                      maxValue={100}
+                      step={1}
+                    />
+                  </Subfield>
+                )}
+              </For>
+              <Show
+                when={project.colorGrade?.lut}
+                fallback={
+                  <EditorButton
+                    onClick={async () => {
+                      const path = await open({
+                        filters: [{ name: "LUT", extensions: ["cube"] }],
+                      });
+                      if (typeof path !== "string") return;
+
+                      setProject("colorGrade", {
+                        ...DEFAULT_COLOR_GRADE,
+                        ...project.colorGrade,
+                        lut: path,
+                      });
+                    }}
+                  >
+                    Add LUT
+                  </EditorButton>
+                }
+              >
+                {(lut) => (
+                  <>
+                    <span class="text-sm text-gray-400 truncate">
+                      {lut().split(/[\\/]/).pop()}
+                    </span>
+                    <Subfield name="LUT Intensity">
+                      <Slider
+                        value={[project.colorGrade?.lutIntensity ?? 100]}
+                        onChange={(v) =>
+                          setProject("colorGrade", "lutIntensity", v[0])
+                        }
+                        minValue={0}
+                        maxValue={100}
+                        step={1}
+                      />
+                    </Subfield>
+                    <EditorButton
+                      leftIcon={<IconCapTrash />}
+                      onClick={() => setProject("colorGrade", "lut", null)}
+                    >
+                      Remove LUT
+                    </EditorButton>
+                  </>
+                )}
+              </Show>
+            </div>
+          </Field>
+          <Field name="Watermark" icon={<IconCapImage />}>
+            <Show
+              when={project.watermark}
+              fallback={
+                <EditorButton onClick={() => watermarkInput.click()}>
+                  Add Watermark
+                </EditorButton>
+              }
+            >
+              {(watermark) => (
+                <div class="flex flex-col gap-[0.75rem]">
+                  <span class="text-sm text-gray-400 truncate">
+                    {watermark().path.split(/[\\/]/).pop()}
+                  </span>
+                  <Subfield name="Corner">
+                    <select
+                      class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                      value={watermark().corner ?? "bottomRight"}
+                      onChange={(e) =>
+                        setProject(
+                          "watermark",
+                          "corner",
+                          e.currentTarget.value as WatermarkCorner
+                        )
+                      }
+                    >
+                      <For each={Object.entries(WATERMARK_CORNERS)}>
+                        {([value, label]) => (
+                          <option value={value}>{label}</option>
+                        )}
+                      </For>
+                    </select>
+                  </Subfield>
+                  <Subfield name="Size">
+                    <Slider
+                      value={[watermark().scale]}
+                      onChange={(v) => setProject("watermark", "scale", v[0])}
+                      minValue={2}
+                      maxValue={40}
+                      step={0.5}
+                    />
+                  </Subfield>
+                  <Subfield name="Opacity">
+                    <Slider
+                      value={[watermark().opacity * 100]}
+                      onChange={(v) =>
+                        setProject("watermark", "opacity", v[0] / 100)
+                      }
+                      minValue={0}
+                      maxValue={100}
+                      step={1}
+                    />
+                  </Subfield>
+                  <Show when={window.FLAGS.freeTierWatermark}>
+                    <Subfield name="Only on Free Exports">
+                      <Toggle
+                        checked={watermark().freeTierOnly ?? false}
+                        onChange={(v) =>
+                          setProject("watermark", "freeTierOnly", v)
+                        }
+                      />
+                    </Subfield>
+                  </Show>
+                  <div class="flex flex-row gap-2">
+                    <EditorButton onClick={() => watermarkInput.click()}>
+                      Replace
+                    </EditorButton>
+                    <EditorButton
+                      leftIcon={<IconCapTrash />}
+                      onClick={() => setProject("watermark", null)}
+                    >
+                      Remove
+                    </EditorButton>
+                  </div>
+                </div>
+              )}
+            </Show>
+            <input
+              type="file"
+              ref={watermarkInput}
+              class="hidden"
+              accept="image/png, image/svg+xml"
+              onChange={async (e) => {
+                const file = e.currentTarget.files?.[0];
+                e.currentTarget.value = "";
+                if (!file) return;
+
+                // see the background image input
+                const extension = file.name.split(".").pop()?.toLowerCase();
+                if (extension !== "png" && extension !== "svg") {
+                  toast.error("Watermarks have to be PNG or SVG images");
+                  return;
+                }
+
+                try {
+                  const data =
+                    extension === "svg"
+                      ? await svgToPng(file)
+                      : new Uint8Array(await file.arrayBuffer());
+                  const fileName = `watermark-${Date.now()}.png`;
+
+                  await writeFile(fileName, data, {
+                    baseDir: BaseDirectory.AppData,
+                  });
+
+                  const path = `${await appDataDir()}/${fileName}`;
+                  setProject("watermark", (watermark) => ({
+                    corner: "bottomRight",
+                    scale: 12,
+                    opacity: 0.8,
+                    freeTierOnly: false,
+                    ...watermark,
+                    path,
+                  }));
+                } catch (err) {
+                  toast.error("Failed to save watermark");
+                }
+              }}
+            />
+          </Field>
+          <Show when={(project.timeline?.segments.length ?? 0) > 1}>
+            <Field name="Transitions" icon={<IconCapPadding />}>
+              <div class="flex flex-col gap-[0.75rem]">
+                <For each={project.timeline?.segments.slice(1) ?? []}>
+                  {(segment, i) => {
+                    // the segment the transition belongs to, after the cut
+                    const index = () => i() + 1;
+                    const cut = () =>
+                      project
+                        .timeline!.segments.slice(0, index())
+                        .reduce(
+                          (t, s) => t + (s.end - s.start) / s.timescale,
+                          0
+                        );
+
+                    return (
+                      <div class="flex flex-col gap-[0.75rem]">
+                        <Subfield name={`Cut at ${formatTime(cut())}`}>
+                          <select
+                            class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                            value={segment.transition?.kind.type ?? "none"}
+                            onChange={(e) => {
+                              const type = e.currentTarget.value;
+                              setProject(
+                                "timeline",
+                                "segments",
+                                index(),
+                                "transition",
+                                type === "none"
+                                  ? null
+                                  : {
+                                      kind: DEFAULT_TRANSITION_KINDS[
+                                        type as TransitionKind["type"]
+                                      ],
+                                      duration:
+                                        segment.transition?.duration ?? 0.5,
+                                    }
+                              );
+                            }}
+                          >
+                            <option value="none">None</option>
+                            <For each={Object.entries(TRANSITION_KINDS)}>
+                              {([value, label]) => (
+                                <option value={value}>{label}</option>
+                              )}
+                            </For>
+                          </select>
+                        </Subfield>
+                        <Show when={segment.transition}>
+                          {(transition) => (
+                            <>
+                              <Subfield name="Duration">
+                                <Slider
+                                  value={[transition().duration]}
+                                  onChange={(v) =>
+                                    setProject(
+                                      "timeline",
+                                      "segments",
+                                      index(),
+                                      "transition",
+                                      "duration",
+                                      v[0]
+                                    )
+                                  }
+                                  minValue={0.1}
+                                  maxValue={2}
+                                  step={0.1}
+                                />
+                              </Subfield>
+                              <Show
+                                when={(() => {
+                                  const kind = transition().kind;
+                                  return kind.type === "dip" && kind;
+                                })()}
+                              >
+                                {(kind) => (
+                                  <RgbInput
+                                    value={kind().color}
+                                    onChange={(color) =>
+                                      setProject(
+                                        "timeline",
+                                        "segments",
+                                        index(),
+                                        "transition",
+                                        "kind",
+                                        { type: "dip", color }
+                                      )
+                                    }
+                                  />
+                                )}
+                              </Show>
+                              <Show
+                                when={(() => {
+                                  const kind = transition().kind;
+                                  return kind.type === "slide" && kind;
+                                })()}
+                              >
+                                {(kind) => (
+                                  <Subfield name="Direction">
+                                    <select
+                                      class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                                      value={kind().direction}
+                                      onChange={(e) =>
+                                        setProject(
+                                          "timeline",
+                                          "segments",
+                                          index(),
+                                          "transition",
+                                          "kind",
+                                          {
+                                            type: "slide",
+                                            direction: e.currentTarget
+                                              .value as SlideDirection,
+                                          }
+                                        )
+                                      }
+                                    >
+                                      <For
+                                        each={Object.entries(SLIDE_DIRECTIONS)}
+                                      >
+                                        {([value, label]) => (
+                                          <option value={value}>{label}</option>
+                                        )}
+                                      </For>
+                                    </select>
+                                  </Subfield>
+                                )}
+                              </Show>
+                            </>
+                          )}
+                        </Show>
+                      </div>
+                    );
+                  }}
+                </For>
+              </div>
+            </Field>
+          </Show>
           {/* <ComingSoonTooltip>
             <Field name="Inset" icon={<IconCapInset />}>
               <Slider
@@ -920,6 +1519,12 @@ export function ConfigSidebar() {
                   onChange={(mirror) => setProject("camera", "mirror", mirror)}
                 />
               </Subfield>
+              <Subfield name="Move Out of Auto Zooms">
+                <Toggle
+                  checked={project.camera.auto_dodge ?? false}
+                  onChange={(dodge) => setProject("camera", "auto_dodge", dodge)}
+                />
+              </Subfield>
               <div>
                 <Subfield name="Camera Position" class="mt-[0.75rem]" />
                 <KRadioGroup
@@ -1075,6 +1680,43 @@ export function ConfigSidebar() {
               </Collapsible>
             </div>
           </Field>
+          <Field name="Border" icon={<IconCapCorners />}>
+            <div class="flex flex-col gap-[0.75rem]">
+              <Subfield name="Show Border">
+                <Toggle
+                  checked={!!project.camera.border}
+                  onChange={(show) =>
+                    setProject(
+                      "camera",
+                      "border",
+                      show ? { width: 2, color: [255, 255, 255] } : null
+                    )
+                  }
+                />
+              </Subfield>
+              <Show when={project.camera.border}>
+                {(border) => (
+                  <>
+                    <Slider
+                      value={[border().width]}
+                      onChange={(v) =>
+                        setProject("camera", "border", "width", v[0])
+                      }
+                      minValue={0}
+                      maxValue={10}
+                      step={0.1}
+                    />
+                    <RgbInput
+                      value={border().color}
+                      onChange={(color) =>
+                        setProject("camera", "border", "color", color)
+                      }
+                    />
+                  </>
+                )}
+              </Show>
+            </div>
+          </Field>
           {/* <ComingSoonTooltip>
             <Field name="Shadow" icon={<IconCapShadow />}>
               <Slider
@@ -1111,13 +1753,321 @@ export function ConfigSidebar() {
                   onChange={(v) => setProject("audio", "mute", v)}
                 />
               </Subfield>
-              {/* <ComingSoonTooltip>
-                <Subfield name="Improve Mic Quality">
-                  <Toggle disabled />
-                </Subfield>
-              </ComingSoonTooltip> */}
+              <Subfield name="Reduce Mic Noise">
+                <Toggle
+                  checked={project.audio.improve}
+                  onChange={(v) => setProject("audio", "improve", v)}
+                  disabled={editorInstance.recordings.segments.every(
+                    (s) => s.audio === null
+                  )}
+                />
+              </Subfield>
+              <Subfield name="Normalize Loudness on Export">
+                <Toggle
+                  checked={
+                    project.audio.loudnessTarget !== null &&
+                    project.audio.loudnessTarget !== undefined
+                  }
+                  onChange={(v) =>
+                    setProject("audio", "loudnessTarget", v ? -14 : null)
+                  }
+                  disabled={project.audio.mute}
+                />
+              </Subfield>
+              <Show when={project.audio.loudnessTarget}>
+                {(target) => (
+                  <Subfield name="Target Loudness (LUFS)">
+                    <Slider
+                      value={[target()]}
+                      onChange={(v) =>
+                        setProject("audio", "loudnessTarget", v[0])
+                      }
+                      minValue={-24}
+                      maxValue={-9}
+                      step={1}
+                      disabled={project.audio.mute}
+                    />
+                  </Subfield>
+                )}
+              </Show>
             </div>
           </Field>
+          <For
+            each={[
+              {
+                key: "mic" as const,
+                name: "Microphone",
+                recorded: editorInstance.recordings.segments.some(
+                  (s) => s.audio !== null
+                ),
+              },
+              {
+                key: "system" as const,
+                name: "System Audio",
+                recorded: editorInstance.recordings.segments.some(
+                  (s) => s.system_audio !== null
+                ),
+              },
+            ].filter((track) => track.recorded)}
+          >
+            {(track) => {
+              const config = (): AudioTrackConfiguration =>
+                project.audio[track.key] ?? {};
+              const setConfig = (v: Partial<AudioTrackConfiguration>) =>
+                setProject("audio", track.key, { ...config(), ...v });
+
+              return (
+                <Field name={track.name} icon={<IconCapAudioOn />}>
+                  <div class="flex flex-col gap-3">
+                    <Subfield name="Mute">
+                      <Toggle
+                        checked={config().mute ?? false}
+                        onChange={(v) => setConfig({ mute: v })}
+                      />
+                    </Subfield>
+                    <Subfield name="Gain (dB)">
+                      <Slider
+                        value={[config().gain ?? 0]}
+                        onChange={(v) => setConfig({ gain: v[0] })}
+                        minValue={-30}
+                        maxValue={12}
+                        step={0.5}
+                        disabled={config().mute}
+                      />
+                    </Subfield>
+                    <Subfield name="Pan">
+                      <Slider
+                        value={[config().pan ?? 0]}
+                        onChange={(v) => setConfig({ pan: v[0] })}
+                        minValue={-1}
+                        maxValue={1}
+                        step={0.05}
+                        disabled={config().mute}
+                      />
+                    </Subfield>
+                  </div>
+                </Field>
+              );
+            }}
+          </For>
+          <Show
+            when={editorInstance.recordings.segments.some(
+              (s) => s.audio !== null || s.system_audio !== null
+            )}
+          >
+            {(_) => {
+              const [silenceCuts, setSilenceCuts] = createSignal<
+                SilenceCut[] | null
+              >(null);
+              const [findingSilences, setFindingSilences] = createSignal(false);
+
+              const silenceDuration = () =>
+                (silenceCuts() ?? []).reduce(
+                  (acc, cut) => acc + cut.end - cut.start,
+                  0
+                );
+
+              // each cut lies within one timeline segment, which is split around it
+              const applySilenceCuts = (cuts: SilenceCut[]) => {
+                if (!project.timeline) return;
+
+                setProject(
+                  "timeline",
+                  "segments",
+                  produce((segments) => {
+                    for (const cut of cuts) {
+                      const i = segments.findIndex(
+                        (s) =>
+                          (s.recordingSegment ?? 0) === cut.recordingSegment &&
+                          s.start <= cut.start &&
+                          cut.end <= s.end
+                      );
+                      if (i === -1) continue;
+
+                      const segment = segments[i];
+                      segments.splice(
+                        i,
+                        1,
+                        ...[
+                          { ...segment, end: cut.start },
+                          { ...segment, start: cut.end },
+                        ].filter((s) => s.end > s.start)
+                      );
+                    }
+                  })
+                );
+                setSilenceCuts(null);
+              };
+
+              return (
+                <Field name="Silences" icon={<IconCapAudioOn />}>
+                  <div class="flex flex-col gap-3">
+                    <Show
+                      when={silenceCuts()}
+                      fallback={
+                        <EditorButton
+                          disabled={findingSilences()}
+                          onClick={async () => {
+                            setFindingSilences(true);
+                            try {
+                              setSilenceCuts(
+                                await commands.generateSilenceCuts()
+                              );
+                            } finally {
+                              setFindingSilences(false);
+                            }
+                          }}
+                        >
+                          {findingSilences()
+                            ? "Finding Silences..."
+                            : "Find Silences"}
+                        </EditorButton>
+                      }
+                    >
+                      {(cuts) => (
+                        <>
+                          <span class="text-sm text-gray-400">
+                            {cuts().length === 0
+                              ? "No long silences found"
+                              : `${cuts().length} silences, ${formatTime(
+                                  silenceDuration()
+                                )} in total`}
+                          </span>
+                          <div class="flex flex-row gap-2">
+                            <Show when={cuts().length > 0}>
+                              <EditorButton
+                                onClick={() => applySilenceCuts(cuts())}
+                              >
+                                Cut Silences
+                              </EditorButton>
+                            </Show>
+                            <EditorButton onClick={() => setSilenceCuts(null)}>
+                              Dismiss
+                            </EditorButton>
+                          </div>
+                        </>
+                      )}
+                    </Show>
+                  </div>
+                </Field>
+              );
+            }}
+          </Show>
+          <Show when={project.timeline}>
+            {(_) => {
+              const [idlePeriods, setIdlePeriods] = createSignal<
+                IdlePeriod[] | null
+              >(null);
+              const [findingIdle, setFindingIdle] = createSignal(false);
+
+              const idleDuration = () =>
+                (idlePeriods() ?? []).reduce(
+                  (acc, period) => acc + period.end - period.start,
+                  0
+                );
+
+              // same as cutting silences, each period lies within one segment
+              const cutIdlePeriods = (periods: IdlePeriod[]) => {
+                setProject(
+                  "timeline",
+                  "segments",
+                  produce((segments) => {
+                    for (const period of periods) {
+                      const i = segments.findIndex(
+                        (s) =>
+                          (s.recordingSegment ?? 0) ===
+                            period.recordingSegment &&
+                          s.start <= period.start &&
+                          period.end <= s.end
+                      );
+                      if (i === -1) continue;
+
+                      const segment = segments[i];
+                      segments.splice(
+                        i,
+                        1,
+                        ...[
+                          { ...segment, end: period.start },
+                          { ...segment, start: period.end },
+                        ].filter((s) => s.end > s.start)
+                      );
+                    }
+                  })
+                );
+                setIdlePeriods(null);
+              };
+
+              const speedUpIdlePeriods = (periods: IdlePeriod[]) => {
+                setProject("timeline", "speedSegments", (segments) => [
+                  ...(segments ?? []),
+                  ...periods.map((period) => ({
+                    start: period.timelineStart,
+                    end: period.timelineEnd,
+                    factor: IDLE_SPEED_FACTOR,
+                  })),
+                ]);
+                setIdlePeriods(null);
+              };
+
+              return (
+                <Field name="Idle Time" icon={<IconCapCursor />}>
+                  <div class="flex flex-col gap-3">
+                    <Show
+                      when={idlePeriods()}
+                      fallback={
+                        <EditorButton
+                          disabled={findingIdle()}
+                          onClick={async () => {
+                            setFindingIdle(true);
+                            try {
+                              setIdlePeriods(
+                                await commands.generateIdlePeriods()
+                              );
+                            } finally {
+                              setFindingIdle(false);
+                            }
+                          }}
+                        >
+                          {findingIdle()
+                            ? "Finding Idle Time..."
+                            : "Find Idle Time"}
+                        </EditorButton>
+                      }
+                    >
+                      {(periods) => (
+                        <>
+                          <span class="text-sm text-gray-400">
+                            {periods().length === 0
+                              ? "No idle time found"
+                              : `${periods().length} idle periods, ${formatTime(
+                                  idleDuration()
+                                )} in total`}
+                          </span>
+                          <div class="flex flex-row gap-2">
+                            <Show when={periods().length > 0}>
+                              <EditorButton
+                                onClick={() => speedUpIdlePeriods(periods())}
+                              >
+                                Speed Up {IDLE_SPEED_FACTOR}x
+                              </EditorButton>
+                              <EditorButton
+                                onClick={() => cutIdlePeriods(periods())}
+                              >
+                                Cut
+                              </EditorButton>
+                            </Show>
+                            <EditorButton onClick={() => setIdlePeriods(null)}>
+                              Dismiss
+                            </EditorButton>
+                          </div>
+                        </>
+                      )}
+                    </Show>
+                  </div>
+                </Field>
+              );
+            }}
+          </Show>
         </KTabs.Content>
         <KTabs.Content value="cursor" class="flex flex-col gap-6">
           {window.FLAGS.recordMouseState === true ? (
@@ -1130,6 +2080,34 @@ export function ConfigSidebar() {
                   />
                 </Subfield>
               </Field>
+              <Field name="Style" icon={<IconCapCursor />}>
+                <KTabs
+                  value={project.cursor.theme ?? "recorded"}
+                  onChange={(v) =>
+                    setProject("cursor", "theme", v as CursorTheme)
+                  }
+                >
+                  <KTabs.List class="flex flex-row items-center rounded-[0.5rem] relative border">
+                    <For
+                      each={
+                        Object.entries(CURSOR_THEMES) as [CursorTheme, string][]
+                      }
+                    >
+                      {([value, label]) => (
+                        <KTabs.Trigger
+                          class="flex-1 text-gray-400 py-1 z-10 ui-selected:text-gray-500 peer outline-none transition-colors duration-100"
+                          value={value}
+                        >
+                          {label}
+                        </KTabs.Trigger>
+                      )}
+                    </For>
+                    <KTabs.Indicator class="absolute flex p-px inset-0 transition-transform peer-focus-visible:outline outline-2 outline-blue-300 outline-offset-2 rounded-[0.6rem] overflow-hidden">
+                      <div class="bg-gray-100 flex-1" />
+                    </KTabs.Indicator>
+                  </KTabs.List>
+                </KTabs>
+              </Field>
               <Field name="Size" icon={<IconCapEnlarge />}>
                 <Slider
                   value={[project.cursor.size]}
@@ -1139,6 +2117,156 @@ export function ConfigSidebar() {
                   step={1}
                 />
               </Field>
+              <Field name="Click Highlight" icon={<IconCapCursor />}>
+                <div class="flex flex-col gap-[0.75rem]">
+                  <Subfield name="Show Clicks">
+                    <Toggle
+                      checked={!!project.cursor.clickHighlight}
+                      onChange={(show) =>
+                        setProject(
+                          "cursor",
+                          "clickHighlight",
+                          show
+                            ? { style: "ripple", radius: 40, duration: 0.5 }
+                            : null
+                        )
+                      }
+                    />
+                  </Subfield>
+                  <Show when={project.cursor.clickHighlight}>
+                    {(highlight) => (
+                      <>
+                        <Subfield name="Filled">
+                          <Toggle
+                            checked={highlight().style === "highlight"}
+                            onChange={(filled) =>
+                              setProject(
+                                "cursor",
+                                "clickHighlight",
+                                "style",
+                                filled ? "highlight" : "ripple"
+                              )
+                            }
+                          />
+                        </Subfield>
+                        <Subfield name="Radius">
+                          <Slider
+                            value={[highlight().radius]}
+                            onChange={(v) =>
+                              setProject(
+                                "cursor",
+                                "clickHighlight",
+                                "radius",
+                                v[0]
+                              )
+                            }
+                            minValue={10}
+                            maxValue={120}
+                            step={1}
+                          />
+                        </Subfield>
+                        <Subfield name="Duration">
+                          <Slider
+                            value={[highlight().duration]}
+                            onChange={(v) =>
+                              setProject(
+                                "cursor",
+                                "clickHighlight",
+                                "duration",
+                                v[0]
+                              )
+                            }
+                            minValue={0.1}
+                            maxValue={2}
+                            step={0.05}
+                          />
+                        </Subfield>
+                        <RgbInput
+                          value={
+                            project.overlays?.clickHighlightColor ??
+                            [0, 114, 178]
+                          }
+                          onChange={(color) =>
+                            setProject("overlays", {
+                              ...project.overlays,
+                              clickHighlightColor: color,
+                            })
+                          }
+                        />
+                      </>
+                    )}
+                  </Show>
+                </div>
+              </Field>
+              <Field name="Trail" icon={<IconCapCursor />}>
+                <div class="flex flex-col gap-[0.75rem]">
+                  <Subfield name="Show Trail">
+                    <Toggle
+                      checked={!!project.cursor.trail}
+                      onChange={(show) =>
+                        setProject(
+                          "cursor",
+                          "trail",
+                          show
+                            ? {
+                                length: 10,
+                                spacing: 0.02,
+                                width: 12,
+                                opacity: 0.5,
+                                color: [255, 255, 255],
+                              }
+                            : null
+                        )
+                      }
+                    />
+                  </Subfield>
+                  <Show when={project.cursor.trail}>
+                    {(trail) => (
+                      <>
+                        <Subfield name="Length">
+                          <Slider
+                            value={[trail().length]}
+                            onChange={(v) =>
+                              setProject("cursor", "trail", "length", v[0])
+                            }
+                            minValue={2}
+                            maxValue={16}
+                            step={1}
+                          />
+                        </Subfield>
+                        <Subfield name="Width">
+                          <Slider
+                            value={[trail().width]}
+                            onChange={(v) =>
+                              setProject("cursor", "trail", "width", v[0])
+                            }
+                            minValue={2}
+                            maxValue={40}
+                            step={1}
+                          />
+                        </Subfield>
+                        <Subfield name="Opacity">
+                          <Slider
+                            value={[trail().opacity]}
+                            onChange={(v) =>
+                              setProject("cursor", "trail", "opacity", v[0])
+                            }
+                            minValue={0}
+                            maxValue={1}
+                            step={0.01}
+                          />
+                        </Subfield>
+                        <RgbInput
+                          value={trail().color}
+                          onChange={(color) =>
+                            setProject("cursor", "trail", "color", color)
+                          }
+                        />
+                      </>
+                    )}
+                  </Show>
+                </div>
+              </Field>
               <KCollapsible open={!project.cursor.raw}>
                 <Field
                   name="Smooth Movement"
@@ -1250,13 +2378,910 @@ export function ConfigSidebar() {
         </KTabs.Content>
         <KTabs.Content value="hotkeys">
           <Field name="Hotkeys" icon={<IconCapHotkeys />}>
-            <ComingSoonTooltip>
+            <div class="flex flex-col gap-[0.75rem]">
               <Subfield name="Show hotkeys">
-                <Toggle disabled />
+                <Toggle
+                  checked={project.hotkeys.show}
+                  onChange={(show) => setProject("hotkeys", "show", show)}
+                />
+              </Subfield>
+              <Subfield name="Include typing">
+                <Toggle
+                  checked={project.hotkeys.filter === "allKeys"}
+                  onChange={(all) =>
+                    setProject(
+                      "hotkeys",
+                      "filter",
+                      all ? "allKeys" : "shortcutsOnly"
+                    )
+                  }
+                />
+              </Subfield>
+              <Subfield name="Duration">
+                <Slider
+                  value={[project.hotkeys.duration ?? 1.5]}
+                  onChange={(v) => setProject("hotkeys", "duration", v[0])}
+                  minValue={0.5}
+                  maxValue={5}
+                  step={0.1}
+                />
+              </Subfield>
+              <Subfield name="Size">
+                <Slider
+                  value={[project.hotkeys.size ?? 4]}
+                  onChange={(v) => setProject("hotkeys", "size", v[0])}
+                  minValue={2}
+                  maxValue={10}
+                  step={0.5}
+                />
               </Subfield>
-            </ComingSoonTooltip>
+            </div>
+          </Field>
+          <Field name="Position" icon={<IconCapHotkeys />}>
+            <KTabs
+              value={project.hotkeys.position ?? "bottomCenter"}
+              onChange={(v) =>
+                setProject("hotkeys", "position", v as HotkeysPosition)
+              }
+            >
+              <KTabs.List class="flex flex-row items-center rounded-[0.5rem] relative border">
+                <For
+                  each={
+                    Object.entries(HOTKEYS_POSITIONS) as [
+                      HotkeysPosition,
+                      string
+                    ][]
+                  }
+                >
+                  {([value, label]) => (
+                    <KTabs.Trigger
+                      class="flex-1 text-gray-400 py-1 z-10 ui-selected:text-gray-500 peer outline-none transition-colors duration-100"
+                      value={value}
+                    >
+                      {label}
+                    </KTabs.Trigger>
+                  )}
+                </For>
+                <KTabs.Indicator class="absolute flex p-px inset-0 transition-transform peer-focus-visible:outline outline-2 outline-blue-300 outline-offset-2 rounded-[0.6rem] overflow-hidden">
+                  <div class="bg-gray-100 flex-1" />
+                </KTabs.Indicator>
+              </KTabs.List>
+            </KTabs>
           </Field>
         </KTabs.Content>
+        <KTabs.Content value="text" class="flex flex-col gap-6">
+          <Field name="Text" icon={<IconCapMessageBubble />}>
+            <div class="flex flex-col gap-3">
+              <span class="text-sm text-gray-400">
+                Titles and callouts shown over the recording
+              </span>
+              <EditorButton
+                onClick={() => {
+                  const start = playbackTime();
+                  setProject("timeline", "textSegments", (segments) => [
+                    ...(segments ?? []),
+                    {
+                      start,
+                      end: start + DEFAULT_TEXT_DURATION,
+                      content: "Title",
+                      font: "sans",
+                      size: 6,
+                      color: [255, 255, 255],
+                      position: { x: 0.06, y: 0.78 },
+                      animation: "fade",
+                    },
+                  ]);
+                }}
+              >
+                Add Text at {formatTime(playbackTime())}
+              </EditorButton>
+            </div>
+          </Field>
+          <For each={project.timeline?.textSegments ?? []}>
+            {(segment, i) => (
+              <Field
+                name={`${formatTime(segment.start)} - ${formatTime(
+                  segment.end
+                )}`}
+                icon={<IconCapMessageBubble />}
+              >
+                <div class="flex flex-col gap-[0.75rem]">
+                  <textarea
+                    class="w-full p-[0.375rem] border rounded-[0.5rem] bg-gray-50 resize-none"
+                    rows={2}
+                    value={segment.content}
+                    onKeyDown={(e) => e.stopPropagation()}
+                    onInput={(e) =>
+                      setProject(
+                        "timeline",
+                        "textSegments",
+                        i(),
+                        "content",
+                        e.currentTarget.value
+                      )
+                    }
+                  />
+                  <Subfield name="Font">
+                    <select
+                      class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                      value={segment.font ?? "sans"}
+                      onChange={(e) =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "font",
+                          e.currentTarget.value as TextFont
+                        )
+                      }
+                    >
+                      <For each={Object.entries(TEXT_FONTS)}>
+                        {([value, label]) => (
+                          <option value={value}>{label}</option>
+                        )}
+                      </For>
+                    </select>
+                  </Subfield>
+                  <Subfield name="Animation">
+                    <select
+                      class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                      value={segment.animation ?? "fade"}
+                      onChange={(e) =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "animation",
+                          e.currentTarget.value as TextAnimation
+                        )
+                      }
+                    >
+                      <For each={Object.entries(TEXT_ANIMATIONS)}>
+                        {([value, label]) => (
+                          <option value={value}>{label}</option>
+                        )}
+                      </For>
+                    </select>
+                  </Subfield>
+                  <Subfield name="Size">
+                    <Slider
+                      value={[segment.size]}
+                      onChange={(v) =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "size",
+                          v[0]
+                        )
+                      }
+                      minValue={2}
+                      maxValue={20}
+                      step={0.5}
+                    />
+                  </Subfield>
+                  <Subfield name="Horizontal position">
+                    <Slider
+                      value={[segment.position.x * 100]}
+                      onChange={(v) =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "position",
+                          "x",
+                          v[0] / 100
+                        )
+                      }
+                      minValue={0}
+                      maxValue={100}
+                      step={1}
+                    />
+                  </Subfield>
+                  <Subfield name="Vertical position">
+                    <Slider
+                      value={[segment.position.y * 100]}
+                      onChange={(v) =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "position",
+                          "y",
+                          v[0] / 100
+                        )
+                      }
+                      minValue={0}
+                      maxValue={100}
+                      step={1}
+                    />
+                  </Subfield>
+                  <RgbInput
+                    value={segment.color}
+                    onChange={(color) =>
+                      setProject(
+                        "timeline",
+                        "textSegments",
+                        i(),
+                        "color",
+                        color
+                      )
+                    }
+                  />
+                  <div class="flex flex-row gap-2">
+                    <EditorButton
+                      onClick={() =>
+                        setProject(
+                          "timeline",
+                          "textSegments",
+                          i(),
+                          "end",
+                          Math.max(playbackTime(), segment.start + 0.1)
+                        )
+                      }
+                    >
+                      End at Playhead
+                    </EditorButton>
+                    <EditorButton
+                      leftIcon={<IconCapTrash />}
+                      onClick={() =>
+                        setProject("timeline", "textSegments", (segments) =>
+                          (segments ?? []).filter((_, j) => j !== i())
+                        )
+                      }
+                    >
+                      Delete
+                    </EditorButton>
+                  </div>
+                </div>
+              </Field>
+            )}
+          </For>
+          <For each={TITLE_CARDS}>
+            {({ key, name }) => (
+              <Field name={name} icon={<IconCapMessageBubble />}>
+                <Show
+                  when={project[key]}
+                  fallback={
+                    <EditorButton
+                      onClick={() =>
+                        setProject(key, {
+                          duration: 3,
+                          title:
+                            key === "intro"
+                              ? prettyName()
+                              : "Thanks for watching",
+                          subtitle: "",
+                          font: "sans",
+                          background: [0, 0, 0],
+                          textColor: [255, 255, 255],
+                          fade: true,
+                        })
+                      }
+                    >
+                      Add {name}
+                    </EditorButton>
+                  }
+                >
+                  {(card) => (
+                    <div class="flex flex-col gap-[0.75rem]">
+                      <input
+                        class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                        placeholder="Title"
+                        value={card().title}
+                        onKeyDown={(e) => e.stopPropagation()}
+                        onInput={(e) =>
+                          setProject(key, "title", e.currentTarget.value)
+                        }
+                      />
+                      <input
+                        class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                        placeholder="Subtitle"
+                        value={card().subtitle ?? ""}
+                        onKeyDown={(e) => e.stopPropagation()}
+                        onInput={(e) =>
+                          setProject(key, "subtitle", e.currentTarget.value)
+                        }
+                      />
+                      <Subfield name="Font">
+                        <select
+                          class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                          value={card().font ?? "sans"}
+                          onChange={(e) =>
+                            setProject(
+                              key,
+                              "font",
+                              e.currentTarget.value as TextFont
+                            )
+                          }
+                        >
+                          <For each={Object.entries(TEXT_FONTS)}>
+                            {([value, label]) => (
+                              <option value={value}>{label}</option>
+                            )}
+                          </For>
+                        </select>
+                      </Subfield>
+                      <Subfield name="Duration">
+                        <Slider
+                          value={[card().duration]}
+                          onChange={(v) => setProject(key, "duration", v[0])}
+                          minValue={1}
+                          maxValue={10}
+                          step={0.5}
+                        />
+                      </Subfield>
+                      <Subfield name="Fade From and To Black">
+                        <Toggle
+                          checked={card().fade ?? false}
+                          onChange={(v) => setProject(key, "fade", v)}
+                        />
+                      </Subfield>
+                      <Subfield name="Background" />
+                      <RgbInput
+                        value={card().background}
+                        onChange={(color) =>
+                          setProject(key, "background", color)
+                        }
+                      />
+                      <Subfield name="Text" />
+                      <RgbInput
+                        value={card().textColor}
+                        onChange={(color) =>
+                          setProject(key, "textColor", color)
+                        }
+                      />
+                      <EditorButton
+                        leftIcon={<IconCapTrash />}
+                        onClick={() => setProject(key, null)}
+                      >
+                        Remove
+                      </EditorButton>
+                    </div>
+                  )}
+                </Show>
+              </Field>
+            )}
+          </For>
+          {(() => {
+            const captions = () => project.captions ?? DEFAULT_CAPTIONS;
+            const setCaptions = (update: Partial<CaptionsConfiguration>) =>
+              setProject("captions", { ...captions(), ...update });
+            // how much of the recording's been transcribed, while captions are generated
+            const [transcribed, setTranscribed] = createSignal<number | null>(
+              null
+            );
+
+            return (
+              <Field name="Captions" icon={<IconCapMessageBubble />}>
+                <div class="flex flex-col gap-[0.75rem]">
+                  <span class="text-sm text-gray-400">
+                    Import or export SRT and WebVTT files, and burn captions
+                    into the video. Generating them needs whisper.cpp installed.
+                  </span>
+                  <div class="flex flex-row flex-wrap gap-2">
+                    <EditorButton
+                      disabled={transcribed() !== null}
+                      onClick={async () => {
+                        const progress = new Channel<TranscriptionProgress>();
+                        progress.onmessage = (p) =>
+                          setTranscribed((p.segment + p.fraction) / p.segments);
+
+                        setTranscribed(0);
+                        try {
+                          const cues = await commands.generateCaptions(progress);
+                          setProject("timeline", "captionSegments", cues);
+                        } catch (e) {
+                          toast.error(`${e}`);
+                        } finally {
+                          setTranscribed(null);
+                        }
+                      }}
+                    >
+                      {transcribed() === null
+                        ? "Generate"
+                        : `Transcribing ${Math.round(transcribed()! * 100)}%`}
+                    </EditorButton>
+                    <EditorButton
+                      onClick={async () => {
+                        const path = await open({
+                          filters: [
+                            {
+                              name: "Captions",
+                              extensions: CAPTION_FILE_EXTENSIONS,
+                            },
+                          ],
+                        });
+                        if (typeof path !== "string") return;
+
+                        try {
+                          const cues = await commands.importCaptions(path);
+                          setProject("timeline", "captionSegments", cues);
+                        } catch (e) {
+                          toast.error(`Failed to import captions: ${e}`);
+                        }
+                      }}
+                    >
+                      Import
+                    </EditorButton>
+                    <EditorButton
+                      disabled={!project.timeline?.captionSegments?.length}
+                      onClick={async () => {
+                        const path = await save({
+                          filters: [
+                            {
+                              name: "Captions",
+                              extensions: CAPTION_FILE_EXTENSIONS,
+                            },
+                          ],
+                          defaultPath: `~/Desktop/${prettyName()}.srt`,
+                        });
+                        if (!path) return;
+
+                        try {
+                          await commands.exportCaptions(
+                            path,
+                            project.timeline?.captionSegments ?? []
+                          );
+                        } catch (e) {
+                          toast.error(`Failed to export captions: ${e}`);
+                        }
+                      }}
+                    >
+                      Export
+                    </EditorButton>
+                    <EditorButton
+                      onClick={() => {
+                        const start = playbackTime();
+                        setProject("timeline", "captionSegments", (cues) =>
+                          [
+                            ...(cues ?? []),
+                            {
+                              start,
+                              end: start + DEFAULT_TEXT_DURATION,
+                              text: "Caption",
+                            },
+                          ].sort((a, b) => a.start - b.start)
+                        );
+                      }}
+                    >
+                      Add at {formatTime(playbackTime())}
+                    </EditorButton>
+                  </div>
+                  <Subfield name="Burn In">
+                    <Toggle
+                      checked={captions().burnIn ?? false}
+                      onChange={(burnIn) => setCaptions({ burnIn })}
+                    />
+                  </Subfield>
+                  <Show when={captions().burnIn}>
+                    <Subfield name="Font">
+                      <select
+                        class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                        value={captions().font ?? "sans"}
+                        onChange={(e) =>
+                          setCaptions({
+                            font: e.currentTarget.value as TextFont,
+                          })
+                        }
+                      >
+                        <For each={Object.entries(TEXT_FONTS)}>
+                          {([value, label]) => (
+                            <option value={value}>{label}</option>
+                          )}
+                        </For>
+                      </select>
+                    </Subfield>
+                    <Subfield name="Size">
+                      <Slider
+                        value={[captions().size]}
+                        onChange={(v) => setCaptions({ size: v[0] })}
+                        minValue={2}
+                        maxValue={10}
+                        step={0.5}
+                      />
+                    </Subfield>
+                    <Subfield name="Text" />
+                    <RgbInput
+                      value={captions().color}
+                      onChange={(color) => setCaptions({ color })}
+                    />
+                    <Subfield name="Background" />
+                    <RgbInput
+                      value={captions().background}
+                      onChange={(background) => setCaptions({ background })}
+                    />
+                    <Subfield name="Background Opacity">
+                      <Slider
+                        value={[captions().backgroundOpacity * 100]}
+                        onChange={(v) =>
+                          setCaptions({ backgroundOpacity: v[0] / 100 })
+                        }
+                        minValue={0}
+                        maxValue={100}
+                      />
+                    </Subfield>
+                  </Show>
+                  <For each={project.timeline?.captionSegments ?? []}>
+                    {(cue, i) => (
+                      <div class="flex flex-col gap-1">
+                        <div class="flex flex-row items-center justify-between">
+                          <span class="text-xs text-gray-400">
+                            {formatTime(cue.start)} - {formatTime(cue.end)}
+                          </span>
+                          <button
+                            type="button"
+                            class="text-gray-400 hover:text-gray-500"
+                            onClick={() =>
+                              setProject(
+                                "timeline",
+                                "captionSegments",
+                                (cues) =>
+                                  (cues ?? []).filter((_, j) => j !== i())
+                              )
+                            }
+                          >
+                            <IconCapTrash class="size-3.5" />
+                          </button>
+                        </div>
+                        <textarea
+                          class="w-full p-[0.375rem] border rounded-[0.5rem] bg-gray-50 resize-none"
+                          rows={2}
+                          value={cue.text}
+                          onKeyDown={(e) => e.stopPropagation()}
+                          onInput={(e) =>
+                            setProject(
+                              "timeline",
+                              "captionSegments",
+                              i(),
+                              "text",
+                              e.currentTarget.value
+                            )
+                          }
+                        />
+                      </div>
+                    )}
+                  </For>
+                </div>
+              </Field>
+            );
+          })()}
+        </KTabs.Content>
+        <KTabs.Content value="annotations" class="flex flex-col gap-6">
+          <Field name="Annotations" icon={<IconCapSquare />}>
+            <div class="flex flex-col gap-3">
+              <span class="text-sm text-gray-400">
+                Point things out, or blur and pixelate what shouldn't be seen
+              </span>
+              <div class="flex flex-row flex-wrap gap-2">
+                <For each={Object.entries(ANNOTATION_KINDS)}>
+                  {([kind, label]) => (
+                    <EditorButton
+                      onClick={() => {
+                        const start = playbackTime();
+                        const arrow = kind === "arrow";
+                        setProject(
+                          "timeline",
+                          "annotationSegments",
+                          (segments) => [
+                            ...(segments ?? []),
+                            {
+                              start,
+                              end: start + DEFAULT_TEXT_DURATION,
+                              kind: kind as AnnotationKind,
+                              from: arrow
+                                ? { x: 0.35, y: 0.35 }
+                                : { x: 0.4, y: 0.4 },
+                              to: arrow
+                                ? { x: 0.5, y: 0.5 }
+                                : { x: 0.6, y: 0.55 },
+                              color: null,
+                              size: DEFAULT_ANNOTATION_SIZES[
+                                kind as AnnotationKind
+                              ],
+                              animation: "fade",
+                            },
+                          ]
+                        );
+                      }}
+                    >
+                      {label}
+                    </EditorButton>
+                  )}
+                </For>
+              </div>
+            </div>
+          </Field>
+          {(() => {
+            const [redactions, setRedactions] = createSignal<
+              RedactionSuggestion[] | null
+            >(null);
+            const [findingRedactions, setFindingRedactions] =
+              createSignal(false);
+
+            const blur = (suggestions: RedactionSuggestion[]) => {
+              setProject("timeline", "annotationSegments", (segments) => [
+                ...(segments ?? []),
+                ...suggestions.map((s) => s.annotation),
+              ]);
+              setRedactions((current) =>
+                (current ?? []).filter((s) => !suggestions.includes(s))
+              );
+            };
+
+            return (
+              <Field name="Sensitive Text" icon={<IconCapBlur />}>
+                <div class="flex flex-col gap-3">
+                  <Show
+                    when={redactions()}
+                    fallback={
+                      <>
+                        <span class="text-sm text-gray-400">
+                          Finds emails, API keys and card numbers on screen.
+                          Needs Tesseract OCR installed.
+                        </span>
+                        <EditorButton
+                          disabled={findingRedactions()}
+                          onClick={async () => {
+                            setFindingRedactions(true);
+                            try {
+                              setRedactions(await commands.generateRedactions());
+                            } catch (e) {
+                              toast.error(`${e}`);
+                            } finally {
+                              setFindingRedactions(false);
+                            }
+                          }}
+                        >
+                          {findingRedactions()
+                            ? "Finding Sensitive Text..."
+                            : "Find Sensitive Text"}
+                        </EditorButton>
+                      </>
+                    }
+                  >
+                    {(suggestions) => (
+                      <>
+                        <span class="text-sm text-gray-400">
+                          {suggestions().length === 0
+                            ? "No sensitive text found"
+                            : `${suggestions().length} found`}
+                        </span>
+                        <For each={suggestions()}>
+                          {(suggestion) => (
+                            <div class="flex flex-row items-center gap-2">
+                              <div class="flex flex-col flex-1 min-w-0">
+                                <span class="truncate">
+                                  {suggestion.preview}
+                                </span>
+                                <span class="text-xs text-gray-400">
+                                  {SENSITIVE_KINDS[suggestion.kind]},{" "}
+                                  {formatTime(suggestion.annotation.start)} -{" "}
+                                  {formatTime(suggestion.annotation.end)}
+                                </span>
+                              </div>
+                              <EditorButton onClick={() => blur([suggestion])}>
+                                Blur
+                              </EditorButton>
+                            </div>
+                          )}
+                        </For>
+                        <div class="flex flex-row gap-2">
+                          <Show when={suggestions().length > 0}>
+                            <EditorButton onClick={() => blur(suggestions())}>
+                              Blur All
+                            </EditorButton>
+                          </Show>
+                          <EditorButton onClick={() => setRedactions(null)}>
+                            Dismiss
+                          </EditorButton>
+                        </div>
+                      </>
+                    )}
+                  </Show>
+                </div>
+              </Field>
+            );
+          })()}
+          <For each={project.timeline?.annotationSegments ?? []}>
+            {(segment, i) => {
+              const pointLabels = () =>
+                segment.kind === "arrow"
+                  ? ["Tail X", "Tail Y", "Head X", "Head Y"]
+                  : ["Left", "Top", "Right", "Bottom"];
+
+              return (
+                <Field
+                  name={`${ANNOTATION_KINDS[segment.kind]} ${formatTime(
+                    segment.start
+                  )} - ${formatTime(segment.end)}`}
+                  icon={<IconCapSquare />}
+                >
+                  <div class="flex flex-col gap-[0.75rem]">
+                    <Subfield name="Kind">
+                      <select
+                        class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                        value={segment.kind}
+                        onChange={(e) => {
+                          const kind = e.currentTarget.value as AnnotationKind;
+                          batch(() => {
+                            setProject(
+                              "timeline",
+                              "annotationSegments",
+                              i(),
+                              "kind",
+                              kind
+                            );
+                            setProject(
+                              "timeline",
+                              "annotationSegments",
+                              i(),
+                              "size",
+                              DEFAULT_ANNOTATION_SIZES[kind]
+                            );
+                          });
+                        }}
+                      >
+                        <For each={Object.entries(ANNOTATION_KINDS)}>
+                          {([value, label]) => (
+                            <option value={value}>{label}</option>
+                          )}
+                        </For>
+                      </select>
+                    </Subfield>
+                    <Show when={!isMask(segment.kind)}>
+                      <Subfield name="Animation">
+                        <select
+                          class="p-[0.375rem] border rounded-[0.5rem] bg-gray-50"
+                          value={segment.animation ?? "fade"}
+                          onChange={(e) =>
+                            setProject(
+                              "timeline",
+                              "annotationSegments",
+                              i(),
+                              "animation",
+                              e.currentTarget.value as AnnotationAnimation
+                            )
+                          }
+                        >
+                          <For each={Object.entries(ANNOTATION_ANIMATIONS)}>
+                            {([value, label]) => (
+                              <option value={value}>{label}</option>
+                            )}
+                          </For>
+                        </select>
+                      </Subfield>
+                    </Show>
+                    <Subfield
+                      name={isMask(segment.kind) ? "Strength" : "Thickness"}
+                    >
+                      <Slider
+                        value={[segment.size]}
+                        onChange={(v) =>
+                          setProject(
+                            "timeline",
+                            "annotationSegments",
+                            i(),
+                            "size",
+                            v[0]
+                          )
+                        }
+                        minValue={0.1}
+                        maxValue={isMask(segment.kind) ? 8 : 3}
+                        step={0.1}
+                      />
+                    </Subfield>
+                    <For
+                      each={
+                        [
+                          ["from", "x"],
+                          ["from", "y"],
+                          ["to", "x"],
+                          ["to", "y"],
+                        ] as const
+                      }
+                    >
+                      {([point, axis], j) => (
+                        <Subfield name={pointLabels()[j()]}>
+                          <Slider
+                            value={[segment[point][axis] * 100]}
+                            onChange={(v) =>
+                              setProject(
+                                "timeline",
+                                "annotationSegments",
+                                i(),
+                                point,
+                                axis,
+                                v[0] / 100
+                              )
+                            }
+                            minValue={0}
+                            maxValue={100}
+                            step={0.5}
+                          />
+                        </Subfield>
+                      )}
+                    </For>
+                    <Subfield name="Follow Recording">
+                      <Toggle
+                        checked={segment.anchor === "display"}
+                        onChange={(follow) =>
+                          setProject(
+                            "timeline",
+                            "annotationSegments",
+                            i(),
+                            "anchor",
+                            follow ? "display" : "frame"
+                          )
+                        }
+                      />
+                    </Subfield>
+                    <Show when={!isMask(segment.kind)}>
+                      <Subfield name="Custom Color">
+                        <Toggle
+                          checked={!!segment.color}
+                          onChange={(custom) =>
+                            setProject(
+                              "timeline",
+                              "annotationSegments",
+                              i(),
+                              "color",
+                              custom ? [255, 255, 255] : null
+                            )
+                          }
+                        />
+                      </Subfield>
+                      <Show when={segment.color}>
+                        {(color) => (
+                          <RgbInput
+                            value={color()}
+                            onChange={(color) =>
+                              setProject(
+                                "timeline",
+                                "annotationSegments",
+                                i(),
+                                "color",
+                                color
+                              )
+                            }
+                          />
+                        )}
+                      </Show>
+                    </Show>
+                    <div class="flex flex-row gap-2">
+                      <EditorButton
+                        onClick={() =>
+                          setProject(
+                            "timeline",
+                            "annotationSegments",
+                            i(),
+                            "end",
+                            Math.max(playbackTime(), segment.start + 0.1)
+                          )
+                        }
+                      >
+                        End at Playhead
+                      </EditorButton>
+                      <EditorButton
+                        leftIcon={<IconCapTrash />}
+                        onClick={() =>
+                          setProject(
+                            "timeline",
+                            "annotationSegments",
+                            (segments) =>
+                              (segments ?? []).filter((_, j) => j !== i())
+                          )
+                        }
+                      >
+                        Delete
+                      </EditorButton>
+                    </div>
+                  </div>
+                </Field>
+              );
+            }}
+          </For>
+        </KTabs.Content>
       </div>
       <Show
         when={(() => {
@@ -1358,6 +3383,12 @@ export function ConfigSidebar() {
                     >
                       Manual
                     </KTabs.Trigger>
+                    <KTabs.Trigger
+                      value="pan"
+                      class="flex-1 text-gray-400 py-1 z-10 ui-selected:text-gray-500 peer outline-none transition-colors duration-100"
+                    >
+                      Pan
+                    </KTabs.Trigger>
                     <KTabs.Indicator class="absolute flex p-px inset-0 transition-transform peer-focus-visible:outline outline-2 outline-blue-300 outline-offset-2 rounded-[0.6rem] overflow-hidden">
                       <div class="bg-gray-100 flex-1" />
                     </KTabs.Indicator>
@@ -1366,7 +3397,7 @@ export function ConfigSidebar() {
                     <Show
                       when={(() => {
                         const m = value().segment.mode;
-                        if (m === "auto") return;
+                        if (m === "auto" || !("manual" in m)) return;
                         return m.manual;
                       })()}
                     >
@@ -1535,6 +3566,43 @@ export function ConfigSidebar() {
                       }}
                     </Show>
                   </KTabs.Content>
+                  <KTabs.Content value="pan" tabIndex="" class="space-y-4">
+                    {(() => {
+                      const pan = () => {
+                        const m = value().segment.mode;
+                        if (m !== "auto" && "pan" in m) return m.pan;
+                        return { x: 0.5, y: 0.5 };
+                      };
+
+                      const setPan = (p: { x: number; y: number }) =>
+                        setProject(
+                          "timeline",
+                          "zoomSegments",
+                          value().selection.index,
+                          "mode",
+                          { pan: p }
+                        );
+
+                      return (
+                        <>
+                          <Slider
+                            value={[pan().x]}
+                            onChange={(v) => setPan({ ...pan(), x: v[0] })}
+                            minValue={0}
+                            maxValue={1}
+                            step={0.001}
+                          />
+                          <Slider
+                            value={[pan().y]}
+                            onChange={(v) => setPan({ ...pan(), y: v[0] })}
+                            minValue={0}
+                            maxValue={1}
+                            step={0.001}
+                          />
+                        </>
+                      );
+                    })()}
+                  </KTabs.Content>
                 </KTabs>
               </Field>
             </div>
@@ -21,8 +21,19 @@ import type { UnlistenFn } from "@tauri-apps/api/event";
 import { getCurrentWindow, ProgressBarStatus } from "@tauri-apps/api/window";
 import { createStore, produce } from "solid-js/store";
 
-import { commands, events, RenderProgress } from "~/utils/tauri";
-import { useEditorContext } from "./context";
+import {
+  AnimatedExportSettings,
+  AnimatedFormat,
+  AspectRatio,
+  commands,
+  events,
+  ExportCodec,
+  ExportSettings,
+  FrameRateConversion,
+  GifDither,
+  RenderProgress,
+} from "~/utils/tauri";
+import { DEFAULT_EXPORT_SETTINGS, useEditorContext } from "./context";
 import { authStore } from "~/store";
 import { trackEvent } from "~/utils/analytics";
 import {
@@ -31,31 +42,126 @@ import {
   MenuItem,
   MenuItemList,
   PopperContent,
+  Toggle,
   topLeftAnimateClasses,
 } from "./ui";
 import Titlebar from "~/components/titlebar/Titlebar";
 import { initializeTitlebar, setTitlebar } from "~/utils/titlebar-state";
 import { Channel } from "@tauri-apps/api/core";
 import { createLicenseQuery } from "~/utils/queries";
+import { TextInput } from "./TextInput";
+import { formatTime } from "./utils";
 
 type ResolutionOption = {
   label: string;
   value: string;
   width: number;
   height: number;
+  // lays the composition out at this shape instead of fitting it in
+  aspectRatio?: AspectRatio;
 };
 
 const RESOLUTION_OPTIONS: ResolutionOption[] = [
   { label: "720p", value: "720p", width: 1280, height: 720 },
   { label: "1080p", value: "1080p", width: 1920, height: 1080 },
   { label: "4K", value: "4k", width: 3840, height: 2160 },
+  {
+    label: "Vertical (9:16)",
+    value: "vertical",
+    width: 1080,
+    height: 1920,
+    aspectRatio: "vertical",
+  },
+  {
+    label: "Square (1:1)",
+    value: "square",
+    width: 1080,
+    height: 1080,
+    aspectRatio: "square",
+  },
+  { label: "Custom", value: "custom", width: 1920, height: 1080 },
 ];
 
+const MIN_CUSTOM_DIMENSION = 16;
+const MAX_CUSTOM_DIMENSION = 8192;
+
+// custom resolutions start at the last custom size used
+function withSavedSize(option: ResolutionOption): ResolutionOption {
+  if (option.value !== "custom") return option;
+
+  return {
+    ...option,
+    width:
+      Number(localStorage.getItem("cap-export-custom-width")) || option.width,
+    height:
+      Number(localStorage.getItem("cap-export-custom-height")) ||
+      option.height,
+  };
+}
+
 const FPS_OPTIONS = [
+  { label: "24 FPS", value: 24 },
   { label: "30 FPS", value: 30 },
   { label: "60 FPS", value: 60 },
 ] satisfies Array<{ label: string; value: number }>;
 
+const FRAME_CONVERSION_OPTIONS = [
+  { label: "Repeat frames", value: "duplicate" },
+  { label: "Blend frames", value: "blend" },
+] satisfies Array<{ label: string; value: FrameRateConversion }>;
+
+type ExportFormat = ExportCodec | AnimatedFormat;
+
+const isAnimated = (format: ExportFormat): format is AnimatedFormat =>
+  format === "gif" || format === "webP";
+
+const CODEC_OPTIONS = (
+  [
+    { label: "H.264", value: "h264", extension: "mp4" },
+    { label: "HEVC (H.265)", value: "hevc", extension: "mp4" },
+    { label: "ProRes 422 HQ", value: "proRes422", extension: "mov" },
+    { label: "ProRes 4444", value: "proRes4444", extension: "mov" },
+    { label: "FFV1 (Lossless)", value: "ffv1", extension: "mkv" },
+    { label: "Ut Video (Lossless)", value: "utVideo", extension: "mkv" },
+    { label: "VP9 (WebM)", value: "vp9", extension: "webm" },
+    { label: "AV1 (WebM)", value: "av1", extension: "webm" },
+    { label: "GIF", value: "gif", extension: "gif" },
+    { label: "Animated WebP", value: "webP", extension: "webp" },
+  ] satisfies Array<{ label: string; value: ExportFormat; extension: string }>
+).filter((opt) => ostype() === "macos" || !opt.value.startsWith("proRes"));
+
+// codecs encoding at a bit rate, which can meet a size limit over two passes
+const takesTargetSize = (format: ExportFormat) =>
+  format === "h264" ||
+  format === "hevc" ||
+  format === "vp9" ||
+  format === "av1";
+
+const takesQuality = (format: ExportFormat) =>
+  takesTargetSize(format) || format === "webP";
+
+const QUALITY_OPTIONS = [
+  { label: "Standard", value: 60 },
+  { label: "High", value: 75 },
+  { label: "Maximum", value: 90 },
+] satisfies Array<{ label: string; value: number }>;
+
+const DITHER_OPTIONS = [
+  { label: "Ordered", value: "ordered" },
+  { label: "Floyd-Steinberg", value: "floydSteinberg" },
+  { label: "None", value: "none" },
+] satisfies Array<{ label: string; value: GifDither }>;
+
+// 0 means no limit
+const MAX_SIZE_OPTIONS = [
+  { label: "No limit", value: 0 },
+  { label: "5 MB", value: 5 * 1024 * 1024 },
+  { label: "10 MB", value: 10 * 1024 * 1024 },
+  { label: "25 MB", value: 25 * 1024 * 1024 },
+  { label: "50 MB", value: 50 * 1024 * 1024 },
+  { label: "100 MB", value: 100 * 1024 * 1024 },
+] satisfies Array<{ label: string; value: number }>;
+
 export interface ExportEstimates {
   duration_seconds: number;
   estimated_time_seconds: number;
@@ -64,21 +170,105 @@ export interface ExportEstimates {
 
 export function Header() {
   const license = createLicenseQuery();
+  const { editorInstance } = useEditorContext();
+
+  // HDR10 only helps when there's HDR in the recording to begin with
+  const recordedHdr = editorInstance.recordings.segments.some(
+    (s) => s.display.color.transfer !== "sdr"
+  );
 
   const [selectedFps, setSelectedFps] = createSignal(
     Number(localStorage.getItem("cap-export-fps")) || 30
   );
   const [selectedResolution, setSelectedResolution] =
     createSignal<ResolutionOption>(
-      RESOLUTION_OPTIONS.find(
-        (opt) => opt.value === localStorage.getItem("cap-export-resolution")
-      ) || RESOLUTION_OPTIONS[0]
+      withSavedSize(
+        RESOLUTION_OPTIONS.find(
+          (opt) => opt.value === localStorage.getItem("cap-export-resolution")
+        ) || RESOLUTION_OPTIONS[0]
+      )
     );
+  const [selectedFrameConversion, setSelectedFrameConversion] =
+    createSignal<FrameRateConversion>(
+      localStorage.getItem("cap-export-frame-conversion") === "blend"
+        ? "blend"
+        : "duplicate"
+    );
+  const [selectedCodec, setSelectedCodec] = createSignal<ExportFormat>(
+    CODEC_OPTIONS.find(
+      (opt) => opt.value === localStorage.getItem("cap-export-codec")
+    )?.value ?? "h264"
+  );
+  const [selectedQuality, setSelectedQuality] = createSignal(
+    Number(localStorage.getItem("cap-export-quality")) || 75
+  );
+  const [selectedDither, setSelectedDither] = createSignal<GifDither>(
+    DITHER_OPTIONS.find(
+      (opt) => opt.value === localStorage.getItem("cap-export-dither")
+    )?.value ?? "ordered"
+  );
+  const [selectedMaxSize, setSelectedMaxSize] = createSignal(
+    Number(localStorage.getItem("cap-export-max-size")) || 0
+  );
+  const [selectedHdr10, setSelectedHdr10] = createSignal(
+    localStorage.getItem("cap-export-hdr10") === "true"
+  );
+  const [selectedSkipStaticFrames, setSelectedSkipStaticFrames] = createSignal(
+    localStorage.getItem("cap-export-skip-static-frames") === "true"
+  );
+  const [selectedDraft, setSelectedDraft] = createSignal(
+    localStorage.getItem("cap-export-draft") === "true"
+  );
+  const exportSettings = (): ExportSettings => {
+    const codec = selectedCodec();
+    return {
+      codec: isAnimated(codec) ? DEFAULT_EXPORT_SETTINGS.codec : codec,
+      bitRate: null,
+      quality: takesQuality(codec) ? selectedQuality() : null,
+      targetSizeKib:
+        takesTargetSize(codec) && selectedMaxSize()
+          ? selectedMaxSize() / 1024
+          : null,
+      hdr10: codec === "hevc" && recordedHdr && selectedHdr10(),
+      skipStaticFrames: takesTargetSize(codec) && selectedSkipStaticFrames(),
+    };
+  };
+  const animatedSettings = (): AnimatedExportSettings | null => {
+    const format = selectedCodec();
+    if (!isAnimated(format)) return null;
+    return {
+      format,
+      maxFps: 15,
+      maxColors: 256,
+      dither: selectedDither(),
+      quality: selectedQuality(),
+      maxSizeKib: selectedMaxSize() / 1024 || null,
+    };
+  };
 
   // Save settings when they change
   createEffect(() => {
     localStorage.setItem("cap-export-fps", selectedFps().toString());
     localStorage.setItem("cap-export-resolution", selectedResolution().value);
+    if (selectedResolution().value === "custom") {
+      const { width, height } = selectedResolution();
+      localStorage.setItem("cap-export-custom-width", width.toString());
+      localStorage.setItem("cap-export-custom-height", height.toString());
+    }
+    localStorage.setItem(
+      "cap-export-frame-conversion",
+      selectedFrameConversion()
+    );
+    localStorage.setItem("cap-export-codec", selectedCodec());
+    localStorage.setItem("cap-export-quality", selectedQuality().toString());
+    localStorage.setItem("cap-export-dither", selectedDither());
+    localStorage.setItem("cap-export-max-size", selectedMaxSize().toString());
+    localStorage.setItem("cap-export-hdr10", selectedHdr10().toString());
+    localStorage.setItem(
+      "cap-export-skip-static-frames",
+      selectedSkipStaticFrames().toString()
+    );
+    localStorage.setItem("cap-export-draft", selectedDraft().toString());
   });
 
   let unlistenTitlebar: UnlistenFn | undefined;
@@ -101,15 +291,42 @@ export function Header() {
         )}
       >
         <div class="flex flex-row gap-2 font-medium items-center">
+          <VirtualCameraButton />
           <ShareButton
             selectedResolution={selectedResolution}
             selectedFps={selectedFps}
+            selectedFrameConversion={selectedFrameConversion}
+            // shareable links are always mp4
+            exportSettings={() =>
+              selectedCodec() === "h264" || selectedCodec() === "hevc"
+                ? exportSettings()
+                : DEFAULT_EXPORT_SETTINGS
+            }
           />
           <ExportButton
             selectedResolution={selectedResolution()}
             selectedFps={selectedFps()}
             setSelectedFps={setSelectedFps}
             setSelectedResolution={setSelectedResolution}
+            selectedFrameConversion={selectedFrameConversion()}
+            setSelectedFrameConversion={setSelectedFrameConversion}
+            selectedCodec={selectedCodec()}
+            setSelectedCodec={setSelectedCodec}
+            selectedQuality={selectedQuality()}
+            setSelectedQuality={setSelectedQuality}
+            selectedDither={selectedDither()}
+            setSelectedDither={setSelectedDither}
+            selectedMaxSize={selectedMaxSize()}
+            setSelectedMaxSize={setSelectedMaxSize}
+            hdr10Available={recordedHdr}
+            selectedHdr10={selectedHdr10()}
+            setSelectedHdr10={setSelectedHdr10}
+            selectedSkipStaticFrames={selectedSkipStaticFrames()}
+            setSelectedSkipStaticFrames={setSelectedSkipStaticFrames}
+            selectedDraft={selectedDraft()}
+            setSelectedDraft={setSelectedDraft}
+            exportSettings={exportSettings()}
+            animatedSettings={animatedSettings()}
           />
         </div>
       </div>
@@ -147,14 +364,78 @@ export function Header() {
   );
 }
 
+// Mirrors the preview into OBS's virtual camera, for using the layout live in calls
+function VirtualCameraButton() {
+  const { videoId } = useEditorContext();
+  const [cameraVideoId, { mutate }] = createResource(() =>
+    commands.getVirtualCamera()
+  );
+  const isOn = () => cameraVideoId() === videoId;
+
+  const toggle = createMutation(() => ({
+    mutationFn: async () => {
+      if (isOn()) {
+        await commands.stopVirtualCamera();
+        mutate(null);
+      } else {
+        trackEvent("virtual_camera_started");
+        await commands.startVirtualCamera();
+        mutate(videoId);
+      }
+    },
+    onError(error) {
+      commands.globalMessageDialog(
+        error instanceof Error ? error.message : String(error)
+      );
+    },
+  }));
+
+  return (
+    <Button
+      variant={isOn() ? "primary" : "secondary"}
+      disabled={toggle.isPending}
+      onClick={() => toggle.mutate()}
+    >
+      {isOn() ? "Stop Camera" : "Virtual Camera"}
+    </Button>
+  );
+}
+
 function ExportButton(props: {
   selectedFps: number;
   selectedResolution: ResolutionOption;
   setSelectedResolution: Setter<ResolutionOption>;
   setSelectedFps: Setter<number>;
+  selectedFrameConversion: FrameRateConversion;
+  setSelectedFrameConversion: Setter<FrameRateConversion>;
+  selectedCodec: ExportFormat;
+  setSelectedCodec: Setter<ExportFormat>;
+  selectedQuality: number;
+  setSelectedQuality: Setter<number>;
+  selectedDither: GifDither;
+  setSelectedDither: Setter<GifDither>;
+  selectedMaxSize: number;
+  setSelectedMaxSize: Setter<number>;
+  // whether the recording is HDR, so an HDR10 export makes sense
+  hdr10Available: boolean;
+  selectedHdr10: boolean;
+  setSelectedHdr10: Setter<boolean>;
+  selectedSkipStaticFrames: boolean;
+  setSelectedSkipStaticFrames: Setter<boolean>;
+  // burns the timecode and project revision into the frames, for review copies
+  selectedDraft: boolean;
+  setSelectedDraft: Setter<boolean>;
+  exportSettings: ExportSettings;
+  // set when exporting an animation rather than a video
+  animatedSettings: AnimatedExportSettings | null;
 }) {
   const { videoId, project, prettyName } = useEditorContext();
   const [showExportOptions, setShowExportOptions] = createSignal(false);
+  const [showStreamOptions, setShowStreamOptions] = createSignal(false);
+  const [streamUrl, setStreamUrl] = createSignal(
+    "rtmp://a.rtmp.youtube.com/live2"
+  );
+  const [streamKey, setStreamKey] = createSignal("");
 
   const [exportEstimates] = createResource(
     () => ({
@@ -164,9 +445,15 @@ function ExportButton(props: {
         y: props.selectedResolution.height,
       },
       fps: props.selectedFps,
+      settings: props.exportSettings,
     }),
     (params) =>
-      commands.getExportEstimates(params.videoId, params.resolution, params.fps)
+      commands.getExportEstimates(
+        params.videoId,
+        params.resolution,
+        params.fps,
+        params.settings
+      )
   );
 
   const exportWithSettings = createMutation(() => ({
@@ -175,9 +462,12 @@ function ExportButton(props: {
 
       setShowExportOptions(false);
 
+      const extension =
+        CODEC_OPTIONS.find((opt) => opt.value === props.selectedCodec)
+          ?.extension ?? "mp4";
       const path = await save({
-        filters: [{ name: "mp4 filter", extensions: ["mp4"] }],
-        defaultPath: `~/Desktop/${prettyName()}.mp4`,
+        filters: [{ name: `${extension} filter`, extensions: [extension] }],
+        defaultPath: `~/Desktop/${prettyName()}.${extension}`,
       });
       if (!path) return;
 
@@ -189,35 +479,34 @@ function ExportButton(props: {
 
       setExportState({ type: "starting" });
 
-      const progress = new Channel<RenderProgress>();
-
-      progress.onmessage = (msg) => {
-        if (msg.type === "EstimatedTotalFrames")
-          setExportState({
-            type: "rendering",
-            renderedFrames: 0,
-            totalFrames: msg.total_frames,
-          });
-        else
-          setExportState(
-            produce((state) => {
-              if (msg.type === "FrameRendered" && state.type === "rendering")
-                state.renderedFrames = msg.current_frame;
-            })
-          );
+      const progress = createProgressChannel();
+
+      const resolutionBase = {
+        x: props.selectedResolution.width,
+        y: props.selectedResolution.height,
       };
 
       try {
-        const videoPath = await commands.exportVideo(
-          videoId,
-          progress,
-          true,
-          props.selectedFps,
-          {
-            x: props.selectedResolution.width,
-            y: props.selectedResolution.height,
-          }
-        );
+        const videoPath = props.animatedSettings
+          ? await commands.exportAnimated(
+              videoId,
+              progress,
+              props.selectedFps,
+              resolutionBase,
+              props.selectedResolution.aspectRatio ?? null,
+              props.animatedSettings
+            )
+          : await commands.exportVideo(
+              videoId,
+              progress,
+              true,
+              props.selectedFps,
+              resolutionBase,
+              props.selectedResolution.aspectRatio ?? null,
+              props.selectedFrameConversion,
+              props.exportSettings,
+              props.selectedDraft
+            );
 
         setExportState({ type: "saving", done: false });
 
@@ -235,15 +524,177 @@ function ExportButton(props: {
     },
   }));
 
+  // published live to an RTMP ingest instead of a file, taking as long as the video plays
+  const streamExport = createMutation(() => ({
+    mutationFn: async () => {
+      setShowExportOptions(false);
+      setShowStreamOptions(false);
+
+      trackEvent("export_stream_started", {
+        resolution: props.selectedResolution.value,
+        fps: props.selectedFps,
+      });
+
+      setExportState({ type: "starting" });
+
+      await commands.exportToStream(
+        videoId,
+        createProgressChannel(),
+        props.selectedFps,
+        {
+          x: props.selectedResolution.width,
+          y: props.selectedResolution.height,
+        },
+        props.selectedResolution.aspectRatio ?? null,
+        { url: streamUrl(), streamKey: streamKey(), bitRate: null }
+      );
+
+      setExportState({ type: "saving", done: true });
+    },
+    onError(error) {
+      // commands fail with their error's message
+      const message = error instanceof Error ? error.message : String(error);
+      if (message !== "Export was cancelled")
+        commands.globalMessageDialog(`Streaming failed: ${message}`);
+    },
+    onSettled() {
+      setTimeout(() => {
+        streamExport.reset();
+        setExportState({ type: "idle" });
+      }, 2000);
+    },
+  }));
+
+  // exported in the background, without tying up the editor
+  const queueExport = createMutation(() => ({
+    mutationFn: async () => {
+      const extension =
+        CODEC_OPTIONS.find((opt) => opt.value === props.selectedCodec)
+          ?.extension ?? "mp4";
+      const path = await save({
+        filters: [{ name: `${extension} filter`, extensions: [extension] }],
+        defaultPath: `~/Desktop/${prettyName()}.${extension}`,
+      });
+      if (!path) return;
+
+      setShowExportOptions(false);
+
+      await commands.queueExports([
+        {
+          videoId,
+          outputPath: path,
+          fps: props.selectedFps,
+          resolutionBase: {
+            x: props.selectedResolution.width,
+            y: props.selectedResolution.height,
+          },
+          aspectRatio: props.selectedResolution.aspectRatio ?? null,
+          frameRateConversion: props.selectedFrameConversion,
+          settings: props.exportSettings,
+          draft: props.selectedDraft,
+        },
+      ]);
+    },
+  }));
+
+  // the screen and camera as separate files, plus a manifest lining them up
+  const splitExport = createMutation(() => ({
+    mutationFn: async () => {
+      const extension =
+        CODEC_OPTIONS.find((opt) => opt.value === props.selectedCodec)
+          ?.extension ?? "mp4";
+      const path = await save({
+        filters: [{ name: `${extension} filter`, extensions: [extension] }],
+        defaultPath: `~/Desktop/${prettyName()}.${extension}`,
+      });
+      if (!path) return;
+
+      setShowExportOptions(false);
+
+      trackEvent("export_split_started", {
+        resolution: props.selectedResolution.value,
+        fps: props.selectedFps,
+      });
+
+      setExportState({ type: "starting" });
+
+      await commands.exportSplit(
+        videoId,
+        createProgressChannel(),
+        path,
+        props.selectedFps,
+        {
+          x: props.selectedResolution.width,
+          y: props.selectedResolution.height,
+        },
+        props.selectedResolution.aspectRatio ?? null,
+        props.selectedFrameConversion,
+        props.exportSettings,
+        props.selectedDraft
+      );
+
+      setExportState({ type: "saving", done: true });
+    },
+    onError(error) {
+      const message = error instanceof Error ? error.message : String(error);
+      if (message !== "Export was cancelled")
+        commands.globalMessageDialog(`Export failed: ${message}`);
+    },
+    onSettled() {
+      setTimeout(() => {
+        splitExport.reset();
+        setExportState({ type: "idle" });
+      }, 2000);
+    },
+  }));
+
   const [exportState, setExportState] = createStore<
     | { type: "idle" }
     | { type: "starting" }
-    | { type: "rendering"; renderedFrames: number; totalFrames: number }
+    | {
+        type: "rendering";
+        renderedFrames: number;
+        totalFrames: number;
+        etaSecs: number | null;
+      }
     | { type: "saving"; done: boolean }
   >({ type: "idle" });
 
+  function createProgressChannel() {
+    const progress = new Channel<RenderProgress>();
+
+    progress.onmessage = (msg) => {
+      if (msg.type === "EstimatedTotalFrames")
+        setExportState({
+          type: "rendering",
+          renderedFrames: 0,
+          totalFrames: msg.total_frames,
+          etaSecs: null,
+        });
+      else
+        setExportState(
+          produce((state) => {
+            if (msg.type === "FrameRendered" && state.type === "rendering") {
+              state.renderedFrames = msg.current_frame;
+              // two-pass exports go over every frame twice
+              state.totalFrames = msg.total_frames;
+              state.etaSecs = msg.eta_secs;
+            }
+          })
+        );
+    };
+
+    return progress;
+  }
+
   createProgressBar(() => {
-    if (exportWithSettings.isIdle || exportState.type === "idle") return;
+    if (
+      (exportWithSettings.isIdle &&
+        streamExport.isIdle &&
+        splitExport.isIdle) ||
+      exportState.type === "idle"
+    )
+      return;
     if (exportState.type === "starting") return 0;
     if (exportState.type === "rendering")
       return (exportState.renderedFrames / exportState.totalFrames) * 100;
@@ -281,7 +732,7 @@ function ExportButton(props: {
                       width: value.width,
                       height: value.height,
                     });
-                    props.setSelectedResolution(value);
+                    props.setSelectedResolution(withSavedSize(value));
                   }
                 }}
                 itemComponent={(props) => (
@@ -315,6 +766,23 @@ function ExportButton(props: {
                   </PopperContent>
                 </KSelect.Portal>
               </KSelect>
+              <Show when={props.selectedResolution.value === "custom"}>
+                <div class="flex flex-row items-center gap-2 mt-2">
+                  <DimensionInput
+                    value={props.selectedResolution.width}
+                    onChange={(width) =>
+                      props.setSelectedResolution((r) => ({ ...r, width }))
+                    }
+                  />
+                  <span class="text-sm text-gray-400">×</span>
+                  <DimensionInput
+                    value={props.selectedResolution.height}
+                    onChange={(height) =>
+                      props.setSelectedResolution((r) => ({ ...r, height }))
+                    }
+                  />
+                </div>
+              </Show>
             </div>
             <div>
               <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
@@ -369,6 +837,328 @@ function ExportButton(props: {
                 </KSelect.Portal>
               </KSelect>
             </div>
+            <div>
+              <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                Frame Rate Conversion
+              </label>
+              <KSelect
+                options={FRAME_CONVERSION_OPTIONS}
+                optionValue="value"
+                optionTextValue="label"
+                placeholder="Select Conversion"
+                value={FRAME_CONVERSION_OPTIONS.find(
+                  (opt) => opt.value === props.selectedFrameConversion
+                )}
+                onChange={(option) => {
+                  const conversion = option?.value ?? "duplicate";
+                  trackEvent("export_frame_conversion_changed", {
+                    conversion,
+                  });
+                  props.setSelectedFrameConversion(conversion);
+                }}
+                itemComponent={(props) => (
+                  <MenuItem<typeof KSelect.Item>
+                    as={KSelect.Item}
+                    item={props.item}
+                  >
+                    <KSelect.ItemLabel class="flex-1">
+                      {props.item.rawValue.label}
+                    </KSelect.ItemLabel>
+                  </MenuItem>
+                )}
+              >
+                <KSelect.Trigger class="flex flex-row items-center h-[2rem] px-[0.375rem] gap-[0.375rem] border rounded-lg border-gray-200 w-full disabled:text-gray-400 transition-colors KSelect">
+                  <KSelect.Value<
+                    (typeof FRAME_CONVERSION_OPTIONS)[number]
+                  > class="flex-1 text-sm text-left truncate text-[--gray-500]">
+                    {(state) => <span>{state.selectedOption()?.label}</span>}
+                  </KSelect.Value>
+                  <KSelect.Icon>
+                    <IconCapChevronDown class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]" />
+                  </KSelect.Icon>
+                </KSelect.Trigger>
+                <KSelect.Portal>
+                  <PopperContent<typeof KSelect.Content>
+                    as={KSelect.Content}
+                    class={cx(topLeftAnimateClasses, "z-50")}
+                  >
+                    <MenuItemList<typeof KSelect.Listbox>
+                      class="max-h-32 overflow-y-auto"
+                      as={KSelect.Listbox}
+                    />
+                  </PopperContent>
+                </KSelect.Portal>
+              </KSelect>
+            </div>
+            <div>
+              <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                Format
+              </label>
+              <KSelect
+                options={CODEC_OPTIONS}
+                optionValue="value"
+                optionTextValue="label"
+                placeholder="Select Format"
+                value={CODEC_OPTIONS.find(
+                  (opt) => opt.value === props.selectedCodec
+                )}
+                onChange={(option) => {
+                  const codec = option?.value ?? "h264";
+                  trackEvent("export_codec_changed", { codec });
+                  props.setSelectedCodec(codec);
+                }}
+                itemComponent={(props) => (
+                  <MenuItem<typeof KSelect.Item>
+                    as={KSelect.Item}
+                    item={props.item}
+                  >
+                    <KSelect.ItemLabel class="flex-1">
+                      {props.item.rawValue.label}
+                    </KSelect.ItemLabel>
+                  </MenuItem>
+                )}
+              >
+                <KSelect.Trigger class="flex flex-row items-center h-[2rem] px-[0.375rem] gap-[0.375rem] border rounded-lg border-gray-200 w-full disabled:text-gray-400 transition-colors KSelect">
+                  <KSelect.Value<
+                    (typeof CODEC_OPTIONS)[number]
+                  > class="flex-1 text-sm text-left truncate text-[--gray-500]">
+                    {(state) => <span>{state.selectedOption()?.label}</span>}
+                  </KSelect.Value>
+                  <KSelect.Icon>
+                    <IconCapChevronDown class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]" />
+                  </KSelect.Icon>
+                </KSelect.Trigger>
+                <KSelect.Portal>
+                  <PopperContent<typeof KSelect.Content>
+                    as={KSelect.Content}
+                    class={cx(topLeftAnimateClasses, "z-50")}
+                  >
+                    <MenuItemList<typeof KSelect.Listbox>
+                      class="max-h-32 overflow-y-auto"
+                      as={KSelect.Listbox}
+                    />
+                  </PopperContent>
+                </KSelect.Portal>
+              </KSelect>
+            </div>
+            <Show
+              when={
+                takesQuality(props.selectedCodec) &&
+                !props.exportSettings.targetSizeKib
+              }
+            >
+              <div>
+                <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                  Quality
+                </label>
+                <KSelect
+                  options={QUALITY_OPTIONS}
+                  optionValue="value"
+                  optionTextValue="label"
+                  placeholder="Select Quality"
+                  value={QUALITY_OPTIONS.find(
+                    (opt) => opt.value === props.selectedQuality
+                  )}
+                  onChange={(option) => {
+                    const quality = option?.value ?? 75;
+                    trackEvent("export_quality_changed", { quality });
+                    props.setSelectedQuality(quality);
+                  }}
+                  itemComponent={(props) => (
+                    <MenuItem<typeof KSelect.Item>
+                      as={KSelect.Item}
+                      item={props.item}
+                    >
+                      <KSelect.ItemLabel class="flex-1">
+                        {props.item.rawValue.label}
+                      </KSelect.ItemLabel>
+                    </MenuItem>
+                  )}
+                >
+                  <KSelect.Trigger class="flex flex-row items-center h-[2rem] px-[0.375rem] gap-[0.375rem] border rounded-lg border-gray-200 w-full disabled:text-gray-400 transition-colors KSelect">
+                    <KSelect.Value<
+                      (typeof QUALITY_OPTIONS)[number]
+                    > class="flex-1 text-sm text-left truncate text-[--gray-500]">
+                      {(state) => <span>{state.selectedOption()?.label}</span>}
+                    </KSelect.Value>
+                    <KSelect.Icon>
+                      <IconCapChevronDown class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]" />
+                    </KSelect.Icon>
+                  </KSelect.Trigger>
+                  <KSelect.Portal>
+                    <PopperContent<typeof KSelect.Content>
+                      as={KSelect.Content}
+                      class={cx(topLeftAnimateClasses, "z-50")}
+                    >
+                      <MenuItemList<typeof KSelect.Listbox>
+                        class="max-h-32 overflow-y-auto"
+                        as={KSelect.Listbox}
+                      />
+                    </PopperContent>
+                  </KSelect.Portal>
+                </KSelect>
+              </div>
+            </Show>
+            <Show when={props.selectedCodec === "gif"}>
+              <div>
+                <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                  Dithering
+                </label>
+                <KSelect
+                  options={DITHER_OPTIONS}
+                  optionValue="value"
+                  optionTextValue="label"
+                  placeholder="Select Dithering"
+                  value={DITHER_OPTIONS.find(
+                    (opt) => opt.value === props.selectedDither
+                  )}
+                  onChange={(option) => {
+                    const dither = option?.value ?? "ordered";
+                    trackEvent("export_dither_changed", { dither });
+                    props.setSelectedDither(dither);
+                  }}
+                  itemComponent={(props) => (
+                    <MenuItem<typeof KSelect.Item>
+                      as={KSelect.Item}
+                      item={props.item}
+                    >
+                      <KSelect.ItemLabel class="flex-1">
+                        {props.item.rawValue.label}
+                      </KSelect.ItemLabel>
+                    </MenuItem>
+                  )}
+                >
+                  <KSelect.Trigger class="flex flex-row items-center h-[2rem] px-[0.375rem] gap-[0.375rem] border rounded-lg border-gray-200 w-full disabled:text-gray-400 transition-colors KSelect">
+                    <KSelect.Value<
+                      (typeof DITHER_OPTIONS)[number]
+                    > class="flex-1 text-sm text-left truncate text-[--gray-500]">
+                      {(state) => <span>{state.selectedOption()?.label}</span>}
+                    </KSelect.Value>
+                    <KSelect.Icon>
+                      <IconCapChevronDown class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]" />
+                    </KSelect.Icon>
+                  </KSelect.Trigger>
+                  <KSelect.Portal>
+                    <PopperContent<typeof KSelect.Content>
+                      as={KSelect.Content}
+                      class={cx(topLeftAnimateClasses, "z-50")}
+                    >
+                      <MenuItemList<typeof KSelect.Listbox>
+                        class="max-h-32 overflow-y-auto"
+                        as={KSelect.Listbox}
+                      />
+                    </PopperContent>
+                  </KSelect.Portal>
+                </KSelect>
+              </div>
+            </Show>
+            <Show
+              when={
+                props.animatedSettings || takesTargetSize(props.selectedCodec)
+              }
+            >
+              <div>
+                <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                  Max Size
+                </label>
+                <KSelect
+                  options={MAX_SIZE_OPTIONS}
+                  optionValue="value"
+                  optionTextValue="label"
+                  placeholder="Select Max Size"
+                  value={MAX_SIZE_OPTIONS.find(
+                    (opt) => opt.value === props.selectedMaxSize
+                  )}
+                  onChange={(option) => {
+                    const maxSize = option?.value ?? 0;
+                    trackEvent("export_max_size_changed", { maxSize });
+                    props.setSelectedMaxSize(maxSize);
+                  }}
+                  itemComponent={(props) => (
+                    <MenuItem<typeof KSelect.Item>
+                      as={KSelect.Item}
+                      item={props.item}
+                    >
+                      <KSelect.ItemLabel class="flex-1">
+                        {props.item.rawValue.label}
+                      </KSelect.ItemLabel>
+                    </MenuItem>
+                  )}
+                >
+                  <KSelect.Trigger class="flex flex-row items-center h-[2rem] px-[0.375rem] gap-[0.375rem] border rounded-lg border-gray-200 w-full disabled:text-gray-400 transition-colors KSelect">
+                    <KSelect.Value<
+                      (typeof MAX_SIZE_OPTIONS)[number]
+                    > class="flex-1 text-sm text-left truncate text-[--gray-500]">
+                      {(state) => <span>{state.selectedOption()?.label}</span>}
+                    </KSelect.Value>
+                    <KSelect.Icon>
+                      <IconCapChevronDown class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]" />
+                    </KSelect.Icon>
+                  </KSelect.Trigger>
+                  <KSelect.Portal>
+                    <PopperContent<typeof KSelect.Content>
+                      as={KSelect.Content}
+                      class={cx(topLeftAnimateClasses, "z-50")}
+                    >
+                      <MenuItemList<typeof KSelect.Listbox>
+                        class="max-h-32 overflow-y-auto"
+                        as={KSelect.Listbox}
+                      />
+                    </PopperContent>
+                  </KSelect.Portal>
+                </KSelect>
+              </div>
+            </Show>
+            <Show
+              when={props.hdr10Available && props.selectedCodec === "hevc"}
+            >
+              <div class="flex flex-row items-center justify-between">
+                <label class="text-sm font-medium text-gray-500 dark:text-gray-400">
+                  HDR10 (Experimental)
+                </label>
+                <Toggle
+                  checked={props.selectedHdr10}
+                  onChange={(hdr10) => {
+                    trackEvent("export_hdr10_changed", { hdr10 });
+                    props.setSelectedHdr10(hdr10);
+                  }}
+                />
+              </div>
+            </Show>
+            <Show when={takesTargetSize(props.selectedCodec)}>
+              <div class="flex flex-row items-center justify-between">
+                <label
+                  class="text-sm font-medium text-gray-500 dark:text-gray-400"
+                  title="Leaves out frames that repeat the last, which shrinks still recordings. Some video editors can't open the variable frame rate files this makes."
+                >
+                  Skip Repeated Frames
+                </label>
+                <Toggle
+                  checked={props.selectedSkipStaticFrames}
+                  onChange={(skip) => {
+                    trackEvent("export_skip_static_frames_changed", { skip });
+                    props.setSelectedSkipStaticFrames(skip);
+                  }}
+                />
+              </div>
+            </Show>
+            <Show when={!props.animatedSettings}>
+              <div class="flex flex-row items-center justify-between">
+                <label
+                  class="text-sm font-medium text-gray-500 dark:text-gray-400"
+                  title="Stamps the timecode and project revision in a corner of every frame, for sending out review copies."
+                >
+                  Draft Burn-In
+                </label>
+                <Toggle
+                  checked={props.selectedDraft}
+                  onChange={(draft) => {
+                    trackEvent("export_draft_changed", { draft });
+                    props.setSelectedDraft(draft);
+                  }}
+                />
+              </div>
+            </Show>
             <Button
               variant="primary"
               class="w-full justify-center"
@@ -376,6 +1166,62 @@ function ExportButton(props: {
             >
               Export Video
             </Button>
+            <Show when={!props.animatedSettings}>
+              <Button
+                variant="secondary"
+                class="w-full justify-center"
+                disabled={queueExport.isPending}
+                onClick={() => queueExport.mutate()}
+              >
+                Add to Export Queue
+              </Button>
+              <Button
+                variant="secondary"
+                class="w-full justify-center"
+                disabled={splitExport.isPending}
+                onClick={() => splitExport.mutate()}
+              >
+                Export Screen and Camera Separately
+              </Button>
+              <Button
+                variant="secondary"
+                class="w-full justify-center"
+                onClick={() => setShowStreamOptions(!showStreamOptions())}
+              >
+                Stream Live
+              </Button>
+              <Show when={showStreamOptions()}>
+                <div class="space-y-2">
+                  <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                    RTMP URL
+                  </label>
+                  <TextInput
+                    class="w-full h-[2rem] px-[0.375rem] border rounded-lg border-gray-200 text-sm text-[--gray-500] bg-transparent"
+                    value={streamUrl()}
+                    onInput={(e) => setStreamUrl(e.currentTarget.value)}
+                  />
+                  <label class="block text-sm font-medium mb-1 text-gray-500 dark:text-gray-400">
+                    Stream Key
+                  </label>
+                  <TextInput
+                    class="w-full h-[2rem] px-[0.375rem] border rounded-lg border-gray-200 text-sm text-[--gray-500] bg-transparent"
+                    type="password"
+                    value={streamKey()}
+                    onInput={(e) => setStreamKey(e.currentTarget.value)}
+                  />
+                  <Button
+                    variant="primary"
+                    class="w-full justify-center"
+                    disabled={
+                      !streamUrl().startsWith("rtmp") || streamExport.isPending
+                    }
+                    onClick={() => streamExport.mutate()}
+                  >
+                    Go Live
+                  </Button>
+                </div>
+              </Show>
+            </Show>
             <Show when={exportEstimates.latest}>
               {(est) => (
                 <div
@@ -438,14 +1284,19 @@ function ExportButton(props: {
         </div>
       </Show>
       <Dialog.Root
-        open={!exportWithSettings.isIdle && exportState.type !== "idle"}
+        open={
+          (!exportWithSettings.isIdle ||
+            !streamExport.isIdle ||
+            !splitExport.isIdle) &&
+          exportState.type !== "idle"
+        }
         onOpenChange={(o) => {
-          // cancellation doesn't work yet
-          // if (!o) exportWithSettings.reset();
+          if (!o && exportState.type === "rendering")
+            commands.cancelExport(videoId);
         }}
       >
         <DialogContent
-          title="Exporting Recording"
+          title={streamExport.isIdle ? "Exporting Recording" : "Streaming Live"}
           confirm={<></>}
           close={<></>}
           class="bg-gray-600 text-gray-500 dark:text-gray-500"
@@ -473,10 +1324,32 @@ function ExportButton(props: {
             <p class="text-xs mt-3 relative z-10">
               {exportState.type == "idle" || exportState.type === "starting"
                 ? "Preparing to render..."
+                : exportState.type === "rendering" && !streamExport.isIdle
+                ? `Streaming live (${formatTime(
+                    exportState.renderedFrames / props.selectedFps
+                  )} of ${formatTime(
+                    exportState.totalFrames / props.selectedFps
+                  )})`
                 : exportState.type === "rendering"
-                ? `Rendering video (${exportState.renderedFrames}/${exportState.totalFrames} frames)`
-                : "Exported successfully!"}
+                ? `Rendering video (${exportState.renderedFrames}/${
+                    exportState.totalFrames
+                  } frames)${
+                    exportState.etaSecs !== null
+                      ? `, ${formatTime(exportState.etaSecs)} left`
+                      : ""
+                  }`
+                : streamExport.isIdle
+                ? "Exported successfully!"
+                : "Stream finished!"}
             </p>
+            <Show when={exportState.type === "rendering"}>
+              <Button
+                variant="secondary"
+                onClick={() => commands.cancelExport(videoId)}
+              >
+                {streamExport.isIdle ? "Cancel" : "End Stream"}
+              </Button>
+            </Show>
           </div>
         </DialogContent>
       </Dialog.Root>
@@ -484,9 +1357,39 @@ function ExportButton(props: {
   );
 }
 
+// Applied once editing finishes, as an even number within what the renderer supports
+function DimensionInput(props: {
+  value: number;
+  onChange: (value: number) => void;
+}) {
+  return (
+    <TextInput
+      class="w-full h-[2rem] px-[0.375rem] border rounded-lg border-gray-200 text-sm text-[--gray-500] bg-transparent"
+      inputMode="numeric"
+      value={props.value}
+      onChange={(e) => {
+        const value = Math.round(Number(e.currentTarget.value));
+        if (!Number.isFinite(value) || value <= 0) {
+          e.currentTarget.value = props.value.toString();
+          return;
+        }
+
+        const clamped = Math.min(
+          Math.max(value, MIN_CUSTOM_DIMENSION),
+          MAX_CUSTOM_DIMENSION
+        );
+        props.onChange(clamped & ~1);
+        e.currentTarget.value = (clamped & ~1).toString();
+      }}
+    />
+  );
+}
+
 function ShareButton(props: {
   selectedResolution: () => ResolutionOption;
   selectedFps: () => number;
+  selectedFrameConversion: () => FrameRateConversion;
+  exportSettings: () => ExportSettings;
 }) {
   const { videoId, project } = useEditorContext();
   const [recordingMeta, metaActions] = createResource(() =>
@@ -564,8 +1467,10 @@ function ShareButton(props: {
           else
             setUploadState(
               produce((state) => {
-                if (msg.type === "FrameRendered" && state.type === "rendering")
+                if (msg.type === "FrameRendered" && state.type === "rendering") {
                   state.renderedFrames = msg.current_frame;
+                  state.totalFrames = msg.total_frames;
+                }
               })
             );
         };
@@ -580,7 +1485,11 @@ function ShareButton(props: {
             y:
               props.selectedResolution()?.height ||
               RESOLUTION_OPTIONS[0].height,
-          }
+          },
+          props.selectedResolution()?.aspectRatio ?? null,
+          props.selectedFrameConversion(),
+          props.exportSettings(),
+          false
         );
 
         setUploadState({ type: "uploading", progress: 0 });
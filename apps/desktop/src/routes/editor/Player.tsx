@@ -3,6 +3,7 @@ import { Select as KSelect } from "@kobalte/core/select";
 import { ToggleButton as KToggleButton } from "@kobalte/core/toggle-button";
 import { createEventListener } from "@solid-primitives/event-listener";
 import { createElementBounds } from "@solid-primitives/bounds";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { cx } from "cva";
 import {
   For,
@@ -12,10 +13,17 @@ import {
   createResource,
   createSignal,
   on,
+  onCleanup,
 } from "solid-js";
 import { reconcile } from "solid-js/store";
 
-import { type AspectRatio, commands } from "~/utils/tauri";
+import {
+  type AspectRatio,
+  type XY,
+  type ZoomDebugInfo,
+  commands,
+  events,
+} from "~/utils/tauri";
 import { FPS, OUTPUT_SIZE, useEditorContext } from "./context";
 import { ASPECT_RATIOS } from "./projectConfig";
 import { authStore } from "~/store";
@@ -84,8 +92,28 @@ export function Player() {
     return total > 0 && total - playbackTime() <= 0.1;
   };
 
+  const [looping, setLooping] = createSignal(false);
+
+  const [zoomDebug, setZoomDebug] = createSignal(false);
+  const [zoomDebugInfo, setZoomDebugInfo] = createSignal<ZoomDebugInfo>();
+
+  createEffect(() => {
+    if (!zoomDebug()) return;
+
+    const unlisten = events
+      .zoomDebugChanged(getCurrentWebviewWindow())
+      .listen((e) => setZoomDebugInfo(e.payload));
+    commands.setZoomDebug(true);
+
+    onCleanup(() => {
+      unlisten.then((f) => f());
+      commands.setZoomDebug(false);
+      setZoomDebugInfo();
+    });
+  });
+
   createEffect(() => {
-    if (isAtEnd() && playing()) {
+    if (isAtEnd() && playing() && !looping()) {
       commands.stopPlayback();
       setPlaying(false);
     }
@@ -233,6 +261,9 @@ export function Player() {
             );
           }}
         </Show>
+        <Show when={zoomDebug() && zoomDebugInfo()}>
+          {(info) => <ZoomDebugOverlay info={info()} />}
+        </Show>
       </div>
       <div class="flex flex-row items-center p-[0.75rem] gap-[0.5rem] z-10 bg-gray-50 justify-between">
         <div class="flex-1 flex items-center">
@@ -271,6 +302,25 @@ export function Player() {
           >
             <IconCapFrameLast class="size-[1.2rem]" />
           </button>
+          <button
+            type="button"
+            title="Loop"
+            classList={{ "text-black": looping() }}
+            onClick={async () => {
+              await commands.setPlaybackLoop(!looping());
+              setLooping(!looping());
+            }}
+          >
+            <IconLucideRepeat class="size-[1.1rem]" />
+          </button>
+          <button
+            type="button"
+            title="Zoom debug"
+            classList={{ "text-black": zoomDebug() }}
+            onClick={() => setZoomDebug(!zoomDebug())}
+          >
+            <IconLucideBug class="size-[1.1rem]" />
+          </button>
         </div>
         <div class="flex-1 flex flex-row justify-end items-center gap-2">
           <Time seconds={totalDuration()} />
@@ -513,6 +563,29 @@ function PresetsDropdown() {
   );
 }
 
+// The zoom state of the frame being previewed, shown over it
+function ZoomDebugOverlay(props: { info: ZoomDebugInfo }) {
+  const xy = (v: XY<number>) => `${v.x.toFixed(3)}, ${v.y.toFixed(3)}`;
+  const segment = (i: number | null) => (i === null ? "none" : `#${i}`);
+
+  return (
+    <div class="absolute top-6 left-6 p-2 rounded bg-black/70 text-white font-mono text-[0.75rem] leading-4 pointer-events-none">
+      <div>time {props.info.time.toFixed(3)}s</div>
+      <div>
+        segment {segment(props.info.segmentIndex)}, from{" "}
+        {segment(props.info.prevSegmentIndex)}
+      </div>
+      <div>
+        amount {props.info.displayAmount.toFixed(3)}x, t{" "}
+        {props.info.t.toFixed(3)}
+      </div>
+      <div>top left {xy(props.info.topLeft)}</div>
+      <div>bottom right {xy(props.info.bottomRight)}</div>
+      <div>auto focus {xy(props.info.autoFocus)}</div>
+    </div>
+  );
+}
+
 function Time(props: { seconds: number; fps?: number }) {
   return (
     <span class="text-gray-400 text-[0.875rem] tabular-nums">
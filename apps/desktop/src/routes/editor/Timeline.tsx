@@ -18,8 +18,9 @@ import { produce } from "solid-js/store";
 import { mergeRefs } from "@solid-primitives/refs";
 import { createMemo } from "solid-js";
 
-import { TimelineSegment } from "~/utils/tauri";
+import { commands, TimelineSegment } from "~/utils/tauri";
 import {
+  FPS,
   SegmentContextProvider,
   TimelineContextProvider,
   TrackContextProvider,
@@ -107,13 +108,13 @@ export function Timeline() {
   async function handleUpdatePlayhead(e: MouseEvent) {
     const { left } = timelineBounds;
     if (zoomSegmentDragState.type !== "moving") {
-      setPlaybackTime(
-        Math.min(
-          secsPerPixel() * (e.clientX - left!) +
-            state.timelineTransform.position,
-          totalDuration()
-        )
+      const time = Math.min(
+        secsPerPixel() * (e.clientX - left!) + state.timelineTransform.position,
+        totalDuration()
       );
+      setPlaybackTime(time);
+      // playback carries on from the new position
+      if (playing()) await commands.seekTo(Math.floor(time * FPS));
     }
   }
 
@@ -196,6 +197,27 @@ export function Timeline() {
         }}
       >
         <TimelineMarkings />
+        <For each={project.timeline?.markers ?? []}>
+          {(marker) => (
+            <div
+              class="w-px absolute top-4 bottom-0 z-10 pointer-events-none bg-blue-300/60"
+              style={{
+                left: `${xPadding}px`,
+                transform: `translateX(${
+                  (marker.time - state.timelineTransform.position) /
+                  secsPerPixel()
+                }px)`,
+              }}
+            >
+              <div
+                class="absolute -top-1 left-0 px-1 rounded-r-sm bg-blue-300 text-white text-[0.625rem] leading-4 whitespace-nowrap max-w-[8rem] truncate pointer-events-auto"
+                title={`${marker.name} (${formatTime(marker.time)})`}
+              >
+                {marker.name}
+              </div>
+            </div>
+          )}
+        </For>
         <Show when={!playing() && previewTime()}>
           {(time) => (
             <div
@@ -323,6 +345,12 @@ function ClipTrack(props: Pick<ComponentProps<"div">, "ref">) {
               .slice(0, i())
               .reduce((t, s) => t + (s.end - s.start) / s.timescale, 0);
 
+          // the main display plus any recorded alongside it
+          const displayCount = () =>
+            1 +
+            (editorInstance.recordings.segments[segment.recordingSegment ?? 0]
+              ?.additional_displays.length ?? 0);
+
           return (
             <SegmentRoot
               class="border-blue-300"
@@ -351,6 +379,8 @@ function ClipTrack(props: Pick<ComponentProps<"div">, "ref">) {
                       end: segment.end,
                       timescale: 1,
                       recordingSegment: segment.recordingSegment,
+                      display: segment.display,
+                      cameraPosition: segment.cameraPosition,
                     });
                     segments[i()].end = splitTime;
                   })
@@ -421,6 +451,24 @@ function ClipTrack(props: Pick<ComponentProps<"div">, "ref">) {
                 <span class="text-black-transparent-60 text-[0.625rem] mt-auto">
                   {formatTime(segment.start)}
                 </span>
+                <Show when={displayCount() > 1}>
+                  <button
+                    onMouseDown={(e) => e.stopPropagation()}
+                    onClick={() => {
+                      setProject(
+                        "timeline",
+                        "segments",
+                        i(),
+                        "display",
+                        ((segment.display ?? 0) + 1) % displayCount()
+                      );
+                    }}
+                    class="px-2 h-6 rounded-full bg-gray-50 text-gray-500 hover:text-gray-600 text-[0.625rem] transition-colors"
+                    title="Switch display"
+                  >
+                    Display {(segment.display ?? 0) + 1}
+                  </button>
+                </Show>
                 {/* <Show when={segments().length > 1}>
                     <button
                       onClick={() => {
@@ -17,6 +17,7 @@ import { createElementBounds, NullableBounds } from "@solid-primitives/bounds";
 
 import {
   PresetsStore,
+  type ExportSettings,
   type ProjectConfiguration,
   type SerializedEditorInstance,
   type XY,
@@ -41,6 +42,12 @@ export const OUTPUT_SIZE = {
   y: 1080,
 };
 
+export const DEFAULT_EXPORT_SETTINGS: ExportSettings = {
+  codec: "h264",
+  bitRate: null,
+  quality: null,
+};
+
 export const MAX_ZOOM_IN = 3;
 
 export const [EditorContextProvider, useEditorContext] = createContextProvider(
@@ -63,7 +70,14 @@ export const [EditorContextProvider, useEditorContext] = createContextProvider(
     );
 
     const [selectedTab, setSelectedTab] = createSignal<
-      "background" | "camera" | "transcript" | "audio" | "cursor" | "hotkeys"
+      | "background"
+      | "camera"
+      | "transcript"
+      | "audio"
+      | "cursor"
+      | "hotkeys"
+      | "text"
+      | "annotations"
     >("background");
 
     const [dialog, setDialog] = createSignal<DialogState>({
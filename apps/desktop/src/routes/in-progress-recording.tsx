@@ -122,6 +122,14 @@ export default function () {
     },
   }));
 
+  events.requestTogglePauseRecording.listen(() => {
+    if (!stopped()) togglePause.mutate();
+  });
+
+  const addMarker = createMutation(() => ({
+    mutationFn: () => commands.addRecordingMarker(),
+  }));
+
   const restartRecording = createMutation(() => ({
     mutationFn: async () => {
       await events.requestRestartRecording.emit();
@@ -183,6 +191,14 @@ export default function () {
             {isPaused() ? <IconCapPlayCircle /> : <IconCapPauseCircle />}
           </ActionButton>
 
+          <ActionButton
+            disabled={addMarker.isPending}
+            onClick={() => addMarker.mutate()}
+            title="Add marker"
+          >
+            <IconLucideBookmarkPlus />
+          </ActionButton>
+
           <ActionButton
             disabled={restartRecording.isPending}
             onClick={() => restartRecording.mutate()}
@@ -27,7 +27,7 @@ import IconLucideClock from "~icons/lucide/clock";
 
 import { commands, events, RenderProgress, UploadResult } from "~/utils/tauri";
 import { createPresets } from "~/utils/createPresets";
-import { FPS, OUTPUT_SIZE } from "./editor/context";
+import { DEFAULT_EXPORT_SETTINGS, FPS, OUTPUT_SIZE } from "./editor/context";
 import { authStore, generalSettingsStore } from "~/store";
 
 type MediaEntry = {
@@ -589,7 +589,11 @@ function createRecordingMutations(
             progress,
             false,
             FPS,
-            OUTPUT_SIZE
+            OUTPUT_SIZE,
+            null,
+            "duplicate",
+            DEFAULT_EXPORT_SETTINGS,
+            false
           );
 
           // Show quick progress animation for existing video
@@ -681,7 +685,11 @@ function createRecordingMutations(
           progress,
           true, // Force re-render
           FPS,
-          OUTPUT_SIZE
+          OUTPUT_SIZE,
+          null,
+          "duplicate",
+          DEFAULT_EXPORT_SETTINGS,
+          false
         );
 
         await commands.copyFileToPath(outputPath, savePath);
@@ -772,7 +780,11 @@ function createRecordingMutations(
             progress,
             false,
             FPS,
-            OUTPUT_SIZE
+            OUTPUT_SIZE,
+            null,
+            "duplicate",
+            DEFAULT_EXPORT_SETTINGS,
+            false
           );
           console.log("Using existing rendered video");
 
@@ -895,8 +907,11 @@ function createRenderProgressChannel(
         else if (
           msg.type === "FrameRendered" &&
           renderState.type === "rendering"
-        )
+        ) {
           renderState.renderedFrames = msg.current_frame;
+          // two-pass exports go over every frame twice
+          renderState.totalFrames = msg.total_frames;
+        }
       })
     );
   };
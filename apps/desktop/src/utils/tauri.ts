@@ -11,6 +11,9 @@ async getRecordingOptions() : Promise<RecordingOptions> {
 async setRecordingOptions(options: RecordingOptions) : Promise<null> {
     return await TAURI_INVOKE("set_recording_options", { options });
 },
+async prepareRecording() : Promise<null> {
+    return await TAURI_INVOKE("prepare_recording");
+},
 async startRecording() : Promise<null> {
     return await TAURI_INVOKE("start_recording");
 },
@@ -23,6 +26,9 @@ async pauseRecording() : Promise<null> {
 async resumeRecording() : Promise<null> {
     return await TAURI_INVOKE("resume_recording");
 },
+async addRecordingMarker() : Promise<null> {
+    return await TAURI_INVOKE("add_recording_marker");
+},
 async listCameras() : Promise<string[]> {
     return await TAURI_INVOKE("list_cameras");
 },
@@ -53,11 +59,77 @@ async focusCapturesPanel() : Promise<void> {
 async getCurrentRecording() : Promise<JsonValue<RecordingInfo | null>> {
     return await TAURI_INVOKE("get_current_recording");
 },
-async exportVideo(videoId: string, progress: TAURI_CHANNEL<RenderProgress>, force: boolean, fps: number, resolutionBase: XY<number>) : Promise<string> {
-    return await TAURI_INVOKE("export_video", { videoId, progress, force, fps, resolutionBase });
+async exportVideo(videoId: string, progress: TAURI_CHANNEL<RenderProgress>, force: boolean, fps: number, resolutionBase: XY<number>, aspectRatio: AspectRatio | null, frameRateConversion: FrameRateConversion, settings: ExportSettings, draft: boolean) : Promise<string> {
+    return await TAURI_INVOKE("export_video", { videoId, progress, force, fps, resolutionBase, aspectRatio, frameRateConversion, settings, draft });
+},
+async exportSplit(videoId: string, progress: TAURI_CHANNEL<RenderProgress>, outputPath: string, fps: number, resolutionBase: XY<number>, aspectRatio: AspectRatio | null, frameRateConversion: FrameRateConversion, settings: ExportSettings, draft: boolean) : Promise<SplitExportManifest> {
+    return await TAURI_INVOKE("export_split", { videoId, progress, outputPath, fps, resolutionBase, aspectRatio, frameRateConversion, settings, draft });
+},
+async exportAnimated(videoId: string, progress: TAURI_CHANNEL<RenderProgress>, fps: number, resolutionBase: XY<number>, aspectRatio: AspectRatio | null, settings: AnimatedExportSettings) : Promise<string> {
+    return await TAURI_INVOKE("export_animated", { videoId, progress, fps, resolutionBase, aspectRatio, settings });
+},
+async exportToStream(videoId: string, progress: TAURI_CHANNEL<RenderProgress>, fps: number, resolutionBase: XY<number>, aspectRatio: AspectRatio | null, target: StreamTarget) : Promise<null> {
+    return await TAURI_INVOKE("export_to_stream", { videoId, progress, fps, resolutionBase, aspectRatio, target });
+},
+async getExportEstimates(videoId: string, resolution: XY<number>, fps: number, settings: ExportSettings) : Promise<ExportEstimates> {
+    return await TAURI_INVOKE("get_export_estimates", { videoId, resolution, fps, settings });
+},
+async cancelExport(videoId: string) : Promise<null> {
+    return await TAURI_INVOKE("cancel_export", { videoId });
+},
+async queueExports(requests: ExportJobRequest[]) : Promise<ExportJob[]> {
+    return await TAURI_INVOKE("queue_exports", { requests });
+},
+async listExportJobs() : Promise<ExportJob[]> {
+    return await TAURI_INVOKE("list_export_jobs");
+},
+async cancelExportJob(id: string) : Promise<null> {
+    return await TAURI_INVOKE("cancel_export_job", { id });
+},
+async clearFinishedExportJobs() : Promise<null> {
+    return await TAURI_INVOKE("clear_finished_export_jobs");
+},
+async setExportQueueParallelism(maxParallel: number) : Promise<null> {
+    return await TAURI_INVOKE("set_export_queue_parallelism", { maxParallel });
+},
+async getStorageInfo() : Promise<StorageInfo> {
+    return await TAURI_INVOKE("get_storage_info");
+},
+async setRecordingsDir(path: string | null, moveProjects: boolean) : Promise<StorageInfo> {
+    return await TAURI_INVOKE("set_recordings_dir", { path, moveProjects });
+},
+async exportProjectBundle(projectPath: string, destination: string) : Promise<null> {
+    return await TAURI_INVOKE("export_project_bundle", { projectPath, destination });
+},
+async importProjectBundle(bundlePath: string) : Promise<string> {
+    return await TAURI_INVOKE("import_project_bundle", { bundlePath });
+},
+async startLanShare(projectPath: string, requireToken: boolean) : Promise<LanShare> {
+    return await TAURI_INVOKE("start_lan_share", { projectPath, requireToken });
+},
+async stopLanShare(projectPath: string) : Promise<void> {
+    await TAURI_INVOKE("stop_lan_share", { projectPath });
+},
+async getLanShare(projectPath: string) : Promise<LanShare | null> {
+    return await TAURI_INVOKE("get_lan_share", { projectPath });
+},
+async getS3StorageConfig() : Promise<S3StorageConfig | null> {
+    return await TAURI_INVOKE("get_s3_storage_config");
+},
+async setS3StorageConfig(config: S3StorageConfig | null) : Promise<null> {
+    return await TAURI_INVOKE("set_s3_storage_config", { config });
 },
-async getExportEstimates(videoId: string, resolution: XY<number>, fps: number) : Promise<ExportEstimates> {
-    return await TAURI_INVOKE("get_export_estimates", { videoId, resolution, fps });
+async testS3StorageConfig(config: S3StorageConfig) : Promise<null> {
+    return await TAURI_INVOKE("test_s3_storage_config", { config });
+},
+async getWebhooks() : Promise<Webhook[]> {
+    return await TAURI_INVOKE("get_webhooks");
+},
+async setWebhooks(webhooks: Webhook[]) : Promise<null> {
+    return await TAURI_INVOKE("set_webhooks", { webhooks });
+},
+async testWebhook(webhook: Webhook) : Promise<null> {
+    return await TAURI_INVOKE("test_webhook", { webhook });
 },
 async copyFileToPath(src: string, dst: string) : Promise<null> {
     return await TAURI_INVOKE("copy_file_to_path", { src, dst });
@@ -83,12 +155,63 @@ async startPlayback(fps: number, resolutionBase: XY<number>) : Promise<null> {
 async stopPlayback() : Promise<null> {
     return await TAURI_INVOKE("stop_playback");
 },
+async startVirtualCamera() : Promise<null> {
+    return await TAURI_INVOKE("start_virtual_camera");
+},
+async stopVirtualCamera() : Promise<void> {
+    await TAURI_INVOKE("stop_virtual_camera");
+},
+async getVirtualCamera() : Promise<string | null> {
+    return await TAURI_INVOKE("get_virtual_camera");
+},
 async setPlayheadPosition(frameNumber: number) : Promise<null> {
     return await TAURI_INVOKE("set_playhead_position", { frameNumber });
 },
 async setProjectConfig(config: ProjectConfiguration) : Promise<null> {
     return await TAURI_INVOKE("set_project_config", { config });
 },
+async applyProjectCommand(command: ProjectCommand) : Promise<ProjectConfiguration> {
+    return await TAURI_INVOKE("apply_project_command", { command });
+},
+async undoProjectEdit() : Promise<ProjectConfiguration> {
+    return await TAURI_INVOKE("undo_project_edit");
+},
+async redoProjectEdit() : Promise<ProjectConfiguration> {
+    return await TAURI_INVOKE("redo_project_edit");
+},
+async generateZoomSegments() : Promise<ZoomSegment[]> {
+    return await TAURI_INVOKE("generate_zoom_segments");
+},
+async setZoomDebug(enabled: boolean) : Promise<void> {
+    await TAURI_INVOKE("set_zoom_debug", { enabled });
+},
+async generateSilenceCuts() : Promise<SilenceCut[]> {
+    return await TAURI_INVOKE("generate_silence_cuts");
+},
+async generateIdlePeriods() : Promise<IdlePeriod[]> {
+    return await TAURI_INVOKE("generate_idle_periods");
+},
+async generateRedactions() : Promise<RedactionSuggestion[]> {
+    return await TAURI_INVOKE("generate_redactions");
+},
+async importCaptions(path: string) : Promise<CaptionSegment[]> {
+    return await TAURI_INVOKE("import_captions", { path });
+},
+async exportCaptions(path: string, captions: CaptionSegment[]) : Promise<null> {
+    return await TAURI_INVOKE("export_captions", { path, captions });
+},
+async generateCaptions(progress: TAURI_CHANNEL<TranscriptionProgress>) : Promise<CaptionSegment[]> {
+    return await TAURI_INVOKE("generate_captions", { progress });
+},
+async getAudioWaveform(recordingSegment: number, track: AudioTrack) : Promise<Waveform | null> {
+    return await TAURI_INVOKE("get_audio_waveform", { recordingSegment, track });
+},
+async getOverlayColorWarnings(overlays: OverlayConfiguration) : Promise<OverlayColorWarning[]> {
+    return await TAURI_INVOKE("get_overlay_color_warnings", { overlays });
+},
+async mergeProjects(projectPaths: string[]) : Promise<string> {
+    return await TAURI_INVOKE("merge_projects", { projectPaths });
+},
 async openEditor(id: string) : Promise<void> {
     await TAURI_INVOKE("open_editor", { id });
 },
@@ -140,6 +263,9 @@ async isCameraWindowOpen() : Promise<boolean> {
 async seekTo(frameNumber: number) : Promise<null> {
     return await TAURI_INVOKE("seek_to", { frameNumber });
 },
+async setPlaybackLoop(enabled: boolean) : Promise<null> {
+    return await TAURI_INVOKE("set_playback_loop", { enabled });
+},
 async sendFeedbackRequest(feedback: string) : Promise<null> {
     return await TAURI_INVOKE("send_feedback_request", { feedback });
 },
@@ -183,6 +309,7 @@ audioInputLevelChange: AudioInputLevelChange,
 authenticationInvalid: AuthenticationInvalid,
 currentRecordingChanged: CurrentRecordingChanged,
 editorStateChanged: EditorStateChanged,
+exportJobEvent: ExportJobEvent,
 newNotification: NewNotification,
 newRecordingAdded: NewRecordingAdded,
 newScreenshotAdded: NewScreenshotAdded,
@@ -190,18 +317,24 @@ recordingMetaChanged: RecordingMetaChanged,
 recordingOptionsChanged: RecordingOptionsChanged,
 recordingStarted: RecordingStarted,
 recordingStopped: RecordingStopped,
+recordingTimer: RecordingTimer,
 renderFrameEvent: RenderFrameEvent,
+requestAddMarker: RequestAddMarker,
 requestNewScreenshot: RequestNewScreenshot,
 requestOpenSettings: RequestOpenSettings,
 requestRestartRecording: RequestRestartRecording,
 requestStartRecording: RequestStartRecording,
 requestStopRecording: RequestStopRecording,
-uploadProgress: UploadProgress
+requestToggleCamera: RequestToggleCamera,
+requestTogglePauseRecording: RequestTogglePauseRecording,
+uploadProgress: UploadProgress,
+zoomDebugChanged: ZoomDebugChanged
 }>({
 audioInputLevelChange: "audio-input-level-change",
 authenticationInvalid: "authentication-invalid",
 currentRecordingChanged: "current-recording-changed",
 editorStateChanged: "editor-state-changed",
+exportJobEvent: "export-job-event",
 newNotification: "new-notification",
 newRecordingAdded: "new-recording-added",
 newScreenshotAdded: "new-screenshot-added",
@@ -209,13 +342,18 @@ recordingMetaChanged: "recording-meta-changed",
 recordingOptionsChanged: "recording-options-changed",
 recordingStarted: "recording-started",
 recordingStopped: "recording-stopped",
+recordingTimer: "recording-timer",
 renderFrameEvent: "render-frame-event",
+requestAddMarker: "request-add-marker",
 requestNewScreenshot: "request-new-screenshot",
 requestOpenSettings: "request-open-settings",
 requestRestartRecording: "request-restart-recording",
 requestStartRecording: "request-start-recording",
 requestStopRecording: "request-stop-recording",
-uploadProgress: "upload-progress"
+requestToggleCamera: "request-toggle-camera",
+requestTogglePauseRecording: "request-toggle-pause-recording",
+uploadProgress: "upload-progress",
+zoomDebugChanged: "zoom-debug-changed"
 })
 
 /** user-defined constants **/
@@ -224,46 +362,84 @@ uploadProgress: "upload-progress"
 
 /** user-defined types **/
 
+export type AnimatedExportSettings = { format: AnimatedFormat; maxFps: number; maxColors: number; dither: GifDither; quality: number; maxSizeKib: number | null }
+export type AnimatedFormat = "gif" | "webP"
+export type AnnotationAnchor = "frame" | "display"
+export type AnnotationAnimation = "none" | "fade" | "grow"
+export type AnnotationKind = "arrow" | "rectangle" | "highlight" | "blur" | "pixelate"
+export type AnnotationSegment = { start: number; end: number; kind: AnnotationKind; from: XY<number>; to: XY<number>; anchor?: AnnotationAnchor; color?: [number, number, number] | null; size: number; animation?: AnnotationAnimation }
 export type AppTheme = "system" | "light" | "dark"
 export type AspectRatio = "wide" | "vertical" | "square" | "classic" | "tall"
 export type Audio = { duration: number; sample_rate: number; channels: number }
-export type AudioConfiguration = { mute: boolean; improve: boolean }
+export type AudioConfiguration = { mute: boolean; improve: boolean; mic?: AudioTrackConfiguration; system?: AudioTrackConfiguration; loudnessTarget?: number | null; preservePitch?: boolean }
 export type AudioInputLevelChange = number
 export type AudioMeta = { path: string }
+export type AudioTrack = "mic" | "system"
+export type AudioTrackConfiguration = { gain?: number; mute?: boolean; pan?: number }
 export type AuthStore = { token: string; user_id: string | null; expires: number; plan: Plan | null; intercom_hash: string | null }
 export type AuthenticationInvalid = null
-export type BackgroundConfiguration = { source: BackgroundSource; blur: number; padding: number; rounding: number; inset: number; crop: Crop | null; shadow?: number; advancedShadow?: ShadowConfiguration | null }
-export type BackgroundSource = { type: "wallpaper"; path: string | null } | { type: "image"; path: string | null } | { type: "color"; value: [number, number, number] } | { type: "gradient"; from: [number, number, number]; to: [number, number, number]; angle?: number }
+export type AutoZoomConfiguration = { deadZoneRadius?: number; maxPanVelocity?: number; scrollLookahead?: number }
+export type BackgroundConfiguration = { source: BackgroundSource; blur: number; padding: number; rounding: number; inset: number; crop: Crop | null; shadow?: number; advancedShadow?: ShadowConfiguration | null; paddingFollowsZoom?: boolean }
+export type BackgroundSource = { type: "wallpaper"; path: string | null } | { type: "image"; path: string | null } | { type: "color"; value: [number, number, number] } | { type: "gradient"; from: [number, number, number]; to: [number, number, number]; angle?: number; kind?: GradientKind } | { type: "screen" } | { type: "transparent" }
 export type Bounds = { x: number; y: number; width: number; height: number }
-export type Camera = { hide: boolean; mirror: boolean; position: CameraPosition; size: number; zoom_size: number | null; rounding?: number; shadow?: number; advanced_shadow?: ShadowConfiguration | null }
+export type Camera = { hide: boolean; mirror: boolean; position: CameraPosition; size: number; zoom_size: number | null; rounding?: number; shadow?: number; advanced_shadow?: ShadowConfiguration | null; border?: CameraBorder | null; auto_dodge?: boolean }
+export type CameraBorder = { width: number; color: [number, number, number] }
 export type CameraMeta = { path: string; fps?: number }
 export type CameraPosition = { x: CameraXPosition; y: CameraYPosition }
 export type CameraXPosition = "left" | "center" | "right"
 export type CameraYPosition = "top" | "bottom"
+export type CaptionSegment = { start: number; end: number; text: string }
+export type CaptionsConfiguration = { burnIn?: boolean; font?: TextFont; size: number; color: [number, number, number]; background: [number, number, number]; backgroundOpacity: number }
 export type CaptureArea = { screen: CaptureScreen; bounds: Bounds }
+export type CaptureRegion = { position: XY<number>; size: XY<number> }
 export type CaptureScreen = { id: number; name: string; refresh_rate: number }
-export type CaptureWindow = { id: number; owner_name: string; name: string; bounds: Bounds; refresh_rate: number }
+export type CaptureWindow = { id: number; owner_name: string; name: string; bounds: Bounds; refresh_rate: number; isolated?: boolean }
+export type ClickHighlightConfiguration = { style?: ClickHighlightStyle; radius: number; duration: number }
+export type ClickHighlightStyle = "ripple" | "highlight"
+export type ColorGradeConfiguration = { brightness: number; contrast: number; saturation: number; lut?: string | null; lutIntensity?: number }
+export type ColorPrimaries = "bt709" | "displayP3" | "bt2020"
 export type CommercialLicense = { licenseKey: string; expiryDate: number | null; refresh: number; activatedOn: number }
 export type Crop = { position: XY<number>; size: XY<number> }
 export type CurrentRecordingChanged = null
 export type CursorAnimationStyle = "regular" | "slow" | "fast"
-export type CursorConfiguration = { hideWhenIdle: boolean; size: number; type: CursorType; animationStyle: CursorAnimationStyle; tension: number; mass: number; friction: number; raw?: boolean; motionBlur?: number }
-export type CursorMeta = { imagePath: string; hotspot: XY<number> }
+export type CursorConfiguration = { hideWhenIdle: boolean; size: number; type: CursorType; animationStyle: CursorAnimationStyle; tension: number; mass: number; friction: number; raw?: boolean; motionBlur?: number; theme?: CursorTheme; clickHighlight?: ClickHighlightConfiguration | null; trail?: CursorTrailConfiguration | null }
+export type CursorMeta = { imagePath: string; hotspot: XY<number>; shape?: CursorShape | null }
+export type CursorShape = "arrow" | "iBeam" | "pointingHand" | "crosshair"
+export type CursorTheme = "recorded" | "macos" | "windows"
+export type CursorTrailConfiguration = { length: number; spacing: number; width: number; opacity: number; color: [number, number, number] }
 export type CursorType = "pointer" | "circle"
 export type Cursors = { [key in string]: string } | { [key in string]: CursorMeta }
+export type DeterminismConfiguration = { enabled: boolean; seed?: number }
 export type Display = { path: string; fps?: number }
 export type EditorStateChanged = { playhead_position: number }
+export type ExportCodec = "h264" | "hevc" | "proRes422" | "proRes4444" | "ffv1" | "utVideo" | "vp9" | "av1"
 export type ExportEstimates = { duration_seconds: number; estimated_time_seconds: number; estimated_size_mb: number }
-export type Flags = { recordMouseState: boolean; split: boolean }
-export type GeneralSettingsStore = { instanceId?: string; uploadIndividualFiles?: boolean; openEditorAfterRecording?: boolean; hideDockIcon?: boolean; hapticsEnabled?: boolean; autoCreateShareableLink?: boolean; enableNotifications?: boolean; disableAutoOpenLinks?: boolean; hasCompletedStartup?: boolean; theme?: AppTheme; commercialLicense?: CommercialLicense | null; lastVersion?: string | null }
+export type ExportJob = { id: string; request: ExportJobRequest; status: ExportJobStatus }
+export type ExportJobEvent = { type: "updated"; job: ExportJob } | { type: "progress"; id: string; progress: ExportProgress } | { type: "removed"; id: string }
+export type ExportJobRequest = { videoId: string; outputPath: string; fps: number; resolutionBase: XY<number>; aspectRatio: AspectRatio | null; frameRateConversion: FrameRateConversion; settings: ExportSettings; draft?: boolean }
+export type ExportJobStatus = { type: "queued" } | { type: "running" } | { type: "done"; path: string } | { type: "failed"; error: string } | { type: "cancelled" }
+export type ExportProgress = { stage: ExportStage; framesDone: number; totalFrames: number; fps: number; etaSecs: number | null }
+export type ExportSettings = { codec: ExportCodec; bitRate: number | null; quality: number | null; targetSizeKib?: number | null; hdr10?: boolean; skipStaticFrames?: boolean }
+export type ExportStage = "firstPass" | "rendering" | "joining"
+export type Flags = { recordMouseState: boolean; split: boolean; freeTierWatermark: boolean }
+export type FrameRateConversion = "duplicate" | "blend"
+export type GeneralSettingsStore = { instanceId?: string; uploadIndividualFiles?: boolean; openEditorAfterRecording?: boolean; hideDockIcon?: boolean; hapticsEnabled?: boolean; autoCreateShareableLink?: boolean; enableNotifications?: boolean; disableAutoOpenLinks?: boolean; disableProxyMedia?: boolean; hasCompletedStartup?: boolean; theme?: AppTheme; commercialLicense?: CommercialLicense | null; lastVersion?: string | null; watchFolders?: string[]; recordingsDir?: string | null; uploadSpeedLimitMbps?: number | null }
+export type GifDither = "none" | "ordered" | "floydSteinberg"
+export type GradientKind = "linear" | "radial"
 export type HapticPattern = "Alignment" | "LevelChange" | "Generic"
 export type HapticPerformanceTime = "Default" | "Now" | "DrawCompleted"
+export type HeatmapPalette = "viridis" | "cividis"
 export type Hotkey = { code: string; meta: boolean; ctrl: boolean; alt: boolean; shift: boolean }
-export type HotkeyAction = "startRecording" | "stopRecording" | "restartRecording" | "takeScreenshot"
-export type HotkeysConfiguration = { show: boolean }
+export type HotkeyAction = "startRecording" | "stopRecording" | "restartRecording" | "takeScreenshot" | "pauseRecording" | "addMarker" | "toggleCamera"
+export type HotkeysConfiguration = { show: boolean; filter?: KeystrokeFilter; position?: HotkeysPosition; duration?: number; size?: number }
+export type HotkeysPosition = "bottomLeft" | "bottomCenter" | "bottomRight"
 export type HotkeysStore = { hotkeys: { [key in HotkeyAction]: Hotkey } }
+export type IdlePeriod = { recordingSegment: number; start: number; end: number; timelineStart: number; timelineEnd: number }
 export type JsonValue<T> = [T]
-export type MultipleSegment = { display: Display; camera?: CameraMeta | null; audio?: AudioMeta | null; cursor?: string | null }
+export type KeystrokeFilter = "shortcutsOnly" | "allKeys"
+export type LanShare = { url: string; token: string | null }
+export type Marker = { time: number; name: string }
+export type MultipleSegment = { display: Display; additional_displays?: Display[]; source?: SegmentSource; region?: CaptureRegion | null; camera?: CameraMeta | null; audio?: AudioMeta | null; system_audio?: AudioMeta | null; cursor?: string | null }
 export type MultipleSegments = { segments: MultipleSegment[]; cursors: Cursors }
 export type NewNotification = { title: string; body: string; is_error: boolean }
 export type NewRecordingAdded = { path: string }
@@ -271,45 +447,86 @@ export type NewScreenshotAdded = { path: string }
 export type OSPermission = "screenRecording" | "camera" | "microphone" | "accessibility"
 export type OSPermissionStatus = "notNeeded" | "empty" | "granted" | "denied"
 export type OSPermissionsCheck = { screenRecording: OSPermissionStatus; microphone: OSPermissionStatus; camera: OSPermissionStatus; accessibility: OSPermissionStatus }
+export type OverlayColorWarning = { overlay: OverlayKind; color: [number, number, number]; content: ScreenContent; contrast: number }
+export type OverlayConfiguration = { palette?: OverlayPalette; heatmap?: HeatmapPalette; clickHighlightColor?: [number, number, number] | null; annotationColor?: [number, number, number] | null }
+export type OverlayKind = "clickHighlight" | "annotation"
+export type OverlayPalette = "okabeIto" | "tolVibrant"
 export type Plan = { upgraded: boolean; manual: boolean; last_checked: number }
 export type PreCreatedVideo = { id: string; link: string; config: S3UploadMeta }
 export type Preset = { name: string; config: ProjectConfiguration }
 export type PresetsStore = { presets: Preset[]; default: number | null }
-export type ProjectConfiguration = { aspectRatio: AspectRatio | null; background: BackgroundConfiguration; camera: Camera; audio: AudioConfiguration; cursor: CursorConfiguration; hotkeys: HotkeysConfiguration; timeline?: TimelineConfiguration | null }
+export type ProjectCommand = { type: "addZoomSegment"; segment: ZoomSegment } | { type: "insertZoomSegment"; index: number; segment: ZoomSegment } | { type: "updateZoomSegment"; index: number; segment: ZoomSegment } | { type: "removeZoomSegment"; index: number } | { type: "splitAt"; time: number } | { type: "deleteRange"; start: number; end: number } | { type: "deleteSegment"; index: number } | { type: "trimSegment"; index: number; start: number; end: number } | { type: "addMarker"; time: number; name: string } | { type: "removeMarker"; index: number } | { type: "setTimeline"; timeline: TimelineConfiguration | null } | { type: "setBackground"; background: BackgroundConfiguration } | { type: "setCamera"; camera: Camera } | { type: "setAudio"; audio: AudioConfiguration } | { type: "setCursor"; cursor: CursorConfiguration } | { type: "setProject"; project: ProjectConfiguration }
+export type ProjectConfiguration = { version?: number; aspectRatio: AspectRatio | null; background: BackgroundConfiguration; camera: Camera; audio: AudioConfiguration; cursor: CursorConfiguration; hotkeys: HotkeysConfiguration; timeline?: TimelineConfiguration | null; determinism?: DeterminismConfiguration; autoZoom?: AutoZoomConfiguration; overlays?: OverlayConfiguration; takes?: Take[]; watermark?: WatermarkConfiguration | null; intro?: TitleCard | null; outro?: TitleCard | null; captions?: CaptionsConfiguration; colorGrade?: ColorGradeConfiguration }
 export type ProjectRecordings = { segments: SegmentRecordings[] }
 export type RecordingInfo = { captureTarget: ScreenCaptureTarget }
 export type RecordingMeta = ({ segment: SingleSegment } | { inner: MultipleSegments }) & { pretty_name: string; sharing?: SharingMeta | null }
 export type RecordingMetaChanged = { id: string }
-export type RecordingOptions = { captureTarget: ScreenCaptureTarget; cameraLabel: string | null; audioInputName: string | null }
+export type RecordingOptions = { captureTarget: ScreenCaptureTarget; cameraLabel: string | null; audioInputName: string | null; sampleUiElements?: boolean; recordKeyboard?: boolean; additionalDisplays?: CaptureScreen[]; replayBufferSecs?: number | null; maxDurationSecs?: number | null; captureSystemAudio?: boolean }
 export type RecordingOptionsChanged = null
 export type RecordingStarted = null
 export type RecordingStopped = { path: string }
+export type RecordingTimer = { type: "startsIn"; secs: number } | { type: "stopsIn"; secs: number }
+export type RedactionSuggestion = { kind: SensitiveKind; preview: string; annotation: AnnotationSegment }
 export type RenderFrameEvent = { frame_number: number; fps: number; resolution_base: XY<number> }
-export type RenderProgress = { type: "Starting"; total_frames: number } | { type: "EstimatedTotalFrames"; total_frames: number } | { type: "FrameRendered"; current_frame: number }
+export type RenderProgress = { type: "Starting"; total_frames: number } | { type: "EstimatedTotalFrames"; total_frames: number } | { type: "FrameRendered"; current_frame: number; total_frames: number; stage: ExportStage; eta_secs: number | null }
+export type RequestAddMarker = null
 export type RequestNewScreenshot = null
 export type RequestOpenSettings = { page: string }
 export type RequestRestartRecording = null
 export type RequestStartRecording = null
 export type RequestStopRecording = null
+export type RequestToggleCamera = null
+export type RequestTogglePauseRecording = null
+export type S3StorageConfig = { endpoint: string; region: string; bucket: string; accessKeyId: string; secretAccessKey: string; forcePathStyle?: boolean; publicUrl?: string | null }
 export type S3UploadMeta = { id: string; user_id: string; aws_region?: string; aws_bucket?: string; aws_endpoint?: string }
 export type ScreenCaptureTarget = ({ variant: "window" } & CaptureWindow) | ({ variant: "screen" } & CaptureScreen) | ({ variant: "area" } & CaptureArea)
-export type SegmentRecordings = { display: Video; camera: Video | null; audio: Audio | null }
+export type ScreenContent = "light" | "dark"
+export type SegmentRecordings = { display: Video; additional_displays: Video[]; camera: Video | null; audio: Audio | null; system_audio: Audio | null }
+export type SegmentSource = { type: "display" } | { type: "window"; owner_name: string; name: string }
+export type SensitiveKind = "email" | "apiKey" | "cardNumber"
 export type SerializedEditorInstance = { framesSocketUrl: string; recordingDuration: number; savedProjectConfig: ProjectConfiguration; recordings: ProjectRecordings; path: string; prettyName: string }
-export type ShadowConfiguration = { size: number; opacity: number; blur: number }
+export type ShadowConfiguration = { size: number; opacity: number; blur: number; offset_x?: number; offset_y?: number }
 export type SharingMeta = { id: string; link: string }
 export type ShowCapWindow = "Setup" | "Main" | { Settings: { page: string | null } } | { Editor: { project_id: string } } | "PrevRecordings" | "WindowCaptureOccluder" | { CaptureArea: { screen: CaptureScreen } } | { Camera: { ws_port: number } } | { InProgressRecording: { position: [number, number] | null } } | "Upgrade" | "SignIn"
+export type SilenceCut = { recordingSegment: number; start: number; end: number }
 export type SingleSegment = { display: Display; camera?: CameraMeta | null; audio?: AudioMeta | null; cursor?: string | null }
-export type TimelineConfiguration = { segments: TimelineSegment[]; zoomSegments: ZoomSegment[] }
-export type TimelineSegment = { recordingSegment?: number; timescale: number; start: number; end: number }
+export type SlideDirection = "left" | "right" | "up" | "down"
+export type SpeedSegment = { start: number; end: number; factor: number }
+export type SplitExportManifest = { fps: number; frameCount: number; duration: number; screen: SplitExportTrack; camera: SplitExportTrack | null }
+export type SplitExportTrack = { path: string; width: number; height: number }
+export type StorageInfo = { recordingsDir: string; isDefault: boolean; availableBytes: number | null }
+export type StreamTarget = { url: string; streamKey: string; bitRate: number | null }
+export type Take = { path: string }
+export type TextAnimation = "none" | "fade" | "slideFromBottom" | "slideFromLeft"
+export type TextFont = "sans" | "serif" | "mono"
+export type TextSegment = { start: number; end: number; content: string; font?: TextFont; size: number; color: [number, number, number]; position: XY<number>; animation?: TextAnimation }
+export type TimelineConfiguration = { segments: TimelineSegment[]; zoomSegments: ZoomSegment[]; speedSegments?: SpeedSegment[]; markers?: Marker[]; textSegments?: TextSegment[]; annotationSegments?: AnnotationSegment[]; captionSegments?: CaptionSegment[] }
+export type TimelineSegment = { recordingSegment?: number; timescale: number; start: number; end: number; display?: number; cameraPosition?: CameraPosition | null; transition?: Transition | null }
+export type TitleCard = { duration: number; title: string; subtitle?: string; font?: TextFont; background: [number, number, number]; textColor: [number, number, number]; fade?: boolean }
+export type TranscriptionProgress = { segment: number; segments: number; fraction: number }
+export type TransferFunction = "sdr" | "pq" | "hlg"
+export type Transition = { kind: TransitionKind; duration: number }
+export type TransitionKind = { type: "crossFade" } | { type: "dip"; color: [number, number, number] } | { type: "slide"; direction: SlideDirection }
 export type UploadMode = { Initial: { pre_created_video: PreCreatedVideo | null } } | "Reupload"
 export type UploadProgress = { progress: number; message: string }
 export type UploadResult = { Success: string } | "NotAuthenticated" | "PlanCheckFailed" | "UpgradeRequired"
-export type Video = { duration: number; width: number; height: number; fps: number }
+export type Video = { duration: number; width: number; height: number; fps: number; color: VideoColor }
+export type VideoColor = { transfer: TransferFunction; primaries: ColorPrimaries }
 export type VideoRecordingMetadata = { duration: number; size: number }
 export type VideoType = "screen" | "output" | "camera"
+export type WatermarkConfiguration = { path: string; corner?: WatermarkCorner; scale: number; opacity: number; freeTierOnly?: boolean }
+export type WatermarkCorner = "topLeft" | "topRight" | "bottomLeft" | "bottomRight"
+export type Waveform = { sampleRate: number; levels: WaveformLevel[] }
+export type WaveformLevel = { bucketSamples: number; peaks: number[]; rms: number[] }
+export type Webhook = { url: string; events?: WebhookEvent[]; secret?: string | null; enabled?: boolean }
+export type WebhookEvent = "recordingStarted" | "recordingStopped" | "exportFinished" | "uploadComplete" | "shareLinkCreated"
 export type XY<T> = { x: T; y: T }
-export type ZoomMode = "auto" | { manual: { x: number; y: number } }
-export type ZoomSegment = { start: number; end: number; amount: number; mode: ZoomMode }
+export type ZoomDebugChanged = ZoomDebugInfo
+export type ZoomDebugInfo = { time: number; segmentIndex: number | null; prevSegmentIndex: number | null; t: number; displayAmount: number; topLeft: XY<number>; bottomRight: XY<number>; autoFocus: XY<number> }
+export type ZoomEasing = "linear" | "easeIn" | "easeOut" | "easeInOut"
+export type ZoomKeyframe = { time: number; amount: number; position?: XY<number> | null; easing?: ZoomEasing }
+export type ZoomMode = "auto" | { manual: { x: number; y: number } } | { window: { window_id: number } } | { pan: { x: number; y: number } }
+export type ZoomSegment = { start: number; end: number; amount: number; mode: ZoomMode; transitionDurationIn?: number | null; transitionDurationOut?: number | null; keyframes?: ZoomKeyframe[] }
 
 /** tauri-specta globals **/
 
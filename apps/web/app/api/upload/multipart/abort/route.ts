@@ -0,0 +1,39 @@
+import { AbortMultipartUploadCommand } from "@aws-sdk/client-s3";
+import type { NextRequest } from "next/server";
+import { getMultipartContext } from "@/utils/multipart";
+
+export async function POST(request: NextRequest) {
+  try {
+    const { fileKey, uploadId } = await request.json();
+
+    const context = await getMultipartContext(request, fileKey);
+    if (context.error) return context.error;
+    const { s3Client, bucketName } = context;
+
+    if (!uploadId) {
+      return Response.json(
+        { error: "Missing required fields" },
+        { status: 400 }
+      );
+    }
+
+    await s3Client.send(
+      new AbortMultipartUploadCommand({
+        Bucket: bucketName,
+        Key: fileKey,
+        UploadId: uploadId,
+      })
+    );
+
+    return Response.json({ success: true });
+  } catch (error) {
+    console.error("Error aborting multipart upload", error);
+    return Response.json(
+      {
+        error: "Error aborting multipart upload",
+        details: error instanceof Error ? error.message : String(error),
+      },
+      { status: 500 }
+    );
+  }
+}
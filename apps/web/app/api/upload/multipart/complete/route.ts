@@ -0,0 +1,64 @@
+import { CompleteMultipartUploadCommand } from "@aws-sdk/client-s3";
+import type { NextRequest } from "next/server";
+import { clientEnv } from "@cap/env";
+import { getMultipartContext } from "@/utils/multipart";
+
+export async function POST(request: NextRequest) {
+  try {
+    const { fileKey, uploadId, parts } = (await request.json()) as {
+      fileKey?: string;
+      uploadId?: string;
+      parts?: { partNumber: number; etag: string }[];
+    };
+
+    const context = await getMultipartContext(request, fileKey);
+    if (context.error) return context.error;
+    const { s3Client, bucketName } = context;
+
+    if (!uploadId || !parts || parts.length === 0) {
+      return Response.json(
+        { error: "Missing required fields" },
+        { status: 400 }
+      );
+    }
+
+    await s3Client.send(
+      new CompleteMultipartUploadCommand({
+        Bucket: bucketName,
+        Key: fileKey,
+        UploadId: uploadId,
+        MultipartUpload: {
+          Parts: [...parts]
+            .sort((a, b) => a.partNumber - b.partNumber)
+            .map((part) => ({ PartNumber: part.partNumber, ETag: part.etag })),
+        },
+      })
+    );
+
+    const videoId = fileKey!.split("/")[1];
+    if (videoId) {
+      try {
+        await fetch(`${clientEnv.NEXT_PUBLIC_WEB_URL}/api/revalidate`, {
+          method: "POST",
+          headers: {
+            "Content-Type": "application/json",
+          },
+          body: JSON.stringify({ videoId }),
+        });
+      } catch (revalidateError) {
+        console.error("Failed to revalidate page:", revalidateError);
+      }
+    }
+
+    return Response.json({ success: true });
+  } catch (error) {
+    console.error("Error completing multipart upload", error);
+    return Response.json(
+      {
+        error: "Error completing multipart upload",
+        details: error instanceof Error ? error.message : String(error),
+      },
+      { status: 500 }
+    );
+  }
+}
@@ -0,0 +1,45 @@
+import { CreateMultipartUploadCommand } from "@aws-sdk/client-s3";
+import type { NextRequest } from "next/server";
+import { contentTypeForKey, getMultipartContext } from "@/utils/multipart";
+
+export async function POST(request: NextRequest) {
+  try {
+    const { fileKey, duration, bandwidth, resolution, videoCodec, audioCodec } =
+      await request.json();
+
+    const context = await getMultipartContext(request, fileKey);
+    if (context.error) return context.error;
+    const { user, s3Client, bucketName } = context;
+
+    const { UploadId } = await s3Client.send(
+      new CreateMultipartUploadCommand({
+        Bucket: bucketName,
+        Key: fileKey,
+        ContentType: contentTypeForKey(fileKey),
+        Metadata: {
+          userid: user.id,
+          duration: duration ?? "",
+          bandwidth: bandwidth ?? "",
+          resolution: resolution ?? "",
+          videocodec: videoCodec ?? "",
+          audiocodec: audioCodec ?? "",
+        },
+      })
+    );
+
+    if (!UploadId) {
+      throw new Error("No upload id was returned");
+    }
+
+    return Response.json({ uploadId: UploadId });
+  } catch (error) {
+    console.error("Error creating multipart upload", error);
+    return Response.json(
+      {
+        error: "Error creating multipart upload",
+        details: error instanceof Error ? error.message : String(error),
+      },
+      { status: 500 }
+    );
+  }
+}
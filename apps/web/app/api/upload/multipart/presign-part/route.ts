@@ -0,0 +1,43 @@
+import { UploadPartCommand } from "@aws-sdk/client-s3";
+import { getSignedUrl } from "@aws-sdk/s3-request-presigner";
+import type { NextRequest } from "next/server";
+import { getMultipartContext } from "@/utils/multipart";
+
+export async function POST(request: NextRequest) {
+  try {
+    const { fileKey, uploadId, partNumber } = await request.json();
+
+    const context = await getMultipartContext(request, fileKey);
+    if (context.error) return context.error;
+    const { s3Client, bucketName } = context;
+
+    if (!uploadId || typeof partNumber !== "number") {
+      return Response.json(
+        { error: "Missing required fields" },
+        { status: 400 }
+      );
+    }
+
+    const presignedUrl = await getSignedUrl(
+      s3Client,
+      new UploadPartCommand({
+        Bucket: bucketName,
+        Key: fileKey,
+        UploadId: uploadId,
+        PartNumber: partNumber,
+      }),
+      { expiresIn: 3600 }
+    );
+
+    return Response.json({ presignedUrl });
+  } catch (error) {
+    console.error("Error presigning upload part", error);
+    return Response.json(
+      {
+        error: "Error presigning upload part",
+        details: error instanceof Error ? error.message : String(error),
+      },
+      { status: 500 }
+    );
+  }
+}
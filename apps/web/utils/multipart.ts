@@ -0,0 +1,69 @@
+import { db } from "@cap/database";
+import { getCurrentUser } from "@cap/database/auth/session";
+import { s3Buckets } from "@cap/database/schema";
+import { eq } from "drizzle-orm";
+import { cookies } from "next/headers";
+import type { NextRequest } from "next/server";
+import { createS3Client, getS3Bucket } from "@/utils/s3";
+
+// The S3 client and bucket for the user making a multipart upload request, who can
+// only upload to their own keys
+export async function getMultipartContext(
+  request: NextRequest,
+  fileKey: string | undefined
+) {
+  const token = request.headers.get("authorization")?.split(" ")[1];
+  if (token) {
+    cookies().set({
+      name: "next-auth.session-token",
+      value: token,
+      path: "/",
+      sameSite: "none",
+      secure: true,
+      httpOnly: true,
+    });
+  }
+
+  const user = await getCurrentUser();
+  if (!user) {
+    return { error: Response.json({ error: true }, { status: 401 }) };
+  }
+
+  if (!fileKey || !fileKey.startsWith(`${user.id}/`)) {
+    return {
+      error: Response.json({ error: "Invalid file key" }, { status: 400 }),
+    };
+  }
+
+  const [bucket] = await db
+    .select()
+    .from(s3Buckets)
+    .where(eq(s3Buckets.ownerId, user.id));
+
+  const s3Client = await createS3Client(
+    bucket
+      ? {
+          endpoint: bucket.endpoint || undefined,
+          region: bucket.region,
+          accessKeyId: bucket.accessKeyId,
+          secretAccessKey: bucket.secretAccessKey,
+        }
+      : null
+  );
+
+  return { user, s3Client, bucketName: await getS3Bucket(bucket) };
+}
+
+export function contentTypeForKey(fileKey: string) {
+  return fileKey.endsWith(".aac")
+    ? "audio/aac"
+    : fileKey.endsWith(".webm")
+    ? "audio/webm"
+    : fileKey.endsWith(".mp4")
+    ? "video/mp4"
+    : fileKey.endsWith(".mp3")
+    ? "audio/mpeg"
+    : fileKey.endsWith(".m3u8")
+    ? "application/x-mpegURL"
+    : "video/mp2t";
+}
@@ -0,0 +1,2 @@
+pub mod silence;
+pub mod waveform;
@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+use cap_project::TimelineConfiguration;
+use serde::Serialize;
+use specta::Type;
+
+// Audio is judged in windows this long, which is short enough to find the gaps between
+// sentences without single quiet samples counting as silence
+const WINDOW_SECS: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SilenceOptions {
+    // windows quieter than this in every track are silent, in dBFS
+    pub threshold_db: f32,
+    // shorter silences are natural pauses and are left alone
+    pub min_duration: f64,
+    // kept on both sides of a cut so speech isn't clipped
+    pub padding: f64,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        Self {
+            threshold_db: -45.0,
+            min_duration: 1.0,
+            padding: 0.2,
+        }
+    }
+}
+
+// A stretch of a recording segment suggested for cutting, in seconds into the segment
+#[derive(Type, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SilenceCut {
+    pub recording_segment: u32,
+    pub start: f64,
+    pub end: f64,
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+// Finds where all of a segment's `tracks` are silent at once, as ranges in seconds with
+// the padding already taken off. Tracks are interleaved f32 samples sharing `channels` and
+// `sample_rate`, and a track that ends early is silent from there on.
+pub fn detect_silences(
+    tracks: &[&[f32]],
+    channels: usize,
+    sample_rate: u32,
+    options: &SilenceOptions,
+) -> Vec<Range<f64>> {
+    let window_samples = ((sample_rate as f64 * WINDOW_SECS) as usize).max(1);
+    let window = window_samples * channels;
+    let window_secs = window_samples as f64 / sample_rate as f64;
+    let threshold = 10f32.powf(options.threshold_db / 20.0);
+
+    let len = tracks.iter().map(|t| t.len()).max().unwrap_or(0);
+    let duration = (len / channels) as f64 / sample_rate as f64;
+    let windows = len.div_ceil(window);
+
+    let is_silent = |i: usize| {
+        tracks.iter().all(|track| {
+            let start = (i * window).min(track.len());
+            let end = ((i + 1) * window).min(track.len());
+            rms(&track[start..end]) < threshold
+        })
+    };
+
+    let mut silences = vec![];
+    let mut run_start = None;
+
+    for i in 0..=windows {
+        let silent = i < windows && is_silent(i);
+
+        match run_start {
+            None if silent => run_start = Some(i),
+            Some(start) if !silent => {
+                run_start = None;
+
+                let start = start as f64 * window_secs;
+                let end = (i as f64 * window_secs).min(duration);
+                if end - start >= options.min_duration {
+                    let cut = start + options.padding..end - options.padding;
+                    if cut.end > cut.start {
+                        silences.push(cut);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    silences
+}
+
+// Narrows each recording segment's silences down to the parts still on the timeline, so
+// applying a cut never brings back something that was already trimmed. `silences` is
+// indexed by recording segment. Without a timeline every segment is used in full.
+pub fn suggest_cuts(
+    timeline: Option<&TimelineConfiguration>,
+    silences: &[Vec<Range<f64>>],
+) -> Vec<SilenceCut> {
+    let Some(timeline) = timeline else {
+        return silences
+            .iter()
+            .enumerate()
+            .flat_map(|(i, silences)| {
+                silences.iter().map(move |s| SilenceCut {
+                    recording_segment: i as u32,
+                    start: s.start,
+                    end: s.end,
+                })
+            })
+            .collect();
+    };
+
+    timeline
+        .segments
+        .iter()
+        .flat_map(|segment| {
+            silences
+                .get(segment.recording_segment as usize)
+                .into_iter()
+                .flatten()
+                .filter_map(move |s| {
+                    let start = s.start.max(segment.start);
+                    let end = s.end.min(segment.end);
+
+                    (end > start).then_some(SilenceCut {
+                        recording_segment: segment.recording_segment,
+                        start,
+                        end,
+                    })
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use cap_project::TimelineSegment;
+
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    // mono track alternating between a loud tone and silence, with durations in seconds
+    fn track(parts: &[(bool, f64)]) -> Vec<f32> {
+        parts
+            .iter()
+            .flat_map(|&(loud, secs)| {
+                let samples = (secs * SAMPLE_RATE as f64) as usize;
+                (0..samples).map(move |i| {
+                    if loud {
+                        if i % 2 == 0 {
+                            0.5
+                        } else {
+                            -0.5
+                        }
+                    } else {
+                        0.0
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn approx(ranges: &[Range<f64>]) -> Vec<(i64, i64)> {
+        ranges
+            .iter()
+            .map(|r| {
+                (
+                    (r.start * 100.0).round() as i64,
+                    (r.end * 100.0).round() as i64,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_long_silences() {
+        let audio = track(&[
+            (true, 1.0),
+            (false, 2.0),
+            (true, 1.0),
+            (false, 0.5),
+            (true, 1.0),
+        ]);
+
+        let silences = detect_silences(&[&audio], 1, SAMPLE_RATE, &SilenceOptions::default());
+
+        assert_eq!(approx(&silences), vec![(120, 280)]);
+    }
+
+    #[test]
+    fn silent_only_when_every_track_is() {
+        let mic = track(&[(true, 1.0), (false, 3.0)]);
+        let system = track(&[(false, 2.0), (true, 1.0)]);
+
+        let silences =
+            detect_silences(&[&mic, &system], 1, SAMPLE_RATE, &SilenceOptions::default());
+
+        // the system track ends a second before the mic does, and is silent from there on
+        assert_eq!(approx(&silences), vec![(120, 180), (320, 380)]);
+    }
+
+    #[test]
+    fn cuts_stay_within_timeline() {
+        let segment = |recording_segment, start, end| TimelineSegment {
+            recording_segment,
+            timescale: 1.0,
+            start,
+            end,
+            display: 0,
+            camera_position: None,
+            transition: None,
+        };
+        let timeline = TimelineConfiguration {
+            segments: vec![segment(0, 0.0, 5.0), segment(1, 3.0, 10.0)],
+            zoom_segments: vec![],
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        };
+
+        let cuts = suggest_cuts(Some(&timeline), &[vec![4.0..8.0], vec![1.0..4.0, 6.0..7.0]]);
+
+        assert_eq!(
+            cuts,
+            vec![
+                SilenceCut {
+                    recording_segment: 0,
+                    start: 4.0,
+                    end: 5.0
+                },
+                SilenceCut {
+                    recording_segment: 1,
+                    start: 3.0,
+                    end: 4.0
+                },
+                SilenceCut {
+                    recording_segment: 1,
+                    start: 6.0,
+                    end: 7.0
+                },
+            ]
+        );
+    }
+}
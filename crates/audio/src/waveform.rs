@@ -0,0 +1,250 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use specta::Type;
+
+// Finest level buckets cover this many samples, around 10ms at 48kHz
+const BASE_BUCKET_SAMPLES: usize = 512;
+// each level's buckets span this many of the level below's
+const LEVEL_FACTOR: usize = 4;
+const LEVELS: usize = 5;
+
+const CACHE_MAGIC: &[u8; 8] = b"CAPWAVE\0";
+// bump when the cache layout or how levels are computed changes
+const CACHE_VERSION: u32 = 1;
+
+// An audio track boiled down for drawing, at several zoom levels from finest to coarsest.
+// Values are amplitudes across all channels, scaled so 255 is full scale.
+#[derive(Type, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Waveform {
+    pub sample_rate: u32,
+    pub levels: Vec<WaveformLevel>,
+}
+
+#[derive(Type, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformLevel {
+    // source samples per bucket, excluding channels
+    pub bucket_samples: u32,
+    pub peaks: Vec<u8>,
+    pub rms: Vec<u8>,
+}
+
+impl WaveformLevel {
+    pub fn bucket_secs(&self, sample_rate: u32) -> f64 {
+        self.bucket_samples as f64 / sample_rate as f64
+    }
+}
+
+fn quantize(amplitude: f32) -> u8 {
+    (amplitude.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl Waveform {
+    // `samples` are interleaved f32
+    pub fn generate(samples: &[f32], channels: usize, sample_rate: u32) -> Self {
+        // peaks and mean squares, kept unquantized until every level has been built
+        let mut level: (Vec<f32>, Vec<f32>) = samples
+            .chunks(BASE_BUCKET_SAMPLES * channels)
+            .map(|bucket| {
+                let peak = bucket.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+                let mean_square = bucket.iter().map(|s| s * s).sum::<f32>() / bucket.len() as f32;
+                (peak, mean_square)
+            })
+            .unzip();
+
+        let mut levels = vec![];
+        let mut bucket_samples = BASE_BUCKET_SAMPLES;
+
+        for _ in 0..LEVELS {
+            levels.push(WaveformLevel {
+                bucket_samples: bucket_samples as u32,
+                peaks: level.0.iter().copied().map(quantize).collect(),
+                rms: level.1.iter().map(|ms| quantize(ms.sqrt())).collect(),
+            });
+
+            level = (
+                level
+                    .0
+                    .chunks(LEVEL_FACTOR)
+                    .map(|peaks| peaks.iter().copied().fold(0.0, f32::max))
+                    .collect(),
+                level
+                    .1
+                    .chunks(LEVEL_FACTOR)
+                    .map(|ms| ms.iter().sum::<f32>() / ms.len() as f32)
+                    .collect(),
+            );
+            bucket_samples *= LEVEL_FACTOR;
+        }
+
+        Self {
+            sample_rate,
+            levels,
+        }
+    }
+
+    // The coarsest level whose buckets are no longer than `bucket_secs`, for drawing about
+    // one bucket per pixel. Falls back to the finest level when zoomed in further than it.
+    pub fn level(&self, bucket_secs: f64) -> Option<&WaveformLevel> {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.bucket_secs(self.sample_rate) <= bucket_secs)
+            .or(self.levels.first())
+    }
+
+    // Cached next to the audio it was made from, eg. `audio-input.ogg` -> `audio-input.waveform`
+    pub fn cache_path(audio_path: &Path) -> PathBuf {
+        audio_path.with_extension("waveform")
+    }
+
+    // Reads the cached waveform for `audio_path`, making and caching it from `samples` if
+    // there's no cache or it's older than the audio. `samples` returns interleaved samples
+    // with their channel count and sample rate.
+    pub fn load_or_generate(
+        audio_path: &Path,
+        samples: impl FnOnce() -> (Vec<f32>, usize, u32),
+    ) -> io::Result<Self> {
+        let cache_path = Self::cache_path(audio_path);
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        let fresh = match (modified(&cache_path), modified(audio_path)) {
+            (Some(cache), Some(audio)) => cache >= audio,
+            _ => false,
+        };
+
+        if fresh {
+            if let Ok(waveform) =
+                File::open(&cache_path).and_then(|f| Self::read(BufReader::new(f)))
+            {
+                return Ok(waveform);
+            }
+        }
+
+        let (samples, channels, sample_rate) = samples();
+        let waveform = Self::generate(&samples, channels, sample_rate);
+
+        let mut file = BufWriter::new(File::create(&cache_path)?);
+        waveform.write(&mut file)?;
+        file.flush()?;
+
+        Ok(waveform)
+    }
+
+    fn write(&self, mut w: impl Write) -> io::Result<()> {
+        w.write_all(CACHE_MAGIC)?;
+        w.write_all(&CACHE_VERSION.to_le_bytes())?;
+        w.write_all(&self.sample_rate.to_le_bytes())?;
+        w.write_all(&(self.levels.len() as u32).to_le_bytes())?;
+
+        for level in &self.levels {
+            w.write_all(&level.bucket_samples.to_le_bytes())?;
+            w.write_all(&(level.peaks.len() as u32).to_le_bytes())?;
+            w.write_all(&level.peaks)?;
+            w.write_all(&level.rms)?;
+        }
+
+        Ok(())
+    }
+
+    fn read(mut r: impl Read) -> io::Result<Self> {
+        let read_u32 = |r: &mut dyn Read| {
+            let mut bytes = [0; 4];
+            r.read_exact(&mut bytes).map(|_| u32::from_le_bytes(bytes))
+        };
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC || read_u32(&mut r)? != CACHE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a waveform cache this version can read",
+            ));
+        }
+
+        let sample_rate = read_u32(&mut r)?;
+        let level_count = read_u32(&mut r)?;
+        let mut levels = vec![];
+
+        for _ in 0..level_count {
+            let bucket_samples = read_u32(&mut r)?;
+            let len = read_u32(&mut r)? as usize;
+
+            let mut peaks = vec![0; len];
+            r.read_exact(&mut peaks)?;
+            let mut rms = vec![0; len];
+            r.read_exact(&mut rms)?;
+
+            levels.push(WaveformLevel {
+                bucket_samples,
+                peaks,
+                rms,
+            });
+        }
+
+        Ok(Self {
+            sample_rate,
+            levels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_coarser_levels() {
+        // stereo, one full scale bucket followed by three quiet ones
+        let samples = (0..BASE_BUCKET_SAMPLES * 4 * 2)
+            .map(|i| {
+                if i < BASE_BUCKET_SAMPLES * 2 {
+                    1.0
+                } else {
+                    0.5
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let waveform = Waveform::generate(&samples, 2, 48_000);
+
+        assert_eq!(waveform.levels.len(), LEVELS);
+        assert_eq!(waveform.levels[0].peaks, vec![255, 128, 128, 128]);
+        assert_eq!(waveform.levels[0].rms, vec![255, 128, 128, 128]);
+        assert_eq!(waveform.levels[1].peaks, vec![255]);
+        // sqrt((1 + 3 * 0.25) / 4)
+        assert_eq!(waveform.levels[1].rms, vec![169]);
+        assert_eq!(
+            waveform.levels[1].bucket_samples as usize,
+            BASE_BUCKET_SAMPLES * LEVEL_FACTOR
+        );
+    }
+
+    #[test]
+    fn picks_level_for_zoom() {
+        let waveform = Waveform::generate(&[0.0; 16], 1, 48_000);
+
+        let bucket_samples = |secs| waveform.level(secs).unwrap().bucket_samples;
+
+        assert_eq!(bucket_samples(0.001), 512);
+        assert_eq!(bucket_samples(0.05), 2048);
+        assert_eq!(bucket_samples(100.0), 512 * 4u32.pow(LEVELS as u32 - 1));
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let waveform = Waveform::generate(&[0.25, -0.5, 1.0, 0.0], 2, 44_100);
+
+        let mut bytes = vec![];
+        waveform.write(&mut bytes).unwrap();
+
+        assert_eq!(Waveform::read(bytes.as_slice()).unwrap(), waveform);
+        assert!(Waveform::read(&bytes[1..]).is_err());
+    }
+}
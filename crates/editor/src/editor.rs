@@ -1,16 +1,50 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{Arc, Mutex as StdMutex},
+    time::Instant,
+};
 
 use cap_media::{feeds::RawCameraFrame, frame_ws::WSFrame};
 use cap_project::{BackgroundSource, CursorEvents, RecordingMeta, XY};
 use cap_rendering::{
     decoder::DecodedFrame, DecodedSegmentFrames, FrameRenderer, ProjectRecordings, ProjectUniforms,
-    RenderVideoConstants,
+    RenderVideoConstants, ZoomDebugInfo,
 };
 use tokio::{
-    sync::{mpsc, oneshot},
+    sync::{mpsc, oneshot, watch},
     task::JoinHandle,
 };
 
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::frame_cache::{FrameCache, FrameKey};
+
+// Resolution a preview is rendered at, relative to the output's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum PreviewQuality {
+    Quarter,
+    Half,
+    Full,
+}
+
+impl PreviewQuality {
+    pub fn scale(self) -> f64 {
+        match self {
+            Self::Quarter => 0.25,
+            Self::Half => 0.5,
+            Self::Full => 1.0,
+        }
+    }
+
+    // encoders and the frame buffers want even dimensions
+    pub fn resolution_base(self, resolution_base: XY<u32>) -> XY<u32> {
+        let scale = |v: u32| (((v as f64 * self.scale()) as u32) & !1).max(2);
+
+        XY::new(scale(resolution_base.x), scale(resolution_base.y))
+    }
+}
+
 pub enum RendererMessage {
     RenderFrame {
         segment_frames: DecodedSegmentFrames,
@@ -19,6 +53,26 @@ pub enum RendererMessage {
         finished: oneshot::Sender<()>,
         resolution_base: XY<u32>,
         cursor: Arc<CursorEvents>,
+        cache_key: Option<FrameKey>,
+    },
+    // renders into the frame cache without displaying the frame
+    PreRenderFrame {
+        segment_frames: DecodedSegmentFrames,
+        background: BackgroundSource,
+        uniforms: ProjectUniforms,
+        finished: oneshot::Sender<()>,
+        resolution_base: XY<u32>,
+        cursor: Arc<CursorEvents>,
+        cache_key: FrameKey,
+    },
+    // renders a frame and hands it back instead of displaying it
+    RenderPreview {
+        segment_frames: DecodedSegmentFrames,
+        background: BackgroundSource,
+        uniforms: ProjectUniforms,
+        resolution_base: XY<u32>,
+        cursor: Arc<CursorEvents>,
+        frame: oneshot::Sender<Option<WSFrame>>,
     },
     Stop {
         finished: oneshot::Sender<()>,
@@ -28,19 +82,27 @@ pub enum RendererMessage {
 pub struct Renderer {
     rx: mpsc::Receiver<RendererMessage>,
     frame_tx: flume::Sender<WSFrame>,
+    mirror_tx: watch::Sender<Option<Arc<WSFrame>>>,
     render_constants: Arc<RenderVideoConstants>,
     total_frames: u32,
+    zoom_debug_tx: watch::Sender<Option<ZoomDebugInfo>>,
+    cache: Arc<StdMutex<FrameCache>>,
 }
 
 pub struct RendererHandle {
     tx: mpsc::Sender<RendererMessage>,
+    frame_tx: flume::Sender<WSFrame>,
+    mirror_tx: watch::Sender<Option<Arc<WSFrame>>>,
+    cache: Arc<StdMutex<FrameCache>>,
 }
 
 impl Renderer {
     pub fn spawn(
         render_constants: Arc<RenderVideoConstants>,
         frame_tx: flume::Sender<WSFrame>,
+        mirror_tx: watch::Sender<Option<Arc<WSFrame>>>,
         meta: &RecordingMeta,
+        zoom_debug_tx: watch::Sender<Option<ZoomDebugInfo>>,
     ) -> RendererHandle {
         let recordings = ProjectRecordings::new(meta);
         let mut max_duration = recordings.duration();
@@ -55,17 +117,26 @@ impl Renderer {
         let total_frames = (30_f64 * max_duration).ceil() as u32;
 
         let (tx, rx) = mpsc::channel(4);
+        let cache = Arc::new(StdMutex::new(FrameCache::new(&meta.project_path)));
 
         let this = Self {
             rx,
-            frame_tx,
+            frame_tx: frame_tx.clone(),
+            mirror_tx: mirror_tx.clone(),
             render_constants,
             total_frames,
+            zoom_debug_tx,
+            cache: cache.clone(),
         };
 
         tokio::spawn(this.run());
 
-        RendererHandle { tx }
+        RendererHandle {
+            tx,
+            frame_tx,
+            mirror_tx,
+            cache,
+        }
     }
 
     async fn run(mut self) {
@@ -83,6 +154,7 @@ impl Renderer {
                         finished,
                         resolution_base,
                         cursor,
+                        cache_key,
                     } => {
                         if let Some(task) = frame_task.as_ref() {
                             if task.is_finished() {
@@ -92,8 +164,6 @@ impl Renderer {
                             }
                         }
 
-                        let frame_tx = self.frame_tx.clone();
-
                         // frame_task = Some(tokio::spawn(async move {
                         let frame = frame_renderer
                             .render(
@@ -106,17 +176,87 @@ impl Renderer {
                             .await
                             .unwrap();
 
-                        frame_tx
-                            .try_send(WSFrame {
-                                data: frame.data,
-                                width: uniforms.output_size.0,
-                                height: uniforms.output_size.1,
-                                stride: frame.padded_bytes_per_row,
-                            })
-                            .ok();
+                        let frame = WSFrame {
+                            data: frame.data,
+                            width: uniforms.output_size.0,
+                            height: uniforms.output_size.1,
+                            stride: frame.padded_bytes_per_row,
+                        };
+
+                        if let Some(cache_key) = cache_key {
+                            self.cache.lock().unwrap().insert(cache_key, &frame);
+                        }
+
+                        show_frame(&self.frame_tx, &self.mirror_tx, frame);
+
+                        // only collected while something in the editor is listening
+                        if self.zoom_debug_tx.receiver_count() > 0 {
+                            self.zoom_debug_tx
+                                .send_replace(Some(uniforms.zoom_debug.clone()));
+                        }
+
                         finished.send(()).ok();
                         // }));
                     }
+                    RendererMessage::PreRenderFrame {
+                        segment_frames,
+                        background,
+                        uniforms,
+                        finished,
+                        resolution_base,
+                        cursor,
+                        cache_key,
+                    } => {
+                        if let Ok(frame) = frame_renderer
+                            .render(
+                                segment_frames,
+                                background,
+                                &uniforms,
+                                resolution_base,
+                                &cursor,
+                            )
+                            .await
+                        {
+                            self.cache.lock().unwrap().insert(
+                                cache_key,
+                                &WSFrame {
+                                    data: frame.data,
+                                    width: uniforms.output_size.0,
+                                    height: uniforms.output_size.1,
+                                    stride: frame.padded_bytes_per_row,
+                                },
+                            );
+                        }
+
+                        finished.send(()).ok();
+                    }
+                    RendererMessage::RenderPreview {
+                        segment_frames,
+                        background,
+                        uniforms,
+                        resolution_base,
+                        cursor,
+                        frame,
+                    } => {
+                        let rendered = frame_renderer
+                            .render(
+                                segment_frames,
+                                background,
+                                &uniforms,
+                                resolution_base,
+                                &cursor,
+                            )
+                            .await
+                            .ok()
+                            .map(|rendered| WSFrame {
+                                data: rendered.data,
+                                width: uniforms.output_size.0,
+                                height: uniforms.output_size.1,
+                                stride: rendered.padded_bytes_per_row,
+                            });
+
+                        frame.send(rendered).ok();
+                    }
                     RendererMessage::Stop { finished } => {
                         // Cancel any ongoing frame task
                         if let Some(task) = frame_task.take() {
@@ -145,6 +285,7 @@ impl RendererHandle {
         uniforms: ProjectUniforms,
         resolution_base: XY<u32>,
         cursor: Arc<CursorEvents>,
+        cache_key: Option<FrameKey>,
     ) {
         let (finished_tx, finished_rx) = oneshot::channel();
 
@@ -155,12 +296,75 @@ impl RendererHandle {
             finished: finished_tx,
             resolution_base,
             cursor,
+            cache_key,
         })
         .await;
 
         finished_rx.await.ok();
     }
 
+    pub async fn pre_render_frame(
+        &self,
+        segment_frames: DecodedSegmentFrames,
+        background: BackgroundSource,
+        uniforms: ProjectUniforms,
+        resolution_base: XY<u32>,
+        cursor: Arc<CursorEvents>,
+        cache_key: FrameKey,
+    ) {
+        let (finished_tx, finished_rx) = oneshot::channel();
+
+        self.send(RendererMessage::PreRenderFrame {
+            segment_frames,
+            background,
+            uniforms,
+            finished: finished_tx,
+            resolution_base,
+            cursor,
+            cache_key,
+        })
+        .await;
+
+        finished_rx.await.ok();
+    }
+
+    pub async fn render_preview(
+        &self,
+        segment_frames: DecodedSegmentFrames,
+        background: BackgroundSource,
+        uniforms: ProjectUniforms,
+        resolution_base: XY<u32>,
+        cursor: Arc<CursorEvents>,
+    ) -> Option<WSFrame> {
+        let (frame_tx, frame_rx) = oneshot::channel();
+
+        self.send(RendererMessage::RenderPreview {
+            segment_frames,
+            background,
+            uniforms,
+            resolution_base,
+            cursor,
+            frame: frame_tx,
+        })
+        .await;
+
+        frame_rx.await.ok().flatten()
+    }
+
+    pub fn is_cached(&self, cache_key: &FrameKey) -> bool {
+        self.cache.lock().unwrap().contains(cache_key)
+    }
+
+    // Shows a previously rendered frame, returning false if it has to be rendered
+    pub fn show_cached(&self, cache_key: &FrameKey) -> bool {
+        let Some(frame) = self.cache.lock().unwrap().get(cache_key) else {
+            return false;
+        };
+
+        show_frame(&self.frame_tx, &self.mirror_tx, frame);
+        true
+    }
+
     pub async fn stop(&self) {
         // Send a stop message to the renderer
         let (tx, rx) = oneshot::channel();
@@ -171,3 +375,17 @@ impl RendererHandle {
         let _ = rx.await;
     }
 }
+
+// Sends a frame to the editor's preview, and to whatever is mirroring it
+fn show_frame(
+    frame_tx: &flume::Sender<WSFrame>,
+    mirror_tx: &watch::Sender<Option<Arc<WSFrame>>>,
+    frame: WSFrame,
+) {
+    // only copied while something is mirroring the preview
+    if mirror_tx.receiver_count() > 0 {
+        mirror_tx.send_replace(Some(Arc::new(frame.clone())));
+    }
+
+    frame_tx.try_send(frame).ok();
+}
@@ -1,22 +1,32 @@
 use crate::editor;
+use crate::frame_cache::FrameKey;
 use crate::playback::{self, PlaybackHandle};
+use crate::proxy;
 use cap_media::data::RawVideoFormat;
 use cap_media::data::VideoInfo;
-use cap_media::feeds::AudioData;
-use cap_media::frame_ws::create_frame_ws;
+use cap_media::feeds::SegmentAudio;
+use cap_media::frame_ws::{create_frame_ws, WSFrame};
 use cap_project::RecordingConfig;
-use cap_project::{CursorEvents, ProjectConfiguration, RecordingMeta, XY};
+use cap_project::{
+    ProjectConfigError, ProjectConfiguration, RecordingMeta, TimelineConfiguration, XY,
+};
 use cap_rendering::{
-    get_duration, DecodedSegmentFrames, ProjectRecordings, ProjectUniforms,
-    RecordingSegmentDecoders, RenderOptions, RenderVideoConstants, SegmentVideoPaths,
+    decode_transition, get_duration, DecodedSegmentFrames, ProjectRecordings, ProjectUniforms,
+    RecordingSegmentDecoders, RenderOptions, RenderVideoConstants, SegmentCursors,
+    SegmentVideoPaths, ZoomDebugInfo,
 };
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::Mutex as StdMutex;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{path::PathBuf, sync::Arc};
 use tokio::sync::{mpsc, watch, Mutex};
 
+// how long the editor has to sit untouched before pre-rendering starts
+const PRE_RENDER_IDLE_DELAY: Duration = Duration::from_millis(750);
+// how far past the playhead to pre-render, in seconds
+const PRE_RENDER_AHEAD: f64 = 3.0;
+
 pub struct EditorInstance {
     pub project_path: PathBuf,
     pub id: String,
@@ -34,6 +44,8 @@ pub struct EditorInstance {
     ws_shutdown: Arc<StdMutex<Option<mpsc::Sender<()>>>>,
     pub segments: Arc<Vec<Segment>>,
     meta: RecordingMeta,
+    zoom_debug: watch::Sender<Option<ZoomDebugInfo>>,
+    preview_mirror: watch::Sender<Option<Arc<WSFrame>>>,
 }
 
 impl EditorInstance {
@@ -53,7 +65,8 @@ impl EditorInstance {
         projects_path: PathBuf,
         video_id: &str,
         on_state_change: impl Fn(&EditorState) + Send + Sync + 'static,
-        get_is_upgraded: impl Fn() -> bool + Send + 'static,
+        get_is_upgraded: impl Fn() -> bool + Send + Sync + 'static,
+        use_proxies: bool,
     ) -> Result<Arc<Self>, String> {
         sentry::configure_scope(|scope| {
             scope.set_tag("crate", "editor");
@@ -74,10 +87,23 @@ impl EditorInstance {
             panic!("Video path {} not found!", project_path.display());
         }
 
-        let meta = cap_project::RecordingMeta::load_for_project(&project_path).unwrap();
-        let project = meta.project_config();
+        let mut project = match ProjectConfiguration::load(&project_path) {
+            Ok(project) => project,
+            // saving over a config from a newer app would lose what this one doesn't know of
+            Err(e @ ProjectConfigError::TooNew { .. }) => return Err(e.to_string()),
+            Err(_) => ProjectConfiguration::default(),
+        };
+        // decoders are made for the takes the project has now, so adding one takes reopening it
+        let meta = cap_project::RecordingMeta::load_for_project(&project_path)
+            .unwrap()
+            .with_takes(&project.takes)?;
         let recordings = ProjectRecordings::new(&meta);
 
+        // recordings that were paused have a segment for each stretch of recording
+        project.timeline.get_or_insert_with(|| {
+            TimelineConfiguration::from_segment_durations(&recordings.segment_durations())
+        });
+
         let render_options = RenderOptions {
             screen_size: XY::new(
                 recordings.segments[0].display.width,
@@ -87,9 +113,14 @@ impl EditorInstance {
                 .camera
                 .as_ref()
                 .map(|c| XY::new(c.width, c.height)),
+            deterministic: project.determinism.enabled,
+            high_quality_scaling: false,
+            gpu: None,
+            screen_color: recordings.segments[0].display.color,
+            hdr10: false,
         };
 
-        let segments = create_segments(&meta).await?;
+        let segments = create_segments(&meta, use_proxies).await?;
 
         let (frame_tx, frame_rx) = flume::bounded(4);
 
@@ -101,10 +132,15 @@ impl EditorInstance {
                 .unwrap(),
         );
 
+        let (zoom_debug, _) = watch::channel(None);
+        let (preview_mirror, _) = watch::channel(None);
+
         let renderer = Arc::new(editor::Renderer::spawn(
             render_constants.clone(),
             frame_tx,
+            preview_mirror.clone(),
             &meta,
+            zoom_debug.clone(),
         ));
 
         let (preview_tx, preview_rx) = watch::channel(None);
@@ -118,8 +154,10 @@ impl EditorInstance {
             render_constants,
             state: Arc::new(Mutex::new(EditorState {
                 playhead_position: 0,
+                loop_playback: false,
                 playback_task: None,
                 preview_task: None,
+                pre_render_task: None,
             })),
             on_state_change: Box::new(on_state_change),
             preview_tx,
@@ -127,18 +165,46 @@ impl EditorInstance {
             ws_shutdown: Arc::new(StdMutex::new(Some(ws_shutdown))),
             segments: Arc::new(segments),
             meta,
+            zoom_debug,
+            preview_mirror,
         });
 
-        this.state.lock().await.preview_task = Some(
-            this.clone()
-                .spawn_preview_renderer(preview_rx, get_is_upgraded),
-        );
+        let get_is_upgraded = Arc::new(get_is_upgraded);
+
+        {
+            let mut state = this.state.lock().await;
+            state.preview_task = Some(
+                this.clone()
+                    .spawn_preview_renderer(preview_rx.clone(), get_is_upgraded.clone()),
+            );
+            state.pre_render_task = Some(
+                this.clone()
+                    .spawn_idle_pre_renderer(preview_rx, get_is_upgraded),
+            );
+        }
 
         Ok(this)
     }
 
+    // Zoom state of each rendered frame, collected only while subscribed
+    pub fn subscribe_zoom_debug(&self) -> watch::Receiver<Option<ZoomDebugInfo>> {
+        self.zoom_debug.subscribe()
+    }
+
+    // Every frame the preview shows from now on, starting with the one it's showing
+    pub fn subscribe_preview(&self) -> watch::Receiver<Option<Arc<WSFrame>>> {
+        let rx = self.preview_mirror.subscribe();
+        // frames are only mirrored while subscribed, so the current one is rendered again
+        self.preview_tx.send_modify(|_| {});
+        rx
+    }
+
+    // The project's meta as it is now, with the recordings it was opened with
     pub fn meta(&self) -> RecordingMeta {
-        RecordingMeta::load_for_project(&self.project_path).unwrap()
+        RecordingMeta {
+            content: self.meta.content.clone(),
+            ..RecordingMeta::load_for_project(&self.project_path).unwrap()
+        }
     }
 
     pub async fn dispose(&self) {
@@ -159,6 +225,11 @@ impl EditorInstance {
             task.await.ok(); // Await the task to ensure it's fully stopped
         }
 
+        if let Some(task) = state.pre_render_task.take() {
+            task.abort();
+            task.await.ok();
+        }
+
         // Stop WebSocket server
         if let Some(ws_shutdown) = self.ws_shutdown.lock().unwrap().take() {
             println!("Shutting down WebSocket server");
@@ -208,6 +279,7 @@ impl EditorInstance {
                 render_constants: self.render_constants.clone(),
                 start_frame_number,
                 project: self.project_config.0.subscribe(),
+                loop_playback: state.loop_playback,
             }
             .start(fps, resolution_base, is_upgraded)
             .await;
@@ -243,10 +315,82 @@ impl EditorInstance {
         }
     }
 
+    // Moves the playhead, taking a running playback along with it
+    pub async fn seek(&self, frame_number: u32) {
+        self.modify_and_emit_state(|state| {
+            state.playhead_position = frame_number;
+
+            if let Some(playback) = &state.playback_task {
+                playback.seek(frame_number);
+            }
+        })
+        .await;
+    }
+
+    pub async fn set_loop_playback(&self, enabled: bool) {
+        let mut state = self.state.lock().await;
+        state.loop_playback = enabled;
+
+        if let Some(playback) = &state.playback_task {
+            playback.set_loop(enabled);
+        }
+    }
+
+    // Renders the composited frame at `time` on the timeline without showing it in the editor,
+    // at a fraction of `resolution_base` set by `quality`. Decoders keep the GOPs they decoded
+    // recently, so scrubbing back and forth over the same area doesn't decode it again.
+    pub async fn render_preview(
+        &self,
+        time: f64,
+        fps: u32,
+        resolution_base: XY<u32>,
+        quality: editor::PreviewQuality,
+        is_upgraded: bool,
+    ) -> Option<WSFrame> {
+        let project = self.project_config.1.borrow().clone();
+        let frame_number = (time * fps as f64).round() as u32;
+        let time = frame_number as f64 / fps as f64;
+        let resolution_base = quality.resolution_base(resolution_base);
+
+        let (segment_time, segment_i) = project.get_segment_time(time)?;
+        let segment = self.segments.get(segment_i as usize)?;
+        let display = project.get_segment_display(time);
+        let cursor = segment.cursors.get(display);
+
+        let mut segment_frames = segment
+            .decoders
+            .get_frames(segment_time as f32, !project.camera.hide, display)
+            .await?;
+        segment_frames.transition = decode_transition(&project, time, |i| {
+            self.segments
+                .get(i as usize)
+                .map(|s| (&s.decoders, &s.cursors))
+        })
+        .await;
+
+        self.renderer
+            .render_preview(
+                segment_frames,
+                project.background.source.clone(),
+                ProjectUniforms::new(
+                    &self.render_constants,
+                    &project,
+                    frame_number,
+                    fps,
+                    resolution_base,
+                    is_upgraded,
+                    cursor,
+                ),
+                resolution_base,
+                cursor.clone(),
+            )
+            .await
+    }
+
     fn spawn_preview_renderer(
         self: Arc<Self>,
         mut preview_rx: watch::Receiver<Option<(u32, u32, XY<u32>)>>,
-        get_is_upgraded: impl Fn() -> bool + Send + 'static,
+        get_is_upgraded: Arc<impl Fn() -> bool + Send + Sync + 'static>,
     ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             loop {
@@ -265,12 +409,27 @@ impl EditorInstance {
                 };
 
                 let segment = &self.segments[segment_i as usize];
+                let display = project.get_segment_display(frame_number as f64 / fps as f64);
+                let cursor = segment.cursors.get(display);
+                let cache_key = FrameKey::new(&project, frame_number, fps, resolution_base);
+
+                if self.renderer.show_cached(&cache_key) {
+                    continue;
+                }
 
-                if let Some(segment_frames) = segment
+                if let Some(mut segment_frames) = segment
                     .decoders
-                    .get_frames(segment_time as f32, !project.camera.hide)
+                    .get_frames(segment_time as f32, !project.camera.hide, display)
                     .await
                 {
+                    segment_frames.transition =
+                        decode_transition(&project, frame_number as f64 / fps as f64, |i| {
+                            self.segments
+                                .get(i as usize)
+                                .map(|s| (&s.decoders, &s.cursors))
+                        })
+                        .await;
+
                     self.renderer
                         .render_frame(
                             segment_frames,
@@ -282,10 +441,11 @@ impl EditorInstance {
                                 fps,
                                 resolution_base,
                                 get_is_upgraded(),
-                                &segment.cursor,
+                                cursor,
                             ),
                             resolution_base,
-                            segment.cursor.clone(),
+                            cursor.clone(),
+                            Some(cache_key),
                         )
                         .await;
                 }
@@ -293,6 +453,110 @@ impl EditorInstance {
         })
     }
 
+    // Once the editor has been left alone for a moment, renders the frames just after the
+    // playhead into the frame cache so pressing play can start from cache. Frames are rendered
+    // one at a time behind any interactive ones, and the pass stops as soon as the playhead,
+    // project or playback state changes.
+    fn spawn_idle_pre_renderer(
+        self: Arc<Self>,
+        mut preview_rx: watch::Receiver<Option<PreviewFrameInstruction>>,
+        get_is_upgraded: Arc<impl Fn() -> bool + Send + Sync + 'static>,
+    ) -> tokio::task::JoinHandle<()> {
+        let mut project_rx = self.project_config.0.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    res = preview_rx.changed() => if res.is_err() { return },
+                    res = project_rx.changed() => if res.is_err() { return },
+                }
+
+                // wait for edits and scrubbing to settle
+                loop {
+                    tokio::select! {
+                        res = preview_rx.changed() => if res.is_err() { return },
+                        res = project_rx.changed() => if res.is_err() { return },
+                        _ = tokio::time::sleep(PRE_RENDER_IDLE_DELAY) => break,
+                    }
+                }
+
+                let Some((playhead, fps, resolution_base)) = *preview_rx.borrow_and_update() else {
+                    continue;
+                };
+                let project = project_rx.borrow_and_update().clone();
+
+                let total_frames = self.get_total_frames(fps);
+                let end = (playhead + (PRE_RENDER_AHEAD * fps as f64) as u32).min(total_frames);
+
+                for frame_number in playhead + 1..end {
+                    if preview_rx.has_changed().unwrap_or(true)
+                        || project_rx.has_changed().unwrap_or(true)
+                        || self.is_playing().await
+                    {
+                        break;
+                    }
+
+                    let cache_key = FrameKey::new(&project, frame_number, fps, resolution_base);
+                    if self.renderer.is_cached(&cache_key) {
+                        continue;
+                    }
+
+                    let Some((segment_time, segment_i)) =
+                        project.get_segment_time(frame_number as f64 / fps as f64)
+                    else {
+                        break;
+                    };
+                    let segment = &self.segments[segment_i as usize];
+                    let display = project.get_segment_display(frame_number as f64 / fps as f64);
+                    let cursor = segment.cursors.get(display);
+
+                    let Some(mut segment_frames) = segment
+                        .decoders
+                        .get_frames(segment_time as f32, !project.camera.hide, display)
+                        .await
+                    else {
+                        continue;
+                    };
+                    segment_frames.transition =
+                        decode_transition(&project, frame_number as f64 / fps as f64, |i| {
+                            self.segments
+                                .get(i as usize)
+                                .map(|s| (&s.decoders, &s.cursors))
+                        })
+                        .await;
+
+                    self.renderer
+                        .pre_render_frame(
+                            segment_frames,
+                            project.background.source.clone(),
+                            ProjectUniforms::new(
+                                &self.render_constants,
+                                &project,
+                                frame_number,
+                                fps,
+                                resolution_base,
+                                get_is_upgraded(),
+                                cursor,
+                            ),
+                            resolution_base,
+                            cursor.clone(),
+                            cache_key,
+                        )
+                        .await;
+                }
+            }
+        })
+    }
+
+    async fn is_playing(&self) -> bool {
+        self.state
+            .lock()
+            .await
+            .playback_task
+            .as_ref()
+            .is_some_and(|p| p.is_playing())
+    }
+
     pub fn get_total_frames(&self, fps: u32) -> u32 {
         // Calculate total frames based on actual video duration and fps
         let duration = get_duration(
@@ -317,29 +581,48 @@ type PreviewFrameInstruction = (u32, u32, XY<u32>);
 
 pub struct EditorState {
     pub playhead_position: u32,
+    pub loop_playback: bool,
     pub playback_task: Option<PlaybackHandle>,
     pub preview_task: Option<tokio::task::JoinHandle<()>>,
+    pub pre_render_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 pub struct Segment {
-    pub audio: Arc<Option<AudioData>>,
-    pub cursor: Arc<CursorEvents>,
+    pub audio: Arc<Option<SegmentAudio>>,
+    pub cursors: SegmentCursors,
     pub decoders: RecordingSegmentDecoders,
 }
 
-pub async fn create_segments(meta: &RecordingMeta) -> Result<Vec<Segment>, String> {
+// With `use_proxies`, displays are decoded from their proxies where they've been generated
+pub async fn create_segments(
+    meta: &RecordingMeta,
+    use_proxies: bool,
+) -> Result<Vec<Segment>, String> {
+    let video_path = |path| {
+        if use_proxies {
+            proxy::editing_path(path)
+        } else {
+            path
+        }
+    };
+
     match &meta.content {
         cap_project::Content::SingleSegment { segment: s } => {
             let audio = Arc::new(
-                s.audio
-                    .as_ref()
-                    .map(|audio_meta| AudioData::from_file(meta.path(&audio_meta.path)).unwrap()),
+                SegmentAudio::from_files(
+                    s.audio
+                        .as_ref()
+                        .map(|audio_meta| meta.path(&audio_meta.path)),
+                    None,
+                )
+                .unwrap(),
             );
 
             let decoders = RecordingSegmentDecoders::new(
                 &meta,
                 SegmentVideoPaths {
-                    display: meta.path(&s.display.path),
+                    display: video_path(meta.path(&s.display.path)),
+                    additional_displays: vec![],
                     camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
                 },
             )
@@ -348,7 +631,7 @@ pub async fn create_segments(meta: &RecordingMeta) -> Result<Vec<Segment>, Strin
 
             Ok(vec![Segment {
                 audio,
-                cursor: Default::default(),
+                cursors: Default::default(),
                 decoders,
             }])
         }
@@ -356,26 +639,39 @@ pub async fn create_segments(meta: &RecordingMeta) -> Result<Vec<Segment>, Strin
             let mut segments = vec![];
 
             for (i, s) in inner.segments.iter().enumerate() {
-                let audio =
-                    Arc::new(s.audio.as_ref().map(|audio_meta| {
-                        AudioData::from_file(meta.path(&audio_meta.path)).unwrap()
-                    }));
-
-                let cursor = Arc::new(s.cursor_events(&meta));
+                let audio = Arc::new(
+                    SegmentAudio::from_files(
+                        s.audio
+                            .as_ref()
+                            .map(|audio_meta| meta.path(&audio_meta.path)),
+                        s.system_audio
+                            .as_ref()
+                            .map(|audio_meta| meta.path(&audio_meta.path)),
+                    )
+                    .unwrap(),
+                );
 
                 let decoders = RecordingSegmentDecoders::new(
                     &meta,
                     SegmentVideoPaths {
-                        display: meta.path(&s.display.path),
+                        display: video_path(meta.path(&s.display.path)),
+                        additional_displays: s
+                            .additional_displays
+                            .iter()
+                            .map(|d| video_path(meta.path(&d.path)))
+                            .collect(),
                         camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
                     },
                 )
                 .await
                 .map_err(|e| format!("MultipleSegments/{i}:{e}"))?;
 
+                let cursors =
+                    SegmentCursors::new(&s.cursor_events(&meta), &decoders.display_sizes());
+
                 segments.push(Segment {
                     audio,
-                    cursor,
+                    cursors,
                     decoders,
                 });
             }
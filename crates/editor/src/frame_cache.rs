@@ -0,0 +1,116 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use cap_media::frame_ws::WSFrame;
+use cap_project::{ProjectConfiguration, XY};
+use cap_rendering::{global_frame_cache, source_id, CachedFrame, FrameCacheKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameKey {
+    pub frame_number: u32,
+    pub fps: u32,
+    pub resolution_base: (u32, u32),
+    pub revision: u64,
+}
+
+impl FrameKey {
+    pub fn new(
+        project: &ProjectConfiguration,
+        frame_number: u32,
+        fps: u32,
+        resolution_base: XY<u32>,
+    ) -> Self {
+        Self {
+            frame_number,
+            fps,
+            resolution_base: (resolution_base.x, resolution_base.y),
+            revision: project_revision(project),
+        }
+    }
+}
+
+// Identifies the edit a frame was rendered for, so frames rendered before a change are never shown
+fn project_revision(project: &ProjectConfiguration) -> u64 {
+    let mut hasher = DefaultHasher::default();
+    serde_json::to_vec(project)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+impl FrameKey {
+    fn cache_key(&self, project: u64) -> FrameCacheKey {
+        FrameCacheKey::Composited {
+            project,
+            revision: self.revision,
+            frame_number: self.frame_number,
+            fps: self.fps,
+            resolution_base: self.resolution_base,
+        }
+    }
+}
+
+// An editor's view of the shared frame cache, holding the frames it rendered
+pub struct FrameCache {
+    shared: Arc<StdMutex<cap_rendering::FrameCache>>,
+    // the project the frames were rendered for
+    project: u64,
+    revision: Option<u64>,
+}
+
+impl FrameCache {
+    pub fn new(project_path: &Path) -> Self {
+        Self {
+            shared: global_frame_cache(),
+            project: source_id(project_path),
+            revision: None,
+        }
+    }
+
+    pub fn contains(&self, key: &FrameKey) -> bool {
+        self.shared
+            .lock()
+            .unwrap()
+            .contains(&key.cache_key(self.project))
+    }
+
+    pub fn get(&self, key: &FrameKey) -> Option<WSFrame> {
+        let frame = self
+            .shared
+            .lock()
+            .unwrap()
+            .get(&key.cache_key(self.project))?;
+
+        Some(WSFrame {
+            data: frame.data.as_ref().clone(),
+            width: frame.width,
+            height: frame.height,
+            stride: frame.stride,
+        })
+    }
+
+    pub fn insert(&mut self, key: FrameKey, frame: &WSFrame) {
+        let mut shared = self.shared.lock().unwrap();
+
+        // anything rendered for an older revision of the project is stale now
+        if self.revision.is_some_and(|r| r != key.revision) {
+            let project = self.project;
+            shared.retain(|k| {
+                !matches!(k, FrameCacheKey::Composited { project: p, revision, .. }
+                    if *p == project && *revision != key.revision)
+            });
+        }
+        self.revision = Some(key.revision);
+
+        shared.insert(
+            key.cache_key(self.project),
+            CachedFrame {
+                data: Arc::new(frame.data.clone()),
+                width: frame.width,
+                height: frame.height,
+                stride: frame.stride,
+            },
+        );
+    }
+}
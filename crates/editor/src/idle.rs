@@ -0,0 +1,104 @@
+use std::path::Path;
+
+// Frames are compared this far apart, which catches anything on screen changing without
+// decoding every frame's pixels
+const SAMPLE_INTERVAL_SECS: f64 = 0.5;
+// frames are shrunk to this size and compared in grayscale, which evens out compression noise
+const SAMPLE_SIZE: (u32, u32) = (64, 36);
+// pixels differing by less than this are noise
+const PIXEL_THRESHOLD: u8 = 12;
+// a frame has changed when more than this fraction of its pixels has
+const CHANGED_FRACTION: f64 = 0.005;
+
+// Seconds into a video at which what it shows changed noticeably from a moment before,
+// for telling a still screen apart from one where something is happening without the
+// cursor or keyboard being used, like a video playing or a build running
+pub fn detect_frame_changes(path: &Path) -> Result<Vec<f64>, String> {
+    let mut input =
+        ffmpeg::format::input(&path).map_err(|e| format!("Failed to open video: {e}"))?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| "No video stream found".to_string())?;
+    let stream_index = stream.index();
+    let time_base = f64::from(stream.time_base());
+
+    let mut decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())
+        .map_err(|e| format!("Failed to create decoder: {e}"))?
+        .decoder()
+        .video()
+        .map_err(|e| format!("Failed to get video decoder: {e}"))?;
+
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::GRAY8,
+        SAMPLE_SIZE.0,
+        SAMPLE_SIZE.1,
+        ffmpeg::software::scaling::Flags::AREA,
+    )
+    .map_err(|e| format!("Failed to create scaler: {e}"))?;
+
+    let mut decoded = ffmpeg::frame::Video::empty();
+    let mut scaled = ffmpeg::frame::Video::empty();
+    let mut previous: Option<Vec<u8>> = None;
+    let mut next_sample = 0.0;
+    let mut changes = vec![];
+
+    let mut drain = |decoder: &mut ffmpeg::decoder::Video| -> Result<(), String> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let Some(time) = decoded.timestamp().map(|ts| ts as f64 * time_base) else {
+                continue;
+            };
+            if time < next_sample {
+                continue;
+            }
+            next_sample = time + SAMPLE_INTERVAL_SECS;
+
+            scaler
+                .run(&decoded, &mut scaled)
+                .map_err(|e| format!("Failed to scale frame: {e}"))?;
+
+            let width = SAMPLE_SIZE.0 as usize;
+            let pixels = (0..SAMPLE_SIZE.1 as usize)
+                .flat_map(|y| {
+                    let row = y * scaled.stride(0);
+                    scaled.data(0)[row..row + width].iter().copied()
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(previous) = &previous {
+                let changed = pixels
+                    .iter()
+                    .zip(previous)
+                    .filter(|(a, b)| a.abs_diff(**b) >= PIXEL_THRESHOLD)
+                    .count();
+
+                if changed as f64 > pixels.len() as f64 * CHANGED_FRACTION {
+                    changes.push(time);
+                }
+            }
+
+            previous = Some(pixels);
+        }
+
+        Ok(())
+    };
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| format!("Failed to decode video: {e}"))?;
+        drain(&mut decoder)?;
+    }
+
+    decoder.send_eof().ok();
+    drain(&mut decoder)?;
+
+    Ok(changes)
+}
@@ -1,5 +1,17 @@
 mod editor;
 mod editor_instance;
+mod frame_cache;
+mod idle;
+mod merge;
 mod playback;
+mod proxy;
+mod redaction;
+mod transcription;
 
+pub use editor::PreviewQuality;
 pub use editor_instance::{create_segments, EditorInstance, EditorState, Segment};
+pub use idle::detect_frame_changes;
+pub use merge::merge_projects;
+pub use proxy::{generate_proxies, proxy_path};
+pub use redaction::{read_frames, OCR_INTERVAL_SECS};
+pub use transcription::{transcribe, TranscriptionProgress};
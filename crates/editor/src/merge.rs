@@ -0,0 +1,376 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use cap_media::{
+    data::{RawVideoFormat, VideoInfo},
+    encoders::{H264Encoder, MP4File},
+};
+use cap_project::{
+    merge_timelines, merged_resolution, AudioMeta, CameraMeta, Content, CursorEvents, CursorMeta,
+    Cursors, Display, Letterbox, MergePart, MultipleSegment, MultipleSegments,
+    ProjectConfiguration, RecordingMeta, XY,
+};
+use cap_rendering::ProjectRecordings;
+use relative_path::RelativePathBuf;
+
+// Concatenates projects into a new one at `project_path`, in the order given. Every source
+// recording segment becomes a segment of the new project, re-encoded with letterboxing
+// when its resolution differs from the largest one, and the timelines, zoom segments and
+// cursor data are rebased to match. Styling comes from the first project.
+pub async fn merge_projects(
+    sources: &[PathBuf],
+    project_path: PathBuf,
+    pretty_name: String,
+) -> Result<RecordingMeta, String> {
+    if sources.is_empty() {
+        return Err("No projects to merge".to_string());
+    }
+
+    let metas = sources
+        .iter()
+        .map(RecordingMeta::load_for_project)
+        .collect::<Result<Vec<_>, _>>()?;
+    let recordings = metas.iter().map(ProjectRecordings::new).collect::<Vec<_>>();
+
+    let display_size = merged_resolution(
+        &recordings
+            .iter()
+            .flat_map(|r| &r.segments)
+            .map(|s| XY::new(s.display.width, s.display.height))
+            .collect::<Vec<_>>(),
+    )
+    .ok_or("Projects have no recordings")?;
+    // the renderer sizes the camera from the first segment that has one
+    let camera_size = recordings
+        .iter()
+        .flat_map(|r| &r.segments)
+        .find_map(|s| s.camera.map(|c| XY::new(c.width, c.height)));
+
+    let content_dir = project_path.join("content");
+    let cursors_dir = content_dir.join("cursors");
+    std::fs::create_dir_all(&cursors_dir).map_err(|e| e.to_string())?;
+
+    let mut segments = vec![];
+    let mut cursors = HashMap::new();
+    let mut parts = vec![];
+
+    for (meta, recordings) in metas.iter().zip(&recordings) {
+        let (source_cursors, sources) = source_segments(meta);
+        let mut durations = vec![];
+        // cursor ids are only unique within a project, so each part's get renumbered
+        let mut cursor_ids = HashMap::<String, String>::new();
+
+        for (id, cursor) in source_cursors {
+            let new_id = cursors.len().to_string();
+            let file_name = format!("cursor_{new_id}.png");
+
+            std::fs::copy(meta.path(&cursor.image_path), cursors_dir.join(&file_name))
+                .map_err(|e| format!("Failed to copy cursor image: {e}"))?;
+
+            cursors.insert(
+                new_id.clone(),
+                CursorMeta {
+                    image_path: RelativePathBuf::from("content/cursors").join(file_name),
+                    hotspot: cursor.hotspot,
+                    shape: cursor.shape,
+                },
+            );
+            cursor_ids.insert(id, new_id);
+        }
+
+        // zoom positions are mapped through the part's first segment
+        let part_letterbox = recordings
+            .segments
+            .first()
+            .map(|s| Letterbox::new(XY::new(s.display.width, s.display.height), display_size))
+            .unwrap_or_else(|| Letterbox::new(display_size, display_size));
+
+        for (source, recording) in sources.iter().zip(&recordings.segments) {
+            let index = segments.len();
+            let dir = content_dir
+                .join("segments")
+                .join(format!("segment-{index}"));
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+            let source_size = XY::new(recording.display.width, recording.display.height);
+            let letterbox = Letterbox::new(source_size, display_size);
+            let display = copy_or_letterbox(
+                &meta.path(&source.display.path),
+                &dir,
+                "display",
+                (source_size != display_size).then_some(letterbox),
+            )
+            .await?;
+
+            let camera = match (&source.camera, recording.camera, camera_size) {
+                (Some(camera), Some(video), Some(camera_size)) => {
+                    let source_size = XY::new(video.width, video.height);
+                    let path = copy_or_letterbox(
+                        &meta.path(&camera.path),
+                        &dir,
+                        "camera",
+                        (source_size != camera_size)
+                            .then(|| Letterbox::new(source_size, camera_size)),
+                    )
+                    .await?;
+
+                    Some(CameraMeta {
+                        path: relative_to(&project_path, &path),
+                        fps: camera.fps,
+                    })
+                }
+                _ => None,
+            };
+
+            let audio = match &source.audio {
+                Some(audio) => {
+                    let path =
+                        copy_or_letterbox(&meta.path(&audio.path), &dir, "audio-input", None)
+                            .await?;
+
+                    Some(AudioMeta {
+                        path: relative_to(&project_path, &path),
+                    })
+                }
+                None => None,
+            };
+
+            let system_audio = match &source.system_audio {
+                Some(audio) => {
+                    let path =
+                        copy_or_letterbox(&meta.path(&audio.path), &dir, "system-audio", None)
+                            .await?;
+
+                    Some(AudioMeta {
+                        path: relative_to(&project_path, &path),
+                    })
+                }
+                None => None,
+            };
+
+            let cursor = match &source.cursor {
+                Some(_) => {
+                    let mut events = source.cursor_events(meta);
+                    rename_cursors(&mut events, &cursor_ids);
+                    letterbox.map_cursor_events(&mut events);
+
+                    let path = dir.join("cursor.json");
+                    std::fs::write(
+                        &path,
+                        serde_json::to_string(&events).map_err(|e| e.to_string())?,
+                    )
+                    .map_err(|e| e.to_string())?;
+
+                    Some(relative_to(&project_path, &path))
+                }
+                None => None,
+            };
+
+            durations.push(recording.display.duration);
+            segments.push(MultipleSegment {
+                display: Display {
+                    path: relative_to(&project_path, &display),
+                    fps: source.display.fps,
+                },
+                // only the main display is letterboxed to the merged size, so timeline
+                // segments on other displays fall back to it
+                additional_displays: vec![],
+                source: source.source.clone(),
+                // the cursor events written above are already mapped into the region
+                region: None,
+                camera,
+                audio,
+                system_audio,
+                cursor,
+                cursor_id_prefix: String::new(),
+            });
+        }
+
+        parts.push(MergePart::new(
+            meta.project_config().timeline,
+            &durations,
+            part_letterbox,
+        ));
+    }
+
+    let meta = RecordingMeta {
+        project_path: project_path.clone(),
+        pretty_name,
+        sharing: None,
+        content: Content::MultipleSegments {
+            inner: MultipleSegments {
+                segments,
+                cursors: Cursors::Correct(cursors),
+            },
+        },
+    };
+    meta.save_for_project().map_err(|e| format!("{e:?}"))?;
+
+    let config = ProjectConfiguration {
+        timeline: Some(merge_timelines(&parts)),
+        ..metas[0].project_config()
+    };
+    config.write(&project_path).map_err(|e| e.to_string())?;
+
+    Ok(meta)
+}
+
+// A project's cursor images and its recording segments in the multiple segment layout
+fn source_segments(meta: &RecordingMeta) -> (HashMap<String, CursorMeta>, Vec<MultipleSegment>) {
+    let MultipleSegments { segments, cursors } = meta.multiple_segments();
+
+    (
+        match cursors {
+            Cursors::Old(_) => HashMap::new(),
+            Cursors::Correct(map) => map,
+        },
+        segments,
+    )
+}
+
+fn rename_cursors(events: &mut CursorEvents, ids: &HashMap<String, String>) {
+    for m in &mut events.moves {
+        if let Some(id) = ids.get(&m.cursor_id) {
+            m.cursor_id = id.clone();
+        }
+    }
+    for c in &mut events.clicks {
+        if let Some(id) = ids.get(&c.cursor_id) {
+            c.cursor_id = id.clone();
+        }
+    }
+}
+
+fn relative_to(project_path: &Path, path: &Path) -> RelativePathBuf {
+    RelativePathBuf::from_path(path.strip_prefix(project_path).unwrap()).unwrap()
+}
+
+// Copies `source` into `dir` as `name`, or re-encodes it into the letterbox when given one
+async fn copy_or_letterbox(
+    source: &Path,
+    dir: &Path,
+    name: &str,
+    letterbox: Option<Letterbox>,
+) -> Result<PathBuf, String> {
+    let Some(letterbox) = letterbox else {
+        let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+        let output = dir.join(format!("{name}.{extension}"));
+
+        tokio::fs::copy(source, &output)
+            .await
+            .map_err(|e| format!("Failed to copy {}: {e}", source.display()))?;
+
+        return Ok(output);
+    };
+
+    let source = source.to_path_buf();
+    let output = dir.join(format!("{name}.mp4"));
+
+    tokio::task::spawn_blocking({
+        let output = output.clone();
+        move || letterbox_video(&source, output, letterbox)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(output)
+}
+
+fn letterbox_video(source: &Path, output: PathBuf, letterbox: Letterbox) -> Result<(), String> {
+    let mut input =
+        ffmpeg::format::input(&source).map_err(|e| format!("Failed to open video: {e}"))?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| "No video stream found".to_string())?;
+    let stream_index = stream.index();
+    let time_base = f64::from(stream.time_base());
+    let rate = stream.avg_frame_rate();
+    let fps = (rate.numerator() as f64 / rate.denominator().max(1) as f64)
+        .round()
+        .max(1.0) as u32;
+
+    let mut decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())
+        .map_err(|e| format!("Failed to create decoder: {e}"))?
+        .decoder()
+        .video()
+        .map_err(|e| format!("Failed to get video decoder: {e}"))?;
+
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::RGBA,
+        letterbox.size.x,
+        letterbox.size.y,
+        ffmpeg::software::scaling::Flags::BILINEAR,
+    )
+    .map_err(|e| format!("Failed to create scaler: {e}"))?;
+
+    let target = letterbox.target;
+    let mut info = VideoInfo::from_raw(RawVideoFormat::Rgba, target.x, target.y, fps);
+    info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+    let mut encoder = MP4File::init(
+        "merge",
+        output,
+        H264Encoder::factory("merge_video", info),
+        |_| None,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let stride = target.x as usize * 4;
+    let mut canvas = [0, 0, 0, 255].repeat(target.x as usize * target.y as usize);
+    let mut decoded = ffmpeg::frame::Video::empty();
+    let mut scaled = ffmpeg::frame::Video::empty();
+    let mut frame_number = 0;
+
+    let mut drain = |decoder: &mut ffmpeg::decoder::Video| -> Result<(), String> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            scaler
+                .run(&decoded, &mut scaled)
+                .map_err(|e| format!("Failed to scale frame: {e}"))?;
+
+            let row_len = letterbox.size.x as usize * 4;
+            for y in 0..letterbox.size.y as usize {
+                let src = y * scaled.stride(0);
+                let dst =
+                    (letterbox.offset.y as usize + y) * stride + letterbox.offset.x as usize * 4;
+                canvas[dst..dst + row_len].copy_from_slice(&scaled.data(0)[src..src + row_len]);
+            }
+
+            // keep the source's timing, falling back to sequential frames when it has none
+            let pts = decoded
+                .timestamp()
+                .map(|ts| (ts as f64 * time_base * fps as f64).round() as i64)
+                .unwrap_or(frame_number);
+            frame_number = pts + 1;
+
+            let mut frame = info.wrap_frame(&canvas, 0, stride);
+            frame.set_pts(Some(pts));
+            encoder.queue_video_frame(frame);
+        }
+
+        Ok(())
+    };
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| format!("Failed to decode video: {e}"))?;
+        drain(&mut decoder)?;
+    }
+
+    decoder.send_eof().ok();
+    drain(&mut decoder)?;
+
+    encoder.finish();
+
+    Ok(())
+}
@@ -1,17 +1,21 @@
 use std::{sync::Arc, time::Duration};
 
 use cap_media::data::{AudioInfo, AudioInfoError, FromSampleBytes};
-use cap_media::feeds::{AudioData, AudioPlaybackBuffer};
+use cap_media::feeds::{AudioPlaybackBuffer, SegmentAudio};
 use cap_project::{ProjectConfiguration, XY};
-use cap_rendering::{ProjectUniforms, RenderVideoConstants};
+use cap_rendering::{decode_transition, ProjectUniforms, RenderVideoConstants};
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     BufferSize, SampleFormat,
 };
-use tokio::{sync::watch, time::Instant};
+use tokio::{
+    sync::{mpsc, watch},
+    time::Instant,
+};
 
 use crate::editor;
 use crate::editor_instance::Segment;
+use crate::frame_cache::FrameKey;
 
 pub struct Playback {
     pub renderer: Arc<editor::RendererHandle>,
@@ -19,6 +23,8 @@ pub struct Playback {
     pub start_frame_number: u32,
     pub project: watch::Receiver<ProjectConfiguration>,
     pub segments: Arc<Vec<Segment>>,
+    // start over from the beginning instead of stopping at the end
+    pub loop_playback: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -28,12 +34,47 @@ pub enum PlaybackEvent {
     Stop,
 }
 
+enum PlaybackCommand {
+    Pause,
+    Resume,
+    Seek(u32),
+    SetLoop(bool),
+}
+
 #[derive(Clone)]
 pub struct PlaybackHandle {
     stop_tx: watch::Sender<bool>,
+    command_tx: mpsc::UnboundedSender<PlaybackCommand>,
     event_rx: watch::Receiver<PlaybackEvent>,
 }
 
+// Where playback is, as a frame and the moment it was at that frame. Video frames are picked
+// from this and audio is restarted from it whenever it changes, which keeps the two in sync.
+struct PlaybackClock {
+    frame_number: u32,
+    at: Instant,
+}
+
+impl PlaybackClock {
+    fn new(frame_number: u32) -> Self {
+        Self {
+            frame_number,
+            at: Instant::now(),
+        }
+    }
+
+    fn time(&self, fps: u32) -> f64 {
+        self.frame_number as f64 / fps as f64 + self.at.elapsed().as_secs_f64()
+    }
+
+    fn frame_start(&self, frame_number: u32, fps: u32) -> Instant {
+        self.at
+            + Duration::from_secs_f64(
+                frame_number.saturating_sub(self.frame_number) as f64 / fps as f64,
+            )
+    }
+}
+
 impl Playback {
     pub async fn start(
         self,
@@ -44,96 +85,172 @@ impl Playback {
         let (stop_tx, mut stop_rx) = watch::channel(false);
         stop_rx.borrow_and_update();
 
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+
         let (event_tx, mut event_rx) = watch::channel(PlaybackEvent::Start);
         event_rx.borrow_and_update();
 
         let handle = PlaybackHandle {
             stop_tx: stop_tx.clone(),
+            command_tx,
             event_rx,
         };
 
         tokio::spawn(async move {
-            let start = Instant::now();
-
             let duration = if let Some(timeline) = &self.project.borrow().timeline {
                 timeline.duration()
             } else {
                 f64::MAX
             };
 
-            // TODO: make this work with >1 segment
-            if self.segments[0].audio.is_some() {
-                AudioPlayback {
-                    segments: self
-                        .segments
-                        .iter()
-                        .map(|s| s.audio.as_ref().as_ref().unwrap().clone())
-                        .collect(),
-                    stop_rx: stop_rx.clone(),
-                    start_frame_number: self.start_frame_number,
-                    project: self.project.clone(),
-                    fps,
-                }
-                .spawn();
-            };
+            let mut clock = PlaybackClock::new(self.start_frame_number);
+            let mut audio = self.spawn_audio(clock.frame_number, fps);
+            let mut loop_playback = self.loop_playback;
+            let mut paused = false;
+            let mut last_frame = None::<u32>;
+            let mut dropped_frames = 0;
+            let mut pending = None::<PlaybackCommand>;
 
             loop {
-                let time =
-                    (self.start_frame_number as f64 / fps as f64) + start.elapsed().as_secs_f64();
-                let frame_number = (time * fps as f64).floor() as u32;
-
-                if frame_number as f64 >= fps as f64 * duration {
+                if *stop_rx.borrow() {
                     break;
+                }
+
+                while let Some(command) = pending.take().or_else(|| command_rx.try_recv().ok()) {
+                    let frame_number = last_frame.unwrap_or(clock.frame_number);
+
+                    match command {
+                        PlaybackCommand::Pause if !paused => {
+                            paused = true;
+                            clock = PlaybackClock::new(frame_number);
+                            audio.take().map(|a| a.send(true));
+                        }
+                        PlaybackCommand::Resume if paused => {
+                            paused = false;
+                            clock = PlaybackClock::new(frame_number);
+                            audio = self.spawn_audio(clock.frame_number, fps);
+                        }
+                        PlaybackCommand::Seek(frame_number) => {
+                            clock = PlaybackClock::new(frame_number);
+                            last_frame = None;
+                            audio.take().map(|a| a.send(true));
+                            if !paused {
+                                audio = self.spawn_audio(clock.frame_number, fps);
+                            }
+                        }
+                        PlaybackCommand::SetLoop(enabled) => loop_playback = enabled,
+                        _ => {}
+                    }
+                }
+
+                // a paused playback still shows where it's seeked to
+                if paused && last_frame.is_some() {
+                    tokio::select! {
+                        _ = stop_rx.changed() => break,
+                        Some(command) = command_rx.recv() => {
+                            // handled at the top of the loop
+                            pending = Some(command);
+                            continue;
+                        }
+                    }
+                }
+
+                let mut frame_number = if paused {
+                    clock.frame_number
+                } else {
+                    (clock.time(fps) * fps as f64).floor() as u32
                 };
 
+                if frame_number as f64 >= fps as f64 * duration {
+                    if !loop_playback || paused {
+                        break;
+                    }
+
+                    clock = PlaybackClock::new(0);
+                    frame_number = 0;
+                    last_frame = None;
+                    audio.take().map(|a| a.send(true));
+                    audio = self.spawn_audio(0, fps);
+                }
+
+                // the previous frame took longer than a frame to render, so skip to the one
+                // that's due now rather than falling behind the audio
+                if let Some(last_frame) = last_frame {
+                    dropped_frames += frame_number.saturating_sub(last_frame + 1);
+                }
+
+                let time = frame_number as f64 / fps as f64;
                 let project = self.project.borrow().clone();
 
                 if let Some((segment_time, segment_i)) = project.get_segment_time(time) {
                     let segment = &self.segments[segment_i as usize];
+                    let display = project.get_segment_display(time);
+                    let cursor = segment.cursors.get(display);
+                    let cache_key = FrameKey::new(&project, frame_number, fps, resolution_base);
+
+                    if !self.renderer.show_cached(&cache_key) {
+                        tokio::select! {
+                            _ = stop_rx.changed() => {
+                               break;
+                            },
+                            data = segment.decoders.get_frames(segment_time as f32, !project.camera.hide, display) => {
+                                if let Some(mut segment_frames) = data {
+                                    segment_frames.transition = decode_transition(&project, time, |i| {
+                                        self.segments.get(i as usize).map(|s| (&s.decoders, &s.cursors))
+                                    })
+                                    .await;
 
-                    tokio::select! {
-                        _ = stop_rx.changed() => {
-                           break;
-                        },
-                        data = segment.decoders.get_frames(segment_time as f32, !project.camera.hide) => {
-                            if let Some(segment_frames) = data {
-                                let uniforms = ProjectUniforms::new(
-                                    &self.render_constants,
-                                    &project,
-                                    frame_number,
-                                    fps,
-                                    resolution_base,
-                                    is_upgraded,
-                                    &segment.cursor
-                                );
-
-                                self
-                                    .renderer
-                                    .render_frame(
-                                        segment_frames,
-                                        project.background.source.clone(),
-                                        uniforms,
+                                    let uniforms = ProjectUniforms::new(
+                                        &self.render_constants,
+                                        &project,
+                                        frame_number,
+                                        fps,
                                         resolution_base,
-                                        segment.cursor.clone()
-                                    )
-                                    .await;
+                                        is_upgraded,
+                                        cursor
+                                    );
+
+                                    self
+                                        .renderer
+                                        .render_frame(
+                                            segment_frames,
+                                            project.background.source.clone(),
+                                            uniforms,
+                                            resolution_base,
+                                            cursor.clone(),
+                                            Some(cache_key),
+                                        )
+                                        .await;
+                                }
+                            }
+                            else => {
                             }
-                        }
-                        else => {
                         }
                     }
                 }
 
-                tokio::time::sleep_until(
-                    start
-                        + (frame_number - self.start_frame_number)
-                            * Duration::from_secs_f32(1.0 / fps as f32),
-                )
-                .await;
-
+                last_frame = Some(frame_number);
                 event_tx.send(PlaybackEvent::Frame(frame_number)).ok();
+
+                if paused {
+                    continue;
+                }
+
+                // wait for the next frame to be due, or for something to change
+                let next_frame = clock.frame_start(frame_number + 1, fps);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(next_frame) => {}
+                    _ = stop_rx.changed() => break,
+                    Some(command) = command_rx.recv() => pending = Some(command),
+                }
+            }
+
+            if dropped_frames > 0 {
+                println!("Playback dropped {dropped_frames} frames");
             }
 
+            audio.take().map(|a| a.send(true));
+
             stop_tx.send(true).ok();
 
             event_tx.send(PlaybackEvent::Stop).ok();
@@ -141,6 +258,29 @@ impl Playback {
 
         handle
     }
+
+    // Plays the audio from `frame_number`, returning what stops it
+    fn spawn_audio(&self, frame_number: u32, fps: u32) -> Option<watch::Sender<bool>> {
+        // TODO: make this work with >1 segment
+        self.segments[0].audio.as_ref().as_ref()?;
+
+        let (stop_tx, stop_rx) = watch::channel(false);
+
+        AudioPlayback {
+            segments: self
+                .segments
+                .iter()
+                .map(|s| s.audio.as_ref().as_ref().unwrap().clone())
+                .collect(),
+            stop_rx,
+            start_frame_number: frame_number,
+            project: self.project.clone(),
+            fps,
+        }
+        .spawn();
+
+        Some(stop_tx)
+    }
 }
 
 impl PlaybackHandle {
@@ -148,6 +288,28 @@ impl PlaybackHandle {
         self.stop_tx.send(true).ok();
     }
 
+    pub fn pause(&self) {
+        self.command_tx.send(PlaybackCommand::Pause).ok();
+    }
+
+    pub fn resume(&self) {
+        self.command_tx.send(PlaybackCommand::Resume).ok();
+    }
+
+    pub fn seek(&self, frame_number: u32) {
+        self.command_tx
+            .send(PlaybackCommand::Seek(frame_number))
+            .ok();
+    }
+
+    pub fn set_loop(&self, enabled: bool) {
+        self.command_tx.send(PlaybackCommand::SetLoop(enabled)).ok();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !*self.stop_tx.borrow()
+    }
+
     pub async fn receive_event(&mut self) -> watch::Ref<'_, PlaybackEvent> {
         self.event_rx.changed().await.ok();
         self.event_rx.borrow_and_update()
@@ -155,7 +317,7 @@ impl PlaybackHandle {
 }
 
 struct AudioPlayback {
-    segments: Vec<AudioData>,
+    segments: Vec<SegmentAudio>,
     stop_rx: watch::Receiver<bool>,
     start_frame_number: u32,
     project: watch::Receiver<ProjectConfiguration>,
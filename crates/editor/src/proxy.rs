@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use cap_media::{
+    data::{RawVideoFormat, VideoInfo},
+    encoders::{H264Encoder, MP4File, RateControl},
+};
+use cap_project::{Content, RecordingMeta};
+
+// recordings with more pixels than this get a proxy
+const PROXY_THRESHOLD: u32 = 1920 * 1080;
+const PROXY_HEIGHT: u32 = 720;
+const PROXY_BIT_RATE: usize = 4_000_000;
+
+// Where the proxy of a recorded video lives, next to it in a `proxies` folder
+pub fn proxy_path(path: &Path) -> PathBuf {
+    let mut proxy = path.with_file_name("proxies");
+    proxy.push(path.file_name().unwrap_or_default());
+    proxy.set_extension("mp4");
+    proxy
+}
+
+// The proxy for `path` if one has been generated, otherwise `path` itself
+pub fn editing_path(path: PathBuf) -> PathBuf {
+    let proxy = proxy_path(&path);
+    if proxy.exists() {
+        proxy
+    } else {
+        path
+    }
+}
+
+// Transcodes a low resolution copy of each large display recording for the editor to preview
+// with, so scrubbing and playback don't have to decode 4K frames. Exports still read the
+// originals. Cameras are left alone, they're rarely large and are drawn at their own size.
+pub fn generate_proxies(meta: &RecordingMeta) -> Result<(), String> {
+    let displays = match &meta.content {
+        Content::SingleSegment { segment } => vec![meta.path(&segment.display.path)],
+        Content::MultipleSegments { inner } => inner
+            .segments
+            .iter()
+            .flat_map(|s| s.displays())
+            .map(|d| meta.path(&d.path))
+            .collect(),
+    };
+
+    for display in displays {
+        if proxy_path(&display).exists() {
+            continue;
+        }
+
+        generate_proxy(&display)?;
+    }
+
+    Ok(())
+}
+
+fn generate_proxy(source: &Path) -> Result<(), String> {
+    let mut input =
+        ffmpeg::format::input(&source).map_err(|e| format!("Failed to open video: {e}"))?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| "No video stream found".to_string())?;
+    let stream_index = stream.index();
+    let time_base = f64::from(stream.time_base());
+    let rate = stream.avg_frame_rate();
+    let fps = (rate.numerator() as f64 / rate.denominator().max(1) as f64)
+        .round()
+        .max(1.0) as u32;
+
+    let mut decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())
+        .map_err(|e| format!("Failed to create decoder: {e}"))?
+        .decoder()
+        .video()
+        .map_err(|e| format!("Failed to get video decoder: {e}"))?;
+
+    if decoder.width() * decoder.height() <= PROXY_THRESHOLD {
+        return Ok(());
+    }
+
+    // encoders want even dimensions
+    let height = PROXY_HEIGHT.min(decoder.height()) & !1;
+    let width = ((decoder.width() as f64 * height as f64 / decoder.height() as f64) as u32) & !1;
+
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::RGBA,
+        width,
+        height,
+        ffmpeg::software::scaling::Flags::BILINEAR,
+    )
+    .map_err(|e| format!("Failed to create scaler: {e}"))?;
+
+    let mut info = VideoInfo::from_raw(RawVideoFormat::Rgba, width, height, fps);
+    info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+    // written under another name first so a half finished proxy is never picked up
+    let output = proxy_path(source);
+    let partial = output.with_extension("partial.mp4");
+    std::fs::create_dir_all(output.parent().unwrap())
+        .map_err(|e| format!("Failed to create proxies folder: {e}"))?;
+
+    let mut encoder = MP4File::init(
+        "proxy",
+        partial.clone(),
+        |o| {
+            H264Encoder::init_with_rate_control(
+                "proxy_video",
+                info,
+                RateControl::BitRate(PROXY_BIT_RATE),
+                o,
+            )
+        },
+        |_| None,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut decoded = ffmpeg::frame::Video::empty();
+    let mut scaled = ffmpeg::frame::Video::empty();
+    let mut frame_number = 0;
+
+    let mut drain = |decoder: &mut ffmpeg::decoder::Video| -> Result<(), String> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            scaler
+                .run(&decoded, &mut scaled)
+                .map_err(|e| format!("Failed to scale frame: {e}"))?;
+
+            // keep the source's timing so proxy frames line up with the original's
+            let pts = decoded
+                .timestamp()
+                .map(|ts| (ts as f64 * time_base * fps as f64).round() as i64)
+                .unwrap_or(frame_number);
+            frame_number = pts + 1;
+
+            encoder.queue_video_frame(info.wrap_frame(scaled.data(0), pts, scaled.stride(0)));
+        }
+
+        Ok(())
+    };
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| format!("Failed to decode video: {e}"))?;
+        drain(&mut decoder)?;
+    }
+
+    decoder.send_eof().ok();
+    drain(&mut decoder)?;
+
+    encoder.finish();
+    drop(encoder);
+
+    std::fs::rename(&partial, &output).map_err(|e| format!("Failed to save proxy: {e}"))
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use cap_project::{OcrFrame, OcrWord, XY};
+
+// Frames are read this far apart. Text has to stay on screen at least this long to be
+// caught, which anything worth reading does.
+pub const OCR_INTERVAL_SECS: f64 = 1.0;
+// frames are shrunk to at most this wide, which keeps screen text legible and OCR quick
+const MAX_WIDTH: u32 = 2560;
+// words read with less confidence than this, out of 100, are noise
+const MIN_CONFIDENCE: f64 = 30.0;
+// a frame with less than this fraction of its pixels changed from the last one read, by
+// more than PIXEL_THRESHOLD, is taken to show the same text without reading it again
+const CHANGED_FRACTION: f64 = 0.001;
+const PIXEL_THRESHOLD: u8 = 12;
+
+// Reads the text on screen throughout a video, every OCR_INTERVAL_SECS. Uses the Tesseract
+// OCR engine, which has to be installed separately.
+pub fn read_frames(path: &Path) -> Result<Vec<OcrFrame>, String> {
+    let mut input =
+        ffmpeg::format::input(&path).map_err(|e| format!("Failed to open video: {e}"))?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| "No video stream found".to_string())?;
+    let stream_index = stream.index();
+    let time_base = f64::from(stream.time_base());
+
+    let mut decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())
+        .map_err(|e| format!("Failed to create decoder: {e}"))?
+        .decoder()
+        .video()
+        .map_err(|e| format!("Failed to get video decoder: {e}"))?;
+
+    let scale = (MAX_WIDTH as f64 / decoder.width() as f64).min(1.0);
+    let size = (
+        ((decoder.width() as f64 * scale) as u32).max(1),
+        ((decoder.height() as f64 * scale) as u32).max(1),
+    );
+
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::GRAY8,
+        size.0,
+        size.1,
+        ffmpeg::software::scaling::Flags::AREA,
+    )
+    .map_err(|e| format!("Failed to create scaler: {e}"))?;
+
+    let mut decoded = ffmpeg::frame::Video::empty();
+    let mut scaled = ffmpeg::frame::Video::empty();
+    let mut previous: Option<(Vec<u8>, Vec<OcrWord>)> = None;
+    let mut next_sample = 0.0;
+    let mut frames = vec![];
+
+    let mut drain = |decoder: &mut ffmpeg::decoder::Video| -> Result<(), String> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let Some(time) = decoded.timestamp().map(|ts| ts as f64 * time_base) else {
+                continue;
+            };
+            if time < next_sample {
+                continue;
+            }
+            next_sample = time + OCR_INTERVAL_SECS;
+
+            scaler
+                .run(&decoded, &mut scaled)
+                .map_err(|e| format!("Failed to scale frame: {e}"))?;
+
+            let width = size.0 as usize;
+            let pixels = (0..size.1 as usize)
+                .flat_map(|y| {
+                    let row = y * scaled.stride(0);
+                    scaled.data(0)[row..row + width].iter().copied()
+                })
+                .collect::<Vec<_>>();
+
+            let words = match previous.take() {
+                Some((previous_pixels, words)) if !changed(&previous_pixels, &pixels) => words,
+                _ => recognize(&pixels, size)?,
+            };
+
+            frames.push(OcrFrame {
+                time,
+                words: words.clone(),
+            });
+            previous = Some((pixels, words));
+        }
+
+        Ok(())
+    };
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| format!("Failed to decode video: {e}"))?;
+        drain(&mut decoder)?;
+    }
+
+    decoder.send_eof().ok();
+    drain(&mut decoder)?;
+
+    Ok(frames)
+}
+
+fn changed(a: &[u8], b: &[u8]) -> bool {
+    // every few pixels is plenty to notice text changing
+    let changed = a
+        .iter()
+        .zip(b)
+        .step_by(7)
+        .filter(|(a, b)| a.abs_diff(**b) >= PIXEL_THRESHOLD)
+        .count();
+
+    changed as f64 > (a.len() / 7) as f64 * CHANGED_FRACTION
+}
+
+// Reads the words in a grayscale image by piping it to Tesseract as a PGM
+fn recognize(pixels: &[u8], (width, height): (u32, u32)) -> Result<Vec<OcrWord>, String> {
+    let mut child = Command::new("tesseract")
+        .args(["stdin", "stdout", "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "Finding sensitive text needs Tesseract OCR to be installed".to_string()
+            }
+            _ => format!("Failed to start Tesseract: {e}"),
+        })?;
+
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin
+            .write_all(format!("P5\n{width} {height}\n255\n").as_bytes())
+            .and_then(|_| stdin.write_all(pixels))
+            .map_err(|e| format!("Failed to send frame to Tesseract: {e}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run Tesseract: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("Tesseract failed with {}", output.status));
+    }
+
+    Ok(parse_tsv(
+        &String::from_utf8_lossy(&output.stdout),
+        (width, height),
+    ))
+}
+
+// Tesseract's TSV output has a row per page, block, paragraph, line and word, with the
+// word rows at level 5:
+// level page_num block_num par_num line_num word_num left top width height conf text
+fn parse_tsv(tsv: &str, (width, height): (u32, u32)) -> Vec<OcrWord> {
+    let mut lines = HashMap::new();
+    let mut words = vec![];
+
+    for row in tsv.lines().skip(1) {
+        let columns = row.split('\t').collect::<Vec<_>>();
+        let [level, _, block, paragraph, line, _, left, top, w, h, confidence, text] = columns[..]
+        else {
+            continue;
+        };
+        if level != "5" || text.trim().is_empty() {
+            continue;
+        }
+
+        let [Some(left), Some(top), Some(w), Some(h), Some(confidence)] =
+            [left, top, w, h, confidence].map(|v| v.parse::<f64>().ok())
+        else {
+            continue;
+        };
+        if confidence < MIN_CONFIDENCE {
+            continue;
+        }
+
+        let next_line = lines.len() as u32;
+        let line = *lines.entry((block, paragraph, line)).or_insert(next_line);
+
+        words.push(OcrWord {
+            text: text.to_string(),
+            line,
+            position: XY::new(left / width as f64, top / height as f64),
+            size: XY::new(w / width as f64, h / height as f64),
+        });
+    }
+
+    words
+}
@@ -0,0 +1,196 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cap_project::TranscriptWord;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// whisper.cpp only takes 16kHz audio
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+// How far along transcribing the recording segments is
+#[derive(Type, Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionProgress {
+    pub segment: u32,
+    pub segments: u32,
+    // of the current segment, from 0 to 1
+    pub fraction: f64,
+}
+
+#[derive(Deserialize)]
+struct WhisperOutput {
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Deserialize)]
+struct WhisperSegment {
+    // in milliseconds
+    offsets: WhisperOffsets,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct WhisperOffsets {
+    from: u64,
+    to: u64,
+}
+
+// Transcribes interleaved f32 audio into words, on this machine. Uses whisper.cpp's CLI and
+// the ggml `model`, which have to be installed separately. `on_progress` gets the fraction
+// done as whisper reports it.
+pub fn transcribe(
+    samples: &[f32],
+    channels: usize,
+    sample_rate: u32,
+    model: &Path,
+    mut on_progress: impl FnMut(f64),
+) -> Result<Vec<TranscriptWord>, String> {
+    let base = std::env::temp_dir().join(format!(
+        "cap-transcription-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos())
+    ));
+    let wav = base.with_extension("wav");
+    let json = base.with_extension("json");
+
+    std::fs::write(&wav, to_wav(samples, channels, sample_rate))
+        .map_err(|e| format!("Failed to write audio for transcription: {e}"))?;
+
+    let result = run_whisper(&wav, &base, model, &mut on_progress).and_then(|_| {
+        let output = std::fs::read_to_string(&json)
+            .map_err(|e| format!("Failed to read the transcription: {e}"))?;
+        parse_output(&output)
+    });
+
+    std::fs::remove_file(&wav).ok();
+    std::fs::remove_file(&json).ok();
+
+    result
+}
+
+fn run_whisper(
+    wav: &Path,
+    output_base: &Path,
+    model: &Path,
+    on_progress: &mut impl FnMut(f64),
+) -> Result<(), String> {
+    let mut child = Command::new("whisper-cli")
+        .arg("--model")
+        .arg(model)
+        .arg("--file")
+        .arg(wav)
+        .arg("--output-file")
+        .arg(output_base)
+        // a segment per word, so every word gets its own timestamps
+        .args([
+            "--output-json",
+            "--max-len",
+            "1",
+            "--split-on-word",
+            "--language",
+            "auto",
+            "--print-progress",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "Generating captions needs whisper.cpp to be installed".to_string()
+            }
+            _ => format!("Failed to start whisper.cpp: {e}"),
+        })?;
+
+    // progress comes as lines like "whisper_print_progress_callback: progress =  45%"
+    let stderr = child.stderr.take().expect("stderr is piped");
+    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        let percent = line
+            .split_once("progress =")
+            .and_then(|(_, p)| p.trim().trim_end_matches('%').parse::<f64>().ok());
+        if let Some(percent) = percent {
+            on_progress((percent / 100.0).clamp(0.0, 1.0));
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run whisper.cpp: {e}"))?;
+    if !status.success() {
+        return Err(format!("whisper.cpp failed with {status}"));
+    }
+
+    Ok(())
+}
+
+// Leaves out what whisper marks as not speech, like "[BLANK_AUDIO]" or "(music)"
+fn parse_output(json: &str) -> Result<Vec<TranscriptWord>, String> {
+    let output: WhisperOutput =
+        serde_json::from_str(json).map_err(|e| format!("Invalid transcription: {e}"))?;
+
+    Ok(output
+        .transcription
+        .into_iter()
+        .filter(|segment| {
+            let text = segment.text.trim();
+            !text.is_empty()
+                && !(text.starts_with('[') && text.ends_with(']'))
+                && !(text.starts_with('(') && text.ends_with(')'))
+        })
+        .map(|segment| TranscriptWord {
+            start: segment.offsets.from as f64 / 1000.0,
+            end: segment.offsets.to as f64 / 1000.0,
+            text: segment.text,
+        })
+        .collect())
+}
+
+// The audio mixed down to mono and resampled for whisper, as a 16-bit PCM WAV file
+fn to_wav(samples: &[f32], channels: usize, sample_rate: u32) -> Vec<u8> {
+    let channels = channels.max(1);
+    let mono = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect::<Vec<_>>();
+
+    // linear interpolation is plenty for speech
+    let step = sample_rate as f64 / WHISPER_SAMPLE_RATE as f64;
+    let length = (mono.len() as f64 / step) as usize;
+    let resampled = (0..length).map(|i| {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let t = (position - index as f64) as f32;
+        let a = mono[index];
+        let b = mono.get(index + 1).copied().unwrap_or(a);
+        a + (b - a) * t
+    });
+
+    let data_size = (length * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&WHISPER_SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(WHISPER_SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+
+    for sample in resampled {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
@@ -0,0 +1,264 @@
+use cap_media::{
+    data::{RawVideoFormat, VideoInfo},
+    encoders::{GifDither, GifEncoder, WebPFile},
+};
+use cap_project::XY;
+use cap_rendering::{ProjectUniforms, RenderedFrame};
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    crop_frame, progress::discard_cancelled, ExportError, ExportProgress, ExportStage, Exporter,
+};
+
+// attempts at fitting under `max_size_kib` before settling for the smallest
+const MAX_SIZE_ATTEMPTS: usize = 4;
+// below this, shrinking further makes the animation unreadable rather than smaller
+const MIN_SCALE: f64 = 0.2;
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnimatedFormat {
+    #[default]
+    Gif,
+    WebP,
+}
+
+impl AnimatedFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::WebP => "webp",
+        }
+    }
+}
+
+impl FromStr for AnimatedFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "gif" => Self::Gif,
+            "webp" => Self::WebP,
+            _ => return Err("expected gif or webp".to_string()),
+        })
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimatedExportSettings {
+    pub format: AnimatedFormat,
+    // animations rarely need the recording's frame rate, and every frame adds to the size
+    pub max_fps: u32,
+    // GIF only, colours in each frame's palette, up to 256
+    pub max_colors: u16,
+    // GIF only
+    pub dither: GifDither,
+    // WebP only, 0 to 100 where 100 is lossless
+    pub quality: u8,
+    // the animation is rendered smaller until it fits in this many KiB, when set
+    pub max_size_kib: Option<u32>,
+}
+
+impl Default for AnimatedExportSettings {
+    fn default() -> Self {
+        Self {
+            format: AnimatedFormat::Gif,
+            max_fps: 15,
+            max_colors: 256,
+            dither: GifDither::default(),
+            quality: 80,
+            max_size_kib: None,
+        }
+    }
+}
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// Exports as an animated GIF or WebP without audio, rendering again at a smaller size
+    /// while the result is over `max_size_kib`.
+    pub async fn export_animated(
+        self,
+        settings: AnimatedExportSettings,
+    ) -> Result<PathBuf, ExportError> {
+        let fps = self.fps.min(settings.max_fps).max(1);
+        let output_path = self.output_path.with_extension(settings.format.extension());
+
+        let mut scale = 1.0;
+
+        for attempt in 1..=MAX_SIZE_ATTEMPTS {
+            self.render_animated(&output_path, fps, scale, &settings)
+                .await?;
+
+            let size = std::fs::metadata(&output_path)?.len();
+
+            match settings.max_size_kib.map(|kib| kib as u64 * 1024) {
+                Some(max_size) if size > max_size && attempt < MAX_SIZE_ATTEMPTS => {
+                    if scale <= MIN_SCALE {
+                        break;
+                    }
+
+                    // size goes roughly with the pixel count, so the sides shrink by its root,
+                    // and a little more since palettes and headers don't shrink with them
+                    scale = (scale * (max_size as f64 / size as f64).sqrt() * 0.9).max(MIN_SCALE);
+                    tracing::info!(
+                        "Animation is {size} bytes, over {max_size}, rendering at {scale:.2}x"
+                    );
+                }
+                _ => break,
+            }
+        }
+
+        Ok(output_path)
+    }
+
+    async fn render_animated(
+        &self,
+        output_path: &Path,
+        fps: u32,
+        scale: f64,
+        settings: &AnimatedExportSettings,
+    ) -> Result<(), ExportError> {
+        let options = self.render_options();
+        let resolution_base = XY::new(
+            ((self.resolution_base.x as f64 * scale) as u32).max(2),
+            ((self.resolution_base.y as f64 * scale) as u32).max(2),
+        );
+
+        let full_size =
+            ProjectUniforms::get_output_size(&options, &self.project, self.resolution_base);
+        let render_size =
+            ProjectUniforms::get_output_size(&options, &self.project, resolution_base);
+        let crop = self
+            .crop
+            .map(|rect| rect.scaled(render_size.0 as f64 / full_size.0 as f64, render_size));
+        let (width, height) = crop
+            .map(|rect| (rect.width, rect.height))
+            .unwrap_or(render_size);
+
+        let (tx_image_data, mut rx_image_data) =
+            tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<(RenderedFrame, u32)>(4);
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let output_path = output_path.to_path_buf();
+            let settings = *settings;
+            let cancellation = self.cancellation.clone();
+
+            move || {
+                match settings.format {
+                    AnimatedFormat::Gif => {
+                        let mut encoder = GifEncoder::init(
+                            output_path.clone(),
+                            width,
+                            height,
+                            fps,
+                            settings.max_colors,
+                            settings.dither,
+                        )?;
+
+                        while let Ok((frame, _)) = frame_rx.recv() {
+                            encoder
+                                .queue_frame(&frame.data, frame.padded_bytes_per_row as usize)?;
+                        }
+
+                        if cancellation.is_cancelled() {
+                            drop(encoder);
+                            return Err(discard_cancelled(&output_path));
+                        }
+
+                        encoder.finish()?;
+                    }
+                    AnimatedFormat::WebP => {
+                        let mut info =
+                            VideoInfo::from_raw(RawVideoFormat::Rgba, width, height, fps);
+                        info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+                        let mut encoder =
+                            WebPFile::init(output_path.clone(), info, settings.quality)?;
+
+                        while let Ok((frame, frame_number)) = frame_rx.recv() {
+                            let mut video_frame = info.wrap_frame(
+                                &frame.data,
+                                0,
+                                frame.padded_bytes_per_row as usize,
+                            );
+                            video_frame.set_pts(Some(frame_number as i64));
+                            encoder.queue_frame(video_frame);
+                        }
+
+                        if cancellation.is_cancelled() {
+                            drop(encoder);
+                            return Err(discard_cancelled(&output_path));
+                        }
+
+                        encoder.finish();
+                    }
+                }
+
+                Ok::<_, ExportError>(())
+            }
+        })
+        .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
+
+        let effects = self.effect_pass(fps);
+        let total_frames = self.frame_count(fps);
+
+        let forward_task = async {
+            let frame_tx = frame_tx;
+            let mut frame_count = 0;
+
+            while let Some((mut frame, frame_number)) =
+                tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
+            {
+                self.cancellation.check()?;
+
+                if let Some(rect) = crop {
+                    frame = crop_frame(&frame, rect);
+                }
+
+                effects.apply(&mut frame, frame_number)?;
+
+                if frame_tx.send((frame, frame_number)).is_err() {
+                    break;
+                }
+
+                frame_count += 1;
+                self.progress
+                    .report(ExportStage::Rendering, frame_count, total_frames);
+            }
+
+            Ok::<_, ExportError>(())
+        };
+
+        let render_task = cap_rendering::render_video_to_channel(
+            options,
+            self.project.clone(),
+            tx_image_data,
+            &self.meta,
+            self.render_segments.clone(),
+            fps,
+            resolution_base,
+            self.is_upgraded,
+            self.frame_rate_conversion,
+        )
+        .map(|r| r.map_err(ExportError::from));
+
+        self.cancellation.or_cancelled(tokio::try_join!(
+            encoder_thread,
+            render_task,
+            forward_task
+        ))?;
+
+        Ok(())
+    }
+}
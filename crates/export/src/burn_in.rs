@@ -0,0 +1,161 @@
+use cap_project::ProjectConfiguration;
+use cap_rendering::RenderedFrame;
+
+use crate::effects::{
+    EffectContext, EffectInputs, EffectKind, EffectManifest, EffectPlugin, EffectRuntime,
+};
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+// Draws "DRAFT <revision> HH:MM:SS:FF" into the bottom left corner of draft exports
+pub struct DraftBurnIn {
+    manifest: EffectManifest,
+    revision: String,
+    fps: u32,
+}
+
+impl DraftBurnIn {
+    pub fn new(project: &ProjectConfiguration, fps: u32) -> Self {
+        Self {
+            manifest: EffectManifest {
+                id: "so.cap.draft-burn-in".to_string(),
+                name: "Draft burn-in".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                kind: EffectKind::Transform,
+                runtime: EffectRuntime::Native,
+                module: None,
+                inputs: EffectInputs::default(),
+            },
+            revision: project_revision(project),
+            fps,
+        }
+    }
+
+    fn apply(&self, frame: &mut RenderedFrame, frame_number: u32) {
+        let text = format!(
+            "DRAFT {} {}",
+            self.revision,
+            timecode(frame_number, self.fps)
+        );
+
+        let scale = (frame.height as usize / 270).max(1);
+        let margin = scale * 4;
+        let advance = (GLYPH_WIDTH + 1) * scale;
+
+        let box_width = text.len() * advance + margin;
+        let box_height = (GLYPH_HEIGHT + 2) * scale;
+        let box_x = margin;
+        let Some(box_y) = (frame.height as usize).checked_sub(box_height + margin) else {
+            return;
+        };
+
+        let stride = frame.padded_bytes_per_row as usize;
+        let width = frame.width as usize;
+
+        let mut blend = |x: usize, y: usize, color: [u8; 3], alpha: u16| {
+            if x >= width {
+                return;
+            }
+            let i = y * stride + x * 4;
+            let Some(pixel) = frame.data.get_mut(i..i + 3) else {
+                return;
+            };
+            for (c, target) in pixel.iter_mut().zip(color) {
+                *c = ((*c as u16 * (255 - alpha) + target as u16 * alpha) / 255) as u8;
+            }
+        };
+
+        for y in box_y..box_y + box_height {
+            for x in box_x..box_x + box_width {
+                blend(x, y, [0, 0, 0], 160);
+            }
+        }
+
+        let text_x = box_x + margin / 2;
+        let text_y = box_y + scale;
+
+        for (i, c) in text.chars().enumerate() {
+            let rows = glyph(c);
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            blend(
+                                text_x + i * advance + col * scale + dx,
+                                text_y + row * scale + dy,
+                                [255, 255, 255],
+                                255,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl EffectPlugin for DraftBurnIn {
+    fn manifest(&self) -> &EffectManifest {
+        &self.manifest
+    }
+
+    fn process(&self, frame: &mut RenderedFrame, context: &EffectContext) -> Result<(), String> {
+        self.apply(frame, context.frame_number);
+        Ok(())
+    }
+}
+
+// Short, stable identifier for the project configuration the export was rendered from,
+// so feedback on a review copy can be matched to the edit it was made against.
+pub fn project_revision(project: &ProjectConfiguration) -> String {
+    let json = serde_json::to_vec(project).unwrap_or_default();
+
+    // FNV-1a, which unlike `DefaultHasher` is stable across toolchains
+    let hash = json.iter().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ *b as u32).wrapping_mul(0x01000193)
+    });
+
+    format!("{hash:08X}")
+}
+
+pub fn timecode(frame_number: u32, fps: u32) -> String {
+    let fps = fps.max(1);
+    let total_seconds = frame_number / fps;
+
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds / 60) % 60,
+        total_seconds % 60,
+        frame_number % fps
+    )
+}
+
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
@@ -0,0 +1,34 @@
+use cap_project::{chapters_webvtt, Chapter};
+use std::path::Path;
+
+use crate::{ExportError, ExportProgress, Exporter};
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    // The project's markers as chapters of the exported video
+    pub(crate) fn chapters(&self) -> Vec<Chapter> {
+        self.project
+            .timeline
+            .as_ref()
+            .map(|t| t.chapters())
+            .unwrap_or_default()
+    }
+}
+
+// Saves `chapters` beside the video at `video_path` as `<name>.chapters.vtt`, which players
+// and formats that don't take chapters from the video itself can use. Nothing is written
+// for a project without markers.
+pub(crate) fn write_webvtt(chapters: &[Chapter], video_path: &Path) -> Result<(), ExportError> {
+    if chapters.is_empty() {
+        return Ok(());
+    }
+
+    std::fs::write(
+        video_path.with_extension("chapters.vtt"),
+        chapters_webvtt(chapters),
+    )?;
+
+    Ok(())
+}
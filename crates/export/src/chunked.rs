@@ -0,0 +1,490 @@
+use cap_media::{
+    data::{AudioInfo, RawVideoFormat, VideoInfo},
+    encoders::{add_chapters, MP4Input, RateControl},
+    MediaError,
+};
+use cap_project::{BackgroundSource, Chapter};
+use cap_rendering::{RecordingSegmentDecoders, RenderOptions, RenderSegment, RenderedFrame};
+use ffmpeg::Rescale;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    chapters, crop_frame, progress::discard_cancelled, segment_video_paths, AudioRender,
+    ExportError, ExportProgress, ExportStage, Exporter, OutputFile,
+};
+
+// Seconds of the timeline each chunk covers. Shorter chunks lose less to an interruption,
+// but each one starts with a keyframe.
+const CHUNK_SECONDS: u32 = 10;
+const MANIFEST_FILE: &str = "chunks.json";
+
+// Kept with the chunks so a resumed export only reuses chunks rendered the same way
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ChunkManifest {
+    // everything that changes what's rendered, as JSON
+    fingerprint: String,
+    chunk_frames: u32,
+    total_frames: u32,
+}
+
+// How every chunk of an export is encoded
+#[derive(Clone)]
+struct ChunkEncoding {
+    rate_control: RateControl,
+    audio_info: Option<AudioInfo>,
+    alpha: bool,
+}
+
+// What a worker renders its chunks with. Each has decoders of its own, so workers don't
+// seek each other around the timeline, and may render on a different GPU.
+struct ChunkWorker {
+    segments: Vec<RenderSegment>,
+    options: RenderOptions,
+    audio: Option<AudioRender>,
+}
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// Like `export_with_custom_muxer`, but renders the timeline in chunks kept in a directory
+    /// beside the output, joining them once they're all done. Exporting the same project again
+    /// after an interruption picks up from the chunks already finished.
+    ///
+    /// Each chunk is encoded once, so a target size is met from its average bit rate alone.
+    pub async fn export_resumable(self) -> Result<PathBuf, ExportError> {
+        let output_path = self
+            .output_path
+            .with_extension(self.settings.codec.extension());
+        let chunks_dir = self.output_path.with_extension("chunks");
+
+        let total_frames = self.frame_count(self.fps);
+        let duration = total_frames as f64 / self.fps as f64;
+        let chunk_frames = CHUNK_SECONDS * self.fps;
+
+        let manifest = ChunkManifest {
+            fingerprint: self.fingerprint()?,
+            chunk_frames,
+            total_frames,
+        };
+        prepare_chunks_dir(&chunks_dir, &manifest)?;
+
+        let audio_info = match self
+            .audio_segments
+            .first()
+            .and_then(|d| d.as_ref().as_ref())
+            .filter(|_| !self.project.audio.mute)
+        {
+            Some(audio_data) => Some(
+                AudioInfo::new(
+                    audio_data.info().sample_format,
+                    audio_data.info().sample_rate,
+                    audio_data.info().channels as u16,
+                )
+                .map_err(Into::<MediaError>::into)?,
+            ),
+            None => None,
+        };
+
+        let alpha = matches!(
+            self.project.background.source,
+            BackgroundSource::Transparent
+        );
+
+        let rate_control = match self
+            .settings
+            .two_pass_bit_rate(duration, audio_info.is_some())
+        {
+            Some(bit_rate) => RateControl::BitRate(bit_rate),
+            None => self.settings.rate_control(),
+        };
+
+        let encoding = ChunkEncoding {
+            rate_control,
+            audio_info,
+            alpha,
+        };
+
+        let extension = self.settings.codec.extension();
+        let progress = AtomicU32::new(0);
+        let mut chunks = vec![];
+        let mut pending = vec![];
+
+        for (i, start) in (0..total_frames).step_by(chunk_frames as usize).enumerate() {
+            let frames = start..(start + chunk_frames).min(total_frames);
+            let chunk_path = chunks_dir.join(format!("{i:05}.{extension}"));
+
+            // chunks are only given their final name once they're complete
+            if chunk_path.exists() {
+                tracing::debug!("Reusing chunk {i} for frames {frames:?}");
+                progress.fetch_add(frames.len() as u32, Ordering::Relaxed);
+            } else {
+                let partial_path = chunks_dir.join(format!("{i:05}-partial.{extension}"));
+                pending.push((frames, partial_path, chunk_path.clone()));
+            }
+
+            chunks.push(chunk_path);
+        }
+
+        self.progress.report(
+            ExportStage::Rendering,
+            progress.load(Ordering::Relaxed),
+            total_frames,
+        );
+
+        // each worker takes every `workers`th chunk still to render
+        let workers = self.workers.min(pending.len()).max(1);
+        let mut assignments = (0..workers).map(|_| vec![]).collect::<Vec<_>>();
+        for (n, chunk) in pending.into_iter().enumerate() {
+            assignments[n % workers].push(chunk);
+        }
+
+        // made once, since measuring the loudness mixes the whole timeline
+        let audio = AudioRender::new(&self.audio_segments, &self.project, &self.meta)?;
+        let gpus = if workers > 1 {
+            cap_rendering::gpu_count()
+        } else {
+            0
+        };
+
+        let mut worker_tasks = vec![];
+        for (index, assigned) in assignments.into_iter().enumerate() {
+            let worker = ChunkWorker {
+                segments: if index == 0 {
+                    self.render_segments.clone()
+                } else {
+                    self.decode_segments().await?
+                },
+                options: RenderOptions {
+                    gpu: (gpus > 1).then(|| (index % gpus) as u32),
+                    ..self.video_render_options()
+                },
+                audio: audio.as_ref().map(|a| a.fork(&self.audio_segments)),
+            };
+
+            worker_tasks.push(self.run_worker(
+                worker,
+                &encoding,
+                assigned,
+                &progress,
+                total_frames,
+            ));
+        }
+
+        self.cancellation
+            .or_cancelled(futures::future::try_join_all(worker_tasks).await)?;
+
+        if chunks.is_empty() {
+            return Err(ExportError::Other("Nothing to export".to_string()));
+        }
+
+        tracing::info!("Joining {} chunks", chunks.len());
+        self.progress
+            .report(ExportStage::Joining, total_frames, total_frames);
+
+        let chapters = self.chapters();
+        tokio::task::spawn_blocking({
+            let output_path = output_path.clone();
+            let chapters = chapters.clone();
+            let fps = self.fps;
+            move || concat_chunks(&chunks, &output_path, &chapters, chunk_frames, fps)
+        })
+        .await??;
+        chapters::write_webvtt(&chapters, &output_path)?;
+
+        std::fs::remove_dir_all(&chunks_dir)?;
+
+        Ok(output_path)
+    }
+
+    // Renders `chunks` one after another, renaming each to its final path once it's done
+    async fn run_worker(
+        &self,
+        mut worker: ChunkWorker,
+        encoding: &ChunkEncoding,
+        chunks: Vec<(Range<u32>, PathBuf, PathBuf)>,
+        progress: &AtomicU32,
+        total_frames: u32,
+    ) -> Result<(), ExportError> {
+        for (frames, partial_path, chunk_path) in chunks {
+            self.render_chunk(
+                &mut worker,
+                encoding,
+                frames,
+                partial_path.clone(),
+                progress,
+                total_frames,
+            )
+            .await?;
+            std::fs::rename(&partial_path, &chunk_path)?;
+        }
+
+        Ok(())
+    }
+
+    // Decoders of the recording separate from the exporter's own
+    async fn decode_segments(&self) -> Result<Vec<RenderSegment>, ExportError> {
+        let mut segments = vec![];
+
+        for (i, segment) in self.render_segments.iter().enumerate() {
+            segments.push(RenderSegment {
+                cursors: segment.cursors.clone(),
+                decoders: RecordingSegmentDecoders::new(
+                    &self.meta,
+                    segment_video_paths(&self.meta, i),
+                )
+                .await
+                .map_err(ExportError::Other)?,
+            });
+        }
+
+        Ok(segments)
+    }
+
+    fn fingerprint(&self) -> Result<String, ExportError> {
+        serde_json::to_string(&serde_json::json!({
+            "project": self.project,
+            "settings": self.settings,
+            "fps": self.fps,
+            "outputSize": self.output_size,
+            "crop": self.crop.map(|c| [c.x, c.y, c.width, c.height]),
+            "draft": self.draft,
+        }))
+        .map_err(|e| ExportError::Other(e.to_string()))
+    }
+
+    // Encodes `frames` of the timeline into a file of their own, with its timestamps
+    // starting from zero
+    async fn render_chunk(
+        &self,
+        worker: &mut ChunkWorker,
+        encoding: &ChunkEncoding,
+        frames: Range<u32>,
+        output_path: PathBuf,
+        progress: &AtomicU32,
+        total_frames: u32,
+    ) -> Result<(), ExportError> {
+        tracing::debug!("Rendering chunk for frames {frames:?}");
+
+        let (tx_image_data, mut rx_image_data) =
+            tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<MP4Input>(4);
+
+        let mut info = VideoInfo::from_raw(
+            RawVideoFormat::Rgba,
+            self.output_size.0,
+            self.output_size.1,
+            self.fps,
+        );
+        info.time_base = ffmpeg::Rational::new(1, self.fps as i32);
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let settings = self.settings;
+            let encoding = encoding.clone();
+            let cancellation = self.cancellation.clone();
+
+            move || {
+                let mut encoder = OutputFile::create(
+                    &settings,
+                    encoding.rate_control,
+                    info,
+                    output_path.clone(),
+                    encoding.alpha,
+                    encoding.audio_info,
+                    &[],
+                )?;
+
+                while let Ok(input) = frame_rx.recv() {
+                    encoder.queue(input);
+                }
+
+                // finished chunks are kept for resuming, but not one cut short
+                if cancellation.is_cancelled() {
+                    drop(encoder);
+                    return Err(discard_cancelled(&output_path));
+                }
+
+                encoder.finish();
+
+                Ok::<_, ExportError>(())
+            }
+        })
+        .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
+
+        let effects = self.effect_pass(self.fps);
+
+        let intro_frames = self.project.intro_frames(self.fps);
+        if let Some(audio) = &mut worker.audio {
+            // the playhead is in the timeline, which starts after the intro
+            audio.buffer.set_playhead(
+                frames.start.saturating_sub(intro_frames) as f64 / self.fps as f64,
+                &self.project,
+            );
+        }
+        let audio = &mut worker.audio;
+
+        let start = frames.start;
+
+        let forward_task = async {
+            let frame_tx = frame_tx;
+
+            while let Some((mut frame, frame_number)) =
+                tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
+            {
+                self.cancellation.check()?;
+
+                if let Some(rect) = self.crop {
+                    frame = crop_frame(&frame, rect);
+                }
+
+                effects.apply(&mut frame, frame_number)?;
+
+                let audio = audio.as_mut().and_then(|audio| {
+                    if frame_number < intro_frames {
+                        return Some(audio.silence(frame_number - start, self.fps));
+                    }
+
+                    audio.next_frame(frame_number - start, self.fps, &self.project)
+                });
+
+                let mut video =
+                    info.wrap_frame(&frame.data, 0, frame.padded_bytes_per_row as usize);
+                video.set_pts(Some((frame_number - start) as i64));
+
+                if frame_tx.send(MP4Input { video, audio }).is_err() {
+                    break;
+                }
+
+                self.progress.report(
+                    ExportStage::Rendering,
+                    progress.fetch_add(1, Ordering::Relaxed) + 1,
+                    total_frames,
+                );
+            }
+
+            Ok::<_, ExportError>(())
+        };
+
+        let render_task = cap_rendering::render_video_range_to_channel(
+            worker.options,
+            self.project.clone(),
+            tx_image_data,
+            &self.meta,
+            worker.segments.clone(),
+            self.fps,
+            self.resolution_base,
+            self.is_upgraded,
+            self.frame_rate_conversion,
+            frames,
+        )
+        .map(|r| r.map_err(ExportError::from));
+
+        tokio::try_join!(encoder_thread, render_task, forward_task)?;
+
+        Ok(())
+    }
+}
+
+// Starts the directory over unless its chunks were rendered for `manifest`
+fn prepare_chunks_dir(dir: &Path, manifest: &ChunkManifest) -> Result<(), ExportError> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+
+    let existing = std::fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<ChunkManifest>(&s).ok());
+    if existing.as_ref() == Some(manifest) {
+        return Ok(());
+    }
+
+    if dir.exists() {
+        tracing::info!("Discarding chunks from a different export");
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)?;
+
+    std::fs::write(
+        manifest_path,
+        serde_json::to_string(manifest).map_err(|e| ExportError::Other(e.to_string()))?,
+    )?;
+
+    Ok(())
+}
+
+// Joins the chunks end to end without encoding them again. Chunk N starts
+// `N * chunk_frames` frames in, which places its packets on the output's timeline.
+// Chapters are added to MP4 files, like exports made in one go.
+fn concat_chunks(
+    chunks: &[PathBuf],
+    output_path: &Path,
+    chapters: &[Chapter],
+    chunk_frames: u32,
+    fps: u32,
+) -> Result<(), MediaError> {
+    let mut output = ffmpeg::format::output(&output_path)?;
+
+    {
+        let first = ffmpeg::format::input(&chunks[0])?;
+        for stream in first.streams() {
+            let mut output_stream =
+                output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+            output_stream.set_parameters(stream.parameters());
+            // let the muxer pick the tag for its own container
+            unsafe {
+                (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+            }
+        }
+    }
+
+    if output_path.extension().is_some_and(|e| e == "mp4") {
+        add_chapters(&mut output, chapters)?;
+    }
+
+    output.write_header()?;
+
+    let stream_count = output.streams().count();
+    let frame_time_base = ffmpeg::Rational::new(1, fps as i32);
+    let mut last_dts = vec![i64::MIN; stream_count];
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let mut input = ffmpeg::format::input(chunk)?;
+        let start = i as i64 * chunk_frames as i64;
+
+        for (stream, mut packet) in input.packets() {
+            let index = stream.index();
+            if index >= stream_count {
+                continue;
+            }
+
+            let time_base = output.stream(index).unwrap().time_base();
+            let offset = start.rescale(frame_time_base, time_base);
+
+            packet.rescale_ts(stream.time_base(), time_base);
+            packet.set_pts(packet.pts().map(|pts| pts + offset));
+            packet.set_dts(packet.dts().map(|dts| dts + offset));
+
+            // the audio encoder's padding can run a packet into the next chunk's first
+            if let Some(dts) = packet.dts() {
+                if dts <= last_dts[index] {
+                    continue;
+                }
+                last_dts[index] = dts;
+            }
+
+            packet.set_position(-1);
+            packet.set_stream(index);
+            packet.write_interleaved(&mut output)?;
+        }
+    }
+
+    output.write_trailer()?;
+
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use cap_media::encoders::{H264Encoder, HevcEncoder, OpusEncoder, PcmEncoder, RateControl};
+use cap_project::{ZoomSegment, ZoomSegments};
+use cap_rendering::{duration_in, duration_out, ProjectRecordings, ProjectUniforms, ZoomDebugInfo};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{estimate_export_size, ExportCodec, ExportProgress, Exporter};
+
+// What an export would render and encode, worked out without decoding or encoding any frames
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlan {
+    pub output_path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub frame_count: u32,
+    pub duration: f64,
+    pub timeline: Vec<PlannedSegment>,
+    pub layers: Vec<PlannedLayer>,
+    pub zoom_keypoints: Vec<ZoomKeypoint>,
+    // the preferred encoder, which for HEVC may fall back to another when exporting
+    pub video_encoder: &'static str,
+    pub audio_encoder: Option<&'static str>,
+    // see `estimate_export_size`
+    pub estimated_size_bytes: u64,
+}
+
+// Where a piece of a recording ends up in the output, in seconds
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedSegment {
+    pub recording_segment: u32,
+    pub display: u32,
+    pub timescale: f64,
+    pub source_start: f64,
+    pub source_end: f64,
+    pub output_start: f64,
+    pub output_end: f64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Layer {
+    Background,
+    Display,
+    Camera,
+    Cursor,
+    Zoom,
+    BurnIn,
+    Audio,
+}
+
+// The output intervals a layer is drawn or heard in, in seconds
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedLayer {
+    pub layer: Layer,
+    pub intervals: Vec<(f64, f64)>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ZoomKeypointKind {
+    ZoomInStart,
+    ZoomedIn,
+    ZoomOutStart,
+    ZoomedOut,
+}
+
+// The renderer's zoom state at a zoom segment's transitions
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomKeypoint {
+    pub segment_index: usize,
+    pub kind: ZoomKeypointKind,
+    pub frame_number: u32,
+    pub zoom: ZoomDebugInfo,
+}
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// Resolves everything `export_with_custom_muxer` would without rendering, for working out
+    /// why an export looks the way it does.
+    pub fn dry_run(&self) -> ExportPlan {
+        let duration = cap_rendering::get_duration(
+            &ProjectRecordings::new(&self.meta),
+            &self.meta,
+            &self.project,
+        );
+        let frame_count = (self.fps as f64 * duration).ceil() as u32;
+
+        let timeline = self.planned_timeline(duration);
+        let has_audio = self
+            .audio_segments
+            .first()
+            .is_some_and(|a| a.is_some() && !self.project.audio.mute);
+
+        let full = vec![(0.0, duration)];
+        let mut layers = vec![
+            PlannedLayer {
+                layer: Layer::Background,
+                intervals: full.clone(),
+            },
+            PlannedLayer {
+                layer: Layer::Display,
+                intervals: full.clone(),
+            },
+        ];
+
+        if self.render_constants.options.camera_size.is_some() && !self.project.camera.hide {
+            layers.push(PlannedLayer {
+                layer: Layer::Camera,
+                intervals: full.clone(),
+            });
+        }
+
+        let cursor_intervals = timeline
+            .iter()
+            .filter(|s| {
+                self.render_segments
+                    .get(s.recording_segment as usize)
+                    .is_some_and(|r| !r.cursors.get(s.display).moves.is_empty())
+            })
+            .map(|s| (s.output_start, s.output_end))
+            .collect::<Vec<_>>();
+        if !cursor_intervals.is_empty() {
+            layers.push(PlannedLayer {
+                layer: Layer::Cursor,
+                intervals: cursor_intervals,
+            });
+        }
+
+        let zoom_segments = self.zoom_segments();
+        if !zoom_segments.is_empty() {
+            layers.push(PlannedLayer {
+                layer: Layer::Zoom,
+                intervals: zoom_segments
+                    .iter()
+                    .map(|s| (s.start, (s.end + duration_out(s)).min(duration)))
+                    .collect(),
+            });
+        }
+
+        if self.draft {
+            layers.push(PlannedLayer {
+                layer: Layer::BurnIn,
+                intervals: full.clone(),
+            });
+        }
+
+        if has_audio {
+            layers.push(PlannedLayer {
+                layer: Layer::Audio,
+                intervals: full,
+            });
+        }
+
+        let zoom_keypoints = zoom_segments
+            .iter()
+            .enumerate()
+            .flat_map(|(segment_index, s)| {
+                [
+                    (ZoomKeypointKind::ZoomInStart, s.start),
+                    (
+                        ZoomKeypointKind::ZoomedIn,
+                        (s.start + duration_in(s)).min(s.end),
+                    ),
+                    (ZoomKeypointKind::ZoomOutStart, s.end),
+                    (ZoomKeypointKind::ZoomedOut, s.end + duration_out(s)),
+                ]
+                .map(|(kind, time)| (segment_index, kind, time))
+            })
+            .filter_map(|(segment_index, kind, time)| {
+                let frame_number = ((time * self.fps as f64).round() as u32).min(frame_count);
+                Some(ZoomKeypoint {
+                    segment_index,
+                    kind,
+                    frame_number,
+                    zoom: self.zoom_at(frame_number)?,
+                })
+            })
+            .collect();
+
+        let mastering = self.settings.codec.mastering_profile();
+        let audio_encoder = match mastering {
+            Some(_) => PcmEncoder::ENCODER_NAME,
+            None => OpusEncoder::ENCODER_NAME,
+        };
+
+        let two_pass = self.settings.target_size_kib.is_some();
+        // the first encoder that could be used, since others are skipped for two-pass exports
+        let preferred = |names: &'static [&'static str]| {
+            names
+                .iter()
+                .copied()
+                .find(|name| !two_pass || RateControl::supports_two_pass(name))
+                .unwrap_or(names[0])
+        };
+
+        let video_encoder = match (self.settings.codec, mastering) {
+            (_, Some(profile)) => profile.encoder_names()[0],
+            (ExportCodec::Hevc, None) => preferred(HevcEncoder::encoder_names()),
+            (ExportCodec::Vp9 | ExportCodec::Av1, None) => {
+                preferred(self.settings.codec.webm_codec().unwrap().encoder_names())
+            }
+            (_, None) => H264Encoder::encoder_name(),
+        };
+
+        ExportPlan {
+            output_path: self.output_path.clone(),
+            width: self.output_size.0,
+            height: self.output_size.1,
+            fps: self.fps,
+            frame_count,
+            duration,
+            timeline,
+            layers,
+            zoom_keypoints,
+            video_encoder,
+            audio_encoder: has_audio.then_some(audio_encoder),
+            estimated_size_bytes: estimate_export_size(
+                &self.settings,
+                self.output_size,
+                self.fps,
+                duration,
+                has_audio,
+            ),
+        }
+    }
+
+    // Without a timeline every frame plays the first recording segment as is
+    fn planned_timeline(&self, duration: f64) -> Vec<PlannedSegment> {
+        let Some(timeline) = &self.project.timeline else {
+            return vec![PlannedSegment {
+                recording_segment: 0,
+                display: 0,
+                timescale: 1.0,
+                source_start: 0.0,
+                source_end: duration,
+                output_start: 0.0,
+                output_end: duration,
+            }];
+        };
+
+        let mut output_start = 0.0;
+
+        timeline
+            .segments
+            .iter()
+            .map(|s| {
+                let output_end = output_start + s.duration();
+                let segment = PlannedSegment {
+                    recording_segment: s.recording_segment,
+                    display: s.display,
+                    timescale: s.timescale,
+                    source_start: s.start,
+                    source_end: s.end,
+                    output_start,
+                    output_end,
+                };
+                output_start = output_end;
+                segment
+            })
+            .collect()
+    }
+
+    // The zoom segments as the renderer will see them
+    fn zoom_segments(&self) -> Vec<ZoomSegment> {
+        let Some(timeline) = &self.project.timeline else {
+            return vec![];
+        };
+
+        let mut segments = timeline.zoom_segments.clone();
+        segments.normalize(timeline.duration());
+        segments
+    }
+
+    fn zoom_at(&self, frame_number: u32) -> Option<ZoomDebugInfo> {
+        let time = frame_number as f64 / self.fps as f64;
+        let (_, segment_i) = self.project.get_segment_time(time)?;
+        let segment = self.render_segments.get(segment_i as usize)?;
+
+        let uniforms = ProjectUniforms::new(
+            &self.render_constants,
+            &self.project,
+            frame_number,
+            self.fps,
+            self.resolution_base,
+            self.is_upgraded,
+            segment.cursors.get(self.project.get_segment_display(time)),
+        );
+
+        Some(uniforms.zoom_debug)
+    }
+}
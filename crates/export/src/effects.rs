@@ -0,0 +1,354 @@
+// Effects run over each exported frame once it's been rendered and cropped, so features can be
+// added to exports without changing the render pipeline. An effect either changes the frame in
+// place or draws an overlay that's blended over it, and can ask for the cursor and keyboard
+// events around the frame.
+//
+// Effects are described by an `effect.json` manifest next to their module. Effects built into
+// the app are added to the exporter directly, and anyone else's are loaded as WebAssembly
+// modules, whose interface is described in `wasm_effect.rs`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use cap_project::{CursorClickEvent, CursorMoveEvent, KeyPressEvent, ProjectConfiguration};
+use cap_rendering::{RenderedFrame, SegmentCursors};
+use serde::{Deserialize, Serialize};
+
+use crate::wasm_effect::WasmEffect;
+
+pub const MANIFEST_FILE: &str = "effect.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub kind: EffectKind,
+    pub runtime: EffectRuntime,
+    // the module's file, relative to the manifest. Built-in effects don't have one.
+    #[serde(default)]
+    pub module: Option<PathBuf>,
+    #[serde(default)]
+    pub inputs: EffectInputs,
+}
+
+impl EffectManifest {
+    pub fn load(dir: &Path) -> Result<Self, EffectError> {
+        let path = dir.join(MANIFEST_FILE);
+        let file = std::fs::File::open(&path)
+            .map_err(|e| EffectError::Manifest(format!("{}: {e}", path.display())))?;
+
+        serde_json::from_reader(file)
+            .map_err(|e| EffectError::Manifest(format!("{}: {e}", path.display())))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EffectKind {
+    // changes the frame's pixels in place
+    Transform,
+    // draws into a transparent frame, which is blended over the export's
+    Overlay,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EffectRuntime {
+    // compiled into the app
+    Native,
+    Wasm,
+}
+
+// The recorded events an effect is given. Key presses include plain typing, so effects only
+// see them when they ask.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectInputs {
+    #[serde(default)]
+    pub cursor: bool,
+    #[serde(default)]
+    pub keyboard: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EffectError {
+    #[error("Invalid effect manifest {0}")]
+    Manifest(String),
+    #[error("Effect '{0}' needs a runtime this build doesn't have")]
+    UnsupportedRuntime(String),
+    #[error("Couldn't load effect '{id}': {message}")]
+    Module { id: String, message: String },
+    #[error("Effect '{id}' failed: {message}")]
+    Failed { id: String, message: String },
+}
+
+// What an effect knows about the frame it's drawing on
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectContext<'a> {
+    pub frame_number: u32,
+    pub fps: u32,
+    // how far into its recording segment the frame is, in milliseconds like the events' times.
+    // None over title cards.
+    pub recording_time_ms: Option<f64>,
+    // the events of the segment the frame is from, empty unless asked for. Positions are
+    // normalized to the recorded display, before it was cropped or zoomed.
+    pub cursor_moves: &'a [CursorMoveEvent],
+    pub clicks: &'a [CursorClickEvent],
+    pub key_presses: &'a [KeyPressEvent],
+}
+
+pub trait EffectPlugin: Send + Sync {
+    fn manifest(&self) -> &EffectManifest;
+
+    // `frame` is RGBA, with rows `padded_bytes_per_row` apart. Overlay effects are handed a
+    // transparent frame the same size as the export's.
+    fn process(&self, frame: &mut RenderedFrame, context: &EffectContext) -> Result<(), String>;
+}
+
+// Loads the effect described by the manifest in `dir`
+pub fn load_effect(dir: &Path) -> Result<Arc<dyn EffectPlugin>, EffectError> {
+    let manifest = EffectManifest::load(dir)?;
+
+    match (manifest.runtime, manifest.module.clone()) {
+        (EffectRuntime::Wasm, Some(module)) => {
+            Ok(Arc::new(WasmEffect::load(manifest, &dir.join(module))?))
+        }
+        (EffectRuntime::Wasm, None) => Err(EffectError::Manifest(format!(
+            "{}: WebAssembly effects need a module",
+            dir.join(MANIFEST_FILE).display()
+        ))),
+        // native effects are compiled in, and added with `Exporter::with_effect`
+        (EffectRuntime::Native, _) => Err(EffectError::UnsupportedRuntime(manifest.id)),
+    }
+}
+
+// Runs effects over an export's frames, in the order they were added
+pub struct EffectPass {
+    effects: Vec<Arc<dyn EffectPlugin>>,
+    project: ProjectConfiguration,
+    cursors: Vec<SegmentCursors>,
+    fps: u32,
+}
+
+impl EffectPass {
+    pub fn new(
+        effects: Vec<Arc<dyn EffectPlugin>>,
+        project: &ProjectConfiguration,
+        cursors: Vec<SegmentCursors>,
+        fps: u32,
+    ) -> Self {
+        Self {
+            effects,
+            project: project.clone(),
+            cursors,
+            fps,
+        }
+    }
+
+    pub fn apply(&self, frame: &mut RenderedFrame, frame_number: u32) -> Result<(), EffectError> {
+        if self.effects.is_empty() {
+            return Ok(());
+        }
+
+        // the timeline starts after the intro, and cards have no recording under them
+        let time = frame_number
+            .checked_sub(self.project.intro_frames(self.fps))
+            .map(|frame| frame as f64 / self.fps as f64);
+        let segment = time.and_then(|time| {
+            let (segment_time, segment_i) = self.project.get_segment_time(time)?;
+            let events = self
+                .cursors
+                .get(segment_i as usize)?
+                .get(self.project.get_segment_display(time));
+
+            Some((segment_time * 1000.0, events))
+        });
+
+        for effect in &self.effects {
+            let manifest = effect.manifest();
+            let events = segment.as_ref().map(|(_, events)| events);
+
+            let context = EffectContext {
+                frame_number,
+                fps: self.fps,
+                recording_time_ms: segment.as_ref().map(|(time, _)| *time),
+                cursor_moves: events
+                    .filter(|_| manifest.inputs.cursor)
+                    .map_or(&[][..], |e| e.moves.as_slice()),
+                clicks: events
+                    .filter(|_| manifest.inputs.cursor)
+                    .map_or(&[][..], |e| e.clicks.as_slice()),
+                key_presses: events
+                    .filter(|_| manifest.inputs.keyboard)
+                    .map_or(&[][..], |e| e.keyboard.presses.as_slice()),
+            };
+
+            let failed = |message| EffectError::Failed {
+                id: manifest.id.clone(),
+                message,
+            };
+
+            match manifest.kind {
+                EffectKind::Transform => effect.process(frame, &context).map_err(failed)?,
+                EffectKind::Overlay => {
+                    let mut overlay = RenderedFrame {
+                        data: vec![0; frame.data.len()],
+                        width: frame.width,
+                        height: frame.height,
+                        padded_bytes_per_row: frame.padded_bytes_per_row,
+                    };
+                    effect.process(&mut overlay, &context).map_err(failed)?;
+                    blend_over(frame, &overlay);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn blend_over(frame: &mut RenderedFrame, overlay: &RenderedFrame) {
+    let stride = frame.padded_bytes_per_row as usize;
+    let width = frame.width as usize;
+
+    for (row, overlay_row) in frame
+        .data
+        .chunks_mut(stride)
+        .zip(overlay.data.chunks(stride))
+    {
+        let pixels = row.chunks_exact_mut(4).take(width);
+        for (pixel, over) in pixels.zip(overlay_row.chunks_exact(4)) {
+            let alpha = over[3] as u16;
+            if alpha == 0 {
+                continue;
+            }
+            for (c, target) in pixel[..3].iter_mut().zip(over) {
+                *c = ((*c as u16 * (255 - alpha) + *target as u16 * alpha) / 255) as u8;
+            }
+            pixel[3] = pixel[3].max(over[3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A module that paints the top-left pixel opaque red, then returns `result`
+    fn fixture(kind: &str, result: i32) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            format!(
+                r#"{{
+                    "id": "test.red-pixel",
+                    "name": "Red Pixel",
+                    "version": "1.0.0",
+                    "kind": "{kind}",
+                    "runtime": "wasm",
+                    "module": "effect.wat"
+                }}"#
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("effect.wat"),
+            format!(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (global $next (mut i32) (i32.const 1024))
+                    (func (export "cap_alloc") (param $len i32) (result i32)
+                        (global.get $next)
+                        (global.set $next (i32.add (global.get $next) (local.get $len))))
+                    (func (export "cap_process")
+                        (param $frame i32) (param $width i32) (param $height i32)
+                        (param $stride i32) (param $context i32) (param $context_len i32)
+                        (result i32)
+                        (i32.store (local.get $frame) (i32.const 0xff0000ff))
+                        (i32.const {result})))"#
+            ),
+        )
+        .unwrap();
+
+        dir
+    }
+
+    fn frame() -> RenderedFrame {
+        RenderedFrame {
+            data: [0, 0, 255, 255].repeat(4),
+            width: 2,
+            height: 2,
+            padded_bytes_per_row: 8,
+        }
+    }
+
+    fn pass(dir: &Path) -> EffectPass {
+        EffectPass::new(
+            vec![load_effect(dir).unwrap()],
+            &ProjectConfiguration::default(),
+            vec![],
+            30,
+        )
+    }
+
+    #[test]
+    fn wasm_transform() {
+        let dir = fixture("transform", 0);
+        let pass = pass(dir.path());
+        let mut frame = frame();
+
+        pass.apply(&mut frame, 0).unwrap();
+        // runs again in the buffers it was given the first time
+        pass.apply(&mut frame, 1).unwrap();
+
+        assert_eq!(&frame.data[..4], &[255, 0, 0, 255]);
+        assert_eq!(&frame.data[4..], &[0, 0, 255, 255].repeat(3)[..]);
+    }
+
+    #[test]
+    fn wasm_overlay() {
+        let dir = fixture("overlay", 0);
+        let mut frame = frame();
+
+        pass(dir.path()).apply(&mut frame, 0).unwrap();
+
+        // the rest of the overlay is transparent, leaving the frame under it
+        assert_eq!(&frame.data[..4], &[255, 0, 0, 255]);
+        assert_eq!(&frame.data[4..], &[0, 0, 255, 255].repeat(3)[..]);
+    }
+
+    #[test]
+    fn wasm_failure() {
+        let dir = fixture("transform", 3);
+
+        let result = pass(dir.path()).apply(&mut frame(), 0);
+
+        assert!(matches!(
+            result,
+            Err(EffectError::Failed { id, message })
+                if id == "test.red-pixel" && message == "cap_process returned 3"
+        ));
+    }
+
+    #[test]
+    fn native_manifest() {
+        let dir = fixture("transform", 0);
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            r#"{ "id": "test.native", "name": "Native", "version": "1.0.0",
+                "kind": "transform", "runtime": "native" }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            load_effect(dir.path()),
+            Err(EffectError::UnsupportedRuntime(id)) if id == "test.native"
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+use cap_media::encoders::{MasteringProfile, OpusEncoder};
+
+use crate::{ExportCodec, ExportSettings};
+
+// 48kHz stereo at 24 bits
+const PCM_BIT_RATE: usize = 48_000 * 2 * 24;
+
+/// Roughly how many bytes an export with these settings would take, for showing before
+/// exporting. Exports at a bit rate or target size land close to it, while constant quality
+/// and mastering exports depend on the content and are only a guess from their pixel rate.
+pub fn estimate_export_size(
+    settings: &ExportSettings,
+    output_size: (u32, u32),
+    fps: u32,
+    duration: f64,
+    has_audio: bool,
+) -> u64 {
+    let mastering = settings.codec.mastering_profile();
+
+    if let (Some(target_size), None) = (settings.target_size_bytes(), mastering) {
+        return target_size;
+    }
+
+    let pixel_rate = output_size.0 as f64 * output_size.1 as f64 * fps as f64;
+
+    let video_bit_rate = match mastering {
+        Some(profile) => pixel_rate * mastering_bits_per_pixel(profile),
+        None => match (settings.bit_rate, settings.quality) {
+            (Some(bit_rate), _) => bit_rate as f64,
+            (None, Some(quality)) => pixel_rate * quality_bits_per_pixel(settings.codec, quality),
+            (None, None) => settings.default_bit_rate().unwrap_or_default() as f64,
+        },
+    };
+
+    let audio_bit_rate = match (has_audio, mastering) {
+        (false, _) => 0,
+        (true, Some(_)) => PCM_BIT_RATE,
+        (true, None) => OpusEncoder::OUTPUT_BITRATE,
+    };
+
+    ((video_bit_rate + audio_bit_rate as f64) * duration / 8.0) as u64
+}
+
+// Rough bits per pixel, since these codecs don't target a bit rate
+fn mastering_bits_per_pixel(profile: MasteringProfile) -> f64 {
+    match profile {
+        MasteringProfile::ProRes422 => 3.5,
+        MasteringProfile::ProRes4444 => 5.3,
+        MasteringProfile::Ffv1 => 8.0,
+        MasteringProfile::UtVideo => 12.0,
+    }
+}
+
+// Typical of screen recordings, which are mostly still and compress well
+fn quality_bits_per_pixel(codec: ExportCodec, quality: u8) -> f64 {
+    let quality = quality.min(100) as f64 / 100.0;
+    let h264 = 0.02 + 0.13 * quality * quality;
+
+    // the newer codecs look the same with fewer bits
+    match codec {
+        ExportCodec::Hevc | ExportCodec::Vp9 => h264 * 0.65,
+        ExportCodec::Av1 => h264 * 0.5,
+        _ => h264,
+    }
+}
@@ -1,18 +1,60 @@
+mod animated;
+mod burn_in;
+mod chapters;
+mod chunked;
+mod dry_run;
+mod effects;
+mod estimate;
+mod progress;
+mod render;
+mod stills;
+mod stream;
+mod two_pass;
+mod wasm_effect;
+
+pub use animated::{AnimatedExportSettings, AnimatedFormat};
+use burn_in::DraftBurnIn;
 use cap_editor::Segment;
+pub use cap_media::encoders::GifDither;
 use cap_media::{
-    data::{cast_f32_slice_to_bytes, AudioInfo, RawVideoFormat, VideoInfo},
-    encoders::{H264Encoder, MP4File, MP4Input, OpusEncoder},
-    feeds::{AudioData, AudioFrameBuffer},
+    data::{cast_f32_slice_to_bytes, AudioInfo, FFAudio, RawVideoFormat, VideoInfo},
+    encoders::{
+        H264Encoder, HevcEncoder, MP4File, MP4Input, MP4Video, MasteringEncoder, MasteringFile,
+        MasteringProfile, OpusEncoder, Pass, RateControl, WebMCodec, WebMEncoder, WebMFile,
+    },
+    feeds::{AudioFrameBuffer, SegmentAudio},
+    filters::{AudioFilter, LoudnessNormalizer},
     MediaError,
 };
-use cap_project::{ProjectConfiguration, RecordingMeta, XY};
+use cap_project::{
+    AspectRatio, BackgroundSource, Chapter, ProjectConfiguration, RecordingMeta, XY,
+};
 use cap_rendering::{
-    ProjectUniforms, RecordingSegmentDecoders, RenderSegment, RenderVideoConstants, RenderedFrame,
-    SegmentVideoPaths,
+    FrameRateConversion, ProjectRecordings, ProjectUniforms, RecordingSegmentDecoders,
+    RenderOptions, RenderSegment, RenderVideoConstants, RenderedFrame, SegmentVideoPaths,
+};
+pub use dry_run::{
+    ExportPlan, Layer, PlannedLayer, PlannedSegment, ZoomKeypoint, ZoomKeypointKind,
 };
+use effects::EffectPass;
+pub use effects::{
+    load_effect, EffectContext, EffectError, EffectInputs, EffectKind, EffectManifest,
+    EffectPlugin, EffectRuntime,
+};
+pub use estimate::estimate_export_size;
 use futures::FutureExt;
 use image::{ImageBuffer, Rgba};
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use progress::{discard_cancelled, ProgressReporter};
+pub use progress::{ExportCancellation, ExportProgress, ExportStage};
+pub use render::{render_project, RenderPreset};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+pub use stills::StillFormat;
+pub use stream::StreamTarget;
+
+// wgpu's default texture size limit, which the enlarged render for a region export has to fit in
+const MAX_RENDER_DIMENSION: u32 = 8192;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ExportError {
@@ -36,26 +78,457 @@ pub enum ExportError {
 
     #[error("Exporting timed out")]
     Timeout(#[from] tokio::time::error::Elapsed),
+
+    #[error("Export was cancelled")]
+    Cancelled,
+
+    #[error("Lost the connection to the stream: {0}")]
+    StreamDisconnected(String),
+
+    #[error("Effect: {0}")]
+    Effect(#[from] EffectError),
+}
+
+// Describes the files produced by `Exporter::export_split`.
+// Both files share fps, frame count and pts, so frame N of one lines up with frame N of the other.
+#[derive(Type, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitExportManifest {
+    pub fps: u32,
+    pub frame_count: u32,
+    pub duration: f64,
+    pub screen: SplitExportTrack,
+    pub camera: Option<SplitExportTrack>,
+}
+
+#[derive(Type, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitExportTrack {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportCodec {
+    #[default]
+    H264,
+    // hardware encoded where available, for smaller files at the same quality
+    Hevc,
+    // for editing tools, in a .mov
+    ProRes422,
+    ProRes4444,
+    // lossless, in a .mkv
+    Ffv1,
+    UtVideo,
+    // for web pages, in a .webm
+    Vp9,
+    Av1,
+}
+
+impl ExportCodec {
+    pub fn mastering_profile(&self) -> Option<MasteringProfile> {
+        match self {
+            Self::H264 | Self::Hevc | Self::Vp9 | Self::Av1 => None,
+            Self::ProRes422 => Some(MasteringProfile::ProRes422),
+            Self::ProRes4444 => Some(MasteringProfile::ProRes4444),
+            Self::Ffv1 => Some(MasteringProfile::Ffv1),
+            Self::UtVideo => Some(MasteringProfile::UtVideo),
+        }
+    }
+
+    pub fn webm_codec(&self) -> Option<WebMCodec> {
+        match self {
+            Self::Vp9 => Some(WebMCodec::Vp9),
+            Self::Av1 => Some(WebMCodec::Av1),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match (self.mastering_profile(), self.webm_codec()) {
+            (Some(profile), _) => profile.extension(),
+            (None, Some(_)) => "webm",
+            (None, None) => "mp4",
+        }
+    }
+}
+
+// As taken by the CLI
+impl FromStr for ExportCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "h264" => Self::H264,
+            "hevc" | "h265" => Self::Hevc,
+            "prores422" => Self::ProRes422,
+            "prores4444" => Self::ProRes4444,
+            "ffv1" => Self::Ffv1,
+            "utvideo" => Self::UtVideo,
+            "vp9" => Self::Vp9,
+            "av1" => Self::Av1,
+            _ => {
+                return Err(
+                    "expected h264, hevc, prores422, prores4444, ffv1, utvideo, vp9 or av1"
+                        .to_string(),
+                )
+            }
+        })
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSettings {
+    pub codec: ExportCodec,
+    // bits per second, or the codec's default when neither this nor `quality` is set.
+    // The mastering codecs ignore these.
+    pub bit_rate: Option<u32>,
+    // 0 to 100, encoding at a constant quality (CRF or CQ) instead of a bit rate
+    pub quality: Option<u8>,
+    // encodes twice to land close to this many KiB, taking priority over the others
+    #[serde(default)]
+    pub target_size_kib: Option<u32>,
+    // encodes HEVC as 10 bit PQ BT.2020 instead of SDR. Experimental, since frames are still
+    // rendered in SDR and only mapped into HDR10's range.
+    #[serde(default)]
+    pub hdr10: bool,
+    // leaves out frames that repeat the last, which shrinks still recordings but makes a
+    // variable frame rate file that some editors can't handle. Only MP4 and WebM skip them.
+    #[serde(default)]
+    pub skip_static_frames: bool,
+}
+
+// Two-pass exports don't go below this however small the target, since the video would be
+// unwatchable anyway
+const MIN_TWO_PASS_BIT_RATE: usize = 100_000;
+
+impl ExportSettings {
+    // None for the mastering codecs, which don't target a bit rate
+    fn default_bit_rate(&self) -> Option<usize> {
+        match self.codec {
+            ExportCodec::H264 => Some(H264Encoder::BIT_RATE),
+            ExportCodec::Hevc => Some(HevcEncoder::BIT_RATE),
+            _ => self.codec.webm_codec().map(|c| c.default_bit_rate()),
+        }
+    }
+
+    fn target_size_bytes(&self) -> Option<u64> {
+        self.target_size_kib.map(|kib| kib as u64 * 1024)
+    }
+
+    // Only HEVC exports can be HDR10
+    fn is_hdr10(&self) -> bool {
+        self.hdr10 && self.codec == ExportCodec::Hevc
+    }
+
+    // For a single pass
+    fn rate_control(&self) -> RateControl {
+        match (self.bit_rate, self.quality) {
+            (Some(bit_rate), _) => RateControl::BitRate(bit_rate as usize),
+            (None, Some(quality)) => RateControl::Quality(quality),
+            (None, None) => RateControl::BitRate(self.default_bit_rate().unwrap_or_default()),
+        }
+    }
+
+    // The video bit rate that fills `target_size_kib` alongside the audio, when two passes
+    // are wanted
+    fn two_pass_bit_rate(&self, duration: f64, has_audio: bool) -> Option<usize> {
+        let target_size = self.target_size_bytes()?;
+        self.default_bit_rate()?;
+
+        let audio_bit_rate = if has_audio {
+            OpusEncoder::OUTPUT_BITRATE
+        } else {
+            0
+        };
+        // container overhead is small enough to leave out
+        let total_bit_rate = (target_size as f64 * 8.0 / duration.max(0.1)) as usize;
+
+        Some(
+            total_bit_rate
+                .saturating_sub(audio_bit_rate)
+                .max(MIN_TWO_PASS_BIT_RATE),
+        )
+    }
+}
+
+// A sub-rectangle of the composition, in 0..1 of the output frame
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ExportRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ExportRegion {
+    fn clamped(self) -> Self {
+        let x = self.x.clamp(0.0, 1.0);
+        let y = self.y.clamp(0.0, 1.0);
+
+        Self {
+            x,
+            y,
+            width: self.width.clamp(0.01, 1.0 - x.min(0.99)),
+            height: self.height.clamp(0.01, 1.0 - y.min(0.99)),
+        }
+    }
+}
+
+// "x,y,width,height", as taken by the CLI
+impl FromStr for ExportRegion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [x, y, width, height] = values[..] else {
+            return Err("expected x,y,width,height".to_string());
+        };
+
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+// Output size presets, each the box the composition is scaled to fit whatever size it was
+// recorded at. Vertical and square also lay the composition out at their own aspect ratio,
+// rather than fitting the recording's shape inside them.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(try_from = "String")]
+pub enum ExportResolution {
+    P720,
+    #[default]
+    P1080,
+    P4K,
+    Vertical,
+    Square,
+    Custom(XY<u32>),
+}
+
+impl ExportResolution {
+    pub fn resolution_base(&self) -> XY<u32> {
+        match self {
+            Self::P720 => XY::new(1280, 720),
+            Self::P1080 => XY::new(1920, 1080),
+            Self::P4K => XY::new(3840, 2160),
+            Self::Vertical => XY::new(1080, 1920),
+            Self::Square => XY::new(1080, 1080),
+            Self::Custom(size) => *size,
+        }
+    }
+
+    pub fn aspect_ratio(&self) -> Option<AspectRatio> {
+        match self {
+            Self::Vertical => Some(AspectRatio::Vertical),
+            Self::Square => Some(AspectRatio::Square),
+            _ => None,
+        }
+    }
+}
+
+// "720p", "1080p", "4k", "vertical", "square" or "WIDTHxHEIGHT", as taken by the CLI
+impl FromStr for ExportResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "720p" => Self::P720,
+            "1080p" => Self::P1080,
+            "4k" | "2160p" => Self::P4K,
+            "vertical" => Self::Vertical,
+            "square" => Self::Square,
+            custom => {
+                let (width, height) = custom
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+                    .ok_or("expected 720p, 1080p, 4k, vertical, square or WIDTHxHEIGHT")?;
+
+                if !(16..=MAX_RENDER_DIMENSION).contains(&width)
+                    || !(16..=MAX_RENDER_DIMENSION).contains(&height)
+                {
+                    return Err(format!(
+                        "width and height must be between 16 and {MAX_RENDER_DIMENSION}"
+                    ));
+                }
+
+                Self::Custom(XY::new(width, height))
+            }
+        })
+    }
+}
+
+// The same strings, as written in render presets
+impl TryFrom<String> for ExportResolution {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// The project's audio mixed for export, taken a video frame's worth at a time
+struct AudioRender {
+    buffer: AudioFrameBuffer,
+    normalizer: Option<LoudnessNormalizer>,
+}
+
+impl AudioRender {
+    // None when there's no audio to export
+    fn new(
+        audio_segments: &[Arc<Option<SegmentAudio>>],
+        project: &ProjectConfiguration,
+        meta: &RecordingMeta,
+    ) -> Result<Option<Self>, ExportError> {
+        if project.audio.mute
+            || audio_segments
+                .first()
+                .and_then(|d| d.as_ref().as_ref())
+                .is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut buffer = Self::mix(audio_segments);
+
+        // the whole mix has to be heard before its loudness is known, so it's
+        // mixed once up front to measure it
+        let normalizer = match project.audio.loudness_target {
+            Some(target) => {
+                let duration =
+                    cap_rendering::get_duration(&ProjectRecordings::new(meta), meta, project);
+
+                buffer
+                    .measure_loudness(duration, project)?
+                    .map(|loudness| LoudnessNormalizer::new(loudness, target))
+            }
+            None => None,
+        };
+
+        Ok(Some(Self { buffer, normalizer }))
+    }
+
+    // Another render of the same mix, for exporting a different part of the timeline
+    // at the same time without measuring its loudness again
+    fn fork(&self, audio_segments: &[Arc<Option<SegmentAudio>>]) -> Self {
+        Self {
+            buffer: Self::mix(audio_segments),
+            normalizer: self.normalizer,
+        }
+    }
+
+    fn mix(audio_segments: &[Arc<Option<SegmentAudio>>]) -> AudioFrameBuffer {
+        AudioFrameBuffer::new(
+            audio_segments
+                .iter()
+                .map(|s| s.as_ref().as_ref().unwrap().clone())
+                .collect(),
+        )
+    }
+
+    // The audio playing over `frame_number`, continuing from the last frame taken
+    fn next_frame(
+        &mut self,
+        frame_number: u32,
+        fps: u32,
+        project: &ProjectConfiguration,
+    ) -> Option<FFAudio> {
+        let audio_info = self.buffer.info();
+        let estimated_samples_per_frame = f64::from(audio_info.sample_rate) / f64::from(fps);
+        let samples = estimated_samples_per_frame.ceil() as usize;
+
+        let (_, frame_data) = self.buffer.next_frame_data(samples, project)?;
+        let mut frame_data = frame_data.to_vec();
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.process(&mut frame_data);
+        }
+
+        Some(self.wrap(&frame_data, frame_number, fps))
+    }
+
+    // A frame's worth of nothing, for frames with no timeline under them
+    fn silence(&self, frame_number: u32, fps: u32) -> FFAudio {
+        let audio_info = self.buffer.info();
+        let samples = (f64::from(audio_info.sample_rate) / f64::from(fps)).ceil() as usize;
+
+        self.wrap(&vec![0.0; samples * audio_info.channels], frame_number, fps)
+    }
+
+    fn wrap(&self, frame_data: &[f32], frame_number: u32, fps: u32) -> FFAudio {
+        let audio_info = self.buffer.info();
+
+        let mut frame = audio_info.wrap_frame(unsafe { cast_f32_slice_to_bytes(frame_data) }, 0);
+        let pts = (frame_number as f64 * f64::from(audio_info.sample_rate) / f64::from(fps)) as i64;
+        frame.set_pts(Some(pts));
+        frame
+    }
+}
+
+// Pixel rectangle a region export crops each rendered frame to
+#[derive(Debug, Clone, Copy)]
+struct CropRect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl CropRect {
+    // The same region of a render `ratio` times the size, kept inside `bounds`
+    fn scaled(self, ratio: f64, bounds: (u32, u32)) -> Self {
+        let x = ((self.x as f64 * ratio) as u32).min(bounds.0.saturating_sub(2));
+        let y = ((self.y as f64 * ratio) as u32).min(bounds.1.saturating_sub(2));
+
+        Self {
+            x,
+            y,
+            width: ((self.width as f64 * ratio) as u32)
+                .min(bounds.0 - x)
+                .max(2)
+                & !1,
+            height: ((self.height as f64 * ratio) as u32)
+                .min(bounds.1 - y)
+                .max(2)
+                & !1,
+        }
+    }
 }
 
 pub struct Exporter<TOnProgress> {
     render_segments: Vec<RenderSegment>,
-    audio_segments: Vec<Arc<Option<AudioData>>>,
+    audio_segments: Vec<Arc<Option<SegmentAudio>>>,
     output_size: (u32, u32),
     output_path: PathBuf,
     project: ProjectConfiguration,
     project_path: PathBuf,
-    on_progress: TOnProgress,
+    progress: ProgressReporter<TOnProgress>,
+    cancellation: ExportCancellation,
     meta: RecordingMeta,
     render_constants: Arc<RenderVideoConstants>,
     fps: u32,
     resolution_base: XY<u32>,
     is_upgraded: bool,
+    draft: bool,
+    effects: Vec<Arc<dyn EffectPlugin>>,
+    crop: Option<CropRect>,
+    frame_rate_conversion: FrameRateConversion,
+    settings: ExportSettings,
+    workers: usize,
 }
 
 impl<TOnProgress> Exporter<TOnProgress>
 where
-    TOnProgress: Fn(u32) + Send + 'static,
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
 {
     pub async fn new(
         project: ProjectConfiguration,
@@ -72,6 +545,9 @@ where
         let output_folder = output_path.parent().unwrap();
         std::fs::create_dir_all(output_folder)?;
 
+        // everything from here on renders the timeline as it plays with speed changes
+        let project = project.with_speed_applied();
+
         let output_size =
             ProjectUniforms::get_output_size(&render_constants.options, &project, resolution_base);
 
@@ -79,23 +555,9 @@ where
         let mut audio_segments = vec![];
 
         for (i, s) in segments.iter().enumerate() {
-            let segment_paths = match &meta.content {
-                cap_project::Content::SingleSegment { segment: s } => SegmentVideoPaths {
-                    display: meta.path(&s.display.path),
-                    camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
-                },
-                cap_project::Content::MultipleSegments { inner } => {
-                    let s = &inner.segments[i];
-
-                    SegmentVideoPaths {
-                        display: meta.path(&s.display.path),
-                        camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
-                    }
-                }
-            };
             render_segments.push(RenderSegment {
-                cursor: s.cursor.clone(),
-                decoders: RecordingSegmentDecoders::new(&meta, segment_paths)
+                cursors: s.cursors.clone(),
+                decoders: RecordingSegmentDecoders::new(&meta, segment_video_paths(&meta, i))
                     .await
                     .map_err(ExportError::Other)?,
             });
@@ -105,7 +567,8 @@ where
         Ok(Self {
             project,
             output_path,
-            on_progress,
+            progress: ProgressReporter::new(on_progress),
+            cancellation: ExportCancellation::default(),
             project_path,
             meta,
             render_constants,
@@ -115,15 +578,357 @@ where
             fps,
             resolution_base,
             is_upgraded,
+            draft: false,
+            effects: vec![],
+            crop: None,
+            frame_rate_conversion: FrameRateConversion::default(),
+            settings: ExportSettings::default(),
+            workers: 1,
         })
     }
 
-    pub async fn export_with_custom_muxer(self) -> Result<PathBuf, ExportError> {
-        struct AudioRender {
-            buffer: AudioFrameBuffer,
+    /// Burns a timecode and project revision into every frame so review copies
+    /// can't be mistaken for the final export.
+    pub fn with_draft(mut self, draft: bool) -> Self {
+        self.draft = draft;
+        self
+    }
+
+    /// Runs `effect` over every frame, after any added before it and before the draft burn-in.
+    pub fn with_effect(mut self, effect: Arc<dyn EffectPlugin>) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    /// How recorded frames are conformed to the export's constant frame rate.
+    pub fn with_frame_rate_conversion(mut self, conversion: FrameRateConversion) -> Self {
+        self.frame_rate_conversion = conversion;
+        self
+    }
+
+    /// Codec and bit rate the composition is encoded with.
+    pub fn with_settings(mut self, settings: ExportSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Lets the export be stopped with `cancellation.cancel()` while it runs.
+    pub fn with_cancellation(mut self, cancellation: ExportCancellation) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// How many parts of the timeline `export_resumable` renders at once, each decoding and
+    /// rendering on its own and spread over the machine's GPUs.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Lays the composition out at `aspect_ratio` instead of the project's own, padding it with
+    /// the background. Must come before `with_region`.
+    pub fn with_aspect_ratio(mut self, aspect_ratio: AspectRatio) -> Self {
+        self.project.aspect_ratio = Some(aspect_ratio);
+        self.output_size = ProjectUniforms::get_output_size(
+            &self.render_constants.options,
+            &self.project,
+            self.resolution_base,
+        );
+        self
+    }
+
+    /// Exports only `region` of the composition, ignoring zoom segments.
+    /// The composition is rendered enlarged so the region keeps the full output resolution.
+    pub fn with_region(mut self, region: ExportRegion) -> Self {
+        let region = region.clamped();
+        let options = &self.render_constants.options;
+
+        if let Some(timeline) = self.project.timeline.as_mut() {
+            timeline.zoom_segments.clear();
+        }
+
+        let scale = 1.0 / region.width.max(region.height);
+        let mut resolution_base = XY::new(
+            (self.resolution_base.x as f64 * scale) as u32,
+            (self.resolution_base.y as f64 * scale) as u32,
+        );
+
+        let (width, height) =
+            ProjectUniforms::get_output_size(options, &self.project, resolution_base);
+        let oversize = width.max(height) as f64 / MAX_RENDER_DIMENSION as f64;
+        if oversize > 1.0 {
+            resolution_base = XY::new(
+                (resolution_base.x as f64 / oversize) as u32,
+                (resolution_base.y as f64 / oversize) as u32,
+            );
         }
 
-        println!("Exporting with custom muxer");
+        let (width, height) =
+            ProjectUniforms::get_output_size(options, &self.project, resolution_base);
+
+        let x = (region.x * width as f64) as u32;
+        let y = (region.y * height as f64) as u32;
+        // encoders need even dimensions
+        let crop = CropRect {
+            x,
+            y,
+            width: ((region.width * width as f64) as u32).min(width - x) & !1,
+            height: ((region.height * height as f64) as u32).min(height - y) & !1,
+        };
+
+        self.resolution_base = resolution_base;
+        self.output_size = (crop.width, crop.height);
+        self.crop = Some(crop);
+        self
+    }
+
+    // Frames in the export at `fps`, with its title cards
+    // The effects run over each frame once it's rendered, the draft burn-in last so nothing
+    // covers it
+    fn effect_pass(&self, fps: u32) -> EffectPass {
+        let mut effects = self.effects.clone();
+        if self.draft {
+            effects.push(Arc::new(DraftBurnIn::new(&self.project, fps)));
+        }
+
+        EffectPass::new(
+            effects,
+            &self.project,
+            self.render_segments
+                .iter()
+                .map(|s| s.cursors.clone())
+                .collect(),
+            fps,
+        )
+    }
+
+    fn frame_count(&self, fps: u32) -> u32 {
+        cap_rendering::get_frame_count(
+            &ProjectRecordings::new(&self.meta),
+            &self.meta,
+            &self.project,
+            fps,
+        )
+    }
+
+    // The format the timeline's audio is encoded in, or None when there's none to export
+    fn audio_info(&self) -> Result<Option<AudioInfo>, ExportError> {
+        let Some(audio_data) = self
+            .audio_segments
+            .first()
+            .and_then(|d| d.as_ref().as_ref())
+            .filter(|_| !self.project.audio.mute)
+        else {
+            return Ok(None);
+        };
+
+        let info = audio_data.info();
+        AudioInfo::new(info.sample_format, info.sample_rate, info.channels as u16)
+            .map(Some)
+            .map_err(|e| MediaError::from(e).into())
+    }
+
+    // Exports can afford the sharper scaling that's too slow for playback
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            high_quality_scaling: true,
+            ..self.render_constants.options
+        }
+    }
+
+    // Stills and animated exports are always SDR
+    fn video_render_options(&self) -> RenderOptions {
+        RenderOptions {
+            hdr10: self.settings.is_hdr10(),
+            ..self.render_options()
+        }
+    }
+
+    /// Exports the screen composition (without the camera bubble) and the raw camera feed
+    /// as two synchronized files next to the output path, plus a `-sync.json` manifest.
+    pub async fn export_split(mut self) -> Result<SplitExportManifest, ExportError> {
+        let stem = self
+            .output_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "result".to_string());
+        let with_suffix =
+            |suffix: &str| self.output_path.with_file_name(format!("{stem}-{suffix}"));
+
+        let screen_path = with_suffix("screen.mp4");
+        let camera_path = with_suffix("camera.mp4");
+        let manifest_path = with_suffix("sync.json");
+
+        // the camera track has no cards to line up with
+        self.project.intro = None;
+        self.project.outro = None;
+
+        let duration = cap_rendering::get_duration(
+            &ProjectRecordings::new(&self.meta),
+            &self.meta,
+            &self.project,
+        );
+        // as many frames as the screen render makes
+        let frame_count = self.frame_count(self.fps);
+
+        let camera = match self.render_constants.options.camera_size {
+            Some(camera_size) => {
+                self.export_camera(camera_path.clone(), camera_size, frame_count)
+                    .await?;
+
+                Some(SplitExportTrack {
+                    path: camera_path,
+                    width: camera_size.x,
+                    height: camera_size.y,
+                })
+            }
+            None => None,
+        };
+
+        self.project.camera.hide = true;
+        self.output_path = screen_path;
+
+        let fps = self.fps;
+        let (width, height) = self.output_size;
+        // the extension follows the codec
+        let screen_path = self.export_with_custom_muxer().await?;
+
+        let manifest = SplitExportManifest {
+            fps,
+            frame_count,
+            duration,
+            screen: SplitExportTrack {
+                path: screen_path,
+                width,
+                height,
+            },
+            camera,
+        };
+
+        std::fs::write(
+            &manifest_path,
+            serde_json::to_string_pretty(&manifest)
+                .map_err(|e| ExportError::Other(e.to_string()))?,
+        )?;
+
+        Ok(manifest)
+    }
+
+    // Walks the same timeline as the screen render so every frame number gets exactly one
+    // camera frame, repeating the last decoded frame where the camera has none. The timeline's
+    // audio goes alongside, so the files can also be lined up by their sound.
+    async fn export_camera(
+        &self,
+        output_path: PathBuf,
+        camera_size: XY<u32>,
+        frame_count: u32,
+    ) -> Result<(), ExportError> {
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<MP4Input>(4);
+
+        let fps = self.fps;
+        let mut info = VideoInfo::from_raw(RawVideoFormat::Rgba, camera_size.x, camera_size.y, fps);
+        info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+        let audio_info = self.audio_info()?;
+        let mut audio = AudioRender::new(&self.audio_segments, &self.project, &self.meta)?;
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let output_path = output_path.clone();
+
+            move || {
+                let mut encoder = MP4File::init(
+                    "camera",
+                    output_path,
+                    H264Encoder::factory("camera_video", info),
+                    move |o| audio_info.map(|a| OpusEncoder::init("camera_audio", a, o)),
+                )?;
+
+                while let Ok(frame) = frame_rx.recv() {
+                    encoder.queue_video_frame(frame.video);
+                    if let Some(audio) = frame.audio {
+                        encoder.queue_audio_frame(audio);
+                    }
+                }
+
+                encoder.finish();
+
+                Ok::<_, ExportError>(())
+            }
+        });
+
+        let stride = camera_size.x as usize * 4;
+        let black_frame = vec![0; stride * camera_size.y as usize];
+        let mut last_frame: Option<cap_rendering::DecodedFrame> = None;
+
+        for frame_number in 0..frame_count {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+
+            // the last frame can round past the end of the timeline, and is padded with the
+            // one before so the file has as many frames as the screen's
+            let camera_frame = match self
+                .project
+                .get_segment_time(frame_number as f64 / fps as f64)
+                .and_then(|(time, i)| Some((time, self.render_segments.get(i as usize)?)))
+            {
+                Some((segment_time, segment)) => segment
+                    .decoders
+                    .get_frames(segment_time as f32, true, 0)
+                    .await
+                    .and_then(|f| f.camera_frame),
+                None => None,
+            };
+
+            if let Some(camera_frame) = camera_frame {
+                last_frame = Some(camera_frame);
+            }
+
+            let data = match &last_frame {
+                Some(frame) => frame.as_slice(),
+                None => &black_frame,
+            };
+
+            let mut video_frame = info.wrap_frame(data, 0, stride);
+            video_frame.set_pts(Some(frame_number as i64));
+
+            let audio_frame = audio.as_mut().and_then(|audio| {
+                if frame_number == 0 {
+                    audio.buffer.set_playhead(0., &self.project);
+                }
+
+                audio.next_frame(frame_number, fps, &self.project)
+            });
+
+            if frame_tx
+                .send(MP4Input {
+                    audio: audio_frame,
+                    video: video_frame,
+                })
+                .is_err()
+            {
+                break;
+            }
+
+            self.progress
+                .report(ExportStage::Rendering, frame_number + 1, frame_count);
+        }
+
+        drop(frame_tx);
+
+        encoder_thread.await??;
+
+        if self.cancellation.is_cancelled() {
+            return Err(discard_cancelled(&output_path));
+        }
+
+        Ok(())
+    }
+
+    pub async fn export_with_custom_muxer(self) -> Result<PathBuf, ExportError> {
+        tracing::debug!("Exporting with custom muxer");
+
+        let cancellation = self.cancellation.clone();
 
         let (tx_image_data, mut rx_image_data) =
             tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
@@ -131,117 +936,134 @@ where
 
         let fps = self.fps;
 
-        let audio_info = match self
-            .audio_segments
-            .get(0)
-            .and_then(|d| d.as_ref().as_ref())
-            .filter(|_| !self.project.audio.mute)
-        {
-            Some(audio_data) => Some(
-                AudioInfo::new(
-                    audio_data.info.sample_format,
-                    audio_data.info.sample_rate,
-                    audio_data.info.channels as u16,
+        let audio_info = self.audio_info()?;
+
+        // alpha only survives when there's no background to cover it
+        let alpha = matches!(
+            self.project.background.source,
+            BackgroundSource::Transparent
+        );
+
+        let total_frames = self.frame_count(self.fps);
+        let duration = total_frames as f64 / self.fps as f64;
+        let two_pass_bit_rate = self
+            .settings
+            .two_pass_bit_rate(duration, audio_info.is_some());
+        // progress runs over both passes
+        let first_pass_frames = match two_pass_bit_rate {
+            Some(_) => total_frames,
+            None => 0,
+        };
+
+        // the directory has to outlive the second pass, which reads the first's statistics
+        let (rate_control, _stats_dir) = match two_pass_bit_rate {
+            Some(bit_rate) => {
+                let stats_dir = tempfile::tempdir()?;
+                let stats = stats_dir.path().join("stats");
+
+                self.first_pass(bit_rate, stats.clone(), alpha).await?;
+
+                (
+                    RateControl::TwoPass {
+                        bit_rate,
+                        pass: Pass::Second,
+                        stats,
+                    },
+                    Some(stats_dir),
                 )
-                .map_err(Into::<MediaError>::into)?,
-            ),
-            _ => None,
+            }
+            None => (self.settings.rate_control(), None),
         };
 
-        let encoder_thread = tokio::task::spawn_blocking(move || {
-            let mut info = VideoInfo::from_raw(
-                RawVideoFormat::Rgba,
-                self.output_size.0,
-                self.output_size.1,
-                self.fps,
-            );
-            info.time_base = ffmpeg::Rational::new(1, self.fps as i32);
-
-            let mut encoder = cap_media::encoders::MP4File::init(
-                "output",
-                self.output_path.clone(),
-                H264Encoder::factory("output_video", info),
-                move |o| audio_info.map(|a| OpusEncoder::init("output_audio", a, o)),
-            )
-            .unwrap();
-
-            while let Ok(frame) = frame_rx.recv() {
-                encoder.queue_video_frame(frame.video);
-                if let Some(audio) = frame.audio {
-                    encoder.queue_audio_frame(audio);
+        let chapters = self.chapters();
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let cancellation = cancellation.clone();
+            let chapters = chapters.clone();
+
+            move || {
+                let mut info = VideoInfo::from_raw(
+                    RawVideoFormat::Rgba,
+                    self.output_size.0,
+                    self.output_size.1,
+                    self.fps,
+                );
+                info.time_base = ffmpeg::Rational::new(1, self.fps as i32);
+
+                let output_path = self
+                    .output_path
+                    .with_extension(self.settings.codec.extension());
+
+                let mut encoder = OutputFile::create(
+                    &self.settings,
+                    rate_control,
+                    info,
+                    output_path.clone(),
+                    alpha,
+                    audio_info,
+                    &chapters,
+                )?;
+
+                while let Ok(frame) = frame_rx.recv() {
+                    encoder.queue(frame);
+                }
+
+                if cancellation.is_cancelled() {
+                    // the file has to be closed before it can be removed
+                    drop(encoder);
+                    return Err(discard_cancelled(&output_path));
                 }
-            }
 
-            encoder.finish();
+                encoder.finish();
 
-            Ok::<_, ExportError>(self.output_path)
+                Ok::<_, ExportError>(output_path)
+            }
         })
         .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
 
+        let effects = self.effect_pass(self.fps);
+
         let render_task = tokio::spawn({
             let project = self.project.clone();
             let project_path = self.project_path.clone();
             async move {
                 println!("Starting FFmpeg output process...");
-                let mut audio = if let Some(_) = self
-                    .audio_segments
-                    .get(0)
-                    .and_then(|d| d.as_ref().as_ref())
-                    .filter(|_| !self.project.audio.mute)
-                {
-                    Some(AudioRender {
-                        buffer: AudioFrameBuffer::new(
-                            self.audio_segments
-                                .iter()
-                                .map(|s| s.as_ref().as_ref().unwrap().clone())
-                                .collect(),
-                        ),
-                    })
-                } else {
-                    None
-                };
+                let mut audio = AudioRender::new(&self.audio_segments, &project, &self.meta)?;
 
                 let mut frame_count = 0;
                 let mut first_frame = None;
 
                 loop {
-                    let Some((frame, frame_number)) =
+                    let Some((mut frame, frame_number)) =
                         tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
                     else {
                         break;
                     };
 
-                    (self.on_progress)(frame_count);
+                    self.cancellation.check()?;
+
+                    if let Some(rect) = self.crop {
+                        frame = crop_frame(&frame, rect);
+                    }
 
                     if frame_count == 0 {
                         first_frame = Some(frame.clone());
                     }
 
-                    let audio_frame = if let Some(audio) = &mut audio {
+                    effects.apply(&mut frame, frame_number)?;
+
+                    let audio_frame = audio.as_mut().and_then(|audio| {
                         if frame_count == 0 {
                             audio.buffer.set_playhead(0., &project);
                         }
 
-                        let audio_info = audio.buffer.info();
-                        let estimated_samples_per_frame =
-                            f64::from(audio_info.sample_rate) / f64::from(self.fps);
-                        let samples = estimated_samples_per_frame.ceil() as usize;
-
-                        if let Some((_, frame_data)) =
-                            audio.buffer.next_frame_data(samples, &project)
-                        {
-                            let mut frame = audio_info
-                                .wrap_frame(unsafe { cast_f32_slice_to_bytes(&frame_data) }, 0);
-                            let pts = (frame_number as f64 * f64::from(audio_info.sample_rate)
-                                / f64::from(fps)) as i64;
-                            frame.set_pts(Some(pts));
-                            Some(frame)
-                        } else {
-                            None
+                        // the timeline's audio waits for the intro to finish
+                        if frame_number < project.intro_frames(fps) {
+                            return Some(audio.silence(frame_number, fps));
                         }
-                    } else {
-                        None
-                    };
+
+                        audio.next_frame(frame_number, fps, &project)
+                    });
 
                     let mut video_frame = VideoInfo::from_raw(
                         RawVideoFormat::Rgba,
@@ -264,6 +1086,12 @@ where
                         .ok();
 
                     frame_count += 1;
+
+                    self.progress.report(
+                        ExportStage::Rendering,
+                        first_pass_frames + frame_count,
+                        first_pass_frames + total_frames,
+                    );
                 }
 
                 // Save the first frame as a screenshot and thumbnail
@@ -305,7 +1133,7 @@ where
         println!("Rendering video to channel");
 
         let render_video_task = cap_rendering::render_video_to_channel(
-            self.render_constants.options,
+            self.video_render_options(),
             self.project,
             tx_image_data,
             &self.meta,
@@ -313,11 +1141,163 @@ where
             self.fps,
             self.resolution_base,
             self.is_upgraded,
+            self.frame_rate_conversion,
         )
         .then(|f| async { f.map_err(Into::into) });
 
-        let (output_path, _, _) = tokio::try_join!(encoder_thread, render_video_task, render_task)?;
+        let (output_path, _, _) = cancellation.or_cancelled(tokio::try_join!(
+            encoder_thread,
+            render_video_task,
+            render_task
+        ))?;
+
+        chapters::write_webvtt(&chapters, &output_path)?;
 
         Ok(output_path)
     }
 }
+
+enum OutputFile {
+    MP4(MP4File),
+    Mastering(MasteringFile),
+    WebM(WebMFile),
+}
+
+impl OutputFile {
+    fn create(
+        settings: &ExportSettings,
+        rate_control: RateControl,
+        info: VideoInfo,
+        output_path: PathBuf,
+        alpha: bool,
+        audio_info: Option<AudioInfo>,
+        // only MP4 files take them, the WebVTT file covers the rest
+        chapters: &[Chapter],
+    ) -> Result<Self, MediaError> {
+        Ok(
+            match (
+                settings.codec.mastering_profile(),
+                settings.codec.webm_codec(),
+            ) {
+                (Some(profile), _) => Self::Mastering(MasteringFile::init(
+                    "output",
+                    output_path,
+                    profile,
+                    |o| MasteringEncoder::init("output_video", info, profile, alpha, o),
+                    audio_info,
+                )?),
+                (None, Some(codec)) => Self::WebM(
+                    WebMFile::init(
+                        "output",
+                        output_path,
+                        |o| WebMEncoder::init("output_video", info, codec, rate_control, alpha, o),
+                        move |o| audio_info.map(|a| OpusEncoder::init("output_audio", a, o)),
+                    )?
+                    .with_static_frame_skipping(settings.skip_static_frames),
+                ),
+                (None, None) => Self::MP4(
+                    MP4File::init_with_chapters(
+                        "output",
+                        output_path,
+                        chapters,
+                        |o| match settings.codec {
+                            ExportCodec::Hevc if settings.is_hdr10() => {
+                                HevcEncoder::init_hdr10("output_video", info, rate_control, o)
+                                    .map(MP4Video::from)
+                            }
+                            ExportCodec::Hevc => {
+                                HevcEncoder::init("output_video", info, rate_control, o)
+                                    .map(MP4Video::from)
+                            }
+                            _ => H264Encoder::init_with_rate_control(
+                                "output_video",
+                                info,
+                                rate_control,
+                                o,
+                            )
+                            .map(MP4Video::from),
+                        },
+                        move |o| audio_info.map(|a| OpusEncoder::init("output_audio", a, o)),
+                    )?
+                    .with_static_frame_skipping(settings.skip_static_frames),
+                ),
+            },
+        )
+    }
+
+    fn queue(&mut self, input: MP4Input) {
+        match self {
+            Self::MP4(file) => {
+                file.queue_video_frame(input.video);
+                if let Some(audio) = input.audio {
+                    file.queue_audio_frame(audio);
+                }
+            }
+            Self::Mastering(file) => {
+                file.queue_video_frame(input.video);
+                if let Some(audio) = input.audio {
+                    file.queue_audio_frame(audio);
+                }
+            }
+            Self::WebM(file) => {
+                file.queue_video_frame(input.video);
+                if let Some(audio) = input.audio {
+                    file.queue_audio_frame(audio);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        match self {
+            Self::MP4(file) => file.finish(),
+            Self::Mastering(file) => file.finish(),
+            Self::WebM(file) => file.finish(),
+        }
+    }
+}
+
+fn segment_video_paths(meta: &RecordingMeta, i: usize) -> SegmentVideoPaths {
+    match &meta.content {
+        cap_project::Content::SingleSegment { segment: s } => SegmentVideoPaths {
+            display: meta.path(&s.display.path),
+            additional_displays: vec![],
+            camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
+        },
+        cap_project::Content::MultipleSegments { inner } => {
+            let s = &inner.segments[i];
+
+            SegmentVideoPaths {
+                display: meta.path(&s.display.path),
+                additional_displays: s
+                    .additional_displays
+                    .iter()
+                    .map(|d| meta.path(&d.path))
+                    .collect(),
+                camera: s.camera.as_ref().map(|c| meta.path(&c.path)),
+            }
+        }
+    }
+}
+
+fn crop_frame(frame: &RenderedFrame, rect: CropRect) -> RenderedFrame {
+    let row_bytes = rect.width as usize * 4;
+    let mut data = Vec::with_capacity(row_bytes * rect.height as usize);
+
+    for row in frame
+        .data
+        .chunks(frame.padded_bytes_per_row as usize)
+        .skip(rect.y as usize)
+        .take(rect.height as usize)
+    {
+        let start = rect.x as usize * 4;
+        data.extend_from_slice(&row[start..start + row_bytes]);
+    }
+
+    RenderedFrame {
+        data,
+        width: rect.width,
+        height: rect.height,
+        padded_bytes_per_row: row_bytes as u32,
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::ExportError;
+
+// What an export is busy with
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportStage {
+    // the first of two passes, which only gathers statistics
+    FirstPass,
+    Rendering,
+    // putting chunks rendered separately together
+    Joining,
+}
+
+// Sent to the exporter's progress callback as frames are done. Frames count over the whole
+// export, so a two-pass export covers every frame twice.
+#[derive(Type, Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgress {
+    pub stage: ExportStage,
+    pub frames_done: u32,
+    pub total_frames: u32,
+    // frames per second since the export started
+    pub fps: f64,
+    // seconds left at that rate, once there's a rate to go by
+    pub eta_secs: Option<f64>,
+}
+
+/// Stops an export from elsewhere, such as a cancel button. Clones share the same flag.
+///
+/// A cancelled export fails with `ExportError::Cancelled` and removes the file it was
+/// writing, leaving chunks finished by a resumable export in place for the next attempt.
+#[derive(Clone, Default, Debug)]
+pub struct ExportCancellation(Arc<AtomicBool>);
+
+impl ExportCancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    // Fails with `ExportError::Cancelled` once cancelled, for stopping render loops
+    pub(crate) fn check(&self) -> Result<(), ExportError> {
+        if self.is_cancelled() {
+            return Err(ExportError::Cancelled);
+        }
+
+        Ok(())
+    }
+
+    // The tasks of a cancelled export each fail their own way, which all comes down to the
+    // cancellation
+    pub(crate) fn or_cancelled<T>(&self, result: Result<T, ExportError>) -> Result<T, ExportError> {
+        result.map_err(|e| {
+            if self.is_cancelled() {
+                ExportError::Cancelled
+            } else {
+                e
+            }
+        })
+    }
+}
+
+// Removes the partly written file of a cancelled export, which has to be closed already
+pub(crate) fn discard_cancelled(path: &Path) -> ExportError {
+    std::fs::remove_file(path).ok();
+    ExportError::Cancelled
+}
+
+// Wraps the exporter's progress callback, working out the rate and time left
+pub(crate) struct ProgressReporter<TOnProgress> {
+    on_progress: TOnProgress,
+    rate: Mutex<Option<Rate>>,
+}
+
+// Frames done since the first report. Frames done before it are left out, since they may
+// have been done earlier, like the chunks a resumed export reuses.
+struct Rate {
+    started: Instant,
+    start_frames: u32,
+    last_frames: u32,
+}
+
+impl<TOnProgress> ProgressReporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    pub fn new(on_progress: TOnProgress) -> Self {
+        Self {
+            on_progress,
+            rate: Mutex::new(None),
+        }
+    }
+
+    pub fn report(&self, stage: ExportStage, frames_done: u32, total_frames: u32) {
+        let fps = {
+            let mut rate = self.rate.lock().unwrap();
+            // frames going backwards means the export started over, like an animation
+            // rendered again at a smaller size
+            let rate = match &mut *rate {
+                Some(rate) if frames_done >= rate.last_frames => rate,
+                rate => rate.insert(Rate {
+                    started: Instant::now(),
+                    start_frames: frames_done,
+                    last_frames: frames_done,
+                }),
+            };
+            rate.last_frames = frames_done;
+
+            let elapsed = rate.started.elapsed().as_secs_f64();
+            if elapsed > 0.0 {
+                (frames_done - rate.start_frames) as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        let eta_secs = (fps > 0.0).then(|| total_frames.saturating_sub(frames_done) as f64 / fps);
+
+        (self.on_progress)(ExportProgress {
+            stage,
+            frames_done: frames_done.min(total_frames),
+            total_frames,
+            fps,
+            eta_secs,
+        });
+    }
+}
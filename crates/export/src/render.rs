@@ -0,0 +1,108 @@
+use cap_editor::create_segments;
+use cap_project::{ProjectConfiguration, RecordingMeta, TimelineConfiguration, XY};
+use cap_rendering::{FrameRateConversion, ProjectRecordings, RenderOptions, RenderVideoConstants};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{ExportError, ExportProgress, ExportResolution, ExportSettings, Exporter};
+
+// How `render_project` exports, read from a JSON file like
+// `{ "resolution": "4k", "codec": "hevc", "quality": 80 }`. Anything left out is the default.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RenderPreset {
+    pub resolution: ExportResolution,
+    // the recording's own frame rate when not set
+    pub fps: Option<u32>,
+    #[serde(flatten)]
+    pub settings: ExportSettings,
+    pub draft: bool,
+    pub blend_frames: bool,
+    // render on the CPU even when there's a GPU, since servers often only have a weak one
+    pub software: bool,
+}
+
+impl RenderPreset {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+}
+
+/// Exports the project at `project_path` without an editor, window or display, rendering
+/// offscreen on whatever adapter is available (in software when there's no GPU). Writes to
+/// `output_path` with the codec's extension, or to the project's own output file.
+pub async fn render_project(
+    project_path: &Path,
+    preset: &RenderPreset,
+    output_path: Option<PathBuf>,
+    on_progress: impl Fn(ExportProgress) + Send + 'static,
+) -> Result<PathBuf, ExportError> {
+    let mut project =
+        ProjectConfiguration::load(project_path).map_err(|e| ExportError::Other(e.to_string()))?;
+    let meta = RecordingMeta::load_for_project(&project_path.to_path_buf())
+        .and_then(|meta| meta.with_takes(&project.takes))
+        .map_err(ExportError::Other)?;
+    let recordings = ProjectRecordings::new(&meta);
+
+    project.timeline.get_or_insert_with(|| {
+        TimelineConfiguration::from_segment_durations(&recordings.segment_durations())
+    });
+
+    let first_segment = &recordings.segments[0];
+    let render_constants = RenderVideoConstants::new(
+        RenderOptions {
+            screen_size: XY::new(first_segment.display.width, first_segment.display.height),
+            camera_size: first_segment
+                .camera
+                .as_ref()
+                .map(|c| XY::new(c.width, c.height)),
+            deterministic: project.determinism.enabled || preset.software,
+            // the exporter turns this on for its own renders
+            high_quality_scaling: false,
+            gpu: None,
+            screen_color: first_segment.display.color,
+            // the exporter turns this on for HDR10 exports
+            hdr10: false,
+        },
+        &meta,
+    )
+    .await?;
+
+    let segments = create_segments(&meta, false)
+        .await
+        .map_err(ExportError::Other)?;
+
+    let fps = preset.fps.unwrap_or_else(|| meta.content.max_fps());
+    let output_path = output_path.unwrap_or_else(|| meta.output_path());
+
+    let mut exporter = Exporter::new(
+        project,
+        output_path,
+        on_progress,
+        project_path.to_path_buf(),
+        meta,
+        Arc::new(render_constants),
+        &segments,
+        fps,
+        preset.resolution.resolution_base(),
+        true,
+    )
+    .await?
+    .with_draft(preset.draft)
+    .with_frame_rate_conversion(if preset.blend_frames {
+        FrameRateConversion::Blend
+    } else {
+        FrameRateConversion::Duplicate
+    })
+    .with_settings(preset.settings);
+
+    if let Some(aspect_ratio) = preset.resolution.aspect_ratio() {
+        exporter = exporter.with_aspect_ratio(aspect_ratio);
+    }
+
+    exporter.export_with_custom_muxer().await
+}
@@ -0,0 +1,201 @@
+use cap_rendering::{RenderVideoConstants, RenderedFrame};
+use futures::FutureExt;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{crop_frame, ExportError, ExportProgress, ExportStage, Exporter};
+
+const JPEG_QUALITY: u8 = 90;
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StillFormat {
+    #[default]
+    Png,
+    // smaller, but without transparency
+    Jpeg,
+}
+
+impl StillFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+        }
+    }
+}
+
+// As taken by the CLI
+impl FromStr for StillFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "png" => Self::Png,
+            "jpeg" | "jpg" => Self::Jpeg,
+            _ => return Err("expected png or jpeg".to_string()),
+        })
+    }
+}
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// Renders the frame `time` seconds into the timeline, as it would appear in a video export.
+    pub async fn render_still(&self, time: f64) -> Result<RenderedFrame, ExportError> {
+        // the editor's constants are set up for playback, without the export's scaling
+        let constants = RenderVideoConstants::new(self.render_options(), &self.meta).await?;
+        let mut frame = cap_rendering::render_single_frame(
+            &constants,
+            self.project.clone(),
+            &self.render_segments,
+            time,
+            self.fps,
+            self.resolution_base,
+            self.is_upgraded,
+        )
+        .await?
+        .ok_or_else(|| ExportError::Other(format!("Nothing to render at {time}s")))?;
+
+        if let Some(rect) = self.crop {
+            frame = crop_frame(&frame, rect);
+        }
+
+        let frame_number = (time * self.fps as f64).round() as u32;
+        self.effect_pass(self.fps).apply(&mut frame, frame_number)?;
+
+        Ok(frame)
+    }
+
+    /// Saves the frame `time` seconds into the timeline as an image, for thumbnails and previews.
+    pub async fn export_still(
+        self,
+        time: f64,
+        format: StillFormat,
+    ) -> Result<PathBuf, ExportError> {
+        let frame = self.render_still(time).await?;
+        let output_path = self.output_path.with_extension(format.extension());
+
+        tokio::task::spawn_blocking({
+            let output_path = output_path.clone();
+            move || save_still(&frame, format, &output_path)
+        })
+        .await??;
+
+        Ok(output_path)
+    }
+
+    /// Renders every frame to a numbered image in a directory named after the output file,
+    /// replacing anything already there. Returns the directory.
+    pub async fn export_frames(self, format: StillFormat) -> Result<PathBuf, ExportError> {
+        let output_dir = self.output_path.with_extension("");
+        if output_dir.exists() {
+            std::fs::remove_dir_all(&output_dir)?;
+        }
+        std::fs::create_dir_all(&output_dir)?;
+
+        let (tx_image_data, mut rx_image_data) =
+            tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<(RenderedFrame, u32)>(4);
+
+        let writer_thread = tokio::task::spawn_blocking({
+            let output_dir = output_dir.clone();
+
+            move || {
+                while let Ok((frame, frame_number)) = frame_rx.recv() {
+                    let path = output_dir.join(format!("{frame_number:06}.{}", format.extension()));
+                    save_still(&frame, format, &path)?;
+                }
+
+                Ok::<_, ExportError>(())
+            }
+        })
+        .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
+
+        let effects = self.effect_pass(self.fps);
+        let total_frames = self.frame_count(self.fps);
+
+        let forward_task = async {
+            let frame_tx = frame_tx;
+            let mut frame_count = 0;
+
+            while let Some((mut frame, frame_number)) =
+                tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
+            {
+                // frames already written are complete images, so they're left in place
+                self.cancellation.check()?;
+
+                if let Some(rect) = self.crop {
+                    frame = crop_frame(&frame, rect);
+                }
+
+                effects.apply(&mut frame, frame_number)?;
+
+                if frame_tx.send((frame, frame_number)).is_err() {
+                    break;
+                }
+
+                frame_count += 1;
+                self.progress
+                    .report(ExportStage::Rendering, frame_count, total_frames);
+            }
+
+            Ok::<_, ExportError>(())
+        };
+
+        let render_task = cap_rendering::render_video_to_channel(
+            self.render_options(),
+            self.project.clone(),
+            tx_image_data,
+            &self.meta,
+            self.render_segments.clone(),
+            self.fps,
+            self.resolution_base,
+            self.is_upgraded,
+            self.frame_rate_conversion,
+        )
+        .map(|r| r.map_err(ExportError::from));
+
+        self.cancellation.or_cancelled(tokio::try_join!(
+            writer_thread,
+            render_task,
+            forward_task
+        ))?;
+
+        Ok(output_dir)
+    }
+}
+
+fn save_still(frame: &RenderedFrame, format: StillFormat, path: &Path) -> Result<(), ExportError> {
+    let row_bytes = frame.width as usize * 4;
+    let data = frame
+        .data
+        .chunks(frame.padded_bytes_per_row as usize)
+        .take(frame.height as usize)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect::<Vec<_>>();
+
+    let image = RgbaImage::from_raw(frame.width, frame.height, data)
+        .ok_or_else(|| ExportError::Other("Frame data doesn't match its size".to_string()))?;
+
+    let result = match format {
+        StillFormat::Png => image.save_with_format(path, image::ImageFormat::Png),
+        StillFormat::Jpeg => {
+            let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgba8(image)
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(file, JPEG_QUALITY))
+        }
+    };
+
+    result.map_err(|e| ExportError::Other(e.to_string()))
+}
@@ -0,0 +1,268 @@
+use cap_media::{
+    data::{AudioInfo, RawVideoFormat, VideoInfo},
+    encoders::{MP4Input, RateControl, RtmpStream},
+    MediaError,
+};
+use cap_rendering::RenderedFrame;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::time::{Duration, Instant};
+
+use crate::{
+    crop_frame, AudioRender, ExportCancellation, ExportError, ExportProgress, ExportStage, Exporter,
+};
+
+// attempts at getting a dropped stream back before giving up on it
+const MAX_RECONNECT_ATTEMPTS: u32 = 6;
+// the wait before the first attempt, doubling after each one that fails
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+// Where `Exporter::export_to_stream` publishes to, like YouTube's or Twitch's RTMP ingest.
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamTarget {
+    // like rtmp://a.rtmp.youtube.com/live2
+    pub url: String,
+    pub stream_key: String,
+    // bits per second, which ingests expect to stay under what the stream is set up for
+    pub bit_rate: Option<u32>,
+}
+
+impl StreamTarget {
+    // ingests are happy with a few megabits for 1080p, a lot less than a file gets
+    const DEFAULT_BIT_RATE: usize = 6_000_000;
+
+    fn publish_url(&self) -> String {
+        let url = self.url.trim().trim_end_matches('/');
+        let key = self.stream_key.trim();
+
+        if key.is_empty() {
+            url.to_string()
+        } else {
+            format!("{url}/{key}")
+        }
+    }
+
+    fn rate_control(&self) -> RateControl {
+        RateControl::BitRate(
+            self.bit_rate
+                .map(|b| b as usize)
+                .unwrap_or(Self::DEFAULT_BIT_RATE),
+        )
+    }
+}
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// Publishes the composition live to `target` instead of writing a file, at the speed it
+    /// plays. A dropped connection is reopened a few times, losing what played while it was down.
+    pub async fn export_to_stream(self, target: StreamTarget) -> Result<(), ExportError> {
+        let (tx_image_data, mut rx_image_data) =
+            tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
+        // frames wait here for their turn to go out without blocking the renderer
+        let (frame_tx, mut frame_rx) = tokio::sync::mpsc::channel::<MP4Input>(4);
+
+        let fps = self.fps;
+        let (width, height) = self.output_size;
+
+        let audio = AudioRender::new(&self.audio_segments, &self.project, &self.meta)?;
+        let audio_info = match &audio {
+            Some(audio) => {
+                let info = audio.buffer.info();
+                Some(
+                    AudioInfo::new(info.sample_format, info.sample_rate, info.channels as u16)
+                        .map_err(Into::<MediaError>::into)?,
+                )
+            }
+            None => None,
+        };
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let cancellation = self.cancellation.clone();
+
+            move || {
+                let mut info = VideoInfo::from_raw(RawVideoFormat::Rgba, width, height, fps);
+                info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+                let connect = || {
+                    RtmpStream::init(
+                        "stream",
+                        &target.publish_url(),
+                        info,
+                        target.rate_control(),
+                        audio_info,
+                    )
+                };
+
+                // a first connection that fails is most likely a wrong URL or key, so it
+                // isn't retried
+                let mut stream = connect()?;
+                let mut pacer = Pacer::new(fps);
+
+                while let Some(input) = frame_rx.blocking_recv() {
+                    let frame_number = input.video.pts().unwrap_or_default() as u32;
+                    pacer.wait(frame_number);
+
+                    stream.queue_video_frame(input.video);
+                    if let Some(audio) = input.audio {
+                        stream.queue_audio_frame(audio);
+                    }
+
+                    if let Some(error) = stream.connection_error() {
+                        tracing::warn!("Stream disconnected at frame {frame_number}: {error}");
+                        stream = reconnect(&connect, &cancellation, error)?;
+                        // carry on from the next frame as if it were on time
+                        pacer.restart(frame_number + 1);
+                    }
+                }
+
+                stream.finish();
+
+                Ok::<_, ExportError>(())
+            }
+        })
+        .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
+
+        let effects = self.effect_pass(fps);
+        let total_frames = self.frame_count(fps);
+
+        let forward_task = async {
+            let frame_tx = frame_tx;
+            let mut audio = audio;
+            let mut frame_count = 0;
+
+            while let Some((mut frame, frame_number)) =
+                tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
+            {
+                self.cancellation.check()?;
+
+                if let Some(rect) = self.crop {
+                    frame = crop_frame(&frame, rect);
+                }
+
+                effects.apply(&mut frame, frame_number)?;
+
+                let audio_frame = audio.as_mut().and_then(|audio| {
+                    if frame_count == 0 {
+                        audio.buffer.set_playhead(0., &self.project);
+                    }
+
+                    // the timeline's audio waits for the intro to finish
+                    if frame_number < self.project.intro_frames(fps) {
+                        return Some(audio.silence(frame_number, fps));
+                    }
+
+                    audio.next_frame(frame_number, fps, &self.project)
+                });
+
+                let mut video_frame = VideoInfo::from_raw(RawVideoFormat::Rgba, width, height, fps)
+                    .wrap_frame(&frame.data, 0, frame.padded_bytes_per_row as usize);
+                video_frame.set_pts(Some(frame_number as i64));
+
+                // the encoder thread only hangs up when it's failed
+                if frame_tx
+                    .send(MP4Input {
+                        audio: audio_frame,
+                        video: video_frame,
+                    })
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+
+                frame_count += 1;
+                self.progress
+                    .report(ExportStage::Rendering, frame_count, total_frames);
+            }
+
+            Ok::<_, ExportError>(())
+        };
+
+        let render_task = cap_rendering::render_video_to_channel(
+            self.video_render_options(),
+            self.project.clone(),
+            tx_image_data,
+            &self.meta,
+            self.render_segments.clone(),
+            fps,
+            self.resolution_base,
+            self.is_upgraded,
+            self.frame_rate_conversion,
+        )
+        .map(|r| r.map_err(ExportError::from));
+
+        self.cancellation.or_cancelled(tokio::try_join!(
+            encoder_thread,
+            render_task,
+            forward_task
+        ))?;
+
+        Ok(())
+    }
+}
+
+// Opens the stream again, waiting longer after each attempt that fails
+fn reconnect(
+    connect: &impl Fn() -> Result<RtmpStream, MediaError>,
+    cancellation: &ExportCancellation,
+    error: ffmpeg::Error,
+) -> Result<RtmpStream, ExportError> {
+    let mut backoff = RECONNECT_BACKOFF;
+    let mut last_error = error.to_string();
+
+    for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+        std::thread::sleep(backoff);
+        cancellation.check()?;
+
+        tracing::info!("Reconnecting stream, attempt {attempt} of {MAX_RECONNECT_ATTEMPTS}");
+
+        match connect() {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e.to_string(),
+        }
+
+        backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+    }
+
+    Err(ExportError::StreamDisconnected(last_error))
+}
+
+// Holds frames back to the rate they play at, since ingests expect a live stream rather
+// than a file uploaded as fast as it renders
+struct Pacer {
+    fps: u32,
+    // when frame `start_frame` was due
+    started: Instant,
+    start_frame: u32,
+}
+
+impl Pacer {
+    fn new(fps: u32) -> Self {
+        Self {
+            fps,
+            started: Instant::now(),
+            start_frame: 0,
+        }
+    }
+
+    fn restart(&mut self, frame_number: u32) {
+        self.started = Instant::now();
+        self.start_frame = frame_number;
+    }
+
+    fn wait(&self, frame_number: u32) {
+        let due = self.started
+            + Duration::from_secs_f64(
+                frame_number.saturating_sub(self.start_frame) as f64 / self.fps as f64,
+            );
+
+        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+    }
+}
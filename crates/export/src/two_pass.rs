@@ -0,0 +1,126 @@
+use cap_media::{
+    data::{RawVideoFormat, VideoInfo},
+    encoders::{MP4Input, Pass, RateControl},
+};
+use cap_rendering::RenderedFrame;
+use futures::FutureExt;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{crop_frame, ExportError, ExportProgress, ExportStage, Exporter, OutputFile};
+
+impl<TOnProgress> Exporter<TOnProgress>
+where
+    TOnProgress: Fn(ExportProgress) + Send + 'static,
+{
+    /// The first pass of a two-pass export, which encodes the video alone into a throwaway
+    /// file beside `stats` to leave statistics there for the real encode.
+    pub(crate) async fn first_pass(
+        &self,
+        bit_rate: usize,
+        stats: PathBuf,
+        alpha: bool,
+    ) -> Result<(), ExportError> {
+        tracing::info!("Encoding first pass at {bit_rate} bits per second");
+
+        let output_path = stats.with_file_name("first_pass");
+        let rate_control = RateControl::TwoPass {
+            bit_rate,
+            pass: Pass::First,
+            stats,
+        };
+
+        let (tx_image_data, mut rx_image_data) =
+            tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(4);
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<(RenderedFrame, u32)>(4);
+
+        let encoder_thread = tokio::task::spawn_blocking({
+            let settings = self.settings;
+            let mut info = VideoInfo::from_raw(
+                RawVideoFormat::Rgba,
+                self.output_size.0,
+                self.output_size.1,
+                self.fps,
+            );
+            info.time_base = ffmpeg::Rational::new(1, self.fps as i32);
+
+            move || {
+                let mut encoder = OutputFile::create(
+                    &settings,
+                    rate_control,
+                    info,
+                    output_path,
+                    alpha,
+                    None,
+                    &[],
+                )?;
+
+                while let Ok((frame, frame_number)) = frame_rx.recv() {
+                    let mut video =
+                        info.wrap_frame(&frame.data, 0, frame.padded_bytes_per_row as usize);
+                    video.set_pts(Some(frame_number as i64));
+                    encoder.queue(MP4Input { video, audio: None });
+                }
+
+                encoder.finish();
+
+                Ok::<_, ExportError>(())
+            }
+        })
+        .then(|f| async { f.map_err(Into::into).and_then(|v| v) });
+
+        // effects change what's encoded, so the statistics have to include them
+        let effects = self.effect_pass(self.fps);
+
+        let total_frames = self.frame_count(self.fps);
+
+        let forward_task = async {
+            let frame_tx = frame_tx;
+            let mut frame_count = 0;
+
+            while let Some((mut frame, frame_number)) =
+                tokio::time::timeout(Duration::from_secs(6), rx_image_data.recv()).await?
+            {
+                self.cancellation.check()?;
+
+                if let Some(rect) = self.crop {
+                    frame = crop_frame(&frame, rect);
+                }
+
+                effects.apply(&mut frame, frame_number)?;
+
+                if frame_tx.send((frame, frame_number)).is_err() {
+                    break;
+                }
+
+                frame_count += 1;
+
+                // progress runs over both passes
+                self.progress
+                    .report(ExportStage::FirstPass, frame_count, total_frames * 2);
+            }
+
+            Ok::<_, ExportError>(())
+        };
+
+        let render_task = cap_rendering::render_video_to_channel(
+            self.video_render_options(),
+            self.project.clone(),
+            tx_image_data,
+            &self.meta,
+            self.render_segments.clone(),
+            self.fps,
+            self.resolution_base,
+            self.is_upgraded,
+            self.frame_rate_conversion,
+        )
+        .map(|r| r.map_err(ExportError::from));
+
+        self.cancellation.or_cancelled(tokio::try_join!(
+            encoder_thread,
+            render_task,
+            forward_task
+        ))?;
+
+        Ok(())
+    }
+}
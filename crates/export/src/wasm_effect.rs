@@ -0,0 +1,135 @@
+// Runs effects compiled to WebAssembly. A module gets no imports, so all it can touch is its
+// own memory, and it exports:
+//
+// - `memory`
+// - `cap_alloc(len: i32) -> i32`, giving the host `len` bytes to write into. The host holds on
+//   to what it's given between frames, and only asks again when it needs a bigger buffer.
+// - `cap_process(frame: i32, width: i32, height: i32, stride: i32, context: i32,
+//   context_len: i32) -> i32`, which draws on the RGBA frame in place and returns 0, or
+//   anything else when it failed. `stride` is the bytes between rows, and `context` is the
+//   frame's `EffectContext` as camelCase JSON.
+//
+// Modules can be given as `.wasm` or as `.wat` text.
+
+use std::{path::Path, sync::Mutex};
+
+use cap_rendering::RenderedFrame;
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+use crate::effects::{EffectContext, EffectError, EffectManifest, EffectPlugin};
+
+pub struct WasmEffect {
+    manifest: EffectManifest,
+    // a module runs one frame at a time
+    instance: Mutex<WasmInstance>,
+}
+
+struct WasmInstance {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<u32, u32>,
+    process: TypedFunc<(u32, u32, u32, u32, u32, u32), u32>,
+    // where the frame and context are written, and how much room each has
+    frame: (u32, usize),
+    context: (u32, usize),
+}
+
+impl WasmEffect {
+    pub fn load(manifest: EffectManifest, path: &Path) -> Result<Self, EffectError> {
+        let failed = |message: String| EffectError::Module {
+            id: manifest.id.clone(),
+            message,
+        };
+
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path)
+            .map_err(|e| failed(format!("{}: {e}", path.display())))?;
+
+        let mut store = Store::new(&engine, ());
+        let instance =
+            Instance::new(&mut store, &module, &[]).map_err(|e| failed(e.to_string()))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| failed("the module doesn't export its memory".to_string()))?;
+        let alloc = instance
+            .get_typed_func(&mut store, "cap_alloc")
+            .map_err(|e| failed(format!("cap_alloc: {e}")))?;
+        let process = instance
+            .get_typed_func(&mut store, "cap_process")
+            .map_err(|e| failed(format!("cap_process: {e}")))?;
+
+        Ok(Self {
+            manifest,
+            instance: Mutex::new(WasmInstance {
+                store,
+                memory,
+                alloc,
+                process,
+                frame: (0, 0),
+                context: (0, 0),
+            }),
+        })
+    }
+}
+
+impl WasmInstance {
+    // Writes `data` into the buffer at `slot`, growing it first if it's too small
+    fn write(
+        &mut self,
+        slot: fn(&mut Self) -> &mut (u32, usize),
+        data: &[u8],
+    ) -> Result<u32, String> {
+        let (mut ptr, len) = *slot(self);
+        if len < data.len() {
+            ptr = self
+                .alloc
+                .call(&mut self.store, data.len() as u32)
+                .map_err(|e| format!("cap_alloc: {e}"))?;
+            *slot(self) = (ptr, data.len());
+        }
+
+        self.memory
+            .write(&mut self.store, ptr as usize, data)
+            .map_err(|e| format!("cap_alloc gave a buffer outside memory: {e}"))?;
+
+        Ok(ptr)
+    }
+}
+
+impl EffectPlugin for WasmEffect {
+    fn manifest(&self) -> &EffectManifest {
+        &self.manifest
+    }
+
+    fn process(&self, frame: &mut RenderedFrame, context: &EffectContext) -> Result<(), String> {
+        let context = serde_json::to_vec(context).map_err(|e| e.to_string())?;
+
+        let mut instance = self.instance.lock().unwrap();
+        let frame_ptr = instance.write(|i| &mut i.frame, &frame.data)?;
+        let context_ptr = instance.write(|i| &mut i.context, &context)?;
+
+        let instance = &mut *instance;
+        let result = instance
+            .process
+            .call(
+                &mut instance.store,
+                (
+                    frame_ptr,
+                    frame.width,
+                    frame.height,
+                    frame.padded_bytes_per_row,
+                    context_ptr,
+                    context.len() as u32,
+                ),
+            )
+            .map_err(|e| format!("cap_process: {e}"))?;
+        if result != 0 {
+            return Err(format!("cap_process returned {result}"));
+        }
+
+        instance
+            .memory
+            .read(&instance.store, frame_ptr as usize, &mut frame.data)
+            .map_err(|e| e.to_string())
+    }
+}
@@ -9,9 +9,9 @@ use ffmpeg::{
     frame::{self, Video},
 };
 use ffmpeg_sys_next::{
-    av_buffer_ref, av_buffer_unref, av_hwdevice_ctx_create, av_hwframe_transfer_data,
-    avcodec_get_hw_config, AVBufferRef, AVCodecContext, AVCodecHWConfig, AVHWDeviceType,
-    AVPixelFormat, AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX,
+    av_buffer_ref, av_buffer_unref, av_frame_copy_props, av_hwdevice_ctx_create,
+    av_hwframe_transfer_data, avcodec_get_hw_config, AVBufferRef, AVCodecContext, AVCodecHWConfig,
+    AVHWDeviceType, AVPixelFormat, AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX,
 };
 
 thread_local! {
@@ -51,6 +51,8 @@ impl HwDevice {
                 let mut sw_frame = frame::Video::empty();
 
                 if av_hwframe_transfer_data(sw_frame.as_mut_ptr(), src.as_ptr(), 0) >= 0 {
+                    // the transfer only copies pixels, the color tags are needed to convert them
+                    av_frame_copy_props(sw_frame.as_mut_ptr(), src.as_ptr());
                     return Some(sw_frame);
                 };
             }
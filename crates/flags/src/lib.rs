@@ -3,9 +3,12 @@
 pub struct Flags {
     pub record_mouse_state: bool,
     pub split: bool,
+    // lets watermarks be limited to exports from accounts that haven't upgraded
+    pub free_tier_watermark: bool,
 }
 
 pub const FLAGS: Flags = Flags {
     record_mouse_state: cfg!(debug_assertions),
     split: false,
+    free_tier_watermark: false,
 };
@@ -22,6 +22,34 @@ pub enum RawVideoFormat {
     Gray,
     YUYV420,
     Rgba,
+    // 10 bit 4:2:0 with interleaved chroma, as HDR captures and hardware decoders give it
+    P010,
+    Yuv420p10,
+    Yuv422p10,
+}
+
+// Whether samples of `format` have more than 8 bits. Encoders keep these at 10 bits so
+// gradients don't band.
+pub fn is_high_bit_depth(format: Pixel) -> bool {
+    matches!(
+        format,
+        Pixel::P010LE
+            | Pixel::YUV420P10LE
+            | Pixel::YUV422P10LE
+            | Pixel::YUV444P10LE
+            | Pixel::RGBA64LE
+            | Pixel::RGB48LE
+            | Pixel::X2RGB10LE
+            | Pixel::X2BGR10LE
+    )
+}
+
+// Whether `format` has chroma at full vertical resolution, which 4:2:0 would throw away
+pub fn is_chroma_422(format: Pixel) -> bool {
+    matches!(
+        format,
+        Pixel::YUV422P | Pixel::YUVJ422P | Pixel::YUYV422 | Pixel::UYVY422 | Pixel::YUV422P10LE
+    )
 }
 
 pub fn ffmpeg_sample_format_for(sample_format: SampleFormat) -> Option<Sample> {
@@ -237,6 +265,9 @@ impl VideoInfo {
                 RawVideoFormat::Gray => Pixel::GRAY8,
                 RawVideoFormat::YUYV420 => Pixel::YUV420P,
                 RawVideoFormat::Rgba => Pixel::RGBA,
+                RawVideoFormat::P010 => Pixel::P010LE,
+                RawVideoFormat::Yuv420p10 => Pixel::YUV420P10LE,
+                RawVideoFormat::Yuv422p10 => Pixel::YUV422P10LE,
             },
             width,
             height,
@@ -276,6 +307,10 @@ impl VideoInfo {
         }
     }
 
+    pub fn is_high_bit_depth(&self) -> bool {
+        is_high_bit_depth(self.pixel_format)
+    }
+
     pub fn pixel_format_int(&self) -> i32 {
         // This is necessary because the AVPixelFormat C enum has specific integer values that
         // the Rust PixelFormat enum doesn't replicate. But there is a From/Into conversion
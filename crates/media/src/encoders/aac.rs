@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+
+use ffmpeg::{
+    codec::{context, encoder},
+    format::{self, sample::Type, Sample},
+};
+
+use crate::{
+    data::{AudioInfo, FFAudio, FFRational},
+    MediaError,
+};
+
+// AAC through FFmpeg's own encoder, for containers that can't carry Opus like FLV
+pub struct AacEncoder {
+    tag: &'static str,
+    encoder: encoder::Audio,
+    packet: ffmpeg::Packet,
+    resampler: ffmpeg::software::resampling::Context,
+    resampled_frame: FFAudio,
+    // the encoder takes planar samples, so each channel is buffered on its own
+    buffers: Vec<VecDeque<u8>>,
+    stream_index: usize,
+    samples: i64,
+}
+
+impl AacEncoder {
+    pub const OUTPUT_BITRATE: usize = 160 * 1000; // 160k
+    pub const ENCODER_NAME: &'static str = "aac";
+    const SAMPLE_FORMAT: Sample = Sample::F32(Type::Planar);
+
+    pub fn init(
+        tag: &'static str,
+        input_config: AudioInfo,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        let codec = encoder::find_by_name(Self::ENCODER_NAME)
+            .ok_or(MediaError::MissingCodec("AAC audio"))?;
+        let mut encoder = context::Context::new_with_codec(codec).encoder().audio()?;
+
+        let resampler = ffmpeg::software::resampler(
+            (
+                input_config.sample_format,
+                input_config.channel_layout(),
+                input_config.sample_rate,
+            ),
+            (
+                Self::SAMPLE_FORMAT,
+                input_config.channel_layout(),
+                input_config.sample_rate,
+            ),
+        )?;
+
+        encoder.set_bit_rate(Self::OUTPUT_BITRATE);
+        encoder.set_rate(input_config.rate());
+        encoder.set_format(Self::SAMPLE_FORMAT);
+        encoder.set_channel_layout(input_config.channel_layout());
+        encoder.set_time_base(FFRational(1, input_config.rate()));
+
+        // FLV and MP4 keep the codec configuration in the header rather than in every packet
+        if output
+            .format()
+            .flags()
+            .contains(format::flag::Flags::GLOBAL_HEADER)
+        {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = encoder.open()?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        let stream_index = output_stream.index();
+        output_stream.set_time_base(FFRational(1, input_config.rate()));
+        output_stream.set_parameters(&encoder);
+
+        Ok(Self {
+            tag,
+            buffers: vec![VecDeque::new(); input_config.channels],
+            encoder,
+            packet: ffmpeg::Packet::empty(),
+            resampler,
+            resampled_frame: FFAudio::empty(),
+            stream_index,
+            samples: 0,
+        })
+    }
+
+    pub fn queue_frame(&mut self, frame: FFAudio, output: &mut format::context::Output) {
+        if let Err(e) = self.resampler.run(&frame, &mut self.resampled_frame) {
+            tracing::error!("{}: Failed to convert audio: {:?}", self.tag, e);
+            return;
+        }
+
+        self.buffer_resampled();
+        self.encode_buffered(false, output);
+    }
+
+    fn buffer_resampled(&mut self) {
+        let bytes = self.resampled_frame.samples() * Self::SAMPLE_FORMAT.bytes();
+
+        for (channel, buffer) in self.buffers.iter_mut().enumerate() {
+            buffer.extend(&self.resampled_frame.data(channel)[0..bytes]);
+        }
+    }
+
+    // Encodes whole frames from the buffers, and what's left over as a short frame when
+    // `flush` is set
+    fn encode_buffered(&mut self, flush: bool, output: &mut format::context::Output) {
+        let sample_bytes = Self::SAMPLE_FORMAT.bytes();
+
+        loop {
+            let buffered = self.buffers.first().map(|b| b.len()).unwrap_or(0) / sample_bytes;
+            let frame_size = self.encoder.frame_size() as usize;
+
+            let samples = match buffered {
+                0 => break,
+                n if n >= frame_size => frame_size,
+                n if flush => n,
+                _ => break,
+            };
+
+            let mut frame = FFAudio::new(
+                self.encoder.format(),
+                samples,
+                self.encoder.channel_layout(),
+            );
+
+            for (channel, buffer) in self.buffers.iter_mut().enumerate() {
+                let bytes = buffer.drain(0..samples * sample_bytes).collect::<Vec<_>>();
+                frame.data_mut(channel)[0..bytes.len()].copy_from_slice(&bytes);
+            }
+
+            frame.set_pts(Some(self.samples));
+            self.samples += samples as i64;
+
+            if let Err(e) = self.encoder.send_frame(&frame) {
+                tracing::error!("{}: Failed to send audio to encoder: {:?}", self.tag, e);
+                return;
+            }
+
+            self.process_packets(output);
+        }
+    }
+
+    fn process_packets(&mut self, output: &mut format::context::Output) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.encoder.time_base(),
+                output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(output) {
+                tracing::error!("{}: Failed to write packet: {:?}", self.tag, e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, output: &mut format::context::Output) {
+        while self.resampler.delay().is_some() {
+            if self.resampler.flush(&mut self.resampled_frame).is_err()
+                || self.resampled_frame.samples() == 0
+            {
+                break;
+            }
+
+            self.buffer_resampled();
+        }
+
+        self.encode_buffered(true, output);
+
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("{}: Failed to send EOF to encoder: {:?}", self.tag, e);
+            return;
+        }
+        self.process_packets(output);
+    }
+}
@@ -0,0 +1,299 @@
+use std::{borrow::Cow, fs::File, io::BufWriter, path::PathBuf};
+
+use color_quant::NeuQuant;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::MediaError;
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GifDither {
+    None,
+    // a fixed pattern, which compresses better and doesn't crawl between frames
+    #[default]
+    Ordered,
+    // error diffusion, smoother gradients at the cost of size and some shimmer
+    FloydSteinberg,
+}
+
+// NeuQuant samples every nth pixel when learning a palette, 10 is its suggested tradeoff
+const SAMPLE_FACTOR: i32 = 10;
+
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+// how far ordered dithering pushes each channel either way
+const ORDERED_SPREAD: f32 = 24.0;
+
+// Each frame gets its own palette so colours stay accurate as the content changes, and only
+// the pixels that changed since the last frame are written, with the rest left transparent
+// over it. Screen recordings are mostly still, so that's where most of the savings come from.
+pub struct GifEncoder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u32,
+    height: u32,
+    fps: u32,
+    max_colors: u16,
+    dither: GifDither,
+    frames: u64,
+    // the last frame's source pixels, tightly packed
+    previous: Option<Vec<u8>>,
+    // held back so frames that don't change anything can extend its delay instead
+    pending: Option<gif::Frame<'static>>,
+}
+
+impl GifEncoder {
+    pub fn init(
+        mut output: PathBuf,
+        width: u32,
+        height: u32,
+        fps: u32,
+        max_colors: u16,
+        dither: GifDither,
+    ) -> Result<Self, MediaError> {
+        output.set_extension("gif");
+
+        let file = File::create(&output).map_err(|e| {
+            tracing::error!("Failed to create {}: {e}", output.display());
+            MediaError::Any("Failed to create GIF file")
+        })?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+            .map_err(|_| MediaError::Any("Failed to create GIF encoder"))?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|_| MediaError::Any("Failed to write GIF header"))?;
+
+        Ok(Self {
+            encoder,
+            width,
+            height,
+            fps,
+            max_colors: max_colors.clamp(2, 256),
+            dither,
+            frames: 0,
+            previous: None,
+            pending: None,
+        })
+    }
+
+    // `data` is RGBA, `stride` bytes per row
+    pub fn queue_frame(&mut self, data: &[u8], stride: usize) -> Result<(), MediaError> {
+        let row_bytes = self.width as usize * 4;
+        let pixels = data
+            .chunks(stride)
+            .take(self.height as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect::<Vec<_>>();
+
+        // delays are in hundredths of a second, so they're rounded against the running total
+        // to keep the timing from drifting at rates that don't divide 100
+        let delay = ((self.frames + 1) * 100 / self.fps as u64
+            - self.frames * 100 / self.fps as u64) as u16;
+        self.frames += 1;
+
+        // any see-through pixel rules out drawing over the last frame, since a pixel turning
+        // transparent would leave the old one showing
+        let has_alpha = pixels.chunks(4).any(|p| p[3] < 128);
+        let previous = self.previous.as_ref().filter(|_| !has_alpha);
+
+        let Some(bounds) = changed_bounds(&pixels, previous, self.width, self.height) else {
+            if let Some(pending) = &mut self.pending {
+                pending.delay += delay;
+            }
+            return Ok(());
+        };
+
+        let frame = self.quantize(&pixels, previous, bounds, delay, has_alpha);
+        self.previous = Some(pixels);
+
+        if let Some(pending) = self.pending.replace(frame) {
+            self.write(&pending)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<(), MediaError> {
+        if let Some(pending) = self.pending.take() {
+            self.write(&pending)?;
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, frame: &gif::Frame) -> Result<(), MediaError> {
+        self.encoder
+            .write_frame(frame)
+            .map_err(|_| MediaError::Any("Failed to write GIF frame"))
+    }
+
+    fn quantize(
+        &self,
+        pixels: &[u8],
+        previous: Option<&Vec<u8>>,
+        bounds: Bounds,
+        delay: u16,
+        has_alpha: bool,
+    ) -> gif::Frame<'static> {
+        let stride = self.width as usize * 4;
+        let region = (bounds.top..bounds.bottom)
+            .flat_map(|y| {
+                let start = y * stride + bounds.left * 4;
+                &pixels[start..start + bounds.width() * 4]
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        // the last index is kept for transparency
+        let quant = NeuQuant::new(SAMPLE_FACTOR, self.max_colors as usize - 1, &region);
+        let transparent = (self.max_colors - 1) as u8;
+        let mut palette = quant.color_map_rgb();
+
+        let unchanged = |x: usize, y: usize| {
+            previous.is_some_and(|previous| {
+                let i = ((bounds.top + y) * self.width as usize + bounds.left + x) * 4;
+                previous[i..i + 4] == pixels[i..i + 4]
+            })
+        };
+
+        let (width, height) = (bounds.width(), bounds.height());
+        let mut indices = vec![transparent; width * height];
+        // error carried to the current and next rows, for Floyd-Steinberg
+        let mut errors = vec![[0f32; 3]; width * 2 + 2];
+
+        for y in 0..height {
+            let (current, next) = errors.split_at_mut(width + 1);
+            next.fill([0.0; 3]);
+
+            for x in 0..width {
+                let pixel = &region[(y * width + x) * 4..][..4];
+                if pixel[3] < 128 || unchanged(x, y) {
+                    continue;
+                }
+
+                let mut color = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+                match self.dither {
+                    GifDither::None => {}
+                    GifDither::Ordered => {
+                        let offset = (BAYER_4X4[y % 4][x % 4] / 16.0 - 0.5) * ORDERED_SPREAD;
+                        color.iter_mut().for_each(|c| *c += offset);
+                    }
+                    GifDither::FloydSteinberg => {
+                        for (c, e) in color.iter_mut().zip(current[x]) {
+                            *c += e;
+                        }
+                    }
+                }
+
+                let color = color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+                let index = quant.index_of(&[color[0], color[1], color[2], 255]);
+                indices[y * width + x] = index as u8;
+
+                if self.dither == GifDither::FloydSteinberg {
+                    let chosen = &palette[index * 3..][..3];
+                    let error = [0, 1, 2].map(|c| color[c] as f32 - chosen[c] as f32);
+
+                    let spread = |cell: &mut [f32; 3], weight: f32| {
+                        for (c, e) in cell.iter_mut().zip(error) {
+                            *c += e * weight;
+                        }
+                    };
+                    spread(&mut current[x + 1], 7.0 / 16.0);
+                    if x > 0 {
+                        spread(&mut next[x - 1], 3.0 / 16.0);
+                    }
+                    spread(&mut next[x], 5.0 / 16.0);
+                    spread(&mut next[x + 1], 1.0 / 16.0);
+                }
+            }
+
+            current.copy_from_slice(&next[..width + 1]);
+        }
+
+        palette.resize(self.max_colors as usize * 3, 0);
+
+        gif::Frame {
+            left: bounds.left as u16,
+            top: bounds.top as u16,
+            width: width as u16,
+            height: height as u16,
+            delay,
+            // without alpha each frame draws over the last, with it each frame stands alone
+            dispose: if has_alpha {
+                gif::DisposalMethod::Background
+            } else {
+                gif::DisposalMethod::Keep
+            },
+            transparent: Some(transparent),
+            palette: Some(palette),
+            buffer: Cow::Owned(indices),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bounds {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+impl Bounds {
+    fn width(&self) -> usize {
+        self.right - self.left
+    }
+
+    fn height(&self) -> usize {
+        self.bottom - self.top
+    }
+}
+
+// The smallest rectangle holding every pixel that differs from `previous`, or the whole
+// frame without one. None when nothing changed.
+fn changed_bounds(
+    pixels: &[u8],
+    previous: Option<&Vec<u8>>,
+    width: u32,
+    height: u32,
+) -> Option<Bounds> {
+    let (width, height) = (width as usize, height as usize);
+
+    let Some(previous) = previous else {
+        return Some(Bounds {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        });
+    };
+
+    let mut bounds: Option<Bounds> = None;
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) * 4;
+            if pixels[i..i + 4] == previous[i..i + 4] {
+                continue;
+            }
+
+            let b = bounds.get_or_insert(Bounds {
+                left: x,
+                top: y,
+                right: x + 1,
+                bottom: y + 1,
+            });
+            b.left = b.left.min(x);
+            b.right = b.right.max(x + 1);
+            b.bottom = y + 1;
+        }
+    }
+
+    bounds
+}
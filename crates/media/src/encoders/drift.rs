@@ -0,0 +1,98 @@
+// Audio devices run on their own clocks, so an hour from a mic rarely holds exactly an hour
+// of samples at its nominal rate. Encoders lay samples back to back, so left alone the audio
+// slowly slides out of sync with video, which follows the capture timestamps. This compares
+// how much audio has arrived with how much the timestamps say should have, and works out how
+// to bring the two back together.
+pub struct AudioDriftCorrector {
+    sample_rate: u32,
+    // timestamp of the first frame, in microseconds
+    start: Option<i64>,
+    // samples queued so far, excluding channels
+    samples: u64,
+    // samples added (or removed, when negative) by corrections so far
+    corrected: f64,
+    // timestamps jitter with when buffers are delivered, so drift is averaged out
+    drift: f64,
+    // a correction is spread over the samples up to here, and isn't added to until then
+    resampling_until: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriftCorrection {
+    None,
+    // stretch (positive `delta`) or squash the next `over` samples by `delta` samples
+    Resample { delta: i32, over: i32 },
+    // the timestamps jumped ahead, so this many samples of silence go before the frame
+    Insert(usize),
+    // the audio got ahead, so this many of the latest samples are dropped
+    Skip(usize),
+}
+
+impl AudioDriftCorrector {
+    // drift smaller than this is just jitter in the timestamps
+    const TOLERANCE_SECS: f64 = 0.02;
+    // more than this is from a hiccup rather than clock drift, and stretching would be audible
+    const MAX_RESAMPLE_SECS: f64 = 0.2;
+    // corrections are spread over this long
+    const CORRECTION_SECS: f64 = 1.0;
+    // at most 0.5% faster or slower, well below what can be heard
+    const MAX_STRETCH: f64 = 0.005;
+    // weight of each new measurement in the averaged drift
+    const SMOOTHING: f64 = 0.05;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            start: None,
+            samples: 0,
+            corrected: 0.0,
+            drift: 0.0,
+            resampling_until: 0,
+        }
+    }
+
+    // Called with each frame's timestamp in microseconds and its length, before it's queued
+    pub fn process(&mut self, timestamp: i64, samples: usize) -> DriftCorrection {
+        let rate = self.sample_rate as f64;
+        let start = *self.start.get_or_insert(timestamp);
+
+        let expected = (timestamp - start) as f64 * rate / 1_000_000.0;
+        // positive when there's less audio than time has passed
+        let drift = expected - (self.samples as f64 + self.corrected);
+        let position = self.samples;
+        self.samples += samples as u64;
+
+        if drift.abs() > Self::MAX_RESAMPLE_SECS * rate {
+            self.corrected += drift;
+            self.drift = 0.0;
+            self.resampling_until = 0;
+
+            return if drift > 0.0 {
+                DriftCorrection::Insert(drift as usize)
+            } else {
+                DriftCorrection::Skip(-drift as usize)
+            };
+        }
+
+        self.drift += (drift - self.drift) * Self::SMOOTHING;
+
+        if position < self.resampling_until || self.drift.abs() < Self::TOLERANCE_SECS * rate {
+            return DriftCorrection::None;
+        }
+
+        let over = Self::CORRECTION_SECS * rate;
+        let delta = self
+            .drift
+            .clamp(-over * Self::MAX_STRETCH, over * Self::MAX_STRETCH)
+            .round();
+
+        self.corrected += delta;
+        self.drift -= delta;
+        self.resampling_until = position + over as u64;
+
+        DriftCorrection::Resample {
+            delta: delta as i32,
+            over: over as i32,
+        }
+    }
+}
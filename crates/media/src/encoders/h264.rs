@@ -1,5 +1,9 @@
+use super::{
+    pixel_format::output_format,
+    rate_control::{quality_to_qp, RateControl},
+};
 use crate::{
-    data::{FFPacket, FFVideo, VideoInfo},
+    data::{is_chroma_422, is_high_bit_depth, FFPacket, FFVideo, VideoInfo},
     MediaError,
 };
 use ffmpeg::{
@@ -19,6 +23,8 @@ pub struct H264Encoder {
 }
 
 impl H264Encoder {
+    pub const BIT_RATE: usize = 12_000_000;
+
     pub fn factory(
         tag: &'static str,
         config: VideoInfo,
@@ -30,44 +36,46 @@ impl H264Encoder {
         tag: &'static str,
         config: VideoInfo,
         output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        Self::init_with_rate_control(tag, config, RateControl::BitRate(Self::BIT_RATE), output)
+    }
+
+    pub fn init_with_rate_control(
+        tag: &'static str,
+        config: VideoInfo,
+        rate_control: RateControl,
+        output: &mut format::context::Output,
     ) -> Result<Self, MediaError> {
         dbg!(config);
 
-        let (codec, options) = get_codec_and_options(&config)?;
+        let (codec, mut options) = get_codec_and_options(&config)?;
 
-        let (format, converter) = if !codec
-            .video()
-            .unwrap()
-            .formats()
-            .unwrap()
-            .any(|f| f == config.pixel_format)
-        {
-            let format = ffmpeg::format::Pixel::YUV420P;
+        // 10 bit sources are encoded as High 10 or High 4:2:2 where libx264 was built for it
+        let format = output_format(&codec, config.pixel_format);
+        let converter = if format != config.pixel_format {
             tracing::debug!(
                 "Converting from {:?} to {:?} for H264 encoding",
                 config.pixel_format,
                 format
             );
-            (
-                format,
-                Some(
-                    ffmpeg::software::converter(
-                        (config.width, config.height),
+            Some(
+                ffmpeg::software::converter(
+                    (config.width, config.height),
+                    config.pixel_format,
+                    format,
+                )
+                .map_err(|e| {
+                    tracing::error!(
+                        "Failed to create converter from {:?} to {:?}: {:?}",
                         config.pixel_format,
                         format,
-                    )
-                    .map_err(|e| {
-                        tracing::error!(
-                            "Failed to create converter from {:?} to YUV420P: {:?}",
-                            config.pixel_format,
-                            e
-                        );
-                        MediaError::Any("Failed to create frame converter")
-                    })?,
-                ),
+                        e
+                    );
+                    MediaError::Any("Failed to create frame converter")
+                })?,
             )
         } else {
-            (config.pixel_format, None)
+            None
         };
 
         let mut encoder_ctx = context::Context::new_with_codec(codec);
@@ -81,8 +89,43 @@ impl H264Encoder {
         encoder.set_format(format);
         encoder.set_time_base(config.frame_rate.invert());
         encoder.set_frame_rate(Some(config.frame_rate));
-        encoder.set_bit_rate(12_000_000);
-        encoder.set_max_bit_rate(12_000_000);
+
+        if is_high_bit_depth(format) && is_chroma_422(format) {
+            options.set("profile", "high422");
+        } else if is_high_bit_depth(format) {
+            options.set("profile", "high10");
+        }
+
+        match &rate_control {
+            RateControl::BitRate(bit_rate) => {
+                encoder.set_bit_rate(*bit_rate);
+                encoder.set_max_bit_rate(*bit_rate);
+            }
+            RateControl::Quality(quality) => {
+                options.set("crf", &quality_to_qp(*quality).to_string());
+            }
+            RateControl::TwoPass {
+                bit_rate,
+                pass,
+                stats,
+            } => RateControl::configure_pass(
+                Self::encoder_name(),
+                &mut encoder,
+                &mut options,
+                *bit_rate,
+                *pass,
+                stats,
+            )?,
+        }
+
+        // FLV and MP4 keep the codec configuration in the header rather than in every packet
+        if output
+            .format()
+            .flags()
+            .contains(format::flag::Flags::GLOBAL_HEADER)
+        {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
 
         let video_encoder = encoder.open_with(options)?;
 
@@ -112,7 +155,7 @@ impl H264Encoder {
                 }
                 Err(e) => {
                     tracing::error!(
-                        "Failed to convert frame: {:?} from format {:?} to YUV420P",
+                        "Failed to convert frame: {:?} from format {:?}",
                         e,
                         frame.format()
                     );
@@ -153,10 +196,8 @@ impl H264Encoder {
         }
         self.process_frame(output);
     }
-}
 
-fn get_codec_and_options(config: &VideoInfo) -> Result<(Codec, Dictionary), MediaError> {
-    let encoder_name = {
+    pub fn encoder_name() -> &'static str {
         if cfg!(target_os = "macos") {
             "libx264"
             // looks terrible rn :(
@@ -164,7 +205,11 @@ fn get_codec_and_options(config: &VideoInfo) -> Result<(Codec, Dictionary), Medi
         } else {
             "libx264"
         }
-    };
+    }
+}
+
+fn get_codec_and_options(config: &VideoInfo) -> Result<(Codec, Dictionary), MediaError> {
+    let encoder_name = H264Encoder::encoder_name();
     if let Some(codec) = encoder::find_by_name(encoder_name) {
         let mut options = Dictionary::new();
 
@@ -0,0 +1,368 @@
+use super::{
+    pixel_format::output_format,
+    rate_control::{quality_to_qp, RateControl},
+};
+use crate::{
+    data::{is_chroma_422, is_high_bit_depth, FFPacket, FFVideo, VideoInfo},
+    MediaError,
+};
+use ffmpeg::{
+    codec::{self, context, encoder},
+    color,
+    format::{self, Pixel},
+    threading::Config,
+    Dictionary,
+};
+
+pub struct HevcEncoder {
+    tag: &'static str,
+    encoder: encoder::Video,
+    config: VideoInfo,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    stream_index: usize,
+    packet: ffmpeg::Packet,
+}
+
+impl HevcEncoder {
+    // HEVC looks as good as H264 at around two thirds of the bit rate
+    pub const BIT_RATE: usize = 8_000_000;
+
+    pub fn factory(
+        tag: &'static str,
+        config: VideoInfo,
+        rate_control: RateControl,
+    ) -> impl FnOnce(&mut format::context::Output) -> Result<Self, MediaError> {
+        move |o| Self::init(tag, config, rate_control, o)
+    }
+
+    pub fn init(
+        tag: &'static str,
+        config: VideoInfo,
+        rate_control: RateControl,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        Self::open(tag, config, rate_control, false, output)
+    }
+
+    // Encodes frames that are already PQ encoded BT.2020 as 10 bit HDR10
+    pub fn init_hdr10(
+        tag: &'static str,
+        config: VideoInfo,
+        rate_control: RateControl,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        Self::open(tag, config, rate_control, true, output)
+    }
+
+    // Hardware encoders can be present in FFmpeg without the hardware to back them,
+    // so each is tried in turn until one opens
+    fn open(
+        tag: &'static str,
+        config: VideoInfo,
+        rate_control: RateControl,
+        hdr10: bool,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        for name in Self::encoder_names() {
+            if rate_control.is_two_pass() && !RateControl::supports_two_pass(name) {
+                continue;
+            }
+
+            let Some(codec) = encoder::find_by_name(name) else {
+                continue;
+            };
+
+            let format = if hdr10 {
+                hdr10_output_format(&codec)
+            } else {
+                output_format(&codec, config.pixel_format)
+            };
+            if hdr10 && !is_high_bit_depth(format) {
+                tracing::warn!("{tag}: {name} can't encode 10 bit video");
+                continue;
+            }
+
+            let video_encoder =
+                match open_encoder(name, codec, &config, format, &rate_control, hdr10) {
+                    Ok(encoder) => encoder,
+                    Err(e) => {
+                        tracing::warn!("{tag}: Failed to open {name}: {e}");
+                        continue;
+                    }
+                };
+
+            tracing::info!("{tag}: Encoding HEVC with {name} as {format:?}");
+
+            let converter = if format != config.pixel_format {
+                let space = if hdr10 {
+                    color::Space::BT2020NCL
+                } else {
+                    color::Space::BT709
+                };
+                Some(create_converter_for(&config, format, space)?)
+            } else {
+                None
+            };
+
+            let mut output_stream = output.add_stream(codec)?;
+            let stream_index = output_stream.index();
+            output_stream.set_time_base(config.frame_rate.invert());
+            output_stream.set_rate(config.frame_rate);
+            output_stream.set_parameters(&video_encoder);
+            // QuickTime and Apple devices only play HEVC in MP4 when tagged hvc1
+            unsafe {
+                (*(*output_stream.as_mut_ptr()).codecpar).codec_tag = u32::from_le_bytes(*b"hvc1");
+            }
+
+            return Ok(Self {
+                tag,
+                encoder: video_encoder,
+                stream_index,
+                config,
+                converter,
+                packet: FFPacket::empty(),
+            });
+        }
+
+        Err(MediaError::MissingCodec("HEVC video"))
+    }
+
+    pub fn queue_frame(&mut self, frame: FFVideo, output: &mut format::context::Output) {
+        let frame = if let Some(converter) = &mut self.converter {
+            let mut new_frame = FFVideo::empty();
+            match converter.run(&frame, &mut new_frame) {
+                Ok(_) => {
+                    new_frame.set_pts(frame.pts());
+                    new_frame
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "{}: Failed to convert frame from format {:?}: {:?}",
+                        self.tag,
+                        frame.format(),
+                        e
+                    );
+                    return;
+                }
+            }
+        } else {
+            frame
+        };
+
+        if let Err(e) = self.encoder.send_frame(&frame) {
+            tracing::error!("{}: Failed to send frame to encoder: {:?}", self.tag, e);
+            return;
+        }
+
+        self.process_frame(output);
+    }
+
+    fn process_frame(&mut self, output: &mut format::context::Output) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.config.time_base,
+                output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(output) {
+                tracing::error!("{}: Failed to write packet: {:?}", self.tag, e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, output: &mut format::context::Output) {
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("{}: Failed to send EOF to encoder: {:?}", self.tag, e);
+            return;
+        }
+        self.process_frame(output);
+    }
+
+    // In order of preference, ending with software encoding
+    pub fn encoder_names() -> &'static [&'static str] {
+        if cfg!(target_os = "macos") {
+            &["hevc_videotoolbox", "libx265"]
+        } else if cfg!(windows) {
+            &["hevc_nvenc", "hevc_mf", "libx265"]
+        } else {
+            &["hevc_nvenc", "libx265"]
+        }
+    }
+}
+
+// HDR10 is always 10 bit 4:2:0, whatever the input
+fn hdr10_output_format(codec: &codec::Codec) -> Pixel {
+    let supported = codec.video().ok().and_then(|v| v.formats());
+    match supported {
+        Some(mut formats) => formats
+            .find(|f| matches!(f, Pixel::P010LE | Pixel::YUV420P10LE))
+            .unwrap_or(Pixel::YUV420P),
+        None => Pixel::P010LE,
+    }
+}
+
+fn open_encoder(
+    name: &str,
+    codec: codec::Codec,
+    config: &VideoInfo,
+    format: Pixel,
+    rate_control: &RateControl,
+    hdr10: bool,
+) -> Result<encoder::Video, ffmpeg::Error> {
+    let mut encoder_ctx = context::Context::new_with_codec(codec);
+    encoder_ctx.set_threading(Config::count(4));
+    let mut encoder = encoder_ctx.encoder().video()?;
+
+    encoder.set_width(config.width);
+    encoder.set_height(config.height);
+    encoder.set_format(format);
+    encoder.set_time_base(config.frame_rate.invert());
+    encoder.set_frame_rate(Some(config.frame_rate));
+
+    // Rendered frames are SDR unless they're HDR10. Tagging them as BT.709 explicitly stops
+    // players from guessing, which for 10 bit HEVC can mean treating the video as HDR and
+    // washing it out.
+    encoder.set_color_range(color::Range::MPEG);
+    unsafe {
+        use ffmpeg::ffi::{AVColorPrimaries, AVColorTransferCharacteristic};
+
+        let ctx = encoder.as_mut_ptr();
+        if hdr10 {
+            encoder.set_colorspace(color::Space::BT2020NCL);
+            (*ctx).color_primaries = AVColorPrimaries::AVCOL_PRI_BT2020;
+            (*ctx).color_trc = AVColorTransferCharacteristic::AVCOL_TRC_SMPTE2084;
+        } else {
+            encoder.set_colorspace(color::Space::BT709);
+            (*ctx).color_primaries = AVColorPrimaries::AVCOL_PRI_BT709;
+            (*ctx).color_trc = AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+        }
+    }
+
+    let mut options = Dictionary::new();
+    let keyframe_interval = (2 * config.frame_rate.numerator()).to_string();
+    options.set("g", &keyframe_interval);
+
+    if is_high_bit_depth(format) && is_chroma_422(format) {
+        options.set("profile", "main422-10");
+    } else if is_high_bit_depth(format) {
+        options.set("profile", "main10");
+    }
+
+    match rate_control {
+        RateControl::BitRate(bit_rate) => {
+            encoder.set_bit_rate(*bit_rate);
+            encoder.set_max_bit_rate(*bit_rate * 3 / 2);
+        }
+        RateControl::TwoPass {
+            bit_rate,
+            pass,
+            stats,
+        } => {
+            RateControl::configure_pass(name, &mut encoder, &mut options, *bit_rate, *pass, stats)?
+        }
+        RateControl::Quality(quality) => {
+            let quality = *quality;
+            let qp = quality_to_qp(quality).to_string();
+
+            match name {
+                "hevc_videotoolbox" | "hevc_mf" => {
+                    encoder.set_flags(codec::Flags::QSCALE);
+                    encoder.set_quality(
+                        quality.min(100) as usize * ffmpeg::ffi::FF_QP2LAMBDA as usize,
+                    );
+                    if name == "hevc_mf" {
+                        options.set("rate_control", "quality");
+                    }
+                }
+                "hevc_nvenc" => {
+                    options.set("rc", "vbr");
+                    options.set("cq", &qp);
+                }
+                _ => options.set("crf", &qp),
+            }
+        }
+    }
+
+    match name {
+        "hevc_videotoolbox" => {
+            options.set("allow_sw", "0");
+        }
+        "hevc_nvenc" => {
+            options.set("preset", "p5");
+        }
+        "hevc_mf" => {
+            options.set("hw_encoding", "1");
+        }
+        _ => {
+            options.set("preset", "fast");
+        }
+    }
+
+    encoder.open_with(options)
+}
+
+pub(super) fn create_converter(
+    config: &VideoInfo,
+    format: Pixel,
+) -> Result<ffmpeg::software::scaling::Context, MediaError> {
+    create_converter_for(config, format, color::Space::BT709)
+}
+
+// Converts to YUV with `space`'s matrix, BT.709 or BT.2020
+fn create_converter_for(
+    config: &VideoInfo,
+    format: Pixel,
+    space: color::Space,
+) -> Result<ffmpeg::software::scaling::Context, MediaError> {
+    let mut converter =
+        ffmpeg::software::converter((config.width, config.height), config.pixel_format, format)
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to create converter from {:?} to {:?}: {:?}",
+                    config.pixel_format,
+                    format,
+                    e
+                );
+                MediaError::Any("Failed to create frame converter")
+            })?;
+
+    // RGB is always full range, while YUV sources such as 10 bit captures are limited
+    let source_full_range = !matches!(
+        config.pixel_format,
+        Pixel::NV12
+            | Pixel::YUV420P
+            | Pixel::YUV422P
+            | Pixel::YUYV422
+            | Pixel::UYVY422
+            | Pixel::P010LE
+            | Pixel::YUV420P10LE
+            | Pixel::YUV422P10LE
+    );
+
+    // swscale defaults to BT.601, which would shift colours against the tags
+    unsafe {
+        use ffmpeg::ffi::{
+            sws_getCoefficients, sws_setColorspaceDetails, SWS_CS_BT2020, SWS_CS_ITU709,
+        };
+
+        let matrix = if space == color::Space::BT2020NCL {
+            SWS_CS_BT2020
+        } else {
+            SWS_CS_ITU709
+        };
+        let coefficients = sws_getCoefficients(matrix as i32);
+        sws_setColorspaceDetails(
+            converter.as_mut_ptr(),
+            coefficients,
+            source_full_range as i32,
+            coefficients,
+            0,
+            0,
+            1 << 16,
+            1 << 16,
+        );
+    }
+
+    Ok(converter)
+}
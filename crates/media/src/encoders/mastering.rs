@@ -0,0 +1,312 @@
+use std::path::PathBuf;
+
+use ffmpeg::{
+    codec::{self, context, encoder},
+    color,
+    format::{self, Pixel},
+    threading::Config,
+    Dictionary,
+};
+
+use super::{hevc::create_converter, PcmEncoder};
+use crate::{
+    data::{AudioInfo, FFAudio, FFPacket, FFVideo, VideoInfo},
+    MediaError,
+};
+
+// Intra-frame codecs for taking exports into editing tools, which decode them far more easily
+// than long-GOP H264, and which can carry alpha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MasteringProfile {
+    // ProRes 422 HQ
+    ProRes422,
+    ProRes4444,
+    // lossless
+    Ffv1,
+    // lossless, larger than FFV1 but quicker to decode
+    UtVideo,
+}
+
+impl MasteringProfile {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::ProRes422 | Self::ProRes4444 => "mov",
+            Self::Ffv1 | Self::UtVideo => "mkv",
+        }
+    }
+
+    pub fn supports_alpha(&self) -> bool {
+        !matches!(self, Self::ProRes422)
+    }
+
+    // In order of preference
+    pub fn encoder_names(&self) -> &'static [&'static str] {
+        match self {
+            Self::ProRes422 | Self::ProRes4444 if cfg!(target_os = "macos") => {
+                &["prores_videotoolbox", "prores_ks"]
+            }
+            Self::ProRes422 | Self::ProRes4444 => &["prores_ks"],
+            Self::Ffv1 => &["ffv1"],
+            Self::UtVideo => &["utvideo"],
+        }
+    }
+
+    // The lossless codecs stay in RGB, since converting to YUV would lose precision
+    fn formats(&self, alpha: bool) -> &'static [Pixel] {
+        match (self, alpha) {
+            (Self::ProRes422, _) => &[Pixel::YUV422P10LE],
+            (Self::ProRes4444, true) => &[Pixel::YUVA444P10LE],
+            (Self::ProRes4444, false) => &[Pixel::YUV444P10LE],
+            (Self::Ffv1 | Self::UtVideo, true) => &[Pixel::GBRAP],
+            (Self::Ffv1 | Self::UtVideo, false) => &[Pixel::GBRP],
+        }
+    }
+}
+
+pub struct MasteringEncoder {
+    tag: &'static str,
+    encoder: encoder::Video,
+    config: VideoInfo,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    stream_index: usize,
+    packet: ffmpeg::Packet,
+}
+
+impl MasteringEncoder {
+    // `alpha` keeps the input's alpha channel, where the profile can carry one
+    pub fn init(
+        tag: &'static str,
+        config: VideoInfo,
+        profile: MasteringProfile,
+        alpha: bool,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        let alpha = alpha && profile.supports_alpha();
+
+        for name in profile.encoder_names() {
+            let Some(codec) = encoder::find_by_name(name) else {
+                continue;
+            };
+
+            let supported = codec
+                .video()
+                .ok()
+                .and_then(|v| v.formats())
+                .map(|f| f.collect::<Vec<_>>())
+                .unwrap_or_default();
+            let Some(format) = profile
+                .formats(alpha)
+                .iter()
+                .copied()
+                .find(|f| supported.contains(f))
+            else {
+                continue;
+            };
+
+            let video_encoder = match open_encoder(name, codec, &config, format, profile) {
+                Ok(encoder) => encoder,
+                Err(e) => {
+                    tracing::warn!("{tag}: Failed to open {name}: {e}");
+                    continue;
+                }
+            };
+
+            tracing::info!("{tag}: Encoding {profile:?} with {name} as {format:?}");
+
+            let converter = if format != config.pixel_format {
+                Some(create_converter(&config, format)?)
+            } else {
+                None
+            };
+
+            let mut output_stream = output.add_stream(codec)?;
+            let stream_index = output_stream.index();
+            output_stream.set_time_base(config.frame_rate.invert());
+            output_stream.set_rate(config.frame_rate);
+            output_stream.set_parameters(&video_encoder);
+
+            return Ok(Self {
+                tag,
+                encoder: video_encoder,
+                stream_index,
+                config,
+                converter,
+                packet: FFPacket::empty(),
+            });
+        }
+
+        Err(MediaError::MissingCodec("mastering video"))
+    }
+
+    pub fn queue_frame(&mut self, frame: FFVideo, output: &mut format::context::Output) {
+        let frame = if let Some(converter) = &mut self.converter {
+            let mut new_frame = FFVideo::empty();
+            match converter.run(&frame, &mut new_frame) {
+                Ok(_) => {
+                    new_frame.set_pts(frame.pts());
+                    new_frame
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "{}: Failed to convert frame from format {:?}: {:?}",
+                        self.tag,
+                        frame.format(),
+                        e
+                    );
+                    return;
+                }
+            }
+        } else {
+            frame
+        };
+
+        if let Err(e) = self.encoder.send_frame(&frame) {
+            tracing::error!("{}: Failed to send frame to encoder: {:?}", self.tag, e);
+            return;
+        }
+
+        self.process_frame(output);
+    }
+
+    fn process_frame(&mut self, output: &mut format::context::Output) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.config.time_base,
+                output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(output) {
+                tracing::error!("{}: Failed to write packet: {:?}", self.tag, e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, output: &mut format::context::Output) {
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("{}: Failed to send EOF to encoder: {:?}", self.tag, e);
+            return;
+        }
+        self.process_frame(output);
+    }
+}
+
+fn open_encoder(
+    name: &str,
+    codec: codec::Codec,
+    config: &VideoInfo,
+    format: Pixel,
+    profile: MasteringProfile,
+) -> Result<encoder::Video, ffmpeg::Error> {
+    let mut encoder_ctx = context::Context::new_with_codec(codec);
+    encoder_ctx.set_threading(Config::count(4));
+    let mut encoder = encoder_ctx.encoder().video()?;
+
+    encoder.set_width(config.width);
+    encoder.set_height(config.height);
+    encoder.set_format(format);
+    encoder.set_time_base(config.frame_rate.invert());
+    encoder.set_frame_rate(Some(config.frame_rate));
+
+    match profile {
+        MasteringProfile::ProRes422 | MasteringProfile::ProRes4444 => {
+            encoder.set_colorspace(color::Space::BT709);
+            encoder.set_color_range(color::Range::MPEG);
+        }
+        MasteringProfile::Ffv1 | MasteringProfile::UtVideo => {
+            encoder.set_colorspace(color::Space::RGB);
+            encoder.set_color_range(color::Range::JPEG);
+        }
+    }
+    unsafe {
+        let ctx = encoder.as_mut_ptr();
+        (*ctx).color_primaries = ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT709;
+        (*ctx).color_trc = ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+    }
+
+    let mut options = Dictionary::new();
+
+    match profile {
+        MasteringProfile::ProRes422 => options.set("profile", "hq"),
+        MasteringProfile::ProRes4444 => options.set("profile", "4444"),
+        MasteringProfile::Ffv1 => {
+            // version 3 with per-slice checksums, the usual choice for archiving
+            options.set("level", "3");
+            options.set("slicecrc", "1");
+            options.set("g", "1");
+        }
+        MasteringProfile::UtVideo => options.set("pred", "median"),
+    }
+
+    if name == "prores_ks" && profile == MasteringProfile::ProRes4444 {
+        // without this prores_ks compresses the alpha channel lossily at 8 bits
+        options.set("alpha_bits", "16");
+    }
+
+    encoder.open_with(options)
+}
+
+// Exports in a mastering profile, in the container that suits it
+pub struct MasteringFile {
+    tag: &'static str,
+    output: format::context::Output,
+    video: MasteringEncoder,
+    audio: Option<PcmEncoder>,
+}
+
+impl MasteringFile {
+    pub fn init(
+        tag: &'static str,
+        mut output: PathBuf,
+        profile: MasteringProfile,
+        video: impl FnOnce(&mut format::context::Output) -> Result<MasteringEncoder, MediaError>,
+        audio: Option<AudioInfo>,
+    ) -> Result<Self, MediaError> {
+        output.set_extension(profile.extension());
+        let mut output = format::output(&output)?;
+
+        let video = video(&mut output)?;
+        let audio = audio
+            .map(|info| PcmEncoder::init("mastering_audio", info, &mut output))
+            .transpose()?;
+
+        // make sure this happens after adding all encoders!
+        output.write_header()?;
+
+        Ok(Self {
+            tag,
+            output,
+            video,
+            audio,
+        })
+    }
+
+    pub fn queue_video_frame(&mut self, frame: FFVideo) {
+        self.video.queue_frame(frame, &mut self.output);
+    }
+
+    pub fn queue_audio_frame(&mut self, frame: FFAudio) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        audio.queue_frame(frame, &mut self.output);
+    }
+
+    pub fn finish(&mut self) {
+        tracing::info!("{}: Finishing encoding", self.tag);
+
+        self.video.finish(&mut self.output);
+
+        if let Some(audio) = &mut self.audio {
+            audio.finish(&mut self.output);
+        }
+
+        if let Err(e) = self.output.write_trailer() {
+            tracing::error!("{}: Failed to write trailer: {:?}", self.tag, e);
+        }
+    }
+}
+
+unsafe impl Send for MasteringEncoder {}
+unsafe impl Send for PcmEncoder {}
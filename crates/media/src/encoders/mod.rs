@@ -1,13 +1,38 @@
 use std::path::PathBuf;
 
+mod aac;
+mod animated_gif;
+mod drift;
 mod h264;
 #[cfg(target_os = "macos")]
 mod h264_avassetwriter;
+mod hevc;
+mod mastering;
 mod mp4;
+mod mp4_repair;
 mod opus;
+mod pcm;
+mod pixel_format;
+mod rate_control;
+mod rtmp;
+mod static_frames;
+mod webm;
+mod webp;
 
+pub use aac::*;
+pub use animated_gif::*;
+pub use drift::*;
 pub use h264::*;
 #[cfg(target_os = "macos")]
 pub use h264_avassetwriter::*;
+pub use hevc::*;
+pub use mastering::*;
 pub use mp4::*;
+pub use mp4_repair::*;
 pub use opus::*;
+pub use pcm::*;
+pub use rate_control::{Pass, RateControl};
+pub use rtmp::*;
+pub use static_frames::*;
+pub use webm::*;
+pub use webp::*;
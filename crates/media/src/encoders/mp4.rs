@@ -3,48 +3,131 @@ use crate::{
     pipeline::task::PipelineSinkTask,
     MediaError,
 };
+use cap_project::Chapter;
 use ffmpeg::format::{self};
 use std::path::PathBuf;
 
-use super::{H264Encoder, OpusEncoder};
+use super::{H264Encoder, HevcEncoder, OpusEncoder, StaticFrames};
 
 pub struct MP4File {
     tag: &'static str,
     output: format::context::Output,
-    video: H264Encoder,
+    video: MP4Video,
     audio: Option<OpusEncoder>,
+    static_frames: Option<StaticFrames>,
+}
+
+pub enum MP4Video {
+    H264(H264Encoder),
+    Hevc(HevcEncoder),
+}
+
+impl From<H264Encoder> for MP4Video {
+    fn from(encoder: H264Encoder) -> Self {
+        Self::H264(encoder)
+    }
+}
+
+impl From<HevcEncoder> for MP4Video {
+    fn from(encoder: HevcEncoder) -> Self {
+        Self::Hevc(encoder)
+    }
 }
 
 impl MP4File {
-    pub fn init(
+    pub fn init<V: Into<MP4Video>>(
+        tag: &'static str,
+        output: PathBuf,
+        video: impl FnOnce(&mut format::context::Output) -> Result<V, MediaError>,
+        audio: impl FnOnce(&mut format::context::Output) -> Option<Result<OpusEncoder, MediaError>>,
+    ) -> Result<Self, MediaError> {
+        Self::init_with_chapters(tag, output, &[], video, audio)
+    }
+
+    // Like `init`, with `chapters` written into the file for players to skip between
+    pub fn init_with_chapters<V: Into<MP4Video>>(
+        tag: &'static str,
+        output: PathBuf,
+        chapters: &[Chapter],
+        video: impl FnOnce(&mut format::context::Output) -> Result<V, MediaError>,
+        audio: impl FnOnce(&mut format::context::Output) -> Option<Result<OpusEncoder, MediaError>>,
+    ) -> Result<Self, MediaError> {
+        Self::open(tag, output, chapters, false, video, audio)
+    }
+
+    // Like `init`, but written as a series of fragments that stay playable if the file is
+    // never finished, so a crash mid-recording can be recovered with `repair_mp4`
+    pub fn init_fragmented<V: Into<MP4Video>>(
+        tag: &'static str,
+        output: PathBuf,
+        video: impl FnOnce(&mut format::context::Output) -> Result<V, MediaError>,
+        audio: impl FnOnce(&mut format::context::Output) -> Option<Result<OpusEncoder, MediaError>>,
+    ) -> Result<Self, MediaError> {
+        Self::open(tag, output, &[], true, video, audio)
+    }
+
+    fn open<V: Into<MP4Video>>(
         tag: &'static str,
         mut output: PathBuf,
-        video: impl FnOnce(&mut format::context::Output) -> Result<H264Encoder, MediaError>,
+        chapters: &[Chapter],
+        fragmented: bool,
+        video: impl FnOnce(&mut format::context::Output) -> Result<V, MediaError>,
         audio: impl FnOnce(&mut format::context::Output) -> Option<Result<OpusEncoder, MediaError>>,
     ) -> Result<Self, MediaError> {
         output.set_extension("mp4");
         let mut output = format::output(&output)?;
 
-        let video = video(&mut output)?;
+        let video = video(&mut output)?.into();
         let audio = audio(&mut output).transpose()?;
+        add_chapters(&mut output, chapters)?;
 
         // make sure this happens after adding all encoders!
-        output.write_header()?;
+        if fragmented {
+            // a fragment at least every second, so a crash loses about that much
+            let mut options = ffmpeg::Dictionary::new();
+            options.set("movflags", "frag_keyframe+empty_moov+default_base_moof");
+            options.set("frag_duration", "1000000");
+            output.write_header_with(options)?;
+        } else {
+            output.write_header()?;
+        }
 
         Ok(Self {
             tag,
             output,
             video,
             audio,
+            static_frames: None,
         })
     }
 
+    // Leaves out frames that repeat the one before, making a variable frame rate file
+    pub fn with_static_frame_skipping(mut self, enabled: bool) -> Self {
+        self.static_frames = enabled.then(StaticFrames::default);
+        self
+    }
+
     pub fn video_format() -> RawVideoFormat {
         RawVideoFormat::YUYV420
     }
 
     pub fn queue_video_frame(&mut self, frame: FFVideo) {
-        self.video.queue_frame(frame, &mut self.output);
+        let frame = match &mut self.static_frames {
+            Some(static_frames) => match static_frames.filter(frame) {
+                Some(frame) => frame,
+                None => return,
+            },
+            None => frame,
+        };
+
+        self.encode_video_frame(frame);
+    }
+
+    fn encode_video_frame(&mut self, frame: FFVideo) {
+        match &mut self.video {
+            MP4Video::H264(video) => video.queue_frame(frame, &mut self.output),
+            MP4Video::Hevc(video) => video.queue_frame(frame, &mut self.output),
+        }
     }
 
     pub fn queue_audio_frame(&mut self, frame: FFAudio) {
@@ -58,7 +141,20 @@ impl MP4File {
     pub fn finish(&mut self) {
         tracing::info!("MP4Encoder: Finishing encoding");
 
-        self.video.finish(&mut self.output);
+        if let Some(mut static_frames) = self.static_frames.take() {
+            tracing::info!(
+                "MP4Encoder: Skipped {} static frames",
+                static_frames.skipped()
+            );
+            if let Some(frame) = static_frames.finish() {
+                self.encode_video_frame(frame);
+            }
+        }
+
+        match &mut self.video {
+            MP4Video::H264(video) => video.finish(&mut self.output),
+            MP4Video::Hevc(video) => video.finish(&mut self.output),
+        }
 
         if let Some(audio) = &mut self.audio {
             tracing::info!("MP4Encoder: Flushing audio encoder");
@@ -72,12 +168,31 @@ impl MP4File {
     }
 }
 
+// Chapters are part of the header, so they have to be added before it's written
+pub fn add_chapters(
+    output: &mut format::context::Output,
+    chapters: &[Chapter],
+) -> Result<(), MediaError> {
+    for (i, chapter) in chapters.iter().enumerate() {
+        output.add_chapter(
+            i as i64,
+            ffmpeg::Rational::new(1, 1000),
+            (chapter.start * 1000.0).round() as i64,
+            (chapter.end * 1000.0).round() as i64,
+            &chapter.title,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub struct MP4Input {
     pub video: FFVideo,
     pub audio: Option<FFAudio>,
 }
 
 unsafe impl Send for H264Encoder {}
+unsafe impl Send for HevcEncoder {}
 
 impl PipelineSinkTask<MP4Input> for MP4File {
     fn run(
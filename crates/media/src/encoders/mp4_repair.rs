@@ -0,0 +1,123 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::MediaError;
+
+// How far an MP4 file got before whatever was writing it stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mp4State {
+    Complete,
+    // written as fragments and cut off before its trailer, `repair_mp4` keeps what's there
+    Interrupted,
+    // cut off before its index was written, so there's nothing to find the frames with
+    MissingIndex,
+}
+
+// Walks the file's top level boxes, without reading what's in them
+pub fn mp4_state(path: &Path) -> io::Result<Mp4State> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut offset = 0;
+    let (mut moov, mut moof, mut mfra, mut truncated) = (false, false, false, false);
+
+    while offset < len {
+        if offset + 8 > len {
+            truncated = true;
+            break;
+        }
+
+        let mut header = [0; 16];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut header[..8])?;
+
+        let mut size = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        if size == 1 {
+            if offset + 16 > len {
+                truncated = true;
+                break;
+            }
+            file.read_exact(&mut header[8..])?;
+            size = u64::from_be_bytes(header[8..].try_into().unwrap());
+        } else if size == 0 {
+            // the box runs to the end of the file
+            size = len - offset;
+        }
+
+        if size < 8 || offset + size > len {
+            truncated = true;
+            break;
+        }
+
+        match &header[4..8] {
+            b"moov" => moov = true,
+            b"moof" => moof = true,
+            b"mfra" => mfra = true,
+            _ => {}
+        }
+
+        offset += size;
+    }
+
+    Ok(match (moov, moof) {
+        (false, _) => Mp4State::MissingIndex,
+        (true, true) if truncated || !mfra => Mp4State::Interrupted,
+        (true, false) if truncated => Mp4State::Interrupted,
+        _ => Mp4State::Complete,
+    })
+}
+
+// Copies the file's packets into a regular MP4 file in its place, dropping whatever was
+// cut off partway through
+pub fn repair_mp4(path: &Path) -> Result<(), MediaError> {
+    let repaired = path.with_extension("repaired.mp4");
+
+    if let Err(e) = remux(path, &repaired) {
+        std::fs::remove_file(&repaired).ok();
+        return Err(e);
+    }
+
+    std::fs::rename(&repaired, path)?;
+
+    Ok(())
+}
+
+fn remux(input_path: &Path, output_path: &Path) -> Result<(), MediaError> {
+    let mut input = ffmpeg::format::input(&input_path)?;
+    let mut output = ffmpeg::format::output(&output_path)?;
+
+    for stream in input.streams() {
+        let mut output_stream =
+            output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+        output_stream.set_parameters(stream.parameters());
+        // let the muxer pick the tag for its own container
+        unsafe {
+            (*output_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+    }
+
+    output.write_header()?;
+
+    let mut packets = 0;
+    for (stream, mut packet) in input.packets() {
+        let index = stream.index();
+        let time_base = output.stream(index).unwrap().time_base();
+
+        packet.rescale_ts(stream.time_base(), time_base);
+        packet.set_position(-1);
+        packet.set_stream(index);
+        packet.write_interleaved(&mut output)?;
+        packets += 1;
+    }
+
+    if packets == 0 {
+        return Err(MediaError::MissingMedia("video"));
+    }
+
+    output.write_trailer()?;
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ use ffmpeg::{
     threading::Config,
 };
 
+use super::{AudioDriftCorrector, DriftCorrection};
 use crate::{
     data::{AudioInfo, FFAudio, FFRational},
     pipeline::task::PipelineSinkTask,
@@ -34,6 +35,12 @@ impl OggFile {
         Ok(Self { encoder, output })
     }
 
+    // For audio captured live, whose frames are timestamped in microseconds
+    pub fn with_drift_correction(mut self) -> Self {
+        self.encoder.correct_drift();
+        self
+    }
+
     pub fn queue_frame(&mut self, frame: FFAudio) {
         self.encoder.queue_frame(frame, &mut self.output);
     }
@@ -52,10 +59,13 @@ pub struct OpusEncoder {
     resampled_frame: FFAudio,
     buffer: VecDeque<u8>,
     stream_index: usize,
+    input_rate: u32,
+    drift: Option<AudioDriftCorrector>,
 }
 
 impl OpusEncoder {
-    const OUTPUT_BITRATE: usize = 128 * 1000; // 128k
+    pub const OUTPUT_BITRATE: usize = 128 * 1000; // 128k
+    pub const ENCODER_NAME: &'static str = "libopus";
     const SAMPLE_FORMAT: Sample = Sample::F32(Type::Packed);
 
     pub fn factory(
@@ -70,7 +80,7 @@ impl OpusEncoder {
         input_config: AudioInfo,
         output: &mut format::context::Output,
     ) -> Result<Self, MediaError> {
-        let codec = encoder::find_by_name("libopus")
+        let codec = encoder::find_by_name(Self::ENCODER_NAME)
             .ok_or(MediaError::TaskLaunch("Could not find Opus codec".into()))?;
         let mut encoder_ctx = context::Context::new_with_codec(codec);
         encoder_ctx.set_threading(Config::count(4));
@@ -138,10 +148,56 @@ impl OpusEncoder {
             packet: ffmpeg::Packet::empty(),
             resampled_frame: FFAudio::empty(),
             resampler,
+            input_rate: input_config.sample_rate,
+            drift: None,
         })
     }
 
+    // Keeps the encoded audio in step with the frames' timestamps rather than trusting the
+    // device to deliver exactly its nominal sample rate. Has to be called before any frames
+    // are queued, since it switches the resampler into a mode that can stretch audio.
+    pub fn correct_drift(&mut self) {
+        unsafe {
+            ffmpeg_sys_next::swr_set_compensation(self.resampler.as_mut_ptr(), 0, 0);
+        }
+        self.drift = Some(AudioDriftCorrector::new(self.input_rate));
+    }
+
+    // input samples to the matching number of bytes in the buffer
+    fn buffer_bytes(&self, input_samples: usize) -> usize {
+        let samples = input_samples as u64 * self.encoder.rate() as u64 / self.input_rate as u64;
+
+        samples as usize * self.encoder.channels() as usize * self.encoder.format().bytes()
+    }
+
     pub fn queue_frame(&mut self, frame: FFAudio, output: &mut format::context::Output) {
+        let correction = match (&mut self.drift, frame.pts()) {
+            (Some(drift), Some(pts)) => drift.process(pts, frame.samples()),
+            _ => DriftCorrection::None,
+        };
+
+        match correction {
+            DriftCorrection::Insert(samples) => {
+                tracing::warn!("{}: inserting {samples} samples to catch up", self.tag);
+                let bytes = self.buffer_bytes(samples);
+                self.buffer.extend(std::iter::repeat(0).take(bytes));
+            }
+            DriftCorrection::Resample { delta, over } => {
+                let to_output = |samples: i32| {
+                    (samples as i64 * self.encoder.rate() as i64 / self.input_rate as i64) as i32
+                };
+
+                unsafe {
+                    ffmpeg_sys_next::swr_set_compensation(
+                        self.resampler.as_mut_ptr(),
+                        to_output(delta),
+                        to_output(over),
+                    );
+                }
+            }
+            _ => {}
+        }
+
         self.resampler
             .run(&frame, &mut self.resampled_frame)
             .unwrap();
@@ -152,6 +208,12 @@ impl OpusEncoder {
                 * self.resampled_frame.format().bytes()],
         );
 
+        if let DriftCorrection::Skip(samples) = correction {
+            tracing::warn!("{}: dropping {samples} samples to fall back", self.tag);
+            let bytes = self.buffer_bytes(samples).min(self.buffer.len());
+            self.buffer.truncate(self.buffer.len() - bytes);
+        }
+
         loop {
             let frame_size_bytes = self.encoder.frame_size() as usize
                 * self.encoder.channels() as usize
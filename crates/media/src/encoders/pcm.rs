@@ -0,0 +1,111 @@
+use ffmpeg::{
+    codec::{context, encoder},
+    format::{self, sample::Type, Sample},
+};
+
+use crate::{
+    data::{AudioInfo, FFAudio, FFRational},
+    MediaError,
+};
+
+// Uncompressed audio, for containers editing tools expect it in
+pub struct PcmEncoder {
+    tag: &'static str,
+    encoder: encoder::Audio,
+    packet: ffmpeg::Packet,
+    resampler: ffmpeg::software::resampling::Context,
+    resampled_frame: FFAudio,
+    stream_index: usize,
+    samples: i64,
+}
+
+impl PcmEncoder {
+    pub const ENCODER_NAME: &'static str = "pcm_s24le";
+    // 24 bit samples are carried in 32 bit ints
+    const SAMPLE_FORMAT: Sample = Sample::I32(Type::Packed);
+
+    pub fn init(
+        tag: &'static str,
+        input_config: AudioInfo,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        let codec = encoder::find_by_name(Self::ENCODER_NAME)
+            .ok_or(MediaError::MissingCodec("PCM audio"))?;
+        let mut encoder = context::Context::new_with_codec(codec).encoder().audio()?;
+
+        let resampler = ffmpeg::software::resampler(
+            (
+                input_config.sample_format,
+                input_config.channel_layout(),
+                input_config.sample_rate,
+            ),
+            (
+                Self::SAMPLE_FORMAT,
+                input_config.channel_layout(),
+                input_config.sample_rate,
+            ),
+        )?;
+
+        encoder.set_rate(input_config.rate());
+        encoder.set_format(Self::SAMPLE_FORMAT);
+        encoder.set_channel_layout(input_config.channel_layout());
+        encoder.set_time_base(FFRational(1, input_config.rate()));
+
+        let encoder = encoder.open()?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        let stream_index = output_stream.index();
+        output_stream.set_time_base(FFRational(1, input_config.rate()));
+        output_stream.set_parameters(&encoder);
+
+        Ok(Self {
+            tag,
+            encoder,
+            packet: ffmpeg::Packet::empty(),
+            resampler,
+            resampled_frame: FFAudio::empty(),
+            stream_index,
+            samples: 0,
+        })
+    }
+
+    pub fn queue_frame(&mut self, frame: FFAudio, output: &mut format::context::Output) {
+        if let Err(e) = self.resampler.run(&frame, &mut self.resampled_frame) {
+            tracing::error!("{}: Failed to convert audio: {:?}", self.tag, e);
+            return;
+        }
+
+        // PCM has no frame size, so frames go straight through, timed by the samples so far
+        self.resampled_frame.set_pts(Some(self.samples));
+        self.samples += self.resampled_frame.samples() as i64;
+
+        if let Err(e) = self.encoder.send_frame(&self.resampled_frame) {
+            tracing::error!("{}: Failed to send audio to encoder: {:?}", self.tag, e);
+            return;
+        }
+
+        self.process_packets(output);
+    }
+
+    fn process_packets(&mut self, output: &mut format::context::Output) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.encoder.time_base(),
+                output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(output) {
+                tracing::error!("{}: Failed to write packet: {:?}", self.tag, e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, output: &mut format::context::Output) {
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("{}: Failed to send EOF to encoder: {:?}", self.tag, e);
+            return;
+        }
+        self.process_packets(output);
+    }
+}
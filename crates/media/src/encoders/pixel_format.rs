@@ -0,0 +1,50 @@
+use ffmpeg::{codec, format::Pixel};
+
+use crate::data::{is_chroma_422, is_high_bit_depth};
+
+// YUV formats to encode `input` as, in order of preference. 10 bit sources, such as HDR
+// captures, stay 10 bit so gradients don't band, keeping 4:2:2 chroma where they have it.
+// Everything else becomes 8 bit 4:2:0.
+pub(super) fn yuv_formats(input: Pixel) -> &'static [Pixel] {
+    match (is_high_bit_depth(input), is_chroma_422(input)) {
+        (true, true) => &[
+            Pixel::YUV422P10LE,
+            Pixel::P010LE,
+            Pixel::YUV420P10LE,
+            Pixel::NV12,
+            Pixel::YUV420P,
+        ],
+        (true, false) => &[
+            Pixel::P010LE,
+            Pixel::YUV420P10LE,
+            Pixel::NV12,
+            Pixel::YUV420P,
+        ],
+        _ => &[Pixel::NV12, Pixel::YUV420P],
+    }
+}
+
+// Keeps the input format when the encoder takes it, otherwise the first of `yuv_formats`
+// it does
+pub(super) fn output_format(codec: &codec::Codec, input: Pixel) -> Pixel {
+    let candidates = yuv_formats(input);
+
+    let Some(supported) = codec
+        .video()
+        .ok()
+        .and_then(|v| v.formats())
+        .map(|f| f.collect::<Vec<_>>())
+    else {
+        return candidates[0];
+    };
+
+    if supported.contains(&input) {
+        return input;
+    }
+
+    candidates
+        .iter()
+        .copied()
+        .find(|f| supported.contains(f))
+        .unwrap_or(Pixel::YUV420P)
+}
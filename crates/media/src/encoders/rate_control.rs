@@ -0,0 +1,123 @@
+use std::{
+    ffi::{CStr, CString},
+    path::{Path, PathBuf},
+};
+
+use ffmpeg::{
+    codec::{self, encoder},
+    Dictionary,
+};
+
+// How the H264, HEVC and WebM encoders trade size against quality
+#[derive(Clone, Debug)]
+pub enum RateControl {
+    // average bits per second
+    BitRate(usize),
+    // 0 (smallest file) to 100 (best looking), leaving the bit rate to the encoder
+    Quality(u8),
+    // An average bit rate hit far more closely by encoding twice. The first pass only writes
+    // statistics to `stats`, which the second reads to spend bits where they're needed.
+    TwoPass {
+        bit_rate: usize,
+        pass: Pass,
+        stats: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pass {
+    First,
+    Second,
+}
+
+impl RateControl {
+    // Encoders that can read statistics from an earlier pass. Others are skipped for two-pass
+    // encoding, since a plain average bit rate would miss the target size.
+    pub fn supports_two_pass(encoder_name: &str) -> bool {
+        matches!(
+            encoder_name,
+            "libx264" | "libx265" | "libvpx-vp9" | "libaom-av1"
+        )
+    }
+
+    pub fn is_two_pass(&self) -> bool {
+        matches!(self, Self::TwoPass { .. })
+    }
+
+    // Sets up `encoder` for one pass of a two-pass encode, before it's opened
+    pub(super) fn configure_pass(
+        name: &str,
+        encoder: &mut encoder::video::Video,
+        options: &mut Dictionary,
+        bit_rate: usize,
+        pass: Pass,
+        stats: &Path,
+    ) -> Result<(), ffmpeg::Error> {
+        encoder.set_bit_rate(bit_rate);
+
+        if name == "libx265" {
+            let pass = match pass {
+                Pass::First => 1,
+                Pass::Second => 2,
+            };
+            options.set(
+                "x265-params",
+                &format!("pass={pass}:stats={}", stats.display()),
+            );
+            return Ok(());
+        }
+
+        encoder.set_flags(match pass {
+            Pass::First => codec::Flags::PASS1,
+            Pass::Second => codec::Flags::PASS2,
+        });
+
+        if name == "libx264" {
+            options.set("stats", &stats.to_string_lossy());
+        } else if pass == Pass::Second {
+            // libvpx and libaom take the first pass's statistics in memory
+            let stats = std::fs::read_to_string(stats)
+                .ok()
+                .and_then(|s| CString::new(s).ok())
+                .ok_or(ffmpeg::Error::InvalidData)?;
+            unsafe {
+                (*encoder.as_mut_ptr()).stats_in = ffmpeg::ffi::av_strdup(stats.as_ptr());
+            }
+        }
+
+        Ok(())
+    }
+
+    // Saves the statistics of an in-memory first pass, once `encoder` has been flushed
+    pub(super) fn finish_pass(&self, name: &str, encoder: &encoder::Video) {
+        let Self::TwoPass {
+            pass: Pass::First,
+            stats,
+            ..
+        } = self
+        else {
+            return;
+        };
+
+        if !matches!(name, "libvpx-vp9" | "libaom-av1") {
+            return;
+        }
+
+        let stats_out = unsafe { (*encoder.as_ptr()).stats_out };
+        if stats_out.is_null() {
+            tracing::warn!("{name}: First pass produced no statistics");
+            return;
+        }
+
+        let stats_out = unsafe { CStr::from_ptr(stats_out) };
+        if let Err(e) = std::fs::write(stats, stats_out.to_bytes()) {
+            tracing::error!("{name}: Failed to save first pass statistics: {e}");
+        }
+    }
+}
+
+// The 0-51 scale x264, x265 and NVENC use, where lower is better. The ends of the scale are
+// either lossless or unwatchable, so quality spans 40 down to 18.
+pub(super) fn quality_to_qp(quality: u8) -> u32 {
+    40 - quality.min(100) as u32 * 22 / 100
+}
@@ -0,0 +1,94 @@
+use ffmpeg::format::{self};
+
+use crate::{
+    data::{AudioInfo, FFAudio, FFVideo, VideoInfo},
+    MediaError,
+};
+
+use super::{AacEncoder, H264Encoder, RateControl};
+
+// H264 and AAC muxed as FLV and published to an RTMP ingest, such as YouTube's or Twitch's.
+// Writes go straight to the network, so a dropped connection shows up as `connection_error`.
+pub struct RtmpStream {
+    tag: &'static str,
+    output: format::context::Output,
+    video: H264Encoder,
+    audio: Option<AacEncoder>,
+    // each connection starts its timestamps at zero, whatever frame it starts on
+    first_pts: Option<i64>,
+}
+
+impl RtmpStream {
+    pub fn init(
+        tag: &'static str,
+        url: &str,
+        video: VideoInfo,
+        rate_control: RateControl,
+        audio: Option<AudioInfo>,
+    ) -> Result<Self, MediaError> {
+        format::network::init();
+
+        let mut output = format::output_as(url, "flv")?;
+
+        let video =
+            H264Encoder::init_with_rate_control("rtmp_video", video, rate_control, &mut output)?;
+        let audio = audio
+            .map(|info| AacEncoder::init("rtmp_audio", info, &mut output))
+            .transpose()?;
+
+        // make sure this happens after adding all encoders!
+        output.write_header()?;
+
+        Ok(Self {
+            tag,
+            output,
+            video,
+            audio,
+            first_pts: None,
+        })
+    }
+
+    pub fn queue_video_frame(&mut self, mut frame: FFVideo) {
+        if let Some(pts) = frame.pts() {
+            let first_pts = *self.first_pts.get_or_insert(pts);
+            frame.set_pts(Some(pts - first_pts));
+        }
+
+        self.video.queue_frame(frame, &mut self.output);
+    }
+
+    pub fn queue_audio_frame(&mut self, frame: FFAudio) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        audio.queue_frame(frame, &mut self.output);
+    }
+
+    // The error the connection failed with, after which nothing more gets through
+    pub fn connection_error(&self) -> Option<ffmpeg::Error> {
+        let error = unsafe {
+            let pb = (*self.output.as_ptr()).pb;
+            if pb.is_null() {
+                return None;
+            }
+            (*pb).error
+        };
+
+        (error < 0).then(|| ffmpeg::Error::from(error))
+    }
+
+    pub fn finish(&mut self) {
+        tracing::info!("{}: Finishing stream", self.tag);
+
+        self.video.finish(&mut self.output);
+
+        if let Some(audio) = &mut self.audio {
+            audio.finish(&mut self.output);
+        }
+
+        if let Err(e) = self.output.write_trailer() {
+            tracing::error!("{}: Failed to write FLV trailer: {:?}", self.tag, e);
+        }
+    }
+}
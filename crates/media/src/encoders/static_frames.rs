@@ -0,0 +1,59 @@
+use crate::data::FFVideo;
+
+// Spots frames identical to the one before so they can be left out of the encode. Screen
+// recordings are mostly still, and MP4 and WebM keep a frame up until the next one's
+// timestamp, so skipping repeats costs nothing on screen. Encoders count their keyframe
+// interval in frames they're given, so skipping also stretches GOPs over still stretches.
+#[derive(Default)]
+pub struct StaticFrames {
+    previous: Vec<u8>,
+    // the latest repeat, encoded at the end so the video doesn't finish early
+    held: Option<FFVideo>,
+    skipped: u64,
+}
+
+impl StaticFrames {
+    // The frame if anything changed, or None when it repeats the last one
+    pub fn filter(&mut self, frame: FFVideo) -> Option<FFVideo> {
+        if self.is_repeat(&frame) {
+            self.skipped += 1;
+            self.held = Some(frame);
+            return None;
+        }
+
+        self.held = None;
+        Some(frame)
+    }
+
+    // The last frame, when it was skipped
+    pub fn finish(&mut self) -> Option<FFVideo> {
+        self.held.take()
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    fn is_repeat(&mut self, frame: &FFVideo) -> bool {
+        let planes = 0..frame.planes();
+        let len = planes.clone().map(|i| frame.data(i).len()).sum::<usize>();
+
+        let mut offset = 0;
+        let repeat = self.previous.len() == len
+            && planes.clone().all(|i| {
+                let data = frame.data(i);
+                let same = self.previous[offset..offset + data.len()] == *data;
+                offset += data.len();
+                same
+            });
+
+        if !repeat {
+            self.previous.clear();
+            for i in planes {
+                self.previous.extend_from_slice(frame.data(i));
+            }
+        }
+
+        repeat
+    }
+}
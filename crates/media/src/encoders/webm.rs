@@ -0,0 +1,399 @@
+use std::path::PathBuf;
+
+use ffmpeg::{
+    codec::{self, context, encoder},
+    color,
+    format::{self, Pixel},
+    threading::Config,
+    Dictionary,
+};
+
+use super::{hevc::create_converter, OpusEncoder, RateControl, StaticFrames};
+use crate::{
+    data::{is_chroma_422, is_high_bit_depth, FFAudio, FFPacket, FFVideo, VideoInfo},
+    MediaError,
+};
+
+// Codecs browsers play straight from a WebM
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WebMCodec {
+    Vp9,
+    Av1,
+}
+
+impl WebMCodec {
+    // Browsers only understand alpha in VP9
+    pub fn supports_alpha(&self) -> bool {
+        matches!(self, Self::Vp9)
+    }
+
+    pub fn default_bit_rate(&self) -> usize {
+        match self {
+            Self::Vp9 => 6_000_000,
+            Self::Av1 => 4_000_000,
+        }
+    }
+
+    // In order of preference. Hardware AV1 comes first since software AV1 is very slow.
+    pub fn encoder_names(&self) -> &'static [&'static str] {
+        match self {
+            Self::Vp9 => &["libvpx-vp9"],
+            Self::Av1 if cfg!(windows) => &[
+                "av1_nvenc",
+                "av1_qsv",
+                "av1_amf",
+                "libsvtav1",
+                "librav1e",
+                "libaom-av1",
+            ],
+            Self::Av1 => &[
+                "av1_nvenc",
+                "av1_qsv",
+                "libsvtav1",
+                "librav1e",
+                "libaom-av1",
+            ],
+        }
+    }
+
+    // 10 bit sources stay 10 bit, which VP9 carries as profile 2 or 3
+    fn formats(&self, alpha: bool, input: Pixel) -> &'static [Pixel] {
+        match (alpha, is_high_bit_depth(input), is_chroma_422(input)) {
+            (true, _, _) => &[Pixel::YUVA420P],
+            (false, true, true) => &[
+                Pixel::YUV422P10LE,
+                Pixel::YUV420P10LE,
+                Pixel::P010LE,
+                Pixel::YUV420P,
+                Pixel::NV12,
+            ],
+            (false, true, false) => &[
+                Pixel::YUV420P10LE,
+                Pixel::P010LE,
+                Pixel::YUV420P,
+                Pixel::NV12,
+            ],
+            // hardware encoders only take NV12
+            (false, false, _) => &[Pixel::YUV420P, Pixel::NV12],
+        }
+    }
+}
+
+pub struct WebMEncoder {
+    tag: &'static str,
+    name: &'static str,
+    rate_control: RateControl,
+    encoder: encoder::Video,
+    config: VideoInfo,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    stream_index: usize,
+    packet: ffmpeg::Packet,
+}
+
+impl WebMEncoder {
+    // `alpha` keeps the input's alpha channel, where the codec can carry one
+    pub fn init(
+        tag: &'static str,
+        config: VideoInfo,
+        codec: WebMCodec,
+        rate_control: RateControl,
+        alpha: bool,
+        output: &mut format::context::Output,
+    ) -> Result<Self, MediaError> {
+        let alpha = alpha && codec.supports_alpha();
+
+        for name in codec.encoder_names() {
+            if rate_control.is_two_pass() && !RateControl::supports_two_pass(name) {
+                continue;
+            }
+
+            let Some(ff_codec) = encoder::find_by_name(name) else {
+                continue;
+            };
+
+            let supported = ff_codec
+                .video()
+                .ok()
+                .and_then(|v| v.formats())
+                .map(|f| f.collect::<Vec<_>>())
+                .unwrap_or_default();
+            let Some(format) = codec
+                .formats(alpha, config.pixel_format)
+                .iter()
+                .copied()
+                .find(|f| supported.contains(f))
+            else {
+                continue;
+            };
+
+            let video_encoder =
+                match open_encoder(name, ff_codec, &config, format, &rate_control, alpha) {
+                    Ok(encoder) => encoder,
+                    Err(e) => {
+                        tracing::warn!("{tag}: Failed to open {name}: {e}");
+                        continue;
+                    }
+                };
+
+            tracing::info!("{tag}: Encoding {codec:?} with {name} as {format:?}");
+
+            let converter = if format != config.pixel_format {
+                Some(create_converter(&config, format)?)
+            } else {
+                None
+            };
+
+            let mut output_stream = output.add_stream(ff_codec)?;
+            let stream_index = output_stream.index();
+            output_stream.set_time_base(config.frame_rate.invert());
+            output_stream.set_rate(config.frame_rate);
+            output_stream.set_parameters(&video_encoder);
+
+            return Ok(Self {
+                tag,
+                name,
+                rate_control,
+                encoder: video_encoder,
+                stream_index,
+                config,
+                converter,
+                packet: FFPacket::empty(),
+            });
+        }
+
+        Err(MediaError::MissingCodec("WebM video"))
+    }
+
+    pub fn queue_frame(&mut self, frame: FFVideo, output: &mut format::context::Output) {
+        let frame = if let Some(converter) = &mut self.converter {
+            let mut new_frame = FFVideo::empty();
+            match converter.run(&frame, &mut new_frame) {
+                Ok(_) => {
+                    new_frame.set_pts(frame.pts());
+                    new_frame
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "{}: Failed to convert frame from format {:?}: {:?}",
+                        self.tag,
+                        frame.format(),
+                        e
+                    );
+                    return;
+                }
+            }
+        } else {
+            frame
+        };
+
+        if let Err(e) = self.encoder.send_frame(&frame) {
+            tracing::error!("{}: Failed to send frame to encoder: {:?}", self.tag, e);
+            return;
+        }
+
+        self.process_frame(output);
+    }
+
+    fn process_frame(&mut self, output: &mut format::context::Output) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.config.time_base,
+                output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(output) {
+                tracing::error!("{}: Failed to write packet: {:?}", self.tag, e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, output: &mut format::context::Output) {
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("{}: Failed to send EOF to encoder: {:?}", self.tag, e);
+            return;
+        }
+        self.process_frame(output);
+        self.rate_control.finish_pass(self.name, &self.encoder);
+    }
+}
+
+fn open_encoder(
+    name: &str,
+    codec: codec::Codec,
+    config: &VideoInfo,
+    format: Pixel,
+    rate_control: &RateControl,
+    alpha: bool,
+) -> Result<encoder::Video, ffmpeg::Error> {
+    let mut encoder_ctx = context::Context::new_with_codec(codec);
+    encoder_ctx.set_threading(Config::count(4));
+    let mut encoder = encoder_ctx.encoder().video()?;
+
+    encoder.set_width(config.width);
+    encoder.set_height(config.height);
+    encoder.set_format(format);
+    encoder.set_time_base(config.frame_rate.invert());
+    encoder.set_frame_rate(Some(config.frame_rate));
+
+    encoder.set_colorspace(color::Space::BT709);
+    encoder.set_color_range(color::Range::MPEG);
+    unsafe {
+        let ctx = encoder.as_mut_ptr();
+        (*ctx).color_primaries = ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT709;
+        (*ctx).color_trc = ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+    }
+
+    let mut options = Dictionary::new();
+    let keyframe_interval = (2 * config.frame_rate.numerator()).to_string();
+    options.set("g", &keyframe_interval);
+
+    match rate_control {
+        RateControl::BitRate(bit_rate) => {
+            encoder.set_bit_rate(*bit_rate);
+            encoder.set_max_bit_rate(*bit_rate * 3 / 2);
+        }
+        RateControl::TwoPass {
+            bit_rate,
+            pass,
+            stats,
+        } => {
+            RateControl::configure_pass(name, &mut encoder, &mut options, *bit_rate, *pass, stats)?
+        }
+        RateControl::Quality(quality) => {
+            let quality = (*quality).min(100) as u32;
+            // the 0-63 scale libvpx and the AV1 encoders share, where lower is better,
+            // spanning 48 down to 15 for the same reason as HEVC's
+            let crf = 48 - quality * 33 / 100;
+
+            match name {
+                "librav1e" => options.set("qp", &(crf * 4).to_string()),
+                "av1_nvenc" => {
+                    options.set("rc", "vbr");
+                    options.set("cq", &crf.to_string());
+                }
+                "av1_qsv" => options.set("global_quality", &crf.to_string()),
+                "av1_amf" => {
+                    options.set("rc", "cqp");
+                    options.set("qp_i", &(crf * 4).to_string());
+                    options.set("qp_p", &(crf * 4).to_string());
+                }
+                _ => {
+                    // libvpx only goes by crf alone when the bit rate is zero
+                    encoder.set_bit_rate(0);
+                    options.set("crf", &crf.to_string());
+                }
+            }
+        }
+    }
+
+    match name {
+        "libvpx-vp9" => {
+            options.set("deadline", "good");
+            options.set("cpu-used", "2");
+            options.set("row-mt", "1");
+            if alpha {
+                // libvpx can't encode alpha with alternate reference frames
+                options.set("auto-alt-ref", "0");
+            }
+        }
+        "libaom-av1" => {
+            options.set("cpu-used", "6");
+            options.set("row-mt", "1");
+        }
+        "libsvtav1" => options.set("preset", "8"),
+        "librav1e" => options.set("speed", "6"),
+        "av1_nvenc" => options.set("preset", "p5"),
+        _ => {}
+    }
+
+    encoder.open_with(options)
+}
+
+// Exports for embedding on web pages, with Opus audio as WebM requires
+pub struct WebMFile {
+    tag: &'static str,
+    output: format::context::Output,
+    video: WebMEncoder,
+    audio: Option<OpusEncoder>,
+    static_frames: Option<StaticFrames>,
+}
+
+impl WebMFile {
+    pub fn init(
+        tag: &'static str,
+        mut output: PathBuf,
+        video: impl FnOnce(&mut format::context::Output) -> Result<WebMEncoder, MediaError>,
+        audio: impl FnOnce(&mut format::context::Output) -> Option<Result<OpusEncoder, MediaError>>,
+    ) -> Result<Self, MediaError> {
+        output.set_extension("webm");
+        let mut output = format::output(&output)?;
+
+        let video = video(&mut output)?;
+        let audio = audio(&mut output).transpose()?;
+
+        // make sure this happens after adding all encoders!
+        output.write_header()?;
+
+        Ok(Self {
+            tag,
+            output,
+            video,
+            audio,
+            static_frames: None,
+        })
+    }
+
+    // Leaves out frames that repeat the one before, making a variable frame rate file
+    pub fn with_static_frame_skipping(mut self, enabled: bool) -> Self {
+        self.static_frames = enabled.then(StaticFrames::default);
+        self
+    }
+
+    pub fn queue_video_frame(&mut self, frame: FFVideo) {
+        let frame = match &mut self.static_frames {
+            Some(static_frames) => match static_frames.filter(frame) {
+                Some(frame) => frame,
+                None => return,
+            },
+            None => frame,
+        };
+
+        self.video.queue_frame(frame, &mut self.output);
+    }
+
+    pub fn queue_audio_frame(&mut self, frame: FFAudio) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        audio.queue_frame(frame, &mut self.output);
+    }
+
+    pub fn finish(&mut self) {
+        tracing::info!("{}: Finishing encoding", self.tag);
+
+        if let Some(mut static_frames) = self.static_frames.take() {
+            tracing::info!(
+                "{}: Skipped {} static frames",
+                self.tag,
+                static_frames.skipped()
+            );
+            if let Some(frame) = static_frames.finish() {
+                self.video.queue_frame(frame, &mut self.output);
+            }
+        }
+
+        self.video.finish(&mut self.output);
+
+        if let Some(audio) = &mut self.audio {
+            audio.finish(&mut self.output);
+        }
+
+        if let Err(e) = self.output.write_trailer() {
+            tracing::error!("{}: Failed to write trailer: {:?}", self.tag, e);
+        }
+    }
+}
+
+unsafe impl Send for WebMEncoder {}
@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use ffmpeg::{
+    codec::{context, encoder},
+    format::{self, Pixel},
+    Dictionary,
+};
+
+use crate::{
+    data::{FFPacket, FFVideo, VideoInfo},
+    MediaError,
+};
+
+// Animated WebP through libwebp, which works out which parts of each frame changed itself
+pub struct WebPFile {
+    output: format::context::Output,
+    encoder: encoder::Video,
+    config: VideoInfo,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    stream_index: usize,
+    packet: ffmpeg::Packet,
+}
+
+impl WebPFile {
+    pub const ENCODER_NAME: &'static str = "libwebp_anim";
+
+    // `quality` is 0 to 100, with 100 being lossless
+    pub fn init(mut output: PathBuf, config: VideoInfo, quality: u8) -> Result<Self, MediaError> {
+        output.set_extension("webp");
+        let mut output = format::output_as(&output, "webp")?;
+
+        let codec = encoder::find_by_name(Self::ENCODER_NAME)
+            .ok_or(MediaError::MissingCodec("WebP video"))?;
+
+        let supported = codec
+            .video()
+            .ok()
+            .and_then(|v| v.formats())
+            .map(|f| f.collect::<Vec<_>>())
+            .unwrap_or_default();
+        // libwebp takes BGRA but not RGBA, which swscale swaps without touching the values
+        let format = [config.pixel_format, Pixel::BGRA, Pixel::YUVA420P]
+            .into_iter()
+            .find(|f| supported.contains(f))
+            .unwrap_or(Pixel::YUVA420P);
+
+        let converter = if format != config.pixel_format {
+            Some(
+                ffmpeg::software::converter(
+                    (config.width, config.height),
+                    config.pixel_format,
+                    format,
+                )
+                .map_err(|_| MediaError::Any("Failed to create frame converter"))?,
+            )
+        } else {
+            None
+        };
+
+        let mut encoder = context::Context::new_with_codec(codec).encoder().video()?;
+        encoder.set_width(config.width);
+        encoder.set_height(config.height);
+        encoder.set_format(format);
+        encoder.set_time_base(config.frame_rate.invert());
+        encoder.set_frame_rate(Some(config.frame_rate));
+
+        let mut options = Dictionary::new();
+        if quality >= 100 {
+            options.set("lossless", "1");
+        } else {
+            options.set("quality", &quality.to_string());
+        }
+
+        let encoder = encoder.open_with(options)?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        let stream_index = output_stream.index();
+        output_stream.set_time_base(config.frame_rate.invert());
+        output_stream.set_rate(config.frame_rate);
+        output_stream.set_parameters(&encoder);
+
+        // the muxer plays once unless told to loop forever
+        let mut muxer_options = Dictionary::new();
+        muxer_options.set("loop", "0");
+        output.write_header_with(muxer_options)?;
+
+        Ok(Self {
+            output,
+            encoder,
+            config,
+            converter,
+            stream_index,
+            packet: FFPacket::empty(),
+        })
+    }
+
+    pub fn queue_frame(&mut self, frame: FFVideo) {
+        let frame = if let Some(converter) = &mut self.converter {
+            let mut new_frame = FFVideo::empty();
+            if let Err(e) = converter.run(&frame, &mut new_frame) {
+                tracing::error!("WebP: Failed to convert frame: {:?}", e);
+                return;
+            }
+            new_frame.set_pts(frame.pts());
+            new_frame
+        } else {
+            frame
+        };
+
+        if let Err(e) = self.encoder.send_frame(&frame) {
+            tracing::error!("WebP: Failed to send frame to encoder: {:?}", e);
+            return;
+        }
+
+        self.process_frame();
+    }
+
+    fn process_frame(&mut self) {
+        while self.encoder.receive_packet(&mut self.packet).is_ok() {
+            self.packet.set_stream(self.stream_index);
+            self.packet.rescale_ts(
+                self.config.time_base,
+                self.output.stream(self.stream_index).unwrap().time_base(),
+            );
+            if let Err(e) = self.packet.write_interleaved(&mut self.output) {
+                tracing::error!("WebP: Failed to write packet: {:?}", e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Err(e) = self.encoder.send_eof() {
+            tracing::error!("WebP: Failed to send EOF to encoder: {:?}", e);
+        }
+        self.process_frame();
+
+        if let Err(e) = self.output.write_trailer() {
+            tracing::error!("WebP: Failed to write trailer: {:?}", e);
+        }
+    }
+}
+
+unsafe impl Send for WebPFile {}
@@ -1,4 +1,4 @@
-use cap_project::{ProjectConfiguration, TimelineConfiguration};
+use cap_project::{AudioConfiguration, ProjectConfiguration, TimelineConfiguration};
 use ffmpeg::{
     codec::{context, decoder},
     format::{
@@ -12,10 +12,11 @@ use ringbuf::{
     traits::{Consumer, Observer, Producer},
     HeapRb,
 };
-use std::{path::PathBuf, sync::Arc};
+use std::{ops::Range, path::PathBuf, sync::Arc};
 
 use crate::{
     data::{cast_bytes_to_f32_slice, cast_f32_slice_to_bytes, AudioInfo, FFAudio, FromSampleBytes},
+    filters::{resample, AudioFilter, DenoiseFilter, Loudness, LoudnessMeter, TimeStretch},
     MediaError,
 };
 
@@ -29,6 +30,22 @@ impl AudioData {
     pub const FORMAT: Sample = Sample::F32(Type::Packed);
 
     pub fn from_file(path: PathBuf) -> Result<Self, MediaError> {
+        Self::decode(path, |info| info)
+    }
+
+    // Decodes to the format segment tracks are mixed in
+    pub fn from_file_for_mixing(path: PathBuf) -> Result<Self, MediaError> {
+        Self::decode(path, |info| AudioInfo {
+            sample_rate: SegmentAudio::SAMPLE_RATE,
+            channels: 2,
+            ..info
+        })
+    }
+
+    fn decode(
+        path: PathBuf,
+        target: impl FnOnce(AudioInfo) -> AudioInfo,
+    ) -> Result<Self, MediaError> {
         let mut input_ctx = ffmpeg::format::input(&path)?;
         let input_stream = input_ctx
             .streams()
@@ -40,7 +57,7 @@ impl AudioData {
         decoder.set_parameters(input_stream.parameters())?;
         decoder.set_packet_time_base(input_stream.time_base());
 
-        let mut info = AudioInfo::from_decoder(&decoder)?;
+        let mut info = target(AudioInfo::from_decoder(&decoder)?);
         info.sample_format = Self::FORMAT;
 
         let stream_index = input_stream.index();
@@ -49,16 +66,99 @@ impl AudioData {
                 &mut decoder,
                 &mut input_ctx,
                 stream_index,
+                info,
             )),
             info,
         })
     }
+
+    // excludes channels
+    fn samples(&self) -> usize {
+        self.buffer.len() / self.info.channels
+    }
+}
+
+// The audio tracks recorded alongside a segment, decoded to the same stereo format so they
+// can be panned and mixed sample by sample. 48kHz is what Opus records at and what the
+// denoiser works at.
+#[derive(Clone, PartialEq)]
+pub struct SegmentAudio {
+    pub mic: Option<AudioData>,
+    pub system: Option<AudioData>,
+}
+
+impl SegmentAudio {
+    pub const SAMPLE_RATE: u32 = DenoiseFilter::SAMPLE_RATE;
+
+    pub fn from_files(
+        mic: Option<PathBuf>,
+        system: Option<PathBuf>,
+    ) -> Result<Option<Self>, MediaError> {
+        let mic = mic.map(AudioData::from_file_for_mixing).transpose()?;
+        let system = system.map(AudioData::from_file_for_mixing).transpose()?;
+
+        Ok((mic.is_some() || system.is_some()).then_some(Self { mic, system }))
+    }
+
+    pub fn info(&self) -> AudioInfo {
+        self.tracks().next().unwrap().info
+    }
+
+    // excludes channels, and covers the longest track
+    pub fn samples(&self) -> usize {
+        self.tracks().map(AudioData::samples).max().unwrap_or(0)
+    }
+
+    pub fn tracks(&self) -> impl Iterator<Item = &AudioData> {
+        self.mic.iter().chain(&self.system)
+    }
+
+    // Sums the tracks over `range` of the interleaved buffers, with each track's filter,
+    // gain and pan applied. Tracks that end early are silent for the rest of the range.
+    fn mix(
+        &self,
+        range: Range<usize>,
+        config: &AudioConfiguration,
+        mic_filter: Option<&mut dyn AudioFilter>,
+        out: &mut Vec<f32>,
+    ) {
+        out.clear();
+        out.resize(range.len(), 0.0);
+
+        let tracks = [
+            (&self.mic, config.mic, mic_filter),
+            (&self.system, config.system, None),
+        ];
+
+        for (track, track_config, filter) in tracks {
+            let Some(track) = track else {
+                continue;
+            };
+
+            let end = range.end.min(track.buffer.len());
+            if range.start >= end {
+                continue;
+            }
+
+            let mut samples = track.buffer[range.start..end].to_vec();
+            if let Some(filter) = filter {
+                filter.process(&mut samples);
+            }
+
+            // samples alternate between the left and right channels
+            let gains = track_config.stereo_gains();
+            for (i, (out, sample)) in out.iter_mut().zip(&samples).enumerate() {
+                *out += sample * gains[(range.start + i) % 2];
+            }
+        }
+    }
 }
 
 fn decode_audio_to_f32(
     decoder: &mut decoder::Audio,
     input_ctx: &mut format::context::Input,
     stream_index: usize,
+    output: AudioInfo,
 ) -> Vec<f32> {
     let mut decoded_frame = ffmpeg::frame::Audio::empty();
     let mut resampled_frame = ffmpeg::frame::Audio::empty();
@@ -67,8 +167,8 @@ fn decode_audio_to_f32(
         (decoder.format(), decoder.channel_layout(), decoder.rate()),
         (
             Sample::F32(Type::Packed),
-            decoder.channel_layout(),
-            decoder.rate(),
+            output.channel_layout(),
+            output.sample_rate,
         ),
     )
     .unwrap();
@@ -153,7 +253,14 @@ fn decode_audio_to_f32(
 }
 
 pub struct AudioFrameBuffer {
-    data: Vec<AudioData>,
+    data: Vec<SegmentAudio>,
+    // the segment's tracks mixed together for the frame being returned
+    mixed: Vec<f32>,
+    // set while the project asks for the microphone to be denoised
+    mic_filter: Option<Box<dyn AudioFilter>>,
+    // for segments playing faster or slower than they were recorded
+    time_stretch: TimeStretch,
+    stretched: Vec<f32>,
     cursor: AudioFrameBufferCursor,
     // sum of `frame.samples()` that have elapsed
     // this * channel count = cursor
@@ -169,12 +276,16 @@ pub struct AudioFrameBufferCursor {
 }
 
 impl AudioFrameBuffer {
-    pub fn new(data: Vec<AudioData>) -> Self {
-        let info = data[0].info;
+    pub fn new(data: Vec<SegmentAudio>) -> Self {
+        let info = data[0].info();
         let sample_size = info.channels * info.sample_format.bytes();
 
         Self {
             data,
+            mixed: vec![],
+            mic_filter: None,
+            time_stretch: TimeStretch::new(info.channels),
+            stretched: vec![],
             cursor: AudioFrameBufferCursor {
                 segment_index: 0,
                 samples: 0,
@@ -185,11 +296,12 @@ impl AudioFrameBuffer {
     }
 
     pub fn info(&self) -> AudioInfo {
-        self.data[0].info
+        self.data[0].info()
     }
 
     pub fn set_playhead(&mut self, playhead: f64, project: &ProjectConfiguration) {
         self.elapsed_samples = self.playhead_to_samples(playhead);
+        self.reset_filters();
 
         self.cursor = match project.get_segment_time(playhead) {
             Some((segment_time, segment_i)) => AudioFrameBufferCursor {
@@ -217,7 +329,7 @@ impl AudioFrameBuffer {
             },
             None => AudioFrameBufferCursor {
                 segment_index: 0,
-                samples: self.data[0].buffer.len() / self.info().channels,
+                samples: self.data[0].samples(),
             },
         };
 
@@ -226,9 +338,17 @@ impl AudioFrameBuffer {
             || cursor_diff.unsigned_abs() > (self.info().sample_rate as usize) / 5
         {
             self.cursor = new_cursor;
+            self.reset_filters();
         }
     }
 
+    fn reset_filters(&mut self) {
+        if let Some(filter) = &mut self.mic_filter {
+            filter.reset();
+        }
+        self.time_stretch.reset();
+    }
+
     fn playhead_to_samples(&self, playhead: f64) -> usize {
         (playhead * self.info().sample_rate as f64) as usize
     }
@@ -270,23 +390,80 @@ impl AudioFrameBuffer {
             self.adjust_cursor(timeline);
         }
         let channels = self.info().channels;
+        let timescale = project
+            .timeline
+            .as_ref()
+            .map(|t| t.get_segment_timescale(self.elapsed_samples_to_playhead()))
+            .unwrap_or(1.0);
+
+        if project.audio.improve != self.mic_filter.is_some() {
+            self.mic_filter = project
+                .audio
+                .improve
+                .then(|| Box::new(DenoiseFilter::new(channels)) as Box<dyn AudioFilter>);
+        }
 
         let data = &self.data[self.cursor.segment_index as usize];
-        let buffer = &data.buffer;
-        if self.cursor.samples >= buffer.len() / channels {
+        let total_samples = data.samples();
+        if self.cursor.samples >= total_samples {
             self.elapsed_samples += samples;
             return None;
         }
 
-        let samples = (samples).min((buffer.len() / channels) - self.cursor.samples);
+        // the recording plays `timescale` times as fast as the timeline here
+        let source_samples = ((samples as f64 * timescale).round() as usize)
+            .max(1)
+            .min(total_samples - self.cursor.samples);
+        let samples = if timescale == 1.0 {
+            source_samples
+        } else {
+            ((source_samples as f64 / timescale).round() as usize).clamp(1, samples)
+        };
 
         let start = self.cursor;
         self.elapsed_samples += samples;
-        self.cursor.samples += samples;
-        Some((
-            samples,
-            &buffer[start.samples * channels..self.cursor.samples * channels],
-        ))
+        self.cursor.samples += source_samples;
+
+        data.mix(
+            start.samples * channels..self.cursor.samples * channels,
+            &project.audio,
+            self.mic_filter.as_deref_mut(),
+            &mut self.mixed,
+        );
+
+        if timescale != 1.0 {
+            if project.audio.preserve_pitch {
+                self.time_stretch
+                    .process(&self.mixed, timescale, samples, &mut self.stretched);
+            } else {
+                resample(&self.mixed, channels, samples, &mut self.stretched);
+            }
+            std::mem::swap(&mut self.mixed, &mut self.stretched);
+        }
+
+        Some((samples, &self.mixed))
+    }
+
+    // Mixes the first `duration` seconds of the project the way they'll be exported and
+    // measures how loud they are. Leaves the playhead back at the start.
+    pub fn measure_loudness(
+        &mut self,
+        duration: f64,
+        project: &ProjectConfiguration,
+    ) -> Result<Option<Loudness>, MediaError> {
+        let info = self.info();
+        let mut meter = LoudnessMeter::new(info.channels, info.sample_rate)?;
+        let chunk_samples = info.sample_rate as usize / 10;
+
+        self.set_playhead(0.0, project);
+        while self.elapsed_samples_to_playhead() < duration {
+            if let Some((_, data)) = self.next_frame_data(chunk_samples, project) {
+                meter.add(data);
+            }
+        }
+        self.set_playhead(0.0, project);
+
+        Ok(meter.finish())
     }
 }
 
@@ -300,11 +477,11 @@ impl<T: FromSampleBytes> AudioPlaybackBuffer<T> {
     pub const PLAYBACK_SAMPLES_COUNT: u32 = 256;
     const PROCESSING_SAMPLES_COUNT: u32 = 1024;
 
-    pub fn new(data: Vec<AudioData>, output_info: AudioInfo) -> Self {
-        println!("Input info: {:?}", data[0].info);
+    pub fn new(data: Vec<SegmentAudio>, output_info: AudioInfo) -> Self {
+        println!("Input info: {:?}", data[0].info());
         println!("Output info: {:?}", output_info);
 
-        let resampler = AudioResampler::new(data[0].info, output_info).unwrap();
+        let resampler = AudioResampler::new(data[0].info(), output_info).unwrap();
 
         // Up to 1 second of pre-rendered audio
         let capacity = (output_info.sample_rate as usize)
@@ -0,0 +1,228 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use super::AudioFilter;
+
+// Spectral noise gate, run separately on each channel. It keeps a running estimate of the
+// noise floor in each frequency band and turns down the bands that don't rise above it, which
+// takes out steady noise like fans and hiss while leaving speech alone. The bands are sized
+// for 48kHz audio.
+pub struct DenoiseFilter {
+    channels: usize,
+    fft: Fft,
+    // a periodic sqrt-Hann window, applied before and after so overlapping frames sum to one
+    window: Vec<f32>,
+    states: Vec<ChannelState>,
+}
+
+struct ChannelState {
+    // samples waiting for a full frame
+    input: Vec<f32>,
+    // frames being added together, of which the first hop is finished once the next frame
+    // is added
+    overlap: Vec<f32>,
+    // denoised samples not handed back yet
+    output: VecDeque<f32>,
+    // power in each band averaged over the last few frames, and the noise's share of it.
+    // None until the first frame's been seen.
+    smoothed: Option<Vec<f32>>,
+    noise: Vec<f32>,
+    gains: Vec<f32>,
+}
+
+impl DenoiseFilter {
+    pub const SAMPLE_RATE: u32 = 48_000;
+
+    // about 21ms at 48kHz
+    const FRAME_SIZE: usize = 1024;
+    const HOP: usize = Self::FRAME_SIZE / 2;
+    const BANDS: usize = Self::FRAME_SIZE / 2 + 1;
+
+    // how much of each band's averaged power carries over from the frame before
+    const POWER_SMOOTHING: f32 = 0.7;
+    // how far a band's noise estimate falls towards a quieter frame, and rises towards a
+    // louder one. Rising over several seconds keeps speech from being taken for noise.
+    const NOISE_FALL: f32 = 0.1;
+    const NOISE_RISE: f32 = 0.002;
+    // how much of the noise estimate is taken out. The estimate follows the quieter end
+    // of the noise, so taking out more than it catches the rest.
+    const OVER_SUBTRACTION: f32 = 2.5;
+    // the furthest a band is turned down, about -20dB, since removing all of it sounds watery
+    const GAIN_FLOOR: f32 = 0.1;
+    // how much of the previous frame's gain carries over, which smooths out the warbling
+    // left by bands flickering on and off
+    const GAIN_SMOOTHING: f32 = 0.5;
+
+    pub fn new(channels: usize) -> Self {
+        let window = (0..Self::FRAME_SIZE)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / Self::FRAME_SIZE as f32).cos()).sqrt())
+            .collect();
+
+        let mut this = Self {
+            channels,
+            fft: Fft::new(Self::FRAME_SIZE),
+            window,
+            states: vec![],
+        };
+        this.reset();
+
+        this
+    }
+
+    fn process_frame(&self, state: &mut ChannelState, re: &mut [f32], im: &mut [f32]) {
+        for (i, sample) in state.input[..Self::FRAME_SIZE].iter().enumerate() {
+            re[i] = sample * self.window[i];
+            im[i] = 0.0;
+        }
+        self.fft.transform(re, im, false);
+
+        let power = (0..Self::BANDS)
+            .map(|k| re[k] * re[k] + im[k] * im[k])
+            .collect::<Vec<_>>();
+        let smoothed = state.smoothed.get_or_insert_with(|| {
+            state.noise = power.clone();
+            power.clone()
+        });
+
+        for k in 0..Self::BANDS {
+            smoothed[k] =
+                smoothed[k] * Self::POWER_SMOOTHING + power[k] * (1.0 - Self::POWER_SMOOTHING);
+
+            let noise = &mut state.noise[k];
+            let rate = if smoothed[k] < *noise {
+                Self::NOISE_FALL
+            } else {
+                Self::NOISE_RISE
+            };
+            *noise += (smoothed[k] - *noise) * rate;
+
+            let gain = if power[k] > 0.0 {
+                (1.0 - Self::OVER_SUBTRACTION * *noise / power[k]).max(Self::GAIN_FLOOR)
+            } else {
+                Self::GAIN_FLOOR
+            };
+            let gain = state.gains[k] * Self::GAIN_SMOOTHING + gain * (1.0 - Self::GAIN_SMOOTHING);
+            state.gains[k] = gain;
+
+            re[k] *= gain;
+            im[k] *= gain;
+            // the upper half mirrors the lower one for real signals
+            if k > 0 && k < Self::FRAME_SIZE / 2 {
+                re[Self::FRAME_SIZE - k] *= gain;
+                im[Self::FRAME_SIZE - k] *= gain;
+            }
+        }
+
+        self.fft.transform(re, im, true);
+
+        for (i, sample) in re.iter().enumerate() {
+            state.overlap[i] += sample * self.window[i];
+        }
+
+        state.output.extend(state.overlap.drain(..Self::HOP));
+        state.overlap.resize(Self::FRAME_SIZE, 0.0);
+        state.input.drain(..Self::HOP);
+    }
+}
+
+impl AudioFilter for DenoiseFilter {
+    fn process(&mut self, samples: &mut [f32]) {
+        let mut states = std::mem::take(&mut self.states);
+
+        for (i, sample) in samples.iter().enumerate() {
+            states[i % self.channels].input.push(*sample);
+        }
+
+        let mut re = vec![0.0; Self::FRAME_SIZE];
+        let mut im = vec![0.0; Self::FRAME_SIZE];
+        for state in &mut states {
+            while state.input.len() >= Self::FRAME_SIZE {
+                self.process_frame(state, &mut re, &mut im);
+            }
+        }
+
+        // the output starts out a frame ahead, so there's always enough of it
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = states[i % self.channels].output.pop_front().unwrap_or(0.0);
+        }
+
+        self.states = states;
+    }
+
+    fn reset(&mut self) {
+        self.states = (0..self.channels)
+            .map(|_| ChannelState {
+                input: vec![],
+                overlap: vec![0.0; Self::FRAME_SIZE],
+                output: VecDeque::from(vec![0.0; Self::FRAME_SIZE]),
+                smoothed: None,
+                noise: vec![],
+                gains: vec![1.0; Self::BANDS],
+            })
+            .collect();
+    }
+}
+
+// In-place radix-2 FFT, for sizes that are a power of two
+struct Fft {
+    size: usize,
+    // e^(-2πik/size) for the first half of k
+    twiddles: Vec<(f32, f32)>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * std::f64::consts::PI * k as f64 / size as f64;
+                (angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+
+        Self { size, twiddles }
+    }
+
+    // The inverse is scaled by 1/size, so transforming forwards and back gives the input
+    fn transform(&self, re: &mut [f32], im: &mut [f32], inverse: bool) {
+        let n = self.size;
+        let bits = n.trailing_zeros();
+
+        for i in 0..n {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if j > i {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            let stride = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..len / 2 {
+                    let (w_re, mut w_im) = self.twiddles[k * stride];
+                    if inverse {
+                        w_im = -w_im;
+                    }
+
+                    let (a, b) = (start + k, start + k + len / 2);
+                    let t_re = re[b] * w_re - im[b] * w_im;
+                    let t_im = re[b] * w_im + im[b] * w_re;
+
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                }
+            }
+            len *= 2;
+        }
+
+        if inverse {
+            let scale = 1.0 / n as f32;
+            re.iter_mut().for_each(|v| *v *= scale);
+            im.iter_mut().for_each(|v| *v *= scale);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{collections::VecDeque, f64::consts::PI};
+
+use super::AudioFilter;
+use crate::MediaError;
+
+// Loudness of a whole mix, as measured by EBU R128
+#[derive(Clone, Copy, Debug)]
+pub struct Loudness {
+    pub integrated_lufs: f64,
+    // highest true peak across all channels, as a linear amplitude
+    pub true_peak: f64,
+}
+
+// Accumulates interleaved f32 samples to measure their loudness once they've all been seen,
+// following ITU-R BS.1770: the audio is K-weighted, its power taken over 400ms blocks that
+// overlap by 75%, and blocks that are silent or far quieter than the rest left out.
+pub struct LoudnessMeter {
+    channels: usize,
+    // how much each channel counts towards the loudness
+    weights: Vec<f64>,
+    filters: Vec<[Biquad; 2]>,
+    // frames in each 100ms step between blocks
+    step_frames: usize,
+    // squared K-weighted samples summed over the current step, and over the last four
+    // steps that make up a block, per channel
+    step_power: Vec<f64>,
+    step_count: usize,
+    recent_steps: VecDeque<Vec<f64>>,
+    // mean power of each finished block, weighted and summed across channels
+    blocks: Vec<f64>,
+    true_peak: TruePeak,
+}
+
+impl LoudnessMeter {
+    // blocks quieter than this are silence, in LUFS
+    const ABSOLUTE_GATE: f64 = -70.0;
+    // and blocks this much quieter than the loudness of the rest are pauses
+    const RELATIVE_GATE: f64 = -10.0;
+    const STEPS_PER_BLOCK: usize = 4;
+
+    pub fn new(channels: usize, sample_rate: u32) -> Result<Self, MediaError> {
+        if channels == 0 || sample_rate == 0 {
+            return Err(MediaError::Any("Failed to create loudness meter"));
+        }
+
+        // 5.1 is laid out L, R, C, LFE, Ls, Rs. The LFE isn't counted and the surrounds are
+        // turned up by 1.5dB. Anything else counts every channel the same.
+        let weights = (0..channels)
+            .map(|channel| match (channels, channel) {
+                (6, 3) => 0.0,
+                (6, 4 | 5) => 1.41,
+                _ => 1.0,
+            })
+            .collect();
+
+        Ok(Self {
+            channels,
+            weights,
+            filters: (0..channels)
+                .map(|_| Biquad::k_weighting(sample_rate as f64))
+                .collect(),
+            step_frames: (sample_rate as usize / 10).max(1),
+            step_power: vec![0.0; channels],
+            step_count: 0,
+            recent_steps: VecDeque::new(),
+            blocks: vec![],
+            true_peak: TruePeak::new(channels, sample_rate),
+        })
+    }
+
+    pub fn add(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let weighted = high_pass.process(shelf.process(*sample as f64));
+                self.step_power[channel] += weighted * weighted;
+            }
+
+            self.true_peak.add(frame);
+
+            self.step_count += 1;
+            if self.step_count == self.step_frames {
+                self.finish_step();
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        let step = std::mem::replace(&mut self.step_power, vec![0.0; self.channels]);
+        self.step_count = 0;
+
+        self.recent_steps.push_back(step);
+        if self.recent_steps.len() > Self::STEPS_PER_BLOCK {
+            self.recent_steps.pop_front();
+        }
+        if self.recent_steps.len() < Self::STEPS_PER_BLOCK {
+            return;
+        }
+
+        let block_frames = (self.step_frames * Self::STEPS_PER_BLOCK) as f64;
+        let power = (0..self.channels)
+            .map(|channel| {
+                let sum = self.recent_steps.iter().map(|s| s[channel]).sum::<f64>();
+                self.weights[channel] * sum / block_frames
+            })
+            .sum();
+
+        self.blocks.push(power);
+    }
+
+    // None when everything measured was silent, which can't be brought up to a target
+    pub fn finish(&self) -> Option<Loudness> {
+        let gated_mean = |threshold: f64| {
+            let gated = self
+                .blocks
+                .iter()
+                .filter(|power| loudness(**power) > threshold)
+                .collect::<Vec<_>>();
+
+            (!gated.is_empty()).then(|| gated.iter().copied().sum::<f64>() / gated.len() as f64)
+        };
+
+        let relative_gate = loudness(gated_mean(Self::ABSOLUTE_GATE)?) + Self::RELATIVE_GATE;
+        let integrated_lufs = loudness(gated_mean(relative_gate.max(Self::ABSOLUTE_GATE))?);
+        if !integrated_lufs.is_finite() {
+            return None;
+        }
+
+        Some(Loudness {
+            integrated_lufs,
+            true_peak: self.true_peak.peak,
+        })
+    }
+}
+
+// in LUFS, for a block's weighted mean power
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+// A second order IIR filter, in direct form I
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    // BS.1770's K-weighting: a high shelf modelling the head, then a high pass, with their
+    // coefficients worked out for the sample rate the same way libebur128 does
+    fn k_weighting(sample_rate: f64) -> [Self; 2] {
+        let f0 = 1681.974450955533;
+        let gain = 3.999843853973347;
+        let q = 0.7071752369554196;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Self::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        [shelf, high_pass]
+    }
+
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+
+        y
+    }
+}
+
+// The highest peak between samples as well as on them, found by upsampling to at least
+// 192kHz as BS.1770 suggests
+struct TruePeak {
+    channels: usize,
+    // each phase's taps of a windowed sinc interpolator
+    phases: Vec<Vec<f64>>,
+    // the last few samples of each channel, newest first
+    history: Vec<VecDeque<f64>>,
+    peak: f64,
+}
+
+impl TruePeak {
+    const TAPS_PER_PHASE: usize = 12;
+
+    fn new(channels: usize, sample_rate: u32) -> Self {
+        let factor = match sample_rate {
+            0..96_000 => 4,
+            96_000..192_000 => 2,
+            _ => 1,
+        };
+
+        let taps = Self::TAPS_PER_PHASE * factor;
+        let center = (taps - 1) as f64 / 2.0;
+        let phases = (0..factor)
+            .map(|phase| {
+                (0..Self::TAPS_PER_PHASE)
+                    .map(|tap| {
+                        let i = tap * factor + phase;
+                        let x = (i as f64 - center) / factor as f64;
+                        let sinc = if x == 0.0 {
+                            1.0
+                        } else {
+                            (PI * x).sin() / (PI * x)
+                        };
+                        let window = 0.5 - 0.5 * (2.0 * PI * i as f64 / (taps - 1) as f64).cos();
+                        sinc * window
+                    })
+                    .collect::<Vec<_>>()
+            })
+            // each phase passes a constant signal through unchanged
+            .map(|taps| {
+                let sum = taps.iter().sum::<f64>();
+                taps.into_iter().map(|tap| tap / sum).collect()
+            })
+            .collect();
+
+        Self {
+            channels,
+            phases,
+            history: vec![VecDeque::from(vec![0.0; Self::TAPS_PER_PHASE]); channels],
+            peak: 0.0,
+        }
+    }
+
+    fn add(&mut self, frame: &[f32]) {
+        for channel in 0..self.channels {
+            let history = &mut self.history[channel];
+            history.pop_back();
+            history.push_front(frame[channel] as f64);
+
+            for phase in &self.phases {
+                let value = phase
+                    .iter()
+                    .zip(history.iter())
+                    .map(|(tap, sample)| tap * sample)
+                    .sum::<f64>();
+                self.peak = self.peak.max(value.abs());
+            }
+        }
+    }
+}
+
+// Brings a measured mix to a target loudness with a single gain, so the dynamics of the
+// mix are untouched. The gain is pulled back where reaching the target would push peaks
+// past the ceiling, leaving the mix quieter than asked for rather than clipped.
+#[derive(Clone, Copy, Debug)]
+pub struct LoudnessNormalizer {
+    gain: f32,
+}
+
+impl LoudnessNormalizer {
+    // in dBTP, the ceiling EBU R128 recommends for delivery
+    pub const TRUE_PEAK_CEILING: f64 = -1.0;
+
+    pub fn new(measured: Loudness, target_lufs: f32) -> Self {
+        let mut gain_db = target_lufs as f64 - measured.integrated_lufs;
+
+        if measured.true_peak > 0.0 {
+            let peak_db = 20.0 * measured.true_peak.log10();
+            gain_db = gain_db.min(Self::TRUE_PEAK_CEILING - peak_db);
+        }
+
+        Self {
+            gain: 10f64.powf(gain_db / 20.0) as f32,
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+impl AudioFilter for LoudnessNormalizer {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
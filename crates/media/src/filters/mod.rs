@@ -0,0 +1,21 @@
+mod denoise;
+mod loudness;
+mod time_stretch;
+
+pub use denoise::*;
+pub use loudness::*;
+pub use time_stretch::*;
+
+// A processing stage for audio on its way to the speakers or an export. Filters run on
+// interleaved f32 samples in the format the audio was decoded to, and can keep state
+// between calls, so they have to be fed the audio in order.
+pub trait AudioFilter: Send {
+    // Replaces `samples` with their filtered version. Filters working on fixed size
+    // blocks delay the audio by up to one block to always return as many samples as
+    // they were given.
+    fn process(&mut self, samples: &mut [f32]);
+
+    // Called when the audio jumps to a different point, so state built up before the
+    // jump doesn't bleed into what comes after it
+    fn reset(&mut self);
+}
@@ -0,0 +1,111 @@
+use std::f32::consts::PI;
+
+// Plays interleaved audio faster or slower while keeping its pitch, by overlapping short
+// windowed grains of the input taken further apart, or closer together, than they're laid
+// down in the output. The output lags the input by one grain, and has to be fed in order
+// like an `AudioFilter`.
+pub struct TimeStretch {
+    channels: usize,
+    window: Vec<f32>,
+    // input not yet consumed, starting at the frame `input_position` counts from
+    input: Vec<f32>,
+    input_position: f64,
+    // overlapped grains, of which the frames before `ready` are finished
+    output: Vec<f32>,
+    ready: usize,
+}
+
+impl TimeStretch {
+    // in frames, about 20ms at 48kHz
+    const GRAIN: usize = 1024;
+    const HOP: usize = Self::GRAIN / 2;
+
+    pub fn new(channels: usize) -> Self {
+        // a periodic Hann window, which sums to one at half-grain hops
+        let window = (0..Self::GRAIN)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / Self::GRAIN as f32).cos())
+            .collect();
+
+        Self {
+            channels,
+            window,
+            input: vec![],
+            input_position: 0.0,
+            output: vec![],
+            ready: 0,
+        }
+    }
+
+    // Takes `input` and writes `frames` frames to `out`, consuming `rate` input frames per
+    // output frame. Silence is written while there isn't a grain's worth of input yet.
+    pub fn process(&mut self, input: &[f32], rate: f64, frames: usize, out: &mut Vec<f32>) {
+        let channels = self.channels;
+        self.input.extend_from_slice(input);
+
+        while self.ready < frames {
+            let start = self.input_position as usize;
+            if (start + Self::GRAIN) * channels > self.input.len() {
+                break;
+            }
+
+            let end = (self.ready + Self::GRAIN) * channels;
+            if self.output.len() < end {
+                self.output.resize(end, 0.0);
+            }
+
+            for (i, weight) in self.window.iter().enumerate() {
+                for channel in 0..channels {
+                    self.output[(self.ready + i) * channels + channel] +=
+                        self.input[(start + i) * channels + channel] * weight;
+                }
+            }
+
+            self.ready += Self::HOP;
+            self.input_position += Self::HOP as f64 * rate;
+        }
+
+        let taken = frames.min(self.ready);
+        out.clear();
+        out.extend(self.output.drain(..taken * channels));
+        out.resize(frames * channels, 0.0);
+        self.ready -= taken;
+
+        // input skipped over at fast rates can run past what's arrived so far
+        let consumed = (self.input_position as usize).min(self.input.len() / channels);
+        self.input.drain(..consumed * channels);
+        self.input_position -= consumed as f64;
+    }
+
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.input_position = 0.0;
+        self.output.clear();
+        self.ready = 0;
+    }
+}
+
+// Stretches interleaved audio to `frames` frames by interpolating between its samples,
+// which changes its pitch along with its speed
+pub fn resample(input: &[f32], channels: usize, frames: usize, out: &mut Vec<f32>) {
+    out.clear();
+
+    let input_frames = input.len() / channels;
+    if input_frames == 0 {
+        out.resize(frames * channels, 0.0);
+        return;
+    }
+
+    let step = input_frames as f64 / frames as f64;
+    for frame in 0..frames {
+        let position = frame as f64 * step;
+        let index = (position as usize).min(input_frames - 1);
+        let next = (index + 1).min(input_frames - 1);
+        let fraction = (position - index as f64) as f32;
+
+        for channel in 0..channels {
+            let a = input[index * channels + channel];
+            let b = input[next * channels + channel];
+            out.push(a + (b - a) * fraction);
+        }
+    }
+}
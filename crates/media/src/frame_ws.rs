@@ -5,6 +5,7 @@ use tokio::sync::mpsc;
 
 use crate::feeds::RawCameraFrame;
 
+#[derive(Clone)]
 pub struct WSFrame {
     pub data: Vec<u8>,
     pub width: u32,
@@ -1,14 +1,22 @@
 use cocoa::{base::id, foundation::NSDictionary};
 use core_foundation::{
     array::CFArrayGetCount,
-    base::FromVoid,
+    base::{CFRelease, CFTypeRef, FromVoid, TCFType},
     dictionary::CFDictionaryGetValue,
     number::{kCFNumberIntType, CFNumberGetValue, CFNumberRef},
+    runloop::{kCFRunLoopCommonModes, CFRunLoop},
     string::{CFString, CFStringRef},
 };
 use core_graphics::{
     base::boolean_t,
-    display::{CFArrayGetValueAtIndex, CFDictionaryRef, CGDisplay, CGDisplayBounds, CGRect},
+    display::{
+        CFArrayGetValueAtIndex, CFDictionaryRef, CGDisplay, CGDisplayBounds, CGPoint, CGRect,
+        CGSize,
+    },
+    event::{
+        CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement, CGEventType,
+        EventField,
+    },
     window::{
         kCGNullWindowID, kCGWindowBounds, kCGWindowLayer, kCGWindowListExcludeDesktopElements,
         kCGWindowListOptionOnScreenOnly, kCGWindowName, kCGWindowNumber, kCGWindowOwnerName,
@@ -17,8 +25,9 @@ use core_graphics::{
 };
 pub use nokhwa_bindings_macos::{AVAuthorizationStatus, AVMediaType};
 use std::{collections::HashMap, ffi::c_void};
+use tracing::warn;
 
-use crate::platform::{Bounds, Window};
+use crate::platform::{Bounds, KeyText, ScrollDelta, Window};
 
 #[link(name = "CoreGraphics", kind = "framework")]
 extern "C" {
@@ -28,6 +37,28 @@ extern "C" {
     ) -> boolean_t;
 }
 
+type AXUIElementRef = *const c_void;
+
+const K_AX_VALUE_CG_POINT_TYPE: u32 = 1;
+const K_AX_VALUE_CG_SIZE_TYPE: u32 = 2;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+    fn AXUIElementCopyElementAtPosition(
+        application: AXUIElementRef,
+        x: f32,
+        y: f32,
+        element: *mut AXUIElementRef,
+    ) -> i32;
+    fn AXUIElementCopyAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
+    fn AXValueGetValue(value: CFTypeRef, the_type: u32, value_ptr: *mut c_void) -> boolean_t;
+}
+
 pub fn get_on_screen_windows() -> Vec<Window> {
     let mut windows = Vec::new();
 
@@ -384,3 +415,635 @@ pub fn primary_monitor_bounds() -> Bounds {
         height: height as f64,
     }
 }
+
+/// Looks up the accessibility element at global points. The accessibility API needs no
+/// setup, so this only matches the Windows sampler.
+pub struct ElementSampler;
+
+impl ElementSampler {
+    pub fn start() -> Self {
+        Self
+    }
+
+    /// Bounds of the element at the given global point.
+    /// Requires the app to be trusted for accessibility, returns `None` otherwise.
+    pub fn bounds_at(&self, x: f64, y: f64) -> Option<Bounds> {
+        element_bounds_at(x, y)
+    }
+}
+
+fn element_bounds_at(x: f64, y: f64) -> Option<Bounds> {
+    unsafe {
+        let system_wide = AXUIElementCreateSystemWide();
+        if system_wide.is_null() {
+            return None;
+        }
+
+        let mut element: AXUIElementRef = std::ptr::null();
+        let result =
+            AXUIElementCopyElementAtPosition(system_wide, x as f32, y as f32, &mut element);
+        CFRelease(system_wide);
+
+        if result != 0 || element.is_null() {
+            return None;
+        }
+
+        let mut position = CGPoint::new(0.0, 0.0);
+        let mut size = CGSize::new(0.0, 0.0);
+
+        let has_position = copy_ax_value(
+            element,
+            "AXPosition",
+            K_AX_VALUE_CG_POINT_TYPE,
+            &mut position as *mut _ as *mut c_void,
+        );
+        let has_size = copy_ax_value(
+            element,
+            "AXSize",
+            K_AX_VALUE_CG_SIZE_TYPE,
+            &mut size as *mut _ as *mut c_void,
+        );
+        CFRelease(element);
+
+        (has_position && has_size).then_some(Bounds {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        })
+    }
+}
+
+unsafe fn copy_ax_value(
+    element: AXUIElementRef,
+    attribute: &'static str,
+    the_type: u32,
+    out: *mut c_void,
+) -> bool {
+    let attribute = CFString::from_static_string(attribute);
+    let mut value: CFTypeRef = std::ptr::null();
+
+    if AXUIElementCopyAttributeValue(element, attribute.as_concrete_TypeRef(), &mut value) != 0
+        || value.is_null()
+    {
+        return false;
+    }
+
+    let ok = AXValueGetValue(value, the_type, out) != 0;
+    CFRelease(value);
+    ok
+}
+
+/// Listens for scroll events anywhere on the system, on a thread running its own run loop.
+/// Needs input monitoring permission, without which no events arrive.
+pub struct ScrollListener {
+    run_loop: Option<CFRunLoop>,
+    rx: std::sync::mpsc::Receiver<ScrollDelta>,
+}
+
+impl ScrollListener {
+    pub fn start() -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (run_loop_tx, run_loop_rx) = std::sync::mpsc::sync_channel(1);
+
+        std::thread::spawn(move || {
+            let tap = CGEventTap::new(
+                CGEventTapLocation::Session,
+                CGEventTapPlacement::TailAppendEventTap,
+                CGEventTapOptions::ListenOnly,
+                vec![CGEventType::ScrollWheel],
+                move |_, _, event| {
+                    // point deltas are positive when scrolling towards the top
+                    tx.send(ScrollDelta {
+                        x: -event.get_integer_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2,
+                        ) as f64,
+                        y: -event.get_integer_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_1,
+                        ) as f64,
+                    })
+                    .ok();
+                    None
+                },
+            );
+
+            let Some((tap, source)) = tap.ok().and_then(|tap| {
+                let source = tap.mach_port.create_runloop_source(0).ok()?;
+                Some((tap, source))
+            }) else {
+                warn!("failed to create scroll event tap");
+                run_loop_tx.send(None).ok();
+                return;
+            };
+
+            let run_loop = CFRunLoop::get_current();
+            run_loop.add_source(&source, unsafe { kCFRunLoopCommonModes });
+            tap.enable();
+
+            run_loop_tx.send(Some(run_loop)).ok();
+            CFRunLoop::run_current();
+        });
+
+        Self {
+            run_loop: run_loop_rx.recv().ok().flatten(),
+            rx,
+        }
+    }
+
+    /// Scrolls since the last call
+    pub fn drain(&self) -> impl Iterator<Item = ScrollDelta> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl Drop for ScrollListener {
+    fn drop(&mut self) {
+        if let Some(run_loop) = &self.run_loop {
+            run_loop.stop();
+        }
+    }
+}
+
+type AXObserverRef = *const c_void;
+
+// AXTextEditType values for typing and for text inserted in one go, which is how input
+// methods' `insertText` calls come through
+const K_AX_TEXT_EDIT_TYPE_TYPING: i32 = 3;
+const K_AX_TEXT_EDIT_TYPE_INSERT: i32 = 2;
+
+// how often the app in front is checked, to follow the user switching apps
+const FRONT_APP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+    fn AXObserverCreateWithInfoCallback(
+        application: i32,
+        callback: unsafe extern "C" fn(
+            AXObserverRef,
+            AXUIElementRef,
+            CFStringRef,
+            CFDictionaryRef,
+            *mut c_void,
+        ),
+        observer: *mut AXObserverRef,
+    ) -> i32;
+    fn AXObserverAddNotification(
+        observer: AXObserverRef,
+        element: AXUIElementRef,
+        notification: CFStringRef,
+        refcon: *mut c_void,
+    ) -> i32;
+    fn AXObserverGetRunLoopSource(
+        observer: AXObserverRef,
+    ) -> core_foundation::runloop::CFRunLoopSourceRef;
+}
+
+/// Hears the text input methods commit, like the characters a Chinese or Japanese input
+/// method turns keys into. Text views tell accessibility clients what gets typed or inserted
+/// into them, including what input methods pass to `insertText`, so this watches the app in
+/// front for that on a thread running its own run loop. Needs the app to be trusted for
+/// accessibility, without which nothing arrives.
+///
+/// Plain typing is reported the same way, so the text only stands for an input method's
+/// while one is active.
+pub struct InputMethodListener {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    rx: std::sync::mpsc::Receiver<String>,
+}
+
+impl InputMethodListener {
+    pub fn start() -> Self {
+        use core_foundation::runloop::{
+            kCFRunLoopDefaultMode, CFRunLoopAddSource, CFRunLoopGetCurrent, CFRunLoopRemoveSource,
+            CFRunLoopRunInMode,
+        };
+        use std::sync::atomic::Ordering;
+
+        let (tx, rx) = std::sync::mpsc::channel::<String>();
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        std::thread::spawn({
+            let stop = stop.clone();
+            move || unsafe {
+                // handed to the callback, and freed once nothing's observed
+                let tx = Box::into_raw(Box::new(tx));
+                let run_loop = CFRunLoopGetCurrent();
+                let mut observed: Option<(i32, AXObserverRef)> = None;
+
+                while !stop.load(Ordering::Relaxed) {
+                    let pid = objc::rc::autoreleasepool(|| frontmost_pid());
+
+                    if observed.map(|(observed, _)| observed) != pid {
+                        if let Some((_, observer)) = observed.take() {
+                            CFRunLoopRemoveSource(
+                                run_loop,
+                                AXObserverGetRunLoopSource(observer),
+                                kCFRunLoopDefaultMode,
+                            );
+                            CFRelease(observer);
+                        }
+
+                        observed = pid.and_then(|pid| {
+                            let observer = observe_text_changes(pid, tx as *mut c_void)?;
+                            CFRunLoopAddSource(
+                                run_loop,
+                                AXObserverGetRunLoopSource(observer),
+                                kCFRunLoopDefaultMode,
+                            );
+                            Some((pid, observer))
+                        });
+                    }
+
+                    // the run loop returns straight away when it has nothing to run
+                    match observed {
+                        Some(_) => {
+                            CFRunLoopRunInMode(
+                                kCFRunLoopDefaultMode,
+                                FRONT_APP_CHECK_INTERVAL.as_secs_f64(),
+                                0,
+                            );
+                        }
+                        None => std::thread::sleep(FRONT_APP_CHECK_INTERVAL),
+                    }
+                }
+
+                if let Some((_, observer)) = observed {
+                    CFRunLoopRemoveSource(
+                        run_loop,
+                        AXObserverGetRunLoopSource(observer),
+                        kCFRunLoopDefaultMode,
+                    );
+                    CFRelease(observer);
+                }
+                drop(Box::from_raw(tx));
+            }
+        });
+
+        Self { stop, rx }
+    }
+
+    /// Text committed since the last call
+    pub fn drain(&self) -> impl Iterator<Item = String> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl Drop for InputMethodListener {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+unsafe fn frontmost_pid() -> Option<i32> {
+    use objc::{msg_send, *};
+
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    let app: id = msg_send![workspace, frontmostApplication];
+    if app.is_null() {
+        return None;
+    }
+
+    let pid: i32 = msg_send![app, processIdentifier];
+    Some(pid)
+}
+
+unsafe fn observe_text_changes(pid: i32, tx: *mut c_void) -> Option<AXObserverRef> {
+    let mut observer: AXObserverRef = std::ptr::null();
+    if AXObserverCreateWithInfoCallback(pid, text_changed, &mut observer) != 0 || observer.is_null()
+    {
+        return None;
+    }
+
+    // notifications for the application cover every element in it
+    let application = AXUIElementCreateApplication(pid);
+    let notification = CFString::from_static_string("AXValueChanged");
+    let result = AXObserverAddNotification(
+        observer,
+        application,
+        notification.as_concrete_TypeRef(),
+        tx,
+    );
+    CFRelease(application);
+
+    if result != 0 {
+        CFRelease(observer);
+        return None;
+    }
+
+    Some(observer)
+}
+
+unsafe extern "C" fn text_changed(
+    _observer: AXObserverRef,
+    _element: AXUIElementRef,
+    _notification: CFStringRef,
+    info: CFDictionaryRef,
+    refcon: *mut c_void,
+) {
+    let tx = &*(refcon as *const std::sync::mpsc::Sender<String>);
+    for text in inserted_text(info) {
+        tx.send(text).ok();
+    }
+}
+
+// The text a value change says was typed or inserted, from the change descriptions
+// AppKit and WebKit attach for VoiceOver
+unsafe fn inserted_text(info: CFDictionaryRef) -> Vec<String> {
+    let value = |dict: CFDictionaryRef, key: &'static str| {
+        let key = CFString::from_static_string(key);
+        CFDictionaryGetValue(dict, key.as_concrete_TypeRef() as *const c_void)
+    };
+
+    if info.is_null() {
+        return vec![];
+    }
+    let changes = value(info, "AXTextChangeValues") as core_foundation::array::CFArrayRef;
+    if changes.is_null() {
+        return vec![];
+    }
+
+    (0..CFArrayGetCount(changes))
+        .filter_map(|i| {
+            let change = CFArrayGetValueAtIndex(changes, i) as CFDictionaryRef;
+            if change.is_null() {
+                return None;
+            }
+
+            let edit_type = value(change, "AXTextEditType") as CFNumberRef;
+            let mut edit_type_value = 0i32;
+            if edit_type.is_null()
+                || !CFNumberGetValue(
+                    edit_type,
+                    kCFNumberIntType,
+                    &mut edit_type_value as *mut i32 as *mut c_void,
+                )
+                || !matches!(
+                    edit_type_value,
+                    K_AX_TEXT_EDIT_TYPE_TYPING | K_AX_TEXT_EDIT_TYPE_INSERT
+                )
+            {
+                return None;
+            }
+
+            let text = value(change, "AXTextChangeValue") as CFStringRef;
+            (!text.is_null())
+                .then(|| CFString::wrap_under_get_rule(text).to_string())
+                .filter(|text| !text.is_empty())
+        })
+        .collect()
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    static kTISPropertyUnicodeKeyLayoutData: CFStringRef;
+    static kTISPropertyInputSourceType: CFStringRef;
+    static kTISTypeKeyboardLayout: CFStringRef;
+
+    fn TISCopyCurrentKeyboardInputSource() -> CFTypeRef;
+    fn TISCopyCurrentKeyboardLayoutInputSource() -> CFTypeRef;
+    fn TISGetInputSourceProperty(source: CFTypeRef, key: CFStringRef) -> CFTypeRef;
+    fn LMGetKbdType() -> u8;
+    fn UCKeyTranslate(
+        layout: *const c_void,
+        virtual_key_code: u16,
+        key_action: u16,
+        modifier_key_state: u32,
+        keyboard_type: u32,
+        key_translate_options: u32,
+        dead_key_state: *mut u32,
+        max_string_length: usize,
+        actual_string_length: *mut usize,
+        unicode_string: *mut u16,
+    ) -> i32;
+}
+
+extern "C" {
+    static _dispatch_main_q: c_void;
+
+    fn dispatch_async_f(
+        queue: *const c_void,
+        context: *mut c_void,
+        work: extern "C" fn(*mut c_void),
+    );
+}
+
+const K_UC_KEY_ACTION_DOWN: u16 = 0;
+const K_UC_KEY_TRANSLATE_NO_DEAD_KEYS_MASK: u32 = 1;
+// shiftKey and optionKey from Events.h, shifted down a byte as UCKeyTranslate wants them
+const SHIFT_KEY_STATE: u32 = (1 << 9) >> 8;
+const OPTION_KEY_STATE: u32 = (1 << 11) >> 8;
+
+// how often the layout's looked up again, to notice the user switching layouts
+const KEYBOARD_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+// The keyboard layout's data and whether an input method's in use, as of the last refresh
+struct KeyboardSnapshot {
+    // `UCKeyboardLayout` data, held in u32s so it's aligned the way it's read
+    layout: Vec<u32>,
+    keyboard_type: u32,
+    input_method: bool,
+}
+
+/// Works out what keys type under the current keyboard layout.
+///
+/// The text input APIs have to be called on the main thread, so the layout is copied there
+/// every so often and keys are translated against the copy. Until the first copy lands,
+/// and in processes that don't run the main queue, nothing is translated.
+pub struct KeyTranslator {
+    keyboard: std::sync::Arc<std::sync::Mutex<Option<KeyboardSnapshot>>>,
+    last_refresh: Option<std::time::Instant>,
+}
+
+impl KeyTranslator {
+    pub fn start() -> Self {
+        let mut this = Self {
+            keyboard: Default::default(),
+            last_refresh: None,
+        };
+        this.refresh();
+
+        this
+    }
+
+    fn refresh(&mut self) {
+        if matches!(self.last_refresh, Some(t) if t.elapsed() < KEYBOARD_REFRESH_INTERVAL) {
+            return;
+        }
+        self.last_refresh = Some(std::time::Instant::now());
+
+        extern "C" fn copy_keyboard(context: *mut c_void) {
+            let keyboard = unsafe {
+                Box::from_raw(
+                    context as *mut std::sync::Arc<std::sync::Mutex<Option<KeyboardSnapshot>>>,
+                )
+            };
+
+            if let Some(snapshot) = unsafe { keyboard_snapshot() } {
+                *keyboard.lock().unwrap() = Some(snapshot);
+            }
+        }
+
+        let context = Box::into_raw(Box::new(self.keyboard.clone()));
+        unsafe {
+            dispatch_async_f(
+                &_dispatch_main_q as *const c_void,
+                context as *mut c_void,
+                copy_keyboard,
+            );
+        }
+    }
+
+    /// What `key`, a `device_query` key name, types with shift and option as given
+    pub fn translate(&mut self, key: &str, shift: bool, option: bool) -> KeyText {
+        self.refresh();
+
+        let Some(key_code) = virtual_key_code(key) else {
+            return KeyText::None;
+        };
+        let keyboard = self.keyboard.lock().unwrap();
+        let Some(keyboard) = &*keyboard else {
+            return KeyText::None;
+        };
+
+        let modifiers =
+            if shift { SHIFT_KEY_STATE } else { 0 } | if option { OPTION_KEY_STATE } else { 0 };
+        let translate = |options: u32| {
+            let mut dead_key_state = 0;
+            let mut buffer = [0u16; 8];
+            let mut length = 0;
+
+            let status = unsafe {
+                UCKeyTranslate(
+                    keyboard.layout.as_ptr() as *const c_void,
+                    key_code,
+                    K_UC_KEY_ACTION_DOWN,
+                    modifiers,
+                    keyboard.keyboard_type,
+                    options,
+                    &mut dead_key_state,
+                    buffer.len(),
+                    &mut length,
+                    buffer.as_mut_ptr(),
+                )
+            };
+
+            (status == 0).then(|| (String::from_utf16_lossy(&buffer[..length]), dead_key_state))
+        };
+
+        match translate(0) {
+            // a dead key types nothing by itself, so it's asked for the accent it stands for
+            Some((text, dead_key_state)) if text.is_empty() && dead_key_state != 0 => {
+                translate(K_UC_KEY_TRANSLATE_NO_DEAD_KEYS_MASK)
+                    .and_then(|(accent, _)| accent.chars().next())
+                    .map(KeyText::Dead)
+                    .unwrap_or(KeyText::None)
+            }
+            Some((text, _)) if text.chars().any(|c| !c.is_control()) => KeyText::Text(text),
+            _ => KeyText::None,
+        }
+    }
+
+    /// Whether an input method, rather than a plain keyboard layout, is taking the keys
+    pub fn input_method_active(&self) -> bool {
+        self.keyboard
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|keyboard| keyboard.input_method)
+    }
+}
+
+// Has to be called on the main thread
+unsafe fn keyboard_snapshot() -> Option<KeyboardSnapshot> {
+    let input_source = TISCopyCurrentKeyboardInputSource();
+    let input_method = !input_source.is_null() && {
+        let source_type = TISGetInputSourceProperty(input_source, kTISPropertyInputSourceType);
+        let is_layout = !source_type.is_null()
+            && CFString::wrap_under_get_rule(source_type as CFStringRef)
+                == CFString::wrap_under_get_rule(kTISTypeKeyboardLayout);
+        CFRelease(input_source);
+        !is_layout
+    };
+
+    let layout_source = TISCopyCurrentKeyboardLayoutInputSource();
+    if layout_source.is_null() {
+        return None;
+    }
+
+    let data = TISGetInputSourceProperty(layout_source, kTISPropertyUnicodeKeyLayoutData);
+    let layout = (!data.is_null()).then(|| {
+        let bytes = core_foundation::data::CFData::wrap_under_get_rule(
+            data as core_foundation::data::CFDataRef,
+        );
+        let mut layout = vec![0u32; bytes.len().div_ceil(4)];
+        std::ptr::copy_nonoverlapping(
+            bytes.bytes().as_ptr(),
+            layout.as_mut_ptr() as *mut u8,
+            bytes.len(),
+        );
+        layout
+    });
+    CFRelease(layout_source);
+
+    Some(KeyboardSnapshot {
+        layout: layout?,
+        keyboard_type: LMGetKbdType() as u32,
+        input_method,
+    })
+}
+
+// The kVK_ANSI_* code of the key `device_query` gives this name. Those name the key's
+// position on a US keyboard, whatever the layout.
+fn virtual_key_code(key: &str) -> Option<u16> {
+    Some(match key {
+        "A" => 0x00,
+        "S" => 0x01,
+        "D" => 0x02,
+        "F" => 0x03,
+        "H" => 0x04,
+        "G" => 0x05,
+        "Z" => 0x06,
+        "X" => 0x07,
+        "C" => 0x08,
+        "V" => 0x09,
+        "B" => 0x0B,
+        "Q" => 0x0C,
+        "W" => 0x0D,
+        "E" => 0x0E,
+        "R" => 0x0F,
+        "Y" => 0x10,
+        "T" => 0x11,
+        "Key1" => 0x12,
+        "Key2" => 0x13,
+        "Key3" => 0x14,
+        "Key4" => 0x15,
+        "Key6" => 0x16,
+        "Key5" => 0x17,
+        "Equal" => 0x18,
+        "Key9" => 0x19,
+        "Key7" => 0x1A,
+        "Minus" => 0x1B,
+        "Key8" => 0x1C,
+        "Key0" => 0x1D,
+        "RightBracket" => 0x1E,
+        "O" => 0x1F,
+        "U" => 0x20,
+        "LeftBracket" => 0x21,
+        "I" => 0x22,
+        "P" => 0x23,
+        "L" => 0x25,
+        "J" => 0x26,
+        "Apostrophe" => 0x27,
+        "K" => 0x28,
+        "Semicolon" => 0x29,
+        "BackSlash" => 0x2A,
+        "Comma" => 0x2B,
+        "Slash" => 0x2C,
+        "N" => 0x2D,
+        "M" => 0x2E,
+        "Dot" => 0x2F,
+        "Grave" => 0x32,
+        _ => return None,
+    })
+}
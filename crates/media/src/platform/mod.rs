@@ -19,6 +19,24 @@ pub struct Bounds {
     pub height: f64,
 }
 
+// A scroll wheel or trackpad scroll, positive towards the bottom and right of the content.
+// macOS reports pixels and Windows wheel notches.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollDelta {
+    pub x: f64,
+    pub y: f64,
+}
+
+// What a key types under the keyboard layout in use, going by the recorder's key names
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyText {
+    Text(String),
+    // a dead key, which puts this accent on whatever's typed next
+    Dead(char),
+    // keys like Enter and the arrows, which don't type anything
+    None,
+}
+
 #[derive(Debug)]
 pub struct Window {
     pub window_id: u32,
@@ -3,7 +3,7 @@ use std::ffi::{c_void, OsString};
 use std::os::windows::ffi::OsStringExt;
 use std::path::PathBuf;
 
-use super::{Bounds, CursorShape, Window};
+use super::{Bounds, CursorShape, KeyText, ScrollDelta, Window};
 
 use tracing::debug;
 use windows::core::{PCWSTR, PWSTR};
@@ -358,3 +358,426 @@ pub fn display_for_window(window: HWND) -> Option<HMONITOR> {
         Some(hwmonitor)
     }
 }
+
+/// Looks up the UI Automation element at screen points, on a thread of its own that sets up
+/// COM and the automation client once, rather than on whichever thread asks.
+pub struct ElementSampler {
+    tx: std::sync::mpsc::Sender<(f64, f64)>,
+    rx: std::sync::mpsc::Receiver<Option<Bounds>>,
+}
+
+impl ElementSampler {
+    pub fn start() -> Self {
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+            COINIT_MULTITHREADED,
+        };
+        use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation};
+
+        let (tx, points) = std::sync::mpsc::channel::<(f64, f64)>();
+        let (bounds_tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || unsafe {
+            if let Err(e) = CoInitializeEx(None, COINIT_MULTITHREADED).ok() {
+                debug!("failed to initialize COM for element sampling: {e}");
+                return;
+            }
+
+            // released before COM is
+            {
+                let automation: IUIAutomation =
+                    match CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER) {
+                        Ok(automation) => automation,
+                        Err(e) => {
+                            debug!("failed to create UI Automation client: {e}");
+                            CoUninitialize();
+                            return;
+                        }
+                    };
+
+                // runs until the sampler is dropped
+                for (x, y) in points {
+                    if bounds_tx.send(element_bounds(&automation, x, y)).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            CoUninitialize();
+        });
+
+        Self { tx, rx }
+    }
+
+    /// Bounds of the element at the given screen point
+    pub fn bounds_at(&self, x: f64, y: f64) -> Option<Bounds> {
+        self.tx.send((x, y)).ok()?;
+        self.rx.recv().ok().flatten()
+    }
+}
+
+unsafe fn element_bounds(
+    automation: &windows::Win32::UI::Accessibility::IUIAutomation,
+    x: f64,
+    y: f64,
+) -> Option<Bounds> {
+    use windows::Win32::Foundation::POINT;
+
+    let element = automation
+        .ElementFromPoint(POINT {
+            x: x as i32,
+            y: y as i32,
+        })
+        .ok()?;
+    let rect = element.CurrentBoundingRectangle().ok()?;
+
+    Some(Bounds {
+        x: rect.left as f64,
+        y: rect.top as f64,
+        width: (rect.right - rect.left) as f64,
+        height: (rect.bottom - rect.top) as f64,
+    })
+}
+
+thread_local! {
+    static SCROLL_TX: std::cell::RefCell<Option<std::sync::mpsc::Sender<ScrollDelta>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Listens for scroll events anywhere on the system through a low level mouse hook,
+/// on a thread pumping the messages the hook is called from.
+pub struct ScrollListener {
+    thread_id: Option<u32>,
+    rx: std::sync::mpsc::Receiver<ScrollDelta>,
+}
+
+impl ScrollListener {
+    pub fn start() -> Self {
+        use windows::Win32::System::Threading::GetCurrentThreadId;
+        use windows::Win32::UI::WindowsAndMessaging::{
+            GetMessageW, SetWindowsHookExW, UnhookWindowsHookEx, MSG, WH_MOUSE_LL,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (thread_id_tx, thread_id_rx) = std::sync::mpsc::sync_channel(1);
+
+        std::thread::spawn(move || unsafe {
+            SCROLL_TX.with(|scroll_tx| *scroll_tx.borrow_mut() = Some(tx));
+
+            let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(scroll_hook), None, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    debug!("failed to install scroll hook: {e}");
+                    thread_id_tx.send(None).ok();
+                    return;
+                }
+            };
+
+            thread_id_tx.send(Some(GetCurrentThreadId())).ok();
+
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {}
+
+            let _ = UnhookWindowsHookEx(hook);
+        });
+
+        Self {
+            thread_id: thread_id_rx.recv().ok().flatten(),
+            rx,
+        }
+    }
+
+    /// Scrolls since the last call
+    pub fn drain(&self) -> impl Iterator<Item = ScrollDelta> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl Drop for ScrollListener {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::WPARAM;
+        use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+
+        if let Some(thread_id) = self.thread_id {
+            let _ = unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+    }
+}
+
+unsafe extern "system" fn scroll_hook(
+    code: i32,
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: LPARAM,
+) -> windows::Win32::Foundation::LRESULT {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, MSLLHOOKSTRUCT, WHEEL_DELTA, WM_MOUSEHWHEEL, WM_MOUSEWHEEL,
+    };
+
+    let message = wparam.0 as u32;
+    if code >= 0 && (message == WM_MOUSEWHEEL || message == WM_MOUSEHWHEEL) {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        // the high word is the signed distance, positive for away from the user or right
+        let notches = (info.mouseData >> 16) as u16 as i16 as f64 / WHEEL_DELTA as f64;
+
+        let delta = if message == WM_MOUSEWHEEL {
+            ScrollDelta {
+                x: 0.0,
+                y: -notches,
+            }
+        } else {
+            ScrollDelta { x: notches, y: 0.0 }
+        };
+
+        SCROLL_TX.with(|tx| {
+            if let Some(tx) = &*tx.borrow() {
+                tx.send(delta).ok();
+            }
+        });
+    }
+
+    CallNextHookEx(None, code, wparam, lparam)
+}
+
+/// Hears the text input method editors commit, like the characters a Chinese or Japanese
+/// IME turns keys into. That's the `GCS_RESULTSTR` an app gets with `WM_IME_COMPOSITION`,
+/// which only the app itself can read, so it's taken from the text edit events UI Automation
+/// raises for finished compositions instead, on a thread of its own that keeps COM set up.
+pub struct InputMethodListener {
+    // dropping it ends the thread
+    _stop: std::sync::mpsc::Sender<()>,
+    rx: std::sync::mpsc::Receiver<String>,
+}
+
+impl InputMethodListener {
+    pub fn start() -> Self {
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+            COINIT_MULTITHREADED,
+        };
+        use windows::Win32::UI::Accessibility::{
+            CUIAutomation8, IUIAutomation3, IUIAutomationCacheRequest,
+            IUIAutomationTextEditTextChangedEventHandler, TextEditChangeType_CompositionFinalized,
+            TreeScope_Subtree,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+
+        std::thread::spawn(move || unsafe {
+            if let Err(e) = CoInitializeEx(None, COINIT_MULTITHREADED).ok() {
+                debug!("failed to initialize COM for input method text: {e}");
+                return;
+            }
+
+            // released before COM is
+            {
+                let automation: IUIAutomation3 =
+                    match CoCreateInstance(&CUIAutomation8, None, CLSCTX_INPROC_SERVER) {
+                        Ok(automation) => automation,
+                        Err(e) => {
+                            debug!("failed to create UI Automation client: {e}");
+                            CoUninitialize();
+                            return;
+                        }
+                    };
+
+                let handler: IUIAutomationTextEditTextChangedEventHandler =
+                    CompositionHandler { tx }.into();
+                let root = automation.GetRootElement().and_then(|root| {
+                    automation
+                        .AddTextEditTextChangedEventHandler(
+                            &root,
+                            TreeScope_Subtree,
+                            TextEditChangeType_CompositionFinalized,
+                            None::<&IUIAutomationCacheRequest>,
+                            &handler,
+                        )
+                        .map(|_| root)
+                });
+
+                match root {
+                    Ok(root) => {
+                        // runs until the listener is dropped
+                        stopped.recv().ok();
+                        let _ = automation.RemoveTextEditTextChangedEventHandler(&root, &handler);
+                    }
+                    Err(e) => debug!("failed to listen for input method text: {e}"),
+                }
+            }
+
+            CoUninitialize();
+        });
+
+        Self { _stop: stop, rx }
+    }
+
+    /// Text committed since the last call
+    pub fn drain(&self) -> impl Iterator<Item = String> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+#[windows::core::implement(
+    windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler
+)]
+struct CompositionHandler {
+    tx: std::sync::mpsc::Sender<String>,
+}
+
+impl windows::Win32::UI::Accessibility::IUIAutomationTextEditTextChangedEventHandler_Impl
+    for CompositionHandler_Impl
+{
+    fn HandleTextEditTextChangedEvent(
+        &self,
+        _sender: Option<&windows::Win32::UI::Accessibility::IUIAutomationElement>,
+        _change: windows::Win32::UI::Accessibility::TextEditChangeType,
+        strings: *const windows::Win32::System::Com::SAFEARRAY,
+    ) -> windows::core::Result<()> {
+        // the finalized text is the only string for this kind of change
+        if let Some(text) = unsafe { first_string(strings) }.filter(|text| !text.is_empty()) {
+            self.tx.send(text).ok();
+        }
+
+        Ok(())
+    }
+}
+
+unsafe fn first_string(strings: *const windows::Win32::System::Com::SAFEARRAY) -> Option<String> {
+    use windows::core::BSTR;
+    use windows::Win32::System::Ole::{SafeArrayGetElement, SafeArrayGetLBound};
+
+    if strings.is_null() {
+        return None;
+    }
+
+    let index = SafeArrayGetLBound(strings, 1).ok()?;
+    let mut text = BSTR::default();
+    SafeArrayGetElement(strings, &index, &mut text as *mut BSTR as *mut c_void).ok()?;
+
+    Some(text.to_string())
+}
+
+/// Works out what keys type under the keyboard layout of the window in the foreground.
+pub struct KeyTranslator;
+
+impl KeyTranslator {
+    pub fn start() -> Self {
+        Self
+    }
+
+    /// What `key`, a `device_query` key name, types with shift and AltGr as given
+    pub fn translate(&mut self, key: &str, shift: bool, alt_gr: bool) -> KeyText {
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            GetKeyboardLayout, MapVirtualKeyExW, ToUnicodeEx, MAPVK_VK_TO_VSC, VK_CONTROL, VK_MENU,
+            VK_SHIFT,
+        };
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+        // ToUnicodeEx otherwise stores a dead key to apply to the next key the user types,
+        // which would take it away from the app they're typing into
+        const DONT_CHANGE_KEYBOARD_STATE: u32 = 1 << 2;
+
+        let Some(virtual_key) = virtual_key(key) else {
+            return KeyText::None;
+        };
+
+        unsafe {
+            let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+            let layout = GetKeyboardLayout(thread_id);
+
+            let mut keyboard_state = [0u8; 256];
+            if shift {
+                keyboard_state[VK_SHIFT.0 as usize] = 0x80;
+            }
+            // AltGr is seen as Ctrl and Alt together
+            if alt_gr {
+                keyboard_state[VK_CONTROL.0 as usize] = 0x80;
+                keyboard_state[VK_MENU.0 as usize] = 0x80;
+            }
+
+            let mut buffer = [0u16; 8];
+            let length = ToUnicodeEx(
+                virtual_key,
+                MapVirtualKeyExW(virtual_key, MAPVK_VK_TO_VSC, layout),
+                &keyboard_state,
+                &mut buffer,
+                DONT_CHANGE_KEYBOARD_STATE,
+                layout,
+            );
+
+            match length {
+                // a dead key, with the accent it stands for in the buffer
+                ..=-1 => char::from_u32(buffer[0] as u32)
+                    .map(KeyText::Dead)
+                    .unwrap_or(KeyText::None),
+                0 => KeyText::None,
+                length => {
+                    let text = String::from_utf16_lossy(&buffer[..length as usize]);
+                    if text.chars().any(|c| !c.is_control()) {
+                        KeyText::Text(text)
+                    } else {
+                        KeyText::None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the foreground window's input method editor is turned on and taking the keys
+    pub fn input_method_active(&self) -> bool {
+        use windows::Win32::Foundation::WPARAM;
+        use windows::Win32::UI::Input::Ime::ImmGetDefaultIMEWnd;
+        use windows::Win32::UI::WindowsAndMessaging::{
+            GetForegroundWindow, SendMessageW, WM_IME_CONTROL,
+        };
+
+        const IMC_GETOPENSTATUS: usize = 0x0005;
+
+        unsafe {
+            let ime_window = ImmGetDefaultIMEWnd(GetForegroundWindow());
+            if ime_window.is_invalid() {
+                return false;
+            }
+
+            SendMessageW(
+                ime_window,
+                WM_IME_CONTROL,
+                WPARAM(IMC_GETOPENSTATUS),
+                LPARAM(0),
+            )
+            .0 != 0
+        }
+    }
+}
+
+// The virtual key code of the key `device_query` gives this name
+fn virtual_key(key: &str) -> Option<u32> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        VK_OEM_1, VK_OEM_2, VK_OEM_3, VK_OEM_4, VK_OEM_5, VK_OEM_6, VK_OEM_7, VK_OEM_COMMA,
+        VK_OEM_MINUS, VK_OEM_PERIOD, VK_OEM_PLUS,
+    };
+
+    let virtual_key = match key {
+        "Minus" => VK_OEM_MINUS,
+        "Equal" => VK_OEM_PLUS,
+        "LeftBracket" => VK_OEM_4,
+        "RightBracket" => VK_OEM_6,
+        "BackSlash" => VK_OEM_5,
+        "Semicolon" => VK_OEM_1,
+        "Apostrophe" => VK_OEM_7,
+        "Comma" => VK_OEM_COMMA,
+        "Dot" => VK_OEM_PERIOD,
+        "Slash" => VK_OEM_2,
+        "Grave" => VK_OEM_3,
+        // letters and digits share their codes with their ASCII characters
+        key => {
+            let mut chars = key.strip_prefix("Key").unwrap_or(key).chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_uppercase() || c.is_ascii_digit() => Some(c as u32),
+                _ => None,
+            };
+        }
+    };
+
+    Some(virtual_key.0 as u32)
+}
@@ -1,7 +1,9 @@
 mod audio_input;
 mod camera;
 mod screen_capture;
+mod system_audio;
 
 pub use audio_input::*;
 pub use camera::*;
 pub use screen_capture::*;
+pub use system_audio::*;
@@ -33,6 +33,10 @@ pub struct CaptureWindow {
     pub name: String,
     pub bounds: Bounds,
     pub refresh_rate: u32,
+    // capture only the window's own contents instead of cropping its display, so other
+    // windows moving over it don't end up in the recording
+    #[serde(default)]
+    pub isolated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
@@ -131,13 +135,17 @@ impl<TCaptureFormat> ScreenCaptureSource<TCaptureFormat> {
         this
     }
 
+    // The space cursor positions are recorded in. Areas use their whole screen, since
+    // their bounds are relative to it and the project maps the cursor into the area.
     pub fn get_bounds(&self) -> Bounds {
         match &self.target {
             ScreenCaptureTarget::Window(capture_window) => capture_window.bounds,
             ScreenCaptureTarget::Screen(capture_screen) => {
                 platform::monitor_bounds(capture_screen.id)
             }
-            ScreenCaptureTarget::Area(capture_area) => capture_area.bounds,
+            ScreenCaptureTarget::Area(capture_area) => {
+                platform::monitor_bounds(capture_area.screen.id)
+            }
         }
     }
 
@@ -154,6 +162,7 @@ impl<TCaptureFormat> ScreenCaptureSource<TCaptureFormat> {
             .collect();
 
         let crop_area = match &self.target {
+            ScreenCaptureTarget::Window(capture_window) if capture_window.isolated => None,
             ScreenCaptureTarget::Window(capture_window) => Some(Area {
                 size: Size {
                     width: capture_window.bounds.width,
@@ -178,6 +187,12 @@ impl<TCaptureFormat> ScreenCaptureSource<TCaptureFormat> {
         };
 
         let target = match &self.target {
+            // scap captures window targets by themselves, with ScreenCaptureKit's desktop
+            // independent window filter on macOS and Windows.Graphics.Capture on Windows
+            ScreenCaptureTarget::Window(w) if w.isolated => targets
+                .iter()
+                .find(|t| matches!(t, Target::Window(window) if window.id == w.id))
+                .cloned(),
             ScreenCaptureTarget::Window(w) => {
                 let window_target = targets
                     .iter()
@@ -346,8 +361,9 @@ fn inner<T>(
 ) {
     trace!("Preparing screen capture source thread...");
 
+    // isolated windows are captured even when covered, so there's no need to raise them
     let maybe_capture_window_id = match &source.target {
-        ScreenCaptureTarget::Window(window) => Some(window.id),
+        ScreenCaptureTarget::Window(window) if !window.isolated => Some(window.id),
         _ => None,
     };
     let mut capturer = match Capturer::build(source.create_options()) {
@@ -509,6 +525,7 @@ pub fn list_windows() -> Vec<(CaptureWindow, Target)> {
                             name: platform_window.name.clone(),
                             bounds: platform_window.bounds,
                             refresh_rate: get_target_fps(&target).unwrap_or_default(),
+                            isolated: false,
                         },
                         target,
                     )
@@ -0,0 +1,395 @@
+use std::time::{Duration, Instant};
+
+use flume::Sender;
+use tracing::{error, info};
+
+use crate::{
+    data::{AudioInfo, FFAudio},
+    pipeline::{
+        clock::RealTimeClock,
+        control::Control,
+        task::{PipelineReadySignal, PipelineSourceTask},
+    },
+    MediaError,
+};
+
+#[cfg(target_os = "macos")]
+use macos as imp;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use unsupported as imp;
+#[cfg(target_os = "windows")]
+use windows as imp;
+
+// Interleaved samples in the source's `AudioInfo` format
+pub struct SystemAudioSamples {
+    data: Vec<u8>,
+    captured_at: Instant,
+}
+
+// Gaps shorter than this are jitter in when buffers arrive rather than missing audio
+const MAX_GAP_MICROS: i64 = 20_000;
+
+// Everything the system plays through its speakers, captured with ScreenCaptureKit on macOS
+// and WASAPI loopback on Windows. Recorded separately from the microphone so the two can
+// be balanced when the project is played back and exported.
+pub struct SystemAudioSource {
+    audio_info: AudioInfo,
+}
+
+impl SystemAudioSource {
+    pub fn init() -> Result<Self, MediaError> {
+        Ok(Self {
+            audio_info: imp::audio_info()?,
+        })
+    }
+
+    pub fn info(&self) -> AudioInfo {
+        self.audio_info
+    }
+
+    // The encoder lays samples out back to back, so time where nothing was delivered has to
+    // be filled in. WASAPI loopback in particular sends nothing while nothing is playing.
+    fn send_silence(&self, output: &Sender<FFAudio>, from: i64, to: i64) -> Result<(), MediaError> {
+        if to - from < MAX_GAP_MICROS {
+            return Ok(());
+        }
+
+        let samples = ((to - from) * self.audio_info.sample_rate as i64 / 1_000_000) as usize;
+        let data = vec![0; samples * self.audio_info.channels * self.audio_info.sample_size()];
+
+        output
+            .send(self.audio_info.wrap_frame(&data, from))
+            .map_err(|_| MediaError::Any("Pipeline is unreachable! Stopping capture"))
+    }
+
+    fn process_samples(
+        &self,
+        clock: &mut RealTimeClock<Instant>,
+        output: &Sender<FFAudio>,
+        samples: SystemAudioSamples,
+        end: &mut i64,
+    ) -> Result<(), MediaError> {
+        let Some(timestamp) = clock.timestamp_for(samples.captured_at) else {
+            return Ok(());
+        };
+
+        self.send_silence(output, *end, timestamp)?;
+
+        let frame = self.audio_info.wrap_frame(&samples.data, timestamp);
+        *end = timestamp + frame.samples() as i64 * 1_000_000 / self.audio_info.sample_rate as i64;
+
+        output
+            .send(frame)
+            .map_err(|_| MediaError::Any("Pipeline is unreachable! Stopping capture"))
+    }
+}
+
+impl PipelineSourceTask for SystemAudioSource {
+    type Output = FFAudio;
+
+    type Clock = RealTimeClock<Instant>;
+
+    fn run(
+        &mut self,
+        mut clock: Self::Clock,
+        ready_signal: PipelineReadySignal,
+        mut control_signal: crate::pipeline::control::PipelineControlSignal,
+        output: Sender<Self::Output>,
+    ) {
+        info!("Preparing system audio source thread...");
+
+        let (samples_tx, samples_rx) = flume::bounded::<SystemAudioSamples>(16);
+        let mut capture = None;
+        // end of the last samples sent, in microseconds since the segment started
+        let mut end = 0;
+
+        ready_signal.send(Ok(())).unwrap();
+
+        loop {
+            match control_signal.last() {
+                Some(Control::Play) => {
+                    if capture.is_none() {
+                        match imp::start(samples_tx.clone()) {
+                            Ok(started) => capture = Some(started),
+                            Err(error) => {
+                                error!("Failed to start system audio capture: {error}");
+                                break;
+                            }
+                        }
+                    }
+
+                    // times out so shutdowns are noticed while nothing is playing
+                    match samples_rx.recv_timeout(Duration::from_millis(100)) {
+                        Ok(samples) => {
+                            if let Err(error) =
+                                self.process_samples(&mut clock, &output, samples, &mut end)
+                            {
+                                error!("{error}");
+                                break;
+                            }
+                        }
+                        Err(flume::RecvTimeoutError::Timeout) => {}
+                        Err(flume::RecvTimeoutError::Disconnected) => {
+                            error!("Lost connection with the system audio capture");
+                            break;
+                        }
+                    }
+                }
+                Some(Control::Shutdown) | None => {
+                    if let Some(capture) = capture.take() {
+                        capture.stop();
+                    }
+
+                    for samples in samples_rx.drain() {
+                        if let Err(error) =
+                            self.process_samples(&mut clock, &output, samples, &mut end)
+                        {
+                            error!("{error}");
+                            break;
+                        }
+                    }
+
+                    // pad out silence at the end too, so the track lasts as long as the video
+                    if let Some(now) = clock.timestamp_for(Instant::now()) {
+                        self.send_silence(&output, end, now).ok();
+                    }
+                    break;
+                }
+            }
+        }
+
+        info!("Shut down system audio source thread.");
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::time::Instant;
+
+    use cidre::{arc, cm, define_obj_type, dispatch, ns, objc, sc};
+    use flume::Sender;
+
+    use super::SystemAudioSamples;
+    use crate::{
+        data::{AudioInfo, Sample, Type},
+        MediaError,
+    };
+
+    const SAMPLE_RATE: u32 = 48_000;
+    const CHANNELS: u16 = 2;
+
+    pub fn audio_info() -> Result<AudioInfo, MediaError> {
+        Ok(AudioInfo::new(
+            Sample::F32(Type::Planar),
+            SAMPLE_RATE,
+            CHANNELS,
+        )?)
+    }
+
+    #[repr(C)]
+    pub struct AudioOutputInner {
+        tx: Sender<SystemAudioSamples>,
+    }
+
+    define_obj_type!(
+        AudioOutput + sc::stream::OutputImpl,
+        AudioOutputInner,
+        SYSTEM_AUDIO_OUTPUT
+    );
+
+    impl sc::stream::Output for AudioOutput {}
+
+    #[objc::add_methods]
+    impl sc::stream::OutputImpl for AudioOutput {
+        extern "C" fn impl_stream_did_output_sample_buf(
+            &mut self,
+            _cmd: Option<&objc::Sel>,
+            _stream: &sc::Stream,
+            sample_buf: &mut cm::SampleBuf,
+            kind: sc::OutputType,
+        ) {
+            if kind != sc::OutputType::Audio {
+                return;
+            }
+
+            let Ok(buf_list) = sample_buf.audio_buf_list::<{ CHANNELS as usize }>() else {
+                return;
+            };
+
+            // ScreenCaptureKit hands over one buffer per channel, which we interleave to
+            // match what cpal gives us for the microphone
+            let planes = buf_list
+                .list()
+                .buffers
+                .iter()
+                .map(|buf| unsafe {
+                    std::slice::from_raw_parts(buf.data, buf.data_bytes_size as usize)
+                })
+                .collect::<Vec<_>>();
+
+            let sample_size = std::mem::size_of::<f32>();
+            let samples = planes[0].len() / sample_size;
+            let mut data = Vec::with_capacity(planes[0].len() * planes.len());
+            for i in 0..samples {
+                for plane in &planes {
+                    data.extend_from_slice(&plane[i * sample_size..(i + 1) * sample_size]);
+                }
+            }
+
+            self.inner_mut()
+                .tx
+                .try_send(SystemAudioSamples {
+                    data,
+                    captured_at: Instant::now(),
+                })
+                .ok();
+        }
+    }
+
+    pub struct Capture {
+        stream: arc::R<sc::Stream>,
+        _output: arc::R<AudioOutput>,
+    }
+
+    pub fn start(tx: Sender<SystemAudioSamples>) -> Result<Capture, MediaError> {
+        let content = futures::executor::block_on(sc::ShareableContent::current())
+            .map_err(|_| MediaError::Any("Failed to get shareable content"))?;
+        let displays = content.displays();
+        let display = displays
+            .iter()
+            .next()
+            .ok_or(MediaError::Any("No display to capture audio from"))?;
+
+        // audio isn't tied to a display, but ScreenCaptureKit needs one to filter on
+        let filter = sc::ContentFilter::with_display_excluding_windows(display, &ns::Array::new());
+
+        let mut cfg = sc::StreamCfg::new();
+        cfg.set_captures_audio(true);
+        cfg.set_excludes_current_process_audio(true);
+        cfg.set_sample_rate(SAMPLE_RATE as _);
+        cfg.set_channel_count(CHANNELS as _);
+
+        let stream = sc::Stream::new(&filter, &cfg);
+        let output = AudioOutput::with(AudioOutputInner { tx });
+        let queue = dispatch::Queue::serial_with_ar_pool();
+
+        stream
+            .add_stream_output(output.as_ref(), sc::OutputType::Audio, Some(&queue))
+            .map_err(|_| MediaError::Any("Failed to add system audio output"))?;
+
+        futures::executor::block_on(stream.start())
+            .map_err(|_| MediaError::Any("Failed to start system audio capture"))?;
+
+        Ok(Capture {
+            stream,
+            _output: output,
+        })
+    }
+
+    impl Capture {
+        pub fn stop(self) {
+            futures::executor::block_on(self.stream.stop()).ok();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::time::Instant;
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use flume::Sender;
+    use tracing::error;
+
+    use super::SystemAudioSamples;
+    use crate::{data::AudioInfo, feeds::MAX_AUDIO_CHANNELS, MediaError};
+
+    fn output_device() -> Result<(cpal::Device, cpal::SupportedStreamConfig), MediaError> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(MediaError::Any("No audio output device"))?;
+        let config = device
+            .default_output_config()
+            .map_err(|_| MediaError::Any("Failed to get the audio output's config"))?;
+
+        Ok((device, config))
+    }
+
+    pub fn audio_info() -> Result<AudioInfo, MediaError> {
+        Ok(AudioInfo::from_stream_config(&output_device()?.1)?)
+    }
+
+    // Drops the channels past the ones we record, like the microphone feed does
+    fn keep_channels(data: &[u8], channels: usize, sample_size: usize) -> Vec<u8> {
+        let kept = channels.min(MAX_AUDIO_CHANNELS as usize);
+        if kept == channels {
+            return data.to_vec();
+        }
+
+        data.chunks(channels * sample_size)
+            .flat_map(|frame| &frame[0..kept * sample_size])
+            .copied()
+            .collect()
+    }
+
+    pub struct Capture(cpal::Stream);
+
+    pub fn start(tx: Sender<SystemAudioSamples>) -> Result<Capture, MediaError> {
+        let (device, config) = output_device()?;
+        let channels = config.channels() as usize;
+        let sample_size = config.sample_format().sample_size();
+
+        // WASAPI captures an output device in loopback mode when it's opened for input
+        let stream = device
+            .build_input_stream_raw(
+                &config.config(),
+                config.sample_format(),
+                move |data, _| {
+                    tx.try_send(SystemAudioSamples {
+                        data: keep_channels(data.bytes(), channels, sample_size),
+                        captured_at: Instant::now(),
+                    })
+                    .ok();
+                },
+                |e| error!("Error in system audio stream: {e}"),
+                None,
+            )
+            .map_err(|_| MediaError::Any("Failed to open the audio output for loopback"))?;
+
+        stream
+            .play()
+            .map_err(|_| MediaError::Any("Failed to start system audio capture"))?;
+
+        Ok(Capture(stream))
+    }
+
+    impl Capture {
+        pub fn stop(self) {
+            self.0.pause().ok();
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod unsupported {
+    use flume::Sender;
+
+    use super::SystemAudioSamples;
+    use crate::{data::AudioInfo, MediaError};
+
+    pub fn audio_info() -> Result<AudioInfo, MediaError> {
+        Err(MediaError::Any(
+            "System audio capture isn't supported on this platform",
+        ))
+    }
+
+    pub struct Capture;
+
+    pub fn start(_: Sender<SystemAudioSamples>) -> Result<Capture, MediaError> {
+        audio_info().map(|_| Capture)
+    }
+
+    impl Capture {
+        pub fn stop(self) {}
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    text::eased_presence, AnnotationAnimation, AnnotationSegment, TextSegment,
+    TimelineConfiguration,
+};
+
+impl AnnotationSegment {
+    // the same as text, so the two can be timed together
+    pub const ANIMATION_DURATION: f64 = TextSegment::ANIMATION_DURATION;
+
+    // How far the annotation has come in at `time`, eased: 1 while it's fully shown, falling
+    // to 0 at the segment's edges. None outside the segment.
+    pub fn presence(&self, time: f64) -> Option<f64> {
+        if time < self.start || time >= self.end {
+            return None;
+        }
+        if self.kind.is_mask() || self.animation == AnnotationAnimation::None {
+            return Some(1.0);
+        }
+
+        Some(eased_presence(
+            self.start,
+            self.end,
+            time,
+            Self::ANIMATION_DURATION,
+        ))
+    }
+}
+
+impl TimelineConfiguration {
+    // The annotations showing at `time`, in the order they're drawn, along with how far
+    // they've come in
+    pub fn annotations_at(&self, time: f64) -> impl Iterator<Item = (&AnnotationSegment, f64)> {
+        self.annotation_segments
+            .iter()
+            .filter_map(move |annotation| Some((annotation, annotation.presence(time)?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AnnotationAnchor, AnnotationKind, XY};
+
+    use super::*;
+
+    fn annotation(kind: AnnotationKind) -> AnnotationSegment {
+        AnnotationSegment {
+            start: 1.0,
+            end: 3.0,
+            kind,
+            from: XY::new(0.2, 0.2),
+            to: XY::new(0.4, 0.3),
+            anchor: AnnotationAnchor::Frame,
+            color: None,
+            size: 0.5,
+            animation: AnnotationAnimation::Grow,
+        }
+    }
+
+    #[test]
+    fn animates_shapes() {
+        let arrow = annotation(AnnotationKind::Arrow);
+
+        assert_eq!(arrow.presence(0.5), None);
+        assert_eq!(arrow.presence(1.0), Some(0.0));
+        assert_eq!(arrow.presence(2.0), Some(1.0));
+        assert_eq!(arrow.presence(3.0), None);
+    }
+
+    #[test]
+    fn masks_stay_fully_on() {
+        let blur = annotation(AnnotationKind::Blur);
+
+        assert_eq!(blur.presence(1.0), Some(1.0));
+        assert_eq!(blur.presence(2.99), Some(1.0));
+    }
+
+    #[test]
+    fn finds_annotations_at_a_time() {
+        let mut timeline = TimelineConfiguration::from_segment_durations(&[10.0]);
+        timeline.annotation_segments = vec![
+            annotation(AnnotationKind::Rectangle),
+            AnnotationSegment {
+                start: 5.0,
+                end: 6.0,
+                ..annotation(AnnotationKind::Pixelate)
+            },
+        ];
+
+        let kinds = |time| {
+            timeline
+                .annotations_at(time)
+                .map(|(a, _)| a.kind)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds(2.0), vec![AnnotationKind::Rectangle]);
+        assert_eq!(kinds(5.5), vec![AnnotationKind::Pixelate]);
+        assert!(kinds(4.0).is_empty());
+    }
+}
@@ -0,0 +1,561 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use relative_path::{Component, RelativePath, RelativePathBuf};
+use serde_json::Value;
+
+pub const BUNDLE_EXTENSION: &str = "capproj";
+
+// A bundle is the magic, then each file as its path, size, data and the data's CRC-32,
+// then the number of files. Everything is little endian.
+const MAGIC: &[u8; 8] = b"CAPPROJ1";
+const ENTRY: u8 = 1;
+const END: u8 = 0;
+
+// Stands in for the project's folder in the bundled config's paths, and is replaced by
+// wherever the project is imported to
+const PROJECT_PLACEHOLDER: &str = "$CAP_PROJECT";
+// made again from the rest of the project, so not worth the space
+const SKIPPED_DIRS: &[&str] = &["output"];
+const CONFIG_FILE: &str = "project-config.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEntry {
+    pub path: RelativePathBuf,
+    pub size: u64,
+    pub crc32: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("This isn't a Cap project bundle")]
+    NotABundle,
+    #[error("The bundle ends early, it may not have finished copying")]
+    Truncated,
+    #[error("'{0}' in the bundle is damaged")]
+    ChecksumMismatch(RelativePathBuf),
+    #[error("'{0}' would be extracted outside of the project")]
+    UnsafePath(String),
+    #[error("The project config is invalid: {0}")]
+    Config(#[from] serde_json::Error),
+}
+
+pub struct BundleWriter<W: Write> {
+    out: W,
+    count: u32,
+}
+
+impl<W: Write> BundleWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        Ok(Self { out, count: 0 })
+    }
+
+    // Streams `size` bytes of `data` into the bundle as the file at `path`
+    pub fn add(
+        &mut self,
+        path: &RelativePath,
+        size: u64,
+        data: impl Read,
+    ) -> Result<BundleEntry, BundleError> {
+        let path_bytes = path.as_str().as_bytes();
+        let path_len = u16::try_from(path_bytes.len())
+            .map_err(|_| BundleError::UnsafePath(path.to_string()))?;
+
+        self.out.write_all(&[ENTRY])?;
+        self.out.write_all(&path_len.to_le_bytes())?;
+        self.out.write_all(path_bytes)?;
+        self.out.write_all(&size.to_le_bytes())?;
+
+        let mut crc = Crc32::default();
+        let copied = copy(&mut data.take(size), &mut self.out, &mut crc)?;
+        if copied != size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("'{path}' changed while it was being bundled"),
+            )
+            .into());
+        }
+
+        let crc32 = crc.finish();
+        self.out.write_all(&crc32.to_le_bytes())?;
+        self.count += 1;
+
+        Ok(BundleEntry {
+            path: path.to_owned(),
+            size,
+            crc32,
+        })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[END])?;
+        self.out.write_all(&self.count.to_le_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// Reads a bundle front to back, so it can be extracted while it's still being downloaded
+pub struct BundleReader<R: Read> {
+    input: R,
+    count: u32,
+    // the entry whose data hasn't been read yet
+    pending: Option<(RelativePathBuf, u64)>,
+}
+
+impl<R: Read> BundleReader<R> {
+    pub fn new(mut input: R) -> Result<Self, BundleError> {
+        let mut magic = [0; 8];
+        read_exact(&mut input, &mut magic).map_err(|_| BundleError::NotABundle)?;
+        if &magic != MAGIC {
+            return Err(BundleError::NotABundle);
+        }
+
+        Ok(Self {
+            input,
+            count: 0,
+            pending: None,
+        })
+    }
+
+    // The next file's path and size. Its data is read with `read_data`, or skipped over
+    // when this is called again.
+    pub fn next_entry(&mut self) -> Result<Option<(RelativePathBuf, u64)>, BundleError> {
+        if self.pending.is_some() {
+            self.read_data(&mut io::sink())?;
+        }
+
+        let mut kind = [0; 1];
+        read_exact(&mut self.input, &mut kind)?;
+
+        match kind[0] {
+            ENTRY => {
+                let mut len = [0; 2];
+                read_exact(&mut self.input, &mut len)?;
+                let mut path = vec![0; u16::from_le_bytes(len) as usize];
+                read_exact(&mut self.input, &mut path)?;
+                let path = String::from_utf8(path).map_err(|e| {
+                    BundleError::UnsafePath(String::from_utf8_lossy(e.as_bytes()).into())
+                })?;
+
+                let mut size = [0; 8];
+                read_exact(&mut self.input, &mut size)?;
+
+                let entry = (RelativePathBuf::from(path), u64::from_le_bytes(size));
+                self.pending = Some(entry.clone());
+                Ok(Some(entry))
+            }
+            END => {
+                let mut count = [0; 4];
+                read_exact(&mut self.input, &mut count)?;
+                if u32::from_le_bytes(count) != self.count {
+                    return Err(BundleError::Truncated);
+                }
+                Ok(None)
+            }
+            _ => Err(BundleError::NotABundle),
+        }
+    }
+
+    // Copies the current file's data to `out`, failing if it doesn't match its checksum
+    pub fn read_data(&mut self, out: &mut impl Write) -> Result<BundleEntry, BundleError> {
+        let Some((path, size)) = self.pending.take() else {
+            return Err(io::Error::other("no entry to read").into());
+        };
+
+        let mut crc = Crc32::default();
+        if copy(&mut (&mut self.input).take(size), out, &mut crc)? != size {
+            return Err(BundleError::Truncated);
+        }
+
+        let mut expected = [0; 4];
+        read_exact(&mut self.input, &mut expected)?;
+        let crc32 = crc.finish();
+        if crc32 != u32::from_le_bytes(expected) {
+            return Err(BundleError::ChecksumMismatch(path));
+        }
+
+        self.count += 1;
+
+        Ok(BundleEntry { path, size, crc32 })
+    }
+}
+
+// Bundles the project along with files its config uses from elsewhere, like background
+// images, which are put in an `assets` folder
+pub fn write_project_bundle(
+    project_path: &Path,
+    out: impl Write,
+) -> Result<Vec<BundleEntry>, BundleError> {
+    let mut writer = BundleWriter::new(out)?;
+    let mut entries = vec![];
+
+    let mut config = match std::fs::read_to_string(project_path.join(CONFIG_FILE)) {
+        Ok(config) => Some(serde_json::from_str::<Value>(&config)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let mut assets = vec![];
+    if let Some(config) = &mut config {
+        bundle_config_paths(config, project_path, &mut assets);
+    }
+
+    let mut files = vec![];
+    list_files(project_path, RelativePath::new(""), &mut files)?;
+
+    for (path, source) in files
+        .into_iter()
+        .filter(|path| path.as_str() != CONFIG_FILE)
+        .map(|path| {
+            let source = path.to_path(project_path);
+            (path, source)
+        })
+        .chain(assets)
+    {
+        let file = File::open(&source)?;
+        let size = file.metadata()?.len();
+        entries.push(writer.add(&path, size, file)?);
+    }
+
+    if let Some(config) = config {
+        let config = serde_json::to_vec_pretty(&config)?;
+        entries.push(writer.add(
+            RelativePath::new(CONFIG_FILE),
+            config.len() as u64,
+            &config[..],
+        )?);
+    }
+
+    writer.finish()?;
+
+    Ok(entries)
+}
+
+// Extracts the files `include` returns true for into `dest`, checking each one as it's
+// written. The rest are read past.
+pub fn extract_bundle(
+    input: impl Read,
+    dest: &Path,
+    include: impl Fn(&RelativePath) -> bool,
+) -> Result<Vec<BundleEntry>, BundleError> {
+    let mut reader = BundleReader::new(input)?;
+    let mut entries = vec![];
+
+    while let Some((path, _)) = reader.next_entry()? {
+        if !is_safe(&path) {
+            return Err(BundleError::UnsafePath(path.into_string()));
+        }
+        if !include(&path) {
+            continue;
+        }
+
+        let target = path.to_path(dest);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // only put in place once it's known to be intact
+        let partial = PathBuf::from(format!("{}.partial", target.display()));
+        let mut file = BufWriter::new(File::create(&partial)?);
+        let entry = reader
+            .read_data(&mut file)
+            .and_then(|entry| Ok(file.flush().map(|_| entry)?));
+        drop(file);
+
+        match entry {
+            Ok(entry) => {
+                std::fs::rename(&partial, &target)?;
+                entries.push(entry);
+            }
+            Err(e) => {
+                std::fs::remove_file(&partial).ok();
+                return Err(e);
+            }
+        }
+    }
+
+    let config_path = dest.join(CONFIG_FILE);
+    if entries.iter().any(|e| e.path.as_str() == CONFIG_FILE) {
+        let mut config: Value = serde_json::from_str(&std::fs::read_to_string(&config_path)?)?;
+        unbundle_config_paths(&mut config, dest);
+        std::fs::write(&config_path, serde_json::to_string_pretty(&config)?)?;
+    }
+
+    Ok(entries)
+}
+
+fn list_files(root: &Path, dir: &RelativePath, files: &mut Vec<RelativePathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir.to_path(root))? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        let path = dir.join(&name);
+
+        if entry.file_type()?.is_dir() {
+            if !(dir.as_str().is_empty() && SKIPPED_DIRS.contains(&name.as_str())) {
+                list_files(root, &path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// Points absolute paths in the config at the placeholder, adding files from outside the
+// project to `assets`
+fn bundle_config_paths(
+    value: &mut Value,
+    project_path: &Path,
+    assets: &mut Vec<(RelativePathBuf, PathBuf)>,
+) {
+    match value {
+        Value::String(s) => {
+            let path = Path::new(s.as_str());
+            if !path.is_absolute() {
+                return;
+            }
+
+            let relative = match path.strip_prefix(project_path) {
+                Ok(inside) => RelativePathBuf::from_path(inside).ok(),
+                Err(_) if path.is_file() => {
+                    let existing = assets.iter().find(|(_, source)| source == path);
+                    match existing {
+                        Some((relative, _)) => Some(relative.clone()),
+                        None => {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            let relative =
+                                RelativePathBuf::from(format!("assets/{}-{name}", assets.len()));
+                            assets.push((relative.clone(), path.to_owned()));
+                            Some(relative)
+                        }
+                    }
+                }
+                Err(_) => None,
+            };
+
+            if let Some(relative) = relative {
+                *s = format!("{PROJECT_PLACEHOLDER}/{relative}");
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|v| bundle_config_paths(v, project_path, assets)),
+        Value::Object(values) => values
+            .values_mut()
+            .for_each(|v| bundle_config_paths(v, project_path, assets)),
+        _ => {}
+    }
+}
+
+fn unbundle_config_paths(value: &mut Value, dest: &Path) {
+    match value {
+        Value::String(s) => {
+            if let Some(relative) = s.strip_prefix(&format!("{PROJECT_PLACEHOLDER}/")) {
+                *s = RelativePath::new(relative)
+                    .to_path(dest)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|v| unbundle_config_paths(v, dest)),
+        Value::Object(values) => values
+            .values_mut()
+            .for_each(|v| unbundle_config_paths(v, dest)),
+        _ => {}
+    }
+}
+
+// Paths that stay inside the folder they're extracted to on every platform
+fn is_safe(path: &RelativePath) -> bool {
+    path.components().next().is_some()
+        && path.components().all(|c| match c {
+            Component::Normal(name) => !name.contains([':', '\\']),
+            _ => false,
+        })
+}
+
+fn read_exact(input: &mut impl Read, buf: &mut [u8]) -> Result<(), BundleError> {
+    input.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => BundleError::Truncated,
+        _ => e.into(),
+    })
+}
+
+fn copy(input: &mut impl Read, out: &mut impl Write, crc: &mut Crc32) -> io::Result<u64> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut copied = 0;
+
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => return Ok(copied),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        crc.update(&buf[..n]);
+        out.write_all(&buf[..n])?;
+        copied += n as u64;
+    }
+}
+
+// CRC-32 as zip and PNG use it
+struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self(!0)
+    }
+}
+
+impl Crc32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 = Self::TABLE[((self.0 ^ byte as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bundle(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = BundleWriter::new(vec![]).unwrap();
+        for (path, data) in files {
+            writer
+                .add(RelativePath::new(path), data.len() as u64, *data)
+                .unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn read_all(bundle: &[u8]) -> Result<Vec<(RelativePathBuf, Vec<u8>)>, BundleError> {
+        let mut reader = BundleReader::new(bundle)?;
+        let mut files = vec![];
+        while let Some((path, _)) = reader.next_entry()? {
+            let mut data = vec![];
+            reader.read_data(&mut data)?;
+            files.push((path, data));
+        }
+        Ok(files)
+    }
+
+    #[test]
+    fn crc32_matches_the_standard() {
+        let mut crc = Crc32::default();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF43926);
+    }
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let bundle = bundle(&[("a.json", b"{}"), ("content/b.mp4", &[1, 2, 3])]);
+
+        assert_eq!(
+            read_all(&bundle).unwrap(),
+            vec![
+                (RelativePathBuf::from("a.json"), b"{}".to_vec()),
+                (RelativePathBuf::from("content/b.mp4"), vec![1, 2, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_entries_that_are_not_read() {
+        let bundle = bundle(&[("a", b"first"), ("b", b"second")]);
+        let mut reader = BundleReader::new(&bundle[..]).unwrap();
+
+        reader.next_entry().unwrap();
+        let (path, size) = reader.next_entry().unwrap().unwrap();
+        assert_eq!((path.as_str(), size), ("b", 6));
+    }
+
+    #[test]
+    fn notices_damage_and_truncation() {
+        let mut damaged = bundle(&[("a", b"hello")]);
+        let data_start = MAGIC.len() + 1 + 2 + 1 + 8;
+        damaged[data_start] ^= 1;
+        assert!(matches!(
+            read_all(&damaged),
+            Err(BundleError::ChecksumMismatch(_))
+        ));
+
+        let whole = bundle(&[("a", b"hello")]);
+        assert!(matches!(
+            read_all(&whole[..whole.len() - 3]),
+            Err(BundleError::Truncated)
+        ));
+        assert!(matches!(
+            read_all(b"not a bundle"),
+            Err(BundleError::NotABundle)
+        ));
+    }
+
+    #[test]
+    fn only_extracts_inside_the_project() {
+        assert!(is_safe(RelativePath::new("content/segments/display.mp4")));
+        assert!(!is_safe(RelativePath::new("../outside")));
+        assert!(!is_safe(RelativePath::new("content/../../outside")));
+        assert!(!is_safe(RelativePath::new("C:/Windows")));
+        assert!(!is_safe(RelativePath::new("")));
+    }
+
+    #[test]
+    fn config_paths_follow_the_project() {
+        let project = std::env::temp_dir().join("project.cap");
+        let inside = project.join("content/bg.png");
+        let mut config =
+            serde_json::json!({ "background": inside.to_string_lossy(), "preset": "sequoia-dark" });
+
+        let mut assets = vec![];
+        bundle_config_paths(&mut config, &project, &mut assets);
+        assert_eq!(config["background"], "$CAP_PROJECT/content/bg.png");
+        assert_eq!(config["preset"], "sequoia-dark");
+
+        let dest = std::env::temp_dir().join("imported.cap");
+        unbundle_config_paths(&mut config, &dest);
+        assert_eq!(
+            config["background"],
+            dest.join("content")
+                .join("bg.png")
+                .to_string_lossy()
+                .as_ref()
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{markers::webvtt_timestamp, CaptionSegment, TimelineConfiguration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionFormat {
+    Srt,
+    WebVtt,
+}
+
+impl CaptionFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::WebVtt),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptionsError {
+    #[error("Invalid cue timing '{0}'")]
+    InvalidTiming(String),
+}
+
+impl TimelineConfiguration {
+    // The captions showing at `time`, in the order they're drawn
+    pub fn captions_at(&self, time: f64) -> impl Iterator<Item = &CaptionSegment> {
+        self.caption_segments
+            .iter()
+            .filter(move |c| time >= c.start && time < c.end)
+    }
+}
+
+// Reads the cues of an SRT or WebVTT file, sorted by when they start. Styling, positioning
+// and formatting tags are dropped, and so are cues without text.
+pub fn parse_captions(contents: &str) -> Result<Vec<CaptionSegment>, CaptionsError> {
+    let contents = contents
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n");
+    let mut cues = vec![];
+
+    for block in contents.split("\n\n") {
+        let mut lines = block.lines().map(str::trim_end);
+        // SRT cues and some WebVTT ones have a number or id before their timing. Blocks
+        // without one, like WebVTT's header, notes and styles, aren't cues.
+        let Some(timing) = lines.by_ref().find(|line| line.contains("-->")) else {
+            continue;
+        };
+
+        let (start, end) = timing
+            .split_once("-->")
+            .and_then(|(start, rest)| {
+                // WebVTT settings follow the end time
+                let end = rest.split_whitespace().next()?;
+                Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+            })
+            .ok_or_else(|| CaptionsError::InvalidTiming(timing.to_string()))?;
+
+        let text = lines
+            .map(strip_tags)
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !text.is_empty() && end > start {
+            cues.push(CaptionSegment { start, end, text });
+        }
+    }
+
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+// `hh:mm:ss.mmm`, with SRT's comma or without the hours as WebVTT allows
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let timestamp = timestamp.replace(',', ".");
+    let mut parts = timestamp.rsplit(':');
+
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next()?.parse::<u32>().ok()?;
+    let hours = parts.next().map_or(Some(0), |h| h.parse::<u32>().ok())?;
+    if parts.next().is_some() || seconds < 0.0 {
+        return None;
+    }
+
+    Some(hours as f64 * 3600.0 + minutes as f64 * 60.0 + seconds)
+}
+
+// Drops `<i>`-style tags and SRT's `{\an8}`-style overrides
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+
+    for c in line.chars() {
+        match closing {
+            Some(close) if c == close => closing = None,
+            Some(_) => {}
+            None if c == '<' => closing = Some('>'),
+            None if c == '{' => closing = Some('}'),
+            None => text.push(c),
+        }
+    }
+
+    text
+}
+
+pub fn captions_srt(captions: &[CaptionSegment]) -> String {
+    let mut srt = String::new();
+
+    for (i, caption) in captions.iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            webvtt_timestamp(caption.start).replace('.', ","),
+            webvtt_timestamp(caption.end).replace('.', ","),
+            cue_text(&caption.text)
+        ));
+    }
+
+    srt
+}
+
+pub fn captions_webvtt(captions: &[CaptionSegment]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+
+    for (i, caption) in captions.iter().enumerate() {
+        vtt.push_str(&format!(
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            webvtt_timestamp(caption.start),
+            webvtt_timestamp(caption.end),
+            cue_text(&caption.text)
+        ));
+    }
+
+    vtt
+}
+
+pub fn write_captions(captions: &[CaptionSegment], format: CaptionFormat) -> String {
+    match format {
+        CaptionFormat::Srt => captions_srt(captions),
+        CaptionFormat::WebVtt => captions_webvtt(captions),
+    }
+}
+
+// a cue's text can't hold a blank line or its timing arrow
+fn cue_text(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("-->", "->")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cue(start: f64, end: f64, text: &str) -> CaptionSegment {
+        CaptionSegment {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_srt() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n<i>world</i>\r\n\r\n\
+                   2\r\n00:01:00,250 --> 00:01:03,000\r\n{\\an8}On top\r\n";
+
+        assert_eq!(
+            parse_captions(srt).unwrap(),
+            vec![cue(1.0, 2.5, "Hello\nworld"), cue(60.25, 63.0, "On top")]
+        );
+    }
+
+    #[test]
+    fn reads_webvtt() {
+        let vtt = "WEBVTT - Demo\n\n\
+                   NOTE written by hand\n\n\
+                   intro\n00:05.000 --> 00:06.000 align:start line:0\n<v Speaker>Second\n\n\
+                   00:00:01.000 --> 00:00:02.000\nFirst\n\n\
+                   00:00:03.000 --> 00:00:04.000\n\n";
+
+        assert_eq!(
+            parse_captions(vtt).unwrap(),
+            vec![cue(1.0, 2.0, "First"), cue(5.0, 6.0, "Second")]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_timings() {
+        assert!(matches!(
+            parse_captions("1\n00:00:01 --> soon\nHello\n"),
+            Err(CaptionsError::InvalidTiming(_))
+        ));
+    }
+
+    #[test]
+    fn writes_both_formats() {
+        let captions = [cue(0.0, 1.5, "One\n\ntwo"), cue(61.5, 62.0, "a --> b")];
+
+        assert_eq!(
+            captions_srt(&captions),
+            "1\n00:00:00,000 --> 00:00:01,500\nOne\ntwo\n\n\
+             2\n00:01:01,500 --> 00:01:02,000\na -> b\n\n"
+        );
+        assert_eq!(
+            captions_webvtt(&captions),
+            "WEBVTT\n\
+             \n1\n00:00:00.000 --> 00:00:01.500\nOne\ntwo\n\
+             \n2\n00:01:01.500 --> 00:01:02.000\na -> b\n"
+        );
+        assert_eq!(parse_captions(&captions_srt(&captions)).unwrap().len(), 2);
+    }
+}
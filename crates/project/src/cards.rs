@@ -0,0 +1,143 @@
+use crate::{text::eased_presence, ProjectConfiguration, TitleCard};
+
+impl TitleCard {
+    // seconds spent fading from and to black, or less on short cards
+    pub const FADE_DURATION: f64 = 0.5;
+
+    // How much of the card shows through the black it fades from and to, `time` seconds in
+    pub fn brightness(&self, time: f64) -> f64 {
+        if !self.fade {
+            return 1.0;
+        }
+
+        eased_presence(0.0, self.duration, time, Self::FADE_DURATION)
+    }
+
+    // Whole frames, so the timeline's frames land on the same frame grid with or without
+    // the card before them
+    pub fn frames(&self, fps: u32) -> u32 {
+        (self.duration.max(0.0) * fps as f64).round() as u32
+    }
+}
+
+// What a frame of an export shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFrame<'a> {
+    // a card, and how many seconds into it
+    Card(&'a TitleCard, f64),
+    // a frame of the timeline, counted from its start
+    Timeline(u32),
+}
+
+impl ProjectConfiguration {
+    pub fn intro_frames(&self, fps: u32) -> u32 {
+        self.intro.as_ref().map_or(0, |card| card.frames(fps))
+    }
+
+    pub fn outro_frames(&self, fps: u32) -> u32 {
+        self.outro.as_ref().map_or(0, |card| card.frames(fps))
+    }
+
+    // Frames in an export with `timeline_frames` of the timeline between its cards
+    pub fn output_frame_count(&self, fps: u32, timeline_frames: u32) -> u32 {
+        self.intro_frames(fps) + timeline_frames + self.outro_frames(fps)
+    }
+
+    // What frame `frame_number` of an export shows, when `timeline_frames` of the timeline
+    // play between its cards. None past the end.
+    pub fn output_frame(
+        &self,
+        frame_number: u32,
+        fps: u32,
+        timeline_frames: u32,
+    ) -> Option<OutputFrame<'_>> {
+        let intro_frames = self.intro_frames(fps);
+        match &self.intro {
+            Some(intro) if frame_number < intro_frames => {
+                return Some(OutputFrame::Card(intro, frame_number as f64 / fps as f64));
+            }
+            _ => {}
+        }
+
+        let frame = frame_number - intro_frames;
+        if frame < timeline_frames {
+            return Some(OutputFrame::Timeline(frame));
+        }
+
+        let frame = frame - timeline_frames;
+        self.outro
+            .as_ref()
+            .filter(|_| frame < self.outro_frames(fps))
+            .map(|outro| OutputFrame::Card(outro, frame as f64 / fps as f64))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::TextFont;
+
+    use super::*;
+
+    fn card(duration: f64, fade: bool) -> TitleCard {
+        TitleCard {
+            duration,
+            title: "Demo".to_string(),
+            subtitle: String::new(),
+            font: TextFont::Sans,
+            background: [0, 0, 0],
+            text_color: [255, 255, 255],
+            fade,
+        }
+    }
+
+    #[test]
+    fn cards_play_around_the_timeline() {
+        let project = ProjectConfiguration {
+            intro: Some(card(2.0, false)),
+            outro: Some(card(1.0, false)),
+            ..Default::default()
+        };
+        let intro = project.intro.as_ref().unwrap();
+        let outro = project.outro.as_ref().unwrap();
+
+        assert_eq!(project.output_frame_count(30, 90), 180);
+        assert_eq!(
+            project.output_frame(15, 30, 90),
+            Some(OutputFrame::Card(intro, 0.5))
+        );
+        assert_eq!(
+            project.output_frame(60, 30, 90),
+            Some(OutputFrame::Timeline(0))
+        );
+        assert_eq!(
+            project.output_frame(149, 30, 90),
+            Some(OutputFrame::Timeline(89))
+        );
+        assert_eq!(
+            project.output_frame(150, 30, 90),
+            Some(OutputFrame::Card(outro, 0.0))
+        );
+        assert_eq!(project.output_frame(180, 30, 90), None);
+    }
+
+    #[test]
+    fn no_cards_is_just_the_timeline() {
+        let project = ProjectConfiguration::default();
+
+        assert_eq!(
+            project.output_frame(0, 30, 90),
+            Some(OutputFrame::Timeline(0))
+        );
+        assert_eq!(project.output_frame(90, 30, 90), None);
+    }
+
+    #[test]
+    fn fades_from_and_to_black() {
+        let faded = card(3.0, true);
+
+        assert_eq!(faded.brightness(0.0), 0.0);
+        assert_eq!(faded.brightness(1.5), 1.0);
+        assert_eq!(faded.brightness(3.0), 0.0);
+        assert_eq!(card(3.0, false).brightness(0.0), 1.0);
+    }
+}
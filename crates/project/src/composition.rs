@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path},
+};
+
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{Content, Cursors, MultipleSegment, MultipleSegments, RecordingMeta, SegmentSource};
+
+// Another recording a project plays from without copying it in, like a retake spliced into
+// the middle. Its segments are numbered after the project's own and those of the takes
+// before it, which is how timeline segments refer to them.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Take {
+    // the take's project directory, relative to the project using it so the two can be
+    // moved together
+    #[specta(type = String)]
+    pub path: RelativePathBuf,
+}
+
+impl Take {
+    // The take at `take_path` as seen from the project at `project_path`
+    pub fn new(project_path: &Path, take_path: &Path) -> Result<Self, String> {
+        let project_path = project_path.canonicalize().map_err(|e| e.to_string())?;
+        let take_path = take_path.canonicalize().map_err(|e| e.to_string())?;
+
+        let common = project_path
+            .components()
+            .zip(take_path.components())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if common == 0 {
+            return Err("The take has to be on the same drive as the project".to_string());
+        }
+
+        let mut path = RelativePathBuf::new();
+        for _ in project_path.components().skip(common) {
+            path.push("..");
+        }
+        for component in take_path.components().skip(common) {
+            let Component::Normal(name) = component else {
+                return Err(format!("Invalid take path '{}'", take_path.display()));
+            };
+            path.push(name.to_string_lossy().as_ref());
+        }
+
+        Ok(Self { path })
+    }
+}
+
+impl RecordingMeta {
+    // The project's recording in the multiple segment layout, which a single segment one
+    // is converted to
+    pub fn multiple_segments(&self) -> MultipleSegments {
+        match &self.content {
+            // the renderer doesn't use single segment recordings' cursor data, so neither do we
+            Content::SingleSegment { segment } => MultipleSegments {
+                segments: vec![MultipleSegment {
+                    display: segment.display.clone(),
+                    additional_displays: vec![],
+                    source: SegmentSource::Display,
+                    region: None,
+                    camera: segment.camera.clone(),
+                    audio: segment.audio.clone(),
+                    system_audio: None,
+                    cursor: None,
+                    cursor_id_prefix: String::new(),
+                }],
+                cursors: Cursors::default(),
+            },
+            Content::MultipleSegments { inner } => inner.clone(),
+        }
+    }
+
+    // The recording with its takes' segments after its own, as the project is rendered.
+    // Paths in the takes are rebased onto this project's directory and their cursor ids
+    // prefixed, so it reads like a single recording. It's never to be saved over the
+    // project's own. Takes of the takes aren't followed.
+    pub fn with_takes(&self, takes: &[Take]) -> Result<RecordingMeta, String> {
+        if takes.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let MultipleSegments {
+            mut segments,
+            cursors,
+        } = self.multiple_segments();
+        let mut cursors = match cursors {
+            Cursors::Old(_) => HashMap::new(),
+            Cursors::Correct(map) => map,
+        };
+
+        for (i, take) in takes.iter().enumerate() {
+            let meta = RecordingMeta::load_for_project(&take.path.to_path(&self.project_path))
+                .map_err(|e| format!("Failed to load take '{}': {e}", take.path))?;
+            let prefix = format!("take{i}-");
+            let take_segments = meta.multiple_segments();
+
+            if let Cursors::Correct(map) = take_segments.cursors {
+                for (id, mut cursor) in map {
+                    cursor.image_path = take.path.join(&cursor.image_path);
+                    cursors.insert(format!("{prefix}{id}"), cursor);
+                }
+            }
+
+            for mut segment in take_segments.segments {
+                rebase(&mut segment, &take.path);
+                segment.cursor_id_prefix = prefix.clone();
+                segments.push(segment);
+            }
+        }
+
+        Ok(RecordingMeta {
+            content: Content::MultipleSegments {
+                inner: MultipleSegments {
+                    segments,
+                    cursors: Cursors::Correct(cursors),
+                },
+            },
+            ..self.clone()
+        })
+    }
+}
+
+fn rebase(segment: &mut MultipleSegment, base: &RelativePath) {
+    for display in std::iter::once(&mut segment.display).chain(&mut segment.additional_displays) {
+        display.path = base.join(&display.path);
+    }
+    if let Some(camera) = &mut segment.camera {
+        camera.path = base.join(&camera.path);
+    }
+    for audio in [&mut segment.audio, &mut segment.system_audio]
+        .into_iter()
+        .flatten()
+    {
+        audio.path = base.join(&audio.path);
+    }
+    if let Some(cursor) = &mut segment.cursor {
+        *cursor = base.join(&*cursor);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{AudioMeta, Display};
+
+    use super::*;
+
+    fn project(dir: &Path, name: &str, segments: usize) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::create_dir_all(&path).unwrap();
+
+        let meta = RecordingMeta {
+            project_path: path.clone(),
+            pretty_name: name.to_string(),
+            sharing: None,
+            content: Content::MultipleSegments {
+                inner: MultipleSegments {
+                    segments: (0..segments)
+                        .map(|i| MultipleSegment {
+                            display: Display {
+                                path: format!("content/segments/segment-{i}/display.mp4").into(),
+                                fps: 30,
+                            },
+                            additional_displays: vec![],
+                            source: SegmentSource::Display,
+                            region: None,
+                            camera: None,
+                            audio: Some(AudioMeta {
+                                path: format!("content/segments/segment-{i}/audio-input.ogg")
+                                    .into(),
+                            }),
+                            system_audio: None,
+                            cursor: None,
+                            cursor_id_prefix: String::new(),
+                        })
+                        .collect(),
+                    cursors: Cursors::default(),
+                },
+            },
+        };
+        meta.save_for_project().unwrap();
+
+        path
+    }
+
+    #[test]
+    fn appends_take_segments() {
+        let dir = std::env::temp_dir().join("cap-project-composition-test");
+        let main = project(&dir, "main.cap", 2);
+        let insert = project(&dir, "insert.cap", 1);
+
+        let take = Take::new(&main, &insert).unwrap();
+        assert_eq!(take.path, RelativePathBuf::from("../insert.cap"));
+
+        let meta = RecordingMeta::load_for_project(&main)
+            .unwrap()
+            .with_takes(&[take])
+            .unwrap();
+        let Content::MultipleSegments { inner } = &meta.content else {
+            panic!("expected multiple segments");
+        };
+
+        assert_eq!(inner.segments.len(), 3);
+        assert_eq!(
+            meta.path(&inner.segments[2].display.path),
+            main.join("../insert.cap/content/segments/segment-0/display.mp4")
+        );
+        assert_eq!(inner.segments[2].cursor_id_prefix, "take0-");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
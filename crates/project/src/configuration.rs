@@ -6,6 +6,11 @@ use std::{
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
+use crate::{
+    migration, KeystrokeFilter, OverlayConfiguration, ProjectConfigError, Take,
+    PROJECT_CONFIG_VERSION,
+};
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum AspectRatio {
@@ -36,7 +41,22 @@ pub enum BackgroundSource {
         to: Color,
         #[serde(default = "default_gradient_angle")]
         angle: u16,
+        #[serde(default)]
+        kind: GradientKind,
     },
+    // a blurred copy of the recording itself, stretched to cover the frame
+    Screen,
+    // kept transparent in exports that carry alpha, and black in those that don't
+    Transparent,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GradientKind {
+    #[default]
+    Linear,
+    // from the center out, ignoring the angle
+    Radial,
 }
 
 fn default_gradient_angle() -> u16 {
@@ -159,6 +179,24 @@ impl Crop {
     pub fn aspect_ratio(&self) -> f32 {
         self.size.x as f32 / self.size.y as f32
     }
+
+    // Keeps the crop at least a pixel in size and inside a display of `bounds`, which a
+    // hand-edited config or a preset made for a different display might not be
+    pub fn clamped(&self, bounds: XY<u32>) -> Crop {
+        let bounds = bounds.map(|v| v.max(1));
+        let position = XY::new(
+            self.position.x.min(bounds.x - 1),
+            self.position.y.min(bounds.y - 1),
+        );
+
+        Crop {
+            position,
+            size: XY::new(
+                self.size.x.clamp(1, bounds.x - position.x),
+                self.size.y.clamp(1, bounds.y - position.y),
+            ),
+        }
+    }
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
@@ -166,6 +204,11 @@ pub struct ShadowConfiguration {
     pub size: f32,    // Overall shadow size (0-100)
     pub opacity: f32, // Shadow opacity (0-100)
     pub blur: f32,    // Shadow blur amount (0-100)
+    // Shadow offset from the frame, relative to its shorter side (-100-100)
+    #[serde(default)]
+    pub offset_x: f32,
+    #[serde(default)]
+    pub offset_y: f32,
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
@@ -181,6 +224,9 @@ pub struct BackgroundConfiguration {
     pub shadow: f32,
     #[serde(default)]
     pub advanced_shadow: Option<ShadowConfiguration>,
+    // shrink the padding away while zoomed in, so zooms end up edge-to-edge
+    #[serde(default)]
+    pub padding_follows_zoom: bool,
 }
 
 impl Default for BackgroundConfiguration {
@@ -194,6 +240,7 @@ impl Default for BackgroundConfiguration {
             crop: None,
             shadow: 73.6,
             advanced_shadow: Some(ShadowConfiguration::default()),
+            padding_follows_zoom: false,
         }
     }
 }
@@ -235,6 +282,27 @@ pub struct Camera {
     pub shadow: f32,
     #[serde(default)]
     pub advanced_shadow: Option<ShadowConfiguration>,
+    #[serde(default)]
+    pub border: Option<CameraBorder>,
+    // move out of the way of what auto zoom segments zoom in on
+    #[serde(default)]
+    pub auto_dodge: bool,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+pub struct CameraBorder {
+    // percent of the camera's size
+    pub width: f32,
+    pub color: Color,
+}
+
+impl Default for CameraBorder {
+    fn default() -> Self {
+        Self {
+            width: 2.0,
+            color: [255, 255, 255],
+        }
+    }
 }
 
 impl Camera {
@@ -261,7 +329,11 @@ impl Default for Camera {
                 size: 33.9,
                 opacity: 44.2,
                 blur: 10.5,
+                offset_x: 0.0,
+                offset_y: 0.0,
             }),
+            border: None,
+            auto_dodge: false,
         }
     }
 }
@@ -272,6 +344,8 @@ impl Default for ShadowConfiguration {
             size: 14.4,
             opacity: 68.1,
             blur: 3.8,
+            offset_x: 0.0,
+            offset_y: 0.0,
         }
     }
 }
@@ -280,7 +354,56 @@ impl Default for ShadowConfiguration {
 #[serde(rename_all = "camelCase")]
 pub struct AudioConfiguration {
     pub mute: bool,
+    // run the microphone through noise suppression
     pub improve: bool,
+    #[serde(default)]
+    pub mic: AudioTrackConfiguration,
+    #[serde(default)]
+    pub system: AudioTrackConfiguration,
+    // integrated loudness in LUFS that exports are normalized to, None exports the mix as is
+    #[serde(default)]
+    pub loudness_target: Option<f32>,
+    // keeps voices at their pitch in sped up or slowed down parts, rather than playing them
+    // higher or lower like a tape would
+    #[serde(default)]
+    pub preserve_pitch: bool,
+}
+
+// How a recorded track is mixed in. Applied when the tracks are mixed for playback and
+// export, so the recorded files are left untouched.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrackConfiguration {
+    // in dB, 0 leaves the track as recorded
+    #[serde(default)]
+    pub gain: f32,
+    #[serde(default)]
+    pub mute: bool,
+    // -1 is fully left, 1 is fully right
+    #[serde(default)]
+    pub pan: f32,
+}
+
+impl AudioTrackConfiguration {
+    pub fn amplitude(&self) -> f32 {
+        if self.mute {
+            0.0
+        } else {
+            10f32.powf(self.gain / 20.0)
+        }
+    }
+
+    // Left and right gains. Panning turns the other side down rather than boosting
+    // either, so a centered track sounds as recorded.
+    pub fn stereo_gains(&self) -> [f32; 2] {
+        let pan = self.pan.clamp(-1.0, 1.0);
+        let amplitude = self.amplitude();
+
+        [
+            amplitude * (1.0 - pan).min(1.0),
+            amplitude * (1.0 + pan).min(1.0),
+        ]
+    }
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
@@ -291,6 +414,17 @@ pub enum CursorType {
     Circle,
 }
 
+// Which sprites the cursor layer draws. `Recorded` uses the images captured during recording,
+// the others swap in a bundled set for the cursor shapes that could be identified.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum CursorTheme {
+    #[default]
+    Recorded,
+    Macos,
+    Windows,
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum CursorAnimationStyle {
@@ -314,6 +448,12 @@ pub struct CursorConfiguration {
     pub raw: bool,
     #[serde(default)]
     pub motion_blur: f32,
+    #[serde(default)]
+    pub theme: CursorTheme,
+    #[serde(default)]
+    pub click_highlight: Option<ClickHighlightConfiguration>,
+    #[serde(default)]
+    pub trail: Option<CursorTrailConfiguration>,
 }
 
 impl Default for CursorConfiguration {
@@ -328,6 +468,9 @@ impl Default for CursorConfiguration {
             friction: 20.0,
             raw: false,
             motion_blur: 0.5,
+            theme: CursorTheme::default(),
+            click_highlight: None,
+            trail: None,
         }
     }
 }
@@ -337,10 +480,176 @@ impl CursorConfiguration {
     }
 }
 
-#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ClickHighlightStyle {
+    // an expanding ring
+    #[default]
+    Ripple,
+    // a filled circle that fades out in place
+    Highlight,
+}
+
+// Drawn at each mouse down, in the overlay palette's click highlight color
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClickHighlightConfiguration {
+    #[serde(default)]
+    pub style: ClickHighlightStyle,
+    // in the same units as the cursor's height, so it scales along with the cursor
+    pub radius: f32,
+    // seconds
+    pub duration: f32,
+}
+
+impl Default for ClickHighlightConfiguration {
+    fn default() -> Self {
+        Self {
+            style: ClickHighlightStyle::default(),
+            radius: 40.0,
+            duration: 0.5,
+        }
+    }
+}
+
+// A fading line through where the cursor was over the last few moments
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorTrailConfiguration {
+    // number of past positions, capped at CursorTrailConfiguration::MAX_LENGTH
+    pub length: u32,
+    // seconds between consecutive positions
+    pub spacing: f32,
+    // in the same units as the cursor's height, narrowing towards the oldest position
+    pub width: f32,
+    pub opacity: f32,
+    pub color: Color,
+}
+
+impl CursorTrailConfiguration {
+    pub const MAX_LENGTH: u32 = 16;
+}
+
+impl Default for CursorTrailConfiguration {
+    fn default() -> Self {
+        Self {
+            length: 10,
+            spacing: 0.02,
+            width: 12.0,
+            opacity: 0.5,
+            color: [255, 255, 255],
+        }
+    }
+}
+
+// How Auto zoom segments follow the cursor
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoZoomConfiguration {
+    // the viewport only recenters once the cursor leaves this radius, in normalized display units
+    #[serde(default = "AutoZoomConfiguration::default_dead_zone_radius")]
+    pub dead_zone_radius: f64,
+    // normalized display units per second, 0 disables the limit
+    #[serde(default = "AutoZoomConfiguration::default_max_pan_velocity")]
+    pub max_pan_velocity: f64,
+    // how far ahead of the cursor the viewport looks while scrolling, in normalized
+    // display units, so the content being scrolled into view stays readable
+    #[serde(default = "AutoZoomConfiguration::default_scroll_lookahead")]
+    pub scroll_lookahead: f64,
+}
+
+impl AutoZoomConfiguration {
+    fn default_dead_zone_radius() -> f64 {
+        0.08
+    }
+
+    fn default_max_pan_velocity() -> f64 {
+        0.6
+    }
+
+    fn default_scroll_lookahead() -> f64 {
+        0.15
+    }
+}
+
+impl Default for AutoZoomConfiguration {
+    fn default() -> Self {
+        Self {
+            dead_zone_radius: Self::default_dead_zone_radius(),
+            max_pan_velocity: Self::default_max_pan_velocity(),
+            scroll_lookahead: Self::default_scroll_lookahead(),
+        }
+    }
+}
+
+// The keystroke overlay, drawn as a pill over the bottom of the frame
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct HotkeysConfiguration {
-    show: bool,
+    pub show: bool,
+    #[serde(default)]
+    pub filter: KeystrokeFilter,
+    #[serde(default)]
+    pub position: HotkeysPosition,
+    // seconds a press stays on screen, including the fade out
+    #[serde(default = "HotkeysConfiguration::default_duration")]
+    pub duration: f32,
+    // text height, in percent of the output height
+    #[serde(default = "HotkeysConfiguration::default_size")]
+    pub size: f32,
+}
+
+impl HotkeysConfiguration {
+    fn default_duration() -> f32 {
+        1.5
+    }
+
+    fn default_size() -> f32 {
+        4.0
+    }
+}
+
+impl Default for HotkeysConfiguration {
+    fn default() -> Self {
+        Self {
+            show: false,
+            filter: KeystrokeFilter::default(),
+            position: HotkeysPosition::default(),
+            duration: Self::default_duration(),
+            size: Self::default_size(),
+        }
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HotkeysPosition {
+    BottomLeft,
+    #[default]
+    BottomCenter,
+    BottomRight,
+}
+
+// Strict mode for frame-identical re-renders across machines
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeterminismConfiguration {
+    pub enabled: bool,
+    // a u32 so it fits in a number in the TypeScript bindings
+    #[serde(default)]
+    pub seed: u32,
+}
+
+impl DeterminismConfiguration {
+    // splitmix64 over the project seed, so effects that need randomness get the
+    // same value for the same frame on every render
+    pub fn frame_seed(&self, frame_number: u32) -> u64 {
+        let mut z = (self.seed as u64)
+            .wrapping_add((frame_number as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
@@ -351,6 +660,15 @@ pub struct TimelineSegment {
     pub timescale: f64,
     pub start: f64,
     pub end: f64,
+    // which of the recording's displays to show, 0 being the main one
+    #[serde(default)]
+    pub display: u32,
+    // moves the camera somewhere other than the project's position for this segment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_position: Option<CameraPosition>,
+    // how this segment takes over from the one before it, rather than cutting straight to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<Transition>,
 }
 
 impl TimelineSegment {
@@ -362,11 +680,40 @@ impl TimelineSegment {
         }
     }
 
-    fn duration(&self) -> f64 {
+    pub fn duration(&self) -> f64 {
         (self.end - self.start) / self.timescale
     }
 }
 
+// The way a sliding transition's incoming segment moves
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SlideDirection {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum TransitionKind {
+    CrossFade,
+    // fading out to the color, then in from it
+    Dip { color: Color },
+    // the incoming segment pushing the outgoing one out of the frame
+    Slide { direction: SlideDirection },
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Transition {
+    pub kind: TransitionKind,
+    // seconds, centered on the cut
+    pub duration: f64,
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ZoomSegment {
@@ -374,6 +721,39 @@ pub struct ZoomSegment {
     pub end: f64,
     pub amount: f64,
     pub mode: ZoomMode,
+    // seconds, falls back to the renderer's default transition when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_duration_in: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_duration_out: Option<f64>,
+    // further zoom stages within the segment, sorted by time. The segment's own amount and
+    // mode act as the keyframe at its start.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keyframes: Vec<ZoomKeyframe>,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomKeyframe {
+    // seconds since the segment's start
+    pub time: f64,
+    pub amount: f64,
+    // normalized display position to zoom towards, keeping the previous one when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<XY<f64>>,
+    // how the zoom moves from the previous keyframe to this one
+    #[serde(default)]
+    pub easing: ZoomEasing,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ZoomEasing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
@@ -381,6 +761,176 @@ pub struct ZoomSegment {
 pub enum ZoomMode {
     Auto,
     Manual { x: f32, y: f32 },
+    // keeps the recorded window centered and fitted to the output
+    Window { window_id: u32 },
+    // scales the display just enough to cover the output, times `amount`, and shows the
+    // part at x, y (0 is the left/top edge, 1 the right/bottom one). For panning across
+    // recordings wider or taller than the output without zooming into them.
+    Pan { x: f32, y: f32 },
+}
+
+// Plays `start..end` of the timeline `factor` times as fast, slowing it down below 1. Times
+// are on the timeline before any speed changes, like those of zoom segments.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedSegment {
+    pub start: f64,
+    pub end: f64,
+    pub factor: f64,
+}
+
+// A named point on the timeline, starting a chapter that runs until the next marker
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+    pub time: f64,
+    pub name: String,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum TextFont {
+    #[default]
+    Sans,
+    Serif,
+    Mono,
+}
+
+// How text comes in at the start of its segment, and goes out the same way at the end
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TextAnimation {
+    None,
+    #[default]
+    Fade,
+    // fading in while rising into place
+    SlideFromBottom,
+    SlideFromLeft,
+}
+
+// Text drawn over the frame while its segment plays, like a lower third or a callout
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSegment {
+    pub start: f64,
+    pub end: f64,
+    // one line per line of text
+    pub content: String,
+    #[serde(default)]
+    pub font: TextFont,
+    // as a percentage of the frame's height
+    pub size: f32,
+    pub color: Color,
+    // where the text's top left corner goes, in fractions of the frame
+    pub position: XY<f64>,
+    #[serde(default)]
+    pub animation: TextAnimation,
+}
+
+// A caption cue, like one read from an SRT or WebVTT file
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionSegment {
+    pub start: f64,
+    pub end: f64,
+    // one line per line of the cue
+    pub text: String,
+}
+
+// How captions look when they're burned into the video
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionsConfiguration {
+    // drawn over the preview and exports. Otherwise captions are only written to files.
+    #[serde(default)]
+    pub burn_in: bool,
+    #[serde(default)]
+    pub font: TextFont,
+    // as a percentage of the frame's height
+    pub size: f32,
+    pub color: Color,
+    // the box behind each line
+    pub background: Color,
+    pub background_opacity: f32,
+}
+
+impl Default for CaptionsConfiguration {
+    fn default() -> Self {
+        Self {
+            burn_in: false,
+            font: TextFont::Sans,
+            size: 4.5,
+            color: [255, 255, 255],
+            background: [0, 0, 0],
+            background_opacity: 0.6,
+        }
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationKind {
+    Arrow,
+    // a rectangle's outline
+    Rectangle,
+    // a translucent fill over the region, like a highlighter pen's
+    Highlight,
+    // hide what's under the region, for masking passwords and other sensitive data
+    Blur,
+    Pixelate,
+}
+
+impl AnnotationKind {
+    pub fn is_mask(&self) -> bool {
+        matches!(self, Self::Blur | Self::Pixelate)
+    }
+}
+
+// How an annotation comes in at the start of its segment, and goes out the same way at the
+// end. Masks ignore it and stay fully on throughout, so what they hide never shows.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationAnimation {
+    None,
+    #[default]
+    Fade,
+    // arrows drawing out from their tail, and boxes out from their center
+    Grow,
+}
+
+// What an annotation's points are fractions of
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationAnchor {
+    // the output frame, so the annotation stays put through zooms
+    #[default]
+    Frame,
+    // the recorded display before cropping, so the annotation moves with it through crops,
+    // padding and zooms and stays over what it points at or hides
+    Display,
+}
+
+// A shape drawn over the frame while its segment plays
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationSegment {
+    pub start: f64,
+    pub end: f64,
+    pub kind: AnnotationKind,
+    // an arrow's tail and head, or opposite corners of the other kinds' region, in
+    // fractions of what it's anchored to
+    pub from: XY<f64>,
+    pub to: XY<f64>,
+    #[serde(default)]
+    pub anchor: AnnotationAnchor,
+    // the overlays' annotation color if not set. Masks don't use it.
+    #[serde(default)]
+    pub color: Option<Color>,
+    // the thickness of arrows and outlines, or the blur radius or pixel size of masks, as a
+    // percentage of the frame's height
+    pub size: f32,
+    #[serde(default)]
+    pub animation: AnnotationAnimation,
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
@@ -388,9 +938,46 @@ pub enum ZoomMode {
 pub struct TimelineConfiguration {
     pub segments: Vec<TimelineSegment>,
     pub zoom_segments: Vec<ZoomSegment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speed_segments: Vec<SpeedSegment>,
+    // sorted by time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<Marker>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub text_segments: Vec<TextSegment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotation_segments: Vec<AnnotationSegment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caption_segments: Vec<CaptionSegment>,
 }
 
 impl TimelineConfiguration {
+    // Every recording segment played in full, back to back. Pausing a recording starts a
+    // new segment, so this plays it back without the pauses.
+    pub fn from_segment_durations(durations: &[f64]) -> Self {
+        Self {
+            segments: durations
+                .iter()
+                .enumerate()
+                .map(|(i, duration)| TimelineSegment {
+                    recording_segment: i as u32,
+                    timescale: 1.0,
+                    start: 0.0,
+                    end: *duration,
+                    display: 0,
+                    camera_position: None,
+                    transition: None,
+                })
+                .collect(),
+            zoom_segments: vec![],
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }
+    }
+
     pub fn get_segment_time(&self, frame_time: f64) -> Option<(f64, u32)> {
         let mut accum_duration = 0.0;
 
@@ -407,6 +994,35 @@ impl TimelineConfiguration {
         None
     }
 
+    pub fn get_segment_display(&self, frame_time: f64) -> u32 {
+        let mut accum_duration = 0.0;
+
+        for segment in self.segments.iter() {
+            accum_duration += segment.duration();
+
+            if frame_time < accum_duration {
+                return segment.display;
+            }
+        }
+
+        0
+    }
+
+    // How many seconds of recording play per second of timeline at `frame_time`
+    pub fn get_segment_timescale(&self, frame_time: f64) -> f64 {
+        let mut accum_duration = 0.0;
+
+        for segment in self.segments.iter() {
+            accum_duration += segment.duration();
+
+            if frame_time < accum_duration {
+                return segment.timescale;
+            }
+        }
+
+        1.0
+    }
+
     pub fn duration(&self) -> f64 {
         self.segments.iter().map(|s| s.duration()).sum()
     }
@@ -414,9 +1030,99 @@ impl TimelineConfiguration {
 
 pub const WALLPAPERS_PATH: &str = "assets/backgrounds/macOS";
 
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum WatermarkCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+// An image drawn over every frame, like a logo
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkConfiguration {
+    // a PNG. The editor turns SVGs into one when they're picked.
+    pub path: String,
+    #[serde(default)]
+    pub corner: WatermarkCorner,
+    // the image's width as a percentage of the frame's width
+    pub scale: f32,
+    pub opacity: f32,
+    // only drawn for accounts that haven't upgraded. Ignored unless the
+    // free_tier_watermark flag is on.
+    #[serde(default)]
+    pub free_tier_only: bool,
+}
+
+// Adjustments to the screen's colors, drawn before anything is laid over it
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorGradeConfiguration {
+    // each from -100 to 100, where 0 leaves the screen as it was recorded
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    // a 3D LUT in the .cube format, applied after the adjustments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lut: Option<String>,
+    // how much of the LUT's look is mixed in (0-100)
+    #[serde(default = "full_lut_intensity")]
+    pub lut_intensity: f32,
+}
+
+impl ColorGradeConfiguration {
+    pub fn is_neutral(&self) -> bool {
+        self.brightness == 0.0
+            && self.contrast == 0.0
+            && self.saturation == 0.0
+            && (self.lut.is_none() || self.lut_intensity == 0.0)
+    }
+}
+
+impl Default for ColorGradeConfiguration {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            lut: None,
+            lut_intensity: full_lut_intensity(),
+        }
+    }
+}
+
+fn full_lut_intensity() -> f32 {
+    100.0
+}
+
+// A card drawn on its own before or after the recording, like a title or a sign-off
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TitleCard {
+    // seconds the card shows for, on top of the timeline's duration
+    pub duration: f64,
+    pub title: String,
+    // a smaller line under the title, left out when empty
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default)]
+    pub font: TextFont,
+    pub background: Color,
+    pub text_color: Color,
+    // fading in from black at the card's start and out to black at its end
+    #[serde(default)]
+    pub fade: bool,
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectConfiguration {
+    // the layout the config was written in, which older ones are migrated from on load
+    #[serde(default = "project_config_version")]
+    pub version: u32,
     pub aspect_ratio: Option<AspectRatio>,
     pub background: BackgroundConfiguration,
     pub camera: Camera,
@@ -425,21 +1131,54 @@ pub struct ProjectConfiguration {
     pub hotkeys: HotkeysConfiguration,
     #[serde(default)]
     pub timeline: Option<TimelineConfiguration>,
+    #[serde(default)]
+    pub determinism: DeterminismConfiguration,
+    #[serde(default)]
+    pub auto_zoom: AutoZoomConfiguration,
+    #[serde(default)]
+    pub overlays: OverlayConfiguration,
+    // other recordings played from alongside this one's, see `RecordingMeta::with_takes`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub takes: Vec<Take>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark: Option<WatermarkConfiguration>,
+    // cards played before and after the timeline in exports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<TitleCard>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outro: Option<TitleCard>,
+    #[serde(default)]
+    pub captions: CaptionsConfiguration,
+    #[serde(default)]
+    pub color_grade: ColorGradeConfiguration,
 }
 
 impl ProjectConfiguration {
-    pub fn load(project_path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+    pub fn load(project_path: impl AsRef<Path>) -> Result<Self, ProjectConfigError> {
         let config_str =
             std::fs::read_to_string(project_path.as_ref().join("project-config.json"))?;
-        let mut config: Self = serde_json::from_str(&config_str).unwrap_or_default();
 
-        Ok(config)
+        Self::from_json(&config_str)
+    }
+
+    // Reads a config written by this version of the app or an older one, which is upgraded
+    pub fn from_json(json: &str) -> Result<Self, ProjectConfigError> {
+        let mut config: serde_json::Value = serde_json::from_str(json)?;
+        migration::migrate(&mut config)?;
+
+        Ok(serde_json::from_value(config)?)
     }
 
     pub fn write(&self, project_path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        // whatever version it was read from, it's held in this version's layout
+        let config = Self {
+            version: PROJECT_CONFIG_VERSION,
+            ..self.clone()
+        };
+
         std::fs::write(
             project_path.as_ref().join("project-config.json"),
-            serde_json::to_string_pretty(self)?,
+            serde_json::to_string_pretty(&config)?,
         )
     }
 
@@ -449,11 +1188,19 @@ impl ProjectConfiguration {
             .map(|t| t.get_segment_time(frame_time as f64))
             .unwrap_or(Some((frame_time as f64, 0)))
     }
+
+    pub fn get_segment_display(&self, frame_time: f64) -> u32 {
+        self.timeline
+            .as_ref()
+            .map(|t| t.get_segment_display(frame_time))
+            .unwrap_or(0)
+    }
 }
 
 impl Default for ProjectConfiguration {
     fn default() -> Self {
         ProjectConfiguration {
+            version: PROJECT_CONFIG_VERSION,
             aspect_ratio: None,
             background: BackgroundConfiguration::default(),
             camera: Camera::default(),
@@ -461,10 +1208,23 @@ impl Default for ProjectConfiguration {
             cursor: CursorConfiguration::default(),
             hotkeys: HotkeysConfiguration::default(),
             timeline: None,
+            determinism: DeterminismConfiguration::default(),
+            auto_zoom: AutoZoomConfiguration::default(),
+            overlays: OverlayConfiguration::default(),
+            takes: vec![],
+            watermark: None,
+            intro: None,
+            outro: None,
+            captions: CaptionsConfiguration::default(),
+            color_grade: ColorGradeConfiguration::default(),
         }
     }
 }
 
+fn project_config_version() -> u32 {
+    PROJECT_CONFIG_VERSION
+}
+
 pub const SLOW_SMOOTHING_SAMPLES: usize = 24;
 pub const REGULAR_SMOOTHING_SAMPLES: usize = 16;
 pub const FAST_SMOOTHING_SAMPLES: usize = 10;
@@ -4,7 +4,11 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 
-use crate::XY;
+use crate::{KeyboardEvents, XY};
+
+// how long a scroll keeps counting towards the scroll direction
+const SCROLL_WINDOW_MS: f64 = 1000.0;
+const SCROLLS_FOR_FULL_DIRECTION: f64 = 3.0;
 
 #[derive(Serialize, Deserialize, Clone, Type, Debug)]
 pub struct CursorMoveEvent {
@@ -14,6 +18,9 @@ pub struct CursorMoveEvent {
     pub unix_time_ms: f64,
     pub x: f64,
     pub y: f64,
+    // which of the recording's displays the cursor was on, x and y are normalized to it
+    #[serde(default)]
+    pub display: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Type, Debug)]
@@ -26,6 +33,66 @@ pub struct CursorClickEvent {
     pub down: bool,
     pub x: f64,
     pub y: f64,
+    #[serde(default)]
+    pub display: u32,
+}
+
+// Bounds of the UI element under the cursor, normalized to the display like move events
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct CursorElementEvent {
+    pub start_time_ms: f64,
+    pub end_time_ms: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl CursorElementEvent {
+    pub fn center(&self) -> XY<f64> {
+        XY::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+// Geometry of an on-screen window, normalized to the display like move events.
+// Only recorded when a window's rect changes, so each sample holds until the next one.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct CursorWindowEvent {
+    pub window_id: u32,
+    pub process_time_ms: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// A scroll wheel or trackpad scroll. Deltas are positive towards the bottom and right of the
+// content, in whatever units the platform reports, so only their direction is comparable.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct CursorScrollEvent {
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
+    pub delta_x: f64,
+    pub delta_y: f64,
+}
+
+// The system cursor a recorded cursor image was identified as, if any
+#[derive(Serialize, Deserialize, Clone, Copy, Type, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum CursorShape {
+    Arrow,
+    IBeam,
+    PointingHand,
+    Crosshair,
+}
+
+impl CursorShape {
+    pub const ALL: [Self; 4] = [
+        Self::Arrow,
+        Self::IBeam,
+        Self::PointingHand,
+        Self::Crosshair,
+    ];
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
@@ -36,6 +103,8 @@ pub struct CursorImages(pub HashMap<String, CursorImage>);
 pub struct CursorImage {
     pub path: PathBuf,
     pub hotspot: XY<f64>,
+    #[serde(default)]
+    pub shape: Option<CursorShape>,
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
@@ -56,6 +125,15 @@ impl CursorData {
 pub struct CursorEvents {
     pub clicks: Vec<CursorClickEvent>,
     pub moves: Vec<CursorMoveEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub elements: Vec<CursorElementEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<CursorWindowEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scrolls: Vec<CursorScrollEvent>,
+    // recorded by the same poller as the mouse, so it lives alongside the cursor data
+    #[serde(default, skip_serializing_if = "KeyboardEvents::is_empty")]
+    pub keyboard: KeyboardEvents,
 }
 
 impl CursorEvents {
@@ -63,6 +141,91 @@ impl CursorEvents {
         let file = File::open(path).map_err(|e| format!("Failed to open cursor file: {}", e))?;
         serde_json::from_reader(file).map_err(|e| format!("Failed to parse cursor data: {}", e))
     }
+
+    // Elements are recorded one after another without overlapping, so the only candidate is
+    // the last one to start at or before `time_ms`
+    pub fn element_at(&self, time_ms: f64) -> Option<&CursorElementEvent> {
+        let index = self
+            .elements
+            .partition_point(|e| e.start_time_ms <= time_ms)
+            .checked_sub(1)?;
+
+        Some(&self.elements[index]).filter(|e| time_ms < e.end_time_ms)
+    }
+
+    pub fn window_at(&self, window_id: u32, time_ms: f64) -> Option<&CursorWindowEvent> {
+        let mut samples = self.windows.iter().filter(|w| w.window_id == window_id);
+        let first = samples.next()?;
+
+        Some(
+            samples
+                .take_while(|w| w.process_time_ms <= time_ms)
+                .last()
+                .unwrap_or(first),
+        )
+    }
+
+    // Direction of recent scrolling, fading out as the scrolls get older. Every scroll counts
+    // the same however far it went, and a few in a row reach the maximum length of 1.
+    pub fn scroll_direction_at(&self, time_ms: f64) -> XY<f64> {
+        let end = self
+            .scrolls
+            .partition_point(|s| s.process_time_ms <= time_ms);
+        let start = self
+            .scrolls
+            .partition_point(|s| s.process_time_ms <= time_ms - SCROLL_WINDOW_MS);
+
+        let sum = self.scrolls[start..end]
+            .iter()
+            .fold(XY::new(0.0, 0.0), |sum, scroll| {
+                let length = scroll.delta_x.hypot(scroll.delta_y);
+                if length == 0.0 {
+                    return sum;
+                }
+
+                let weight = 1.0 - (time_ms - scroll.process_time_ms) / SCROLL_WINDOW_MS;
+                sum + XY::new(scroll.delta_x, scroll.delta_y) * (weight / length)
+            })
+            / SCROLLS_FOR_FULL_DIRECTION;
+
+        let length = sum.x.hypot(sum.y);
+        if length > 1.0 {
+            sum / length
+        } else {
+            sum
+        }
+    }
+
+    // The events for one display's track. Elements and windows are only recorded for the
+    // main display, while scrolls and key presses don't depend on where the cursor is.
+    pub fn for_display(&self, display: u32) -> Self {
+        Self {
+            clicks: self
+                .clicks
+                .iter()
+                .filter(|c| c.display == display)
+                .cloned()
+                .collect(),
+            moves: self
+                .moves
+                .iter()
+                .filter(|m| m.display == display)
+                .cloned()
+                .collect(),
+            elements: if display == 0 {
+                self.elements.clone()
+            } else {
+                vec![]
+            },
+            windows: if display == 0 {
+                self.windows.clone()
+            } else {
+                vec![]
+            },
+            scrolls: self.scrolls.clone(),
+            keyboard: self.keyboard.clone(),
+        }
+    }
 }
 
 impl From<CursorData> for CursorEvents {
@@ -70,6 +233,109 @@ impl From<CursorData> for CursorEvents {
         Self {
             clicks: value.clicks,
             moves: value.moves,
+            elements: vec![],
+            windows: vec![],
+            scrolls: vec![],
+            keyboard: KeyboardEvents::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scroll(time_ms: f64, delta_x: f64, delta_y: f64) -> CursorScrollEvent {
+        CursorScrollEvent {
+            process_time_ms: time_ms,
+            unix_time_ms: 0.0,
+            delta_x,
+            delta_y,
         }
     }
+
+    #[test]
+    fn scroll_direction() {
+        let events = CursorEvents {
+            scrolls: vec![
+                scroll(0.0, 0.0, 40.0),
+                scroll(100.0, 0.0, 2.0),
+                scroll(200.0, 0.0, 120.0),
+                scroll(300.0, 0.0, 10.0),
+            ],
+            ..Default::default()
+        };
+
+        // continuous scrolling saturates, whatever the size of each scroll
+        let direction = events.scroll_direction_at(300.0);
+        assert_eq!(direction.x, 0.0);
+        assert_eq!(direction.y, 1.0);
+
+        // fades out once scrolling stops
+        let direction = events.scroll_direction_at(1150.0);
+        assert!(direction.y > 0.0 && direction.y < 0.1);
+        assert_eq!(events.scroll_direction_at(1400.0).y, 0.0);
+        assert_eq!(events.scroll_direction_at(-10.0).y, 0.0);
+    }
+
+    #[test]
+    fn element_lookup() {
+        let element = |start_time_ms: f64, end_time_ms: f64| CursorElementEvent {
+            start_time_ms,
+            end_time_ms,
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0,
+        };
+
+        let events = CursorEvents {
+            elements: vec![
+                element(100.0, 200.0),
+                element(200.0, 250.0),
+                element(400.0, 500.0),
+            ],
+            ..Default::default()
+        };
+
+        let start_of = |time_ms| events.element_at(time_ms).map(|e| e.start_time_ms);
+        assert_eq!(start_of(50.0), None);
+        assert_eq!(start_of(100.0), Some(100.0));
+        assert_eq!(start_of(200.0), Some(200.0));
+        assert_eq!(start_of(300.0), None);
+        assert_eq!(start_of(499.0), Some(400.0));
+        assert_eq!(start_of(500.0), None);
+    }
+
+    #[test]
+    fn display_tracks() {
+        let mv = |time_ms: f64, display: u32| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "0".to_string(),
+            process_time_ms: time_ms,
+            unix_time_ms: 0.0,
+            x: 0.5,
+            y: 0.5,
+            display,
+        };
+
+        let events = CursorEvents {
+            moves: vec![mv(0.0, 0), mv(100.0, 1), mv(200.0, 1), mv(300.0, 0)],
+            scrolls: vec![scroll(150.0, 0.0, 1.0)],
+            ..Default::default()
+        };
+
+        let secondary = events.for_display(1);
+        assert_eq!(
+            secondary
+                .moves
+                .iter()
+                .map(|m| m.process_time_ms)
+                .collect::<Vec<_>>(),
+            vec![100.0, 200.0]
+        );
+        assert_eq!(secondary.scrolls.len(), 1);
+        assert_eq!(events.for_display(0).moves.len(), 2);
+        assert!(events.for_display(2).moves.is_empty());
+    }
 }
@@ -0,0 +1,435 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    AudioConfiguration, BackgroundConfiguration, Camera, CursorConfiguration, ProjectConfiguration,
+    TimelineConfiguration, ZoomSegment,
+};
+
+// An edit to a project that can be taken back. Applying one gives back the command that
+// undoes it, so undoing and redoing are both just applying commands. Indexes are u32 so
+// they're plain numbers in the TypeScript bindings.
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ProjectCommand {
+    AddZoomSegment {
+        segment: ZoomSegment,
+    },
+    InsertZoomSegment {
+        index: u32,
+        segment: ZoomSegment,
+    },
+    UpdateZoomSegment {
+        index: u32,
+        segment: ZoomSegment,
+    },
+    RemoveZoomSegment {
+        index: u32,
+    },
+    SplitAt {
+        time: f64,
+    },
+    DeleteRange {
+        start: f64,
+        end: f64,
+    },
+    DeleteSegment {
+        index: u32,
+    },
+    TrimSegment {
+        index: u32,
+        start: f64,
+        end: f64,
+    },
+    AddMarker {
+        time: f64,
+        name: String,
+    },
+    RemoveMarker {
+        index: u32,
+    },
+    // timeline edits ripple through every track, so they're undone by putting the whole
+    // timeline back
+    SetTimeline {
+        timeline: Option<TimelineConfiguration>,
+    },
+    SetBackground {
+        background: BackgroundConfiguration,
+    },
+    SetCamera {
+        camera: Camera,
+    },
+    SetAudio {
+        audio: AudioConfiguration,
+    },
+    SetCursor {
+        cursor: CursorConfiguration,
+    },
+    // anything else, replacing the configuration as a whole
+    SetProject {
+        project: Box<ProjectConfiguration>,
+    },
+}
+
+impl ProjectCommand {
+    // Applies the command to `project`, returning the command that undoes it. Returns None,
+    // leaving the project as it was, when the command doesn't apply, like removing a zoom
+    // segment that isn't there or editing a project without a timeline.
+    pub fn apply(self, project: &mut ProjectConfiguration) -> Option<ProjectCommand> {
+        use ProjectCommand::*;
+
+        Some(match self {
+            AddZoomSegment { segment } => {
+                let zooms = &mut project.timeline.as_mut()?.zoom_segments;
+                let index = zooms.partition_point(|z| z.start <= segment.start);
+                zooms.insert(index, segment);
+
+                RemoveZoomSegment {
+                    index: index as u32,
+                }
+            }
+            InsertZoomSegment { index, segment } => {
+                let zooms = &mut project.timeline.as_mut()?.zoom_segments;
+                if index as usize > zooms.len() {
+                    return None;
+                }
+                zooms.insert(index as usize, segment);
+
+                RemoveZoomSegment { index }
+            }
+            UpdateZoomSegment { index, segment } => {
+                let zoom = project
+                    .timeline
+                    .as_mut()?
+                    .zoom_segments
+                    .get_mut(index as usize)?;
+
+                UpdateZoomSegment {
+                    index,
+                    segment: std::mem::replace(zoom, segment),
+                }
+            }
+            RemoveZoomSegment { index } => {
+                let zooms = &mut project.timeline.as_mut()?.zoom_segments;
+                if index as usize >= zooms.len() {
+                    return None;
+                }
+
+                InsertZoomSegment {
+                    index,
+                    segment: zooms.remove(index as usize),
+                }
+            }
+            SplitAt { time } => {
+                let timeline = project.timeline.as_mut()?;
+                let previous = timeline.clone();
+                if !timeline.split_at(time) {
+                    return None;
+                }
+
+                SetTimeline {
+                    timeline: Some(previous),
+                }
+            }
+            DeleteRange { start, end } => {
+                let timeline = project.timeline.as_mut()?;
+                let previous = timeline.clone();
+                timeline.delete_range(start, end);
+
+                SetTimeline {
+                    timeline: Some(previous),
+                }
+            }
+            DeleteSegment { index } => {
+                let timeline = project.timeline.as_mut()?;
+                if index as usize >= timeline.segments.len() {
+                    return None;
+                }
+                let previous = timeline.clone();
+                timeline.delete_segment(index as usize);
+
+                SetTimeline {
+                    timeline: Some(previous),
+                }
+            }
+            TrimSegment { index, start, end } => {
+                let timeline = project.timeline.as_mut()?;
+                let previous = timeline.clone();
+                if !timeline.trim_segment(index as usize, start, end) {
+                    return None;
+                }
+
+                SetTimeline {
+                    timeline: Some(previous),
+                }
+            }
+            AddMarker { time, name } => {
+                let index = project.timeline.as_mut()?.add_marker(time, name);
+
+                RemoveMarker {
+                    index: index as u32,
+                }
+            }
+            RemoveMarker { index } => {
+                let marker = project.timeline.as_mut()?.remove_marker(index as usize)?;
+
+                AddMarker {
+                    time: marker.time,
+                    name: marker.name,
+                }
+            }
+            SetTimeline { timeline } => SetTimeline {
+                timeline: std::mem::replace(&mut project.timeline, timeline),
+            },
+            SetBackground { background } => SetBackground {
+                background: std::mem::replace(&mut project.background, background),
+            },
+            SetCamera { camera } => SetCamera {
+                camera: std::mem::replace(&mut project.camera, camera),
+            },
+            SetAudio { audio } => SetAudio {
+                audio: std::mem::replace(&mut project.audio, audio),
+            },
+            SetCursor { cursor } => SetCursor {
+                cursor: std::mem::replace(&mut project.cursor, cursor),
+            },
+            SetProject { project: new } => SetProject {
+                project: Box::new(std::mem::replace(project, *new)),
+            },
+        })
+    }
+}
+
+// The edits made to a project, kept as the commands that undo and redo them
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EditHistory {
+    undo: Vec<ProjectCommand>,
+    redo: Vec<ProjectCommand>,
+}
+
+impl EditHistory {
+    // the oldest edits are forgotten past this many
+    pub const MAX_ENTRIES: usize = 200;
+
+    const FILE_NAME: &'static str = "edit-history.json";
+
+    // The history saved in `project_path`, or an empty one if there's none or it can't be read
+    pub fn load(project_path: impl AsRef<Path>) -> Self {
+        std::fs::read_to_string(project_path.as_ref().join(Self::FILE_NAME))
+            .ok()
+            .and_then(|history| serde_json::from_str(&history).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write(&self, project_path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        std::fs::write(
+            project_path.as_ref().join(Self::FILE_NAME),
+            serde_json::to_string(self)?,
+        )
+    }
+
+    // Applies `command` to `project` as a new edit, which can't be followed by anything
+    // undone before it. Returns false if the command didn't apply.
+    pub fn execute(&mut self, project: &mut ProjectConfiguration, command: ProjectCommand) -> bool {
+        let Some(inverse) = command.apply(project) else {
+            return false;
+        };
+
+        self.undo.push(inverse);
+        if self.undo.len() > Self::MAX_ENTRIES {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+
+        true
+    }
+
+    pub fn undo(&mut self, project: &mut ProjectConfiguration) -> bool {
+        Self::step(&mut self.undo, &mut self.redo, project)
+    }
+
+    pub fn redo(&mut self, project: &mut ProjectConfiguration) -> bool {
+        Self::step(&mut self.redo, &mut self.undo, project)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // Applies the latest command in `from`, moving what undoes it to `to`. A command that
+    // no longer applies, if the project was changed outside the history, is dropped.
+    fn step(
+        from: &mut Vec<ProjectCommand>,
+        to: &mut Vec<ProjectCommand>,
+        project: &mut ProjectConfiguration,
+    ) -> bool {
+        while let Some(command) = from.pop() {
+            if let Some(inverse) = command.apply(project) {
+                to.push(inverse);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ZoomMode, ZoomSegment};
+
+    use super::*;
+
+    fn project() -> ProjectConfiguration {
+        ProjectConfiguration {
+            timeline: Some(TimelineConfiguration::from_segment_durations(&[10.0])),
+            ..Default::default()
+        }
+    }
+
+    fn zoom(start: f64, end: f64) -> ZoomSegment {
+        ZoomSegment {
+            start,
+            end,
+            amount: 2.0,
+            mode: ZoomMode::Auto,
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![],
+        }
+    }
+
+    fn zooms(project: &ProjectConfiguration) -> Vec<(f64, f64)> {
+        project
+            .timeline
+            .as_ref()
+            .unwrap()
+            .zoom_segments
+            .iter()
+            .map(|z| (z.start, z.end))
+            .collect()
+    }
+
+    fn segments(project: &ProjectConfiguration) -> Vec<(f64, f64)> {
+        project
+            .timeline
+            .as_ref()
+            .unwrap()
+            .segments
+            .iter()
+            .map(|s| (s.start, s.end))
+            .collect()
+    }
+
+    #[test]
+    fn undoes_and_redoes_zoom_edits() {
+        let mut project = project();
+        let mut history = EditHistory::default();
+
+        assert!(history.execute(
+            &mut project,
+            ProjectCommand::AddZoomSegment {
+                segment: zoom(4.0, 5.0)
+            }
+        ));
+        assert!(history.execute(
+            &mut project,
+            ProjectCommand::AddZoomSegment {
+                segment: zoom(1.0, 2.0)
+            }
+        ));
+        assert!(history.execute(
+            &mut project,
+            ProjectCommand::UpdateZoomSegment {
+                index: 1,
+                segment: zoom(6.0, 7.0)
+            }
+        ));
+        assert_eq!(zooms(&project), vec![(1.0, 2.0), (6.0, 7.0)]);
+
+        assert!(history.undo(&mut project));
+        assert_eq!(zooms(&project), vec![(1.0, 2.0), (4.0, 5.0)]);
+        assert!(history.undo(&mut project));
+        assert_eq!(zooms(&project), vec![(4.0, 5.0)]);
+
+        assert!(history.redo(&mut project));
+        assert!(history.redo(&mut project));
+        assert_eq!(zooms(&project), vec![(1.0, 2.0), (6.0, 7.0)]);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn undoes_timeline_edits() {
+        let mut project = project();
+        let mut history = EditHistory::default();
+
+        history.execute(
+            &mut project,
+            ProjectCommand::AddZoomSegment {
+                segment: zoom(6.0, 8.0),
+            },
+        );
+        history.execute(
+            &mut project,
+            ProjectCommand::DeleteRange {
+                start: 2.0,
+                end: 4.0,
+            },
+        );
+        assert_eq!(segments(&project), vec![(0.0, 2.0), (4.0, 10.0)]);
+        assert_eq!(zooms(&project), vec![(4.0, 6.0)]);
+
+        history.undo(&mut project);
+        assert_eq!(segments(&project), vec![(0.0, 10.0)]);
+        assert_eq!(zooms(&project), vec![(6.0, 8.0)]);
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut project = project();
+        let mut history = EditHistory::default();
+
+        history.execute(
+            &mut project,
+            ProjectCommand::AddMarker {
+                time: 1.0,
+                name: "a".to_string(),
+            },
+        );
+        history.undo(&mut project);
+        assert!(history.can_redo());
+
+        history.execute(&mut project, ProjectCommand::SplitAt { time: 5.0 });
+        assert!(!history.can_redo());
+        assert!(!history.execute(&mut project, ProjectCommand::RemoveZoomSegment { index: 3 }));
+        assert!(history.undo(&mut project));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn survives_a_round_trip() {
+        let mut project = project();
+        let mut history = EditHistory::default();
+
+        let mut background = project.background.clone();
+        background.blur = 40.0;
+        history.execute(&mut project, ProjectCommand::SetBackground { background });
+
+        let json = serde_json::to_string(&history).unwrap();
+        let mut history: EditHistory = serde_json::from_str(&json).unwrap();
+
+        assert!(history.undo(&mut project));
+        assert_eq!(project.background.blur, 0.0);
+    }
+}
@@ -0,0 +1,236 @@
+use std::ops::Range;
+
+use serde::Serialize;
+use specta::Type;
+
+use crate::{CursorEvents, TimelineConfiguration};
+
+#[derive(Clone, Copy, Debug)]
+pub struct IdleOptions {
+    // stretches without anything happening for at least this long are idle
+    pub min_duration: f64,
+    // kept on both sides of an idle stretch, so what leads into and out of it stays
+    pub padding: f64,
+    // cursor moves shorter than this, as a fraction of the display, are a hand resting on
+    // the mouse rather than someone using it
+    pub min_cursor_move: f64,
+}
+
+impl Default for IdleOptions {
+    fn default() -> Self {
+        Self {
+            min_duration: 3.0,
+            padding: 0.5,
+            min_cursor_move: 0.002,
+        }
+    }
+}
+
+// A stretch of a recording segment where nothing happened, in seconds into the segment,
+// along with where it plays on the timeline for speeding it up instead of cutting it
+#[derive(Type, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlePeriod {
+    pub recording_segment: u32,
+    pub start: f64,
+    pub end: f64,
+    pub timeline_start: f64,
+    pub timeline_end: f64,
+}
+
+// Seconds into a segment at which the cursor moved, clicked or scrolled, or a key was pressed
+pub fn input_activity(events: &CursorEvents, options: &IdleOptions) -> Vec<f64> {
+    let mut times = vec![];
+
+    // the first position is where the cursor started, not a move
+    let mut last_position = None;
+    for event in &events.moves {
+        match last_position {
+            Some((x, y)) if f64::hypot(event.x - x, event.y - y) < options.min_cursor_move => {}
+            Some(_) => {
+                times.push(event.process_time_ms / 1000.0);
+                last_position = Some((event.x, event.y));
+            }
+            None => last_position = Some((event.x, event.y)),
+        }
+    }
+
+    times.extend(events.clicks.iter().map(|e| e.process_time_ms / 1000.0));
+    times.extend(events.scrolls.iter().map(|e| e.process_time_ms / 1000.0));
+    times.extend(
+        events
+            .keyboard
+            .presses
+            .iter()
+            .map(|e| e.process_time_ms / 1000.0),
+    );
+
+    times
+}
+
+// Finds the stretches of a segment `duration` seconds long between the `activity` times,
+// in any order, with the padding already taken off. The segment's start and end bound the
+// first and last stretches.
+pub fn detect_idle(activity: &[f64], duration: f64, options: &IdleOptions) -> Vec<Range<f64>> {
+    let mut times = activity
+        .iter()
+        .copied()
+        .filter(|t| (0.0..=duration).contains(t))
+        .collect::<Vec<_>>();
+    times.sort_by(f64::total_cmp);
+
+    let mut idle = vec![];
+    let mut previous = 0.0;
+
+    for time in times.into_iter().chain(std::iter::once(duration)) {
+        if time - previous >= options.min_duration {
+            let range = previous + options.padding..time - options.padding;
+            if range.end > range.start {
+                idle.push(range);
+            }
+        }
+
+        previous = time;
+    }
+
+    idle
+}
+
+// Narrows each recording segment's idle stretches down to the parts still on the timeline,
+// like silence cuts. `idle` is indexed by recording segment.
+pub fn suggest_idle_periods(
+    timeline: &TimelineConfiguration,
+    idle: &[Vec<Range<f64>>],
+) -> Vec<IdlePeriod> {
+    let mut periods = vec![];
+    let mut offset = 0.0;
+
+    for segment in &timeline.segments {
+        let ranges = idle
+            .get(segment.recording_segment as usize)
+            .into_iter()
+            .flatten();
+
+        for range in ranges {
+            let start = range.start.max(segment.start);
+            let end = range.end.min(segment.end);
+            if end <= start {
+                continue;
+            }
+
+            periods.push(IdlePeriod {
+                recording_segment: segment.recording_segment,
+                start,
+                end,
+                timeline_start: offset + (start - segment.start) / segment.timescale,
+                timeline_end: offset + (end - segment.start) / segment.timescale,
+            });
+        }
+
+        offset += segment.duration();
+    }
+
+    periods
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{CursorMoveEvent, KeyPressEvent, TimelineSegment};
+
+    use super::*;
+
+    fn cursor_move(secs: f64, x: f64) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "0".to_string(),
+            process_time_ms: secs * 1000.0,
+            unix_time_ms: 0.0,
+            x,
+            y: 0.5,
+            display: 0,
+        }
+    }
+
+    #[test]
+    fn ignores_cursor_jitter() {
+        let mut events = CursorEvents {
+            moves: vec![
+                cursor_move(1.0, 0.5),
+                cursor_move(2.0, 0.5005),
+                cursor_move(3.0, 0.6),
+            ],
+            ..Default::default()
+        };
+        events.keyboard.presses.push(KeyPressEvent {
+            key: "a".to_string(),
+            active_modifiers: vec![],
+            process_time_ms: 4000.0,
+            unix_time_ms: 0.0,
+            text: None,
+            composing: false,
+        });
+
+        assert_eq!(
+            input_activity(&events, &IdleOptions::default()),
+            vec![3.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn finds_long_gaps() {
+        let idle = detect_idle(&[9.0, 2.0, 3.0], 20.0, &IdleOptions::default());
+
+        assert_eq!(idle, vec![3.5..8.5, 9.5..19.5]);
+    }
+
+    #[test]
+    fn idle_from_the_start() {
+        let idle = detect_idle(&[], 4.0, &IdleOptions::default());
+
+        assert_eq!(idle, vec![0.5..3.5]);
+    }
+
+    #[test]
+    fn places_periods_on_the_timeline() {
+        let segment = |recording_segment, start, end, timescale| TimelineSegment {
+            recording_segment,
+            timescale,
+            start,
+            end,
+            display: 0,
+            camera_position: None,
+            transition: None,
+        };
+        let timeline = TimelineConfiguration {
+            segments: vec![segment(0, 2.0, 6.0, 1.0), segment(1, 0.0, 10.0, 2.0)],
+            zoom_segments: vec![],
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        };
+
+        let periods = suggest_idle_periods(&timeline, &[vec![0.0..4.0], vec![4.0..8.0]]);
+
+        assert_eq!(
+            periods,
+            vec![
+                IdlePeriod {
+                    recording_segment: 0,
+                    start: 2.0,
+                    end: 4.0,
+                    timeline_start: 0.0,
+                    timeline_end: 2.0,
+                },
+                IdlePeriod {
+                    recording_segment: 1,
+                    start: 4.0,
+                    end: 8.0,
+                    timeline_start: 6.0,
+                    timeline_end: 8.0,
+                },
+            ]
+        );
+    }
+}
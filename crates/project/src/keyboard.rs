@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+pub const MODIFIER_META: &str = "Meta";
+pub const MODIFIER_CONTROL: &str = "Control";
+pub const MODIFIER_ALT: &str = "Alt";
+pub const MODIFIER_SHIFT: &str = "Shift";
+
+// The key of a press standing for text an input method committed, which carries the text
+// the composing keys before it turned into
+pub const KEY_INPUT_METHOD: &str = "InputMethod";
+
+// A non-modifier key going down. `key` is the recorder's key name, modifiers use the
+// MODIFIER_* names and are listed in the order they should be displayed.
+#[derive(Serialize, Deserialize, Clone, Type, Debug)]
+pub struct KeyPressEvent {
+    pub key: String,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: f64,
+    // what the key typed on the keyboard layout it was recorded with, including the accent
+    // of a dead key pressed before it. For shortcuts it's the key's character instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    // a dead key, or a key an input method took for text it hadn't finished composing,
+    // which don't type anything themselves
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub composing: bool,
+}
+
+impl KeyPressEvent {
+    // Held with a modifier that turns typing into a command. Shift alone only changes
+    // what's typed, so it doesn't count.
+    pub fn is_shortcut(&self) -> bool {
+        self.active_modifiers.iter().any(|m| m != MODIFIER_SHIFT)
+    }
+
+    // Keys that don't produce text, which are safe to show even when typing is hidden
+    pub fn is_special(&self) -> bool {
+        matches!(
+            self.key.as_str(),
+            "Escape"
+                | "Enter"
+                | "Tab"
+                | "Backspace"
+                | "Delete"
+                | "Up"
+                | "Down"
+                | "Left"
+                | "Right"
+                | "Home"
+                | "End"
+                | "PageUp"
+                | "PageDown"
+        ) || (self.key.starts_with('F') && self.key[1..].parse::<u8>().is_ok())
+    }
+
+    pub fn is_visible(&self, filter: KeystrokeFilter) -> bool {
+        match filter {
+            KeystrokeFilter::ShortcutsOnly => self.is_shortcut() || self.is_special(),
+            KeystrokeFilter::AllKeys => !self.composing,
+        }
+    }
+
+    // Typed text, as opposed to a shortcut or a key like Enter
+    fn typed(&self) -> Option<&str> {
+        self.text
+            .as_deref()
+            .filter(|_| !self.is_shortcut() && !self.composing)
+    }
+
+    pub fn label(&self) -> String {
+        // whitespace wouldn't show up by itself
+        if let Some(text) = self.typed().filter(|t| !t.trim().is_empty()) {
+            return text.to_string();
+        }
+
+        let key = match self.text.as_deref().filter(|_| self.is_shortcut()) {
+            Some(text) if !text.trim().is_empty() => text.to_uppercase(),
+            _ => key_label(&self.key).to_string(),
+        };
+
+        // macOS menus show shortcuts as symbols run together, in this same order
+        if cfg!(target_os = "macos") {
+            return self
+                .active_modifiers
+                .iter()
+                .map(|m| match m.as_str() {
+                    MODIFIER_CONTROL => "⌃",
+                    MODIFIER_ALT => "⌥",
+                    MODIFIER_SHIFT => "⇧",
+                    MODIFIER_META => "⌘",
+                    m => m,
+                })
+                .chain(std::iter::once(key.as_str()))
+                .collect();
+        }
+
+        self.active_modifiers
+            .iter()
+            .map(|m| match m.as_str() {
+                MODIFIER_META => "Win",
+                MODIFIER_CONTROL => "Ctrl",
+                m => m,
+            })
+            .chain(std::iter::once(key.as_str()))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+// What the overlay shows for `presses`. A run of typing reads as the text it typed, while
+// shortcuts and other keys stand apart.
+pub fn overlay_label<'a>(presses: impl IntoIterator<Item = &'a KeyPressEvent>) -> String {
+    let mut label = String::new();
+    let mut typing = false;
+
+    for press in presses {
+        match press.typed() {
+            Some(text) if typing => label.push_str(text),
+            typed => {
+                if !label.is_empty() {
+                    label.push_str("   ");
+                }
+                label.push_str(&press.label());
+                typing = typed.is_some();
+            }
+        }
+    }
+
+    label
+}
+
+// What typing `text` after a dead key for `accent` comes out as. Accents that don't
+// combine with the text are typed before it, as the OS would.
+pub fn compose_dead_key(accent: char, text: &str) -> String {
+    const COMPOSITIONS: &[(&[char], &str, &str)] = &[
+        (&['`'], "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        (&['´', '\''], "aeiouyAEIOUYcCnN", "áéíóúýÁÉÍÓÚÝćĆńŃ"),
+        (&['^', 'ˆ'], "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        (&['~', '˜'], "anoANO", "ãñõÃÑÕ"),
+        (&['¨', '"'], "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+        (&['¸'], "cC", "çÇ"),
+        (&['°', '˚'], "aA", "åÅ"),
+    ];
+
+    // a dead key followed by a space types the accent itself
+    if text == " " {
+        return accent.to_string();
+    }
+
+    let mut chars = text.chars();
+    let composed = match (chars.next(), chars.next()) {
+        (Some(base), None) => COMPOSITIONS
+            .iter()
+            .find(|(accents, _, _)| accents.contains(&accent))
+            .and_then(|(_, bases, composed)| {
+                let index = bases.chars().position(|c| c == base)?;
+                composed.chars().nth(index)
+            }),
+        _ => None,
+    };
+
+    match composed {
+        Some(c) => c.to_string(),
+        None => format!("{accent}{text}"),
+    }
+}
+
+fn key_label(key: &str) -> &str {
+    match key {
+        "Escape" => "Esc",
+        "Up" => "↑",
+        "Down" => "↓",
+        "Left" => "←",
+        "Right" => "→",
+        "Minus" => "-",
+        "Equal" => "=",
+        "LeftBracket" => "[",
+        "RightBracket" => "]",
+        "BackSlash" => "\\",
+        "Semicolon" => ";",
+        "Apostrophe" => "'",
+        "Comma" => ",",
+        "Dot" => ".",
+        "Slash" => "/",
+        "Grave" => "`",
+        // a committed full width space
+        KEY_INPUT_METHOD => "Space",
+        key => key
+            .strip_prefix("Key")
+            .or_else(|| key.strip_prefix("Numpad"))
+            .filter(|digit| digit.len() == 1)
+            .unwrap_or(key),
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
+pub struct KeyboardEvents {
+    pub presses: Vec<KeyPressEvent>,
+}
+
+impl KeyboardEvents {
+    pub fn is_empty(&self) -> bool {
+        self.presses.is_empty()
+    }
+
+    // Presses passing `filter` in the `duration_ms` leading up to `time_ms`, oldest first
+    pub fn recent(
+        &self,
+        time_ms: f64,
+        duration_ms: f64,
+        filter: KeystrokeFilter,
+    ) -> impl Iterator<Item = &KeyPressEvent> {
+        let end = self
+            .presses
+            .partition_point(|p| p.process_time_ms <= time_ms);
+        let start = self
+            .presses
+            .partition_point(|p| p.process_time_ms <= time_ms - duration_ms);
+
+        self.presses[start..end]
+            .iter()
+            .filter(move |p| p.is_visible(filter))
+    }
+}
+
+// What the keystroke overlay may show. Plain typing can contain anything from
+// passwords to private messages, so it's hidden unless asked for.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeystrokeFilter {
+    #[default]
+    ShortcutsOnly,
+    AllKeys,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn press(time_ms: f64, key: &str, modifiers: &[&str]) -> KeyPressEvent {
+        KeyPressEvent {
+            key: key.to_string(),
+            active_modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            process_time_ms: time_ms,
+            unix_time_ms: 0.0,
+            text: None,
+            composing: false,
+        }
+    }
+
+    fn typed(time_ms: f64, key: &str, text: &str) -> KeyPressEvent {
+        KeyPressEvent {
+            text: Some(text.to_string()),
+            ..press(time_ms, key, &[])
+        }
+    }
+
+    #[test]
+    fn shortcuts_only_hides_typing() {
+        let events = KeyboardEvents {
+            presses: vec![
+                press(100.0, "H", &[MODIFIER_SHIFT]),
+                press(200.0, "I", &[]),
+                press(300.0, "S", &[MODIFIER_CONTROL]),
+                press(400.0, "Enter", &[]),
+                press(500.0, "F5", &[]),
+            ],
+        };
+
+        let visible = |filter| {
+            events
+                .recent(500.0, 1000.0, filter)
+                .map(|p| p.key.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            visible(KeystrokeFilter::ShortcutsOnly),
+            vec!["S", "Enter", "F5"]
+        );
+        assert_eq!(visible(KeystrokeFilter::AllKeys).len(), 5);
+        assert_eq!(
+            events
+                .recent(350.0, 100.0, KeystrokeFilter::AllKeys)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(
+            press(0.0, "Key1", &[MODIFIER_CONTROL, MODIFIER_SHIFT]).label(),
+            "Ctrl + Shift + 1"
+        );
+        assert_eq!(press(0.0, "Dot", &[]).label(), ".");
+        assert_eq!(press(0.0, "Escape", &[]).label(), "Esc");
+
+        // the layout's character, rather than the key's US name
+        let azerty_a = KeyPressEvent {
+            text: Some("a".to_string()),
+            ..press(0.0, "Q", &[MODIFIER_CONTROL])
+        };
+        assert_eq!(azerty_a.label(), "Ctrl + A");
+        assert_eq!(typed(0.0, "Key2", "é").label(), "é");
+        assert_eq!(typed(0.0, "Space", " ").label(), "Space");
+    }
+
+    #[test]
+    fn typing_reads_as_text() {
+        let dead_key = KeyPressEvent {
+            composing: true,
+            ..press(300.0, "LeftBracket", &[])
+        };
+        let presses = [
+            typed(100.0, "C", "c"),
+            typed(200.0, "A", "a"),
+            dead_key,
+            typed(400.0, "E", &compose_dead_key('^', "e")),
+            press(500.0, "Enter", &[]),
+            typed(600.0, "Key1", "!"),
+            typed(700.0, "Space", " "),
+            typed(800.0, "X", "x"),
+            press(900.0, "S", &[MODIFIER_CONTROL]),
+        ];
+
+        let events = KeyboardEvents {
+            presses: presses.to_vec(),
+        };
+        assert_eq!(
+            overlay_label(events.recent(900.0, 1000.0, KeystrokeFilter::AllKeys)),
+            "caê   Enter   ! x   Ctrl + S"
+        );
+    }
+
+    #[test]
+    fn input_method_text() {
+        let composing = |time_ms, key| KeyPressEvent {
+            composing: true,
+            ..typed(time_ms, key, "n")
+        };
+        let committed = typed(400.0, KEY_INPUT_METHOD, "你好");
+        let presses = [
+            typed(100.0, "A", "a"),
+            composing(200.0, "N"),
+            composing(300.0, "H"),
+            committed.clone(),
+            press(500.0, "Enter", &[]),
+        ];
+
+        let events = KeyboardEvents {
+            presses: presses.to_vec(),
+        };
+        assert_eq!(
+            overlay_label(events.recent(500.0, 1000.0, KeystrokeFilter::AllKeys)),
+            "a你好   Enter"
+        );
+        assert!(!committed.is_visible(KeystrokeFilter::ShortcutsOnly));
+        assert_eq!(typed(0.0, KEY_INPUT_METHOD, "\u{3000}").label(), "Space");
+    }
+
+    #[test]
+    fn dead_keys() {
+        assert_eq!(compose_dead_key('´', "e"), "é");
+        assert_eq!(compose_dead_key('\'', "E"), "É");
+        assert_eq!(compose_dead_key('˜', "n"), "ñ");
+        assert_eq!(compose_dead_key('¨', " "), "¨");
+        assert_eq!(compose_dead_key('^', "x"), "^x");
+    }
+}
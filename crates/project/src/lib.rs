@@ -1,10 +1,44 @@
+mod annotation;
+mod bundle;
+mod captions;
+mod cards;
+mod composition;
 mod configuration;
 mod cursor;
+mod history;
+mod idle;
+mod keyboard;
+mod markers;
+mod merge;
 mod meta;
+mod migration;
+mod palette;
+mod redaction;
+mod speed;
+mod text;
+mod timeline_edit;
+mod transcript;
+mod transition;
+mod zoom_segments;
 
+pub use bundle::*;
+pub use captions::*;
+pub use cards::*;
+pub use composition::*;
 pub use configuration::*;
 pub use cursor::*;
+pub use history::*;
+pub use idle::*;
+pub use keyboard::*;
+pub use markers::*;
+pub use merge::*;
 pub use meta::*;
+pub use migration::{ProjectConfigError, PROJECT_CONFIG_VERSION};
+pub use palette::*;
+pub use redaction::*;
+pub use transcript::*;
+pub use transition::*;
+pub use zoom_segments::*;
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
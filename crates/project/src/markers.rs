@@ -0,0 +1,186 @@
+use crate::{Marker, TimelineConfiguration};
+
+// The part of the timeline from one marker up to the next, or to the end after the last
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+impl TimelineConfiguration {
+    // Adds a marker where it belongs among the others, returning its index
+    pub fn add_marker(&mut self, time: f64, name: impl Into<String>) -> usize {
+        let index = self.markers.partition_point(|m| m.time <= time);
+        self.markers.insert(
+            index,
+            Marker {
+                time,
+                name: name.into(),
+            },
+        );
+
+        index
+    }
+
+    pub fn rename_marker(&mut self, index: usize, name: impl Into<String>) -> bool {
+        let Some(marker) = self.markers.get_mut(index) else {
+            return false;
+        };
+
+        marker.name = name.into();
+        true
+    }
+
+    // Moves a marker to `time`, returning its index from then on
+    pub fn move_marker(&mut self, index: usize, time: f64) -> Option<usize> {
+        if index >= self.markers.len() {
+            return None;
+        }
+
+        let marker = self.markers.remove(index);
+        Some(self.add_marker(time, marker.name))
+    }
+
+    pub fn remove_marker(&mut self, index: usize) -> Option<Marker> {
+        (index < self.markers.len()).then(|| self.markers.remove(index))
+    }
+
+    // The chapters the markers split the timeline into. Markers past the end are left out,
+    // as are ones sharing their time with the next. Before the first marker there's no
+    // chapter, like a video without markers has none.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let duration = self.duration();
+        let markers = self
+            .markers
+            .iter()
+            .filter(|m| m.time >= 0.0 && m.time < duration)
+            .collect::<Vec<_>>();
+
+        markers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, marker)| {
+                let end = markers.get(i + 1).map_or(duration, |m| m.time);
+
+                (end > marker.time).then(|| Chapter {
+                    start: marker.time,
+                    end,
+                    title: marker.name.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+// The chapters as a WebVTT chapters file, for players that take chapters alongside a video
+pub fn chapters_webvtt(chapters: &[Chapter]) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+
+    for (i, chapter) in chapters.iter().enumerate() {
+        // a cue's text can't hold a blank line or its timing arrow
+        let title = chapter
+            .title
+            .replace(['\r', '\n'], " ")
+            .replace("-->", "->");
+
+        vtt.push_str(&format!(
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            webvtt_timestamp(chapter.start),
+            webvtt_timestamp(chapter.end),
+            title
+        ));
+    }
+
+    vtt
+}
+
+pub(crate) fn webvtt_timestamp(secs: f64) -> String {
+    let ms = (secs.max(0.0) * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timeline(duration: f64) -> TimelineConfiguration {
+        TimelineConfiguration::from_segment_durations(&[duration])
+    }
+
+    fn times(timeline: &TimelineConfiguration) -> Vec<f64> {
+        timeline.markers.iter().map(|m| m.time).collect()
+    }
+
+    #[test]
+    fn keeps_markers_sorted() {
+        let mut t = timeline(60.0);
+
+        assert_eq!(t.add_marker(30.0, "b"), 0);
+        assert_eq!(t.add_marker(10.0, "a"), 0);
+        assert_eq!(t.add_marker(45.0, "c"), 2);
+        assert_eq!(t.move_marker(0, 50.0), Some(2));
+        assert_eq!(times(&t), vec![30.0, 45.0, 50.0]);
+
+        assert!(t.rename_marker(2, "moved"));
+        assert_eq!(t.markers[2].name, "moved");
+        assert_eq!(t.remove_marker(0).map(|m| m.name), Some("b".to_string()));
+        assert_eq!(t.remove_marker(5), None);
+    }
+
+    #[test]
+    fn chapters_run_to_the_next_marker() {
+        let mut t = timeline(60.0);
+        t.add_marker(0.0, "Intro");
+        t.add_marker(20.0, "Setup");
+        t.add_marker(20.0, "Duplicate");
+        t.add_marker(90.0, "Past the end");
+
+        assert_eq!(
+            t.chapters(),
+            vec![
+                Chapter {
+                    start: 0.0,
+                    end: 20.0,
+                    title: "Intro".to_string(),
+                },
+                Chapter {
+                    start: 20.0,
+                    end: 60.0,
+                    title: "Duplicate".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_webvtt() {
+        let chapters = [
+            Chapter {
+                start: 0.0,
+                end: 61.5,
+                title: "Intro".to_string(),
+            },
+            Chapter {
+                start: 61.5,
+                end: 3725.25,
+                title: "Two\nlines --> one".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            chapters_webvtt(&chapters),
+            "WEBVTT\n\
+             \n1\n00:00:00.000 --> 00:01:01.500\nIntro\n\
+             \n2\n00:01:01.500 --> 01:02:05.250\nTwo lines -> one\n"
+        );
+    }
+}
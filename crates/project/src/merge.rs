@@ -0,0 +1,309 @@
+use crate::{
+    AnnotationSegment, CaptionSegment, CursorEvents, Marker, SpeedSegment, TextSegment,
+    TimelineConfiguration, TimelineSegment, ZoomMode, ZoomSegment, XY,
+};
+
+// One project being appended to a merged project
+pub struct MergePart {
+    pub timeline: TimelineConfiguration,
+    // how many recording segments the part brings along
+    pub recording_segments: u32,
+    pub letterbox: Letterbox,
+}
+
+impl MergePart {
+    // Projects that were never edited have no timeline, in which case every recording
+    // segment plays in full, like the editor does.
+    pub fn new(
+        timeline: Option<TimelineConfiguration>,
+        segment_durations: &[f64],
+        letterbox: Letterbox,
+    ) -> Self {
+        let timeline = timeline
+            .unwrap_or_else(|| TimelineConfiguration::from_segment_durations(segment_durations));
+
+        Self {
+            timeline,
+            recording_segments: segment_durations.len() as u32,
+            letterbox,
+        }
+    }
+}
+
+// Plays the parts back to back. Each part's recording segments come after the previous
+// parts' ones, and its zoom, speed, text, annotation and caption segments and markers are
+// shifted past the previous parts' duration, with zoom segments mapped into its letterboxed
+// area.
+pub fn merge_timelines(parts: &[MergePart]) -> TimelineConfiguration {
+    let mut segments = vec![];
+    let mut zoom_segments = vec![];
+    let mut speed_segments = vec![];
+    let mut markers = vec![];
+    let mut text_segments = vec![];
+    let mut annotation_segments = vec![];
+    let mut caption_segments = vec![];
+    let mut recording_offset = 0;
+    let mut time_offset = 0.0;
+
+    for part in parts {
+        segments.extend(part.timeline.segments.iter().map(|s| TimelineSegment {
+            recording_segment: s.recording_segment + recording_offset,
+            ..s.clone()
+        }));
+
+        zoom_segments.extend(part.timeline.zoom_segments.iter().map(|s| {
+            let mut segment = ZoomSegment {
+                start: s.start + time_offset,
+                end: s.end + time_offset,
+                ..s.clone()
+            };
+            part.letterbox.map_zoom_segment(&mut segment);
+            segment
+        }));
+
+        speed_segments.extend(part.timeline.speed_segments.iter().map(|s| SpeedSegment {
+            start: s.start + time_offset,
+            end: s.end + time_offset,
+            ..*s
+        }));
+
+        markers.extend(part.timeline.markers.iter().map(|m| Marker {
+            time: m.time + time_offset,
+            ..m.clone()
+        }));
+
+        text_segments.extend(part.timeline.text_segments.iter().map(|t| TextSegment {
+            start: t.start + time_offset,
+            end: t.end + time_offset,
+            ..t.clone()
+        }));
+
+        annotation_segments.extend(part.timeline.annotation_segments.iter().map(|a| {
+            AnnotationSegment {
+                start: a.start + time_offset,
+                end: a.end + time_offset,
+                ..a.clone()
+            }
+        }));
+
+        caption_segments.extend(
+            part.timeline
+                .caption_segments
+                .iter()
+                .map(|c| CaptionSegment {
+                    start: c.start + time_offset,
+                    end: c.end + time_offset,
+                    ..c.clone()
+                }),
+        );
+
+        recording_offset += part.recording_segments;
+        time_offset += part.timeline.duration();
+    }
+
+    TimelineConfiguration {
+        segments,
+        zoom_segments,
+        speed_segments,
+        markers,
+        text_segments,
+        annotation_segments,
+        caption_segments,
+    }
+}
+
+// The size every part is rendered at: the largest one, so nothing gets downscaled
+// unless its aspect ratio differs.
+pub fn merged_resolution(sizes: &[XY<u32>]) -> Option<XY<u32>> {
+    sizes
+        .iter()
+        .copied()
+        .reduce(|a, b| if b.x * b.y > a.x * a.y { b } else { a })
+}
+
+// Where a part's video sits within the merged resolution, scaled to fit and centered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+    pub target: XY<u32>,
+    pub size: XY<u32>,
+    pub offset: XY<u32>,
+}
+
+impl Letterbox {
+    pub fn new(source: XY<u32>, target: XY<u32>) -> Self {
+        let scale = (target.x as f64 / source.x.max(1) as f64)
+            .min(target.y as f64 / source.y.max(1) as f64);
+
+        // encoders want even dimensions
+        let even = |v: f64, max: u32| ((v.round() as u32 / 2) * 2).clamp(2, max);
+        let size = XY::new(
+            even(source.x as f64 * scale, target.x),
+            even(source.y as f64 * scale, target.y),
+        );
+
+        Self {
+            target,
+            size,
+            offset: XY::new((target.x - size.x) / 2, (target.y - size.y) / 2),
+        }
+    }
+
+    pub fn fills_target(&self) -> bool {
+        self.size == self.target
+    }
+
+    // Maps a position normalized to the source video to one normalized to the target
+    pub fn map_point(&self, point: XY<f64>) -> XY<f64> {
+        XY::new(
+            (self.offset.x as f64 + point.x * self.size.x as f64) / self.target.x as f64,
+            (self.offset.y as f64 + point.y * self.size.y as f64) / self.target.y as f64,
+        )
+    }
+
+    fn map_extent(&self, extent: XY<f64>) -> XY<f64> {
+        XY::new(
+            extent.x * self.size.x as f64 / self.target.x as f64,
+            extent.y * self.size.y as f64 / self.target.y as f64,
+        )
+    }
+
+    pub fn map_cursor_events(&self, events: &mut CursorEvents) {
+        if self.fills_target() {
+            return;
+        }
+
+        for m in &mut events.moves {
+            XY { x: m.x, y: m.y } = self.map_point(XY::new(m.x, m.y));
+        }
+        for c in &mut events.clicks {
+            XY { x: c.x, y: c.y } = self.map_point(XY::new(c.x, c.y));
+        }
+        for e in &mut events.elements {
+            XY { x: e.x, y: e.y } = self.map_point(XY::new(e.x, e.y));
+            XY {
+                x: e.width,
+                y: e.height,
+            } = self.map_extent(XY::new(e.width, e.height));
+        }
+        for w in &mut events.windows {
+            XY { x: w.x, y: w.y } = self.map_point(XY::new(w.x, w.y));
+            XY {
+                x: w.width,
+                y: w.height,
+            } = self.map_extent(XY::new(w.width, w.height));
+        }
+    }
+
+    fn map_zoom_segment(&self, segment: &mut ZoomSegment) {
+        if self.fills_target() {
+            return;
+        }
+
+        if let ZoomMode::Manual { x, y } | ZoomMode::Pan { x, y } = &mut segment.mode {
+            let point = self.map_point(XY::new(*x as f64, *y as f64));
+            (*x, *y) = (point.x as f32, point.y as f32);
+        }
+
+        for keyframe in &mut segment.keyframes {
+            keyframe.position = keyframe.position.map(|p| self.map_point(p));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timeline(segments: &[(u32, f64, f64)], zooms: &[(f64, f64)]) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments: segments
+                .iter()
+                .map(|&(recording_segment, start, end)| TimelineSegment {
+                    recording_segment,
+                    timescale: 1.0,
+                    start,
+                    end,
+                    display: 0,
+                    camera_position: None,
+                    transition: None,
+                })
+                .collect(),
+            zoom_segments: zooms
+                .iter()
+                .map(|&(start, end)| ZoomSegment {
+                    start,
+                    end,
+                    amount: 2.0,
+                    mode: ZoomMode::Manual { x: 0.5, y: 0.0 },
+                    transition_duration_in: None,
+                    transition_duration_out: None,
+                    keyframes: vec![],
+                })
+                .collect(),
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }
+    }
+
+    #[test]
+    fn rebases_segments_and_zooms() {
+        let same = Letterbox::new(XY::new(1920, 1080), XY::new(1920, 1080));
+        let parts = [
+            MergePart::new(
+                Some(timeline(&[(0, 2.0, 6.0), (1, 0.0, 3.0)], &[(1.0, 2.0)])),
+                &[10.0, 3.0],
+                same,
+            ),
+            MergePart::new(None, &[5.0], same),
+            MergePart::new(
+                Some(timeline(&[(0, 1.0, 2.0)], &[(0.5, 1.0)])),
+                &[2.0],
+                same,
+            ),
+        ];
+
+        let merged = merge_timelines(&parts);
+
+        assert_eq!(
+            merged
+                .segments
+                .iter()
+                .map(|s| (s.recording_segment, s.start, s.end))
+                .collect::<Vec<_>>(),
+            vec![(0, 2.0, 6.0), (1, 0.0, 3.0), (2, 0.0, 5.0), (3, 1.0, 2.0)]
+        );
+        assert_eq!(
+            merged
+                .zoom_segments
+                .iter()
+                .map(|s| (s.start, s.end))
+                .collect::<Vec<_>>(),
+            vec![(1.0, 2.0), (12.5, 13.0)]
+        );
+        assert_eq!(merged.duration(), 13.0);
+    }
+
+    #[test]
+    fn letterboxes_narrower_parts() {
+        let target = merged_resolution(&[XY::new(1280, 720), XY::new(1920, 1080)]).unwrap();
+        assert_eq!(target, XY::new(1920, 1080));
+
+        let letterbox = Letterbox::new(XY::new(1440, 1080), target);
+        assert_eq!(letterbox.size, XY::new(1440, 1080));
+        assert_eq!(letterbox.offset, XY::new(240, 0));
+        assert_eq!(letterbox.map_point(XY::new(0.0, 0.5)), XY::new(0.125, 0.5));
+        assert_eq!(letterbox.map_point(XY::new(1.0, 1.0)), XY::new(0.875, 1.0));
+
+        let mut timeline = timeline(&[(0, 0.0, 1.0)], &[(0.0, 1.0)]);
+        letterbox.map_zoom_segment(&mut timeline.zoom_segments[0]);
+        assert!(matches!(
+            timeline.zoom_segments[0].mode,
+            ZoomMode::Manual { x, y } if x == 0.5 && y == 0.0
+        ));
+
+        assert!(Letterbox::new(XY::new(1280, 720), target).fills_target());
+    }
+}
@@ -8,7 +8,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::{CursorEvents, CursorImage, CursorImages, ProjectConfiguration, XY};
+use crate::{CursorEvents, CursorImage, CursorImages, CursorShape, ProjectConfiguration, XY};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct Display {
@@ -177,6 +177,8 @@ pub struct CursorMeta {
     #[specta(type = String)]
     pub image_path: RelativePathBuf,
     pub hotspot: XY<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape: Option<CursorShape>,
 }
 
 impl MultipleSegments {
@@ -195,6 +197,7 @@ impl MultipleSegments {
                         CursorImage {
                             path: meta.path(&v.image_path),
                             hotspot: v.hotspot,
+                            shape: v.shape,
                         },
                     )
                 })
@@ -203,16 +206,91 @@ impl MultipleSegments {
     }
 }
 
+// What a segment's display track was recorded from. Window segments only contain the
+// window's own pixels, captured even while other windows cover it, and their cursor
+// events are relative to the window's bounds.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum SegmentSource {
+    #[default]
+    Display,
+    Window {
+        owner_name: String,
+        name: String,
+    },
+}
+
+impl SegmentSource {
+    pub fn is_display(&self) -> bool {
+        matches!(self, Self::Display)
+    }
+}
+
+// The part of the display an area recording captured, in fractions of the display.
+// Cursor events are recorded relative to the whole display and mapped into the region
+// when they're loaded.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq)]
+pub struct CaptureRegion {
+    pub position: XY<f64>,
+    pub size: XY<f64>,
+}
+
+impl CaptureRegion {
+    // Maps a position normalized to the display to one normalized to the region. Points
+    // outside of the region end up outside of 0-1.
+    pub fn map_point(&self, point: XY<f64>) -> XY<f64> {
+        (point - self.position) / self.size
+    }
+
+    pub fn map_cursor_events(&self, events: &mut CursorEvents) {
+        for m in events.moves.iter_mut().filter(|m| m.display == 0) {
+            XY { x: m.x, y: m.y } = self.map_point(XY::new(m.x, m.y));
+        }
+        for c in events.clicks.iter_mut().filter(|c| c.display == 0) {
+            XY { x: c.x, y: c.y } = self.map_point(XY::new(c.x, c.y));
+        }
+        for e in &mut events.elements {
+            XY { x: e.x, y: e.y } = self.map_point(XY::new(e.x, e.y));
+            XY {
+                x: e.width,
+                y: e.height,
+            } = XY::new(e.width, e.height) / self.size;
+        }
+        for w in &mut events.windows {
+            XY { x: w.x, y: w.y } = self.map_point(XY::new(w.x, w.y));
+            XY {
+                x: w.width,
+                y: w.height,
+            } = XY::new(w.width, w.height) / self.size;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct MultipleSegment {
     pub display: Display,
+    // other displays recorded at the same time. `display` is track 0 and these follow it,
+    // which is the numbering timeline segments and cursor events use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_displays: Vec<Display>,
+    #[serde(default, skip_serializing_if = "SegmentSource::is_display")]
+    pub source: SegmentSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<CaptureRegion>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub camera: Option<CameraMeta>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub audio: Option<AudioMeta>,
+    // what the system played while recording, kept apart from the microphone in `audio`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_audio: Option<AudioMeta>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[specta(type = Option<String>)]
     pub cursor: Option<RelativePathBuf>,
+    // put in front of the cursor ids of its events, for segments of a take whose ids would
+    // clash with the project's own
+    #[serde(skip)]
+    pub cursor_id_prefix: String,
 }
 
 impl MultipleSegment {
@@ -220,6 +298,10 @@ impl MultipleSegment {
         meta.project_path.join(path)
     }
 
+    pub fn displays(&self) -> impl Iterator<Item = &Display> {
+        std::iter::once(&self.display).chain(&self.additional_displays)
+    }
+
     pub fn cursor_events(&self, meta: &RecordingMeta) -> CursorEvents {
         let Some(cursor_path) = &self.cursor else {
             return CursorEvents::default();
@@ -230,7 +312,22 @@ impl MultipleSegment {
 
         // Try to load the cursor data
         match CursorEvents::load_from_file(&full_path) {
-            Ok(data) => data,
+            Ok(mut data) => {
+                if let Some(region) = &self.region {
+                    region.map_cursor_events(&mut data);
+                }
+
+                if !self.cursor_id_prefix.is_empty() {
+                    for m in &mut data.moves {
+                        m.cursor_id.insert_str(0, &self.cursor_id_prefix);
+                    }
+                    for c in &mut data.clicks {
+                        c.cursor_id.insert_str(0, &self.cursor_id_prefix);
+                    }
+                }
+
+                data
+            }
             Err(e) => {
                 eprintln!("Failed to load cursor data: {}", e);
                 CursorEvents::default()
@@ -241,7 +338,8 @@ impl MultipleSegment {
 
 #[cfg(test)]
 mod test {
-    use super::RecordingMeta;
+    use super::{CaptureRegion, Content, RecordingMeta, SegmentSource};
+    use crate::{CursorEvents, CursorMoveEvent, XY};
 
     fn test_meta_deserialize(s: &str) {
         let _: RecordingMeta = serde_json::from_str(s).unwrap();
@@ -347,5 +445,114 @@ mod test {
 		          ]
 		        }"#,
         );
+
+        // multiple displays
+        test_meta_deserialize(
+            r#"{
+              "pretty_name": "Cap 2025-01-08 at 10.12.05",
+              "sharing": null,
+              "segments": [
+                {
+                  "display": {
+                    "path": "content/segments/segment-0/display.mp4"
+                  },
+                  "additional_displays": [
+                    {
+                      "path": "content/segments/segment-0/display-1.mp4"
+                    }
+                  ],
+                  "cursor": "content/segments/segment-0/cursor.json"
+                }
+              ]
+            }"#,
+        );
+
+        // system audio
+        test_meta_deserialize(
+            r#"{
+              "pretty_name": "Cap 2025-01-12 at 09.41.17",
+              "sharing": null,
+              "segments": [
+                {
+                  "display": {
+                    "path": "content/segments/segment-0/display.mp4"
+                  },
+                  "audio": {
+                    "path": "content/segments/segment-0/audio-input.ogg"
+                  },
+                  "system_audio": {
+                    "path": "content/segments/segment-0/system-audio.ogg"
+                  }
+                }
+              ]
+            }"#,
+        );
+    }
+
+    #[test]
+    fn window_segment() {
+        let meta: RecordingMeta = serde_json::from_str(
+            r#"{
+              "pretty_name": "Cap 2025-01-10 at 14.02.51",
+              "sharing": null,
+              "segments": [
+                {
+                  "display": {
+                    "path": "content/segments/segment-0/display.mp4"
+                  },
+                  "source": {
+                    "type": "window",
+                    "owner_name": "Safari",
+                    "name": "Cap"
+                  }
+                },
+                {
+                  "display": {
+                    "path": "content/segments/segment-1/display.mp4"
+                  }
+                }
+              ]
+            }"#,
+        )
+        .unwrap();
+
+        let Content::MultipleSegments { inner } = meta.content else {
+            panic!("expected multiple segments");
+        };
+
+        assert_eq!(
+            inner.segments[0].source,
+            SegmentSource::Window {
+                owner_name: "Safari".to_string(),
+                name: "Cap".to_string(),
+            }
+        );
+        assert_eq!(inner.segments[1].source, SegmentSource::Display);
+    }
+
+    #[test]
+    fn region_maps_cursor() {
+        let region = CaptureRegion {
+            position: XY::new(0.25, 0.5),
+            size: XY::new(0.5, 0.25),
+        };
+        let mv = |x, y, display| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: 0.0,
+            unix_time_ms: 0.0,
+            x,
+            y,
+            display,
+        };
+        let mut events = CursorEvents {
+            moves: vec![mv(0.5, 0.625, 0), mv(0.0, 0.0, 0), mv(0.5, 0.5, 1)],
+            ..Default::default()
+        };
+
+        region.map_cursor_events(&mut events);
+
+        let positions = events.moves.iter().map(|m| (m.x, m.y)).collect::<Vec<_>>();
+        assert_eq!(positions, vec![(0.5, 0.5), (-0.5, -2.0), (0.5, 0.5)]);
     }
 }
@@ -0,0 +1,115 @@
+use serde_json::{Map, Value};
+
+use crate::ProjectConfiguration;
+
+// The version of project configs this app writes. Changes to the config that older files
+// can't be read as they are bump it, along with a migration that rewrites them.
+pub const PROJECT_CONFIG_VERSION: u32 = 1;
+
+// `MIGRATIONS[n]` upgrades a version `n` config to version `n + 1`. Configs written before
+// versioning have no version and start at 0.
+const MIGRATIONS: [fn(&mut Map<String, Value>); PROJECT_CONFIG_VERSION as usize] =
+    [fill_missing_sections];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectConfigError {
+    #[error("Couldn't read the project config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The project config is invalid: {0}")]
+    Invalid(#[from] serde_json::Error),
+    #[error(
+        "The project config is version {version}, newer than the version {} this app supports. Update Cap to open it.",
+        PROJECT_CONFIG_VERSION
+    )]
+    TooNew { version: u32 },
+}
+
+// Brings a config written by any version up to this one to the current version
+pub(crate) fn migrate(config: &mut Value) -> Result<(), ProjectConfigError> {
+    let Value::Object(config) = config else {
+        return Err(ProjectConfigError::Invalid(serde::de::Error::custom(
+            "expected an object",
+        )));
+    };
+
+    let version = match config.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                ProjectConfigError::Invalid(serde::de::Error::custom("invalid version"))
+            })?,
+    };
+
+    if version > PROJECT_CONFIG_VERSION {
+        return Err(ProjectConfigError::TooNew { version });
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config);
+    }
+    config.insert("version".to_string(), PROJECT_CONFIG_VERSION.into());
+
+    Ok(())
+}
+
+// 0 to 1. Before versioning, a config missing any of its sections, like one written before
+// the section existed, failed to load and was replaced by the defaults as a whole. The
+// missing sections alone are given their defaults instead.
+fn fill_missing_sections(config: &mut Map<String, Value>) {
+    let Ok(Value::Object(defaults)) = serde_json::to_value(ProjectConfiguration::default()) else {
+        return;
+    };
+
+    for (key, value) in defaults {
+        config.entry(key).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn fills_in_unversioned_configs() {
+        let config = ProjectConfiguration::from_json(
+            &json!({
+                "aspectRatio": null,
+                "background": {
+                    "source": { "type": "color", "value": [255, 0, 0] },
+                    "blur": 0.0,
+                    "padding": 12.0,
+                    "rounding": 0.0,
+                    "inset": 0,
+                    "crop": null
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(config.version, PROJECT_CONFIG_VERSION);
+        assert_eq!(config.background.padding, 12.0);
+    }
+
+    #[test]
+    fn current_configs_round_trip() {
+        let json = serde_json::to_string(&ProjectConfiguration::default()).unwrap();
+
+        assert!(ProjectConfiguration::from_json(&json).is_ok());
+    }
+
+    #[test]
+    fn rejects_newer_configs() {
+        let mut config = serde_json::to_value(ProjectConfiguration::default()).unwrap();
+        config["version"] = json!(PROJECT_CONFIG_VERSION + 1);
+
+        assert!(matches!(
+            ProjectConfiguration::from_json(&config.to_string()),
+            Err(ProjectConfigError::TooNew { version }) if version == PROJECT_CONFIG_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::Color;
+
+// WCAG's minimum for graphical objects, which overlays are closest to
+pub const MIN_OVERLAY_CONTRAST: f64 = 3.0;
+
+// What recordings mostly show: light documents and web pages, or dark editors and terminals
+const LIGHT_CONTENT: Color = [255, 255, 255];
+const DARK_CONTENT: Color = [30, 30, 30];
+
+// Categorical palettes for click highlights and annotations. Each one stays distinguishable
+// under protanopia, deuteranopia and tritanopia, and every color clears MIN_OVERLAY_CONTRAST
+// against both light and dark content.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlayPalette {
+    #[default]
+    OkabeIto,
+    TolVibrant,
+}
+
+impl OverlayPalette {
+    pub fn colors(&self) -> &'static [Color] {
+        match self {
+            Self::OkabeIto => &[[0, 114, 178], [213, 94, 0], [0, 158, 115], [204, 121, 167]],
+            Self::TolVibrant => &[[0, 119, 187], [238, 51, 119], [0, 153, 136], [204, 51, 17]],
+        }
+    }
+}
+
+// Perceptually uniform gradients for heatmaps, which read correctly without full color vision
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HeatmapPalette {
+    #[default]
+    Viridis,
+    Cividis,
+}
+
+impl HeatmapPalette {
+    pub fn stops(&self) -> &'static [Color] {
+        match self {
+            Self::Viridis => &[
+                [68, 1, 84],
+                [59, 82, 139],
+                [33, 145, 140],
+                [94, 201, 98],
+                [253, 231, 37],
+            ],
+            Self::Cividis => &[
+                [0, 32, 77],
+                [65, 77, 107],
+                [124, 123, 120],
+                [188, 175, 111],
+                [255, 234, 70],
+            ],
+        }
+    }
+
+    // Samples the gradient at `t` in 0..=1
+    pub fn sample(&self, t: f64) -> Color {
+        let stops = self.stops();
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (position.floor() as usize).min(stops.len() - 2);
+        let t = position - i as f64;
+
+        std::array::from_fn(|c| {
+            let (a, b) = (stops[i][c] as f64, stops[i + 1][c] as f64);
+            (a + (b - a) * t).round() as u16
+        })
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayConfiguration {
+    #[serde(default)]
+    pub palette: OverlayPalette,
+    #[serde(default)]
+    pub heatmap: HeatmapPalette,
+    // custom colors replace the palette's pick for that overlay
+    #[serde(default)]
+    pub click_highlight_color: Option<Color>,
+    #[serde(default)]
+    pub annotation_color: Option<Color>,
+}
+
+impl OverlayConfiguration {
+    pub fn click_highlight_color(&self) -> Color {
+        self.click_highlight_color
+            .unwrap_or(self.palette.colors()[0])
+    }
+
+    pub fn annotation_color(&self) -> Color {
+        self.annotation_color.unwrap_or(self.palette.colors()[1])
+    }
+
+    // Warns about user-selected colors that would be hard to see over typical screen content
+    pub fn lint(&self) -> Vec<OverlayColorWarning> {
+        [
+            (OverlayKind::ClickHighlight, self.click_highlight_color),
+            (OverlayKind::Annotation, self.annotation_color),
+        ]
+        .into_iter()
+        .filter_map(|(overlay, color)| Some((overlay, color?)))
+        .flat_map(|(overlay, color)| {
+            [
+                (ScreenContent::Light, LIGHT_CONTENT),
+                (ScreenContent::Dark, DARK_CONTENT),
+            ]
+            .into_iter()
+            .filter_map(move |(content, background)| {
+                let contrast = contrast_ratio(color, background);
+
+                (contrast < MIN_OVERLAY_CONTRAST).then_some(OverlayColorWarning {
+                    overlay,
+                    color,
+                    content,
+                    contrast,
+                })
+            })
+        })
+        .collect()
+    }
+}
+
+#[derive(Type, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlayKind {
+    ClickHighlight,
+    Annotation,
+}
+
+#[derive(Type, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenContent {
+    Light,
+    Dark,
+}
+
+#[derive(Type, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayColorWarning {
+    pub overlay: OverlayKind,
+    pub color: Color,
+    pub content: ScreenContent,
+    pub contrast: f64,
+}
+
+// WCAG contrast ratio between two sRGB colors, from 1 (identical) to 21 (black on white)
+pub fn contrast_ratio(a: Color, b: Color) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn relative_luminance(color: Color) -> f64 {
+    let [r, g, b] = color.map(|c| {
+        let c = c.min(255) as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn palettes_pass_their_own_lint() {
+        for palette in [OverlayPalette::OkabeIto, OverlayPalette::TolVibrant] {
+            for &color in palette.colors() {
+                let config = OverlayConfiguration {
+                    click_highlight_color: Some(color),
+                    ..Default::default()
+                };
+                assert!(config.lint().is_empty(), "{palette:?} {color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn low_contrast_custom_color_warns() {
+        let config = OverlayConfiguration {
+            annotation_color: Some([240, 228, 66]),
+            ..Default::default()
+        };
+
+        let warnings = config.lint();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].overlay, OverlayKind::Annotation);
+        assert_eq!(warnings[0].content, ScreenContent::Light);
+        assert!(warnings[0].contrast < 1.5);
+    }
+
+    #[test]
+    fn heatmap_sample_hits_stops() {
+        let palette = HeatmapPalette::Viridis;
+        assert_eq!(palette.sample(0.0), palette.stops()[0]);
+        assert_eq!(palette.sample(1.0), palette.stops()[4]);
+        assert_eq!(palette.sample(0.5), palette.stops()[2]);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{ProjectConfiguration, SpeedSegment, TimelineConfiguration};
+
+impl TimelineConfiguration {
+    // The speed segments that take effect, in order and clamped to the timeline. Where
+    // segments overlap the earlier one wins.
+    fn speed_ranges(&self) -> Vec<SpeedSegment> {
+        let duration = self.duration();
+
+        let mut ranges = self
+            .speed_segments
+            .iter()
+            .filter(|s| s.factor.is_finite() && s.factor > 0.0)
+            .map(|s| SpeedSegment {
+                start: s.start.max(0.0),
+                end: s.end.min(duration),
+                ..*s
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let mut last_end = 0.0_f64;
+        ranges.retain_mut(|range| {
+            range.start = range.start.max(last_end);
+            last_end = last_end.max(range.end);
+            range.end > range.start
+        });
+
+        ranges
+    }
+
+    // Where `time` on the timeline ends up once its speed segments are applied
+    pub fn speed_adjusted_time(&self, time: f64) -> f64 {
+        adjust_time(&self.speed_ranges(), time)
+    }
+
+    // The timeline as it plays with its speed segments applied, which is what gets rendered.
+    // Segments are split where speed changes and play faster or slower through their
+    // timescale, which video, cursor and audio all follow, and zoom, text and caption segments
+    // and markers are moved to stay over the same moments.
+    pub fn with_speed_applied(&self) -> Self {
+        let ranges = self.speed_ranges();
+        let mut timeline = Self {
+            speed_segments: vec![],
+            ..self.clone()
+        };
+
+        if ranges.is_empty() {
+            return timeline;
+        }
+
+        for range in &ranges {
+            timeline.split_at(range.start);
+            timeline.split_at(range.end);
+        }
+
+        // after splitting, every segment lies either inside a range or outside all of them
+        let mut offset = 0.0;
+        for segment in &mut timeline.segments {
+            let middle = offset + segment.duration() / 2.0;
+            offset += segment.duration();
+
+            if let Some(range) = ranges.iter().find(|r| middle > r.start && middle < r.end) {
+                segment.timescale *= range.factor;
+            }
+        }
+
+        for zoom in &mut timeline.zoom_segments {
+            let start = zoom.start;
+            zoom.start = adjust_time(&ranges, zoom.start);
+            zoom.end = adjust_time(&ranges, zoom.end);
+
+            for keyframe in &mut zoom.keyframes {
+                keyframe.time = adjust_time(&ranges, start + keyframe.time) - zoom.start;
+            }
+        }
+
+        for text in &mut timeline.text_segments {
+            text.start = adjust_time(&ranges, text.start);
+            text.end = adjust_time(&ranges, text.end);
+        }
+
+        for annotation in &mut timeline.annotation_segments {
+            annotation.start = adjust_time(&ranges, annotation.start);
+            annotation.end = adjust_time(&ranges, annotation.end);
+        }
+
+        for caption in &mut timeline.caption_segments {
+            caption.start = adjust_time(&ranges, caption.start);
+            caption.end = adjust_time(&ranges, caption.end);
+        }
+
+        for marker in &mut timeline.markers {
+            marker.time = adjust_time(&ranges, marker.time);
+        }
+
+        timeline
+    }
+}
+
+impl ProjectConfiguration {
+    pub fn with_speed_applied(&self) -> Self {
+        Self {
+            timeline: self
+                .timeline
+                .as_ref()
+                .map(TimelineConfiguration::with_speed_applied),
+            ..self.clone()
+        }
+    }
+}
+
+// Each range before `time` takes `1 / factor` as long to play
+fn adjust_time(ranges: &[SpeedSegment], time: f64) -> f64 {
+    let mut adjusted = time;
+
+    for range in ranges.iter().take_while(|r| time > r.start) {
+        let covered = time.min(range.end) - range.start;
+        adjusted -= covered - covered / range.factor;
+    }
+
+    adjusted
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{TimelineSegment, ZoomKeyframe, ZoomMode, ZoomSegment};
+
+    use super::*;
+
+    fn timeline(speeds: &[(f64, f64, f64)]) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments: vec![TimelineSegment {
+                recording_segment: 0,
+                timescale: 1.0,
+                start: 0.0,
+                end: 10.0,
+                display: 0,
+                camera_position: None,
+                transition: None,
+            }],
+            zoom_segments: vec![ZoomSegment {
+                start: 5.0,
+                end: 9.0,
+                amount: 2.0,
+                mode: ZoomMode::Auto,
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![ZoomKeyframe {
+                    time: 3.0,
+                    amount: 1.5,
+                    position: None,
+                    easing: Default::default(),
+                }],
+            }],
+            speed_segments: speeds
+                .iter()
+                .map(|&(start, end, factor)| SpeedSegment { start, end, factor })
+                .collect(),
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }
+    }
+
+    #[test]
+    fn speeds_up_segments() {
+        let t = timeline(&[(2.0, 6.0, 2.0)]).with_speed_applied();
+
+        let segments = t
+            .segments
+            .iter()
+            .map(|s| (s.start, s.end, s.timescale))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![(0.0, 2.0, 1.0), (2.0, 6.0, 2.0), (6.0, 10.0, 1.0)]
+        );
+        assert_eq!(t.duration(), 8.0);
+        assert!(t.speed_segments.is_empty());
+
+        // 6 seconds into the recording is now 4 seconds in
+        assert_eq!(t.get_segment_time(4.0), Some((6.0, 0)));
+    }
+
+    #[test]
+    fn moves_zoom_segments() {
+        let t = timeline(&[(2.0, 6.0, 2.0), (8.0, 10.0, 0.5)]).with_speed_applied();
+
+        let zoom = &t.zoom_segments[0];
+        assert_eq!((zoom.start, zoom.end), (3.5, 8.0));
+        assert_eq!(zoom.keyframes[0].time, 2.5);
+    }
+
+    #[test]
+    fn earlier_overlapping_segment_wins() {
+        let t = timeline(&[(4.0, 8.0, 0.5), (2.0, 6.0, 2.0)]);
+
+        assert_eq!(t.speed_adjusted_time(6.0), 4.0);
+        assert_eq!(t.speed_adjusted_time(8.0), 8.0);
+        assert_eq!(t.with_speed_applied().duration(), 10.0);
+    }
+}
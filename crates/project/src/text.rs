@@ -0,0 +1,89 @@
+use crate::{TextAnimation, TextSegment, TimelineConfiguration};
+
+impl TextSegment {
+    // seconds spent coming in and going out, or less in short segments
+    pub const ANIMATION_DURATION: f64 = 0.3;
+
+    // How far the text has come in at `time`, eased: 1 while it's fully shown, falling to 0
+    // at the segment's edges. None outside the segment.
+    pub fn presence(&self, time: f64) -> Option<f64> {
+        if time < self.start || time >= self.end {
+            return None;
+        }
+        if self.animation == TextAnimation::None {
+            return Some(1.0);
+        }
+
+        Some(eased_presence(
+            self.start,
+            self.end,
+            time,
+            Self::ANIMATION_DURATION,
+        ))
+    }
+}
+
+// How far an overlay shown over `start..end`, spending `duration` coming in and going out,
+// has come in at `time`
+pub(crate) fn eased_presence(start: f64, end: f64, time: f64, duration: f64) -> f64 {
+    let duration = duration.min((end - start) / 2.0);
+    let t = ((time - start).min(end - time) / duration).clamp(0.0, 1.0);
+
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl TimelineConfiguration {
+    // The text segments showing at `time`, in the order they're drawn, along with how far
+    // they've come in
+    pub fn text_at(&self, time: f64) -> impl Iterator<Item = (&TextSegment, f64)> {
+        self.text_segments
+            .iter()
+            .filter_map(move |text| Some((text, text.presence(time)?)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{TextFont, XY};
+
+    use super::*;
+
+    fn text(start: f64, end: f64, animation: TextAnimation) -> TextSegment {
+        TextSegment {
+            start,
+            end,
+            content: "Title".to_string(),
+            font: TextFont::Sans,
+            size: 5.0,
+            color: [255, 255, 255],
+            position: XY::new(0.1, 0.8),
+            animation,
+        }
+    }
+
+    #[test]
+    fn eases_in_and_out() {
+        let t = text(1.0, 3.0, TextAnimation::Fade);
+
+        assert_eq!(t.presence(0.5), None);
+        assert_eq!(t.presence(1.0), Some(0.0));
+        assert!((t.presence(1.15).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(t.presence(2.0), Some(1.0));
+        assert_eq!(t.presence(3.0), None);
+    }
+
+    #[test]
+    fn short_segments_animate_faster() {
+        let t = text(0.0, 0.2, TextAnimation::SlideFromLeft);
+
+        assert_eq!(t.presence(0.1), Some(1.0));
+        assert_eq!(t.presence(0.05), Some(0.5));
+    }
+
+    #[test]
+    fn no_animation_shows_at_once() {
+        let t = text(1.0, 3.0, TextAnimation::None);
+
+        assert_eq!(t.presence(1.0), Some(1.0));
+    }
+}
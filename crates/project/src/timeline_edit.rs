@@ -0,0 +1,422 @@
+use crate::{TimelineConfiguration, TimelineSegment};
+
+// Times closer than this are the same, so float error from earlier edits doesn't leave
+// slivers of segments behind
+const EPSILON: f64 = 1e-6;
+
+// Edits made on the timeline, in timeline seconds unless noted otherwise. Zoom, speed, text,
+// annotation and caption segments and markers are laid out in timeline time, so they're
+// moved along with the cut.
+// Cursor events and audio are in recording time, which segments map to, so they follow by
+// themselves.
+impl TimelineConfiguration {
+    // Where segment `index` starts on the timeline
+    fn segment_offset(&self, index: usize) -> f64 {
+        self.segments[..index].iter().map(|s| s.duration()).sum()
+    }
+
+    // Splits the segment playing at `time` in two, both keeping its settings apart from the
+    // transition, which stays at the first's start. Returns false when `time` is on a
+    // segment's edge or outside the timeline.
+    pub fn split_at(&mut self, time: f64) -> bool {
+        let mut offset = 0.0;
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let duration = segment.duration();
+
+            if time > offset + EPSILON && time < offset + duration - EPSILON {
+                let split = segment.start + (time - offset) * segment.timescale;
+                let second = TimelineSegment {
+                    start: split,
+                    transition: None,
+                    ..segment.clone()
+                };
+
+                self.segments[i].end = split;
+                self.segments.insert(i + 1, second);
+
+                return true;
+            }
+
+            offset += duration;
+        }
+
+        false
+    }
+
+    // Splices `segment` in at `time`, moving everything after it along. Returns false when
+    // `time` is outside the timeline.
+    pub fn insert_segment(&mut self, time: f64, segment: TimelineSegment) -> bool {
+        if time < -EPSILON || time > self.duration() + EPSILON {
+            return false;
+        }
+
+        self.split_at(time);
+
+        let mut offset = 0.0;
+        let index = self
+            .segments
+            .iter()
+            .position(|s| {
+                let start = offset;
+                offset += s.duration();
+                start >= time - EPSILON
+            })
+            .unwrap_or(self.segments.len());
+
+        self.shift_tracks(time, segment.duration());
+        self.segments.insert(index, segment);
+
+        true
+    }
+
+    // Removes `start..end` from the timeline, with everything after it moving back to close
+    // the gap. Zoom segments inside the range are dropped and ones across its edges trimmed.
+    pub fn delete_range(&mut self, start: f64, end: f64) {
+        let start = start.max(0.0);
+        let end = end.min(self.duration());
+        if end - start <= EPSILON {
+            return;
+        }
+
+        self.split_at(start);
+        self.split_at(end);
+
+        // every segment now lies either inside the range or outside it
+        let mut offset = 0.0;
+        self.segments.retain(|segment| {
+            let middle = offset + segment.duration() / 2.0;
+            offset += segment.duration();
+            middle < start || middle > end
+        });
+
+        self.ripple_tracks(start, end);
+    }
+
+    pub fn delete_segment(&mut self, index: usize) {
+        if index >= self.segments.len() {
+            return;
+        }
+
+        let start = self.segment_offset(index);
+        self.delete_range(start, start + self.segments[index].duration());
+    }
+
+    // Moves the in and out points of segment `index` to `start` and `end` seconds into its
+    // recording, which the caller keeps within the recording's length. Later segments and
+    // zoom segments move with the segment's new end. Returns false if the points are invalid.
+    pub fn trim_segment(&mut self, index: usize, start: f64, end: f64) -> bool {
+        let Some(segment) = self.segments.get(index) else {
+            return false;
+        };
+        if !(start >= 0.0 && end - start > EPSILON) {
+            return false;
+        }
+
+        let offset = self.segment_offset(index);
+        let old_end = offset + segment.duration();
+        // timeline seconds gained at either end, or lost when negative
+        let in_change = (segment.start - start) / segment.timescale;
+        let out_change = (end - segment.end) / segment.timescale;
+
+        // the end first, so the in point's change doesn't move where it is
+        if out_change < 0.0 {
+            self.ripple_tracks(old_end + out_change, old_end);
+        } else {
+            self.shift_tracks(old_end, out_change);
+        }
+
+        if in_change < 0.0 {
+            self.ripple_tracks(offset, offset - in_change);
+        } else {
+            // zoom segments starting with this segment start with its new in point
+            self.shift_tracks(offset - EPSILON, in_change);
+        }
+
+        self.segments[index].start = start;
+        self.segments[index].end = end;
+
+        true
+    }
+
+    // Removes `start..end` of the zoom, speed, text and annotation segments' time. Markers in
+    // it move to its start rather than being lost.
+    fn ripple_tracks(&mut self, start: f64, end: f64) {
+        let ripple = |time: f64| {
+            if time <= start {
+                time
+            } else if time >= end {
+                time - (end - start)
+            } else {
+                start
+            }
+        };
+
+        self.zoom_segments.retain_mut(|zoom| {
+            let old_start = zoom.start;
+            zoom.start = ripple(zoom.start);
+            zoom.end = ripple(zoom.end);
+
+            zoom.keyframes.retain_mut(|keyframe| {
+                let time = old_start + keyframe.time;
+                keyframe.time = ripple(time) - zoom.start;
+                time <= start || time >= end
+            });
+
+            zoom.end - zoom.start > EPSILON
+        });
+
+        self.speed_segments.retain_mut(|speed| {
+            speed.start = ripple(speed.start);
+            speed.end = ripple(speed.end);
+            speed.end - speed.start > EPSILON
+        });
+
+        self.text_segments.retain_mut(|text| {
+            text.start = ripple(text.start);
+            text.end = ripple(text.end);
+            text.end - text.start > EPSILON
+        });
+
+        self.annotation_segments.retain_mut(|annotation| {
+            annotation.start = ripple(annotation.start);
+            annotation.end = ripple(annotation.end);
+            annotation.end - annotation.start > EPSILON
+        });
+
+        self.caption_segments.retain_mut(|caption| {
+            caption.start = ripple(caption.start);
+            caption.end = ripple(caption.end);
+            caption.end - caption.start > EPSILON
+        });
+
+        for marker in &mut self.markers {
+            marker.time = ripple(marker.time);
+        }
+    }
+
+    // Inserts `by` seconds at `at`, lengthening zoom, speed, text, annotation and caption
+    // segments across it and moving markers after it
+    fn shift_tracks(&mut self, at: f64, by: f64) {
+        if by <= 0.0 {
+            return;
+        }
+
+        for zoom in &mut self.zoom_segments {
+            if zoom.start >= at {
+                zoom.start += by;
+                zoom.end += by;
+            } else if zoom.end > at {
+                zoom.end += by;
+
+                for keyframe in &mut zoom.keyframes {
+                    if zoom.start + keyframe.time >= at {
+                        keyframe.time += by;
+                    }
+                }
+            }
+        }
+
+        for speed in &mut self.speed_segments {
+            if speed.start >= at {
+                speed.start += by;
+            }
+            if speed.end > at {
+                speed.end += by;
+            }
+        }
+
+        for text in &mut self.text_segments {
+            if text.start >= at {
+                text.start += by;
+            }
+            if text.end > at {
+                text.end += by;
+            }
+        }
+
+        for annotation in &mut self.annotation_segments {
+            if annotation.start >= at {
+                annotation.start += by;
+            }
+            if annotation.end > at {
+                annotation.end += by;
+            }
+        }
+
+        for caption in &mut self.caption_segments {
+            if caption.start >= at {
+                caption.start += by;
+            }
+            if caption.end > at {
+                caption.end += by;
+            }
+        }
+
+        for marker in &mut self.markers {
+            if marker.time >= at {
+                marker.time += by;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ZoomKeyframe, ZoomMode, ZoomSegment};
+
+    use super::*;
+
+    fn timeline(segments: &[(f64, f64)], zooms: &[(f64, f64)]) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments: segments
+                .iter()
+                .map(|&(start, end)| TimelineSegment {
+                    recording_segment: 0,
+                    timescale: 1.0,
+                    start,
+                    end,
+                    display: 0,
+                    camera_position: None,
+                    transition: None,
+                })
+                .collect(),
+            zoom_segments: zooms
+                .iter()
+                .map(|&(start, end)| ZoomSegment {
+                    start,
+                    end,
+                    amount: 2.0,
+                    mode: ZoomMode::Auto,
+                    transition_duration_in: None,
+                    transition_duration_out: None,
+                    keyframes: vec![],
+                })
+                .collect(),
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }
+    }
+
+    fn segments(timeline: &TimelineConfiguration) -> Vec<(f64, f64)> {
+        timeline.segments.iter().map(|s| (s.start, s.end)).collect()
+    }
+
+    fn zooms(timeline: &TimelineConfiguration) -> Vec<(f64, f64)> {
+        timeline
+            .zoom_segments
+            .iter()
+            .map(|s| (s.start, s.end))
+            .collect()
+    }
+
+    #[test]
+    fn splits_inside_segments_only() {
+        let mut t = timeline(&[(0.0, 4.0), (10.0, 14.0)], &[]);
+
+        assert!(t.split_at(6.0));
+        assert!(!t.split_at(4.0));
+        assert!(!t.split_at(20.0));
+        assert_eq!(segments(&t), vec![(0.0, 4.0), (10.0, 12.0), (12.0, 14.0)]);
+    }
+
+    #[test]
+    fn split_follows_timescale() {
+        let mut t = timeline(&[(0.0, 8.0)], &[]);
+        t.segments[0].timescale = 2.0;
+
+        assert!(t.split_at(1.0));
+        assert_eq!(segments(&t), vec![(0.0, 2.0), (2.0, 8.0)]);
+    }
+
+    #[test]
+    fn delete_range_ripples() {
+        let mut t = timeline(
+            &[(0.0, 4.0), (10.0, 14.0)],
+            &[(1.0, 2.0), (2.5, 3.5), (3.0, 7.0), (7.0, 8.0)],
+        );
+
+        t.delete_range(2.0, 6.0);
+
+        assert_eq!(segments(&t), vec![(0.0, 2.0), (12.0, 14.0)]);
+        assert_eq!(zooms(&t), vec![(1.0, 2.0), (2.0, 3.0), (3.0, 4.0)]);
+        assert_eq!(t.duration(), 4.0);
+    }
+
+    #[test]
+    fn delete_range_moves_keyframes() {
+        let mut t = timeline(&[(0.0, 10.0)], &[(1.0, 9.0)]);
+        t.zoom_segments[0].keyframes = [2.0, 4.0, 6.0]
+            .into_iter()
+            .map(|time| ZoomKeyframe {
+                time,
+                amount: 1.5,
+                position: None,
+                easing: Default::default(),
+            })
+            .collect();
+
+        t.delete_range(4.0, 6.0);
+
+        assert_eq!(zooms(&t), vec![(1.0, 7.0)]);
+        let times = t.zoom_segments[0]
+            .keyframes
+            .iter()
+            .map(|k| k.time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![2.0, 4.0]);
+    }
+
+    #[test]
+    fn delete_segment_closes_gap() {
+        let mut t = timeline(&[(0.0, 2.0), (5.0, 8.0), (9.0, 10.0)], &[(5.5, 6.0)]);
+
+        t.delete_segment(1);
+
+        assert_eq!(segments(&t), vec![(0.0, 2.0), (9.0, 10.0)]);
+        assert_eq!(zooms(&t), vec![(2.5, 3.0)]);
+    }
+
+    #[test]
+    fn trim_moves_later_zooms() {
+        let mut t = timeline(&[(0.0, 4.0), (10.0, 14.0)], &[(1.0, 2.0), (5.0, 6.0)]);
+
+        assert!(t.trim_segment(0, 1.0, 3.0));
+        assert_eq!(segments(&t), vec![(1.0, 3.0), (10.0, 14.0)]);
+        assert_eq!(zooms(&t), vec![(0.0, 1.0), (3.0, 4.0)]);
+
+        assert!(t.trim_segment(1, 9.0, 15.0));
+        assert_eq!(segments(&t), vec![(1.0, 3.0), (9.0, 15.0)]);
+        assert_eq!(zooms(&t), vec![(0.0, 1.0), (4.0, 5.0)]);
+
+        assert!(!t.trim_segment(0, 3.0, 3.0));
+        assert!(!t.trim_segment(2, 0.0, 1.0));
+    }
+
+    #[test]
+    fn inserts_segments() {
+        let mut t = timeline(&[(0.0, 4.0)], &[(1.0, 2.0), (3.0, 4.0)]);
+        let insert = TimelineSegment {
+            recording_segment: 1,
+            ..t.segments[0].clone()
+        };
+
+        assert!(t.insert_segment(
+            2.0,
+            TimelineSegment {
+                end: 3.0,
+                ..insert.clone()
+            }
+        ));
+        assert_eq!(segments(&t), vec![(0.0, 2.0), (0.0, 3.0), (2.0, 4.0)]);
+        assert_eq!(t.segments[1].recording_segment, 1);
+        assert_eq!(zooms(&t), vec![(1.0, 2.0), (6.0, 7.0)]);
+
+        assert!(t.insert_segment(t.duration(), insert.clone()));
+        assert_eq!(t.duration(), 13.0);
+        assert!(!t.insert_segment(20.0, insert));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{CaptionSegment, TimelineConfiguration};
+
+// Cues are broken before they'd go past any of these
+const MAX_LINE_CHARS: usize = 42;
+const MAX_CUE_LINES: usize = 2;
+const MAX_CUE_SECS: f64 = 6.0;
+// pauses longer than this start a new cue
+const MAX_GAP_SECS: f64 = 0.8;
+
+// A word heard in a recording segment's audio, in seconds into the segment
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranscriptWord {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+// Captions for the words still on the timeline, like idle periods. `words` is indexed by
+// recording segment. Words go into cues of up to two lines, broken at pauses and the ends
+// of sentences.
+pub fn captions_from_transcript(
+    timeline: &TimelineConfiguration,
+    words: &[Vec<TranscriptWord>],
+) -> Vec<CaptionSegment> {
+    let mut placed = vec![];
+    let mut offset = 0.0;
+
+    for segment in &timeline.segments {
+        let segment_words = words
+            .get(segment.recording_segment as usize)
+            .into_iter()
+            .flatten();
+
+        for word in segment_words {
+            // a word cut in two goes with the part holding its middle
+            let middle = (word.start + word.end) / 2.0;
+            if middle < segment.start || middle >= segment.end {
+                continue;
+            }
+
+            let start = word.start.max(segment.start);
+            let end = word.end.min(segment.end);
+            placed.push(TranscriptWord {
+                start: offset + (start - segment.start) / segment.timescale,
+                end: offset + (end - segment.start) / segment.timescale,
+                text: word.text.trim().to_string(),
+            });
+        }
+
+        offset += segment.duration();
+    }
+
+    let mut cues = vec![];
+    let mut cue: Vec<TranscriptWord> = vec![];
+
+    for word in placed.into_iter().filter(|w| !w.text.is_empty()) {
+        // punctuation whisper splits off belongs to the word before it
+        if is_punctuation(&word.text) {
+            if let Some(last) = cue.last_mut() {
+                last.text.push_str(&word.text);
+                last.end = last.end.max(word.end);
+                continue;
+            }
+        }
+
+        if let (Some(first), Some(last)) = (cue.first(), cue.last()) {
+            let lines = wrap(cue.iter().chain([&word]).map(|w| w.text.as_str())).len();
+
+            if word.start - last.end > MAX_GAP_SECS
+                || word.end - first.start > MAX_CUE_SECS
+                || lines > MAX_CUE_LINES
+                || ends_sentence(&last.text)
+            {
+                cues.push(to_cue(&cue));
+                cue.clear();
+            }
+        }
+
+        cue.push(word);
+    }
+
+    if !cue.is_empty() {
+        cues.push(to_cue(&cue));
+    }
+
+    cues
+}
+
+fn is_punctuation(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_punctuation())
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.ends_with(['.', '?', '!'])
+}
+
+// The words in lines of at most MAX_LINE_CHARS, unless a word is longer
+fn wrap<'a>(words: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= MAX_LINE_CHARS => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    lines
+}
+
+fn to_cue(words: &[TranscriptWord]) -> CaptionSegment {
+    CaptionSegment {
+        start: words[0].start,
+        end: words[words.len() - 1].end,
+        text: wrap(words.iter().map(|w| w.text.as_str())).join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::TimelineSegment;
+
+    use super::*;
+
+    fn word(start: f64, end: f64, text: &str) -> TranscriptWord {
+        TranscriptWord {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    fn texts(cues: &[CaptionSegment]) -> Vec<&str> {
+        cues.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn breaks_at_sentences_and_pauses() {
+        let timeline = TimelineConfiguration::from_segment_durations(&[20.0]);
+        let words = vec![vec![
+            word(0.0, 0.3, " Hello"),
+            word(0.3, 0.4, "."),
+            word(0.5, 0.8, " This"),
+            word(0.8, 1.0, " is"),
+            word(1.0, 1.4, " Cap"),
+            word(5.0, 5.4, " Later"),
+        ]];
+
+        let cues = captions_from_transcript(&timeline, &words);
+
+        assert_eq!(texts(&cues), vec!["Hello.", "This is Cap", "Later"]);
+        assert_eq!((cues[0].start, cues[0].end), (0.0, 0.4));
+        assert_eq!((cues[1].start, cues[1].end), (0.5, 1.4));
+    }
+
+    #[test]
+    fn wraps_long_cues() {
+        let timeline = TimelineConfiguration::from_segment_durations(&[20.0]);
+        let words = vec![(0..20)
+            .map(|i| word(i as f64 * 0.2, i as f64 * 0.2 + 0.2, "word"))
+            .collect()];
+
+        let cues = captions_from_transcript(&timeline, &words);
+
+        assert_eq!(
+            texts(&cues),
+            vec![
+                "word word word word word word word word\nword word word word word word word word",
+                "word word word word",
+            ]
+        );
+    }
+
+    #[test]
+    fn follows_the_timeline() {
+        let mut timeline = TimelineConfiguration::from_segment_durations(&[10.0]);
+        timeline.segments = vec![TimelineSegment {
+            recording_segment: 0,
+            timescale: 2.0,
+            start: 4.0,
+            end: 10.0,
+            display: 0,
+            camera_position: None,
+            transition: None,
+        }];
+        let words = vec![vec![word(1.0, 2.0, "cut"), word(6.0, 7.0, "kept")]];
+
+        let cues = captions_from_transcript(&timeline, &words);
+
+        assert_eq!(texts(&cues), vec!["kept"]);
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 1.5));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{TimelineConfiguration, TimelineSegment, Transition};
+
+// A frame of one of the segments either side of a transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionSide {
+    pub recording_segment: u32,
+    pub display: u32,
+    // in the recording segment
+    pub time: f64,
+}
+
+// A transition under way at some time on the timeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActiveTransition<'a> {
+    pub transition: &'a Transition,
+    // 0 at the transition's start, 1 at its end
+    pub progress: f64,
+    // The segments either side of the cut. Each only has frames on its own side, so the
+    // outgoing one holds its last frame after the cut and the incoming one its first
+    // before it.
+    pub from: TransitionSide,
+    pub to: TransitionSide,
+}
+
+impl TimelineSegment {
+    // What this segment shows `offset` seconds of timeline from its start, kept within it
+    fn side_at(&self, offset: f64) -> TransitionSide {
+        TransitionSide {
+            recording_segment: self.recording_segment,
+            display: self.display,
+            time: (self.start + offset * self.timescale).clamp(self.start, self.end),
+        }
+    }
+}
+
+impl TimelineConfiguration {
+    // The transition under way at `time`, if any. Transitions are centered on their cut,
+    // and shortened to fit in the segments either side of it.
+    pub fn transition_at(&self, time: f64) -> Option<ActiveTransition<'_>> {
+        let mut cut = 0.0;
+
+        for pair in self.segments.windows(2) {
+            let [from, to] = pair else {
+                continue;
+            };
+            cut += from.duration();
+
+            let Some(transition) = &to.transition else {
+                continue;
+            };
+            let duration = transition.duration.min(from.duration()).min(to.duration());
+            if duration <= 0.0 {
+                continue;
+            }
+
+            let start = cut - duration / 2.0;
+            // the cuts only come later from here
+            if time < start {
+                return None;
+            }
+            if time >= start + duration {
+                continue;
+            }
+
+            return Some(ActiveTransition {
+                transition,
+                progress: (time - start) / duration,
+                from: from.side_at(from.duration() - (cut - time)),
+                to: to.side_at(time - cut),
+            });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::TransitionKind;
+
+    use super::*;
+
+    fn timeline(segments: &[(f64, f64)], transition: Option<f64>) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments: segments
+                .iter()
+                .enumerate()
+                .map(|(i, &(start, end))| TimelineSegment {
+                    recording_segment: i as u32,
+                    timescale: 1.0,
+                    start,
+                    end,
+                    display: 0,
+                    camera_position: None,
+                    transition: transition.filter(|_| i > 0).map(|duration| Transition {
+                        kind: TransitionKind::CrossFade,
+                        duration,
+                    }),
+                })
+                .collect(),
+            zoom_segments: vec![],
+            speed_segments: vec![],
+            markers: vec![],
+            text_segments: vec![],
+            annotation_segments: vec![],
+            caption_segments: vec![],
+        }
+    }
+
+    #[test]
+    fn centers_on_the_cut() {
+        let t = timeline(&[(0.0, 4.0), (10.0, 14.0)], Some(1.0));
+
+        assert_eq!(t.transition_at(3.4), None);
+        assert_eq!(t.transition_at(4.5), None);
+
+        let before = t.transition_at(3.75).unwrap();
+        assert_eq!(before.progress, 0.25);
+        assert_eq!(before.from.time, 3.75);
+        assert_eq!(before.from.recording_segment, 0);
+        assert_eq!(before.to.time, 10.0);
+
+        let after = t.transition_at(4.25).unwrap();
+        assert_eq!(after.progress, 0.75);
+        assert_eq!(after.from.time, 4.0);
+        assert_eq!(after.to.time, 10.25);
+        assert_eq!(after.to.recording_segment, 1);
+    }
+
+    #[test]
+    fn fits_in_short_segments() {
+        let t = timeline(&[(0.0, 4.0), (0.0, 0.5)], Some(2.0));
+
+        assert_eq!(t.transition_at(3.7), None);
+        assert_eq!(t.transition_at(4.0).unwrap().progress, 0.5);
+    }
+
+    #[test]
+    fn plain_cuts_have_no_transition() {
+        let t = timeline(&[(0.0, 4.0), (10.0, 14.0)], None);
+
+        assert_eq!(t.transition_at(4.0), None);
+    }
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+use specta::Type;
+
+use crate::ZoomSegment;
+
+// Something `ZoomSegments::normalize` had to repair. Indices refer to the segments as they
+// were before normalizing.
+#[derive(Type, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ZoomSegmentWarning {
+    // end isn't after start, or either isn't a number
+    Invalid { index: usize },
+    // lay entirely outside the recording
+    OutOfBounds { index: usize },
+    // partially outside the recording and was trimmed to fit
+    Clamped { index: usize },
+    // segments weren't in chronological order
+    Unsorted,
+    // `second` started before `first` ended and was merged into it
+    Overlapping { first: usize, second: usize },
+    // keyframes weren't in chronological order
+    UnsortedKeyframes { index: usize },
+}
+
+pub trait ZoomSegments {
+    // Whether the segments are valid, in order, non-overlapping and within `duration`
+    fn is_normalized(&self, duration: f64) -> bool;
+
+    // Sorts the segments, merges overlapping ones and clamps them to `duration`,
+    // dropping any that end up empty. Merged segments keep the earlier segment's zoom.
+    fn normalize(&mut self, duration: f64) -> Vec<ZoomSegmentWarning>;
+}
+
+impl ZoomSegments for Vec<ZoomSegment> {
+    fn is_normalized(&self, duration: f64) -> bool {
+        let mut prev_end = 0.0;
+
+        self.iter().all(|s| {
+            let valid = s.start.is_finite()
+                && s.end.is_finite()
+                && s.start >= prev_end
+                && s.start < s.end
+                && s.end <= duration
+                && s.keyframes.windows(2).all(|k| k[0].time <= k[1].time);
+            prev_end = s.end;
+            valid
+        })
+    }
+
+    fn normalize(&mut self, duration: f64) -> Vec<ZoomSegmentWarning> {
+        let mut warnings = vec![];
+        let duration = duration.max(0.0);
+
+        let mut segments = std::mem::take(self)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, mut segment)| {
+                if !segment.start.is_finite()
+                    || !segment.end.is_finite()
+                    || segment.end <= segment.start
+                {
+                    warnings.push(ZoomSegmentWarning::Invalid { index });
+                    return None;
+                }
+
+                if segment.end <= 0.0 || segment.start >= duration {
+                    warnings.push(ZoomSegmentWarning::OutOfBounds { index });
+                    return None;
+                }
+
+                if segment.start < 0.0 || segment.end > duration {
+                    segment.start = segment.start.max(0.0);
+                    segment.end = segment.end.min(duration);
+                    warnings.push(ZoomSegmentWarning::Clamped { index });
+                }
+
+                if segment.keyframes.windows(2).any(|k| k[1].time < k[0].time) {
+                    segment.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+                    warnings.push(ZoomSegmentWarning::UnsortedKeyframes { index });
+                }
+
+                Some((index, segment))
+            })
+            .collect::<Vec<_>>();
+
+        if segments.windows(2).any(|w| w[1].1.start < w[0].1.start) {
+            segments.sort_by(|a, b| a.1.start.total_cmp(&b.1.start));
+            warnings.push(ZoomSegmentWarning::Unsorted);
+        }
+
+        let mut merged: Vec<(usize, ZoomSegment)> = vec![];
+
+        for (index, segment) in segments {
+            match merged.last_mut() {
+                Some((first, prev)) if segment.start < prev.end => {
+                    prev.end = prev.end.max(segment.end);
+                    warnings.push(ZoomSegmentWarning::Overlapping {
+                        first: *first,
+                        second: index,
+                    });
+                }
+                _ => merged.push((index, segment)),
+            }
+        }
+
+        *self = merged.into_iter().map(|(_, s)| s).collect();
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ZoomMode;
+
+    use super::*;
+
+    fn segment(start: f64, end: f64) -> ZoomSegment {
+        ZoomSegment {
+            start,
+            end,
+            amount: 2.0,
+            mode: ZoomMode::Auto,
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![],
+        }
+    }
+
+    fn bounds(segments: &[ZoomSegment]) -> Vec<(f64, f64)> {
+        segments.iter().map(|s| (s.start, s.end)).collect()
+    }
+
+    #[test]
+    fn already_normalized() {
+        let mut segments = vec![segment(1.0, 2.0), segment(2.0, 4.0)];
+
+        assert!(segments.is_normalized(10.0));
+        assert_eq!(segments.normalize(10.0), vec![]);
+        assert_eq!(bounds(&segments), vec![(1.0, 2.0), (2.0, 4.0)]);
+    }
+
+    #[test]
+    fn sorts_and_merges() {
+        let mut segments = vec![segment(5.0, 7.0), segment(1.0, 3.0), segment(2.0, 4.0)];
+
+        assert!(!segments.is_normalized(10.0));
+        assert_eq!(
+            segments.normalize(10.0),
+            vec![
+                ZoomSegmentWarning::Unsorted,
+                ZoomSegmentWarning::Overlapping {
+                    first: 1,
+                    second: 2
+                }
+            ]
+        );
+        assert_eq!(bounds(&segments), vec![(1.0, 4.0), (5.0, 7.0)]);
+    }
+
+    #[test]
+    fn clamps_and_drops() {
+        let mut segments = vec![
+            segment(-1.0, 1.0),
+            segment(3.0, 3.0),
+            segment(8.0, 12.0),
+            segment(11.0, 12.0),
+        ];
+
+        assert_eq!(
+            segments.normalize(10.0),
+            vec![
+                ZoomSegmentWarning::Clamped { index: 0 },
+                ZoomSegmentWarning::Invalid { index: 1 },
+                ZoomSegmentWarning::Clamped { index: 2 },
+                ZoomSegmentWarning::OutOfBounds { index: 3 },
+            ]
+        );
+        assert_eq!(bounds(&segments), vec![(0.0, 1.0), (8.0, 10.0)]);
+    }
+}
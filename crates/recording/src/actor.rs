@@ -2,7 +2,7 @@ use std::{
     fs::File,
     path::PathBuf,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use cap_flags::FLAGS;
@@ -11,28 +11,36 @@ use cap_media::{
     encoders::{H264Encoder, MP4File, OggFile, OpusEncoder},
     feeds::{AudioInputFeed, CameraFeed},
     pipeline::{builder::PipelineBuilder, Pipeline, RealTimeClock},
-    sources::{AudioInputSource, CameraSource, ScreenCaptureSource, ScreenCaptureTarget},
+    platform::Bounds,
+    sources::{
+        AudioInputSource, CameraSource, ScreenCaptureSource, ScreenCaptureTarget, SystemAudioSource,
+    },
     MediaError,
 };
-use cap_project::{CursorEvents, RecordingMeta};
+use cap_project::{CursorEvents, KeyboardEvents, RecordingMeta};
 use cap_utils::spawn_actor;
 use either::Either;
 use relative_path::{RelativePath, RelativePathBuf};
 use thiserror::Error;
 use tokio::sync::{oneshot, Mutex};
 use tracing::{
-    debug, info,
+    debug, error, info,
     instrument::{self, WithSubscriber},
-    trace, Instrument,
+    trace, warn, Dispatch, Instrument,
 };
 use tracing_subscriber::{fmt::FormatFields, layer::SubscriberExt, Layer};
 
 use crate::{
     cursor::{spawn_cursor_recorder, CursorActor, Cursors},
+    recovery::InProgress,
     RecordingOptions,
 };
 
 enum ActorState {
+    Scheduled {
+        segment: PreparedSegment,
+        start_at: f64,
+    },
     Recording {
         pipeline: RecordingPipeline,
         pipeline_done_rx: oneshot::Receiver<()>,
@@ -51,6 +59,34 @@ pub enum ActorControlMessage {
     Pause(oneshot::Sender<Result<(), RecordingError>>),
     Resume(oneshot::Sender<Result<(), RecordingError>>),
     Stop(oneshot::Sender<Result<CompletedRecording, RecordingError>>),
+    AddMarker(oneshot::Sender<Result<(), RecordingError>>),
+}
+
+// Progress of scheduled and time limited recordings, for showing countdowns
+#[derive(Debug, Clone)]
+pub enum ActorEvent {
+    // seconds until a scheduled recording starts
+    StartsIn(u32),
+    Started,
+    // seconds until a recording with a max duration stops itself
+    StopsIn(u32),
+    // the max duration was reached, `ActorHandle::stop` returns the finished recording
+    AutoStopped,
+    // free space on the recording's disk fell past a warning threshold, in bytes left
+    LowDiskSpace(u64),
+    // the disk is about to fill up, so the recording is being stopped. `AutoStopped` follows.
+    DiskFull,
+}
+
+// What wakes up a recording segment, besides its pipeline ending by itself
+enum SegmentWake {
+    Control(ActorControlMessage),
+    // a replay buffer's segment reached the chunk length
+    ChunkFull,
+    // the recording reached its max duration
+    TimeLimit,
+    // the recording's disk is nearly full
+    DiskFull,
 }
 
 pub struct Actor {
@@ -58,6 +94,55 @@ pub struct Actor {
     recording_dir: PathBuf,
     options: RecordingOptions,
     segments: Vec<RecordingSegment>,
+    events_tx: flume::Sender<ActorEvent>,
+    // the lowest free space threshold that's been warned about
+    disk_space_warning: Option<u64>,
+    // when markers were added, in the same clock as the segments
+    markers: Vec<f64>,
+}
+
+impl Actor {
+    // how long the finished segments ran for
+    fn recorded_secs(&self) -> f64 {
+        self.segments.iter().map(|s| s.end - s.start).sum()
+    }
+
+    // events are only status updates, so they're dropped when nobody keeps up with them
+    fn send_event(&self, event: ActorEvent) {
+        self.events_tx.try_send(event).ok();
+    }
+
+    // Warns as free space falls past each threshold, and returns true once the recording
+    // has to stop while there's still room to finish writing its files
+    fn check_disk_space(&mut self) -> bool {
+        let available = match cap_utils::available_disk_space(&self.recording_dir) {
+            Ok(available) => available,
+            Err(e) => {
+                warn!("failed to check disk space: {e}");
+                return false;
+            }
+        };
+
+        if available < MIN_DISK_SPACE {
+            warn!("{available} bytes of disk space left, stopping recording");
+            self.send_event(ActorEvent::DiskFull);
+            return true;
+        }
+
+        let threshold = LOW_DISK_SPACE_WARNINGS
+            .into_iter()
+            .filter(|&threshold| available < threshold)
+            .min();
+        if let Some(threshold) = threshold {
+            if !matches!(self.disk_space_warning, Some(warned) if warned <= threshold) {
+                warn!("{available} bytes of disk space left");
+                self.disk_space_warning = Some(threshold);
+                self.send_event(ActorEvent::LowDiskSpace(available));
+            }
+        }
+
+        false
+    }
 }
 
 pub struct RecordingSegment {
@@ -69,7 +154,9 @@ pub struct RecordingSegment {
 struct RecordingPipeline {
     pub inner: Pipeline<RealTimeClock<()>>,
     pub display_output_path: PathBuf,
+    pub additional_display_output_paths: Vec<PathBuf>,
     pub audio_output_path: Option<PathBuf>,
+    pub system_audio_output_path: Option<PathBuf>,
     pub camera: Option<CameraPipelineInfo>,
     pub cursor: Option<CursorPipeline>,
 }
@@ -83,6 +170,7 @@ struct CursorPipeline {
 pub struct ActorHandle {
     ctrl_tx: flume::Sender<ActorControlMessage>,
     pub options: RecordingOptions,
+    pub events: flume::Receiver<ActorEvent>,
 }
 
 #[derive(Error, Debug)]
@@ -107,6 +195,12 @@ pub enum RecordingError {
 
     #[error("IO/{0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Recording was stopped before it started")]
+    NotStarted,
+
+    #[error("There isn't enough disk space left to record")]
+    DiskFull,
 }
 
 macro_rules! send_message {
@@ -129,16 +223,39 @@ impl ActorHandle {
     pub async fn resume(&self) -> Result<(), RecordingError> {
         send_message!(self.ctrl_tx, ActorControlMessage::Resume)
     }
+
+    // Marks the current moment, which shows up as a marker on the project's timeline
+    pub async fn add_marker(&self) -> Result<(), RecordingError> {
+        send_message!(self.ctrl_tx, ActorControlMessage::AddMarker)
+    }
 }
 
-pub async fn spawn_recording_actor(
+// A recording whose first segment has its capture and encoders set up but not running, so
+// starting it only has to press play. It holds open output files and capture threads,
+// so it must be either started or discarded.
+pub struct PreparedRecording {
     id: String,
     recording_dir: PathBuf,
     options: RecordingOptions,
     camera_feed: Option<Arc<Mutex<CameraFeed>>>,
     audio_input_feed: Option<AudioInputFeed>,
-) -> Result<(ActorHandle, tokio::sync::oneshot::Receiver<()>), RecordingError> {
+    dispatch: Dispatch,
+    segment: PreparedSegment,
+}
+
+pub async fn prepare_recording(
+    id: String,
+    recording_dir: PathBuf,
+    options: RecordingOptions,
+    camera_feed: Option<Arc<Mutex<CameraFeed>>>,
+    audio_input_feed: Option<AudioInputFeed>,
+) -> Result<PreparedRecording, RecordingError> {
     ensure_dir(&recording_dir)?;
+    if cap_utils::available_disk_space(&recording_dir).is_ok_and(|a| a < MIN_DISK_SPACE) {
+        std::fs::remove_dir(&recording_dir).ok();
+        return Err(RecordingError::DiskFull);
+    }
+
     let logfile = File::create(recording_dir.join("recording-logs.log"))?;
 
     let collector = tracing_subscriber::registry()
@@ -163,48 +280,167 @@ pub async fn spawn_recording_actor(
                         .from_env_lossy(),
                 ),
         );
-
-    let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+    let dispatch = Dispatch::new(collector);
 
     async {
-        async {
-            trace!("creating recording actor");
+        trace!("preparing recording");
 
-            let content_dir = ensure_dir(&recording_dir.join("content"))?;
+        let content_dir = ensure_dir(&recording_dir.join("content"))?;
 
-            let segments_dir = ensure_dir(&content_dir.join("segments"))?;
-            let cursors_dir = ensure_dir(&content_dir.join("cursors"))?;
+        let segments_dir = ensure_dir(&content_dir.join("segments"))?;
+        let cursors_dir = ensure_dir(&content_dir.join("cursors"))?;
 
-            let screen_source = create_screen_capture(&options);
+        let screen_sources = create_screen_captures(&options);
 
-            debug!("screen capture: {screen_source:#?}");
+        debug!("screen capture: {screen_sources:#?}");
 
-            if let Some(camera_feed) = &camera_feed {
-                let camera_feed = camera_feed.lock().await;
-                debug!("camera device info: {:#?}", camera_feed.camera_info());
-                debug!("camera video info: {:#?}", camera_feed.video_info());
-            }
+        let mut camera_fps = None;
+        if let Some(camera_feed) = &camera_feed {
+            let camera_feed = camera_feed.lock().await;
+            debug!("camera device info: {:#?}", camera_feed.camera_info());
+            debug!("camera video info: {:#?}", camera_feed.video_info());
 
-            if let Some(audio_feed) = &audio_input_feed {
-                debug!("mic audio info: {:#?}", audio_feed.audio_info())
-            }
+            let frame_rate = camera_feed.video_info().frame_rate;
+            camera_fps = Some((frame_rate.0 / frame_rate.1) as u32);
+        }
 
-            let index = 0;
-            let (pipeline, pipeline_done_rx) = create_segment_pipeline(
-                &segments_dir,
-                &cursors_dir,
-                index,
-                screen_source.clone(),
-                camera_feed.as_deref(),
-                audio_input_feed.as_ref(),
-                Default::default(),
-                index,
-            )
-            .await?;
+        if let Some(audio_feed) = &audio_input_feed {
+            debug!("mic audio info: {:#?}", audio_feed.audio_info())
+        }
+
+        // lets the recording be recovered if the app stops before it does
+        InProgress {
+            pretty_name: format!(
+                "Cap {}",
+                chrono::Local::now().format("%Y-%m-%d at %H.%M.%S")
+            ),
+            options: options.clone(),
+            camera_fps,
+        }
+        .write(&recording_dir)?;
+
+        let segment = prepare_segment_pipeline(
+            &segments_dir,
+            &cursors_dir,
+            0,
+            screen_sources,
+            camera_feed.as_deref(),
+            audio_input_feed.as_ref(),
+            options.capture_system_audio,
+            Default::default(),
+            0,
+            options.sample_ui_elements,
+            options.record_keyboard,
+        )
+        .await?;
+
+        Ok(PreparedRecording {
+            id,
+            recording_dir,
+            options,
+            camera_feed,
+            audio_input_feed,
+            dispatch: dispatch.clone(),
+            segment,
+        })
+    }
+    .instrument(tracing::info_span!("recording"))
+    .with_subscriber(dispatch.clone())
+    .await
+}
+
+pub async fn spawn_recording_actor(
+    id: String,
+    recording_dir: PathBuf,
+    options: RecordingOptions,
+    camera_feed: Option<Arc<Mutex<CameraFeed>>>,
+    audio_input_feed: Option<AudioInputFeed>,
+) -> Result<(ActorHandle, tokio::sync::oneshot::Receiver<()>), RecordingError> {
+    prepare_recording(id, recording_dir, options, camera_feed, audio_input_feed)
+        .await?
+        .start()
+        .await
+}
+
+impl PreparedRecording {
+    pub fn options(&self) -> &RecordingOptions {
+        &self.options
+    }
+
+    // Shuts down the standby pipeline and deletes everything it wrote
+    pub async fn discard(self) {
+        let Self {
+            recording_dir,
+            dispatch,
+            segment,
+            ..
+        } = self;
+
+        segment.discard().with_subscriber(dispatch.clone()).await;
+        // the log file has to be closed before its directory can be removed on Windows
+        drop(dispatch);
+
+        std::fs::remove_dir_all(&recording_dir).ok();
+    }
+
+    pub async fn start(
+        self,
+    ) -> Result<(ActorHandle, tokio::sync::oneshot::Receiver<()>), RecordingError> {
+        spawn_prepared_actor(self, None).await
+    }
+
+    // Keeps the pipeline on standby until `start_at`, in seconds since the unix epoch
+    pub async fn schedule(
+        self,
+        start_at: f64,
+    ) -> Result<(ActorHandle, tokio::sync::oneshot::Receiver<()>), RecordingError> {
+        spawn_prepared_actor(self, Some(start_at)).await
+    }
+}
+
+async fn spawn_prepared_actor(
+    prepared: PreparedRecording,
+    start_at: Option<f64>,
+) -> Result<(ActorHandle, tokio::sync::oneshot::Receiver<()>), RecordingError> {
+    let PreparedRecording {
+        id,
+        recording_dir,
+        options,
+        camera_feed,
+        audio_input_feed,
+        dispatch,
+        segment,
+    } = prepared;
+
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel::<()>();
+
+    async {
+        async {
+            let content_dir = recording_dir.join("content");
+            let segments_dir = content_dir.join("segments");
+            let cursors_dir = content_dir.join("cursors");
+
+            // later segments are captured from the same sources
+            let screen_sources = create_screen_captures(&options);
 
-            let segment_start_time = current_time_f64();
+            let mut state = match start_at {
+                Some(start_at) if start_at > current_time_f64() => {
+                    ActorState::Scheduled { segment, start_at }
+                }
+                _ => {
+                    let (pipeline, pipeline_done_rx) = segment.start().await?;
+
+                    ActorState::Recording {
+                        pipeline,
+                        pipeline_done_rx,
+                        index: 0,
+                        segment_start_time: current_time_f64(),
+                    }
+                }
+            };
 
             let (ctrl_tx, ctrl_rx) = flume::bounded(1);
+            let (events_tx, events_rx) = flume::bounded(16);
 
             trace!("spawning recording actor");
 
@@ -216,17 +452,64 @@ pub async fn spawn_recording_actor(
                         recording_dir,
                         options,
                         segments: Vec::new(),
-                    };
-
-                    let mut state = ActorState::Recording {
-                        pipeline,
-                        pipeline_done_rx,
-                        index,
-                        segment_start_time,
+                        events_tx,
+                        disk_space_warning: None,
+                        markers: Vec::new(),
                     };
 
                     'outer: loop {
                         state = match state {
+                            ActorState::Scheduled { segment, start_at } => {
+                                info!("recording actor scheduled");
+
+                                let mut ticker = tokio::time::interval(Duration::from_secs(1));
+                                let start = tokio::time::sleep(Duration::from_secs_f64(
+                                    (start_at - current_time_f64()).max(0.0),
+                                ));
+                                tokio::pin!(start);
+
+                                loop {
+                                    tokio::select! {
+                                        _ = &mut start => break,
+                                        _ = ticker.tick() => {
+                                            let secs_left = start_at - current_time_f64();
+                                            actor.send_event(ActorEvent::StartsIn(
+                                                secs_left.max(0.0).ceil() as u32,
+                                            ));
+                                        }
+                                        msg = ctrl_rx.recv_async() => match msg {
+                                            Ok(ActorControlMessage::Stop(tx)) => {
+                                                segment.discard().await;
+                                                std::fs::remove_dir_all(&actor.recording_dir).ok();
+                                                tx.send(Err(RecordingError::NotStarted)).ok();
+                                                break 'outer;
+                                            }
+                                            Ok(_) => {}
+                                            Err(_) => {
+                                                segment.discard().await;
+                                                break 'outer;
+                                            }
+                                        },
+                                    }
+                                }
+
+                                match segment.start().await {
+                                    Ok((pipeline, pipeline_done_rx)) => {
+                                        actor.send_event(ActorEvent::Started);
+
+                                        ActorState::Recording {
+                                            pipeline,
+                                            pipeline_done_rx,
+                                            index: 0,
+                                            segment_start_time: current_time_f64(),
+                                        }
+                                    }
+                                    Err(e) => {
+                                        error!("failed to start scheduled recording: {e}");
+                                        ActorState::Stopped
+                                    }
+                                }
+                            }
                             ActorState::Recording {
                                 mut pipeline,
                                 mut pipeline_done_rx,
@@ -234,8 +517,66 @@ pub async fn spawn_recording_actor(
                                 segment_start_time,
                             } => {
                                 info!("recording actor recording");
+
+                                // replay buffers move on to a new segment every chunk, so the
+                                // ones that fall out of the buffer can be dropped whole
+                                let replay_buffer = actor.options.replay_buffer_secs.is_some();
+                                let chunk_full = async move {
+                                    if !replay_buffer {
+                                        return std::future::pending().await;
+                                    }
+
+                                    let remaining =
+                                        segment_start_time + REPLAY_CHUNK_SECS - current_time_f64();
+                                    tokio::time::sleep(Duration::from_secs_f64(remaining.max(0.0)))
+                                        .await
+                                };
+                                tokio::pin!(chunk_full);
+
+                                let max_duration = actor.options.max_duration_secs;
+                                let recorded_secs = actor.recorded_secs();
+                                let secs_left = move || {
+                                    max_duration.map(|max| {
+                                        max as f64
+                                            - recorded_secs
+                                            - (current_time_f64() - segment_start_time)
+                                    })
+                                };
+                                let time_limit = async move {
+                                    match secs_left() {
+                                        Some(secs) => {
+                                            tokio::time::sleep(Duration::from_secs_f64(
+                                                secs.max(0.0),
+                                            ))
+                                            .await
+                                        }
+                                        None => std::future::pending().await,
+                                    }
+                                };
+                                tokio::pin!(time_limit);
+                                let mut ticker = tokio::time::interval(Duration::from_secs(1));
+                                let mut disk_check = tokio::time::interval(DISK_CHECK_INTERVAL);
+
                                 loop {
                                     let msg = tokio::select! {
+                                        _ = &mut chunk_full => SegmentWake::ChunkFull,
+                                        _ = &mut time_limit => SegmentWake::TimeLimit,
+                                        _ = disk_check.tick() => {
+                                            if !actor.check_disk_space() {
+                                                continue;
+                                            }
+
+                                            SegmentWake::DiskFull
+                                        }
+                                        _ = ticker.tick(), if max_duration.is_some() => {
+                                            if let Some(secs) = secs_left() {
+                                                actor.send_event(ActorEvent::StopsIn(
+                                                    secs.max(0.0).ceil() as u32,
+                                                ));
+                                            }
+
+                                            continue;
+                                        }
                                         _ = &mut pipeline_done_rx => {
                                             if let Some(cursor) = &mut pipeline.cursor {
                                                 if let Some(actor) = cursor.actor.take() {
@@ -250,7 +591,7 @@ pub async fn spawn_recording_actor(
                                                 break 'outer;
                                             };
 
-                                            msg
+                                            SegmentWake::Control(msg)
                                         }
                                     };
 
@@ -273,6 +614,12 @@ pub async fn spawn_recording_actor(
                                                     serde_json::to_string_pretty(&CursorEvents {
                                                         clicks: res.clicks,
                                                         moves: res.moves,
+                                                        elements: res.elements,
+                                                        windows: res.windows,
+                                                        scrolls: res.scrolls,
+                                                        keyboard: KeyboardEvents {
+                                                            presses: res.key_presses,
+                                                        },
                                                     })?,
                                                 )?;
 
@@ -294,7 +641,77 @@ pub async fn spawn_recording_actor(
                                     }
 
                                     break match msg {
-                                        ActorControlMessage::Pause(tx) => {
+                                        SegmentWake::TimeLimit | SegmentWake::DiskFull => {
+                                            let res =
+                                                shutdown(pipeline, &mut actor, segment_start_time)
+                                                    .await;
+                                            let events_tx = actor.events_tx.clone();
+                                            let res = match res {
+                                                Ok((cursors, _)) => {
+                                                    stop_recording(actor, cursors).await
+                                                }
+                                                Err(e) => Err(e),
+                                            };
+
+                                            events_tx.try_send(ActorEvent::AutoStopped).ok();
+
+                                            // hold on to the recording until it's asked for
+                                            while let Ok(msg) = ctrl_rx.recv_async().await {
+                                                if let ActorControlMessage::Stop(tx) = msg {
+                                                    tx.send(res).ok();
+                                                    break;
+                                                }
+                                            }
+
+                                            break 'outer;
+                                        }
+                                        SegmentWake::ChunkFull => {
+                                            let (cursors, next_cursor_id) = match shutdown(
+                                                pipeline,
+                                                &mut actor,
+                                                segment_start_time,
+                                            )
+                                            .await
+                                            {
+                                                Ok(res) => res,
+                                                Err(e) => {
+                                                    error!("failed to end replay segment: {e}");
+                                                    break 'outer;
+                                                }
+                                            };
+
+                                            prune_replay_segments(&mut actor);
+
+                                            match create_segment_pipeline(
+                                                &segments_dir,
+                                                &cursors_dir,
+                                                index + 1,
+                                                screen_sources.clone(),
+                                                camera_feed.as_deref(),
+                                                audio_input_feed.as_ref(),
+                                                actor.options.capture_system_audio,
+                                                cursors,
+                                                next_cursor_id,
+                                                actor.options.sample_ui_elements,
+                                                actor.options.record_keyboard,
+                                            )
+                                            .await
+                                            {
+                                                Ok((pipeline, pipeline_done_rx)) => {
+                                                    ActorState::Recording {
+                                                        pipeline,
+                                                        pipeline_done_rx,
+                                                        index: index + 1,
+                                                        segment_start_time: current_time_f64(),
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    error!("failed to start replay segment: {e}");
+                                                    ActorState::Stopped
+                                                }
+                                            }
+                                        }
+                                        SegmentWake::Control(ActorControlMessage::Pause(tx)) => {
                                             let (res, cursors, next_cursor_id) = match shutdown(
                                                 pipeline,
                                                 &mut actor,
@@ -315,7 +732,7 @@ pub async fn spawn_recording_actor(
                                                 next_cursor_id,
                                             }
                                         }
-                                        ActorControlMessage::Stop(tx) => {
+                                        SegmentWake::Control(ActorControlMessage::Stop(tx)) => {
                                             let res =
                                                 shutdown(pipeline, &mut actor, segment_start_time)
                                                     .await;
@@ -330,6 +747,13 @@ pub async fn spawn_recording_actor(
 
                                             break 'outer;
                                         }
+                                        SegmentWake::Control(ActorControlMessage::AddMarker(
+                                            tx,
+                                        )) => {
+                                            actor.markers.push(current_time_f64());
+                                            tx.send(Ok(())).ok();
+                                            continue;
+                                        }
                                         _ => continue,
                                     };
                                 }
@@ -355,11 +779,14 @@ pub async fn spawn_recording_actor(
                                                 &segments_dir,
                                                 &cursors_dir,
                                                 next_index,
-                                                screen_source.clone(),
+                                                screen_sources.clone(),
                                                 camera_feed.as_deref(),
                                                 audio_input_feed.as_ref(),
+                                                actor.options.capture_system_audio,
                                                 cursors,
                                                 next_cursor_id,
+                                                actor.options.sample_ui_elements,
+                                                actor.options.record_keyboard,
                                             )
                                             .await
                                             {
@@ -379,6 +806,11 @@ pub async fn spawn_recording_actor(
 
                                             state
                                         }
+                                        ActorControlMessage::AddMarker(tx) => {
+                                            actor.markers.push(current_time_f64());
+                                            tx.send(Ok(())).ok();
+                                            continue;
+                                        }
                                         _ => continue,
                                     };
                                 }
@@ -397,12 +829,16 @@ pub async fn spawn_recording_actor(
                 .in_current_span()
             });
 
-            Ok(ActorHandle { ctrl_tx, options })
+            Ok(ActorHandle {
+                ctrl_tx,
+                options,
+                events: events_rx,
+            })
         }
         .instrument(tracing::info_span!("recording"))
         .await
     }
-    .with_subscriber(collector)
+    .with_subscriber(dispatch)
     .await
     .map(|a| (a, done_rx))
 }
@@ -414,14 +850,99 @@ pub struct CompletedRecording {
     pub meta: RecordingMeta,
     pub cursor_data: cap_project::CursorImages,
     pub segments: Vec<RecordingSegment>,
+    // where playback of the first segment starts, which skips what a replay buffer's oldest
+    // segment holds beyond the buffer's length
+    pub first_segment_start: f64,
+    // when markers were added, in the segments' clock
+    pub markers: Vec<f64>,
+}
+
+impl CompletedRecording {
+    pub fn timeline(&self, segment_durations: &[f64]) -> cap_project::TimelineConfiguration {
+        let mut timeline =
+            cap_project::TimelineConfiguration::from_segment_durations(segment_durations);
+
+        if let Some(first) = timeline.segments.first_mut() {
+            first.start = self.first_segment_start.min(first.end);
+        }
+
+        let times = self
+            .markers
+            .iter()
+            .filter_map(|&time| self.marker_time(time, segment_durations))
+            .collect::<Vec<_>>();
+        for (i, time) in times.into_iter().enumerate() {
+            timeline.add_marker(time, format!("Marker {}", i + 1));
+        }
+
+        timeline
+    }
+
+    // Where a marker added at `time` lands on the timeline. Ones added while paused land
+    // where recording picked back up, and ones from before what was kept are dropped.
+    fn marker_time(&self, time: f64, segment_durations: &[f64]) -> Option<f64> {
+        let mut offset = 0.0;
+
+        for (segment, duration) in self.segments.iter().zip(segment_durations) {
+            if time < segment.end {
+                let time = offset + (time - segment.start).clamp(0.0, *duration);
+                return Some(time - self.first_segment_start).filter(|t| *t >= 0.0);
+            }
+
+            offset += duration;
+        }
+
+        // added after the last segment ended, like while the recording was stopping
+        Some(offset - self.first_segment_start)
+    }
+}
+
+const REPLAY_CHUNK_SECS: f64 = 10.0;
+
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// free space, in bytes, past which the recording warns about it
+const LOW_DISK_SPACE_WARNINGS: [u64; 2] = [5_000_000_000, 2_000_000_000];
+// enough for the encoders to flush and the files to be finalized
+const MIN_DISK_SPACE: u64 = 500_000_000;
+
+// Drops the oldest segments that a replay buffer doesn't need to cover its length anymore,
+// along with their files
+fn prune_replay_segments(actor: &mut Actor) {
+    let Some(buffer_secs) = actor.options.replay_buffer_secs else {
+        return;
+    };
+
+    loop {
+        let total = actor.recorded_secs();
+
+        match actor.segments.first() {
+            Some(oldest) if total - (oldest.end - oldest.start) >= buffer_secs as f64 => {
+                let oldest = actor.segments.remove(0);
+                if let Some(dir) = oldest.pipeline.display_output_path.parent() {
+                    std::fs::remove_dir_all(dir).ok();
+                }
+            }
+            _ => break,
+        }
+    }
 }
 
 async fn stop_recording(
-    actor: Actor,
+    mut actor: Actor,
     cursors: Cursors,
 ) -> Result<CompletedRecording, RecordingError> {
     use cap_project::*;
 
+    prune_replay_segments(&mut actor);
+
+    let first_segment_start = match actor.options.replay_buffer_secs {
+        Some(buffer_secs) => (actor.recorded_secs() - buffer_secs as f64).max(0.0),
+        None => 0.0,
+    };
+
+    let source = segment_source(&actor.options);
+    let region = capture_region(&actor.options);
+
     let meta = RecordingMeta {
         project_path: actor.recording_dir.clone(),
         sharing: None,
@@ -446,6 +967,22 @@ async fn stop_recording(
                                 .unwrap(),
                                 fps: actor.options.capture_target.recording_fps(),
                             },
+                            additional_displays: s
+                                .pipeline
+                                .additional_display_output_paths
+                                .iter()
+                                .zip(&actor.options.additional_displays)
+                                .map(|(path, screen)| Display {
+                                    path: RelativePathBuf::from_path(
+                                        path.strip_prefix(&actor.recording_dir).unwrap(),
+                                    )
+                                    .unwrap(),
+                                    fps: ScreenCaptureTarget::Screen(screen.clone())
+                                        .recording_fps(),
+                                })
+                                .collect(),
+                            source: source.clone(),
+                            region,
                             camera: s.pipeline.camera.as_ref().map(|camera| CameraMeta {
                                 path: RelativePathBuf::from_path(
                                     camera
@@ -463,6 +1000,14 @@ async fn stop_recording(
                                 )
                                 .unwrap(),
                             }),
+                            system_audio: s.pipeline.system_audio_output_path.as_ref().map(
+                                |path| AudioMeta {
+                                    path: RelativePathBuf::from_path(
+                                        path.strip_prefix(&actor.recording_dir).unwrap().to_owned(),
+                                    )
+                                    .unwrap(),
+                                },
+                            ),
                             cursor: s.pipeline.cursor.as_ref().map(|cursor| {
                                 RelativePathBuf::from_path(
                                     cursor
@@ -473,6 +1018,7 @@ async fn stop_recording(
                                 )
                                 .unwrap()
                             }),
+                            cursor_id_prefix: String::new(),
                         })
                         .collect()
                 },
@@ -486,6 +1032,7 @@ async fn stop_recording(
                                     image_path: RelativePathBuf::from("content/cursors")
                                         .join(&cursor.file_name),
                                     hotspot: cursor.hotspot,
+                                    shape: cursor.shape,
                                 },
                             )
                         })
@@ -497,52 +1044,200 @@ async fn stop_recording(
 
     meta.save_for_project()
         .map_err(Either::either_into::<RecordingError>)?;
+    InProgress::remove(&actor.recording_dir);
 
-    let project_config = cap_project::ProjectConfiguration::default();
-    project_config
-        .write(&actor.recording_dir)
-        .map_err(RecordingError::from)?;
-
-    Ok(CompletedRecording {
+    let completed = CompletedRecording {
         id: actor.id,
         meta,
         cursor_data: Default::default(),
         recording_dir: actor.recording_dir,
         display_source: actor.options.capture_target,
         segments: actor.segments,
-    })
+        first_segment_start,
+        markers: actor.markers,
+    };
+
+    // the recorder can't read video durations, so this goes by how long each segment ran
+    let project_config = cap_project::ProjectConfiguration {
+        timeline: (first_segment_start > 0.0 || !completed.markers.is_empty()).then(|| {
+            completed.timeline(
+                &completed
+                    .segments
+                    .iter()
+                    .map(|s| s.end - s.start)
+                    .collect::<Vec<_>>(),
+            )
+        }),
+        ..Default::default()
+    };
+    project_config
+        .write(&completed.recording_dir)
+        .map_err(RecordingError::from)?;
+
+    Ok(completed)
 }
 
-fn create_screen_capture(
+// Isolated window captures are marked so the editor can frame them like a window
+pub(crate) fn segment_source(options: &RecordingOptions) -> cap_project::SegmentSource {
+    match &options.capture_target {
+        ScreenCaptureTarget::Window(window) if window.isolated => {
+            cap_project::SegmentSource::Window {
+                owner_name: window.owner_name.clone(),
+                name: window.name.clone(),
+            }
+        }
+        _ => cap_project::SegmentSource::Display,
+    }
+}
+
+// Where in the screen an area capture was taken from, relative to its size
+pub(crate) fn capture_region(options: &RecordingOptions) -> Option<cap_project::CaptureRegion> {
+    match &options.capture_target {
+        ScreenCaptureTarget::Area(area) => {
+            let screen = cap_media::platform::monitor_bounds(area.screen.id);
+
+            Some(cap_project::CaptureRegion {
+                position: cap_project::XY::new(
+                    area.bounds.x / screen.width,
+                    area.bounds.y / screen.height,
+                ),
+                size: cap_project::XY::new(
+                    area.bounds.width / screen.width,
+                    area.bounds.height / screen.height,
+                ),
+            })
+        }
+        _ => None,
+    }
+}
+
+// The capture target's source followed by one for each additional display, in track order
+fn create_screen_captures(
     recording_options: &RecordingOptions,
-) -> ScreenCaptureSource<impl MakeCapturePipeline> {
+) -> Vec<ScreenCaptureSource<impl MakeCapturePipeline>> {
+    let targets = std::iter::once(recording_options.capture_target.clone()).chain(
+        recording_options
+            .additional_displays
+            .iter()
+            .cloned()
+            .map(ScreenCaptureTarget::Screen),
+    );
+
     #[cfg(target_os = "macos")]
     {
-        ScreenCaptureSource::<cap_media::sources::CMSampleBufferCapture>::init(
-            &recording_options.capture_target,
-            None,
-        )
+        targets
+            .map(|target| {
+                ScreenCaptureSource::<cap_media::sources::CMSampleBufferCapture>::init(
+                    &target, None,
+                )
+            })
+            .collect()
     }
     #[cfg(not(target_os = "macos"))]
     {
-        ScreenCaptureSource::<cap_media::sources::AVFrameCapture>::init(
-            &recording_options.capture_target,
-            None,
-        )
+        targets
+            .map(|target| {
+                ScreenCaptureSource::<cap_media::sources::AVFrameCapture>::init(&target, None)
+            })
+            .collect()
     }
 }
 
-#[tracing::instrument(skip_all, name = "segment", fields(index = index))]
 async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
     segments_dir: &PathBuf,
     cursors_dir: &PathBuf,
     index: u32,
-    screen_source: ScreenCaptureSource<TCaptureFormat>,
+    screen_sources: Vec<ScreenCaptureSource<TCaptureFormat>>,
     camera_feed: Option<&Mutex<CameraFeed>>,
     audio_input_feed: Option<&AudioInputFeed>,
+    capture_system_audio: bool,
     prev_cursors: Cursors,
     next_cursors_id: u32,
+    sample_ui_elements: bool,
+    record_keyboard: bool,
 ) -> Result<(RecordingPipeline, oneshot::Receiver<()>), MediaError> {
+    prepare_segment_pipeline(
+        segments_dir,
+        cursors_dir,
+        index,
+        screen_sources,
+        camera_feed,
+        audio_input_feed,
+        capture_system_audio,
+        prev_cursors,
+        next_cursors_id,
+        sample_ui_elements,
+        record_keyboard,
+    )
+    .await?
+    .start()
+    .await
+}
+
+// A segment's pipeline, built but not yet playing
+struct PreparedSegment {
+    pipeline: RecordingPipeline,
+    pipeline_done_rx: oneshot::Receiver<()>,
+    cursor: Option<PendingCursor>,
+}
+
+// The cursor recorder polls from the moment it's spawned, so it waits for the segment to start
+struct PendingCursor {
+    output_path: PathBuf,
+    screen_bounds: Bounds,
+    additional_display_bounds: Vec<Bounds>,
+    cursors_dir: PathBuf,
+    prev_cursors: Cursors,
+    next_cursors_id: u32,
+    sample_ui_elements: bool,
+    record_keyboard: bool,
+}
+
+impl PreparedSegment {
+    async fn start(mut self) -> Result<(RecordingPipeline, oneshot::Receiver<()>), MediaError> {
+        self.pipeline.cursor = self.cursor.map(|cursor| CursorPipeline {
+            output_path: cursor.output_path,
+            actor: Some(spawn_cursor_recorder(
+                cursor.screen_bounds,
+                cursor.additional_display_bounds,
+                cursor.cursors_dir,
+                cursor.prev_cursors,
+                cursor.next_cursors_id,
+                cursor.sample_ui_elements,
+                cursor.record_keyboard,
+            )),
+        });
+
+        self.pipeline.inner.play().await?;
+
+        info!("pipeline playing");
+
+        Ok((self.pipeline, self.pipeline_done_rx))
+    }
+
+    async fn discard(mut self) {
+        if let Err(e) = self.pipeline.inner.shutdown().await {
+            debug!("failed to shut down standby pipeline: {e}");
+        }
+
+        info!("standby pipeline discarded");
+    }
+}
+
+#[tracing::instrument(skip_all, name = "segment", fields(index = index))]
+async fn prepare_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
+    segments_dir: &PathBuf,
+    cursors_dir: &PathBuf,
+    index: u32,
+    screen_sources: Vec<ScreenCaptureSource<TCaptureFormat>>,
+    camera_feed: Option<&Mutex<CameraFeed>>,
+    audio_input_feed: Option<&AudioInputFeed>,
+    capture_system_audio: bool,
+    prev_cursors: Cursors,
+    next_cursors_id: u32,
+    sample_ui_elements: bool,
+    record_keyboard: bool,
+) -> Result<PreparedSegment, MediaError> {
     let camera_feed = match camera_feed.as_ref() {
         Some(camera_feed) => Some(camera_feed.lock().await),
         None => None,
@@ -555,23 +1250,34 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
     let mut pipeline_builder = Pipeline::builder(clock);
 
     let display_output_path = dir.join("display.mp4");
+    let mut additional_display_output_paths = vec![];
+    let mut display_bounds = vec![];
 
     trace!("preparing segment pipeline {index}");
 
-    let screen_bounds = screen_source.get_bounds();
-    pipeline_builder = TCaptureFormat::make_capture_pipeline(
-        pipeline_builder,
-        screen_source,
-        &display_output_path,
-    )?;
-
-    info!(
-        r#"screen pipeline prepared, will output to "{}""#,
-        display_output_path
-            .strip_prefix(&segments_dir)
-            .unwrap()
-            .display()
-    );
+    for (i, screen_source) in screen_sources.into_iter().enumerate() {
+        let (name, output_path) = if i == 0 {
+            ("screen".to_string(), display_output_path.clone())
+        } else {
+            let output_path = dir.join(format!("display-{i}.mp4"));
+            additional_display_output_paths.push(output_path.clone());
+            (format!("screen_{i}"), output_path)
+        };
+
+        display_bounds.push(screen_source.get_bounds());
+        pipeline_builder = TCaptureFormat::make_capture_pipeline(
+            pipeline_builder,
+            screen_source,
+            &name,
+            &output_path,
+        )?;
+
+        info!(
+            r#"{name} pipeline prepared, will output to "{}""#,
+            output_path.strip_prefix(&segments_dir).unwrap().display()
+        );
+    }
+    let screen_bounds = display_bounds.remove(0);
 
     let audio_output_path = if let Some(mic_source) = audio_input_feed.map(AudioInputSource::init) {
         let mic_config = mic_source.info();
@@ -580,7 +1286,8 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
         let mic_encoder = OggFile::init(
             output_path.clone(),
             OpusEncoder::factory("microphone", mic_config),
-        )?;
+        )?
+        .with_drift_correction();
 
         pipeline_builder = pipeline_builder
             .source("microphone_capture", mic_source)
@@ -596,11 +1303,36 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
         None
     };
 
+    let system_audio_output_path = if capture_system_audio {
+        let system_audio_source = SystemAudioSource::init()?;
+        let system_audio_config = system_audio_source.info();
+        let output_path = dir.join("system-audio.ogg");
+
+        let system_audio_encoder = OggFile::init(
+            output_path.clone(),
+            OpusEncoder::factory("system_audio", system_audio_config),
+        )?
+        .with_drift_correction();
+
+        pipeline_builder = pipeline_builder
+            .source("system_audio_capture", system_audio_source)
+            .sink("system_audio_encoder", system_audio_encoder);
+
+        info!(
+            "system audio pipeline prepared, will output to {}",
+            output_path.strip_prefix(&segments_dir).unwrap().display()
+        );
+
+        Some(output_path)
+    } else {
+        None
+    };
+
     let camera = if let Some(camera_source) = camera_feed.map(CameraSource::init) {
         let camera_config = camera_source.info();
         let output_path = dir.join("camera.mp4");
 
-        let camera_encoder = MP4File::init(
+        let camera_encoder = MP4File::init_fragmented(
             "camera",
             output_path.clone(),
             H264Encoder::factory("camera", camera_config),
@@ -624,36 +1356,34 @@ async fn create_segment_pipeline<TCaptureFormat: MakeCapturePipeline>(
         None
     };
 
-    let (mut pipeline, pipeline_done_rx) = pipeline_builder.build().await?;
-
-    let cursor = FLAGS.record_mouse_state.then(|| {
-        let cursor = spawn_cursor_recorder(
-            screen_bounds,
-            cursors_dir.clone(),
-            prev_cursors,
-            next_cursors_id,
-        );
-
-        CursorPipeline {
-            output_path: dir.join("cursor.json"),
-            actor: Some(cursor),
-        }
+    let (pipeline, pipeline_done_rx) = pipeline_builder.build().await?;
+
+    let cursor = FLAGS.record_mouse_state.then(|| PendingCursor {
+        output_path: dir.join("cursor.json"),
+        screen_bounds,
+        additional_display_bounds: display_bounds,
+        cursors_dir: cursors_dir.clone(),
+        prev_cursors,
+        next_cursors_id,
+        sample_ui_elements,
+        record_keyboard,
     });
 
-    pipeline.play().await?;
+    info!("pipeline ready");
 
-    info!("pipeline playing");
-
-    Ok((
-        RecordingPipeline {
+    Ok(PreparedSegment {
+        pipeline: RecordingPipeline {
             inner: pipeline,
             display_output_path,
+            additional_display_output_paths,
             audio_output_path,
+            system_audio_output_path,
             camera,
-            cursor,
+            cursor: None,
         },
         pipeline_done_rx,
-    ))
+        cursor,
+    })
 }
 
 struct CameraPipelineInfo {
@@ -672,6 +1402,7 @@ trait MakeCapturePipeline: std::fmt::Debug + 'static {
     fn make_capture_pipeline(
         builder: CapturePipelineBuilder,
         source: ScreenCaptureSource<Self>,
+        name: &str,
         output_path: impl Into<PathBuf>,
     ) -> Result<CapturePipelineBuilder, MediaError>
     where
@@ -683,6 +1414,7 @@ impl MakeCapturePipeline for cap_media::sources::CMSampleBufferCapture {
     fn make_capture_pipeline(
         builder: CapturePipelineBuilder,
         source: ScreenCaptureSource<Self>,
+        name: &str,
         output_path: impl Into<PathBuf>,
     ) -> Result<CapturePipelineBuilder, MediaError> {
         let screen_config = source.info();
@@ -693,8 +1425,8 @@ impl MakeCapturePipeline for cap_media::sources::CMSampleBufferCapture {
         )?;
 
         Ok(builder
-            .source("screen_capture", source)
-            .sink("screen_capture_encoder", screen_encoder))
+            .source(format!("{name}_capture"), source)
+            .sink(format!("{name}_capture_encoder"), screen_encoder))
     }
 }
 
@@ -702,21 +1434,22 @@ impl MakeCapturePipeline for cap_media::sources::AVFrameCapture {
     fn make_capture_pipeline(
         builder: CapturePipelineBuilder,
         source: ScreenCaptureSource<Self>,
+        name: &str,
         output_path: impl Into<PathBuf>,
     ) -> Result<CapturePipelineBuilder, MediaError>
     where
         Self: Sized,
     {
         let screen_config = source.info();
-        let screen_encoder = MP4File::init(
+        let screen_encoder = MP4File::init_fragmented(
             "screen",
             output_path.into(),
             H264Encoder::factory("screen", screen_config),
             |_| None,
         )?;
         Ok(builder
-            .source("screen_capture", source)
-            .sink("screen_capture_encoder", screen_encoder))
+            .source(format!("{name}_capture"), source)
+            .sink(format!("{name}_capture_encoder"), screen_encoder))
     }
 }
 
@@ -1,13 +1,17 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::{DefaultHasher, Hash, Hasher},
     path::PathBuf,
     sync::{atomic::AtomicBool, Arc},
     time::{Duration, Instant},
 };
 
-use cap_media::platform::Bounds;
-use cap_project::{CursorClickEvent, CursorMoveEvent, XY};
+use cap_media::platform::{Bounds, InputMethodListener, KeyText, KeyTranslator};
+use cap_project::{
+    compose_dead_key, CursorClickEvent, CursorElementEvent, CursorMoveEvent, CursorScrollEvent,
+    CursorShape, CursorWindowEvent, KeyPressEvent, KEY_INPUT_METHOD, MODIFIER_ALT,
+    MODIFIER_CONTROL, MODIFIER_META, MODIFIER_SHIFT, XY,
+};
 use cap_utils::spawn_actor;
 use device_query::{DeviceQuery, DeviceState};
 use image::GenericImageView;
@@ -18,6 +22,7 @@ pub struct Cursor {
     pub file_name: String,
     pub id: u32,
     pub hotspot: XY<f64>,
+    pub shape: Option<CursorShape>,
 }
 
 pub type Cursors = HashMap<u64, Cursor>;
@@ -28,8 +33,17 @@ pub struct CursorActorResponse {
     pub next_cursor_id: u32,
     pub moves: Vec<CursorMoveEvent>,
     pub clicks: Vec<CursorClickEvent>,
+    pub elements: Vec<CursorElementEvent>,
+    pub windows: Vec<CursorWindowEvent>,
+    pub scrolls: Vec<CursorScrollEvent>,
+    pub key_presses: Vec<KeyPressEvent>,
 }
 
+// accessibility queries are much slower than polling the mouse
+const ELEMENT_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+// enumerating windows is slower still, and windows rarely move mid-recording
+const WINDOW_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct CursorActor {
     stop_signal: Arc<AtomicBool>,
     rx: oneshot::Receiver<CursorActorResponse>,
@@ -46,9 +60,12 @@ impl CursorActor {
 #[tracing::instrument(name = "cursor", skip_all)]
 pub fn spawn_cursor_recorder(
     screen_bounds: Bounds,
+    additional_display_bounds: Vec<Bounds>,
     cursors_dir: PathBuf,
     prev_cursors: Cursors,
     next_cursor_id: u32,
+    sample_ui_elements: bool,
+    record_keyboard: bool,
 ) -> CursorActor {
     let stop_signal = Arc::new(AtomicBool::new(false));
     let (tx, rx) = oneshot::channel();
@@ -58,6 +75,13 @@ pub fn spawn_cursor_recorder(
         async move {
             let device_state = DeviceState::new();
             let mut last_mouse_state = device_state.get_mouse();
+            let mut last_keys = vec![];
+            let mut key_translator = record_keyboard.then(KeyTranslator::start);
+            let input_method_listener = record_keyboard.then(InputMethodListener::start);
+            let mut pending_dead_key = None;
+            let scroll_listener = cap_media::platform::ScrollListener::start();
+            let element_sampler =
+                sample_ui_elements.then(cap_media::platform::ElementSampler::start);
             let start_time = Instant::now();
 
             let mut response = CursorActorResponse {
@@ -65,7 +89,15 @@ pub fn spawn_cursor_recorder(
                 next_cursor_id,
                 moves: vec![],
                 clicks: vec![],
+                elements: vec![],
+                windows: vec![],
+                scrolls: vec![],
+                key_presses: vec![],
             };
+            let mut current_element: Option<CursorElementEvent> = None;
+            let mut last_element_sample: Option<Instant> = None;
+            let mut window_rects = HashMap::<u32, [f64; 4]>::new();
+            let mut last_window_sample: Option<Instant> = None;
 
             // Create cursors directory if it doesn't exist
             std::fs::create_dir_all(&cursors_dir).unwrap();
@@ -105,6 +137,7 @@ pub fn spawn_cursor_recorder(
                                         file_name,
                                         id: response.next_cursor_id,
                                         hotspot: data.hotspot,
+                                        shape: data.shape,
                                     },
                                 );
                                 response.next_cursor_id += 1;
@@ -215,6 +248,12 @@ pub fn spawn_cursor_recorder(
 
                     debug!("Normalized coords: ({}, {})", x, y);
 
+                    let (display, x, y) = locate_on_additional_display(
+                        &additional_display_bounds,
+                        mouse_state.coords,
+                    )
+                    .unwrap_or((0, x, y));
+
                     let mouse_event = CursorMoveEvent {
                         active_modifiers: vec![],
                         cursor_id: cursor_id.clone(),
@@ -222,6 +261,7 @@ pub fn spawn_cursor_recorder(
                         unix_time_ms: unix_time,
                         x,
                         y,
+                        display,
                     };
                     response.moves.push(mouse_event);
                 }
@@ -305,6 +345,12 @@ pub fn spawn_cursor_recorder(
                         y.max(0.0).min(1.0)
                     };
 
+                    let (display, x, y) = locate_on_additional_display(
+                        &additional_display_bounds,
+                        mouse_state.coords,
+                    )
+                    .unwrap_or((0, x, y));
+
                     let mouse_event = CursorClickEvent {
                         down: pressed,
                         active_modifiers: vec![],
@@ -314,14 +360,159 @@ pub fn spawn_cursor_recorder(
                         unix_time_ms: unix_time,
                         x,
                         y,
+                        display,
                     };
                     response.clicks.push(mouse_event);
                 }
 
+                response
+                    .scrolls
+                    .extend(scroll_listener.drain().map(|delta| CursorScrollEvent {
+                        process_time_ms: elapsed,
+                        unix_time_ms: unix_time,
+                        delta_x: delta.x,
+                        delta_y: delta.y,
+                    }));
+
+                if let Some(translator) = &mut key_translator {
+                    let keys = device_state
+                        .get_keys()
+                        .iter()
+                        .map(|key| format!("{key:?}"))
+                        .collect::<Vec<_>>();
+
+                    let active_modifiers = [
+                        MODIFIER_CONTROL,
+                        MODIFIER_ALT,
+                        MODIFIER_SHIFT,
+                        MODIFIER_META,
+                    ]
+                    .into_iter()
+                    .filter(|modifier| keys.iter().any(|k| modifier_name(k) == Some(*modifier)))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+
+                    for key in &keys {
+                        if last_keys.contains(key) || modifier_name(key).is_some() {
+                            continue;
+                        }
+
+                        let mut active_modifiers = active_modifiers.clone();
+                        let (text, composing) = typed_text(
+                            translator,
+                            &mut pending_dead_key,
+                            key,
+                            &mut active_modifiers,
+                        );
+
+                        response.key_presses.push(KeyPressEvent {
+                            key: key.clone(),
+                            active_modifiers,
+                            process_time_ms: elapsed,
+                            unix_time_ms: unix_time,
+                            text,
+                            composing,
+                        });
+                    }
+
+                    last_keys = keys;
+
+                    // the text the composing keys above turned into. Plain typing can come
+                    // through too, which the keys already cover.
+                    if let Some(listener) = &input_method_listener {
+                        let input_method = translator.input_method_active();
+                        response
+                            .key_presses
+                            .extend(listener.drain().filter(|_| input_method).map(|text| {
+                                KeyPressEvent {
+                                    key: KEY_INPUT_METHOD.to_string(),
+                                    active_modifiers: vec![],
+                                    process_time_ms: elapsed,
+                                    unix_time_ms: unix_time,
+                                    text: Some(text),
+                                    composing: false,
+                                }
+                            }));
+                    }
+                }
+
+                if sample_ui_elements
+                    && !matches!(last_element_sample, Some(t) if t.elapsed() < ELEMENT_SAMPLE_INTERVAL)
+                {
+                    last_element_sample = Some(Instant::now());
+
+                    let (mouse_x, mouse_y) = mouse_state.coords;
+                    let element = element_sampler
+                        .as_ref()
+                        .and_then(|sampler| sampler.bounds_at(mouse_x as f64, mouse_y as f64))
+                        .and_then(|bounds| normalize_element_bounds(bounds, &screen_bounds));
+
+                    let unchanged = match (&current_element, &element) {
+                        (Some(current), Some([x, y, width, height])) => {
+                            current.x == *x
+                                && current.y == *y
+                                && current.width == *width
+                                && current.height == *height
+                        }
+                        (None, None) => true,
+                        _ => false,
+                    };
+
+                    if !unchanged {
+                        if let Some(mut prev) = current_element.take() {
+                            prev.end_time_ms = elapsed;
+                            response.elements.push(prev);
+                        }
+
+                        current_element = element.map(|[x, y, width, height]| CursorElementEvent {
+                            start_time_ms: elapsed,
+                            end_time_ms: elapsed,
+                            x,
+                            y,
+                            width,
+                            height,
+                        });
+                    }
+                }
+
+                if !matches!(last_window_sample, Some(t) if t.elapsed() < WINDOW_SAMPLE_INTERVAL) {
+                    last_window_sample = Some(Instant::now());
+
+                    let windows = cap_media::platform::get_on_screen_windows();
+                    let on_screen = windows.iter().map(|w| w.window_id).collect::<HashSet<_>>();
+                    window_rects.retain(|id, _| on_screen.contains(id));
+
+                    for window in windows {
+                        let Some(rect) = normalize_bounds(window.bounds, &screen_bounds) else {
+                            continue;
+                        };
+
+                        if window_rects.get(&window.window_id) == Some(&rect) {
+                            continue;
+                        }
+                        window_rects.insert(window.window_id, rect);
+
+                        let [x, y, width, height] = rect;
+                        response.windows.push(CursorWindowEvent {
+                            window_id: window.window_id,
+                            process_time_ms: elapsed,
+                            x,
+                            y,
+                            width,
+                            height,
+                        });
+                    }
+                }
+
                 last_mouse_state = mouse_state;
                 tokio::time::sleep(Duration::from_millis(10)).await;
             }
 
+            if let Some(mut element) = current_element.take() {
+                element.end_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+                response.elements.push(element);
+            }
+
             tx.send(response).ok();
         }
     });
@@ -329,10 +520,136 @@ pub fn spawn_cursor_recorder(
     CursorActor { rx, stop_signal }
 }
 
+// Maps device_query's left/right variants onto the shared modifier names
+fn modifier_name(key: &str) -> Option<&'static str> {
+    match key {
+        "LControl" | "RControl" => Some(MODIFIER_CONTROL),
+        "LAlt" | "RAlt" | "LOption" | "ROption" => Some(MODIFIER_ALT),
+        "LShift" | "RShift" => Some(MODIFIER_SHIFT),
+        "Command" | "RCommand" | "LMeta" | "RMeta" => Some(MODIFIER_META),
+        _ => None,
+    }
+}
+
+// What pressing `key` typed on the user's keyboard layout, and whether it's still being
+// composed into text, carrying a dead key's accent over to the key after it. Shortcuts get
+// the key's own character. Modifiers that only picked the character are dropped.
+fn typed_text(
+    translator: &mut KeyTranslator,
+    pending_dead_key: &mut Option<char>,
+    key: &str,
+    modifiers: &mut Vec<String>,
+) -> (Option<String>, bool) {
+    let held = |modifier: &str| modifiers.iter().any(|m| m == modifier);
+
+    // Option on macOS and AltGr, seen as Ctrl + Alt, on Windows type other characters
+    let alt_gr =
+        held(MODIFIER_ALT) && !held(MODIFIER_META) && held(MODIFIER_CONTROL) == cfg!(windows);
+    let shortcut = !alt_gr && modifiers.iter().any(|m| m != MODIFIER_SHIFT);
+
+    let typed = if shortcut {
+        KeyText::None
+    } else {
+        translator.translate(key, held(MODIFIER_SHIFT), alt_gr)
+    };
+
+    match typed {
+        KeyText::Dead(accent) => {
+            *pending_dead_key = Some(accent);
+            modifiers.retain(|m| m == MODIFIER_SHIFT);
+            (None, true)
+        }
+        KeyText::Text(text) => {
+            modifiers.retain(|m| m == MODIFIER_SHIFT);
+            let text = match pending_dead_key.take() {
+                Some(accent) => compose_dead_key(accent, &text),
+                None => text,
+            };
+            (Some(text), translator.input_method_active())
+        }
+        KeyText::None => {
+            *pending_dead_key = None;
+            match translator.translate(key, false, false) {
+                KeyText::Text(text) => (Some(text), false),
+                _ => (None, false),
+            }
+        }
+    }
+}
+
+// Converts global screen bounds to [x, y, width, height] relative to the recorded display.
+fn normalize_bounds(bounds: Bounds, screen_bounds: &Bounds) -> Option<[f64; 4]> {
+    if screen_bounds.width <= 0.0 || screen_bounds.height <= 0.0 {
+        return None;
+    }
+
+    #[cfg(target_os = "macos")]
+    let (x, y) = {
+        let primary_bounds = cap_media::platform::primary_monitor_bounds();
+
+        (
+            bounds.x - screen_bounds.x,
+            bounds.y + (screen_bounds.y + screen_bounds.height - primary_bounds.height),
+        )
+    };
+
+    #[cfg(not(target_os = "macos"))]
+    let (x, y) = (bounds.x - screen_bounds.x, bounds.y - screen_bounds.y);
+
+    let normalized = [
+        x / screen_bounds.width,
+        y / screen_bounds.height,
+        bounds.width / screen_bounds.width,
+        bounds.height / screen_bounds.height,
+    ];
+
+    if normalized.iter().any(|v| !v.is_finite()) || normalized[2] <= 0.0 || normalized[3] <= 0.0 {
+        return None;
+    }
+
+    Some(normalized)
+}
+
+// The additional display the cursor is on, if any, as its track number (the recorded display
+// is track 0) and the cursor's position normalized to it.
+fn locate_on_additional_display(
+    display_bounds: &[Bounds],
+    (mouse_x, mouse_y): (i32, i32),
+) -> Option<(u32, f64, f64)> {
+    if display_bounds.is_empty() {
+        return None;
+    }
+
+    #[cfg(target_os = "macos")]
+    let primary_bounds = cap_media::platform::primary_monitor_bounds();
+
+    display_bounds.iter().enumerate().find_map(|(i, bounds)| {
+        #[cfg(target_os = "macos")]
+        let (x, y) = (
+            mouse_x as f64 - bounds.x,
+            mouse_y as f64 + (bounds.y + bounds.height - primary_bounds.height),
+        );
+
+        #[cfg(not(target_os = "macos"))]
+        let (x, y) = (mouse_x as f64 - bounds.x, mouse_y as f64 - bounds.y);
+
+        let (x, y) = (x / bounds.width, y / bounds.height);
+
+        ((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)).then_some((i as u32 + 1, x, y))
+    })
+}
+
+// Elements covering most of the display (windows, web views) are not useful to frame.
+fn normalize_element_bounds(bounds: Bounds, screen_bounds: &Bounds) -> Option<[f64; 4]> {
+    normalize_bounds(bounds, screen_bounds)
+        .filter(|[_, _, width, height]| *width <= 0.5 && *height <= 0.5)
+}
+
 #[derive(Debug)]
 struct CursorData {
     image: Vec<u8>,
     hotspot: XY<f64>,
+    shape: Option<CursorShape>,
 }
 
 #[cfg(target_os = "macos")]
@@ -381,12 +698,34 @@ fn get_cursor_image_data() -> Option<CursorData> {
             let slice = std::slice::from_raw_parts(bytes, length as usize);
             let data = slice.to_vec();
 
+            // currentSystemCursor hands back a new instance every time,
+            // so the standard cursors can only be recognised by their image
+            let shape = [
+                (sel!(arrowCursor), CursorShape::Arrow),
+                (sel!(IBeamCursor), CursorShape::IBeam),
+                (sel!(pointingHandCursor), CursorShape::PointingHand),
+                (sel!(crosshairCursor), CursorShape::Crosshair),
+            ]
+            .into_iter()
+            .find(|(selector, _)| {
+                let standard_cursor: id = msg_send![nscursor_class, performSelector: *selector];
+                if standard_cursor == nil {
+                    return false;
+                }
+                let standard_image: id = msg_send![standard_cursor, image];
+                let standard_data: id = msg_send![standard_image, TIFFRepresentation];
+                let equal: bool = msg_send![image_data, isEqualToData: standard_data];
+                equal
+            })
+            .map(|(_, shape)| shape);
+
             Some(CursorData {
                 image: data,
                 hotspot: XY::new(
                     cursor_hotspot.x / cursor_size.width,
                     cursor_hotspot.y / cursor_size.height,
                 ),
+                shape,
             })
         }
     })
@@ -690,6 +1029,27 @@ fn get_cursor_image_data() -> Option<CursorData> {
         Some(CursorData {
             image: png_data,
             hotspot: XY::new(hotspot_x, hotspot_y),
+            shape: windows_cursor_shape(cursor_info.hCursor),
         })
     }
 }
+
+// The standard system cursors are shared handles, so comparing against them is enough
+#[cfg(windows)]
+fn windows_cursor_shape(
+    cursor: windows::Win32::UI::WindowsAndMessaging::HCURSOR,
+) -> Option<CursorShape> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        LoadCursorW, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_IBEAM,
+    };
+
+    [
+        (IDC_ARROW, CursorShape::Arrow),
+        (IDC_IBEAM, CursorShape::IBeam),
+        (IDC_HAND, CursorShape::PointingHand),
+        (IDC_CROSS, CursorShape::Crosshair),
+    ]
+    .into_iter()
+    .find(|(id, _)| unsafe { LoadCursorW(None, *id) }.is_ok_and(|c| c == cursor))
+    .map(|(_, shape)| shape)
+}
@@ -1,7 +1,11 @@
 pub mod actor;
 pub mod cursor;
+pub mod recovery;
 
-pub use actor::{spawn_recording_actor, ActorHandle, CompletedRecording, RecordingError};
+pub use actor::{
+    prepare_recording, spawn_recording_actor, ActorEvent, ActorHandle, CompletedRecording,
+    PreparedRecording, RecordingError,
+};
 
 use cap_media::sources::*;
 use serde::{Deserialize, Serialize};
@@ -12,6 +16,24 @@ pub struct RecordingOptions {
     pub capture_target: ScreenCaptureTarget,
     pub camera_label: Option<String>,
     pub audio_input_name: Option<String>,
+    // record bounds of the UI element under the cursor for Auto zoom framing
+    #[serde(default)]
+    pub sample_ui_elements: bool,
+    // record key presses for the keystroke overlay
+    #[serde(default)]
+    pub record_keyboard: bool,
+    // other screens to capture alongside the target, each into its own track
+    #[serde(default)]
+    pub additional_displays: Vec<CaptureScreen>,
+    // keep only this many of the latest seconds, and save them when the recording stops
+    #[serde(default)]
+    pub replay_buffer_secs: Option<u32>,
+    // stop by itself once this many seconds have been recorded, not counting pauses
+    #[serde(default)]
+    pub max_duration_secs: Option<u32>,
+    // record what the system plays into its own track, next to the microphone's
+    #[serde(default)]
+    pub capture_system_audio: bool,
 }
 
 impl Default for RecordingOptions {
@@ -24,6 +46,12 @@ impl Default for RecordingOptions {
             }),
             camera_label: None,
             audio_input_name: None,
+            sample_ui_elements: false,
+            record_keyboard: false,
+            additional_displays: vec![],
+            replay_buffer_secs: None,
+            max_duration_secs: None,
+            capture_system_audio: false,
         }
     }
 }
@@ -0,0 +1,248 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cap_media::encoders::{mp4_state, repair_mp4, Mp4State};
+use cap_project::{
+    AudioMeta, CameraMeta, Content, CursorEvents, CursorMeta, Cursors, Display, MultipleSegment,
+    MultipleSegments, ProjectConfiguration, RecordingMeta, XY,
+};
+use either::Either;
+use relative_path::RelativePathBuf;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    actor::{capture_region, segment_source},
+    RecordingOptions,
+};
+
+// Sits in a project while it's being recorded, and is replaced by its recording-meta.json
+// once the recording stops
+pub const IN_PROGRESS_FILE: &str = "recording-in-progress.json";
+
+// What the recording's meta is made from, if it never gets to write it
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InProgress {
+    pub pretty_name: String,
+    pub options: RecordingOptions,
+    pub camera_fps: Option<u32>,
+}
+
+impl InProgress {
+    pub(crate) fn write(&self, project_path: &Path) -> std::io::Result<()> {
+        fs::write(
+            project_path.join(IN_PROGRESS_FILE),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    pub(crate) fn remove(project_path: &Path) {
+        fs::remove_file(project_path.join(IN_PROGRESS_FILE)).ok();
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RecoveryError {
+    #[error("None of the recording's segments could be recovered")]
+    NothingRecovered,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+pub struct RecoveredRecording {
+    pub meta: RecordingMeta,
+    // files that were cut off before anything in them could be found, and were left out
+    pub lost: Vec<PathBuf>,
+}
+
+// Projects in `recordings_dir` that were still recording when the app last stopped. Only
+// meaningful on startup, as anything being recorded right now looks the same.
+pub fn find_unfinished_recordings(recordings_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(recordings_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(IN_PROGRESS_FILE).exists())
+        .collect()
+}
+
+// Repairs the videos of a recording that never finished and writes the meta it never got
+// to. Segments without a usable screen recording are left out.
+pub fn recover_recording(project_path: &Path) -> Result<RecoveredRecording, RecoveryError> {
+    let in_progress: InProgress =
+        serde_json::from_str(&fs::read_to_string(project_path.join(IN_PROGRESS_FILE))?)?;
+    let options = &in_progress.options;
+
+    let content_dir = project_path.join("content");
+    let mut indices = fs::read_dir(content_dir.join("segments"))?
+        .flatten()
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("segment-")?
+                .parse::<u32>()
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    indices.sort();
+
+    let mut lost = vec![];
+    let mut segments = vec![];
+
+    for index in indices {
+        let relative = RelativePathBuf::from(format!("content/segments/segment-{index}"));
+        let path = |name: &str| relative.join(name).to_path(project_path);
+
+        if !recover_video(&path("display.mp4"), &mut lost) {
+            continue;
+        }
+
+        // a track missing from the middle would shift the ones after it onto the wrong display
+        let additional_displays = options
+            .additional_displays
+            .iter()
+            .enumerate()
+            .map(|(i, screen)| (format!("display-{}.mp4", i + 1), screen))
+            .take_while(|(name, _)| recover_video(&path(name), &mut lost))
+            .map(|(name, screen)| Display {
+                path: relative.join(name),
+                fps: cap_media::sources::ScreenCaptureTarget::Screen(screen.clone())
+                    .recording_fps(),
+            })
+            .collect();
+
+        let camera = in_progress
+            .camera_fps
+            .filter(|_| recover_video(&path("camera.mp4"), &mut lost))
+            .map(|fps| CameraMeta {
+                path: relative.join("camera.mp4"),
+                fps,
+            });
+
+        // Ogg pages stand on their own, so audio that was cut off plays up to where it stops
+        let audio = |name: &str| {
+            fs::metadata(path(name))
+                .is_ok_and(|m| m.len() > 0)
+                .then(|| AudioMeta {
+                    path: relative.join(name),
+                })
+        };
+
+        // cursor events are only written when a segment ends
+        let cursor = CursorEvents::load_from_file(&path("cursor.json"))
+            .is_ok()
+            .then(|| relative.join("cursor.json"));
+
+        segments.push(MultipleSegment {
+            display: Display {
+                path: relative.join("display.mp4"),
+                fps: options.capture_target.recording_fps(),
+            },
+            additional_displays,
+            source: segment_source(options),
+            region: capture_region(options),
+            camera,
+            audio: audio("audio-input.ogg"),
+            system_audio: audio("system-audio.ogg"),
+            cursor,
+            cursor_id_prefix: String::new(),
+        });
+    }
+
+    if segments.is_empty() {
+        return Err(RecoveryError::NothingRecovered);
+    }
+    let segment_count = segments.len();
+
+    let meta = RecordingMeta {
+        project_path: project_path.to_owned(),
+        pretty_name: in_progress.pretty_name,
+        sharing: None,
+        content: Content::MultipleSegments {
+            inner: MultipleSegments {
+                segments,
+                cursors: Cursors::Correct(recover_cursors(&content_dir.join("cursors"))),
+            },
+        },
+    };
+
+    meta.save_for_project()
+        .map_err(Either::either_into::<RecoveryError>)?;
+    if !project_path.join("project-config.json").exists() {
+        ProjectConfiguration::default().write(project_path)?;
+    }
+    InProgress::remove(project_path);
+
+    info!(
+        "recovered {} segments of {}, {} files lost",
+        segment_count,
+        project_path.display(),
+        lost.len()
+    );
+
+    Ok(RecoveredRecording { meta, lost })
+}
+
+// Whether the video at `path` can be used, repairing it if it was cut off. Files that
+// weren't recorded at all aren't counted as lost.
+fn recover_video(path: &Path, lost: &mut Vec<PathBuf>) -> bool {
+    let state = match mp4_state(path) {
+        Ok(state) => state,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return false,
+        Err(e) => {
+            warn!("failed to read {}: {e}", path.display());
+            Mp4State::MissingIndex
+        }
+    };
+
+    let recovered = match state {
+        Mp4State::Complete => true,
+        Mp4State::Interrupted => repair_mp4(path)
+            .map_err(|e| warn!("failed to repair {}: {e}", path.display()))
+            .is_ok(),
+        // AVAssetWriter only writes the index once it finishes
+        Mp4State::MissingIndex => false,
+    };
+
+    if !recovered {
+        lost.push(path.to_owned());
+    }
+
+    recovered
+}
+
+// The cursor images saved so far. Their hotspots were only kept in memory, so cursors are
+// drawn from their top left corner.
+fn recover_cursors(cursors_dir: &Path) -> std::collections::HashMap<String, CursorMeta> {
+    fs::read_dir(cursors_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_str()?.to_owned();
+            let id = file_name
+                .strip_prefix("cursor_")?
+                .strip_suffix(".png")?
+                .to_owned();
+
+            Some((
+                id,
+                CursorMeta {
+                    image_path: RelativePathBuf::from("content/cursors").join(file_name),
+                    hotspot: XY::new(0.0, 0.0),
+                    shape: None,
+                },
+            ))
+        })
+        .collect()
+}
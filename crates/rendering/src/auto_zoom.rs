@@ -0,0 +1,284 @@
+use cap_project::{CursorEvents, ProjectConfiguration, ZoomMode, ZoomSegment, XY};
+
+use crate::{zoom::ZOOM_DURATION, SegmentCursors};
+
+// clicks further apart than this in time or space start a new cluster
+const CLUSTER_GAP: f64 = 2.5;
+const CLUSTER_RADIUS: f64 = 0.2;
+// the cursor lingering near a cluster keeps it zoomed in for up to this long
+const MAX_HOLD: f64 = 4.0;
+const MIN_HOLD: f64 = 1.5;
+// a pause in movement longer than this counts as idle and zooms back out
+const IDLE_GAP: f64 = 1.0;
+const MIN_CLICKS: usize = 2;
+const AMOUNT: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    time: f64,
+    position: XY<f64>,
+}
+
+struct Cluster {
+    start: f64,
+    end: f64,
+    clicks: usize,
+    sum: XY<f64>,
+}
+
+impl Cluster {
+    fn new(click: Activity) -> Self {
+        Self {
+            start: click.time,
+            end: click.time,
+            clicks: 1,
+            sum: click.position,
+        }
+    }
+
+    fn center(&self) -> XY<f64> {
+        self.sum / self.clicks as f64
+    }
+
+    fn accepts(&self, click: Activity) -> bool {
+        click.time - self.end <= CLUSTER_GAP
+            && distance(self.center(), click.position) <= CLUSTER_RADIUS
+    }
+
+    fn push(&mut self, click: Activity) {
+        self.end = click.time;
+        self.clicks += 1;
+        self.sum = self.sum + click.position;
+    }
+}
+
+/// Proposes zoom segments from click and movement density, in timeline time.
+/// Bursts of clicks in one area of the screen get a segment centered on them, which stays
+/// zoomed in while the cursor keeps moving nearby and ends once it goes idle or leaves.
+/// `segment_cursors` holds the cursor events of each recording segment.
+pub fn generate_zoom_segments(
+    project: &ProjectConfiguration,
+    segment_cursors: &[&SegmentCursors],
+) -> Vec<ZoomSegment> {
+    let (clicks, moves, duration) = timeline_activity(project, segment_cursors);
+
+    let mut clusters: Vec<Cluster> = vec![];
+    for click in clicks {
+        match clusters.last_mut() {
+            Some(cluster) if cluster.accepts(click) => cluster.push(click),
+            _ => clusters.push(Cluster::new(click)),
+        }
+    }
+
+    let mut segments: Vec<ZoomSegment> = vec![];
+
+    for cluster in clusters.into_iter().filter(|c| c.clicks >= MIN_CLICKS) {
+        let center = cluster.center();
+
+        // start early enough that the zoom has finished transitioning by the first click
+        let start = (cluster.start - ZOOM_DURATION).max(0.0);
+        let end = hold_end(&cluster, &moves).min(duration);
+
+        if end <= start {
+            continue;
+        }
+
+        if let Some(prev) = segments.last_mut() {
+            if start <= prev.end {
+                prev.end = prev.end.max(end);
+                continue;
+            }
+        }
+
+        segments.push(ZoomSegment {
+            start,
+            end,
+            amount: AMOUNT,
+            mode: ZoomMode::Manual {
+                x: center.x as f32,
+                y: center.y as f32,
+            },
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![],
+        });
+    }
+
+    segments
+}
+
+// Extends the cluster past its last click while the cursor keeps moving around its center
+fn hold_end(cluster: &Cluster, moves: &[Activity]) -> f64 {
+    let center = cluster.center();
+    let limit = cluster.end + MAX_HOLD;
+    let mut last_active = cluster.end;
+
+    for m in moves
+        .iter()
+        .skip_while(|m| m.time <= cluster.end)
+        .take_while(|m| m.time <= limit)
+    {
+        if m.time - last_active > IDLE_GAP || distance(center, m.position) > CLUSTER_RADIUS {
+            break;
+        }
+
+        last_active = m.time;
+    }
+
+    (cluster.end + MIN_HOLD)
+        .max(last_active + IDLE_GAP)
+        .min(limit)
+}
+
+// Maps each segment's click-down and move events onto the timeline, skipping anything
+// that falls in a cut, and returns them sorted alongside the timeline's duration.
+fn timeline_activity(
+    project: &ProjectConfiguration,
+    segment_cursors: &[&SegmentCursors],
+) -> (Vec<Activity>, Vec<Activity>, f64) {
+    let mut clicks = vec![];
+    let mut moves = vec![];
+
+    let mut map_events = |cursor: &CursorEvents, to_timeline: &dyn Fn(f64) -> Option<f64>| {
+        clicks.extend(cursor.clicks.iter().filter(|c| c.down).filter_map(|c| {
+            Some(Activity {
+                time: to_timeline(c.process_time_ms / 1000.0)?,
+                position: XY::new(c.x, c.y),
+            })
+        }));
+        moves.extend(cursor.moves.iter().filter_map(|m| {
+            Some(Activity {
+                time: to_timeline(m.process_time_ms / 1000.0)?,
+                position: XY::new(m.x, m.y),
+            })
+        }));
+    };
+
+    let duration = match &project.timeline {
+        Some(timeline) => {
+            let mut offset = 0.0;
+
+            for segment in &timeline.segments {
+                let Some(cursors) = segment_cursors.get(segment.recording_segment as usize) else {
+                    continue;
+                };
+                let cursor: &CursorEvents = cursors.get(segment.display);
+
+                map_events(cursor, &|time| {
+                    (segment.start..=segment.end)
+                        .contains(&time)
+                        .then(|| offset + (time - segment.start) / segment.timescale)
+                });
+
+                offset += (segment.end - segment.start) / segment.timescale;
+            }
+
+            offset
+        }
+        None => {
+            if let Some(cursors) = segment_cursors.first() {
+                map_events(cursors.get(0), &Some);
+            }
+
+            moves
+                .iter()
+                .chain(clicks.iter())
+                .map(|a| a.time)
+                .fold(0.0, f64::max)
+        }
+    };
+
+    clicks.sort_by(|a, b| a.time.total_cmp(&b.time));
+    moves.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    (clicks, moves, duration)
+}
+
+fn distance(a: XY<f64>, b: XY<f64>) -> f64 {
+    let d = a - b;
+    (d.x * d.x + d.y * d.y).sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use cap_project::{CursorClickEvent, CursorMoveEvent};
+
+    use super::*;
+
+    fn click(time: f64, x: f64, y: f64) -> CursorClickEvent {
+        CursorClickEvent {
+            active_modifiers: vec![],
+            cursor_num: 0,
+            cursor_id: "default".to_string(),
+            process_time_ms: time * 1000.0,
+            unix_time_ms: 0.0,
+            down: true,
+            x,
+            y,
+            display: 0,
+        }
+    }
+
+    fn mv(time: f64, x: f64, y: f64) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time * 1000.0,
+            unix_time_ms: 0.0,
+            x,
+            y,
+            display: 0,
+        }
+    }
+
+    #[test]
+    fn click_cluster_becomes_segment() {
+        let cursor = CursorEvents {
+            clicks: vec![
+                click(3.0, 0.2, 0.2),
+                click(4.0, 0.22, 0.2),
+                // lone click elsewhere doesn't get a segment
+                click(12.0, 0.8, 0.8),
+            ],
+            moves: vec![mv(0.0, 0.5, 0.5), mv(20.0, 0.5, 0.5)],
+            ..Default::default()
+        };
+
+        let segments = generate_zoom_segments(
+            &ProjectConfiguration::default(),
+            &[&SegmentCursors::from(cursor)],
+        );
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].start, 2.0);
+        assert_eq!(segments[0].end, 4.0 + MIN_HOLD);
+        let ZoomMode::Manual { x, y } = segments[0].mode else {
+            panic!("expected manual zoom");
+        };
+        assert!((x - 0.21).abs() < 1e-6 && (y - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn nearby_movement_extends_segment() {
+        let cursor = CursorEvents {
+            clicks: vec![click(3.0, 0.2, 0.2), click(4.0, 0.2, 0.2)],
+            moves: vec![
+                mv(4.5, 0.25, 0.2),
+                mv(5.5, 0.2, 0.25),
+                mv(6.5, 0.2, 0.2),
+                // moved away
+                mv(7.0, 0.9, 0.9),
+                mv(20.0, 0.9, 0.9),
+            ],
+            ..Default::default()
+        };
+
+        let segments = generate_zoom_segments(
+            &ProjectConfiguration::default(),
+            &[&SegmentCursors::from(cursor)],
+        );
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].end, 6.5 + IDLE_GAP);
+    }
+}
@@ -0,0 +1,253 @@
+use cap_project::{CameraPosition, CameraXPosition, CameraYPosition, ProjectConfiguration, XY};
+
+pub const CAMERA_PADDING: f32 = 50.0;
+
+// seconds the camera takes to glide to a timeline segment's position
+const CAMERA_MOVE_DURATION: f64 = 0.4;
+
+// Where the camera bubble sits in the output frame, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraLayout {
+    pub position: XY<f32>,
+    pub size: XY<f32>,
+}
+
+impl CameraLayout {
+    pub fn new(
+        project: &ProjectConfiguration,
+        output_size: XY<f32>,
+        zoom_t: f32,
+        time: f64,
+        dodge: Option<XY<f32>>,
+    ) -> Self {
+        let min_axis = output_size.x.min(output_size.y);
+
+        // Calculate camera size based on zoom
+        let base_size = project.camera.size / 100.0;
+        let zoom_size = project
+            .camera
+            .zoom_size
+            .unwrap_or(cap_project::Camera::default_zoom_size())
+            / 100.0;
+
+        let zoomed_size = zoom_t * zoom_size * base_size + (1.0 - zoom_t) * base_size;
+        let size = XY::new(
+            min_axis * zoomed_size + CAMERA_PADDING,
+            min_axis * zoomed_size + CAMERA_PADDING,
+        );
+
+        let (from, to, progress) = segment_positions(project, time);
+        let position = {
+            let from = corner_position(&from, output_size, size);
+            let to = corner_position(&to, output_size, size);
+            from + (to - from) * ease(progress)
+        };
+
+        // moves away as the zoom comes in and back as it goes out
+        let position = match dodge.and_then(|target| dodge_position(&to, target, output_size, size))
+        {
+            Some(dodged) => position + (dodged - position) * ease(zoom_t),
+            None => position,
+        };
+
+        Self { position, size }
+    }
+
+    pub fn bounds(&self) -> [f32; 4] {
+        [
+            self.position.x,
+            self.position.y,
+            self.position.x + self.size.x,
+            self.position.y + self.size.y,
+        ]
+    }
+}
+
+pub fn corner_position(position: &CameraPosition, output_size: XY<f32>, size: XY<f32>) -> XY<f32> {
+    XY::new(
+        match position.x {
+            CameraXPosition::Left => CAMERA_PADDING,
+            CameraXPosition::Center => output_size.x / 2.0 - size.x / 2.0,
+            CameraXPosition::Right => output_size.x - CAMERA_PADDING - size.x,
+        },
+        match position.y {
+            CameraYPosition::Top => CAMERA_PADDING,
+            CameraYPosition::Bottom => output_size.y - size.y - CAMERA_PADDING,
+        },
+    )
+}
+
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// The closest other corner for the camera when it sits on top of `target`, trying the
+// other side before the other edge
+fn dodge_position(
+    position: &CameraPosition,
+    target: XY<f32>,
+    output_size: XY<f32>,
+    size: XY<f32>,
+) -> Option<XY<f32>> {
+    let covers = |corner: XY<f32>| {
+        let start = corner - CAMERA_PADDING;
+        let end = corner + size + XY::new(CAMERA_PADDING, CAMERA_PADDING);
+        target.x >= start.x && target.x <= end.x && target.y >= start.y && target.y <= end.y
+    };
+
+    if !covers(corner_position(position, output_size, size)) {
+        return None;
+    }
+
+    let flipped_x = match position.x {
+        CameraXPosition::Left => CameraXPosition::Right,
+        CameraXPosition::Center => CameraXPosition::Center,
+        CameraXPosition::Right => CameraXPosition::Left,
+    };
+    let flipped_y = match position.y {
+        CameraYPosition::Top => CameraYPosition::Bottom,
+        CameraYPosition::Bottom => CameraYPosition::Top,
+    };
+
+    [
+        (flipped_x.clone(), position.y.clone()),
+        (position.x.clone(), flipped_y.clone()),
+        (flipped_x, flipped_y),
+    ]
+    .into_iter()
+    .map(|(x, y)| corner_position(&CameraPosition { x, y }, output_size, size))
+    .find(|corner| !covers(*corner))
+}
+
+// Timeline segments can put the camera somewhere other than the project's position, which
+// it glides to over the start of the segment. Returns the positions it's moving between at
+// `time` and how far along it is.
+fn segment_positions(
+    project: &ProjectConfiguration,
+    time: f64,
+) -> (CameraPosition, CameraPosition, f32) {
+    let default = &project.camera.position;
+    let Some(timeline) = &project.timeline else {
+        return (default.clone(), default.clone(), 1.0);
+    };
+
+    let mut start = 0.0;
+    let mut prev = None;
+
+    for segment in &timeline.segments {
+        let position = segment.camera_position.as_ref().unwrap_or(default);
+        let end = start + segment.duration();
+
+        if time < end {
+            let progress = ((time - start) / CAMERA_MOVE_DURATION).clamp(0.0, 1.0) as f32;
+            return (prev.unwrap_or(position).clone(), position.clone(), progress);
+        }
+
+        prev = Some(position);
+        start = end;
+    }
+
+    let last = prev.unwrap_or(default);
+    (last.clone(), last.clone(), 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use cap_project::{TimelineConfiguration, TimelineSegment};
+
+    use super::*;
+
+    const OUTPUT: XY<f32> = XY {
+        x: 1000.0,
+        y: 500.0,
+    };
+
+    fn project(positions: &[Option<CameraPosition>]) -> ProjectConfiguration {
+        ProjectConfiguration {
+            timeline: Some(TimelineConfiguration {
+                segments: positions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, camera_position)| TimelineSegment {
+                        recording_segment: 0,
+                        timescale: 1.0,
+                        start: i as f64 * 2.0,
+                        end: i as f64 * 2.0 + 2.0,
+                        display: 0,
+                        camera_position: camera_position.clone(),
+                        transition: None,
+                    })
+                    .collect(),
+                zoom_segments: vec![],
+                speed_segments: vec![],
+                markers: vec![],
+                text_segments: vec![],
+                annotation_segments: vec![],
+                caption_segments: vec![],
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn top_left() -> CameraPosition {
+        CameraPosition {
+            x: CameraXPosition::Left,
+            y: CameraYPosition::Top,
+        }
+    }
+
+    #[test]
+    fn defaults_to_project_position() {
+        let project = ProjectConfiguration::default();
+        let layout = CameraLayout::new(&project, OUTPUT, 0.0, 1.0, None);
+
+        // bottom right by default
+        assert_eq!(
+            layout.bounds()[2..],
+            [OUTPUT.x - CAMERA_PADDING, OUTPUT.y - CAMERA_PADDING]
+        );
+    }
+
+    #[test]
+    fn glides_to_segment_position() {
+        let project = project(&[None, Some(top_left())]);
+        let at = |time| CameraLayout::new(&project, OUTPUT, 0.0, time, None).position;
+
+        let default = at(1.0);
+        let moved = at(3.0);
+        assert_eq!(moved, XY::new(CAMERA_PADDING, CAMERA_PADDING));
+
+        // eased, so exactly halfway at the middle of the move
+        let halfway = at(2.0 + CAMERA_MOVE_DURATION / 2.0);
+        assert_eq!(halfway, (default + moved) / 2.0);
+    }
+
+    #[test]
+    fn starts_in_first_segment_position() {
+        let project = project(&[Some(top_left()), None]);
+        let position = CameraLayout::new(&project, OUTPUT, 0.0, 0.0, None).position;
+
+        assert_eq!(position, XY::new(CAMERA_PADDING, CAMERA_PADDING));
+    }
+
+    #[test]
+    fn dodges_zoom_target_while_zoomed() {
+        let project = ProjectConfiguration::default();
+        let at = |zoom_t, target| CameraLayout::new(&project, OUTPUT, zoom_t, 0.0, Some(target));
+
+        // nowhere near the bubble, so it stays in the bottom right
+        let layout = at(1.0, XY::new(100.0, 100.0));
+        assert_eq!(
+            layout.bounds()[2..],
+            [OUTPUT.x - CAMERA_PADDING, OUTPUT.y - CAMERA_PADDING]
+        );
+
+        // under it, so it moves to the bottom left as the zoom comes in
+        let under = XY::new(850.0, 350.0);
+        assert_eq!(
+            at(1.0, under).position,
+            XY::new(CAMERA_PADDING, layout.position.y)
+        );
+        assert_eq!(at(0.0, under).bounds()[2], OUTPUT.x - CAMERA_PADDING);
+    }
+}
@@ -0,0 +1,184 @@
+use ffmpeg::{color, frame, software::scaling};
+use serde::Serialize;
+use specta::Type;
+
+// Brightness SDR white is shown at when mixed with HDR, per BT.2408
+pub const REFERENCE_WHITE_NITS: f32 = 203.0;
+// What HDR captures are assumed to peak at, since screen recordings don't carry mastering
+// metadata. Most HDR displays top out around here.
+pub const HDR_PEAK_NITS: f32 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferFunction {
+    // sRGB or BT.709, which are treated the same
+    #[default]
+    Sdr,
+    // SMPTE ST 2084, used by HDR10
+    Pq,
+    Hlg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorPrimaries {
+    #[default]
+    Bt709,
+    DisplayP3,
+    Bt2020,
+}
+
+impl ColorPrimaries {
+    // Converts linear RGB in these primaries to CIE XYZ, both with a D65 white point
+    fn to_xyz(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Bt709 => [
+                [0.4124, 0.3576, 0.1805],
+                [0.2126, 0.7152, 0.0722],
+                [0.0193, 0.1192, 0.9505],
+            ],
+            Self::DisplayP3 => [
+                [0.4866, 0.2657, 0.1982],
+                [0.2290, 0.6917, 0.0793],
+                [0.0000, 0.0451, 1.0439],
+            ],
+            Self::Bt2020 => [
+                [0.6370, 0.1446, 0.1689],
+                [0.2627, 0.6780, 0.0593],
+                [0.0000, 0.0281, 1.0610],
+            ],
+        }
+    }
+
+    fn from_xyz(self) -> [[f32; 3]; 3] {
+        invert(self.to_xyz())
+    }
+
+    // Converts linear RGB in these primaries to linear RGB in `to`'s
+    pub fn conversion_to(self, to: ColorPrimaries) -> [[f32; 3]; 3] {
+        multiply(to.from_xyz(), self.to_xyz())
+    }
+}
+
+// How a video's pixels map to colors, as tagged in its stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoColor {
+    pub transfer: TransferFunction,
+    pub primaries: ColorPrimaries,
+}
+
+impl VideoColor {
+    // Untagged streams are taken to be SDR BT.709, like players do
+    pub fn from_decoder(decoder: &ffmpeg::decoder::Video) -> Self {
+        Self {
+            transfer: match decoder.color_transfer_characteristic() {
+                color::TransferCharacteristic::SMPTE2084 => TransferFunction::Pq,
+                color::TransferCharacteristic::ARIB_STD_B67 => TransferFunction::Hlg,
+                _ => TransferFunction::Sdr,
+            },
+            primaries: match decoder.color_primaries() {
+                color::Primaries::SMPTE432 | color::Primaries::SMPTE431 => {
+                    ColorPrimaries::DisplayP3
+                }
+                color::Primaries::BT2020 => ColorPrimaries::Bt2020,
+                _ => ColorPrimaries::Bt709,
+            },
+        }
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.transfer != TransferFunction::Sdr
+    }
+
+    // Whether frames have to be converted before they're composited with SDR layers
+    pub fn needs_conversion(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+// Makes swscale convert YUV frames to RGB with the matrix and range they were encoded with.
+// It uses BT.601 otherwise, which shifts the colors of anything HD or HDR.
+pub fn set_source_colorspace(scaler: &mut scaling::Context, frame: &frame::Video) {
+    use ffmpeg::ffi::{
+        sws_getCoefficients, sws_setColorspaceDetails, SWS_CS_BT2020, SWS_CS_DEFAULT, SWS_CS_ITU709,
+    };
+
+    let matrix = match frame.color_space() {
+        color::Space::BT709 => SWS_CS_ITU709,
+        color::Space::BT2020NCL | color::Space::BT2020CL => SWS_CS_BT2020,
+        // untagged HD frames are almost always BT.709
+        color::Space::Unspecified if frame.height() >= 720 => SWS_CS_ITU709,
+        _ => SWS_CS_DEFAULT,
+    };
+    let full_range = frame.color_range() == color::Range::JPEG;
+
+    unsafe {
+        let coefficients = sws_getCoefficients(matrix as i32);
+        sws_setColorspaceDetails(
+            scaler.as_mut_ptr(),
+            coefficients,
+            full_range as i32,
+            sws_getCoefficients(SWS_CS_DEFAULT as i32),
+            1,
+            0,
+            1 << 16,
+            1 << 16,
+        );
+    }
+}
+
+fn multiply(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    out
+}
+
+fn invert(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let [[a, b, c], [d, e, f], [g, h, i]] = m;
+    let det = a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g);
+
+    [
+        [e * i - f * h, c * h - b * i, b * f - c * e],
+        [f * g - d * i, a * i - c * g, c * d - a * f],
+        [d * h - e * g, b * g - a * h, a * e - b * d],
+    ]
+    .map(|row| row.map(|v| v / det))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn apply(m: [[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+        m.map(|row| row.iter().zip(rgb).map(|(a, b)| a * b).sum())
+    }
+
+    #[test]
+    fn conversions_keep_white() {
+        for from in [
+            ColorPrimaries::Bt709,
+            ColorPrimaries::DisplayP3,
+            ColorPrimaries::Bt2020,
+        ] {
+            for c in apply(from.conversion_to(ColorPrimaries::Bt709), [1.0; 3]) {
+                assert!((c - 1.0).abs() < 0.01, "{from:?} white became {c}");
+            }
+        }
+    }
+
+    #[test]
+    fn wide_gamut_reds_fall_outside_bt709() {
+        let red = apply(
+            ColorPrimaries::Bt2020.conversion_to(ColorPrimaries::Bt709),
+            [1.0, 0.0, 0.0],
+        );
+
+        assert!(red[0] > 1.0);
+        assert!(red[1] < 0.0);
+    }
+}
@@ -25,7 +25,14 @@ pub struct CompositeVideoFrameUniforms {
     pub shadow_size: f32,
     pub shadow_opacity: f32,
     pub shadow_blur: f32,
-    pub _padding: [f32; 3],
+    pub shadow_offset: [f32; 2],
+    pub border_width: f32,
+    // 1.0 to resample the frame with `lanczos3` rather than the bilinear sampler
+    pub lanczos: f32,
+    pub border_color: [f32; 4],
+    // varies the motion blur's jitter from frame to frame
+    pub seed: u32,
+    pub _padding: [u32; 3],
 }
 
 impl CompositeVideoFrameUniforms {
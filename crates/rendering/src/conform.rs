@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// How output frames are made when an export's frame rate doesn't line up with the
+// recording's. Screen captures only deliver frames when something on screen changes, so
+// every export is conformed to a constant rate one way or the other.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameRateConversion {
+    // repeat the latest recorded frame, which keeps every frame sharp
+    #[default]
+    Duplicate,
+    // mix the recorded frames either side of each output frame, which smooths out motion
+    // when converting between rates that don't divide evenly
+    Blend,
+}
+
+// Times that land inside the recorded frames either side of `time`, and how far `time` is
+// from the first towards the second
+pub fn blend_between(time: f64, source_fps: u32) -> (f64, f64, f32) {
+    let fps = source_fps as f64;
+    let position = time * fps;
+    let before = position.floor();
+
+    // the middle of each frame, so rounding can't land the lookup on a neighbour
+    (
+        (before + 0.5) / fps,
+        (before + 1.5) / fps,
+        (position - before) as f32,
+    )
+}
+
+// `weight` of 0 gives `a`, 1 gives `b`
+pub fn blend_frames(a: &[u8], b: &[u8], weight: f32) -> Vec<u8> {
+    a.iter()
+        .zip(b)
+        .map(|(&a, &b)| (a as f32 + (b as f32 - a as f32) * weight).round() as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blends_between_neighbouring_frames() {
+        let (before, after, weight) = blend_between(0.025, 60);
+
+        assert_eq!((before * 60.0).floor(), 1.0);
+        assert_eq!((after * 60.0).floor(), 2.0);
+        assert!((weight - 0.5).abs() < 1e-4);
+
+        let (before, _, weight) = blend_between(1.0, 30);
+        assert_eq!((before * 30.0).floor(), 30.0);
+        assert_eq!(weight, 0.0);
+    }
+
+    #[test]
+    fn mixes_pixels() {
+        assert_eq!(
+            blend_frames(&[0, 100, 255], &[255, 200, 255], 0.25),
+            vec![64, 125, 255]
+        );
+    }
+}
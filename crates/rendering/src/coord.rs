@@ -14,6 +14,10 @@ pub struct RawDisplayUVSpace;
 #[derive(Default, Clone, Copy, Debug)]
 pub struct CroppedDisplaySpace;
 
+// zoom bounds and positions
+#[derive(Default, Clone, Copy, Debug)]
+pub struct CroppedDisplayUVSpace;
+
 #[derive(Default, Clone, Copy, Debug)]
 pub struct FrameSpace;
 
@@ -71,6 +75,21 @@ impl Coord<RawDisplayUVSpace> {
             .to_cropped_display_space(options, project)
             .to_frame_space(options, project, resolution_base)
     }
+
+    pub fn to_cropped_display_uv_space(
+        &self,
+        options: &RenderOptions,
+        project: &ProjectConfiguration,
+    ) -> Coord<CroppedDisplayUVSpace> {
+        let crop = ProjectUniforms::get_crop(options, project);
+
+        Coord::new(
+            self.to_raw_display_space(options)
+                .to_cropped_display_space(options, project)
+                .coord
+                / crop.size.map(|v| v as f64),
+        )
+    }
 }
 
 impl Coord<RawDisplaySpace> {
@@ -0,0 +1,257 @@
+use cap_project::{CursorShape, CursorTheme, XY};
+use image::{Rgba, RgbaImage};
+
+// sprites are drawn on a 32 unit grid and rasterized at this many pixels per unit,
+// so they stay sharp when the cursor is scaled up or zoomed into
+const PIXELS_PER_UNIT: f32 = 4.0;
+// samples per pixel along each axis
+const SUPERSAMPLING: u32 = 4;
+// transparent margin around the outline, in grid units
+const MARGIN: f32 = 1.0;
+
+pub struct CursorSprite {
+    pub image: RgbaImage,
+    // normalized to the sprite's size, like recorded cursor hotspots
+    pub hotspot: XY<f64>,
+}
+
+struct SpriteStyle {
+    fill: [u8; 3],
+    outline: [u8; 3],
+    outline_width: f32,
+}
+
+fn style(theme: CursorTheme) -> SpriteStyle {
+    match theme {
+        CursorTheme::Windows => SpriteStyle {
+            fill: [255, 255, 255],
+            outline: [0, 0, 0],
+            outline_width: 1.0,
+        },
+        _ => SpriteStyle {
+            fill: [0, 0, 0],
+            outline: [255, 255, 255],
+            outline_width: 1.25,
+        },
+    }
+}
+
+// Outline of each shape in grid units, plus its hotspot
+fn outline(theme: CursorTheme, shape: CursorShape) -> (&'static [[f32; 2]], [f32; 2]) {
+    match (shape, theme) {
+        (CursorShape::Arrow, CursorTheme::Windows) => (
+            &[
+                [0.0, 0.0],
+                [0.0, 20.0],
+                [4.5, 15.5],
+                [7.5, 22.5],
+                [10.0, 21.5],
+                [7.0, 14.5],
+                [13.0, 14.5],
+            ],
+            [0.0, 0.0],
+        ),
+        (CursorShape::Arrow, _) => (
+            &[
+                [0.0, 0.0],
+                [0.0, 22.0],
+                [5.0, 17.0],
+                [8.5, 25.0],
+                [12.0, 23.5],
+                [8.5, 16.0],
+                [15.0, 16.0],
+            ],
+            [0.0, 0.0],
+        ),
+        (CursorShape::IBeam, _) => (
+            &[
+                [0.0, 0.0],
+                [10.0, 0.0],
+                [10.0, 3.0],
+                [7.0, 3.0],
+                [7.0, 19.0],
+                [10.0, 19.0],
+                [10.0, 22.0],
+                [0.0, 22.0],
+                [0.0, 19.0],
+                [3.0, 19.0],
+                [3.0, 3.0],
+                [0.0, 3.0],
+            ],
+            [5.0, 11.0],
+        ),
+        (CursorShape::PointingHand, _) => (
+            &[
+                [4.75, 0.0],
+                [8.75, 0.0],
+                [8.75, 8.0],
+                [11.5, 8.5],
+                [14.5, 9.5],
+                [17.5, 10.5],
+                [17.5, 19.0],
+                [15.0, 24.0],
+                [6.5, 24.0],
+                [0.0, 15.5],
+                [0.5, 13.0],
+                [3.0, 12.5],
+                [4.75, 14.5],
+            ],
+            [6.75, 0.0],
+        ),
+        (CursorShape::Crosshair, _) => (
+            &[
+                [9.0, 0.0],
+                [13.0, 0.0],
+                [13.0, 9.0],
+                [22.0, 9.0],
+                [22.0, 13.0],
+                [13.0, 13.0],
+                [13.0, 22.0],
+                [9.0, 22.0],
+                [9.0, 13.0],
+                [0.0, 13.0],
+                [0.0, 9.0],
+                [9.0, 9.0],
+            ],
+            [11.0, 11.0],
+        ),
+    }
+}
+
+// Rasterizes the bundled sprite for a shape. The outline is drawn inside the polygon's edge,
+// so it never reaches past the hotspot.
+pub fn cursor_sprite(theme: CursorTheme, shape: CursorShape) -> CursorSprite {
+    let (points, hotspot) = outline(theme, shape);
+    let style = style(theme);
+
+    let max = points
+        .iter()
+        .fold([0.0f32; 2], |max, p| [max[0].max(p[0]), max[1].max(p[1])]);
+    let width = ((max[0] + MARGIN * 2.0) * PIXELS_PER_UNIT).ceil() as u32;
+    let height = ((max[1] + MARGIN * 2.0) * PIXELS_PER_UNIT).ceil() as u32;
+
+    let mut image = RgbaImage::new(width, height);
+    let samples = (SUPERSAMPLING * SUPERSAMPLING) as f32;
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let mut fill = 0.0;
+        let mut outline = 0.0;
+
+        for sy in 0..SUPERSAMPLING {
+            for sx in 0..SUPERSAMPLING {
+                let point = [
+                    (x as f32 + (sx as f32 + 0.5) / SUPERSAMPLING as f32) / PIXELS_PER_UNIT
+                        - MARGIN,
+                    (y as f32 + (sy as f32 + 0.5) / SUPERSAMPLING as f32) / PIXELS_PER_UNIT
+                        - MARGIN,
+                ];
+
+                if !contains(points, point) {
+                    continue;
+                }
+
+                if edge_distance(points, point) < style.outline_width {
+                    outline += 1.0;
+                } else {
+                    fill += 1.0;
+                }
+            }
+        }
+
+        let alpha = (fill + outline) / samples;
+        if alpha == 0.0 {
+            continue;
+        }
+
+        let color = |channel: usize| {
+            (style.fill[channel] as f32 * fill + style.outline[channel] as f32 * outline)
+                / (fill + outline)
+        };
+
+        // premultiplied, which is what the cursor pipeline's blend state expects
+        *pixel = Rgba([
+            (color(0) * alpha).round() as u8,
+            (color(1) * alpha).round() as u8,
+            (color(2) * alpha).round() as u8,
+            (alpha * 255.0).round() as u8,
+        ]);
+    }
+
+    CursorSprite {
+        hotspot: XY::new(
+            ((hotspot[0] + MARGIN) * PIXELS_PER_UNIT / width as f32) as f64,
+            ((hotspot[1] + MARGIN) * PIXELS_PER_UNIT / height as f32) as f64,
+        ),
+        image,
+    }
+}
+
+// even-odd rule
+fn contains(points: &[[f32; 2]], p: [f32; 2]) -> bool {
+    let mut inside = false;
+
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < (b[0] - a[0]) * (p[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+fn edge_distance(points: &[[f32; 2]], p: [f32; 2]) -> f32 {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let b = points[(i + 1) % points.len()];
+            let ab = [b[0] - a[0], b[1] - a[1]];
+            let ap = [p[0] - a[0], p[1] - a[1]];
+            let t =
+                ((ap[0] * ab[0] + ap[1] * ab[1]) / (ab[0] * ab[0] + ab[1] * ab[1])).clamp(0.0, 1.0);
+
+            (ap[0] - ab[0] * t).hypot(ap[1] - ab[1] * t)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arrow_sprites() {
+        for (theme, fill) in [(CursorTheme::Macos, 0), (CursorTheme::Windows, 255)] {
+            let sprite = cursor_sprite(theme, CursorShape::Arrow);
+            let (width, height) = sprite.image.dimensions();
+
+            // the hotspot sits on the arrow's tip, just inside the margin
+            let hotspot = [
+                (sprite.hotspot.x * width as f64).round() as u32,
+                (sprite.hotspot.y * height as f64).round() as u32,
+            ];
+            assert_eq!(hotspot, [4, 4]);
+            assert_eq!(sprite.image.get_pixel(0, 0)[3], 0);
+            assert_eq!(sprite.image.get_pixel(width - 1, 0)[3], 0);
+
+            let inside = sprite.image.get_pixel(4 * 4, 14 * 4);
+            assert_eq!(*inside, Rgba([fill, fill, fill, 255]));
+        }
+    }
+
+    #[test]
+    fn every_shape_has_a_sprite() {
+        for theme in [CursorTheme::Macos, CursorTheme::Windows] {
+            for shape in CursorShape::ALL {
+                let sprite = cursor_sprite(theme, shape);
+                assert!(sprite.image.pixels().any(|p| p[3] == 255));
+                assert!((0.0..=1.0).contains(&sprite.hotspot.x));
+                assert!((0.0..=1.0).contains(&sprite.hotspot.y));
+            }
+        }
+    }
+}
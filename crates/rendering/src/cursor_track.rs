@@ -0,0 +1,373 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use cap_project::{CursorConfiguration, CursorEvents, CursorMoveEvent, XY};
+
+use crate::spring_mass_damper::{SpringMassDamperSimulation, SIMULATION_TICK};
+
+// samples per second of the precomputed smoothed track, comfortably above any export fps
+const SAMPLE_RATE: f64 = 120.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CursorTrackPosition {
+    pub position: XY<f64>,
+    pub velocity: XY<f32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    position: XY<f32>,
+    velocity: XY<f32>,
+}
+
+// A segment's cursor movement, preprocessed so per-frame lookups don't scan every event.
+// The spring smoothing is simulated once up front and resampled at a fixed rate.
+pub struct CursorTrack {
+    moves: Vec<CursorMoveEvent>,
+    click_times_ms: Vec<f64>,
+    samples: Vec<Sample>,
+}
+
+impl CursorTrack {
+    pub fn new(cursor: &CursorEvents, tension: f32, mass: f32, friction: f32) -> Self {
+        let mut click_times_ms = cursor
+            .clicks
+            .iter()
+            .filter(|c| c.down)
+            .map(|c| c.process_time_ms)
+            .collect::<Vec<_>>();
+        click_times_ms.sort_by(f64::total_cmp);
+
+        Self {
+            samples: resample_smoothed(&cursor.moves, tension, mass, friction),
+            moves: cursor.moves.clone(),
+            click_times_ms,
+        }
+    }
+
+    // The last move at or before `time_secs`, or the first move if there's none yet
+    pub fn move_at(&self, time_secs: f64) -> Option<&CursorMoveEvent> {
+        let time_ms = time_secs * 1000.0;
+        let index = self
+            .moves
+            .partition_point(|m| m.process_time_ms <= time_ms)
+            .saturating_sub(1);
+
+        self.moves.get(index)
+    }
+
+    pub fn position_at(&self, time_secs: f64, raw: bool) -> Option<CursorTrackPosition> {
+        let first = self.moves.first()?;
+        let last = self.moves.last()?;
+        let time_ms = time_secs * 1000.0;
+
+        if raw || time_ms < first.process_time_ms || time_ms > last.process_time_ms {
+            let event = self.move_at(time_secs)?;
+
+            return Some(CursorTrackPosition {
+                position: XY::new(event.x, event.y),
+                velocity: XY::new(0.0, 0.0),
+            });
+        }
+
+        let index = time_secs * SAMPLE_RATE;
+        let i = (index.floor() as usize).min(self.samples.len() - 1);
+        let a = self.samples[i];
+        let b = self.samples.get(i + 1).copied().unwrap_or(a);
+        let t = (index - i as f64).clamp(0.0, 1.0) as f32;
+
+        Some(CursorTrackPosition {
+            position: (a.position + (b.position - a.position) * t).map(|v| v as f64),
+            velocity: a.velocity + (b.velocity - a.velocity) * t,
+        })
+    }
+
+    // Seconds since the last mouse down at or before `time_secs`
+    pub fn time_since_click(&self, time_secs: f64) -> Option<f64> {
+        let time_ms = time_secs * 1000.0;
+        let index = self.click_times_ms.partition_point(|t| *t <= time_ms);
+
+        index
+            .checked_sub(1)
+            .map(|i| (time_ms - self.click_times_ms[i]) / 1000.0)
+    }
+
+    // Positions at `time_secs` and every `spacing_secs` before it, stopping at the first move
+    pub fn trail(
+        &self,
+        time_secs: f64,
+        length: usize,
+        spacing_secs: f64,
+        raw: bool,
+    ) -> Vec<XY<f64>> {
+        let Some(first) = self.moves.first() else {
+            return vec![];
+        };
+        let first_secs = first.process_time_ms / 1000.0;
+
+        (0..length)
+            .map(|i| time_secs - i as f64 * spacing_secs)
+            .take_while(|t| *t >= first_secs)
+            .filter_map(|t| self.position_at(t, raw))
+            .map(|p| p.position)
+            .collect()
+    }
+
+    // Times of the mouse downs in the `duration_secs` leading up to `time_secs`, latest first
+    pub fn clicks_within(
+        &self,
+        time_secs: f64,
+        duration_secs: f64,
+    ) -> impl Iterator<Item = f64> + '_ {
+        let time_ms = time_secs * 1000.0;
+        let end = self.click_times_ms.partition_point(|t| *t <= time_ms);
+        let start = self
+            .click_times_ms
+            .partition_point(|t| *t <= time_ms - duration_secs * 1000.0);
+
+        self.click_times_ms[start..end]
+            .iter()
+            .rev()
+            .map(|t| t / 1000.0)
+    }
+}
+
+// events address and move count, plus the smoothing parameters
+type TrackKey = (usize, usize, [u32; 3]);
+
+// Tracks are built on first use and kept for as long as the render constants live,
+// so a whole export or editing session shares one per segment and smoothing setting.
+#[derive(Default)]
+pub struct CursorTracks {
+    tracks: Mutex<HashMap<TrackKey, Arc<CursorTrack>>>,
+}
+
+impl CursorTracks {
+    pub fn get(&self, cursor: &CursorEvents, config: &CursorConfiguration) -> Arc<CursorTrack> {
+        let key: TrackKey = (
+            cursor as *const CursorEvents as usize,
+            cursor.moves.len(),
+            [
+                config.tension.to_bits(),
+                config.mass.to_bits(),
+                config.friction.to_bits(),
+            ],
+        );
+
+        self.tracks
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(CursorTrack::new(
+                    cursor,
+                    config.tension,
+                    config.mass,
+                    config.friction,
+                ))
+            })
+            .clone()
+    }
+}
+
+struct SmoothedCursorEvent {
+    time: f32,
+    target_position: XY<f32>,
+    position: XY<f32>,
+    velocity: XY<f32>,
+}
+
+fn smoothed_cursor_events(
+    moves: &[CursorMoveEvent],
+    tension: f32,
+    mass: f32,
+    friction: f32,
+) -> Vec<SmoothedCursorEvent> {
+    let mut last_time = 0.0;
+
+    let mut events = vec![];
+
+    let mut sim = SpringMassDamperSimulation::new(tension, mass, friction);
+
+    sim.set_position(XY::new(moves[0].x, moves[0].y).map(|v| v as f32));
+    sim.set_velocity(XY::new(0.0, 0.0));
+
+    if moves[0].process_time_ms > 0.0 {
+        events.push(SmoothedCursorEvent {
+            time: 0.0,
+            target_position: sim.position,
+            position: sim.position,
+            velocity: sim.velocity,
+        })
+    }
+
+    for (i, m) in moves.iter().enumerate() {
+        let target_position = moves
+            .get(i + 1)
+            .map(|e| XY::new(e.x, e.y).map(|v| v as f32))
+            .unwrap_or(sim.target_position);
+        sim.set_target_position(target_position);
+
+        sim.run(m.process_time_ms as f32 - last_time);
+
+        last_time = m.process_time_ms as f32;
+
+        events.push(SmoothedCursorEvent {
+            time: m.process_time_ms as f32,
+            target_position,
+            position: sim.position,
+            velocity: sim.velocity,
+        });
+    }
+
+    events
+}
+
+// Samples the smoothed position every 1 / SAMPLE_RATE seconds. Each sample continues from
+// the last event before it, the same way a single query against the events would, but
+// consecutive samples within one event interval carry the simulation forward tick by tick
+// instead of re-running it from the event.
+fn resample_smoothed(
+    moves: &[CursorMoveEvent],
+    tension: f32,
+    mass: f32,
+    friction: f32,
+) -> Vec<Sample> {
+    let Some(last) = moves.last() else {
+        return vec![];
+    };
+
+    let events = smoothed_cursor_events(moves, tension, mass, friction);
+    let count = (last.process_time_ms / 1000.0 * SAMPLE_RATE).ceil() as usize + 1;
+
+    let mut samples = Vec::with_capacity(count);
+    let mut sim = SpringMassDamperSimulation::new(tension, mass, friction);
+    let mut event_index = 0;
+    let mut current = None;
+    let mut ticks = 0;
+
+    for i in 0..count {
+        let time_ms = (i as f64 / SAMPLE_RATE * 1000.0) as f32;
+
+        while event_index + 1 < events.len() && events[event_index + 1].time <= time_ms {
+            event_index += 1;
+        }
+
+        let e = &events[event_index];
+
+        if current != Some(event_index) {
+            sim.set_position(e.position);
+            sim.set_velocity(e.velocity);
+            sim.set_target_position(e.target_position);
+            current = Some(event_index);
+            ticks = 0;
+        }
+
+        // `run` always advances in whole ticks, so stepping one at a time lands on the same state
+        let target_ticks = ((time_ms - e.time) / SIMULATION_TICK).ceil() as u32;
+        while ticks < target_ticks {
+            sim.run(SIMULATION_TICK);
+            ticks += 1;
+        }
+
+        samples.push(Sample {
+            position: sim.position,
+            velocity: sim.velocity,
+        });
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod test {
+    use cap_project::CursorClickEvent;
+
+    use super::*;
+
+    fn mv(time_ms: f64, x: f64, y: f64) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: time_ms,
+            unix_time_ms: 0.0,
+            x,
+            y,
+            display: 0,
+        }
+    }
+
+    fn click(time_ms: f64) -> CursorClickEvent {
+        CursorClickEvent {
+            active_modifiers: vec![],
+            cursor_num: 0,
+            cursor_id: "default".to_string(),
+            process_time_ms: time_ms,
+            unix_time_ms: 0.0,
+            down: true,
+            x: 0.0,
+            y: 0.0,
+            display: 0,
+        }
+    }
+
+    #[test]
+    fn samples_match_simulating_from_the_last_event() {
+        let cursor = CursorEvents {
+            moves: vec![
+                mv(100.0, 0.1, 0.1),
+                mv(400.0, 0.8, 0.3),
+                mv(1000.0, 0.2, 0.9),
+            ],
+            ..Default::default()
+        };
+        let (tension, mass, friction) = (100.0, 1.0, 20.0);
+        let track = CursorTrack::new(&cursor, tension, mass, friction);
+        let events = smoothed_cursor_events(&cursor.moves, tension, mass, friction);
+
+        for time_ms in [250.0, 500.0, 750.0] {
+            let e = events.iter().rev().find(|e| e.time <= time_ms).unwrap();
+            let mut sim = SpringMassDamperSimulation::new(tension, mass, friction);
+            sim.set_position(e.position);
+            sim.set_velocity(e.velocity);
+            sim.set_target_position(e.target_position);
+            sim.run(time_ms - e.time);
+
+            let position = track
+                .position_at(time_ms as f64 / 1000.0, false)
+                .unwrap()
+                .position;
+            assert!((position.x - sim.position.x as f64).abs() < 1e-3);
+            assert!((position.y - sim.position.y as f64).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn raw_lookups() {
+        let cursor = CursorEvents {
+            moves: vec![mv(100.0, 0.1, 0.1), mv(400.0, 0.8, 0.3)],
+            clicks: vec![click(200.0), click(50.0)],
+            ..Default::default()
+        };
+        let track = CursorTrack::new(&cursor, 100.0, 1.0, 20.0);
+
+        assert_eq!(track.move_at(0.0).unwrap().x, 0.1);
+        assert_eq!(track.position_at(0.3, true).unwrap().position.x, 0.1);
+        assert_eq!(track.position_at(0.5, false).unwrap().position.x, 0.8);
+        assert!((track.time_since_click(0.3).unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(track.time_since_click(0.01), None);
+        assert_eq!(
+            track.clicks_within(0.3, 0.5).collect::<Vec<_>>(),
+            vec![0.2, 0.05]
+        );
+        assert_eq!(track.clicks_within(0.3, 0.2).collect::<Vec<_>>(), vec![0.2]);
+
+        let trail = track.trail(0.45, 5, 0.1, true);
+        assert_eq!(
+            trail.iter().map(|p| p.x).collect::<Vec<_>>(),
+            vec![0.8, 0.1, 0.1, 0.1]
+        );
+    }
+}
@@ -11,10 +11,15 @@ use cidre::{
     cv::{self, pixel_buffer::LockFlags},
     ns,
 };
-use ffmpeg::{codec, format, frame, Rational};
+use ffmpeg::{codec, color, format, frame, Rational};
 use tokio::{runtime::Handle as TokioHandle, sync::oneshot};
 
-use super::{pts_to_frame, DecodedFrame, VideoDecoderMessage, FRAME_CACHE_SIZE};
+use crate::{color::set_source_colorspace, frame_cache::source_id};
+
+use super::{
+    pts_to_frame, share_frame, DecodedFrame, GopCache, VideoDecoderMessage, FRAME_CACHE_SIZE,
+    GOP_CACHE_BYTES,
+};
 
 #[derive(Clone)]
 enum CachedFrame {
@@ -23,7 +28,15 @@ enum CachedFrame {
 }
 
 impl CachedFrame {
-    fn process(&mut self) -> Arc<Vec<u8>> {
+    fn size(&self) -> usize {
+        match self {
+            CachedFrame::Raw(image_buf) => image_buf.width() * image_buf.height() * 4,
+            CachedFrame::Processed(data) => data.len(),
+        }
+    }
+
+    // `color_space` is the stream's, since frames read through AVFoundation aren't tagged
+    fn process(&mut self, color_space: color::Space) -> Arc<Vec<u8>> {
         match self {
             CachedFrame::Raw(image_buf) => {
                 let format = pixel_format_to_pixel(image_buf.pixel_format());
@@ -125,6 +138,8 @@ impl CachedFrame {
                         format::Pixel::RGBA,
                     )
                     .unwrap();
+                    ffmpeg_frame.set_color_space(color_space);
+                    set_source_colorspace(&mut converter, &ffmpeg_frame);
 
                     let mut rgb_frame = frame::Video::empty();
                     converter.run(&ffmpeg_frame, &mut rgb_frame).unwrap();
@@ -174,7 +189,7 @@ impl AVAssetReaderDecoder {
 
         let handle = std::thread::spawn(move || {
             let init = || {
-                let (pixel_format, width, height) = {
+                let (pixel_format, width, height, color_space) = {
                     let input = ffmpeg::format::input(&path).unwrap();
 
                     let input_stream = input
@@ -198,16 +213,19 @@ impl AVAssetReaderDecoder {
                         pixel_to_pixel_format(decoder.format()),
                         decoder.width(),
                         decoder.height(),
+                        decoder.color_space(),
                     )
                 };
 
                 Ok((
                     get_reader_track_output(&path, 0.0, &handle, pixel_format)?,
                     pixel_format,
+                    (width, height),
+                    color_space,
                 ))
             };
 
-            let ((mut track_output, mut reader), pixel_format) = match init() {
+            let ((mut track_output, mut reader), pixel_format, size, color_space) = match init() {
                 Ok(v) => {
                     ready_tx.send(Ok(())).ok();
                     v
@@ -218,9 +236,16 @@ impl AVAssetReaderDecoder {
                 }
             };
 
+            let source = source_id(&path);
+
             // let black_frame = LazyCell::new(|| Arc::new(vec![0; (width * height * 4) as usize]));
 
-            let mut cache = BTreeMap::<u32, CachedFrame>::new();
+            // frames are stored with the frame the reader was started at
+            let mut cache = BTreeMap::<u32, (u32, CachedFrame)>::new();
+            let mut gop_cache = GopCache::<CachedFrame>::new(GOP_CACHE_BYTES);
+            let mut reader_start = 0;
+            // set when a frame came from the GOP cache, as the reader is no longer near it
+            let mut seek_needed = false;
 
             let mut last_active_frame = None::<u32>;
 
@@ -232,11 +257,19 @@ impl AVAssetReaderDecoder {
                     VideoDecoderMessage::GetFrame(requested_time, sender) => {
                         let requested_frame = (requested_time * fps as f32).floor() as u32;
 
-                        let mut sender = if let Some(cached) = cache.get_mut(&requested_frame) {
-                            let data = cached.process();
+                        let mut sender = if let Some((_, cached)) = cache.get_mut(&requested_frame)
+                        {
+                            let data = cached.process(color_space);
+
+                            sender.send(data.clone()).ok();
+                            last_sent_frame = Some((requested_frame, data));
+                            continue;
+                        } else if let Some(cached) = gop_cache.get_mut(requested_frame) {
+                            let data = cached.process(color_space);
 
                             sender.send(data.clone()).ok();
                             last_sent_frame = Some((requested_frame, data));
+                            seek_needed = true;
                             continue;
                         } else {
                             Some(sender)
@@ -246,6 +279,7 @@ impl AVAssetReaderDecoder {
                         let cache_max = requested_frame + FRAME_CACHE_SIZE as u32 / 2;
 
                         if requested_frame == 0
+                            || seek_needed
                             || last_sent_frame
                                 .as_ref()
                                 .map(|last| {
@@ -264,6 +298,8 @@ impl AVAssetReaderDecoder {
                             )
                             .unwrap();
                             last_decoded_frame = None;
+                            reader_start = requested_frame;
+                            seek_needed = false;
                         }
 
                         last_active_frame = Some(requested_frame);
@@ -296,7 +332,7 @@ impl AVAssetReaderDecoder {
                             {
                                 let (frame_number, frame) = last_decoded_frame;
 
-                                let data = frame.process();
+                                let data = frame.process(color_space);
                                 last_sent_frame = Some((*frame_number, data.clone()));
                                 sender.send(data).ok();
                             }
@@ -309,7 +345,8 @@ impl AVAssetReaderDecoder {
                             if !too_small_for_cache_bounds {
                                 if current_frame == requested_frame {
                                     if let Some(sender) = sender.take() {
-                                        let data = cache_frame.process();
+                                        let data = cache_frame.process(color_space);
+                                        share_frame(source, current_frame, &data, size);
                                         last_sent_frame = Some((current_frame, data.clone()));
                                         sender.send(data).ok();
 
@@ -334,13 +371,20 @@ impl AVAssetReaderDecoder {
                                             }
                                         };
 
-                                        cache.remove(&frame);
+                                        if let Some((start, evicted)) = cache.remove(&frame) {
+                                            let size = evicted.size();
+                                            gop_cache.insert(start, frame, evicted, size);
+                                        }
                                     } else {
-                                        cache.clear()
+                                        for (frame, (start, evicted)) in std::mem::take(&mut cache)
+                                        {
+                                            let size = evicted.size();
+                                            gop_cache.insert(start, frame, evicted, size);
+                                        }
                                     }
                                 }
 
-                                cache.insert(current_frame, cache_frame.clone());
+                                cache.insert(current_frame, (reader_start, cache_frame.clone()));
                             }
 
                             if current_frame > requested_frame && sender.is_some() {
@@ -350,7 +394,7 @@ impl AVAssetReaderDecoder {
                                 {
                                     sender.send(last_sent_frame.1.clone()).ok();
                                 } else if let Some(sender) = sender.take() {
-                                    sender.send(cache_frame.process()).ok();
+                                    sender.send(cache_frame.process(color_space)).ok();
                                 }
                             }
 
@@ -380,6 +424,8 @@ fn pixel_to_pixel_format(pixel: format::Pixel) -> cv::PixelFormat {
         format::Pixel::NV12 => cv::PixelFormat::_420V,
         // this is intentional, it works and is faster /shrug
         format::Pixel::YUV420P => cv::PixelFormat::_420V,
+        // 10 bit HDR captures are read as 8 bit, which is all the renderer works in
+        format::Pixel::P010LE | format::Pixel::YUV420P10LE => cv::PixelFormat::_420V,
         format::Pixel::RGBA => cv::PixelFormat::_32_RGBA,
         _ => todo!(),
     }
@@ -1,38 +1,42 @@
 use std::{
     cell::LazyCell,
     collections::BTreeMap,
-    path::PathBuf,
-    sync::{mpsc, Arc},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, OnceLock},
 };
 
 use ffmpeg::{
     codec::{self, Capabilities},
-    format, frame, rescale, software, Codec, Rescale,
+    format, frame, rescale, software, Codec, Rational, Rescale,
 };
 use ffmpeg_hw_device::{CodecContextExt, CodecExt, HwDevice};
 use ffmpeg_sys_next::{avcodec_find_decoder, AVHWDeviceType};
 use log::debug;
 use tokio::sync::oneshot;
 
-use super::{pts_to_frame, DecodedFrame, VideoDecoderMessage, FRAME_CACHE_SIZE};
+use crate::{color::set_source_colorspace, frame_cache::source_id};
+
+use super::{
+    pts_to_frame, share_frame, DecodedFrame, GopCache, KeyframeIndex, VideoDecoderMessage,
+    DECODER_POOL_SIZE, FRAME_CACHE_SIZE, GOP_CACHE_BYTES,
+};
 
 #[derive(Clone)]
 struct CachedFrame {
     data: CachedFrameData,
+    // the keyframe decoding of this frame started from
+    gop: u32,
 }
 
 impl CachedFrame {
-    fn process(&mut self, decoder: &codec::decoder::Video) -> Arc<Vec<u8>> {
+    fn process(&mut self, size: (u32, u32)) -> Arc<Vec<u8>> {
         match &mut self.data {
             CachedFrameData::Raw(frame) => {
                 let rgb_frame = if frame.format() != format::Pixel::RGBA {
                     // Reinitialize the scaler with the new input format
-                    let mut scaler = software::converter(
-                        (decoder.width(), decoder.height()),
-                        frame.format(),
-                        format::Pixel::RGBA,
-                    )
-                    .unwrap();
+                    let mut scaler =
+                        software::converter(size, frame.format(), format::Pixel::RGBA).unwrap();
+                    set_source_colorspace(&mut scaler, frame);
 
                     let mut rgb_frame = frame::Video::empty();
                     scaler.run(&frame, &mut rgb_frame).unwrap();
@@ -72,103 +76,205 @@ enum CachedFrameData {
     Processed(Arc<Vec<u8>>),
 }
 
-pub struct FfmpegDecoder;
+// One demuxer and decoder reading through the file. Each is parked wherever its last
+// request left it, so going back to any of those places doesn't need a seek.
+struct DecoderCursor {
+    input: format::context::Input,
+    decoder: codec::decoder::Video,
+    hw_device: Option<HwDevice>,
+    stream_index: usize,
+    time_base: Rational,
+    start_offset: i64,
+    last_decoded_frame: Option<u32>,
+    // the frame decoding resumes from after a seek, until a frame has been decoded
+    seeked_to: u32,
+    // the keyframe the frames being decoded started from
+    current_gop: Option<u32>,
+    last_used: u64,
+}
 
-impl FfmpegDecoder {
-    pub fn spawn(
-        name: &'static str,
-        path: PathBuf,
-        fps: u32,
-        rx: mpsc::Receiver<VideoDecoderMessage>,
-        ready_tx: oneshot::Sender<Result<(), String>>,
-    ) {
-        std::thread::spawn(move || {
-            let mut input = ffmpeg::format::input(&path).unwrap();
+impl DecoderCursor {
+    fn open(path: &Path) -> Result<Self, String> {
+        let input = ffmpeg::format::input(path).map_err(|e| e.to_string())?;
 
-            let input_stream = input
-                .streams()
-                .best(ffmpeg::media::Type::Video)
-                .ok_or("Could not find a video stream")
-                .unwrap();
+        let input_stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or("Could not find a video stream")?;
 
-            let decoder_codec =
-                find_decoder(&input, &input_stream, input_stream.parameters().id()).unwrap();
+        let decoder_codec = find_decoder(&input, &input_stream, input_stream.parameters().id())
+            .ok_or("Could not find a decoder")?;
 
-            let mut context = codec::context::Context::new_with_codec(decoder_codec);
-            context.set_parameters(input_stream.parameters()).unwrap();
+        let mut context = codec::context::Context::new_with_codec(decoder_codec);
+        context
+            .set_parameters(input_stream.parameters())
+            .map_err(|e| e.to_string())?;
 
-            let input_stream_index = input_stream.index();
-            let time_base = input_stream.time_base();
-            let frame_rate = input_stream.rate();
+        let stream_index = input_stream.index();
+        let time_base = input_stream.time_base();
+        let start_offset = input_stream.start_time();
 
-            // Create a decoder for the video stream
-            let mut decoder = context.decoder().video().unwrap();
+        // Create a decoder for the video stream
+        let mut decoder = context.decoder().video().map_err(|e| e.to_string())?;
 
-            {
-                use codec::threading::{Config, Type};
+        {
+            use codec::threading::{Config, Type};
 
-                let capabilities = decoder_codec.capabilities();
+            let capabilities = decoder_codec.capabilities();
 
-                if capabilities.intersects(Capabilities::FRAME_THREADS) {
-                    decoder.set_threading(Config::kind(Type::Frame));
-                } else if capabilities.intersects(Capabilities::SLICE_THREADS) {
-                    decoder.set_threading(Config::kind(Type::Slice));
-                } else {
-                    decoder.set_threading(Config::count(1));
-                }
+            if capabilities.intersects(Capabilities::FRAME_THREADS) {
+                decoder.set_threading(Config::kind(Type::Frame));
+            } else if capabilities.intersects(Capabilities::SLICE_THREADS) {
+                decoder.set_threading(Config::kind(Type::Slice));
+            } else {
+                decoder.set_threading(Config::count(1));
             }
+        }
 
-            let width = decoder.width();
-            let height = decoder.height();
+        let width = decoder.width();
+        let height = decoder.height();
 
-            let exceeds_common_hw_limits = width > 4096 || height > 4096;
+        let exceeds_common_hw_limits = width > 4096 || height > 4096;
 
-            let mut hw_device = if exceeds_common_hw_limits {
-                debug!("Video dimensions {width}x{height} exceed common hardware decoder limits (4096x4096), not using hardware acceleration");
-                None
+        let hw_device = if exceeds_common_hw_limits {
+            debug!("Video dimensions {width}x{height} exceed common hardware decoder limits (4096x4096), not using hardware acceleration");
+            None
+        } else {
+            let hw_device_types = if cfg!(target_os = "macos") {
+                [AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX].as_slice()
             } else {
-                let hw_device_types = if cfg!(target_os = "macos") {
-                    [AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX].as_slice()
-                } else {
-                    [
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_CUDA,
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_D3D12VA,
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_D3D11VA,
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_VULKAN,
-                        AVHWDeviceType::AV_HWDEVICE_TYPE_DXVA2,
-                    ]
-                    .as_slice()
-                };
-
-                hw_device_types
-                    .iter()
-                    .find_map(|&typ| decoder.try_use_hw_device(typ).ok())
+                [
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_CUDA,
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_D3D12VA,
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_D3D11VA,
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_VULKAN,
+                    AVHWDeviceType::AV_HWDEVICE_TYPE_DXVA2,
+                ]
+                .as_slice()
             };
-            let hw_device = hw_device.as_ref();
 
-            if hw_device.is_none() && !exceeds_common_hw_limits {
-                debug!("No hardware acceleration available, falling back to software decoding");
+            hw_device_types
+                .iter()
+                .find_map(|&typ| decoder.try_use_hw_device(typ).ok())
+        };
+
+        if hw_device.is_none() && !exceeds_common_hw_limits {
+            debug!("No hardware acceleration available, falling back to software decoding");
+        }
+
+        Ok(Self {
+            input,
+            decoder,
+            hw_device,
+            stream_index,
+            time_base,
+            start_offset,
+            last_decoded_frame: None,
+            seeked_to: 0,
+            current_gop: None,
+            last_used: 0,
+        })
+    }
+
+    // the first frame this cursor will decode next
+    fn next_frame(&self) -> u32 {
+        self.last_decoded_frame
+            .map(|f| f + 1)
+            .unwrap_or(self.seeked_to)
+    }
+
+    fn seek(&mut self, frame_number: u32, fps: u32) {
+        // just past the frame's start, so rounding can't land on the keyframe before it
+        let timestamp_us = ((frame_number as f64 + 0.5) / fps as f64 * 1_000_000.0) as i64;
+        let position = timestamp_us.rescale((1, 1_000_000), rescale::TIME_BASE)
+            + self
+                .start_offset
+                .rescale(self.time_base, rescale::TIME_BASE);
+
+        debug!("seeking to {}", position);
+        self.decoder.flush();
+        self.input.seek(position, ..position).ok();
+        self.last_decoded_frame = None;
+        self.seeked_to = frame_number;
+        self.current_gop = None;
+    }
+}
+
+// Reads where the keyframes are without decoding anything. Demuxing the whole file takes a
+// moment, so this runs alongside the decoder, which seeks by distance until it's ready.
+fn build_keyframe_index(path: &Path, fps: u32) -> Result<KeyframeIndex, ffmpeg::Error> {
+    let mut input = ffmpeg::format::input(path)?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let stream_index = stream.index();
+    let time_base = stream.time_base();
+    let start_offset = stream.start_time();
+
+    Ok(KeyframeIndex::new(
+        input
+            .packets()
+            .filter(|(stream, packet)| stream.index() == stream_index && packet.is_key())
+            .filter_map(|(_, packet)| packet.pts())
+            .map(|pts| pts_to_frame(pts - start_offset, time_base, fps)),
+    ))
+}
+
+pub struct FfmpegDecoder;
+
+impl FfmpegDecoder {
+    pub fn spawn(
+        name: &'static str,
+        path: PathBuf,
+        fps: u32,
+        rx: mpsc::Receiver<VideoDecoderMessage>,
+        ready_tx: oneshot::Sender<Result<(), String>>,
+    ) {
+        let keyframes = Arc::new(OnceLock::new());
+
+        std::thread::spawn({
+            let path = path.clone();
+            let keyframes = keyframes.clone();
+            move || match build_keyframe_index(&path, fps) {
+                Ok(index) => {
+                    keyframes.set(index).ok();
+                }
+                Err(e) => debug!("{name}: no keyframe index: {e}"),
             }
+        });
 
-            let mut temp_frame = ffmpeg::frame::Video::empty();
+        std::thread::spawn(move || {
+            let mut cursors = match DecoderCursor::open(&path) {
+                Ok(cursor) => vec![cursor],
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let mut uses = 0;
 
-            // let mut packets = input.packets().peekable();
+            let width = cursors[0].decoder.width();
+            let height = cursors[0].decoder.height();
+            let size = (width, height);
+            let source = source_id(&path);
+
+            let mut temp_frame = ffmpeg::frame::Video::empty();
 
             let black_frame = LazyCell::new(|| Arc::new(vec![0; (width * height * 4) as usize]));
 
             let mut cache = BTreeMap::<u32, CachedFrame>::new();
+            let mut gop_cache = GopCache::<CachedFrame>::new(GOP_CACHE_BYTES);
+            let frame_bytes = (width * height * 4) as usize;
             // active frame is a frame that triggered decode.
             // frames that are within render_more_margin of this frame won't trigger decode.
             let mut last_active_frame = None::<u32>;
 
-            let mut last_decoded_frame = None::<u32>;
             let mut last_sent_frame = None::<(u32, DecodedFrame)>;
 
             let mut peekable_requests = PeekableReceiver { rx, peeked: None };
 
-            let mut packets = input.packets().peekable();
-
             let _ = ready_tx.send(Ok(()));
 
             while let Ok(r) = peekable_requests.recv() {
@@ -179,7 +285,13 @@ impl FfmpegDecoder {
                         // continue;
 
                         let mut sender = if let Some(cached) = cache.get_mut(&requested_frame) {
-                            let data = cached.process(&decoder);
+                            let data = cached.process(size);
+
+                            sender.send(data.clone()).ok();
+                            last_sent_frame = Some((requested_frame, data));
+                            continue;
+                        } else if let Some(cached) = gop_cache.get_mut(requested_frame) {
+                            let data = cached.process(size);
 
                             sender.send(data.clone()).ok();
                             last_sent_frame = Some((requested_frame, data));
@@ -191,28 +303,59 @@ impl FfmpegDecoder {
                         let cache_min = requested_frame.saturating_sub(FRAME_CACHE_SIZE as u32 / 2);
                         let cache_max = requested_frame + FRAME_CACHE_SIZE as u32 / 2;
 
-                        if requested_frame == 0
-                            || last_sent_frame
-                                .as_ref()
-                                .map(|last| {
-                                    requested_frame < last.0 ||
-                                // seek forward for big jumps. this threshold is arbitrary but should be derived from i-frames in future
-                                requested_frame - last.0 > FRAME_CACHE_SIZE as u32
-                                })
-                                .unwrap_or(true)
-                        {
-                            let timestamp_us =
-                                ((requested_frame as f32 / frame_rate.numerator() as f32)
-                                    * 1_000_000.0) as i64;
-                            let position = timestamp_us.rescale((1, 1_000_000), rescale::TIME_BASE);
+                        // carry on with whichever cursor can get to the frame soonest, otherwise
+                        // seek one to the keyframe before it, opening another while the pool
+                        // isn't full
+                        let keyframes = keyframes.get();
+                        let can_decode_forward = |cursor: &DecoderCursor| {
+                            let next = cursor.next_frame();
+                            match keyframes {
+                                Some(keyframes) => {
+                                    keyframes.should_decode_forward(next, requested_frame)
+                                }
+                                // seek forward for big jumps. this threshold is arbitrary
+                                None => {
+                                    next <= requested_frame
+                                        && requested_frame - next <= FRAME_CACHE_SIZE as u32
+                                }
+                            }
+                        };
 
-                            debug!("seeking to {}", position);
-                            decoder.flush();
-                            input.seek(position, ..position).unwrap();
-                            last_decoded_frame = None;
+                        let cursor_i = match cursors
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, c)| can_decode_forward(c))
+                            .max_by_key(|(_, c)| c.next_frame())
+                            .map(|(i, _)| i)
+                        {
+                            Some(i) => i,
+                            None => {
+                                let i = if cursors.len() < DECODER_POOL_SIZE {
+                                    match DecoderCursor::open(&path) {
+                                        Ok(cursor) => {
+                                            cursors.push(cursor);
+                                            cursors.len() - 1
+                                        }
+                                        Err(e) => {
+                                            debug!("{name}: failed to open another decoder: {e}");
+                                            least_recently_used(&cursors)
+                                        }
+                                    }
+                                } else {
+                                    least_recently_used(&cursors)
+                                };
+
+                                let seek_frame = keyframes
+                                    .map(|k| k.keyframe_for(requested_frame))
+                                    .unwrap_or(requested_frame);
+                                cursors[i].seek(seek_frame, fps);
+                                i
+                            }
+                        };
 
-                            packets = input.packets().peekable();
-                        }
+                        uses += 1;
+                        let cursor = &mut cursors[cursor_i];
+                        cursor.last_used = uses;
 
                         last_active_frame = Some(requested_frame);
 
@@ -220,7 +363,12 @@ impl FfmpegDecoder {
                             if peekable_requests.peek().is_some() {
                                 break;
                             }
-                            let Some((stream, packet)) = packets.next() else {
+                            let Some((stream_index, packet)) = cursor
+                                .input
+                                .packets()
+                                .next()
+                                .map(|(stream, packet)| (stream.index(), packet))
+                            else {
                                 // handles the case where the cache doesn't contain a frame so we fallback to the previously sent one
                                 if let Some(last_sent_frame) = &last_sent_frame {
                                     if last_sent_frame.0 < requested_frame {
@@ -232,22 +380,21 @@ impl FfmpegDecoder {
                                 break;
                             };
 
-                            if stream.index() == input_stream_index {
-                                let start_offset = stream.start_time();
-
-                                let _ = decoder.send_packet(&packet);
+                            if stream_index == cursor.stream_index {
+                                let _ = cursor.decoder.send_packet(&packet);
 
                                 let mut exit = false;
 
-                                while decoder.receive_frame(&mut temp_frame).is_ok() {
+                                while cursor.decoder.receive_frame(&mut temp_frame).is_ok() {
                                     let current_frame = pts_to_frame(
-                                        temp_frame.pts().unwrap() - start_offset,
-                                        time_base,
+                                        temp_frame.pts().unwrap() - cursor.start_offset,
+                                        cursor.time_base,
                                         fps,
                                     );
 
                                     // Handles frame skips. requested_frame == last_decoded_frame should be handled by the frame cache.
-                                    if let Some((last_decoded_frame, sender)) = last_decoded_frame
+                                    if let Some((last_decoded_frame, sender)) = cursor
+                                        .last_decoded_frame
                                         .filter(|last_decoded_frame| {
                                             requested_frame > *last_decoded_frame
                                                 && requested_frame < current_frame
@@ -256,19 +403,27 @@ impl FfmpegDecoder {
                                     {
                                         let data = cache
                                             .get_mut(&last_decoded_frame)
-                                            .map(|f| f.process(&decoder))
+                                            .map(|f| f.process(size))
                                             .unwrap_or_else(|| black_frame.clone());
 
                                         last_sent_frame = Some((last_decoded_frame, data.clone()));
                                         sender.send(data).ok();
                                     }
 
-                                    last_decoded_frame = Some(current_frame);
+                                    cursor.last_decoded_frame = Some(current_frame);
+
+                                    // a seek always lands on a keyframe
+                                    if temp_frame.is_key() || cursor.current_gop.is_none() {
+                                        cursor.current_gop = Some(current_frame);
+                                    }
+                                    let gop = cursor.current_gop.unwrap_or(current_frame);
 
                                     let exceeds_cache_bounds = current_frame > cache_max;
                                     let too_small_for_cache_bounds = current_frame < cache_min;
 
-                                    let frame = hw_device
+                                    let frame = cursor
+                                        .hw_device
+                                        .as_ref()
                                         .and_then(|d| d.get_hwframe(&temp_frame))
                                         .unwrap_or(std::mem::replace(
                                             &mut temp_frame,
@@ -278,11 +433,13 @@ impl FfmpegDecoder {
                                     if !too_small_for_cache_bounds {
                                         let mut cache_frame = CachedFrame {
                                             data: CachedFrameData::Raw(frame),
+                                            gop,
                                         };
 
                                         if current_frame == requested_frame {
                                             if let Some(sender) = sender.take() {
-                                                let data = cache_frame.process(&decoder);
+                                                let data = cache_frame.process(size);
+                                                share_frame(source, current_frame, &data, size);
                                                 last_sent_frame =
                                                     Some((current_frame, data.clone()));
                                                 sender.send(data).ok();
@@ -309,9 +466,23 @@ impl FfmpegDecoder {
                                                     }
                                                 };
 
-                                                cache.remove(&frame);
+                                                if let Some(evicted) = cache.remove(&frame) {
+                                                    gop_cache.insert(
+                                                        evicted.gop,
+                                                        frame,
+                                                        evicted,
+                                                        frame_bytes,
+                                                    );
+                                                }
                                             } else {
-                                                cache.clear()
+                                                for (frame, evicted) in std::mem::take(&mut cache) {
+                                                    gop_cache.insert(
+                                                        evicted.gop,
+                                                        frame,
+                                                        evicted,
+                                                        frame_bytes,
+                                                    );
+                                                }
                                             }
                                         }
 
@@ -339,6 +510,15 @@ impl FfmpegDecoder {
     }
 }
 
+fn least_recently_used(cursors: &[DecoderCursor]) -> usize {
+    cursors
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| c.last_used)
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
 pub fn find_decoder(
     s: &format::context::Input,
     st: &format::stream::Stream,
@@ -1,10 +1,13 @@
 use ::ffmpeg::Rational;
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
     sync::{mpsc, Arc},
 };
 use tokio::sync::oneshot;
 
+use crate::frame_cache::{global_frame_cache, source_id, CachedFrame, FrameCacheKey};
+
 #[cfg(target_os = "macos")]
 mod avassetreader;
 mod ffmpeg;
@@ -21,14 +24,151 @@ pub fn pts_to_frame(pts: i64, time_base: Rational, fps: u32) -> u32 {
 }
 
 pub const FRAME_CACHE_SIZE: usize = 100;
+// how much each decoder keeps of the GOPs it decoded before
+pub const GOP_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+// how many decoders each video keeps parked at different places in the file
+pub const DECODER_POOL_SIZE: usize = 3;
+// roughly what a seek costs, in frames decoded
+pub const SEEK_COST_FRAMES: u32 = 10;
+
+// The frames of a video that decoding can start from, in order
+#[derive(Debug, Default)]
+pub struct KeyframeIndex(Vec<u32>);
+
+impl KeyframeIndex {
+    pub fn new(frames: impl IntoIterator<Item = u32>) -> Self {
+        let mut frames = frames.into_iter().collect::<Vec<_>>();
+        frames.sort_unstable();
+        frames.dedup();
+        Self(frames)
+    }
+
+    // The keyframe decoding has to start from to reach `frame_number`
+    pub fn keyframe_for(&self, frame_number: u32) -> u32 {
+        match self.0.binary_search(&frame_number) {
+            Ok(i) => self.0[i],
+            Err(0) => 0,
+            Err(i) => self.0[i - 1],
+        }
+    }
+
+    // Whether a decoder about to produce `next` should carry on to `frame_number` rather
+    // than seek to the keyframe before it
+    pub fn should_decode_forward(&self, next: u32, frame_number: u32) -> bool {
+        next <= frame_number && next + SEEK_COST_FRAMES >= self.keyframe_for(frame_number)
+    }
+}
+
+struct Gop<T> {
+    frames: BTreeMap<u32, T>,
+    bytes: usize,
+    last_used: u64,
+}
+
+// Frames that have left a decoder's frame cache, grouped by the frame their decoding started
+// from, like a GOP's keyframe. Scrubbing back to somewhere decoded recently is served from
+// here rather than seeking and decoding again. Whole groups are evicted once over
+// `max_bytes`, least recently used first.
+pub struct GopCache<T> {
+    gops: BTreeMap<u32, Gop<T>>,
+    max_bytes: usize,
+    bytes: usize,
+    uses: u64,
+}
+
+impl<T> GopCache<T> {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            gops: BTreeMap::new(),
+            max_bytes,
+            bytes: 0,
+            uses: 0,
+        }
+    }
+
+    pub fn insert(&mut self, start: u32, frame_number: u32, frame: T, bytes: usize) {
+        self.uses += 1;
+
+        let gop = self.gops.entry(start).or_insert_with(|| Gop {
+            frames: BTreeMap::new(),
+            bytes: 0,
+            last_used: 0,
+        });
+        gop.last_used = self.uses;
+        if gop.frames.insert(frame_number, frame).is_none() {
+            gop.bytes += bytes;
+            self.bytes += bytes;
+        }
+
+        // the group being filled stays, even if it's over the budget alone
+        while self.bytes > self.max_bytes {
+            let Some(oldest) = self
+                .gops
+                .iter()
+                .filter(|(s, _)| **s != start)
+                .min_by_key(|(_, gop)| gop.last_used)
+                .map(|(s, _)| *s)
+            else {
+                break;
+            };
+
+            if let Some(gop) = self.gops.remove(&oldest) {
+                self.bytes -= gop.bytes;
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, frame_number: u32) -> Option<&mut T> {
+        self.uses += 1;
+
+        // groups can overlap when decoding started partway into another's frames
+        let uses = self.uses;
+        self.gops
+            .range_mut(..=frame_number)
+            .rev()
+            .find(|(_, gop)| gop.frames.contains_key(&frame_number))
+            .and_then(|(_, gop)| {
+                gop.last_used = uses;
+                gop.frames.get_mut(&frame_number)
+            })
+    }
+}
 
 #[derive(Clone)]
 pub struct AsyncVideoDecoderHandle {
     sender: mpsc::Sender<VideoDecoderMessage>,
+    source: u64,
+    fps: u32,
+}
+
+// Puts a frame a decoder decoded into the shared cache, for any decoder of the same file
+pub(crate) fn share_frame(source: u64, frame_number: u32, data: &DecodedFrame, size: (u32, u32)) {
+    global_frame_cache().lock().unwrap().insert(
+        FrameCacheKey::Decoded {
+            source,
+            frame_number,
+        },
+        CachedFrame {
+            data: data.clone(),
+            width: size.0,
+            height: size.1,
+            stride: size.0 * 4,
+        },
+    );
 }
 
 impl AsyncVideoDecoderHandle {
     pub async fn get_frame(&self, time: f32) -> Option<DecodedFrame> {
+        // another decoder of this file, like the editor's while exporting, may have it already
+        let key = FrameCacheKey::Decoded {
+            source: self.source,
+            frame_number: (time * self.fps as f32).floor() as u32,
+        };
+        if let Some(frame) = global_frame_cache().lock().unwrap().get(&key) {
+            return Some(frame.data);
+        }
+
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.sender
             .send(VideoDecoderMessage::GetFrame(time, tx))
@@ -45,7 +185,11 @@ pub async fn spawn_decoder(
     let (ready_tx, ready_rx) = oneshot::channel::<Result<(), String>>();
     let (tx, rx) = mpsc::channel();
 
-    let handle = AsyncVideoDecoderHandle { sender: tx };
+    let handle = AsyncVideoDecoderHandle {
+        sender: tx,
+        source: source_id(&path),
+        fps,
+    };
 
     if cfg!(target_os = "macos") {
         #[cfg(target_os = "macos")]
@@ -56,3 +200,68 @@ pub async fn spawn_decoder(
 
     ready_rx.await.map_err(|e| e.to_string())?.map(|()| handle)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_the_keyframe_before_a_frame() {
+        let index = KeyframeIndex::new([60, 0, 30]);
+
+        assert_eq!(index.keyframe_for(0), 0);
+        assert_eq!(index.keyframe_for(29), 0);
+        assert_eq!(index.keyframe_for(30), 30);
+        assert_eq!(index.keyframe_for(100), 60);
+    }
+
+    #[test]
+    fn decodes_forward_within_a_gop_and_just_past_it() {
+        let index = KeyframeIndex::new([0, 60, 120]);
+
+        assert!(index.should_decode_forward(10, 50));
+        // crossing into the next GOP is cheaper than seeking to its keyframe
+        assert!(index.should_decode_forward(55, 61));
+        assert!(!index.should_decode_forward(10, 100));
+        assert!(!index.should_decode_forward(50, 40));
+    }
+
+    #[test]
+    fn finds_frames_in_their_gop() {
+        let mut cache = GopCache::new(1000);
+        cache.insert(0, 0, "a", 1);
+        cache.insert(0, 1, "b", 1);
+        cache.insert(30, 31, "c", 1);
+
+        assert_eq!(cache.get_mut(1), Some(&mut "b"));
+        assert_eq!(cache.get_mut(31), Some(&mut "c"));
+        assert_eq!(cache.get_mut(30), None);
+        assert_eq!(cache.get_mut(2), None);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_gop() {
+        let mut cache = GopCache::new(4);
+        cache.insert(0, 0, 0, 2);
+        cache.insert(30, 30, 30, 2);
+        // using the first makes the second the oldest
+        cache.get_mut(0);
+        cache.insert(60, 60, 60, 2);
+
+        assert_eq!(cache.get_mut(0), Some(&mut 0));
+        assert_eq!(cache.get_mut(30), None);
+        assert_eq!(cache.get_mut(60), Some(&mut 60));
+    }
+
+    #[test]
+    fn keeps_the_gop_being_filled() {
+        let mut cache = GopCache::new(2);
+        cache.insert(0, 0, 0, 2);
+        cache.insert(30, 30, 30, 2);
+        cache.insert(30, 31, 31, 2);
+
+        assert_eq!(cache.get_mut(0), None);
+        assert_eq!(cache.get_mut(30), Some(&mut 30));
+        assert_eq!(cache.get_mut(31), Some(&mut 31));
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use cap_project::{CursorEvents, XY};
+
+// Other displays are drawn into the main display's frame, scaled to fit and centered.
+// Returns where a display of `size` ends up, as an offset and size in fractions of the
+// main display.
+pub fn fit_display(size: XY<u32>, main: XY<u32>) -> (XY<f64>, XY<f64>) {
+    let size = size.map(|v| v as f64);
+    let main = main.map(|v| v as f64);
+
+    let scale = (main.x / size.x).min(main.y / size.y);
+    let fitted = size * scale / main;
+
+    ((XY::new(1.0, 1.0) - fitted) / 2.0, fitted)
+}
+
+// A recording segment's cursor events split into one track per display, with positions
+// mapped to where each display is drawn. Kept behind `Arc`s since the smoothed cursor
+// tracks are cached per event list.
+#[derive(Clone)]
+pub struct SegmentCursors(Vec<Arc<CursorEvents>>);
+
+impl SegmentCursors {
+    // `display_sizes` are in track order, starting with the main display
+    pub fn new(events: &CursorEvents, display_sizes: &[XY<u32>]) -> Self {
+        let Some(&main) = display_sizes.first() else {
+            return Self::default();
+        };
+
+        Self(
+            display_sizes
+                .iter()
+                .enumerate()
+                .map(|(i, &size)| {
+                    let mut events = events.for_display(i as u32);
+
+                    if size != main {
+                        let (offset, fitted) = fit_display(size, main);
+                        let fit = |x: &mut f64, y: &mut f64| {
+                            *x = offset.x + *x * fitted.x;
+                            *y = offset.y + *y * fitted.y;
+                        };
+
+                        events
+                            .moves
+                            .iter_mut()
+                            .for_each(|m| fit(&mut m.x, &mut m.y));
+                        events
+                            .clicks
+                            .iter_mut()
+                            .for_each(|c| fit(&mut c.x, &mut c.y));
+                    }
+
+                    Arc::new(events)
+                })
+                .collect(),
+        )
+    }
+
+    // Falls back to the main display for tracks the recording doesn't have
+    pub fn get(&self, display: u32) -> &Arc<CursorEvents> {
+        self.0.get(display as usize).unwrap_or(&self.0[0])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<CursorEvents>> {
+        self.0.iter()
+    }
+}
+
+// A recording of just the main display
+impl From<CursorEvents> for SegmentCursors {
+    fn from(events: CursorEvents) -> Self {
+        Self(vec![Arc::new(events)])
+    }
+}
+
+impl Default for SegmentCursors {
+    fn default() -> Self {
+        Self(vec![Default::default()])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cap_project::CursorMoveEvent;
+
+    use super::*;
+
+    #[test]
+    fn fits_narrower_display() {
+        let (offset, size) = fit_display(XY::new(1000, 1000), XY::new(2000, 1000));
+
+        assert_eq!((offset.x, offset.y), (0.25, 0.0));
+        assert_eq!((size.x, size.y), (0.5, 1.0));
+    }
+
+    #[test]
+    fn maps_cursor_into_fitted_display() {
+        let mv = |display| CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            process_time_ms: 0.0,
+            unix_time_ms: 0.0,
+            x: 1.0,
+            y: 0.5,
+            display,
+        };
+        let events = CursorEvents {
+            moves: vec![mv(0), mv(1)],
+            ..Default::default()
+        };
+
+        let cursors = SegmentCursors::new(&events, &[XY::new(2000, 1000), XY::new(1000, 1000)]);
+
+        assert_eq!(cursors.get(0).moves[0].x, 1.0);
+        assert_eq!(cursors.get(1).moves[0].x, 0.75);
+        assert_eq!(cursors.get(1).moves[0].y, 0.5);
+        assert_eq!(cursors.get(5).moves[0].x, 1.0);
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// roughly 4 seconds of 1080p frames at 30fps
+pub const DEFAULT_FRAME_CACHE_BYTES: usize = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameCacheKey {
+    // a frame of a video file as it was decoded
+    Decoded {
+        source: u64,
+        frame_number: u32,
+    },
+    // a frame of a project with everything drawn onto it
+    Composited {
+        project: u64,
+        revision: u64,
+        frame_number: u32,
+        fps: u32,
+        resolution_base: (u32, u32),
+    },
+}
+
+impl FrameCacheKey {
+    // decoding again is cheaper than compositing again, so decoded frames go first
+    fn is_decoded(&self) -> bool {
+        matches!(self, Self::Decoded { .. })
+    }
+}
+
+// Identifies a video file or project across decoders and renderers
+pub fn source_id(path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::default();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Clone)]
+pub struct CachedFrame {
+    pub data: Arc<Vec<u8>>,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum EvictionPolicy {
+    // evicts whichever frame went unused the longest
+    #[default]
+    Lru,
+    // sweeps frames in the order they were added, sparing any used since the last sweep
+    Clock,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub insertions: u64,
+    pub evictions: u64,
+    pub bytes: usize,
+    pub frames: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FrameCacheEvent {
+    Hit(FrameCacheKey),
+    Miss(FrameCacheKey),
+    Insert(FrameCacheKey, usize),
+    Evict(FrameCacheKey, usize),
+}
+
+struct Entry {
+    frame: CachedFrame,
+    last_used: u64,
+    // for the clock policy, whether the frame was used since the hand last passed it
+    referenced: bool,
+}
+
+// Decoded and composited frames kept within a memory budget. Once over it, decoded frames
+// are evicted before composited ones, following the eviction policy within each kind.
+pub struct FrameCache {
+    entries: HashMap<FrameCacheKey, Entry>,
+    // the order frames were added in, decoded then composited, for the clock hand to sweep
+    clocks: [VecDeque<FrameCacheKey>; 2],
+    budget: usize,
+    policy: EvictionPolicy,
+    uses: u64,
+    stats: FrameCacheStats,
+    observer: Option<Box<dyn Fn(FrameCacheEvent) + Send + Sync>>,
+}
+
+impl Default for FrameCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FRAME_CACHE_BYTES, EvictionPolicy::default())
+    }
+}
+
+impl FrameCache {
+    pub fn new(budget: usize, policy: EvictionPolicy) -> Self {
+        Self {
+            entries: HashMap::new(),
+            clocks: Default::default(),
+            budget,
+            policy,
+            uses: 0,
+            stats: FrameCacheStats::default(),
+            observer: None,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict();
+    }
+
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
+    // Called with every hit, miss, insertion and eviction, for instrumentation
+    pub fn set_observer(&mut self, observer: impl Fn(FrameCacheEvent) + Send + Sync + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    pub fn stats(&self) -> FrameCacheStats {
+        self.stats
+    }
+
+    // Checks for a frame without counting it as a use
+    pub fn contains(&self, key: &FrameCacheKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn get(&mut self, key: &FrameCacheKey) -> Option<CachedFrame> {
+        self.uses += 1;
+
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.stats.misses += 1;
+            self.emit(FrameCacheEvent::Miss(*key));
+            return None;
+        };
+
+        entry.last_used = self.uses;
+        entry.referenced = true;
+        let frame = entry.frame.clone();
+
+        self.stats.hits += 1;
+        self.emit(FrameCacheEvent::Hit(*key));
+        Some(frame)
+    }
+
+    pub fn insert(&mut self, key: FrameCacheKey, frame: CachedFrame) {
+        if self.entries.contains_key(&key) {
+            return;
+        }
+
+        self.uses += 1;
+        let bytes = frame.data.len();
+
+        self.entries.insert(
+            key,
+            Entry {
+                frame,
+                last_used: self.uses,
+                referenced: false,
+            },
+        );
+        self.clocks[clock(&key)].push_back(key);
+
+        self.stats.insertions += 1;
+        self.stats.bytes += bytes;
+        self.stats.frames += 1;
+        self.emit(FrameCacheEvent::Insert(key, bytes));
+
+        self.evict();
+    }
+
+    // Drops every frame `keep` returns false for, without counting them as evictions
+    pub fn retain(&mut self, keep: impl Fn(&FrameCacheKey) -> bool) {
+        for clock in &mut self.clocks {
+            clock.retain(&keep);
+        }
+
+        let stats = &mut self.stats;
+        self.entries.retain(|key, entry| {
+            let keep = keep(key);
+            if !keep {
+                stats.bytes -= entry.frame.data.len();
+                stats.frames -= 1;
+            }
+            keep
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    fn evict(&mut self) {
+        while self.stats.bytes > self.budget {
+            let Some(key) = self.victim() else {
+                break;
+            };
+
+            let Some(entry) = self.entries.remove(&key) else {
+                continue;
+            };
+            let bytes = entry.frame.data.len();
+
+            self.stats.evictions += 1;
+            self.stats.bytes -= bytes;
+            self.stats.frames -= 1;
+            self.emit(FrameCacheEvent::Evict(key, bytes));
+        }
+    }
+
+    // Takes the next frame to evict off its clock
+    fn victim(&mut self) -> Option<FrameCacheKey> {
+        let clock = self.clocks.iter_mut().find(|c| !c.is_empty())?;
+
+        match self.policy {
+            EvictionPolicy::Lru => {
+                let i = clock
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, key)| self.entries.get(key).map_or(0, |e| e.last_used))
+                    .map(|(i, _)| i)?;
+                clock.remove(i)
+            }
+            EvictionPolicy::Clock => loop {
+                let key = clock.pop_front()?;
+                match self.entries.get_mut(&key) {
+                    Some(entry) if entry.referenced => {
+                        entry.referenced = false;
+                        clock.push_back(key);
+                    }
+                    _ => return Some(key),
+                }
+            },
+        }
+    }
+
+    fn emit(&self, event: FrameCacheEvent) {
+        if let Some(observer) = &self.observer {
+            observer(event);
+        }
+    }
+}
+
+fn clock(key: &FrameCacheKey) -> usize {
+    if key.is_decoded() {
+        0
+    } else {
+        1
+    }
+}
+
+// The cache shared by everything in the app that decodes or renders frames, so preview and
+// export can pick up each other's work
+pub fn global_frame_cache() -> Arc<Mutex<FrameCache>> {
+    static CACHE: OnceLock<Arc<Mutex<FrameCache>>> = OnceLock::new();
+
+    CACHE.get_or_init(Default::default).clone()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(bytes: usize) -> CachedFrame {
+        CachedFrame {
+            data: Arc::new(vec![0; bytes]),
+            width: 1,
+            height: 1,
+            stride: 4,
+        }
+    }
+
+    fn decoded(frame_number: u32) -> FrameCacheKey {
+        FrameCacheKey::Decoded {
+            source: 0,
+            frame_number,
+        }
+    }
+
+    fn composited(frame_number: u32) -> FrameCacheKey {
+        FrameCacheKey::Composited {
+            project: 0,
+            revision: 0,
+            frame_number,
+            fps: 30,
+            resolution_base: (1920, 1080),
+        }
+    }
+
+    #[test]
+    fn lru_evicts_the_least_recently_used() {
+        let mut cache = FrameCache::new(2, EvictionPolicy::Lru);
+        cache.insert(decoded(0), frame(1));
+        cache.insert(decoded(1), frame(1));
+        cache.get(&decoded(0));
+        cache.insert(decoded(2), frame(1));
+
+        assert!(cache.contains(&decoded(0)));
+        assert!(!cache.contains(&decoded(1)));
+        assert!(cache.contains(&decoded(2)));
+    }
+
+    #[test]
+    fn clock_spares_frames_used_since_the_last_sweep() {
+        let mut cache = FrameCache::new(3, EvictionPolicy::Clock);
+        cache.insert(decoded(0), frame(1));
+        cache.insert(decoded(1), frame(1));
+        cache.insert(decoded(2), frame(1));
+        cache.get(&decoded(0));
+        cache.insert(decoded(3), frame(1));
+
+        assert!(cache.contains(&decoded(0)));
+        assert!(!cache.contains(&decoded(1)));
+
+        // the second chance is used up
+        cache.insert(decoded(4), frame(1));
+        cache.insert(decoded(5), frame(1));
+        assert!(!cache.contains(&decoded(0)));
+    }
+
+    #[test]
+    fn evicts_decoded_frames_before_composited_ones() {
+        let mut cache = FrameCache::new(2, EvictionPolicy::Lru);
+        cache.insert(composited(0), frame(1));
+        cache.insert(decoded(0), frame(1));
+        cache.insert(composited(1), frame(1));
+
+        assert!(cache.contains(&composited(0)));
+        assert!(!cache.contains(&decoded(0)));
+        assert!(cache.contains(&composited(1)));
+    }
+
+    #[test]
+    fn counts_what_happens() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut cache = FrameCache::new(1, EvictionPolicy::Lru);
+        cache.set_observer({
+            let events = events.clone();
+            move |event| events.lock().unwrap().push(event)
+        });
+
+        cache.insert(decoded(0), frame(1));
+        cache.get(&decoded(0));
+        cache.get(&decoded(1));
+        cache.insert(decoded(1), frame(1));
+
+        assert_eq!(
+            cache.stats(),
+            FrameCacheStats {
+                hits: 1,
+                misses: 1,
+                insertions: 2,
+                evictions: 1,
+                bytes: 1,
+                frames: 1,
+            }
+        );
+        assert_eq!(events.lock().unwrap().len(), 5);
+    }
+}
@@ -112,6 +112,34 @@ impl FramePipelineEncoder {
         render_pass.draw(0..4, 0..1);
     }
 
+    // Sends what's been encoded so far to the GPU, so textures and buffers written after
+    // this don't change what it draws
+    pub fn flush(&mut self, state: &FramePipelineState) {
+        let encoder = std::mem::replace(&mut self.encoder, Self::new(state).encoder);
+        state
+            .constants
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    // Fills `output_view` with `color` without drawing anything
+    pub fn clear(&mut self, output_view: &wgpu::TextureView, color: wgpu::Color) {
+        self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+    }
+
     pub fn padded_bytes_per_row(&self, state: &FramePipelineState<'_>) -> u32 {
         // Calculate the aligned bytes per row
         let align = COPY_BYTES_PER_ROW_ALIGNMENT;
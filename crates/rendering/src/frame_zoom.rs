@@ -0,0 +1,259 @@
+use cap_project::{CursorEvents, ProjectConfiguration, ZoomMode, ZoomSegment, XY};
+use rayon::prelude::*;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    auto_zoom_target, cursor_track::CursorTracks, zoom::*, Coord, ProjectUniforms,
+    RawDisplayUVSpace, RenderOptions, RenderSegment,
+};
+
+// A frame's zoom, clamped to the output frame
+#[derive(Debug, Clone)]
+pub struct FrameZoom {
+    pub zoom: InterpolatedZoom,
+    pub zoom_debug: ZoomDebugInfo,
+    // where the current auto segment's target sits in the output once zoomed in, in pixels,
+    // for the camera to move out of the way of
+    pub camera_dodge: Option<XY<f64>>,
+}
+
+impl FrameZoom {
+    // `zoom_segments` are the timeline's, already normalized
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        options: &RenderOptions,
+        cursor_tracks: &CursorTracks,
+        focus_tracks: &AutoFocusTracks,
+        project: &ProjectConfiguration,
+        zoom_segments: &[ZoomSegment],
+        frame_number: u32,
+        fps: u32,
+        resolution_base: XY<u32>,
+        cursor_events: &CursorEvents,
+    ) -> Self {
+        let output_size = ProjectUniforms::get_output_size(options, project, resolution_base);
+        // computed in f64 so long renders don't drift from the frame grid
+        let frame_time = frame_number as f64 / fps as f64;
+
+        let segment_cursor = SegmentsCursor::new(frame_time, zoom_segments);
+
+        let cursor_track = cursor_tracks.get(cursor_events, &project.cursor);
+        // cursor data covers the whole display, while zoom only sees the cropped part of it
+        let to_cropped = |point: XY<f64>| {
+            Coord::<RawDisplayUVSpace>::new(point)
+                .to_cropped_display_uv_space(options, project)
+                .coord
+        };
+
+        let auto_target = |t| {
+            let (segment_time, _) = project.get_segment_time(t)?;
+            auto_zoom_target(
+                cursor_events,
+                &cursor_track,
+                &project.auto_zoom,
+                segment_time,
+            )
+            .map(to_cropped)
+        };
+        let auto_segment = [segment_cursor.segment, segment_cursor.prev_segment]
+            .into_iter()
+            .flatten()
+            .find(|s| matches!(s.mode, ZoomMode::Auto));
+
+        let focus_track = auto_segment.and_then(|s| {
+            focus_tracks.get(options, project, cursor_events, s, || {
+                // the focus stops mattering once the segment has fully zoomed out
+                let end = s.end + duration_out(s);
+                AutoFocusTrack::simulate(s.start, end, &project.auto_zoom, &auto_target)
+            })
+        });
+        let auto_focus = focus_track
+            .as_ref()
+            .map(|track| track.at(frame_time))
+            .unwrap_or(XY::new(0.0, 0.0));
+
+        let window_rect = |segment: Option<&ZoomSegment>| {
+            let ZoomMode::Window { window_id } = segment?.mode else {
+                return None;
+            };
+            let (segment_time, _) = project.get_segment_time(frame_time)?;
+            let window = cursor_events.window_at(window_id, segment_time * 1000.0)?;
+
+            let start = to_cropped(XY::new(window.x, window.y));
+            let end = to_cropped(XY::new(window.x + window.width, window.y + window.height));
+            let size = end - start;
+
+            Some([start.x, start.y, size.x, size.y])
+        };
+
+        let display_offset = ProjectUniforms::display_offset(options, project, resolution_base);
+        let display_size = ProjectUniforms::display_size(options, project, resolution_base);
+
+        // zooming keeps the focus where it is on the display, so this is where it ends up
+        // in the output. Decided once per segment so the camera doesn't chase the cursor.
+        let camera_dodge = auto_segment
+            .zip(focus_track.as_ref())
+            .filter(|_| project.camera.auto_dodge)
+            .map(|(s, track)| track.at((s.start + duration_in(s)).min(s.end)))
+            .map(|target| display_offset.coord + target * display_size.coord);
+
+        let viewport_start = XY::new(0.0, 0.0) - display_offset.coord / display_size.coord;
+        let viewport_end = (XY::new(output_size.0 as f64, output_size.1 as f64)
+            - display_offset.coord)
+            / display_size.coord;
+        let viewport_size = viewport_end - viewport_start;
+
+        let focus = ZoomFocus {
+            auto: auto_focus,
+            segment_window: window_rect(segment_cursor.segment),
+            prev_segment_window: window_rect(segment_cursor.prev_segment),
+            cover_amount: viewport_size.x.max(viewport_size.y),
+        };
+
+        let mut zoom = InterpolatedZoom::new(segment_cursor, focus);
+        if project.background.padding_follows_zoom {
+            zoom.bounds = zoom
+                .bounds
+                .expand_into_padding(zoom.t, viewport_start, viewport_end);
+        }
+        zoom.bounds = zoom.bounds.clamp_to_viewport(viewport_start, viewport_end);
+        let zoom_debug = ZoomDebugInfo::new(&segment_cursor, &zoom, auto_focus);
+
+        Self {
+            zoom,
+            zoom_debug,
+            camera_dodge,
+        }
+    }
+}
+
+// Every frame's zoom for an export, computed up front and in parallel so the render loop
+// that feeds the GPU doesn't wait on it.
+pub struct FrameZooms {
+    start: u32,
+    frames: Vec<Option<FrameZoom>>,
+}
+
+impl FrameZooms {
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute(
+        options: &RenderOptions,
+        cursor_tracks: &CursorTracks,
+        focus_tracks: &AutoFocusTracks,
+        project: &ProjectConfiguration,
+        zoom_segments: &[ZoomSegment],
+        segments: &[RenderSegment],
+        frames: Range<u32>,
+        fps: u32,
+        resolution_base: XY<u32>,
+    ) -> Self {
+        // build the smoothed cursor tracks before the frames start asking for them
+        segments
+            .par_iter()
+            .flat_map_iter(|segment| segment.cursors.iter())
+            .for_each(|cursor| {
+                cursor_tracks.get(cursor, &project.cursor);
+            });
+
+        let start = frames.start;
+        let frames = frames
+            .into_par_iter()
+            .map(|frame_number| {
+                let time = frame_number as f64 / fps as f64;
+                let (_, segment_i) = project.get_segment_time(time)?;
+                let segment = segments.get(segment_i as usize)?;
+
+                Some(FrameZoom::new(
+                    options,
+                    cursor_tracks,
+                    focus_tracks,
+                    project,
+                    zoom_segments,
+                    frame_number,
+                    fps,
+                    resolution_base,
+                    segment.cursors.get(project.get_segment_display(time)),
+                ))
+            })
+            .collect();
+
+        Self { start, frames }
+    }
+
+    // Hands out a frame's zoom, which the render loop only needs once
+    pub fn take(&mut self, frame_number: u32) -> Option<FrameZoom> {
+        let index = frame_number.checked_sub(self.start)?;
+        self.frames.get_mut(index as usize)?.take()
+    }
+}
+
+// events address and move count, plus the segment's start and end
+type FocusKey = (usize, usize, u64, u64);
+
+// Auto segments' focus tracks, simulated on first use and kept until the project changes
+// in a way that moves them, so each frame only looks its focus up.
+#[derive(Default)]
+pub struct AutoFocusTracks {
+    tracks: Mutex<(u64, HashMap<FocusKey, Option<Arc<AutoFocusTrack>>>)>,
+}
+
+impl AutoFocusTracks {
+    pub fn get(
+        &self,
+        options: &RenderOptions,
+        project: &ProjectConfiguration,
+        cursor: &CursorEvents,
+        segment: &ZoomSegment,
+        simulate: impl FnOnce() -> Option<AutoFocusTrack>,
+    ) -> Option<Arc<AutoFocusTrack>> {
+        let inputs = Self::inputs_hash(options, project);
+        let key: FocusKey = (
+            cursor as *const CursorEvents as usize,
+            cursor.moves.len(),
+            segment.start.to_bits(),
+            segment.end.to_bits(),
+        );
+
+        let mut tracks = self.tracks.lock().unwrap();
+        if tracks.0 != inputs {
+            *tracks = (inputs, HashMap::new());
+        }
+
+        tracks
+            .1
+            .entry(key)
+            .or_insert_with(|| simulate().map(Arc::new))
+            .clone()
+    }
+
+    // Everything besides the cursor events and segment that the focus depends on
+    fn inputs_hash(options: &RenderOptions, project: &ProjectConfiguration) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        let crop = ProjectUniforms::get_crop(options, project);
+        [crop.position.x, crop.position.y, crop.size.x, crop.size.y].hash(&mut hasher);
+
+        let config = &project.auto_zoom;
+        [
+            config.dead_zone_radius,
+            config.max_pan_velocity,
+            config.scroll_lookahead,
+        ]
+        .map(f64::to_bits)
+        .hash(&mut hasher);
+
+        for segment in project.timeline.iter().flat_map(|t| &t.segments) {
+            segment.recording_segment.hash(&mut hasher);
+            [segment.timescale, segment.start, segment.end]
+                .map(f64::to_bits)
+                .hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+}
@@ -0,0 +1,220 @@
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline, frame_pipeline::FramePipeline, srgb_to_linear, Coord,
+    RawDisplayUVSpace,
+};
+
+pub struct AnnotationLayer {
+    frame_sampler: wgpu::Sampler,
+    shape_bind_group_layout: wgpu::BindGroupLayout,
+    shape_pipeline: wgpu::RenderPipeline,
+    mask_bind_group_layout: wgpu::BindGroupLayout,
+    mask_pipeline: wgpu::RenderPipeline,
+}
+
+impl AnnotationLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let uniforms_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let shape_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Annotation Bind Group Layout"),
+                entries: &[uniforms_entry],
+            });
+
+        let mask_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Annotation Mask Bind Group Layout"),
+                entries: &[
+                    uniforms_entry,
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        Self {
+            frame_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            shape_pipeline: create_overlay_render_pipeline(
+                device,
+                &shape_bind_group_layout,
+                include_wgsl!("../shaders/annotation.wgsl"),
+                "Annotation Pipeline",
+            ),
+            mask_pipeline: create_overlay_render_pipeline(
+                device,
+                &mask_bind_group_layout,
+                include_wgsl!("../shaders/annotation-mask.wgsl"),
+                "Annotation Mask Pipeline",
+            ),
+            shape_bind_group_layout,
+            mask_bind_group_layout,
+        }
+    }
+
+    pub fn render(&self, pipeline: &mut FramePipeline, resolution_base: XY<u32>) {
+        let uniforms = pipeline.state.uniforms;
+        let constants = pipeline.state.constants;
+
+        let Some(timeline) = &uniforms.project.timeline else {
+            return;
+        };
+        let output_size = [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32];
+        let default_color = uniforms.project.overlays.annotation_color();
+
+        for (annotation, presence) in timeline.annotations_at(uniforms.time) {
+            let presence = presence as f32;
+            let (reach, opacity) = match annotation.animation {
+                AnnotationAnimation::Grow => (presence, 1.0),
+                AnnotationAnimation::Fade => (1.0, presence),
+                AnnotationAnimation::None => (1.0, 1.0),
+            };
+            let color = annotation.color.unwrap_or(default_color);
+
+            let to_output = |point: XY<f64>| match annotation.anchor {
+                AnnotationAnchor::Frame => [
+                    point.x as f32 * output_size[0],
+                    point.y as f32 * output_size[1],
+                ],
+                AnnotationAnchor::Display => {
+                    let position = Coord::<RawDisplayUVSpace>::new(point)
+                        .to_frame_space(&constants.options, &uniforms.project, resolution_base)
+                        .to_zoomed_frame_space(
+                            &constants.options,
+                            &uniforms.project,
+                            resolution_base,
+                            &uniforms.zoom,
+                        );
+                    [position.coord.x as f32, position.coord.y as f32]
+                }
+            };
+            let (from, to) = (to_output(annotation.from), to_output(annotation.to));
+
+            let annotation_uniforms = AnnotationUniforms {
+                points: [from[0], from[1], to[0], to[1]],
+                color: [
+                    srgb_to_linear(color[0]),
+                    srgb_to_linear(color[1]),
+                    srgb_to_linear(color[2]),
+                    opacity,
+                ],
+                output_size,
+                size: (annotation.size / 100.0 * output_size[1]).max(1.0),
+                reach,
+                kind: match annotation.kind {
+                    AnnotationKind::Arrow => 0,
+                    AnnotationKind::Rectangle => 1,
+                    AnnotationKind::Highlight => 2,
+                    AnnotationKind::Blur => 3,
+                    AnnotationKind::Pixelate => 4,
+                },
+                ..Default::default()
+            };
+
+            let uniform_buffer =
+                constants
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Annotation Uniform Buffer"),
+                        contents: bytemuck::cast_slice(&[annotation_uniforms]),
+                        usage: wgpu::BufferUsages::UNIFORM,
+                    });
+
+            if annotation.kind.is_mask() {
+                // masks read the frame drawn so far, so it's copied over to the other texture
+                // with the region hidden
+                pipeline.state.switch_output();
+
+                let bind_group = constants
+                    .device
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.mask_bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: uniform_buffer.as_entire_binding(),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::TextureView(
+                                    pipeline.state.get_other_texture_view(),
+                                ),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: wgpu::BindingResource::Sampler(&self.frame_sampler),
+                            },
+                        ],
+                        label: Some("Annotation Mask Bind Group"),
+                    });
+
+                pipeline.encoder.do_render_pass(
+                    pipeline.state.get_current_texture_view(),
+                    &self.mask_pipeline,
+                    bind_group,
+                    wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                );
+            } else {
+                let bind_group = constants
+                    .device
+                    .create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.shape_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_buffer.as_entire_binding(),
+                        }],
+                        label: Some("Annotation Bind Group"),
+                    });
+
+                pipeline.encoder.do_render_pass(
+                    pipeline.state.get_current_texture_view(),
+                    &self.shape_pipeline,
+                    bind_group,
+                    wgpu::LoadOp::Load,
+                );
+            }
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct AnnotationUniforms {
+    // from and to in output pixels
+    points: [f32; 4],
+    color: [f32; 4],
+    output_size: [f32; 2],
+    size: f32,
+    reach: f32,
+    kind: u32,
+    _padding: [u32; 3],
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use bytemuck::{Pod, Zeroable};
-use cap_project::BackgroundSource;
+use cap_project::{BackgroundSource, GradientKind};
 use image::GenericImageView;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -18,12 +18,17 @@ pub enum Background {
         start: [f32; 4],
         end: [f32; 4],
         angle: f32,
+        radial: bool,
     },
     Image {
         path: String,
     },
+    Screen,
 }
 
+// Without enough blur the screen background reads as a second copy of the recording
+const SCREEN_BACKGROUND_MIN_BLUR: f64 = 40.0;
+
 impl From<BackgroundSource> for Background {
     fn from(value: BackgroundSource) -> Self {
         match value {
@@ -33,7 +38,12 @@ impl From<BackgroundSource> for Background {
                 srgb_to_linear(value[2]),
                 1.0,
             ]),
-            BackgroundSource::Gradient { from, to, angle } => Background::Gradient {
+            BackgroundSource::Gradient {
+                from,
+                to,
+                angle,
+                kind,
+            } => Background::Gradient {
                 start: [
                     srgb_to_linear(from[0]),
                     srgb_to_linear(from[1]),
@@ -47,6 +57,7 @@ impl From<BackgroundSource> for Background {
                     1.0,
                 ],
                 angle: angle as f32,
+                radial: kind == GradientKind::Radial,
             },
             BackgroundSource::Image { path } | BackgroundSource::Wallpaper { path } => {
                 if let Some(path) = path {
@@ -63,6 +74,8 @@ impl From<BackgroundSource> for Background {
                 }
                 Background::Color([1.0, 1.0, 1.0, 1.0])
             }
+            BackgroundSource::Screen => Background::Screen,
+            BackgroundSource::Transparent => Background::Color([0.0, 0.0, 0.0, 0.0]),
         }
     }
 }
@@ -80,10 +93,10 @@ impl BackgroundLayer {
         pipeline.state.switch_output();
 
         // First, handle the background
-        match background {
+        match &background {
             Background::Image { path } => {
                 let mut textures = constants.background_textures.write().await;
-                let texture = match textures.entry(path.clone()) {
+                let texture = match textures.entry(path.to_string()) {
                     std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
                     std::collections::hash_map::Entry::Vacant(e) => {
                         let img = image::open(&path)
@@ -131,59 +144,18 @@ impl BackgroundLayer {
                     }
                 };
 
-                let output_ar = uniforms.output_size.1 as f32 / uniforms.output_size.0 as f32;
-                let image_ar = texture.height() as f32 / texture.width() as f32;
-
-                let y_height = if output_ar < image_ar {
-                    ((image_ar - output_ar) / 2.0) / image_ar
-                } else {
-                    0.0
-                };
-
-                let x_width = if output_ar > image_ar {
-                    let output_ar = 1.0 / output_ar;
-                    let image_ar = 1.0 / image_ar;
-
-                    ((image_ar - output_ar) / 2.0) / image_ar
-                } else {
-                    0.0
-                };
-
-                let image_uniforms = ImageBackgroundUniforms {
-                    output_size: [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32],
-                    padding: 0.0,
-                    x_width,
-                    y_height,
-                    _padding: 0.0,
-                };
-
-                let uniform_buffer =
-                    constants
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Image Background Uniforms"),
-                            contents: bytemuck::cast_slice(&[image_uniforms]),
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-
                 let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let bind_group = constants.image_background_pipeline.bind_group(
-                    &constants.device,
-                    &uniform_buffer,
-                    &texture_view,
-                );
-
-                pipeline.encoder.do_render_pass(
-                    pipeline.state.get_current_texture_view(),
-                    &constants.image_background_pipeline.render_pipeline,
-                    bind_group,
-                    wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                );
+                Self::render_cover(pipeline, texture, &texture_view);
+            }
+            // the display layer has already uploaded this frame's screen texture
+            Background::Screen => {
+                let (texture, texture_view) = &constants.screen_frame;
+                Self::render_cover(pipeline, texture, texture_view);
             }
             _ => {
                 let bind_group = constants.gradient_or_color_pipeline.bind_group(
                     &constants.device,
-                    &GradientOrColorUniforms::from(background).to_buffer(&constants.device),
+                    &GradientOrColorUniforms::from(background.clone()).to_buffer(&constants.device),
                 );
 
                 pipeline.encoder.do_render_pass(
@@ -195,10 +167,19 @@ impl BackgroundLayer {
             }
         }
 
-        if uniforms.project.background.blur > 0.0 {
+        let blur = match background {
+            Background::Screen => uniforms
+                .project
+                .background
+                .blur
+                .max(SCREEN_BACKGROUND_MIN_BLUR),
+            _ => uniforms.project.background.blur,
+        };
+
+        if blur > 0.0 {
             pipeline.state.switch_output();
 
-            let blur_strength = uniforms.project.background.blur as f32 / 100.0;
+            let blur_strength = blur as f32 / 100.0;
             let blur_uniform = BackgroundBlurUniforms {
                 output_size: [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32],
                 blur_strength,
@@ -239,6 +220,64 @@ impl BackgroundLayer {
 
         Ok(())
     }
+
+    // Draws the texture over the whole output, cropping whichever sides overflow
+    fn render_cover(
+        pipeline: &mut FramePipeline,
+        texture: &wgpu::Texture,
+        view: &wgpu::TextureView,
+    ) {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+
+        let output_ar = uniforms.output_size.1 as f32 / uniforms.output_size.0 as f32;
+        let image_ar = texture.height() as f32 / texture.width() as f32;
+
+        let y_height = if output_ar < image_ar {
+            ((image_ar - output_ar) / 2.0) / image_ar
+        } else {
+            0.0
+        };
+
+        let x_width = if output_ar > image_ar {
+            let output_ar = 1.0 / output_ar;
+            let image_ar = 1.0 / image_ar;
+
+            ((image_ar - output_ar) / 2.0) / image_ar
+        } else {
+            0.0
+        };
+
+        let image_uniforms = ImageBackgroundUniforms {
+            output_size: [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32],
+            padding: 0.0,
+            x_width,
+            y_height,
+            _padding: 0.0,
+        };
+
+        let uniform_buffer =
+            constants
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Image Background Uniforms"),
+                    contents: bytemuck::cast_slice(&[image_uniforms]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+
+        let bind_group = constants.image_background_pipeline.bind_group(
+            &constants.device,
+            &uniform_buffer,
+            view,
+        );
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_current_texture_view(),
+            &constants.image_background_pipeline.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        );
+    }
 }
 
 pub struct ImageBackgroundPipeline {
@@ -395,7 +434,8 @@ pub struct GradientOrColorUniforms {
     pub start: [f32; 4],
     pub end: [f32; 4],
     pub angle: f32,
-    _padding: [f32; 3],
+    pub radial: f32,
+    _padding: [f32; 2],
 }
 
 impl GradientOrColorUniforms {
@@ -417,15 +457,22 @@ impl From<Background> for GradientOrColorUniforms {
                 start: color,
                 end: color,
                 angle: 0.0,
-                _padding: [0.0; 3],
+                radial: 0.0,
+                _padding: [0.0; 2],
             },
-            Background::Gradient { start, end, angle } => Self {
+            Background::Gradient {
+                start,
+                end,
+                angle,
+                radial,
+            } => Self {
                 start,
                 end,
                 angle,
-                _padding: [0.0; 3],
+                radial: if radial { 1.0 } else { 0.0 },
+                _padding: [0.0; 2],
             },
-            Background::Image { .. } => {
+            Background::Image { .. } | Background::Screen => {
                 unreachable!("Image backgrounds should be handled separately")
             }
         }
@@ -0,0 +1,162 @@
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+    srgb_to_linear,
+    zoom::InterpolatedZoom,
+    Coord, DecodedSegmentFrames, ProjectUniforms, RawDisplayUVSpace,
+};
+
+// clicks closer together than this are rare enough that dropping the oldest is fine
+const MAX_CLICKS: usize = 8;
+
+pub struct ClickHighlightLayer {
+    uniform_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl ClickHighlightLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Click Highlight Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/click-highlight.wgsl"),
+            "Click Highlight Pipeline",
+        );
+
+        Self {
+            uniform_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Click Highlight Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[ClickHighlightUniforms::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    pub fn render(
+        &self,
+        pipeline: &mut FramePipeline,
+        segment_frames: &DecodedSegmentFrames,
+        resolution_base: XY<u32>,
+        cursor: &CursorEvents,
+        zoom: &InterpolatedZoom,
+    ) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+        let project = &uniforms.project;
+
+        let Some(config) = &project.cursor.click_highlight else {
+            return;
+        };
+        if config.duration <= 0.0 {
+            return;
+        }
+
+        let segment_time = segment_frames.segment_time as f64;
+        let track = constants.cursor_tracks.get(cursor, &project.cursor);
+
+        let mut clicks = [[0.0; 4]; MAX_CLICKS];
+        let mut count = 0;
+
+        for click_time in track
+            .clicks_within(segment_time, config.duration as f64)
+            .take(MAX_CLICKS)
+        {
+            // drawn where the rendered cursor was, which may lag the raw click when smoothing
+            let Some(position) = track.position_at(click_time, project.cursor.raw) else {
+                continue;
+            };
+
+            let position = Coord::<RawDisplayUVSpace>::new(position.position)
+                .to_frame_space(&constants.options, project, resolution_base)
+                .to_zoomed_frame_space(&constants.options, project, resolution_base, zoom);
+            let progress = (segment_time - click_time) / config.duration as f64;
+
+            clicks[count] = [position.x as f32, position.y as f32, progress as f32, 0.0];
+            count += 1;
+        }
+
+        if count == 0 {
+            return;
+        }
+
+        let color = project.overlays.click_highlight_color();
+
+        let click_uniforms = ClickHighlightUniforms {
+            clicks,
+            color: [
+                srgb_to_linear(color[0]),
+                srgb_to_linear(color[1]),
+                srgb_to_linear(color[2]),
+                1.0,
+            ],
+            radius: config.radius
+                * ProjectUniforms::cursor_scale(&constants.options, project, resolution_base, zoom),
+            count: count as f32,
+            style: match config.style {
+                ClickHighlightStyle::Ripple => 0.0,
+                ClickHighlightStyle::Highlight => 1.0,
+            },
+            _padding: 0.0,
+        };
+
+        constants.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[click_uniforms]),
+        );
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                }],
+                label: Some("Click Highlight Bind Group"),
+            });
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_current_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Load,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct ClickHighlightUniforms {
+    // frame space position, then progress through the animation
+    clicks: [[f32; 4]; MAX_CLICKS],
+    color: [f32; 4],
+    radius: f32,
+    count: f32,
+    style: f32,
+    _padding: f32,
+}
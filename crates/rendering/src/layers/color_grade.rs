@@ -0,0 +1,360 @@
+use std::sync::Mutex;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{create_overlay_render_pipeline, frame_pipeline::FramePipeline};
+
+// LUTs bigger than this are unusual and take a lot of memory as 3D textures
+const MAX_LUT_SIZE: u32 = 128;
+
+// A 3D lookup table read from a .cube file
+#[derive(Debug)]
+pub struct Lut {
+    pub size: u32,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    // RGBA, with red changing fastest, then green, then blue
+    pub data: Vec<u8>,
+}
+
+impl Lut {
+    // Parses the Resolve/Adobe .cube format. Only 3D LUTs are supported.
+    pub fn parse_cube(source: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut data = vec![];
+
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let Some(keyword) = parts.next() else {
+                continue;
+            };
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err("1D LUTs aren't supported".to_string()),
+                "LUT_3D_SIZE" => {
+                    size = Some(
+                        parts
+                            .next()
+                            .and_then(|s| s.parse::<u32>().ok())
+                            .filter(|s| (2..=MAX_LUT_SIZE).contains(s))
+                            .ok_or_else(|| format!("Invalid LUT size '{line}'"))?,
+                    );
+                }
+                "DOMAIN_MIN" => domain_min = parse_triple(parts)?,
+                "DOMAIN_MAX" => domain_max = parse_triple(parts)?,
+                _ => {
+                    let rgb = parse_triple(std::iter::once(keyword).chain(parts))?;
+                    data.extend(rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+                    data.push(255);
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| "Missing LUT_3D_SIZE".to_string())?;
+        let entries = size.pow(3) as usize;
+        if data.len() != entries * 4 {
+            return Err(format!(
+                "Expected {entries} entries but found {}",
+                data.len() / 4
+            ));
+        }
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            data,
+        })
+    }
+
+    // Leaves colors as they are, bound when a project has no LUT
+    fn identity() -> Self {
+        let data = (0..8u8)
+            .flat_map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1, 1].map(|c| c * 255))
+            .collect();
+
+        Self {
+            size: 2,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data,
+        }
+    }
+
+    fn create_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+        let size = wgpu::Extent3d {
+            width: self.size,
+            height: self.size,
+            depth_or_array_layers: self.size,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color Grade LUT Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            // the entries are already sRGB, the shader grades in sRGB
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            texture.as_image_copy(),
+            &self.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.size * 4),
+                rows_per_image: Some(self.size),
+            },
+            size,
+        );
+
+        texture
+    }
+}
+
+fn parse_triple<'a>(mut parts: impl Iterator<Item = &'a str>) -> Result<[f32; 3], String> {
+    let mut triple = [0.0; 3];
+    for value in &mut triple {
+        *value = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| "Expected three numbers".to_string())?;
+    }
+    Ok(triple)
+}
+
+#[derive(Default)]
+struct ColorGradeCache {
+    // the project's LUT, or None if it couldn't be read, so it isn't tried every frame
+    lut: Option<(String, Option<(Lut, wgpu::Texture)>)>,
+}
+
+pub struct ColorGradeLayer {
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    identity_lut: (Lut, wgpu::Texture),
+    cache: Mutex<ColorGradeCache>,
+}
+
+impl ColorGradeLayer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Color Grade Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/color-grade.wgsl"),
+            "Color Grade Pipeline",
+        );
+
+        let identity_lut = Lut::identity();
+        let identity_texture = identity_lut.create_texture(device, queue);
+
+        Self {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            render_pipeline,
+            identity_lut: (identity_lut, identity_texture),
+            cache: Mutex::new(ColorGradeCache::default()),
+        }
+    }
+
+    // Grades the screen texture in place, so everything drawn from it afterwards is graded
+    pub fn render(&self, pipeline: &mut FramePipeline) {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+        let grade = &uniforms.project.color_grade;
+
+        if grade.is_neutral() {
+            return;
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+
+        let lut_path = grade.lut.as_ref().filter(|_| grade.lut_intensity > 0.0);
+        if let Some(path) = lut_path {
+            if !matches!(&cache.lut, Some((p, _)) if p == path) {
+                let lut = std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| Lut::parse_cube(&source))
+                    .map(|lut| {
+                        let texture = lut.create_texture(&constants.device, &constants.queue);
+                        (lut, texture)
+                    })
+                    .map_err(|e| tracing::warn!("Failed to load LUT '{path}': {e}"))
+                    .ok();
+                cache.lut = Some((path.clone(), lut));
+            }
+        }
+
+        let loaded_lut = match (&cache.lut, lut_path) {
+            (Some((_, Some(lut))), Some(_)) => Some(lut),
+            _ => None,
+        };
+        let (lut, lut_texture) = loaded_lut.unwrap_or(&self.identity_lut);
+
+        let uniforms = ColorGradeUniforms {
+            domain_min: [lut.domain_min[0], lut.domain_min[1], lut.domain_min[2], 0.0],
+            domain_max: [lut.domain_max[0], lut.domain_max[1], lut.domain_max[2], 0.0],
+            brightness: grade.brightness.clamp(-100.0, 100.0) / 200.0,
+            contrast: grade.contrast.clamp(-100.0, 100.0) / 100.0,
+            saturation: grade.saturation.clamp(-100.0, 100.0) / 100.0,
+            lut_intensity: if loaded_lut.is_some() {
+                grade.lut_intensity.clamp(0.0, 100.0) / 100.0
+            } else {
+                0.0
+            },
+            lut_size: lut.size as f32,
+            ..Default::default()
+        };
+
+        let uniform_buffer =
+            constants
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Color Grade Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[uniforms]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+        let lut_view = lut_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&constants.screen_frame.1),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&lut_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+                label: Some("Color Grade Bind Group"),
+            });
+
+        drop(cache);
+
+        constants
+            .screen_pass
+            .run(pipeline, &self.render_pipeline, bind_group);
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct ColorGradeUniforms {
+    domain_min: [f32; 4],
+    domain_max: [f32; 4],
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    lut_intensity: f32,
+    lut_size: f32,
+    _padding: [f32; 3],
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_cube_files() {
+        let lut = Lut::parse_cube(
+            "# made by hand
+TITLE \"Invert\"
+LUT_3D_SIZE 2
+DOMAIN_MIN 0 0 0
+DOMAIN_MAX 1 1 1
+
+1 1 1
+0 1 1
+1 0 1
+0 0 1
+1 1 0
+0 1 0
+1 0 0
+0 0 0
+",
+        )
+        .unwrap();
+
+        assert_eq!(lut.size, 2);
+        assert_eq!(&lut.data[..8], &[255, 255, 255, 255, 0, 255, 255, 255]);
+        assert_eq!(&lut.data[28..], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn rejects_incomplete_cube_files() {
+        assert!(Lut::parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(Lut::parse_cube("0 0 0\n").is_err());
+        assert!(Lut::parse_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+    }
+}
@@ -6,7 +6,6 @@ use wgpu::{include_wgsl, util::DeviceExt, FilterMode};
 
 use crate::{
     frame_pipeline::{FramePipeline, FramePipelineState},
-    spring_mass_damper::SpringMassDamperSimulation,
     zoom::InterpolatedZoom,
     Coord, DecodedSegmentFrames, ProjectUniforms, RawDisplayUVSpace, STANDARD_CURSOR_HEIGHT,
 };
@@ -146,16 +145,17 @@ impl CursorLayer {
             constants,
             ..
         } = &pipeline.state;
-        let segment_time = segment_frames.segment_time;
-
-        let Some(interpolated_cursor) = interpolate_cursor(
-            cursor,
-            segment_time,
-            uniforms.project.cursor.tension,
-            uniforms.project.cursor.mass,
-            uniforms.project.cursor.friction,
-            uniforms.project.cursor.raw,
-        ) else {
+        let segment_time = segment_frames.segment_time as f64;
+        let track = constants
+            .cursor_tracks
+            .get(cursor, &uniforms.project.cursor);
+
+        let Some(interpolated_cursor) =
+            track.position_at(segment_time, uniforms.project.cursor.raw)
+        else {
+            return;
+        };
+        let Some(cursor_event) = track.move_at(segment_time) else {
             return;
         };
 
@@ -167,17 +167,15 @@ impl CursorLayer {
 
         let speed = (velocity[0] * velocity[0] + velocity[1] * velocity[1]).sqrt();
         let motion_blur_amount = (speed * 0.3).min(1.0) * 0.0; // uniforms.project.cursor.motion_blur;
-        let cursor_event = find_cursor_event(&cursor, segment_time);
 
-        let last_click_time = cursor
-            .clicks
-            .iter()
-            .filter(|click| click.down && click.process_time_ms <= (segment_time as f64) * 1000.0)
-            .max_by_key(|click| click.process_time_ms as i64)
-            .map(|click| ((segment_time as f64) * 1000.0 - click.process_time_ms) as f32 / 1000.0)
+        let last_click_time = track
+            .time_since_click(segment_time)
+            .map(|t| t as f32)
             .unwrap_or(1.0);
 
-        let Some(cursor_texture) = constants.cursor_textures.get(&cursor_event.cursor_id) else {
+        let Some(cursor_texture) =
+            constants.cursor_texture(&cursor_event.cursor_id, uniforms.project.cursor.theme)
+        else {
             return;
         };
 
@@ -185,7 +183,7 @@ impl CursorLayer {
         let aspect_ratio = cursor_size.width as f32 / cursor_size.height as f32;
 
         let cursor_size_percentage = if uniforms.cursor_size <= 0.0 {
-            100.0
+            1.0
         } else {
             uniforms.cursor_size / 100.0
         };
@@ -195,8 +193,7 @@ impl CursorLayer {
             STANDARD_CURSOR_HEIGHT * cursor_size_percentage,
         ];
 
-        let position = interpolated_cursor
-            .position
+        let position = Coord::<RawDisplayUVSpace>::new(interpolated_cursor.position)
             .to_frame_space(&constants.options, &uniforms.project, resolution_base)
             .to_zoomed_frame_space(&constants.options, &uniforms.project, resolution_base, zoom);
         let relative_position = [position.x as f32, position.y as f32];
@@ -295,183 +292,3 @@ pub struct CursorUniforms {
     hotspot: [f32; 2],
     _alignment: [f32; 5],
 }
-
-pub fn find_cursor_event(cursor: &CursorEvents, time: f32) -> &CursorMoveEvent {
-    let time_ms = time * 1000.0;
-
-    if cursor.moves[0].process_time_ms > time_ms.into() {
-        return &cursor.moves[0];
-    }
-
-    let event = cursor
-        .moves
-        .iter()
-        .rev()
-        .find(|event| {
-            // println!("Checking event at time: {}ms", event.process_time_ms);
-            event.process_time_ms <= time_ms.into()
-        })
-        .unwrap_or(&cursor.moves[0]);
-
-    event
-}
-
-struct InterpolatedCursorPosition {
-    position: Coord<RawDisplayUVSpace>,
-    velocity: XY<f32>,
-}
-
-fn interpolate_cursor(
-    cursor: &CursorEvents,
-    time_secs: f32,
-    tension: f32,
-    mass: f32,
-    friction: f32,
-    raw: bool,
-) -> Option<InterpolatedCursorPosition> {
-    let time_ms = (time_secs * 1000.0) as f64;
-
-    if cursor.moves.is_empty() {
-        return None;
-    }
-
-    if cursor.moves[0].process_time_ms > time_ms.into() {
-        let event = &cursor.moves[0];
-
-        return Some(InterpolatedCursorPosition {
-            position: Coord::new(XY {
-                x: event.x,
-                y: event.y,
-            }),
-            velocity: XY::new(0.0, 0.0),
-        });
-    }
-
-    if let Some(event) = cursor.moves.last() {
-        if event.process_time_ms < time_ms.into() {
-            return Some(InterpolatedCursorPosition {
-                position: Coord::new(XY {
-                    x: event.x,
-                    y: event.y,
-                }),
-                velocity: XY::new(0.0, 0.0),
-            });
-        }
-    }
-
-    if raw {
-        let pos = cursor.moves.windows(2).enumerate().find_map(|(i, chunk)| {
-            if time_ms >= chunk[0].process_time_ms && time_ms < chunk[1].process_time_ms {
-                let c = &chunk[0];
-                Some(XY::new(c.x as f32, c.y as f32))
-            } else {
-                None
-            }
-        })?;
-
-        Some(InterpolatedCursorPosition {
-            position: Coord::new(XY {
-                x: pos.x as f64,
-                y: pos.y as f64,
-            }),
-            velocity: XY::new(0.0, 0.0),
-        })
-    } else {
-        let events = get_smoothed_cursor_events(&cursor.moves, tension, mass, friction);
-        interpolate_smoothed_position(&events, time_secs as f64, tension, mass, friction)
-    }
-}
-
-fn interpolate_smoothed_position(
-    smoothed_events: &[SmoothedCursorEvent],
-    query_time: f64,
-    tension: f32,
-    mass: f32,
-    friction: f32,
-) -> Option<InterpolatedCursorPosition> {
-    if smoothed_events.is_empty() {
-        return None;
-    }
-
-    let mut sim = SpringMassDamperSimulation::new(tension, mass, friction);
-
-    let query_time_ms = (query_time * 1000.0) as f32;
-
-    match smoothed_events
-        .windows(2)
-        .find(|chunk| chunk[0].time <= query_time_ms && query_time_ms < chunk[1].time)
-    {
-        Some(c) => {
-            sim.set_position(c[0].position);
-            sim.set_velocity(c[0].velocity);
-            sim.set_target_position(c[0].target_position);
-            sim.run(query_time_ms - c[0].time);
-        }
-        None => {
-            let e = smoothed_events.last().unwrap();
-            sim.set_position(e.position);
-            sim.set_velocity(e.velocity);
-            sim.set_target_position(e.target_position);
-            sim.run(query_time_ms - e.time);
-        }
-    };
-
-    Some(InterpolatedCursorPosition {
-        position: Coord::new(sim.position.map(|v| v as f64)),
-        velocity: sim.velocity,
-    })
-}
-
-#[derive(Debug)]
-struct SmoothedCursorEvent {
-    time: f32,
-    target_position: XY<f32>,
-    position: XY<f32>,
-    velocity: XY<f32>,
-}
-
-fn get_smoothed_cursor_events(
-    moves: &[CursorMoveEvent],
-    tension: f32,
-    mass: f32,
-    friction: f32,
-) -> Vec<SmoothedCursorEvent> {
-    let mut last_time = 0.0;
-
-    let mut events = vec![];
-
-    let mut sim = SpringMassDamperSimulation::new(tension, mass, friction);
-
-    sim.set_position(XY::new(moves[0].x, moves[0].y).map(|v| v as f32));
-    sim.set_velocity(XY::new(0.0, 0.0));
-
-    if moves[0].process_time_ms > 0.0 {
-        events.push(SmoothedCursorEvent {
-            time: 0.0,
-            target_position: sim.position,
-            position: sim.position,
-            velocity: sim.velocity,
-        })
-    }
-
-    for (i, m) in moves.iter().enumerate() {
-        let target_position = moves
-            .get(i + 1)
-            .map(|e| XY::new(e.x, e.y).map(|v| v as f32))
-            .unwrap_or(sim.target_position);
-        sim.set_target_position(target_position);
-
-        sim.run(m.process_time_ms as f32 - last_time);
-
-        last_time = m.process_time_ms as f32;
-
-        events.push(SmoothedCursorEvent {
-            time: m.process_time_ms as f32,
-            target_position,
-            position: sim.position,
-            velocity: sim.velocity,
-        });
-    }
-
-    events
-}
@@ -0,0 +1,147 @@
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+    srgb_to_linear,
+    zoom::InterpolatedZoom,
+    Coord, DecodedSegmentFrames, ProjectUniforms, RawDisplayUVSpace,
+};
+
+const MAX_POINTS: usize = CursorTrailConfiguration::MAX_LENGTH as usize;
+
+pub struct CursorTrailLayer {
+    uniform_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl CursorTrailLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cursor Trail Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/cursor-trail.wgsl"),
+            "Cursor Trail Pipeline",
+        );
+
+        Self {
+            uniform_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Cursor Trail Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[CursorTrailUniforms::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    pub fn render(
+        &self,
+        pipeline: &mut FramePipeline,
+        segment_frames: &DecodedSegmentFrames,
+        resolution_base: XY<u32>,
+        cursor: &CursorEvents,
+        zoom: &InterpolatedZoom,
+    ) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+        let project = &uniforms.project;
+
+        let Some(config) = &project.cursor.trail else {
+            return;
+        };
+
+        let track = constants.cursor_tracks.get(cursor, &project.cursor);
+        let trail = track.trail(
+            segment_frames.segment_time as f64,
+            (config.length as usize).min(MAX_POINTS),
+            config.spacing.max(0.001) as f64,
+            project.cursor.raw,
+        );
+
+        // a single point has no line to draw
+        if trail.len() < 2 {
+            return;
+        }
+
+        let mut points = [[0.0; 4]; MAX_POINTS];
+        for (point, position) in points.iter_mut().zip(&trail) {
+            let position = Coord::<RawDisplayUVSpace>::new(*position)
+                .to_frame_space(&constants.options, project, resolution_base)
+                .to_zoomed_frame_space(&constants.options, project, resolution_base, zoom);
+
+            *point = [position.x as f32, position.y as f32, 0.0, 0.0];
+        }
+
+        let trail_uniforms = CursorTrailUniforms {
+            points,
+            color: [
+                srgb_to_linear(config.color[0]),
+                srgb_to_linear(config.color[1]),
+                srgb_to_linear(config.color[2]),
+                1.0,
+            ],
+            width: config.width
+                * ProjectUniforms::cursor_scale(&constants.options, project, resolution_base, zoom),
+            opacity: config.opacity.clamp(0.0, 1.0),
+            count: trail.len() as f32,
+            _padding: 0.0,
+        };
+
+        constants.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[trail_uniforms]),
+        );
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                }],
+                label: Some("Cursor Trail Bind Group"),
+            });
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_current_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Load,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct CursorTrailUniforms {
+    // frame space positions, newest first
+    points: [[f32; 4]; MAX_POINTS],
+    color: [f32; 4],
+    width: f32,
+    opacity: f32,
+    count: f32,
+    _padding: f32,
+}
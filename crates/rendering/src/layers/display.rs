@@ -1,34 +1,171 @@
-use crate::{frame_pipeline::FramePipeline, DecodedSegmentFrames};
+use std::sync::Mutex;
+
+use bytemuck::{Pod, Zeroable};
+use cap_project::XY;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline, display_tracks::fit_display, frame_pipeline::FramePipeline,
+    DecodedSegmentFrames, RenderVideoConstants,
+};
 
 pub struct DisplayLayer {
-    // composite_resources: CompositeFrameResources,
+    uniform_buffer: wgpu::Buffer,
+    texture_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    fit_pipeline: wgpu::RenderPipeline,
+    // frames from displays that aren't the main display's size land here first, and are
+    // then drawn fitted into the screen texture
+    track_texture: Mutex<Option<wgpu::Texture>>,
 }
 
 impl DisplayLayer {
-    pub fn render(pipeline: &mut FramePipeline, segment_frames: &DecodedSegmentFrames) {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Fit Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let fit_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/display_fit.wgsl"),
+            "Display Fit Pipeline",
+        );
+
+        Self {
+            uniform_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Display Fit Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[DisplayFitUniforms::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+            texture_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            fit_pipeline,
+            track_texture: Mutex::new(None),
+        }
+    }
+
+    // Runs before the background is drawn, which can sample the screen texture too
+    pub fn upload(&self, pipeline: &mut FramePipeline, segment_frames: &DecodedSegmentFrames) {
         let constants = pipeline.state.constants;
-        let uniforms = pipeline.state.uniforms;
-        let frame_size = pipeline.state.constants.options.screen_size;
-
-        constants.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &constants.screen_frame.0,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &segment_frames.screen_frame,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(constants.options.screen_size.x * 4),
-                rows_per_image: None,
-            },
-            wgpu::Extent3d {
-                width: frame_size.x,
-                height: frame_size.y,
-                depth_or_array_layers: 1,
-            },
+        let screen_size = constants.options.screen_size;
+        let frame_size = segment_frames.screen_size;
+
+        if frame_size == screen_size {
+            write_frame(constants, &constants.screen_frame.0, segment_frames);
+            return;
+        }
+
+        let mut track_texture = self.track_texture.lock().unwrap();
+        if !matches!(&*track_texture, Some(t) if t.width() == frame_size.x && t.height() == frame_size.y)
+        {
+            *track_texture = Some(constants.device.create_texture(&wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width: frame_size.x,
+                    height: frame_size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some("Display Track Texture"),
+                view_formats: &[],
+            }));
+        }
+        let Some(texture) = &*track_texture else {
+            return;
+        };
+
+        write_frame(constants, texture, segment_frames);
+
+        let (offset, size) = fit_display(frame_size, screen_size);
+        let screen_size = screen_size.map(|v| v as f64);
+        let offset = offset * screen_size;
+        let size = size * screen_size;
+
+        constants.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[DisplayFitUniforms {
+                rect: [
+                    offset.x as f32,
+                    offset.y as f32,
+                    size.x as f32,
+                    size.y as f32,
+                ],
+            }]),
+        );
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                    },
+                ],
+                label: Some("Display Fit Bind Group"),
+            });
+
+        drop(track_texture);
+
+        pipeline.encoder.do_render_pass(
+            &constants.screen_frame.1,
+            &self.fit_pipeline,
+            bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
         );
+    }
+
+    pub fn render(pipeline: &mut FramePipeline) {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
 
         pipeline.state.switch_output();
 
@@ -45,3 +182,38 @@ impl DisplayLayer {
         );
     }
 }
+
+fn write_frame(
+    constants: &RenderVideoConstants,
+    texture: &wgpu::Texture,
+    segment_frames: &DecodedSegmentFrames,
+) {
+    let frame_size = segment_frames.screen_size;
+
+    constants.queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &segment_frames.screen_frame,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(frame_size.x * 4),
+            rows_per_image: None,
+        },
+        wgpu::Extent3d {
+            width: frame_size.x,
+            height: frame_size.y,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct DisplayFitUniforms {
+    // x, y, width, height in screen texture pixels
+    rect: [f32; 4],
+}
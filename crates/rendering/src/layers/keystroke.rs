@@ -0,0 +1,231 @@
+use std::sync::Mutex;
+
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use tracing::warn;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline, create_rgba_texture,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+    text::TextRasterizer,
+    DecodedSegmentFrames,
+};
+
+// older presses are dropped so a burst of typing doesn't run off the frame
+const MAX_LABEL_CHARS: usize = 32;
+// seconds at the end of the duration spent fading out
+const FADE_DURATION: f64 = 0.3;
+
+pub struct KeystrokeLayer {
+    uniform_buffer: wgpu::Buffer,
+    texture_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    text: Option<TextRasterizer>,
+    // the label only changes on a key press, so it's rasterized once and reused
+    label_texture: Mutex<Option<(String, u32, wgpu::Texture)>>,
+}
+
+impl KeystrokeLayer {
+    pub fn new(device: &wgpu::Device, deterministic: bool) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Keystroke Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/keystroke.wgsl"),
+            "Keystroke Pipeline",
+        );
+
+        let text =
+            TextRasterizer::load_system_font().map(|text| text.with_fallback(!deterministic));
+        if text.is_none() {
+            warn!("no system font found, keystrokes won't be rendered");
+        }
+
+        Self {
+            uniform_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Keystroke Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[KeystrokeUniforms::default()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+            texture_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            render_pipeline,
+            text,
+            label_texture: Mutex::new(None),
+        }
+    }
+
+    pub fn render(
+        &self,
+        pipeline: &mut FramePipeline,
+        segment_frames: &DecodedSegmentFrames,
+        cursor: &CursorEvents,
+    ) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+        let config = &uniforms.project.hotkeys;
+
+        if !config.show || config.duration <= 0.0 {
+            return;
+        }
+        let Some(text) = &self.text else {
+            return;
+        };
+
+        let time_ms = segment_frames.segment_time as f64 * 1000.0;
+        let presses = cursor
+            .keyboard
+            .recent(time_ms, config.duration as f64 * 1000.0, config.filter)
+            .collect::<Vec<_>>();
+        let Some(latest) = presses.last() else {
+            return;
+        };
+
+        let mut label = overlay_label([*latest]);
+        for start in (0..presses.len() - 1).rev() {
+            let longer = overlay_label(presses[start..].iter().copied());
+            if longer.chars().count() > MAX_LABEL_CHARS {
+                break;
+            }
+            label = longer;
+        }
+
+        let output_size = [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32];
+        let px = (config.size / 100.0 * output_size[1]).round().max(1.0) as u32;
+
+        let mut label_texture = self.label_texture.lock().unwrap();
+        if !matches!(&*label_texture, Some((l, p, _)) if *l == label && *p == px) {
+            let image = text.rasterize(&label, px as f32);
+            let texture = create_rgba_texture(
+                &constants.device,
+                &constants.queue,
+                "Keystroke Label Texture",
+                &image,
+            );
+            *label_texture = Some((label, px, texture));
+        }
+        let Some((_, _, texture)) = &*label_texture else {
+            return;
+        };
+
+        let text_size = [texture.width() as f32, texture.height() as f32];
+        let padding = [px as f32 * 0.6, px as f32 * 0.25];
+        let pill_size = [
+            text_size[0] + padding[0] * 2.0,
+            text_size[1] + padding[1] * 2.0,
+        ];
+        let margin = output_size[1] * 0.05;
+
+        let pill_x = match config.position {
+            HotkeysPosition::BottomLeft => margin,
+            HotkeysPosition::BottomCenter => (output_size[0] - pill_size[0]) / 2.0,
+            HotkeysPosition::BottomRight => output_size[0] - margin - pill_size[0],
+        };
+        let pill_y = output_size[1] - margin - pill_size[1];
+
+        let age = (time_ms - latest.process_time_ms) / 1000.0;
+        let opacity = ((config.duration as f64 - age) / FADE_DURATION).clamp(0.0, 1.0);
+
+        let keystroke_uniforms = KeystrokeUniforms {
+            pill: [pill_x, pill_y, pill_size[0], pill_size[1]],
+            text: [
+                pill_x + padding[0],
+                pill_y + padding[1],
+                text_size[0],
+                text_size[1],
+            ],
+            radius: pill_size[1] / 2.0,
+            opacity: opacity as f32,
+            _padding: [0.0; 2],
+        };
+
+        constants.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[keystroke_uniforms]),
+        );
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                    },
+                ],
+                label: Some("Keystroke Bind Group"),
+            });
+
+        drop(label_texture);
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_current_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Load,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct KeystrokeUniforms {
+    // x, y, width, height in output pixels
+    pill: [f32; 4],
+    text: [f32; 4],
+    radius: f32,
+    opacity: f32,
+    _padding: [f32; 2],
+}
@@ -1,9 +1,29 @@
-mod background;
+mod annotation;
+pub mod background;
 mod camera;
+mod click_highlight;
+mod color_grade;
 mod cursor;
+mod cursor_trail;
 mod display;
+mod keystroke;
+mod screen_pass;
+mod text;
+mod tone_map;
+mod transition;
+mod watermark;
 
+pub use annotation::*;
 pub use background::*;
 pub use camera::*;
+pub use click_highlight::*;
+pub use color_grade::*;
 pub use cursor::*;
+pub use cursor_trail::*;
 pub use display::*;
+pub use keystroke::*;
+pub use screen_pass::*;
+pub use text::*;
+pub use tone_map::*;
+pub use transition::*;
+pub use watermark::*;
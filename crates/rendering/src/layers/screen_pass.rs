@@ -0,0 +1,51 @@
+use std::sync::Mutex;
+
+use crate::frame_pipeline::FramePipeline;
+
+// Redraws the screen texture through a shader, for effects applied to the recording before
+// it's composited. A texture can't be sampled while it's drawn to, so the pass draws to a
+// scratch texture that's copied back over the screen.
+#[derive(Default)]
+pub struct ScreenPass {
+    scratch: Mutex<Option<wgpu::Texture>>,
+}
+
+impl ScreenPass {
+    pub fn run(
+        &self,
+        pipeline: &mut FramePipeline,
+        render_pipeline: &wgpu::RenderPipeline,
+        bind_group: wgpu::BindGroup,
+    ) {
+        let constants = pipeline.state.constants;
+        let screen = &constants.screen_frame.0;
+
+        let mut scratch = self.scratch.lock().unwrap();
+        let scratch = scratch.get_or_insert_with(|| {
+            constants.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Screen Pass Texture"),
+                size: screen.size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: screen.format(),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        });
+        let scratch_view = scratch.create_view(&wgpu::TextureViewDescriptor::default());
+
+        pipeline.encoder.do_render_pass(
+            &scratch_view,
+            render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        );
+
+        pipeline.encoder.encoder.copy_texture_to_texture(
+            scratch.as_image_copy(),
+            screen.as_image_copy(),
+            screen.size(),
+        );
+    }
+}
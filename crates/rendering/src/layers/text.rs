@@ -0,0 +1,382 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline, create_rgba_texture,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+    srgb_to_linear,
+    text::TextRasterizer,
+    RenderVideoConstants,
+};
+
+// how far sliding text travels coming in, in ems
+const SLIDE_DISTANCE: f32 = 1.0;
+// title card text sizes, as fractions of the frame's height
+const CARD_TITLE_SIZE: f32 = 0.08;
+const CARD_SUBTITLE_SIZE: f32 = 0.04;
+// the gap between captions and the frame's bottom, as a fraction of its height
+const CAPTION_MARGIN: f32 = 0.06;
+// how far caption boxes reach past their line, in ems
+const CAPTION_PADDING: [f32; 2] = [0.35, 0.1];
+
+type TextureKey = (String, TextFont, u32);
+
+pub struct TextLayer {
+    texture_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    // fonts are only loaded once something uses them, and stay None if the system has none
+    fonts: Mutex<HashMap<TextFont, Option<TextRasterizer>>>,
+    // text only changes when it's edited, so it's rasterized once and reused while it shows
+    textures: Mutex<HashMap<TextureKey, wgpu::Texture>>,
+}
+
+impl TextLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/text.wgsl"),
+            "Text Pipeline",
+        );
+
+        Self {
+            texture_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            render_pipeline,
+            fonts: Mutex::new(HashMap::new()),
+            textures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn render(&self, pipeline: &mut FramePipeline) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+
+        let Some(timeline) = &uniforms.project.timeline else {
+            return;
+        };
+        let output_size = [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32];
+
+        let mut fonts = self.fonts.lock().unwrap();
+        let mut textures = self.textures.lock().unwrap();
+        let mut shown = vec![];
+        let mut draws = vec![];
+
+        for (text, presence) in timeline.text_at(uniforms.time) {
+            if text.content.trim().is_empty() {
+                continue;
+            }
+
+            let px = (text.size / 100.0 * output_size[1]).round().max(1.0) as u32;
+            let key = (text.content.clone(), text.font, px);
+
+            if !rasterize(&mut fonts, &mut textures, constants, &key) {
+                continue;
+            }
+
+            let presence = presence as f32;
+            let slide = (1.0 - presence) * SLIDE_DISTANCE * px as f32;
+            let offset = match text.animation {
+                TextAnimation::SlideFromBottom => [0.0, slide],
+                TextAnimation::SlideFromLeft => [-slide, 0.0],
+                TextAnimation::None | TextAnimation::Fade => [0.0, 0.0],
+            };
+
+            let texture = &textures[&key];
+            let text_uniforms = TextUniforms {
+                rect: [
+                    text.position.x as f32 * output_size[0] + offset[0],
+                    text.position.y as f32 * output_size[1] + offset[1],
+                    texture.width() as f32,
+                    texture.height() as f32,
+                ],
+                color: [
+                    srgb_to_linear(text.color[0]),
+                    srgb_to_linear(text.color[1]),
+                    srgb_to_linear(text.color[2]),
+                    presence,
+                ],
+                ..Default::default()
+            };
+
+            draws.push(self.bind_group(constants, texture, text_uniforms));
+            shown.push(key);
+        }
+
+        let captions = &uniforms.project.captions;
+        if captions.burn_in {
+            let px = (captions.size / 100.0 * output_size[1]).round().max(1.0) as u32;
+            let padding = CAPTION_PADDING.map(|p| (p * px as f32).round());
+
+            // each line gets its own box, with the lines of cues showing at once stacked in
+            // the order they started
+            let lines = timeline
+                .captions_at(uniforms.time)
+                .flat_map(|caption| caption.text.lines())
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| (line.to_string(), captions.font, px))
+                .filter(|key| rasterize(&mut fonts, &mut textures, constants, key))
+                .collect::<Vec<_>>();
+
+            let height = lines
+                .iter()
+                .map(|key| textures[key].height() as f32 + padding[1] * 2.0)
+                .sum::<f32>();
+            let mut y = (output_size[1] * (1.0 - CAPTION_MARGIN) - height).round();
+
+            let color = captions.color.map(srgb_to_linear);
+            let background = captions.background.map(srgb_to_linear);
+            let opacity = captions.background_opacity.clamp(0.0, 1.0);
+
+            for key in lines {
+                let texture = &textures[&key];
+                let size = [texture.width() as f32, texture.height() as f32];
+                let text_uniforms = TextUniforms {
+                    rect: [
+                        ((output_size[0] - size[0]) / 2.0).round(),
+                        y + padding[1],
+                        size[0],
+                        size[1],
+                    ],
+                    color: [color[0], color[1], color[2], 1.0],
+                    background: [
+                        background[0] * opacity,
+                        background[1] * opacity,
+                        background[2] * opacity,
+                        opacity,
+                    ],
+                    padding,
+                    ..Default::default()
+                };
+                y += size[1] + padding[1] * 2.0;
+
+                draws.push(self.bind_group(constants, texture, text_uniforms));
+                shown.push(key);
+            }
+        }
+
+        // text that's no longer showing, or was edited, won't be drawn again soon
+        textures.retain(|key, _| shown.contains(key));
+
+        drop(textures);
+        drop(fonts);
+
+        for bind_group in draws {
+            pipeline.encoder.do_render_pass(
+                pipeline.state.get_current_texture_view(),
+                &self.render_pipeline,
+                bind_group,
+                wgpu::LoadOp::Load,
+            );
+        }
+    }
+
+    // Draws a title card over the whole frame, `time` seconds in
+    pub fn render_card(&self, pipeline: &mut FramePipeline, card: &TitleCard, time: f64) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+
+        let output_size = [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32];
+        let brightness = card.brightness(time) as f32;
+
+        let mut fonts = self.fonts.lock().unwrap();
+        let mut textures = self.textures.lock().unwrap();
+
+        let lines = [
+            (&card.title, CARD_TITLE_SIZE),
+            (&card.subtitle, CARD_SUBTITLE_SIZE),
+        ]
+        .into_iter()
+        .filter(|(content, _)| !content.trim().is_empty())
+        .map(|(content, size)| {
+            let px = (size * output_size[1]).round().max(1.0) as u32;
+            (content.clone(), card.font, px)
+        })
+        .filter(|key| rasterize(&mut fonts, &mut textures, constants, key))
+        .collect::<Vec<_>>();
+
+        // the lines sit one under the other, centered together in the frame
+        let height = lines
+            .iter()
+            .map(|key| textures[key].height() as f32)
+            .sum::<f32>();
+        let mut y = ((output_size[1] - height) / 2.0).round();
+
+        let color = card.text_color.map(srgb_to_linear);
+        let draws = lines
+            .iter()
+            .map(|key| {
+                let texture = &textures[key];
+                let size = [texture.width() as f32, texture.height() as f32];
+                let text_uniforms = TextUniforms {
+                    rect: [
+                        ((output_size[0] - size[0]) / 2.0).round(),
+                        y,
+                        size[0],
+                        size[1],
+                    ],
+                    // drawn opaque over the background, so both fade to black together
+                    color: [
+                        color[0] * brightness,
+                        color[1] * brightness,
+                        color[2] * brightness,
+                        1.0,
+                    ],
+                    ..Default::default()
+                };
+                y += size[1];
+
+                self.bind_group(constants, texture, text_uniforms)
+            })
+            .collect::<Vec<_>>();
+
+        drop(textures);
+        drop(fonts);
+
+        let background = card
+            .background
+            .map(|c| (srgb_to_linear(c) * brightness) as f64);
+        pipeline.encoder.clear(
+            pipeline.state.get_current_texture_view(),
+            wgpu::Color {
+                r: background[0],
+                g: background[1],
+                b: background[2],
+                a: 1.0,
+            },
+        );
+
+        for bind_group in draws {
+            pipeline.encoder.do_render_pass(
+                pipeline.state.get_current_texture_view(),
+                &self.render_pipeline,
+                bind_group,
+                wgpu::LoadOp::Load,
+            );
+        }
+    }
+
+    // each text is drawn in its own pass, so each gets its own uniforms
+    fn bind_group(
+        &self,
+        constants: &RenderVideoConstants,
+        texture: &wgpu::Texture,
+        text_uniforms: TextUniforms,
+    ) -> wgpu::BindGroup {
+        let uniform_buffer =
+            constants
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Text Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[text_uniforms]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                    },
+                ],
+                label: Some("Text Bind Group"),
+            })
+    }
+}
+
+// Rasterizes the text the first time it's drawn at a size. False when there's no font to
+// draw it with.
+fn rasterize(
+    fonts: &mut HashMap<TextFont, Option<TextRasterizer>>,
+    textures: &mut HashMap<TextureKey, wgpu::Texture>,
+    constants: &RenderVideoConstants,
+    key: &TextureKey,
+) -> bool {
+    if textures.contains_key(key) {
+        return true;
+    }
+
+    let (content, font, px) = key;
+    let Some(rasterizer) = fonts.entry(*font).or_insert_with(|| {
+        TextRasterizer::load_system_font_like(*font)
+            .map(|text| text.with_fallback(!constants.options.deterministic))
+    }) else {
+        return false;
+    };
+
+    let image = rasterizer.rasterize_lines(content, *px as f32);
+    let texture = create_rgba_texture(&constants.device, &constants.queue, "Text Texture", &image);
+    textures.insert(key.clone(), texture);
+
+    true
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct TextUniforms {
+    // x, y, width, height in output pixels
+    rect: [f32; 4],
+    color: [f32; 4],
+    // premultiplied
+    background: [f32; 4],
+    padding: [f32; 2],
+    _padding: [f32; 2],
+}
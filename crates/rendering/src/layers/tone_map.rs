@@ -0,0 +1,213 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    color::{ColorPrimaries, TransferFunction, HDR_PEAK_NITS, REFERENCE_WHITE_NITS},
+    create_overlay_render_pipeline,
+    frame_pipeline::FramePipeline,
+};
+
+// Converts HDR and wide gamut screen frames to SDR BT.709 in place, so they match every other
+// layer and don't look washed out once exported.
+//
+// Frames are still uploaded as 8-bit sRGB, so PQ and HLG code values lose precision on the
+// way in and smooth gradients can band. Highlights above reference white are rolled off
+// rather than clipped.
+pub struct ToneMapLayer {
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl ToneMapLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = create_bind_group_layout(device, "Tone Map Bind Group Layout");
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/tone-map.wgsl"),
+            "Tone Map Pipeline",
+        );
+
+        Self {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor::default()),
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    pub fn render(&self, pipeline: &mut FramePipeline) {
+        let constants = pipeline.state.constants;
+        let color = constants.options.screen_color;
+
+        if !color.needs_conversion() {
+            return;
+        }
+
+        let uniforms = ToneMapUniforms {
+            gamut: rows(color.primaries.conversion_to(ColorPrimaries::Bt709)),
+            transfer: match color.transfer {
+                TransferFunction::Sdr => 0,
+                TransferFunction::Pq => 1,
+                TransferFunction::Hlg => 2,
+            },
+            peak: HDR_PEAK_NITS / REFERENCE_WHITE_NITS,
+            ..Default::default()
+        };
+
+        let bind_group = create_bind_group(
+            &constants.device,
+            &self.bind_group_layout,
+            &self.sampler,
+            bytemuck::cast_slice(&[uniforms]),
+            &constants.screen_frame.1,
+            "Tone Map",
+        );
+
+        constants
+            .screen_pass
+            .run(pipeline, &self.render_pipeline, bind_group);
+    }
+}
+
+// Converts the finished SDR frame to PQ and BT.2020 for HDR10 exports, placing SDR white at
+// reference white. It's stored in the same 8-bit texture, and the encoder widens it to 10
+// bits, so there's no more highlight detail than the SDR export has.
+pub struct Hdr10Layer {
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl Hdr10Layer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = create_bind_group_layout(device, "HDR10 Bind Group Layout");
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/hdr10.wgsl"),
+            "HDR10 Pipeline",
+        );
+
+        Self {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor::default()),
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    pub fn render(&self, pipeline: &mut FramePipeline) {
+        let constants = pipeline.state.constants;
+
+        let uniforms = Hdr10Uniforms {
+            gamut: rows(ColorPrimaries::Bt709.conversion_to(ColorPrimaries::Bt2020)),
+        };
+
+        let bind_group = create_bind_group(
+            &constants.device,
+            &self.bind_group_layout,
+            &self.sampler,
+            bytemuck::cast_slice(&[uniforms]),
+            pipeline.state.get_current_texture_view(),
+            "HDR10",
+        );
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_other_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        );
+        pipeline.state.switch_output();
+    }
+}
+
+// Pads each row out to a vec4, as WGSL lays out uniforms
+fn rows(matrix: [[f32; 3]; 3]) -> [[f32; 4]; 3] {
+    matrix.map(|[a, b, c]| [a, b, c, 0.0])
+}
+
+fn create_bind_group_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    uniforms: &[u8],
+    texture_view: &wgpu::TextureView,
+    label: &str,
+) -> wgpu::BindGroup {
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{label} Uniform Buffer")),
+        contents: uniforms,
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some(&format!("{label} Bind Group")),
+    })
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct ToneMapUniforms {
+    gamut: [[f32; 4]; 3],
+    transfer: u32,
+    peak: f32,
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct Hdr10Uniforms {
+    gamut: [[f32; 4]; 3],
+}
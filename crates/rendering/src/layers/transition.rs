@@ -0,0 +1,209 @@
+use std::sync::Mutex;
+
+use bytemuck::{Pod, Zeroable};
+use cap_project::*;
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+    srgb_to_linear, TransitionFrames,
+};
+
+pub struct TransitionLayer {
+    texture_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    // the frame from the other side of the cut, held while this side's is drawn
+    held: Mutex<Option<wgpu::Texture>>,
+}
+
+impl TransitionLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Transition Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/transition.wgsl"),
+            "Transition Pipeline",
+        );
+
+        Self {
+            texture_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            render_pipeline,
+            held: Mutex::new(None),
+        }
+    }
+
+    // Keeps the frame drawn so far, to blend with the one drawn next
+    pub fn hold(&self, pipeline: &mut FramePipeline) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+        let size = wgpu::Extent3d {
+            width: uniforms.output_size.0,
+            height: uniforms.output_size.1,
+            depth_or_array_layers: 1,
+        };
+
+        let mut held = self.held.lock().unwrap();
+        if !matches!(&*held, Some(t) if t.size() == size) {
+            *held = Some(constants.device.create_texture(&wgpu::TextureDescriptor {
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some("Transition Texture"),
+                view_formats: &[],
+            }));
+        }
+        let Some(texture) = &*held else {
+            return;
+        };
+
+        pipeline.encoder.encoder.copy_texture_to_texture(
+            pipeline.state.get_current_texture().as_image_copy(),
+            texture.as_image_copy(),
+            size,
+        );
+    }
+
+    // Blends the frame drawn since `hold` with the one held
+    pub fn render(&self, pipeline: &mut FramePipeline, transition: &TransitionFrames) {
+        let constants = pipeline.state.constants;
+
+        let guard = self.held.lock().unwrap();
+        let Some(held) = &*guard else {
+            return;
+        };
+        let held_view = held.create_view(&wgpu::TextureViewDescriptor::default());
+        let current_view = pipeline.state.get_current_texture_view();
+        let (from, to) = if transition.incoming {
+            (current_view, &held_view)
+        } else {
+            (&held_view, current_view)
+        };
+
+        let mut uniforms = TransitionUniforms {
+            progress: transition.progress,
+            ..Default::default()
+        };
+        match transition.kind {
+            TransitionKind::CrossFade => {}
+            TransitionKind::Dip { color } => {
+                uniforms.kind = 1;
+                uniforms.color = [
+                    srgb_to_linear(color[0]),
+                    srgb_to_linear(color[1]),
+                    srgb_to_linear(color[2]),
+                    1.0,
+                ];
+            }
+            TransitionKind::Slide { direction } => {
+                uniforms.kind = 2;
+                uniforms.direction = match direction {
+                    SlideDirection::Left => [-1.0, 0.0],
+                    SlideDirection::Right => [1.0, 0.0],
+                    SlideDirection::Up => [0.0, -1.0],
+                    SlideDirection::Down => [0.0, 1.0],
+                };
+            }
+        }
+
+        let uniform_buffer =
+            constants
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Transition Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[uniforms]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(from),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(to),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                    },
+                ],
+                label: Some("Transition Bind Group"),
+            });
+
+        drop(guard);
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_other_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        );
+        pipeline.state.switch_output();
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct TransitionUniforms {
+    color: [f32; 4],
+    direction: [f32; 2],
+    progress: f32,
+    kind: u32,
+}
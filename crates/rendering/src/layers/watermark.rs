@@ -0,0 +1,215 @@
+use std::sync::Mutex;
+
+use bytemuck::{Pod, Zeroable};
+use cap_flags::FLAGS;
+use cap_project::*;
+use image::{imageops::FilterType, RgbaImage};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{
+    create_overlay_render_pipeline, create_rgba_texture,
+    frame_pipeline::{FramePipeline, FramePipelineState},
+};
+
+// the gap between the watermark and the frame's edges, as a fraction of its shorter side
+const MARGIN: f32 = 0.03;
+
+#[derive(Default)]
+struct WatermarkCache {
+    // the picked image, or None if it couldn't be read, so it isn't tried every frame
+    image: Option<(String, Option<RgbaImage>)>,
+    // the image resized to the width it's drawn at, since the texture has no mipmaps to
+    // shrink it smoothly
+    texture: Option<((String, u32), wgpu::Texture)>,
+}
+
+pub struct WatermarkLayer {
+    texture_sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    cache: Mutex<WatermarkCache>,
+}
+
+impl WatermarkLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Watermark Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline = create_overlay_render_pipeline(
+            device,
+            &bind_group_layout,
+            include_wgsl!("../shaders/watermark.wgsl"),
+            "Watermark Pipeline",
+        );
+
+        Self {
+            texture_sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            bind_group_layout,
+            render_pipeline,
+            cache: Mutex::new(WatermarkCache::default()),
+        }
+    }
+
+    pub fn render(&self, pipeline: &mut FramePipeline) {
+        let FramePipelineState {
+            uniforms,
+            constants,
+            ..
+        } = &pipeline.state;
+
+        let Some(watermark) = &uniforms.project.watermark else {
+            return;
+        };
+        if FLAGS.free_tier_watermark && watermark.free_tier_only && uniforms.is_upgraded {
+            return;
+        }
+
+        let output_size = [uniforms.output_size.0 as f32, uniforms.output_size.1 as f32];
+        let width = (watermark.scale / 100.0 * output_size[0]).round().max(1.0) as u32;
+
+        let mut cache = self.cache.lock().unwrap();
+
+        if !matches!(&cache.image, Some((path, _)) if *path == watermark.path) {
+            let image = image::open(&watermark.path)
+                .map(|image| image.to_rgba8())
+                .map_err(|e| tracing::warn!("Failed to load watermark '{}': {e}", watermark.path))
+                .ok();
+            cache.image = Some((watermark.path.clone(), image));
+            cache.texture = None;
+        }
+
+        let key = (watermark.path.clone(), width);
+        if !matches!(&cache.texture, Some((k, _)) if *k == key) {
+            let Some((_, Some(image))) = &cache.image else {
+                return;
+            };
+            let height = ((image.height() as f32 * width as f32 / image.width() as f32).round()
+                as u32)
+                .max(1);
+            let resized = image::imageops::resize(image, width, height, FilterType::Triangle);
+
+            cache.texture = Some((
+                key,
+                create_rgba_texture(
+                    &constants.device,
+                    &constants.queue,
+                    "Watermark Texture",
+                    &resized,
+                ),
+            ));
+        }
+        let Some((_, texture)) = &cache.texture else {
+            return;
+        };
+
+        let size = [texture.width() as f32, texture.height() as f32];
+        let margin = MARGIN * output_size[0].min(output_size[1]);
+        let (left, top) = match watermark.corner {
+            WatermarkCorner::TopLeft => (true, true),
+            WatermarkCorner::TopRight => (false, true),
+            WatermarkCorner::BottomLeft => (true, false),
+            WatermarkCorner::BottomRight => (false, false),
+        };
+
+        let watermark_uniforms = WatermarkUniforms {
+            rect: [
+                if left {
+                    margin
+                } else {
+                    output_size[0] - margin - size[0]
+                },
+                if top {
+                    margin
+                } else {
+                    output_size[1] - margin - size[1]
+                },
+                size[0],
+                size[1],
+            ],
+            opacity: watermark.opacity.clamp(0.0, 1.0),
+            ..Default::default()
+        };
+
+        let uniform_buffer =
+            constants
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Watermark Uniform Buffer"),
+                    contents: bytemuck::cast_slice(&[watermark_uniforms]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = constants
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                    },
+                ],
+                label: Some("Watermark Bind Group"),
+            });
+
+        drop(cache);
+
+        pipeline.encoder.do_render_pass(
+            pipeline.state.get_current_texture_view(),
+            &self.render_pipeline,
+            bind_group,
+            wgpu::LoadOp::Load,
+        );
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
+pub struct WatermarkUniforms {
+    // x, y, width, height in output pixels
+    rect: [f32; 4],
+    opacity: f32,
+    _padding: [f32; 3],
+}
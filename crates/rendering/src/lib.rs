@@ -1,71 +1,130 @@
 use anyhow::Result;
 use cap_project::{
-    AspectRatio, BackgroundSource, CameraXPosition, CameraYPosition, Content, Crop, CursorEvents,
-    ProjectConfiguration, RecordingMeta, XY,
+    AspectRatio, AutoZoomConfiguration, BackgroundSource, Content, Crop, CursorEvents, CursorShape,
+    CursorTheme, OutputFrame, ProjectConfiguration, RecordingMeta, TimelineConfiguration,
+    TitleCard, TransitionKind, ZoomSegment, ZoomSegments, XY,
 };
 use composite_frame::{CompositeVideoFramePipeline, CompositeVideoFrameUniforms};
 use core::f64;
+use cursor_track::{CursorTrack, CursorTracks};
 use decoder::{spawn_decoder, AsyncVideoDecoderHandle};
-use frame_pipeline::{FramePipeline, FramePipelineEncoder, FramePipelineState};
+pub use frame_pipeline::{FramePipeline, FramePipelineEncoder, FramePipelineState};
 use futures::future::OptionFuture;
 use futures::FutureExt;
 use layers::{
-    Background, BackgroundBlurPipeline, BackgroundLayer, CameraLayer, CursorLayer, DisplayLayer,
-    GradientOrColorPipeline, ImageBackgroundPipeline,
+    AnnotationLayer, BackgroundBlurPipeline, ClickHighlightLayer, ColorGradeLayer, CursorLayer,
+    CursorTrailLayer, DisplayLayer, GradientOrColorPipeline, Hdr10Layer, ImageBackgroundPipeline,
+    KeystrokeLayer, ScreenPass, TextLayer, ToneMapLayer, TransitionLayer, WatermarkLayer,
 };
 use specta::Type;
-use std::{collections::HashMap, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, ops::Range, sync::Arc};
 use tokio::sync::mpsc;
 
-use image::GenericImageView;
 use std::{path::PathBuf, time::Instant};
+use tracing::{debug, info, warn};
 
+pub mod auto_zoom;
+mod camera_layout;
+pub mod color;
 mod composite_frame;
+pub mod conform;
 mod coord;
+mod cursor_sprites;
+mod cursor_track;
 pub mod decoder;
+mod display_tracks;
+mod frame_cache;
 mod frame_pipeline;
+mod frame_zoom;
 mod layers;
+
+pub use layers::background;
 mod project_recordings;
+mod render_graph;
 mod spring_mass_damper;
+mod text;
 mod zoom;
 
+pub use camera_layout::CameraLayout;
+pub use color::VideoColor;
+pub use conform::FrameRateConversion;
 pub use coord::*;
 pub use decoder::DecodedFrame;
+pub use display_tracks::SegmentCursors;
+pub use frame_cache::{
+    global_frame_cache, source_id, CachedFrame, EvictionPolicy, FrameCache, FrameCacheEvent,
+    FrameCacheKey, FrameCacheStats, DEFAULT_FRAME_CACHE_BYTES,
+};
+use frame_zoom::AutoFocusTracks;
+pub use frame_zoom::{FrameZoom, FrameZooms};
 pub use project_recordings::{ProjectRecordings, SegmentRecordings, Video};
+pub use render_graph::{GraphFrame, RenderGraph, RenderGraphError, RenderNode, RenderSlot};
+pub use zoom::{duration_in, duration_out, ZoomDebugInfo};
 
 use zoom::*;
 
 const STANDARD_CURSOR_HEIGHT: f32 = 75.0;
+// How many decoded frames can wait for the GPU during a render. Each holds a full-size
+// screen frame, so this bounds memory as much as it smooths over slow decodes.
+const DECODE_AHEAD_FRAMES: usize = 8;
 
 #[derive(Debug, Clone, Copy, Type)]
 pub struct RenderOptions {
     pub camera_size: Option<XY<u32>>,
     pub screen_size: XY<u32>,
+    // prefer the software adapter and draw text only with its chosen font, so output doesn't
+    // depend on the machine's GPU or installed fonts
+    pub deterministic: bool,
+    // scale recorded frames with a Lanczos filter instead of bilinear sampling, which is
+    // sharper but too slow for playback
+    pub high_quality_scaling: bool,
+    // which of `gpu_count` GPUs to render on, or the system's preferred one when None
+    pub gpu: Option<u32>,
+    // how the recorded screen is encoded, HDR or wide gamut captures being converted to SDR
+    pub screen_color: VideoColor,
+    // output PQ encoded BT.2020 frames for an HDR10 export. Experimental.
+    pub hdr10: bool,
 }
 
 #[derive(Clone)]
 pub struct RecordingSegmentDecoders {
-    screen: AsyncVideoDecoderHandle,
+    // one per display with its frame size and rate, the main display first
+    screens: Vec<(AsyncVideoDecoderHandle, XY<u32>, u32)>,
     camera: Option<AsyncVideoDecoderHandle>,
 }
 
 pub struct SegmentVideoPaths {
     pub display: PathBuf,
+    // the other displays recorded alongside `display`, in track order
+    pub additional_displays: Vec<PathBuf>,
     pub camera: Option<PathBuf>,
 }
 
 impl RecordingSegmentDecoders {
     pub async fn new(meta: &RecordingMeta, segment: SegmentVideoPaths) -> Result<Self, String> {
-        let screen = spawn_decoder(
-            "screen",
-            meta.project_path.join(segment.display),
-            match &meta.content {
-                Content::SingleSegment { segment } => segment.display.fps,
-                Content::MultipleSegments { inner } => inner.segments[0].display.fps,
-            },
-        )
-        .await
-        .map_err(|e| format!("Screen:{e}"))?;
+        let display_fps = |i: usize| match &meta.content {
+            Content::SingleSegment { segment } => segment.display.fps,
+            Content::MultipleSegments { inner } => inner.segments[0]
+                .displays()
+                .nth(i)
+                .map_or(inner.segments[0].display.fps, |d| d.fps),
+        };
+
+        let mut screens = vec![];
+        for (i, path) in std::iter::once(segment.display)
+            .chain(segment.additional_displays)
+            .enumerate()
+        {
+            let path = meta.project_path.join(path);
+            let video = Video::new(&path).map_err(|e| format!("Screen/{i}:{e}"))?;
+            let fps = display_fps(i);
+            let decoder = spawn_decoder("screen", path, fps)
+                .await
+                .map_err(|e| format!("Screen/{i}:{e}"))?;
+
+            screens.push((decoder, XY::new(video.width, video.height), fps));
+        }
+
         let camera = OptionFuture::from(segment.camera.map(|camera| {
             spawn_decoder(
                 "camera",
@@ -82,16 +141,27 @@ impl RecordingSegmentDecoders {
         .await
         .transpose()?;
 
-        Ok(Self { screen, camera })
+        Ok(Self { screens, camera })
     }
 
+    pub fn display_sizes(&self) -> Vec<XY<u32>> {
+        self.screens.iter().map(|(_, size, _)| *size).collect()
+    }
+
+    // Frames from the given display's track, or the main display's if there's no such track
     pub async fn get_frames(
         &self,
         segment_time: f32,
         needs_camera: bool,
+        display: u32,
     ) -> Option<DecodedSegmentFrames> {
+        let (screen, screen_size, _) = self
+            .screens
+            .get(display as usize)
+            .unwrap_or(&self.screens[0]);
+
         let (screen, camera) = tokio::join!(
-            self.screen.get_frame(segment_time),
+            screen.get_frame(segment_time),
             OptionFuture::from(
                 needs_camera
                     .then(|| self.camera.as_ref().map(|d| d.get_frame(segment_time)))
@@ -101,10 +171,49 @@ impl RecordingSegmentDecoders {
 
         Some(DecodedSegmentFrames {
             screen_frame: screen?,
+            screen_size: *screen_size,
             camera_frame: camera.flatten(),
             segment_time,
+            transition: None,
         })
     }
+
+    // Like `get_frames`, but mixes the recorded frames either side of `segment_time`.
+    // Only the screen is blended, the camera already delivers frames at a steady rate.
+    pub async fn get_blended_frames(
+        &self,
+        segment_time: f32,
+        needs_camera: bool,
+        display: u32,
+    ) -> Option<DecodedSegmentFrames> {
+        let (_, _, fps) = self
+            .screens
+            .get(display as usize)
+            .unwrap_or(&self.screens[0]);
+        let (_, after, weight) = conform::blend_between(segment_time as f64, *fps);
+
+        // the screen frame here is the one before `segment_time`
+        let mut frames = self.get_frames(segment_time, needs_camera, display).await?;
+
+        if weight == 0.0 {
+            return Some(frames);
+        }
+
+        // past the last recorded frame there's nothing to blend towards
+        let Some(next) = self.get_frames(after as f32, false, display).await else {
+            return Some(frames);
+        };
+
+        if !Arc::ptr_eq(&frames.screen_frame, &next.screen_frame) {
+            frames.screen_frame = Arc::new(conform::blend_frames(
+                &frames.screen_frame,
+                &next.screen_frame,
+                weight,
+            ));
+        }
+
+        Some(frames)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -123,8 +232,21 @@ pub enum RenderingError {
     ImageLoadError(String),
 }
 
+// A frame handed from the decoding task to the render loop
+enum DecodedOutputFrame {
+    Card,
+    Timeline {
+        // counted from the timeline's start, after any intro
+        frame_number: u32,
+        segment_i: u32,
+        display: u32,
+        frames: Option<DecodedSegmentFrames>,
+    },
+}
+
+#[derive(Clone)]
 pub struct RenderSegment {
-    pub cursor: Arc<CursorEvents>,
+    pub cursors: SegmentCursors,
     pub decoders: RecordingSegmentDecoders,
 }
 
@@ -137,10 +259,46 @@ pub async fn render_video_to_channel(
     fps: u32,
     resolution_base: XY<u32>,
     is_upgraded: bool,
+    frame_rate_conversion: FrameRateConversion,
+) -> Result<(), RenderingError> {
+    render_video_range_to_channel(
+        options,
+        project,
+        sender,
+        meta,
+        segments,
+        fps,
+        resolution_base,
+        is_upgraded,
+        frame_rate_conversion,
+        0..u32::MAX,
+    )
+    .await
+}
+
+// Like `render_video_to_channel`, but only renders the frames numbered within `frames`,
+// so part of a timeline can be rendered without going through what comes before it
+pub async fn render_video_range_to_channel(
+    options: RenderOptions,
+    mut project: ProjectConfiguration,
+    sender: mpsc::Sender<(RenderedFrame, u32)>,
+    meta: &RecordingMeta,
+    segments: Vec<RenderSegment>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    is_upgraded: bool,
+    frame_rate_conversion: FrameRateConversion,
+    frames: Range<u32>,
 ) -> Result<(), RenderingError> {
     let constants = RenderVideoConstants::new(options, meta).await?;
     let recordings = ProjectRecordings::new(meta);
 
+    if let Some(timeline) = project.timeline.as_mut() {
+        for warning in timeline.zoom_segments.normalize(timeline.duration()) {
+            warn!(?warning, "repaired zoom segments");
+        }
+    }
+
     ffmpeg::init().unwrap();
 
     let start_time = Instant::now();
@@ -148,49 +306,184 @@ pub async fn render_video_to_channel(
     // Get the duration from the timeline if it exists, otherwise use the longest source duration
     let duration = get_duration(&recordings, meta, &project);
 
-    let total_frames = (fps as f64 * duration).ceil() as u32;
-    println!(
-        "Final export duration: {} seconds ({} frames at {}fps)",
-        duration, total_frames, fps
-    );
+    // frames are numbered from the start of the intro, when there is one
+    let timeline_frames = (fps as f64 * duration).ceil() as u32;
+    let intro_frames = project.intro_frames(fps);
+    let total_frames = project.output_frame_count(fps, timeline_frames);
+    let end_frame = frames.end.min(total_frames);
+    info!(duration, total_frames, fps, ?frames, "starting render");
 
-    let mut frame_number = 0;
+    let start_frame = frames.start;
+    let mut rendered_frames = 0;
     let background = project.background.source.clone();
+    let zoom_segments = project
+        .timeline
+        .as_ref()
+        .map(|t| t.zoom_segments.as_slice())
+        .unwrap_or_default();
+
+    let mut frame_zooms = FrameZooms::compute(
+        &constants.options,
+        &constants.cursor_tracks,
+        &constants.focus_tracks,
+        &project,
+        zoom_segments,
+        &segments,
+        start_frame
+            .saturating_sub(intro_frames)
+            .min(timeline_frames)
+            ..end_frame.saturating_sub(intro_frames).min(timeline_frames),
+        fps,
+        resolution_base,
+    );
+    debug!(elapsed = ?start_time.elapsed(), "computed frame zooms");
 
     let mut frame_renderer = FrameRenderer::new(&constants);
 
-    loop {
-        if frame_number >= total_frames {
-            break;
+    // decoding runs ahead on its own task, so the next frames are ready by the time the GPU
+    // is done with the current one
+    let (decoded_tx, mut decoded_rx) = mpsc::channel(DECODE_AHEAD_FRAMES);
+    tokio::spawn({
+        let project = project.clone();
+        let segments = segments.clone();
+        let frames = start_frame..end_frame;
+
+        async move {
+            for output_frame_number in frames {
+                let frame_number =
+                    match project.output_frame(output_frame_number, fps, timeline_frames) {
+                        Some(OutputFrame::Timeline(frame_number)) => frame_number,
+                        Some(OutputFrame::Card(..)) => {
+                            // cards are drawn from nothing, so there's nothing to decode
+                            if decoded_tx
+                                .send((output_frame_number, DecodedOutputFrame::Card))
+                                .await
+                                .is_err()
+                            {
+                                break;
+                            }
+                            continue;
+                        }
+                        None => break,
+                    };
+
+                let time = frame_number as f64 / fps as f64;
+                // past the end of the timeline, where the outro may still follow
+                let Some((segment_time, segment_i)) = project.get_segment_time(time) else {
+                    continue;
+                };
+
+                let segment = &segments[segment_i as usize];
+                let display = project.get_segment_display(time);
+
+                let mut segment_frames = match frame_rate_conversion {
+                    FrameRateConversion::Duplicate => {
+                        segment
+                            .decoders
+                            .get_frames(segment_time as f32, !project.camera.hide, display)
+                            .await
+                    }
+                    FrameRateConversion::Blend => {
+                        segment
+                            .decoders
+                            .get_blended_frames(segment_time as f32, !project.camera.hide, display)
+                            .await
+                    }
+                };
+                if let Some(frames) = &mut segment_frames {
+                    frames.transition = decode_transition(&project, time, |i| {
+                        segments.get(i as usize).map(|s| (&s.decoders, &s.cursors))
+                    })
+                    .await;
+                }
+
+                // the render loop has stopped
+                if decoded_tx
+                    .send((
+                        output_frame_number,
+                        DecodedOutputFrame::Timeline {
+                            frame_number,
+                            segment_i,
+                            display,
+                            frames: segment_frames,
+                        },
+                    ))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
         }
+    });
 
-        let Some((segment_time, segment_i)) =
-            project.get_segment_time(frame_number as f64 / fps as f64)
-        else {
-            break;
-        };
+    while let Some((output_frame_number, decoded)) = decoded_rx.recv().await {
+        rendered_frames += 1;
+
+        let (frame_number, segment_i, display, segment_frames) = match decoded {
+            DecodedOutputFrame::Timeline {
+                frame_number,
+                segment_i,
+                display,
+                frames,
+            } => (frame_number, segment_i, display, frames),
+            DecodedOutputFrame::Card => {
+                let Some(OutputFrame::Card(card, time)) =
+                    project.output_frame(output_frame_number, fps, timeline_frames)
+                else {
+                    continue;
+                };
 
-        let segment = &segments[segment_i as usize];
+                // sized like the timeline frame it's next to
+                let frame_number = output_frame_number
+                    .saturating_sub(intro_frames)
+                    .min(timeline_frames.saturating_sub(1));
+                let time_in_timeline = frame_number as f64 / fps as f64;
+                let segment_i = project
+                    .get_segment_time(time_in_timeline)
+                    .map_or(0, |(_, segment_i)| segment_i);
+                let cursor = segments[segment_i as usize]
+                    .cursors
+                    .get(project.get_segment_display(time_in_timeline));
+                let uniforms = ProjectUniforms::new(
+                    &constants,
+                    &project,
+                    frame_number,
+                    fps,
+                    resolution_base,
+                    is_upgraded,
+                    cursor,
+                );
 
-        // do this after all usages but before any 'continue' to handle frame skip
-        let frame_number = {
-            let prev = frame_number;
-            std::mem::replace(&mut frame_number, prev + 1)
+                let frame = frame_renderer.render_card(card, time, &uniforms).await?;
+                sender.send((frame, output_frame_number)).await?;
+                continue;
+            }
         };
-
-        if let Some(segment_frames) = segment
-            .decoders
-            .get_frames(segment_time as f32, !project.camera.hide)
-            .await
-        {
-            let uniforms = ProjectUniforms::new(
+        let cursor = segments[segment_i as usize].cursors.get(display);
+
+        if let Some(segment_frames) = segment_frames {
+            let frame_zoom = frame_zooms.take(frame_number).unwrap_or_else(|| {
+                FrameZoom::new(
+                    &constants.options,
+                    &constants.cursor_tracks,
+                    &constants.focus_tracks,
+                    &project,
+                    zoom_segments,
+                    frame_number,
+                    fps,
+                    resolution_base,
+                    cursor,
+                )
+            });
+            let uniforms = ProjectUniforms::with_zoom(
                 &constants,
                 &project,
                 frame_number,
                 fps,
                 resolution_base,
                 is_upgraded,
-                &segment.cursor,
+                frame_zoom,
             );
             let frame = frame_renderer
                 .render(
@@ -198,7 +491,7 @@ pub async fn render_video_to_channel(
                     background.clone(),
                     &uniforms,
                     resolution_base,
-                    &segment.cursor,
+                    cursor,
                 )
                 .await?;
 
@@ -206,41 +499,109 @@ pub async fn render_video_to_channel(
                 continue;
             }
 
-            sender.send((frame, frame_number)).await?;
+            sender.send((frame, output_frame_number)).await?;
         }
     }
 
     let total_time = start_time.elapsed();
-    println!(
-        "Render complete. Processed {frame_number} frames in {:?} seconds",
-        total_time.as_secs_f32()
+    info!(
+        frames = rendered_frames,
+        seconds = total_time.as_secs_f32(),
+        "render complete"
     );
 
     Ok(())
 }
 
+// Frames a render of the project comes to at `fps`, with its title cards
+pub fn get_frame_count(
+    recordings: &ProjectRecordings,
+    meta: &RecordingMeta,
+    project: &ProjectConfiguration,
+    fps: u32,
+) -> u32 {
+    let duration = get_duration(recordings, meta, project);
+    project.output_frame_count(fps, (fps as f64 * duration).ceil() as u32)
+}
+
+// Renders the frame `time` seconds into the timeline on its own, as `render_video_to_channel`
+// would have rendered it. None when nothing is there to render.
+pub async fn render_single_frame(
+    constants: &RenderVideoConstants,
+    mut project: ProjectConfiguration,
+    segments: &[RenderSegment],
+    time: f64,
+    fps: u32,
+    resolution_base: XY<u32>,
+    is_upgraded: bool,
+) -> Result<Option<RenderedFrame>, RenderingError> {
+    if let Some(timeline) = project.timeline.as_mut() {
+        for warning in timeline.zoom_segments.normalize(timeline.duration()) {
+            warn!(?warning, "repaired zoom segments");
+        }
+    }
+
+    let Some((segment_time, segment_i)) = project.get_segment_time(time) else {
+        return Ok(None);
+    };
+
+    let segment = &segments[segment_i as usize];
+    let display = project.get_segment_display(time);
+    let cursor = segment.cursors.get(display);
+
+    let Some(mut segment_frames) = segment
+        .decoders
+        .get_frames(segment_time as f32, !project.camera.hide, display)
+        .await
+    else {
+        return Ok(None);
+    };
+    segment_frames.transition = decode_transition(&project, time, |i| {
+        segments.get(i as usize).map(|s| (&s.decoders, &s.cursors))
+    })
+    .await;
+
+    let frame_number = (time * fps as f64).round() as u32;
+    let uniforms = ProjectUniforms::new(
+        constants,
+        &project,
+        frame_number,
+        fps,
+        resolution_base,
+        is_upgraded,
+        cursor,
+    );
+
+    FrameRenderer::new(constants)
+        .render(
+            segment_frames,
+            project.background.source.clone(),
+            &uniforms,
+            resolution_base,
+            cursor,
+        )
+        .await
+        .map(Some)
+}
+
 pub fn get_duration(
     recordings: &ProjectRecordings,
     meta: &RecordingMeta,
     project: &ProjectConfiguration,
 ) -> f64 {
     let mut max_duration = recordings.duration();
-    println!("Initial screen recording duration: {}", max_duration);
 
     // Check camera duration if it exists
     if let Some(camera_path) = meta.content.camera_path() {
         if let Ok(camera_duration) =
             recordings.get_source_duration(&camera_path.to_path(&meta.project_path))
         {
-            println!("Camera recording duration: {}", camera_duration);
             max_duration = max_duration.max(camera_duration);
-            println!("New max duration after camera check: {}", max_duration);
         }
     }
 
     // If there's a timeline, ensure all segments extend to the max duration
     if let Some(timeline) = &project.timeline {
-        println!("Found timeline with {} segments", timeline.segments.len());
         // for (i, segment) in timeline.segments.iter().enumerate() {
         //     println!(
         //         "Segment {} - current end: {}, max_duration: {}",
@@ -252,13 +613,13 @@ pub fn get_duration(
         //     }
         // }
         let final_duration = timeline.duration();
-        println!(
-            "Final timeline duration after adjustments: {}",
-            final_duration
+        debug!(
+            segments = timeline.segments.len(),
+            final_duration, "using timeline duration"
         );
         final_duration
     } else {
-        println!("No timeline found, using max_duration: {}", max_duration);
+        debug!(max_duration, "no timeline, using recording duration");
         max_duration
     }
 }
@@ -266,6 +627,7 @@ pub fn get_duration(
 pub struct CursorTexture {
     inner: wgpu::Texture,
     hotspot: XY<f64>,
+    shape: Option<CursorShape>,
 }
 
 pub struct RenderVideoConstants {
@@ -276,6 +638,7 @@ pub struct RenderVideoConstants {
     pub options: RenderOptions,
     composite_video_frame_pipeline: CompositeVideoFramePipeline,
     pub cursor_textures: HashMap<String, CursorTexture>,
+    cursor_sprites: HashMap<(CursorTheme, CursorShape), CursorTexture>,
     gradient_or_color_pipeline: GradientOrColorPipeline,
     image_background_pipeline: ImageBackgroundPipeline,
     pub background_blur_pipeline: BackgroundBlurPipeline,
@@ -283,16 +646,77 @@ pub struct RenderVideoConstants {
     screen_frame: (wgpu::Texture, wgpu::TextureView),
     camera_frame: Option<(wgpu::Texture, wgpu::TextureView)>,
     cursor_layer: CursorLayer,
+    click_highlight_layer: ClickHighlightLayer,
+    cursor_trail_layer: CursorTrailLayer,
+    keystroke_layer: KeystrokeLayer,
+    text_layer: TextLayer,
+    annotation_layer: AnnotationLayer,
+    watermark_layer: WatermarkLayer,
+    transition_layer: TransitionLayer,
+    display_layer: DisplayLayer,
+    color_grade_layer: ColorGradeLayer,
+    tone_map_layer: ToneMapLayer,
+    hdr10_layer: Hdr10Layer,
+    screen_pass: ScreenPass,
+    cursor_tracks: CursorTracks,
+    focus_tracks: AutoFocusTracks,
+    render_graph: RenderGraph,
+}
+
+// Adapters backed by an actual GPU, in a stable order
+fn hardware_adapters(instance: &wgpu::Instance) -> Vec<wgpu::Adapter> {
+    instance
+        .enumerate_adapters(wgpu::Backends::PRIMARY)
+        .into_iter()
+        .filter(|a| a.get_info().device_type != wgpu::DeviceType::Cpu)
+        .collect()
+}
+
+// How many GPUs renders can be spread across
+pub fn gpu_count() -> usize {
+    hardware_adapters(&wgpu::Instance::new(wgpu::InstanceDescriptor::default())).len()
 }
 
 impl RenderVideoConstants {
     pub async fn new(options: RenderOptions, meta: &RecordingMeta) -> Result<Self, RenderingError> {
-        println!("Initializing wgpu...");
+        debug!("initializing wgpu");
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
-            .await
-            .ok_or(RenderingError::NoAdapter)?;
+        let fallback_adapter = if options.deterministic {
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..Default::default()
+                })
+                .await;
+            if adapter.is_none() {
+                warn!("no fallback adapter available, determinism is not guaranteed");
+            }
+            adapter
+        } else {
+            None
+        };
+        let chosen_adapter = fallback_adapter.or_else(|| {
+            options
+                .gpu
+                .and_then(|i| hardware_adapters(&instance).into_iter().nth(i as usize))
+        });
+        let adapter = match chosen_adapter {
+            Some(adapter) => adapter,
+            None => match instance
+                .request_adapter(&wgpu::RequestAdapterOptions::default())
+                .await
+            {
+                Some(adapter) => adapter,
+                // machines with no GPU, like most servers, can still render in software
+                None => instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        force_fallback_adapter: true,
+                        ..Default::default()
+                    })
+                    .await
+                    .ok_or(RenderingError::NoAdapter)?,
+            },
+        };
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
@@ -304,6 +728,7 @@ impl RenderVideoConstants {
             .await?;
 
         let cursor_textures = Self::load_cursor_textures(&device, &queue, meta);
+        let cursor_sprites = Self::create_cursor_sprites(&device, &queue);
         let composite_video_frame_pipeline = CompositeVideoFramePipeline::new(&device);
         let gradient_or_color_pipeline = GradientOrColorPipeline::new(&device);
 
@@ -366,12 +791,28 @@ impl RenderVideoConstants {
             _instance: instance,
             _adapter: adapter,
             cursor_layer: CursorLayer::new(&device),
+            click_highlight_layer: ClickHighlightLayer::new(&device),
+            cursor_trail_layer: CursorTrailLayer::new(&device),
+            keystroke_layer: KeystrokeLayer::new(&device, options.deterministic),
+            text_layer: TextLayer::new(&device),
+            annotation_layer: AnnotationLayer::new(&device),
+            watermark_layer: WatermarkLayer::new(&device),
+            transition_layer: TransitionLayer::new(&device),
+            display_layer: DisplayLayer::new(&device),
+            color_grade_layer: ColorGradeLayer::new(&device, &queue),
+            tone_map_layer: ToneMapLayer::new(&device),
+            hdr10_layer: Hdr10Layer::new(&device),
+            screen_pass: ScreenPass::default(),
+            cursor_tracks: CursorTracks::default(),
+            focus_tracks: AutoFocusTracks::default(),
+            render_graph: RenderGraph::compositor(),
             device,
             queue,
             options,
             composite_video_frame_pipeline,
             gradient_or_color_pipeline,
             cursor_textures,
+            cursor_sprites,
             image_background_pipeline,
             background_textures,
             screen_frame,
@@ -380,124 +821,176 @@ impl RenderVideoConstants {
         })
     }
 
+    // Draws `node` into every frame, where its inputs and outputs place it among the layers
+    pub fn add_render_node(
+        &mut self,
+        node: impl RenderNode + 'static,
+    ) -> Result<(), RenderGraphError> {
+        self.render_graph.add(node)
+    }
+
     fn load_cursor_textures(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         meta: &RecordingMeta,
     ) -> HashMap<String, CursorTexture> {
-        println!("Starting to load cursor textures");
-        println!("Project path: {:?}", meta.project_path);
-        // println!("Cursor images to load: {:?}", cursor.cursor_images);
-
         let mut textures = HashMap::new();
 
-        // Create the full path to the cursors directory
-        let cursors_dir = meta.project_path.join("content").join("cursors");
-        println!("Cursors directory: {:?}", cursors_dir);
-
         let cursor_images = match &meta.content {
             Content::SingleSegment { .. } => Default::default(),
             Content::MultipleSegments { inner } => inner.cursor_images(meta).unwrap_or_default(),
         };
 
         for (cursor_id, cursor) in &cursor_images.0 {
-            println!(
-                "Loading cursor image: {} -> {}",
-                cursor_id,
-                cursor.path.display()
-            );
-
-            println!("Full cursor path: {:?}", cursor);
-
             if !cursor.path.exists() {
-                println!("Cursor image file does not exist: {:?}", cursor);
+                warn!(path = %cursor.path.display(), "cursor image file does not exist");
                 continue;
             }
 
             match image::open(&cursor.path) {
                 Ok(img) => {
-                    let dimensions = img.dimensions();
-                    println!(
-                        "Loaded cursor image dimensions: {}x{}",
-                        dimensions.0, dimensions.1
-                    );
-
-                    let rgba = img.into_rgba8();
-
-                    // Create the texture
-                    let texture = device.create_texture(&wgpu::TextureDescriptor {
-                        label: Some(&format!("Cursor Texture {}", cursor_id)),
-                        size: wgpu::Extent3d {
-                            width: dimensions.0,
-                            height: dimensions.1,
-                            depth_or_array_layers: 1,
-                        },
-                        mip_level_count: 1,
-                        sample_count: 1,
-                        dimension: wgpu::TextureDimension::D2,
-                        format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                        view_formats: &[],
-                    });
-
-                    queue.write_texture(
-                        wgpu::ImageCopyTexture {
-                            texture: &texture,
-                            mip_level: 0,
-                            origin: wgpu::Origin3d::ZERO,
-                            aspect: wgpu::TextureAspect::All,
-                        },
-                        &rgba,
-                        wgpu::ImageDataLayout {
-                            offset: 0,
-                            bytes_per_row: Some(4 * dimensions.0),
-                            rows_per_image: None,
-                        },
-                        wgpu::Extent3d {
-                            width: dimensions.0,
-                            height: dimensions.1,
-                            depth_or_array_layers: 1,
-                        },
-                    );
-
                     textures.insert(
                         cursor_id.clone(),
-                        CursorTexture {
-                            inner: texture,
-                            hotspot: cursor.hotspot,
-                        },
+                        create_cursor_texture(
+                            device,
+                            queue,
+                            &format!("Cursor Texture {}", cursor_id),
+                            &img.into_rgba8(),
+                            cursor.hotspot,
+                            cursor.shape,
+                        ),
                     );
-                    println!("Successfully loaded cursor texture: {}", cursor_id);
                 }
                 Err(e) => {
-                    println!(
-                        "Failed to load cursor image {}: {}",
-                        cursor.path.display(),
-                        e
-                    );
+                    warn!(path = %cursor.path.display(), "failed to load cursor image: {e}");
                     // Don't return error, just skip this cursor image
                     continue;
                 }
             }
         }
 
-        println!(
-            "Completed loading cursor textures. Total loaded: {}",
-            textures.len()
-        );
+        debug!(count = textures.len(), "loaded cursor textures");
         textures
     }
+
+    // The themed sets are small enough to keep every sprite around,
+    // so switching theme in the editor doesn't need to touch the GPU
+    fn create_cursor_sprites(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> HashMap<(CursorTheme, CursorShape), CursorTexture> {
+        [CursorTheme::Macos, CursorTheme::Windows]
+            .into_iter()
+            .flat_map(|theme| CursorShape::ALL.map(|shape| (theme, shape)))
+            .map(|(theme, shape)| {
+                let sprite = cursor_sprites::cursor_sprite(theme, shape);
+                let texture = create_cursor_texture(
+                    device,
+                    queue,
+                    &format!("Cursor Sprite {theme:?} {shape:?}"),
+                    &sprite.image,
+                    sprite.hotspot,
+                    Some(shape),
+                );
+
+                ((theme, shape), texture)
+            })
+            .collect()
+    }
+
+    // Swaps in the theme's sprite when the recorded cursor's shape is known. Moves recorded
+    // without a cursor image are drawn as an arrow, since there's nothing else to show.
+    fn cursor_texture(&self, cursor_id: &str, theme: CursorTheme) -> Option<&CursorTexture> {
+        let recorded = self.cursor_textures.get(cursor_id);
+        if theme == CursorTheme::Recorded {
+            return recorded;
+        }
+
+        let shape = match recorded {
+            Some(texture) => texture.shape,
+            None => Some(CursorShape::Arrow),
+        };
+
+        shape
+            .and_then(|shape| self.cursor_sprites.get(&(theme, shape)))
+            .or(recorded)
+    }
+}
+
+fn create_cursor_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    rgba: &image::RgbaImage,
+    hotspot: XY<f64>,
+    shape: Option<CursorShape>,
+) -> CursorTexture {
+    CursorTexture {
+        inner: create_rgba_texture(device, queue, label, rgba),
+        hotspot,
+        shape,
+    }
+}
+
+// Uploads an image that's sampled by a layer, like cursors and overlay text
+pub fn create_rgba_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    rgba: &image::RgbaImage,
+) -> wgpu::Texture {
+    let dimensions = rgba.dimensions();
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        rgba,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * dimensions.0),
+            rows_per_image: None,
+        },
+        wgpu::Extent3d {
+            width: dimensions.0,
+            height: dimensions.1,
+            depth_or_array_layers: 1,
+        },
+    );
+
+    texture
 }
 
 #[derive(Clone, Debug)]
 pub struct ProjectUniforms {
     pub output_size: (u32, u32),
+    // seconds into the timeline
+    pub time: f64,
     pub cursor_size: f32,
     display: CompositeVideoFrameUniforms,
     camera: Option<CompositeVideoFrameUniforms>,
     pub project: ProjectConfiguration,
     pub is_upgraded: bool,
     pub zoom: InterpolatedZoom,
+    pub zoom_debug: ZoomDebugInfo,
 }
 
 #[derive(Debug, Clone)]
@@ -512,19 +1005,22 @@ impl Zoom {
     }
 }
 
-const CAMERA_PADDING: f32 = 50.0;
-
 const SCREEN_MAX_PADDING: f64 = 0.4;
 
 impl ProjectUniforms {
     fn get_crop(options: &RenderOptions, project: &ProjectConfiguration) -> Crop {
-        project.background.crop.as_ref().cloned().unwrap_or(Crop {
-            position: XY { x: 0, y: 0 },
-            size: XY {
-                x: options.screen_size.x,
-                y: options.screen_size.y,
-            },
-        })
+        project
+            .background
+            .crop
+            .as_ref()
+            .map(|crop| crop.clamped(options.screen_size))
+            .unwrap_or(Crop {
+                position: XY { x: 0, y: 0 },
+                size: XY {
+                    x: options.screen_size.x,
+                    y: options.screen_size.y,
+                },
+            })
     }
 
     fn get_padding(options: &RenderOptions, project: &ProjectConfiguration) -> f64 {
@@ -658,6 +1154,20 @@ impl ProjectUniforms {
         end - display_offset
     }
 
+    // Output pixels per unit of cursor height, for overlays that should stay in proportion
+    // with the cursor as the display is padded or zoomed
+    pub fn cursor_scale(
+        options: &RenderOptions,
+        project: &ProjectConfiguration,
+        resolution_base: XY<u32>,
+        zoom: &InterpolatedZoom,
+    ) -> f32 {
+        let output_size = Self::get_output_size(options, project, resolution_base);
+        let display_size = Self::display_size(options, project, resolution_base);
+
+        zoom.display_amount() as f32 * (display_size.coord.x as f32 / output_size.0 as f32)
+    }
+
     pub fn new(
         constants: &RenderVideoConstants,
         project: &ProjectConfiguration,
@@ -666,10 +1176,54 @@ impl ProjectUniforms {
         resolution_base: XY<u32>,
         is_upgraded: bool,
         cursor_events: &CursorEvents,
+    ) -> Self {
+        let zoom_segments = project
+            .timeline
+            .as_ref()
+            .map(normalized_zoom_segments)
+            .unwrap_or_default();
+        let frame_zoom = FrameZoom::new(
+            &constants.options,
+            &constants.cursor_tracks,
+            &constants.focus_tracks,
+            project,
+            &zoom_segments,
+            frame_number,
+            fps,
+            resolution_base,
+            cursor_events,
+        );
+
+        Self::with_zoom(
+            constants,
+            project,
+            frame_number,
+            fps,
+            resolution_base,
+            is_upgraded,
+            frame_zoom,
+        )
+    }
+
+    // Like `new`, but with the frame's zoom already computed by a `FrameZooms` pre-pass
+    pub fn with_zoom(
+        constants: &RenderVideoConstants,
+        project: &ProjectConfiguration,
+        frame_number: u32,
+        fps: u32,
+        resolution_base: XY<u32>,
+        is_upgraded: bool,
+        frame_zoom: FrameZoom,
     ) -> Self {
         let options = &constants.options;
         let output_size = Self::get_output_size(options, project, resolution_base);
-        let frame_time = frame_number as f32 / fps as f32;
+        let lanczos = if options.high_quality_scaling {
+            1.0
+        } else {
+            0.0
+        };
+        // seeds the motion blur's jitter, so it's the same for the frame on every render
+        let seed = project.determinism.frame_seed(frame_number) as u32;
 
         // let zoom_keyframes = ZoomKeyframes::new(project);
         // let current_zoom = zoom_keyframes.interpolate(time as f64);
@@ -696,16 +1250,13 @@ impl ProjectUniforms {
 
         let crop = Self::get_crop(options, project);
 
-        let segment_cursor = SegmentsCursor::new(
-            frame_time as f64,
-            project
-                .timeline
-                .as_ref()
-                .map(|t| t.zoom_segments.as_slice())
-                .unwrap_or(&[]),
-        );
-
-        let zoom = InterpolatedZoom::new(segment_cursor);
+        let FrameZoom {
+            zoom,
+            zoom_debug,
+            camera_dodge,
+        } = frame_zoom;
+        let display_offset = Self::display_offset(options, project, resolution_base);
+        let display_size = Self::display_size(options, project, resolution_base);
 
         let display = {
             let output_size = XY::new(output_size.0 as f64, output_size.1 as f64);
@@ -720,9 +1271,6 @@ impl ProjectUniforms {
                 (crop.position.y + crop.size.y) as f64,
             ));
 
-            let display_offset = Self::display_offset(options, project, resolution_base);
-            let display_size = Self::display_size(options, project, resolution_base);
-
             let end = Coord::new(output_size) - display_offset;
 
             let (zoom_start, zoom_end) = (
@@ -768,7 +1316,16 @@ impl ProjectUniforms {
                     .advanced_shadow
                     .as_ref()
                     .map_or(50.0, |s| s.blur),
-                _padding: [0.0; 3],
+                shadow_offset: project
+                    .background
+                    .advanced_shadow
+                    .as_ref()
+                    .map_or([0.0, 0.0], |s| [s.offset_x, s.offset_y]),
+                border_width: 0.0,
+                lanczos,
+                border_color: [0.0; 4],
+                seed,
+                _padding: [0; 3],
             }
         };
 
@@ -778,44 +1335,16 @@ impl ProjectUniforms {
             .map(|camera_size| {
                 let output_size = [output_size.0 as f32, output_size.1 as f32];
                 let frame_size = [camera_size.x as f32, camera_size.y as f32];
-                let min_axis = output_size[0].min(output_size[1]);
-
-                // Calculate camera size based on zoom
-                let base_size = project.camera.size / 100.0;
-                let zoom_size = project
-                    .camera
-                    .zoom_size
-                    .unwrap_or(cap_project::Camera::default_zoom_size())
-                    / 100.0;
-
-                let zoomed_size =
-                    (zoom.t as f32) * zoom_size * base_size + (1.0 - zoom.t as f32) * base_size;
-
-                let size = [
-                    min_axis * zoomed_size + CAMERA_PADDING,
-                    min_axis * zoomed_size + CAMERA_PADDING,
-                ];
-
-                let position = {
-                    let x = match &project.camera.position.x {
-                        CameraXPosition::Left => CAMERA_PADDING,
-                        CameraXPosition::Center => output_size[0] / 2.0 - (size[0]) / 2.0,
-                        CameraXPosition::Right => output_size[0] - CAMERA_PADDING - size[0],
-                    };
-                    let y = match &project.camera.position.y {
-                        CameraYPosition::Top => CAMERA_PADDING,
-                        CameraYPosition::Bottom => output_size[1] - size[1] - CAMERA_PADDING,
-                    };
 
-                    [x, y]
-                };
-
-                let target_bounds = [
-                    position[0],
-                    position[1],
-                    position[0] + size[0],
-                    position[1] + size[1],
-                ];
+                let layout = CameraLayout::new(
+                    project,
+                    XY::new(output_size[0], output_size[1]),
+                    zoom.t as f32,
+                    frame_number as f64 / fps as f64,
+                    camera_dodge.map(|p| XY::new(p.x as f32, p.y as f32)),
+                );
+                let size = [layout.size.x, layout.size.y];
+                let target_bounds = layout.bounds();
 
                 // Calculate camera motion blur based on zoom transition
                 let camera_motion_blur = 0.0;
@@ -855,22 +1384,75 @@ impl ProjectUniforms {
                         .advanced_shadow
                         .as_ref()
                         .map_or(50.0, |s| s.blur),
-                    _padding: [0.0; 3],
+                    shadow_offset: project
+                        .camera
+                        .advanced_shadow
+                        .as_ref()
+                        .map_or([0.0, 0.0], |s| [s.offset_x, s.offset_y]),
+                    border_width: project
+                        .camera
+                        .border
+                        .as_ref()
+                        .map_or(0.0, |b| b.width / 100.0 * size[0]),
+                    lanczos,
+                    border_color: project.camera.border.as_ref().map_or([0.0; 4], |b| {
+                        [
+                            srgb_to_linear(b.color[0]),
+                            srgb_to_linear(b.color[1]),
+                            srgb_to_linear(b.color[2]),
+                            1.0,
+                        ]
+                    }),
+                    seed,
+                    _padding: [0; 3],
                 }
             });
 
         Self {
             output_size,
+            time: frame_number as f64 / fps as f64,
             cursor_size: project.cursor.size as f32,
             display,
             camera,
             project: project.clone(),
             is_upgraded,
             zoom,
+            zoom_debug,
         }
     }
 }
 
+// Zoom interpolation assumes sorted, non-overlapping segments, which an edited or
+// hand-written project can't be trusted to have
+fn normalized_zoom_segments(timeline: &TimelineConfiguration) -> Cow<'_, [ZoomSegment]> {
+    let duration = timeline.duration();
+
+    if timeline.zoom_segments.is_normalized(duration) {
+        return Cow::Borrowed(&timeline.zoom_segments);
+    }
+
+    let mut segments = timeline.zoom_segments.clone();
+    segments.normalize(duration);
+    Cow::Owned(segments)
+}
+
+// Prefers the UI element under the cursor when one was recorded,
+// so Auto zoom frames the whole control instead of the exact cursor point.
+// While scrolling, the target leads in the scroll direction to show what's coming into view.
+fn auto_zoom_target(
+    cursor_events: &CursorEvents,
+    cursor_track: &CursorTrack,
+    config: &AutoZoomConfiguration,
+    time_secs: f64,
+) -> Option<XY<f64>> {
+    let target = match cursor_events.element_at(time_secs * 1000.0) {
+        Some(element) => element.center(),
+        None => cursor_track.move_at(time_secs).map(|m| XY::new(m.x, m.y))?,
+    };
+
+    Some(target + cursor_events.scroll_direction_at(time_secs * 1000.0) * config.scroll_lookahead)
+}
+
 #[derive(Clone)]
 pub struct RenderedFrame {
     pub data: Vec<u8>,
@@ -881,8 +1463,49 @@ pub struct RenderedFrame {
 
 pub struct DecodedSegmentFrames {
     pub screen_frame: DecodedFrame,
+    pub screen_size: XY<u32>,
     pub camera_frame: Option<DecodedFrame>,
     pub segment_time: f32,
+    pub transition: Option<TransitionFrames>,
+}
+
+// The segment on the other side of a cut, blended with a frame's own while a transition's
+// under way
+pub struct TransitionFrames {
+    pub kind: TransitionKind,
+    pub progress: f32,
+    pub frames: Box<DecodedSegmentFrames>,
+    pub cursor: Arc<CursorEvents>,
+    // whether these frames are the ones coming in, rather than going out
+    pub incoming: bool,
+}
+
+// The frames a transition under way at `time` blends with, from the segment on the other
+// side of the cut from the one playing. `segment` gives a recording segment's decoders
+// and cursors.
+pub async fn decode_transition<'a>(
+    project: &ProjectConfiguration,
+    time: f64,
+    segment: impl Fn(u32) -> Option<(&'a RecordingSegmentDecoders, &'a SegmentCursors)>,
+) -> Option<TransitionFrames> {
+    let active = project.timeline.as_ref()?.transition_at(time)?;
+
+    // the playing segment is the outgoing one until the cut
+    let incoming = active.progress < 0.5;
+    let side = if incoming { active.to } else { active.from };
+
+    let (decoders, cursors) = segment(side.recording_segment)?;
+    let frames = decoders
+        .get_frames(side.time as f32, !project.camera.hide, side.display)
+        .await?;
+
+    Some(TransitionFrames {
+        kind: active.transition.kind,
+        progress: active.progress as f32,
+        frames: Box::new(frames),
+        cursor: cursors.get(side.display).clone(),
+        incoming,
+    })
 }
 
 pub struct FrameRenderer<'a> {
@@ -951,20 +1574,57 @@ impl<'a> FrameRenderer<'a> {
         )
         .await
     }
+
+    // Renders a frame of a title card, `time` seconds in
+    pub async fn render_card(
+        &mut self,
+        card: &TitleCard,
+        time: f64,
+        uniforms: &ProjectUniforms,
+    ) -> Result<RenderedFrame, RenderingError> {
+        self.update_output_textures(uniforms.output_size.0, uniforms.output_size.1);
+        let textures = self.output_textures.as_ref().unwrap();
+
+        let mut state = FramePipelineState::new(self.constants, uniforms, textures);
+        let mut encoder = FramePipelineEncoder::new(&state);
+
+        {
+            let mut pipeline = FramePipeline {
+                state: &mut state,
+                encoder: &mut encoder,
+            };
+
+            self.constants
+                .text_layer
+                .render_card(&mut pipeline, card, time);
+
+            self.constants.watermark_layer.render(&mut pipeline);
+        }
+
+        let padded_bytes_per_row = encoder.padded_bytes_per_row(&state);
+        let image_data = encoder.copy_output(state).await?;
+
+        Ok(RenderedFrame {
+            data: image_data,
+            padded_bytes_per_row,
+            width: uniforms.output_size.0,
+            height: uniforms.output_size.1,
+        })
+    }
 }
 
 // TODO: reuse as many resources as possible
 // https://github.com/gfx-rs/wgpu/wiki/Encapsulating-Graphics-Work
 async fn produce_frame(
     constants: &RenderVideoConstants,
-    segment_frames: DecodedSegmentFrames,
+    mut segment_frames: DecodedSegmentFrames,
     background: BackgroundSource,
     uniforms: &ProjectUniforms,
     resolution_base: XY<u32>,
     textures: &(wgpu::Texture, wgpu::Texture),
     cursor: &CursorEvents,
 ) -> Result<RenderedFrame, RenderingError> {
-    let background = Background::from(background);
+    let transition = segment_frames.transition.take();
 
     let mut state = FramePipelineState::new(constants, uniforms, textures);
     let mut encoder = FramePipelineEncoder::new(&state);
@@ -975,37 +1635,19 @@ async fn produce_frame(
             encoder: &mut encoder,
         };
 
-        BackgroundLayer::render(&mut pipeline, background).await?;
-
-        DisplayLayer::render(&mut pipeline, &segment_frames);
-
-        constants.cursor_layer.render(
-            &mut pipeline,
-            &segment_frames,
-            resolution_base,
-            &cursor,
-            &uniforms.zoom,
-        );
-
-        if let (
-            Some(camera_size),
-            Some(camera_frame),
-            Some(uniforms),
-            Some((texture, texture_view)),
-        ) = (
-            constants.options.camera_size,
-            &segment_frames.camera_frame,
-            &uniforms.camera,
-            &constants.camera_frame,
-        ) {
-            CameraLayer::render(
+        constants
+            .render_graph
+            .run(
                 &mut pipeline,
-                camera_size,
-                camera_frame,
-                uniforms,
-                (texture, texture_view),
-            );
-        }
+                &GraphFrame {
+                    segment_frames: &segment_frames,
+                    background: &background,
+                    resolution_base,
+                    cursor,
+                    transition: transition.as_ref(),
+                },
+            )
+            .await?;
     }
 
     let padded_bytes_per_row = encoder.padded_bytes_per_row(&state);
@@ -1081,6 +1723,66 @@ pub fn create_shader_render_pipeline(
     })
 }
 
+// For layers drawn over the frame with premultiplied alpha, as a single full-frame quad
+pub fn create_overlay_render_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    shader: wgpu::ShaderModuleDescriptor,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(shader);
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let empty_constants: HashMap<String, f64> = HashMap::new();
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &empty_constants,
+                zero_initialize_workgroup_memory: false,
+                vertex_pulling_transform: false,
+            },
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &empty_constants,
+                zero_initialize_workgroup_memory: false,
+                vertex_pulling_transform: false,
+            },
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
 fn srgb_to_linear(c: u16) -> f32 {
     let c = c as f32 / 255.0;
     if c <= 0.04045 {
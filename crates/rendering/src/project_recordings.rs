@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::RecordingMeta;
+use crate::{RecordingMeta, VideoColor};
 use serde::Serialize;
 use specta::Type;
 
@@ -10,6 +10,7 @@ pub struct Video {
     pub width: u32,
     pub height: u32,
     pub fps: u32,
+    pub color: VideoColor,
 }
 
 impl Video {
@@ -35,6 +36,7 @@ impl Video {
             height: video_decoder.height(),
             duration: input.duration() as f64 / 1_000_000.0,
             fps: fps.round() as u32,
+            color: VideoColor::from_decoder(&video_decoder),
         })
     }
 
@@ -90,8 +92,10 @@ impl ProjectRecordings {
 
                 vec![SegmentRecordings {
                     display,
+                    additional_displays: vec![],
                     camera,
                     audio,
+                    system_audio: None,
                 }]
             }
             crate::Content::MultipleSegments { inner } => inner
@@ -100,6 +104,13 @@ impl ProjectRecordings {
                 .map(|s| {
                     let display = Video::new(&meta.path(&s.display.path))
                         .expect("Failed to read display video");
+                    let additional_displays = s
+                        .additional_displays
+                        .iter()
+                        .map(|d| {
+                            Video::new(&meta.path(&d.path)).expect("Failed to read display video")
+                        })
+                        .collect();
                     let camera = s.camera.as_ref().map(|camera| {
                         Video::new(&meta.path(&camera.path)).expect("Failed to read camera video")
                     });
@@ -107,11 +118,17 @@ impl ProjectRecordings {
                         .audio
                         .as_ref()
                         .map(|audio| Audio::new(&meta.path(&audio.path)));
+                    let system_audio = s
+                        .system_audio
+                        .as_ref()
+                        .map(|audio| Audio::new(&meta.path(&audio.path)));
 
                     SegmentRecordings {
                         display,
+                        additional_displays,
                         camera,
                         audio,
+                        system_audio,
                     }
                 })
                 .collect(),
@@ -124,6 +141,10 @@ impl ProjectRecordings {
         self.segments.iter().map(|s| s.duration()).sum()
     }
 
+    pub fn segment_durations(&self) -> Vec<f64> {
+        self.segments.iter().map(|s| s.duration()).collect()
+    }
+
     pub fn get_source_duration(&self, path: &PathBuf) -> Result<f64, String> {
         Video::new(path).map(|v| v.duration)
     }
@@ -132,8 +153,10 @@ impl ProjectRecordings {
 #[derive(Debug, Clone, Serialize, Type)]
 pub struct SegmentRecordings {
     pub display: Video,
+    pub additional_displays: Vec<Video>,
     pub camera: Option<Video>,
     pub audio: Option<Audio>,
+    pub system_audio: Option<Audio>,
 }
 
 impl SegmentRecordings {
@@ -142,6 +165,7 @@ impl SegmentRecordings {
             Some(self.display.duration),
             self.camera.as_ref().map(|s| s.duration),
             self.audio.as_ref().map(|s| s.duration),
+            self.system_audio.as_ref().map(|s| s.duration),
         ]
         .into_iter()
         .flatten()
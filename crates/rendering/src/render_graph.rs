@@ -0,0 +1,803 @@
+use cap_project::{BackgroundSource, CursorEvents, XY};
+use futures::future::{self, BoxFuture};
+
+use crate::{
+    frame_pipeline::FramePipeline,
+    layers::{Background, BackgroundLayer, CameraLayer, DisplayLayer},
+    DecodedSegmentFrames, RenderingError, TransitionFrames,
+};
+
+// What's been drawn into a frame so far. Nodes read slots other nodes have written, which
+// decides the order they're drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderSlot {
+    // the recording's frames on the GPU, ready to be sampled
+    Source,
+    Background,
+    // the display placed, cropped and zoomed over the background
+    Screen,
+    // click highlights, the cursor and its trail over the screen
+    Cursor,
+    // everything that belongs to the recording, the camera included
+    Recording,
+    // the recording with any transition to the other side of a cut blended in
+    Timeline,
+    // annotations and text laid over the timeline
+    Overlays,
+    Output,
+}
+
+impl RenderSlot {
+    // Recording slots are drawn once for each side of a transition
+    fn is_recording(self) -> bool {
+        self <= RenderSlot::Recording
+    }
+}
+
+// What a node is given to draw a frame with
+pub struct GraphFrame<'a> {
+    pub segment_frames: &'a DecodedSegmentFrames,
+    pub background: &'a BackgroundSource,
+    pub resolution_base: XY<u32>,
+    pub cursor: &'a CursorEvents,
+    pub transition: Option<&'a TransitionFrames>,
+}
+
+// A layer or effect drawn into the frame. Nodes draw onto the pipeline's current texture,
+// switching output when they need to sample what's already there.
+pub trait RenderNode: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // every slot here is drawn before this node is. Nodes that also output a slot they read
+    // change it in place, and are drawn in the order they were added.
+    fn inputs(&self) -> &[RenderSlot];
+
+    fn outputs(&self) -> &[RenderSlot];
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>>;
+
+    // Whether the node draws part of the recording, rather than something over the timeline
+    fn is_recording(&self) -> bool {
+        self.outputs().iter().all(|slot| slot.is_recording())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderGraphError {
+    #[error("Node '{node}' reads {slot:?}, which nothing draws")]
+    MissingInput {
+        node: &'static str,
+        slot: RenderSlot,
+    },
+    #[error("Nodes {0:?} wait on each other")]
+    Cycle(Vec<&'static str>),
+}
+
+// The nodes a frame is composited from, in the order they're drawn
+#[derive(Default)]
+pub struct RenderGraph {
+    nodes: Vec<Box<dyn RenderNode>>,
+}
+
+impl RenderGraph {
+    pub fn new(nodes: Vec<Box<dyn RenderNode>>) -> Result<Self, RenderGraphError> {
+        let order = order(&nodes)?;
+
+        let mut nodes = nodes.into_iter().map(Some).collect::<Vec<_>>();
+        let nodes = order.into_iter().filter_map(|i| nodes[i].take()).collect();
+
+        Ok(Self { nodes })
+    }
+
+    // The layers every frame is composited from
+    pub fn compositor() -> Self {
+        Self::new(vec![
+            Box::new(UploadNode),
+            Box::new(ToneMapNode),
+            Box::new(ColorGradeNode),
+            Box::new(BackgroundNode),
+            Box::new(ScreenNode),
+            Box::new(ClickHighlightNode),
+            Box::new(CursorTrailNode),
+            Box::new(CursorNode),
+            Box::new(KeystrokeNode),
+            Box::new(CameraNode),
+            Box::new(TransitionNode),
+            Box::new(AnnotationNode),
+            Box::new(TextNode),
+            Box::new(WatermarkNode),
+            Box::new(Hdr10Node),
+        ])
+        .expect("compositor nodes should form a graph")
+    }
+
+    // Adds an effect, drawn wherever its inputs and outputs place it. Nodes that could go
+    // in either order are drawn in the order they were added.
+    pub fn add(&mut self, node: impl RenderNode + 'static) -> Result<(), RenderGraphError> {
+        let mut nodes = std::mem::take(&mut self.nodes);
+        nodes.push(Box::new(node));
+
+        if let Err(e) = order(&nodes) {
+            nodes.pop();
+            self.nodes = nodes;
+            return Err(e);
+        }
+
+        *self = Self::new(nodes)?;
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.nodes.iter().map(|node| node.name())
+    }
+
+    pub async fn run(
+        &self,
+        pipeline: &mut FramePipeline<'_, '_>,
+        frame: &GraphFrame<'_>,
+    ) -> Result<(), RenderingError> {
+        // the other side of the cut is drawn first and held to blend with this side
+        if let Some(transition) = frame.transition {
+            let other_side = GraphFrame {
+                segment_frames: &transition.frames,
+                background: frame.background,
+                resolution_base: frame.resolution_base,
+                cursor: &transition.cursor,
+                transition: None,
+            };
+            self.run_stage(pipeline, &other_side, true).await?;
+
+            let constants = pipeline.state.constants;
+            constants.transition_layer.hold(pipeline);
+            // this side's frames are uploaded to the same textures
+            pipeline.encoder.flush(pipeline.state);
+        }
+
+        self.run_stage(pipeline, frame, true).await?;
+        self.run_stage(pipeline, frame, false).await
+    }
+
+    async fn run_stage(
+        &self,
+        pipeline: &mut FramePipeline<'_, '_>,
+        frame: &GraphFrame<'_>,
+        recording: bool,
+    ) -> Result<(), RenderingError> {
+        for node in self.nodes.iter().filter(|n| n.is_recording() == recording) {
+            node.render(pipeline, frame).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Indices of `nodes` in the order they can be drawn in, keeping the order they're given in
+// where it doesn't matter
+fn order(nodes: &[Box<dyn RenderNode>]) -> Result<Vec<usize>, RenderGraphError> {
+    for node in nodes {
+        for slot in node.inputs() {
+            if !nodes.iter().any(|n| n.outputs().contains(slot)) {
+                return Err(RenderGraphError::MissingInput {
+                    node: node.name(),
+                    slot: *slot,
+                });
+            }
+        }
+    }
+
+    let modifies = |i: usize, slot: &RenderSlot| {
+        nodes[i].inputs().contains(slot) && nodes[i].outputs().contains(slot)
+    };
+
+    // node `j` waits on node `i` when `i` draws something `j` reads, unless both change it
+    // in place and `i` was added later
+    let waits_on = |j: usize, i: usize| {
+        i != j
+            && nodes[j].inputs().iter().any(|slot| {
+                nodes[i].outputs().contains(slot)
+                    && !(i > j && modifies(i, slot) && modifies(j, slot))
+            })
+    };
+
+    let mut placed = vec![false; nodes.len()];
+    let mut order = Vec::with_capacity(nodes.len());
+
+    while order.len() < nodes.len() {
+        let next = (0..nodes.len())
+            .find(|&j| !placed[j] && (0..nodes.len()).all(|i| placed[i] || !waits_on(j, i)));
+
+        let Some(next) = next else {
+            return Err(RenderGraphError::Cycle(
+                (0..nodes.len())
+                    .filter(|&i| !placed[i])
+                    .map(|i| nodes[i].name())
+                    .collect(),
+            ));
+        };
+
+        placed[next] = true;
+        order.push(next);
+    }
+
+    Ok(order)
+}
+
+fn drawn<'a>() -> BoxFuture<'a, Result<(), RenderingError>> {
+    Box::pin(future::ready(Ok(())))
+}
+
+struct UploadNode;
+
+impl RenderNode for UploadNode {
+    fn name(&self) -> &'static str {
+        "upload"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants
+            .display_layer
+            .upload(pipeline, frame.segment_frames);
+        drawn()
+    }
+}
+
+// Converts HDR and wide gamut recordings to SDR BT.709 before anything else samples them
+struct ToneMapNode;
+
+impl RenderNode for ToneMapNode {
+    fn name(&self) -> &'static str {
+        "tone_map"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants.tone_map_layer.render(pipeline);
+        drawn()
+    }
+}
+
+// Grades the uploaded screen in place, so the screen and anything sampling it are graded
+// but the background isn't
+struct ColorGradeNode;
+
+impl RenderNode for ColorGradeNode {
+    fn name(&self) -> &'static str {
+        "color_grade"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants.color_grade_layer.render(pipeline);
+        drawn()
+    }
+}
+
+struct BackgroundNode;
+
+impl RenderNode for BackgroundNode {
+    fn name(&self) -> &'static str {
+        "background"
+    }
+
+    // blurred backgrounds sample the screen
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Source]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Background]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        Box::pin(BackgroundLayer::render(
+            pipeline,
+            Background::from(frame.background.clone()),
+        ))
+    }
+}
+
+// The zoom and crop are applied here too, through the display's uniforms
+struct ScreenNode;
+
+impl RenderNode for ScreenNode {
+    fn name(&self) -> &'static str {
+        "screen"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Background]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Screen]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        DisplayLayer::render(pipeline);
+        drawn()
+    }
+}
+
+struct ClickHighlightNode;
+
+impl RenderNode for ClickHighlightNode {
+    fn name(&self) -> &'static str {
+        "click_highlight"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Screen]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Cursor]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+        constants.click_highlight_layer.render(
+            pipeline,
+            frame.segment_frames,
+            frame.resolution_base,
+            frame.cursor,
+            &uniforms.zoom,
+        );
+        drawn()
+    }
+}
+
+struct CursorTrailNode;
+
+impl RenderNode for CursorTrailNode {
+    fn name(&self) -> &'static str {
+        "cursor_trail"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Screen]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Cursor]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+        constants.cursor_trail_layer.render(
+            pipeline,
+            frame.segment_frames,
+            frame.resolution_base,
+            frame.cursor,
+            &uniforms.zoom,
+        );
+        drawn()
+    }
+}
+
+struct CursorNode;
+
+impl RenderNode for CursorNode {
+    fn name(&self) -> &'static str {
+        "cursor"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Screen]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Cursor]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+        constants.cursor_layer.render(
+            pipeline,
+            frame.segment_frames,
+            frame.resolution_base,
+            frame.cursor,
+            &uniforms.zoom,
+        );
+        drawn()
+    }
+}
+
+struct KeystrokeNode;
+
+impl RenderNode for KeystrokeNode {
+    fn name(&self) -> &'static str {
+        "keystroke"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Cursor]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Recording]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants
+            .keystroke_layer
+            .render(pipeline, frame.segment_frames, frame.cursor);
+        drawn()
+    }
+}
+
+struct CameraNode;
+
+impl RenderNode for CameraNode {
+    fn name(&self) -> &'static str {
+        "camera"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Cursor]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Recording]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        let uniforms = pipeline.state.uniforms;
+
+        if let (
+            Some(camera_size),
+            Some(camera_frame),
+            Some(uniforms),
+            Some((texture, texture_view)),
+        ) = (
+            constants.options.camera_size,
+            &frame.segment_frames.camera_frame,
+            &uniforms.camera,
+            &constants.camera_frame,
+        ) {
+            CameraLayer::render(
+                pipeline,
+                camera_size,
+                camera_frame,
+                uniforms,
+                (texture, texture_view),
+            );
+        }
+
+        drawn()
+    }
+}
+
+struct TransitionNode;
+
+impl RenderNode for TransitionNode {
+    fn name(&self) -> &'static str {
+        "transition"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Recording]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Timeline]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        if let Some(transition) = frame.transition {
+            let constants = pipeline.state.constants;
+            constants.transition_layer.render(pipeline, transition);
+        }
+        drawn()
+    }
+}
+
+struct AnnotationNode;
+
+impl RenderNode for AnnotationNode {
+    fn name(&self) -> &'static str {
+        "annotation"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Timeline]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Overlays]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        frame: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants
+            .annotation_layer
+            .render(pipeline, frame.resolution_base);
+        drawn()
+    }
+}
+
+struct TextNode;
+
+impl RenderNode for TextNode {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Timeline]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Overlays]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants.text_layer.render(pipeline);
+        drawn()
+    }
+}
+
+struct WatermarkNode;
+
+impl RenderNode for WatermarkNode {
+    fn name(&self) -> &'static str {
+        "watermark"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Overlays]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Output]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        constants.watermark_layer.render(pipeline);
+        drawn()
+    }
+}
+
+// Re-encodes the finished frame as HDR10 when exporting it
+struct Hdr10Node;
+
+impl RenderNode for Hdr10Node {
+    fn name(&self) -> &'static str {
+        "hdr10"
+    }
+
+    fn inputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Output]
+    }
+
+    fn outputs(&self) -> &[RenderSlot] {
+        &[RenderSlot::Output]
+    }
+
+    fn render<'a>(
+        &'a self,
+        pipeline: &'a mut FramePipeline<'_, '_>,
+        _: &'a GraphFrame<'_>,
+    ) -> BoxFuture<'a, Result<(), RenderingError>> {
+        let constants = pipeline.state.constants;
+        if constants.options.hdr10 {
+            constants.hdr10_layer.render(pipeline);
+        }
+        drawn()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestNode {
+        name: &'static str,
+        inputs: Vec<RenderSlot>,
+        outputs: Vec<RenderSlot>,
+    }
+
+    impl RenderNode for TestNode {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn inputs(&self) -> &[RenderSlot] {
+            &self.inputs
+        }
+
+        fn outputs(&self) -> &[RenderSlot] {
+            &self.outputs
+        }
+
+        fn render<'a>(
+            &'a self,
+            _: &'a mut FramePipeline<'_, '_>,
+            _: &'a GraphFrame<'_>,
+        ) -> BoxFuture<'a, Result<(), RenderingError>> {
+            drawn()
+        }
+    }
+
+    fn node(name: &'static str, inputs: &[RenderSlot], outputs: &[RenderSlot]) -> TestNode {
+        TestNode {
+            name,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+        }
+    }
+
+    #[test]
+    fn compositor_draws_layers_in_the_usual_order() {
+        assert_eq!(
+            RenderGraph::compositor().names().collect::<Vec<_>>(),
+            [
+                "upload",
+                "tone_map",
+                "color_grade",
+                "background",
+                "screen",
+                "click_highlight",
+                "cursor_trail",
+                "cursor",
+                "keystroke",
+                "camera",
+                "transition",
+                "annotation",
+                "text",
+                "watermark",
+                "hdr10",
+            ]
+        );
+    }
+
+    #[test]
+    fn added_nodes_are_drawn_after_their_inputs() {
+        let mut graph = RenderGraph::compositor();
+        graph
+            .add(node(
+                "grade",
+                &[RenderSlot::Timeline],
+                &[RenderSlot::Overlays],
+            ))
+            .unwrap();
+
+        let names = graph.names().collect::<Vec<_>>();
+        let position = |name| names.iter().position(|n| *n == name).unwrap();
+
+        assert!(position("grade") > position("transition"));
+        assert!(position("grade") < position("watermark"));
+    }
+
+    #[test]
+    fn nodes_changing_a_slot_in_place_keep_their_order() {
+        let graph = RenderGraph::new(vec![
+            Box::new(node("source", &[], &[RenderSlot::Source])),
+            Box::new(node("a", &[RenderSlot::Source], &[RenderSlot::Source])),
+            Box::new(node("b", &[RenderSlot::Source], &[RenderSlot::Source])),
+            Box::new(node("screen", &[RenderSlot::Source], &[RenderSlot::Screen])),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            graph.names().collect::<Vec<_>>(),
+            ["source", "a", "b", "screen"]
+        );
+    }
+
+    #[test]
+    fn rejects_nodes_reading_what_nothing_draws() {
+        let mut graph =
+            RenderGraph::new(vec![Box::new(node("a", &[], &[RenderSlot::Background]))]).unwrap();
+
+        assert!(matches!(
+            graph.add(node("b", &[RenderSlot::Screen], &[RenderSlot::Output])),
+            Err(RenderGraphError::MissingInput {
+                node: "b",
+                slot: RenderSlot::Screen
+            })
+        ));
+        assert_eq!(graph.names().collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let result = RenderGraph::new(vec![
+            Box::new(node("a", &[RenderSlot::Screen], &[RenderSlot::Background])),
+            Box::new(node("b", &[RenderSlot::Background], &[RenderSlot::Screen])),
+        ]);
+
+        assert!(matches!(result, Err(RenderGraphError::Cycle(nodes)) if nodes == ["a", "b"]));
+    }
+}
@@ -0,0 +1,84 @@
+struct Uniforms {
+    // opposite corners of the masked region, in output pixels
+    points: vec4<f32>,
+    color: vec4<f32>,
+    output_size: vec2<f32>,
+    // the blur radius or pixel size, in output pixels
+    size: f32,
+    reach: f32,
+    kind: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+};
+
+const KIND_BLUR: u32 = 3u;
+const KIND_PIXELATE: u32 = 4u;
+
+// samples taken across each side of the blur kernel and of each pixelated block
+const BLUR_SAMPLES: i32 = 6;
+const BLOCK_SAMPLES: i32 = 4;
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_frame: texture_2d<f32>;
+@group(0) @binding(2) var s_frame: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn sample_frame(p: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(t_frame, s_frame, p / u.output_size, 0.0);
+}
+
+// the frame is copied over as it is outside the region, and hidden inside it
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position.xy;
+    let low = min(u.points.xy, u.points.zw);
+    let high = max(u.points.xy, u.points.zw);
+
+    if (any(p < low) || any(p > high)) {
+        return sample_frame(p);
+    }
+
+    let size = max(u.size, 1.0);
+    var color = vec4<f32>(0.0);
+    var total_weight = 0.0;
+
+    if (u.kind == KIND_PIXELATE) {
+        // every pixel in a block gets the block's average
+        let block_start = low + floor((p - low) / size) * size;
+        for (var y = 0; y < BLOCK_SAMPLES; y++) {
+            for (var x = 0; x < BLOCK_SAMPLES; x++) {
+                let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(BLOCK_SAMPLES) * size;
+                color += sample_frame(min(block_start + offset, high));
+                total_weight += 1.0;
+            }
+        }
+    } else if (u.kind == KIND_BLUR) {
+        let sigma = size * 0.5;
+        for (var y = -BLUR_SAMPLES; y <= BLUR_SAMPLES; y++) {
+            for (var x = -BLUR_SAMPLES; x <= BLUR_SAMPLES; x++) {
+                let offset = vec2<f32>(f32(x), f32(y)) / f32(BLUR_SAMPLES) * size;
+                let weight = exp(-dot(offset, offset) / (2.0 * sigma * sigma));
+                color += sample_frame(p + offset) * weight;
+                total_weight += weight;
+            }
+        }
+    } else {
+        return sample_frame(p);
+    }
+
+    return color / total_weight;
+}
@@ -0,0 +1,103 @@
+struct Uniforms {
+    // an arrow's tail and head, or opposite corners of a box, in output pixels
+    points: vec4<f32>,
+    // rgb, then the opacity the shape is drawn at
+    color: vec4<f32>,
+    output_size: vec2<f32>,
+    // line thickness in output pixels
+    size: f32,
+    // how far the shape has grown, 1 for all of it
+    reach: f32,
+    kind: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+};
+
+const KIND_ARROW: u32 = 0u;
+const KIND_RECTANGLE: u32 = 1u;
+const KIND_HIGHLIGHT: u32 = 2u;
+
+const HIGHLIGHT_OPACITY: f32 = 0.35;
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ab = b - a;
+    let t = clamp(dot(p - a, ab) / max(dot(ab, ab), 1e-6), 0.0, 1.0);
+    return length(p - (a + ab * t));
+}
+
+fn arrow_coverage(p: vec2<f32>) -> f32 {
+    let tail = u.points.xy;
+    let head = mix(tail, u.points.zw, u.reach);
+    let span = length(head - tail);
+    if (span < 0.5) {
+        return 0.0;
+    }
+
+    let direction = (head - tail) / span;
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    let head_length = min(u.size * 4.0, span);
+    let head_half_width = u.size * 2.5;
+
+    let shaft_end = head - direction * head_length * 0.5;
+    let shaft = clamp(u.size * 0.5 - segment_distance(p, tail, shaft_end) + 0.5, 0.0, 1.0);
+
+    // a triangle with its tip on the head, distances measured back from the tip
+    let along = dot(head - p, direction);
+    let across = abs(dot(p - head, normal));
+    let slope = head_half_width / head_length;
+    let edge = (along * slope - across) / sqrt(1.0 + slope * slope);
+    let tip = clamp(min(edge, head_length - along) + 0.5, 0.0, 1.0);
+
+    return max(shaft, tip);
+}
+
+fn box_distance(p: vec2<f32>) -> f32 {
+    let low = min(u.points.xy, u.points.zw);
+    let high = max(u.points.xy, u.points.zw);
+    let center = (low + high) * 0.5;
+    let half_size = (high - low) * 0.5 * u.reach;
+
+    let q = abs(p - center) - half_size;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position.xy;
+
+    var coverage = 0.0;
+    if (u.kind == KIND_ARROW) {
+        coverage = arrow_coverage(p);
+    } else if (u.kind == KIND_RECTANGLE) {
+        // the outline is centered on the box's edge
+        coverage = clamp(u.size * 0.5 - abs(box_distance(p)) + 0.5, 0.0, 1.0);
+    } else if (u.kind == KIND_HIGHLIGHT) {
+        coverage = clamp(0.5 - box_distance(p), 0.0, 1.0) * HIGHLIGHT_OPACITY;
+    }
+
+    let alpha = coverage * u.color.a;
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    // premultiplied
+    return vec4<f32>(u.color.rgb * alpha, alpha);
+}
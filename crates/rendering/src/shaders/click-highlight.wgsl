@@ -0,0 +1,56 @@
+struct Uniforms {
+    // xy: position in frame space, z: progress through the animation
+    clicks: array<vec4<f32>, 8>,
+    color: vec4<f32>,
+    radius: f32,
+    count: f32,
+    style: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn ease_out(t: f32) -> f32 {
+    return 1.0 - pow(1.0 - t, 3.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var alpha = 0.0;
+
+    for (var i = 0; i < i32(u.count); i++) {
+        let click = u.clicks[i];
+        let progress = clamp(click.z, 0.0, 1.0);
+        let distance = length(in.position.xy - click.xy);
+        let fade = 1.0 - progress;
+
+        var coverage = 0.0;
+        if (u.style < 0.5) {
+            // ring that expands from a third of the radius outwards
+            let radius = u.radius * mix(0.3, 1.0, ease_out(progress));
+            let thickness = max(u.radius * 0.12, 1.0);
+            coverage = 1.0 - smoothstep(thickness * 0.5 - 0.75, thickness * 0.5 + 0.75, abs(distance - radius));
+        } else {
+            let radius = u.radius * mix(0.8, 1.0, ease_out(progress));
+            coverage = (1.0 - smoothstep(radius - 1.0, radius + 1.0, distance)) * 0.5;
+        }
+
+        alpha = max(alpha, coverage * fade);
+    }
+
+    return vec4<f32>(u.color.rgb * alpha, alpha);
+}
@@ -0,0 +1,75 @@
+struct Uniforms {
+    // the LUT's input range, from its DOMAIN_MIN and DOMAIN_MAX
+    domain_min: vec4<f32>,
+    domain_max: vec4<f32>,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    lut_intensity: f32,
+    lut_size: f32,
+    _padding0: f32,
+    _padding1: f32,
+    _padding2: f32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_screen: texture_2d<f32>;
+@group(0) @binding(2) var t_lut: texture_3d<f32>;
+@group(0) @binding(3) var s_linear: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(
+        1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055,
+        c * 12.92,
+        c <= vec3<f32>(0.0031308)
+    );
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(
+        pow((c + 0.055) / 1.055, vec3<f32>(2.4)),
+        c / 12.92,
+        c <= vec3<f32>(0.04045)
+    );
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_screen));
+    let color = textureSampleLevel(t_screen, s_linear, in.position.xy / size, 0.0);
+
+    // graded in sRGB, which is what the sliders and LUTs expect
+    var c = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+
+    c = c + u.brightness;
+    c = (c - 0.5) * (1.0 + u.contrast) + 0.5;
+
+    let luma = dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+    c = mix(vec3<f32>(luma), c, 1.0 + u.saturation);
+    c = clamp(c, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    if (u.lut_intensity > 0.0) {
+        let range = max(u.domain_max.xyz - u.domain_min.xyz, vec3<f32>(1e-6));
+        let input = clamp((c - u.domain_min.xyz) / range, vec3<f32>(0.0), vec3<f32>(1.0));
+        // the outer entries sit at texel centers, not the texture's edges
+        let coord = input * ((u.lut_size - 1.0) / u.lut_size) + 0.5 / u.lut_size;
+        let graded = textureSampleLevel(t_lut, s_linear, coord, 0.0).rgb;
+        c = mix(c, graded, u.lut_intensity);
+    }
+
+    return vec4<f32>(srgb_to_linear(c), color.a);
+}
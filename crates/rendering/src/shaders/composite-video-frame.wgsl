@@ -13,6 +13,11 @@ struct Uniforms {
     shadow_size: f32,
     shadow_opacity: f32,
     shadow_blur: f32,
+    shadow_offset: vec2<f32>,
+    border_width: f32,
+    lanczos: f32,
+    border_color: vec4<f32>,
+    seed: u32,
 };
 
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
@@ -77,8 +82,9 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
         shadow_enabled
     );
 
-    // Calculate shadow with no offset
-    let shadow_dist = sdf_rounded_rect(p - center, size, uniforms.rounding_px);
+    // Offset is relative to the frame's shorter side, like size and blur
+    let shadow_offset = uniforms.shadow_offset / 100.0 * min_frame_size;
+    let shadow_dist = sdf_rounded_rect(p - center - shadow_offset, size, uniforms.rounding_px);
     
     // Apply blur and size to shadow
     let shadow_strength_final = smoothstep(shadow_size + shadow_blur, -shadow_blur, abs(shadow_dist));
@@ -92,24 +98,24 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
     
     // If outside the target area, just blend shadow with intermediate
     if target_uv.x < 0.0 || target_uv.x > 1.0 || target_uv.y < 0.0 || target_uv.y > 1.0 {
-        return vec4<f32>(
-            mix(bg_color.rgb, vec3<f32>(0.0), shadow_color.a),
-            1.0
-        );
+        return over(shadow_color, bg_color);
     }
 
-    var base_color = sample_texture(target_uv, crop_bounds_uv);
+    // the blur's extra samples stay bilinear, since they're smeared anyway
+    var base_color: vec4<f32>;
+    if uniforms.lanczos > 0.0 {
+        base_color = sample_lanczos(target_uv, crop_bounds_uv);
+    } else {
+        base_color = sample_texture(target_uv, crop_bounds_uv);
+    }
     base_color = apply_rounded_corners(base_color, target_uv);
+    base_color = apply_border(base_color, dist);
 
     let blur_amount = select(uniforms.motion_blur_amount, uniforms.camera_motion_blur_amount, uniforms.camera_motion_blur_amount > 0.0);
 
     if blur_amount < 0.01 {
         // First blend shadow with intermediate, then blend result with base color
-        let with_shadow = vec4<f32>(
-            mix(bg_color.rgb, vec3<f32>(0.0), shadow_color.a),
-            1.0
-        );
-        return mix(with_shadow, base_color, base_color.a);
+        return over(base_color, over(shadow_color, bg_color));
     }
 
     let center_uv = vec2<f32>(0.5, 0.5);
@@ -125,7 +131,7 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
         let t = f32(i) / f32(num_samples);
         let dist_from_center = length(target_uv - center_uv);
 
-        let random_offset = (rand(target_uv + vec2<f32>(t)) - 0.5) * 0.1 * smoothstep(0.0, 0.2, blur_amount);
+        let random_offset = (rand(p, u32(i) * 2u) - 0.5) * 0.1 * smoothstep(0.0, 0.2, blur_amount);
 
         let base_scale = select(
             0.08,  // Regular content scale
@@ -134,7 +140,7 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
         );
         let scale = dist_from_center * blur_amount * (base_scale + random_offset) * smoothstep(0.0, 0.1, blur_amount);
 
-        let angle_variation = (rand(target_uv + vec2<f32>(t * 2.0)) - 0.5) * 0.1 * smoothstep(0.0, 0.2, blur_amount);
+        let angle_variation = (rand(p, u32(i) * 2u + 1u) - 0.5) * 0.1 * smoothstep(0.0, 0.2, blur_amount);
         let rotated_dir = vec2<f32>(
             dir.x * cos(angle_variation) - dir.y * sin(angle_variation),
             dir.x * sin(angle_variation) + dir.y * cos(angle_variation)
@@ -153,9 +159,20 @@ fn fs_main(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
         }
     }
 
-    let final_color = accum / weight_sum;
+    let final_color = apply_border(accum / weight_sum, dist);
     let blurred = vec4(final_color.rgb, base_color.a);
-    return mix(mix(bg_color, shadow_color, shadow_color.a), blurred, blurred.a);
+    return over(blurred, over(shadow_color, bg_color));
+}
+
+// Straight alpha "over", so a transparent background stays transparent around the frame
+fn over(top: vec4<f32>, bottom: vec4<f32>) -> vec4<f32> {
+    let alpha = top.a + bottom.a * (1.0 - top.a);
+    if alpha <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    let color = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / alpha;
+    return vec4<f32>(color, alpha);
 }
 
 fn sample_texture(uv: vec2<f32>, crop_bounds_uv: vec4<f32>) -> vec4<f32> {
@@ -172,6 +189,65 @@ fn sample_texture(uv: vec2<f32>, crop_bounds_uv: vec4<f32>) -> vec4<f32> {
     return vec4(0.0);
 }
 
+const PI: f32 = 3.14159265;
+const LANCZOS_RADIUS: f32 = 3.0;
+// How far the kernel widens when shrinking the frame, keeping the taps per pixel bounded.
+// Shrinking further than this still aliases a little, though far less than bilinear.
+const MAX_LANCZOS_SCALE: f32 = 2.0;
+
+fn lanczos_weight(x: f32) -> f32 {
+    if abs(x) < 0.0001 {
+        return 1.0;
+    }
+    if abs(x) >= LANCZOS_RADIUS {
+        return 0.0;
+    }
+
+    let px = PI * x;
+    return LANCZOS_RADIUS * sin(px) * sin(px / LANCZOS_RADIUS) / (px * px);
+}
+
+// `sample_texture` with a Lanczos-3 filter, stretched over more texels when the frame is
+// drawn smaller than it was recorded so fine detail like text doesn't shimmer
+fn sample_lanczos(uv: vec2<f32>, crop_bounds_uv: vec4<f32>) -> vec4<f32> {
+    if uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 {
+        return vec4(0.0);
+    }
+
+    var sample_uv = uv;
+    if uniforms.mirror_x != 0.0 {
+        sample_uv.x = 1.0 - sample_uv.x;
+    }
+
+    let dims = vec2<f32>(textureDimensions(frame_texture));
+    let cropped_uv = sample_uv * (crop_bounds_uv.zw - crop_bounds_uv.xy) + crop_bounds_uv.xy;
+    let crop_px = (crop_bounds_uv.zw - crop_bounds_uv.xy) * dims;
+    let scale = clamp(crop_px / uniforms.target_size, vec2(1.0), vec2(MAX_LANCZOS_SCALE));
+
+    let center = cropped_uv * dims - 0.5;
+    let start = vec2<i32>(ceil(center - LANCZOS_RADIUS * scale));
+    let end = vec2<i32>(floor(center + LANCZOS_RADIUS * scale));
+    let max_texel = vec2<i32>(dims) - 1;
+
+    var accum = vec3<f32>(0.0);
+    var weight_sum = 0.0;
+
+    for (var y = start.y; y <= end.y; y = y + 1) {
+        let weight_y = lanczos_weight((f32(y) - center.y) / scale.y);
+
+        for (var x = start.x; x <= end.x; x = x + 1) {
+            let weight = weight_y * lanczos_weight((f32(x) - center.x) / scale.x);
+            let texel = clamp(vec2<i32>(x, y), vec2<i32>(0), max_texel);
+
+            accum += textureLoad(frame_texture, texel, 0).rgb * weight;
+            weight_sum += weight;
+        }
+    }
+
+    // the negative lobes can overshoot around hard edges
+    return vec4(clamp(accum / weight_sum, vec3(0.0), vec3(1.0)), 1.0);
+}
+
 fn apply_rounded_corners(current_color: vec4<f32>, target_uv: vec2<f32>) -> vec4<f32> {
     let target_coord = abs(target_uv * uniforms.target_size - uniforms.target_size / 2.0);
     let rounding_point = uniforms.target_size / 2.0 - uniforms.rounding_px;
@@ -188,8 +264,22 @@ fn apply_rounded_corners(current_color: vec4<f32>, target_uv: vec2<f32>) -> vec4
     return current_color;
 }
 
-fn rand(co: vec2<f32>) -> f32 {
-    return fract(sin(dot(co, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+// Tints the inside edge of the frame, keeping the rounded corners' alpha
+fn apply_border(current_color: vec4<f32>, dist: f32) -> vec4<f32> {
+    if uniforms.border_width <= 0.0 {
+        return current_color;
+    }
+
+    let border = smoothstep(-uniforms.border_width - 1.0, -uniforms.border_width, dist);
+    return vec4<f32>(mix(current_color.rgb, uniforms.border_color.rgb, border * uniforms.border_color.a), current_color.a);
+}
+
+// A PCG hash of the pixel, the sample and the frame's seed. Unlike hashing with sin() it's
+// integer math, which gives the same bits on every GPU.
+fn rand(pixel: vec2<f32>, sample: u32) -> f32 {
+    let state = (u32(pixel.x) * 1973u + u32(pixel.y) * 9277u + sample * 26699u + uniforms.seed) * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32((word >> 22u) ^ word) / 4294967295.0;
 }
 
 fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
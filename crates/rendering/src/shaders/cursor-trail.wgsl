@@ -0,0 +1,53 @@
+struct Uniforms {
+    // xy: position in frame space, newest first
+    points: array<vec4<f32>, 16>,
+    color: vec4<f32>,
+    width: f32,
+    opacity: f32,
+    count: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ab = b - a;
+    let len_sq = dot(ab, ab);
+    if (len_sq == 0.0) {
+        return length(p - a);
+    }
+    let t = clamp(dot(p - a, ab) / len_sq, 0.0, 1.0);
+    return length(p - (a + ab * t));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var alpha = 0.0;
+    let segments = i32(u.count) - 1;
+
+    for (var i = 0; i < segments; i++) {
+        // fades and narrows from the newest position to the oldest
+        let age = f32(i) / f32(segments);
+        let half_width = max(u.width * (1.0 - age) * 0.5, 0.5);
+        let distance = segment_distance(in.position.xy, u.points[i].xy, u.points[i + 1].xy);
+        let coverage = 1.0 - smoothstep(half_width - 0.75, half_width + 0.75, distance);
+
+        alpha = max(alpha, coverage * (1.0 - age) * u.opacity);
+    }
+
+    return vec4<f32>(u.color.rgb * alpha, alpha);
+}
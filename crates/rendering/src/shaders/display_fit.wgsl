@@ -0,0 +1,34 @@
+struct Uniforms {
+    // x, y, width, height in pixels of the frame being drawn into
+    rect: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_display: texture_2d<f32>;
+@group(0) @binding(2) var s_display: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = (in.position.xy - u.rect.xy) / u.rect.zw;
+
+    // outside the fitted display the cleared black shows through
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0))) {
+        return vec4<f32>(0.0);
+    }
+
+    return textureSampleLevel(t_display, s_display, uv, 0.0);
+}
@@ -1,7 +1,8 @@
 struct Uniforms {
 	start: vec4<f32>,
 	end: vec4<f32>,
-	angle: f32
+	angle: f32,
+	radial: f32
 };
 
 @group(0) @binding(0) var<uniform> u: Uniforms;
@@ -18,9 +19,13 @@ fn gradient(uv: vec2<f32>) -> vec4<f32> {
 
 		let proj = dot(uv - 0.5, dir) + 0.5;
 
-		let t = clamp(proj, 0.0, 1.0);
+		var t = clamp(proj, 0.0, 1.0);
+		// corners are 0.7071 from the center
+		if (u.radial > 0.5) {
+			t = clamp(length(uv - 0.5) / 0.7071, 0.0, 1.0);
+		}
 
-		return mix(vec4<f32>(u.start.rgb, 1.0), vec4<f32>(u.end.rgb, 1.0), t);
+		return mix(u.start, u.end, t);
 }
 
 struct VertexOutput {
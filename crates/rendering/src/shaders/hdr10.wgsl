@@ -0,0 +1,62 @@
+struct Uniforms {
+    // converts BT.709 to BT.2020, as rows
+    gamut0: vec4<f32>,
+    gamut1: vec4<f32>,
+    gamut2: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_frame: texture_2d<f32>;
+@group(0) @binding(2) var s_frame: sampler;
+
+const REFERENCE_WHITE_NITS: f32 = 203.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(
+        pow((c + 0.055) / 1.055, vec3<f32>(2.4)),
+        c / 12.92,
+        c <= vec3<f32>(0.04045)
+    );
+}
+
+// SMPTE ST 2084's inverse EOTF
+fn nits_to_pq(nits: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let y = pow(clamp(nits / 10000.0, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3<f32>(m2));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_frame));
+    let color = textureSampleLevel(t_frame, s_frame, in.position.xy / size, 0.0);
+
+    let gamut = mat3x3<f32>(u.gamut0.xyz, u.gamut1.xyz, u.gamut2.xyz);
+    // the rows were uploaded as columns
+    let rgb = max(color.rgb * gamut, vec3<f32>(0.0));
+
+    // SDR white sits at reference white
+    let pq = nits_to_pq(rgb * REFERENCE_WHITE_NITS);
+
+    // written through the sRGB encoding so the PQ code values are what's stored
+    return vec4<f32>(srgb_to_linear(pq), color.a);
+}
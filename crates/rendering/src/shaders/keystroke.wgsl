@@ -0,0 +1,55 @@
+struct Uniforms {
+    // x, y, width, height in output pixels
+    pill: vec4<f32>,
+    text: vec4<f32>,
+    radius: f32,
+    opacity: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_text: texture_2d<f32>;
+@group(0) @binding(2) var s_text: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn rounded_rect_distance(p: vec2<f32>, rect: vec4<f32>, radius: f32) -> f32 {
+    let half_size = rect.zw * 0.5;
+    let q = abs(p - rect.xy - half_size) - half_size + radius;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position.xy;
+
+    let pill = 1.0 - smoothstep(-0.75, 0.75, rounded_rect_distance(p, u.pill, u.radius));
+    if (pill <= 0.0) {
+        discard;
+    }
+
+    let uv = (p - u.text.xy) / u.text.zw;
+    var text = 0.0;
+    if (all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0))) {
+        text = textureSampleLevel(t_text, s_text, uv, 0.0).a;
+    }
+
+    let background = 0.75 * pill;
+    let alpha = (text + background * (1.0 - text)) * u.opacity;
+
+    // white text over a near-black pill, premultiplied
+    let color = vec3<f32>(text) + vec3<f32>(0.02) * background * (1.0 - text);
+    return vec4<f32>(color * u.opacity, alpha);
+}
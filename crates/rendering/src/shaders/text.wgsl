@@ -0,0 +1,47 @@
+struct Uniforms {
+    // x, y, width, height in output pixels
+    rect: vec4<f32>,
+    // rgb, then the opacity the text is drawn at
+    color: vec4<f32>,
+    // a box drawn behind the text, premultiplied, reaching `padding` pixels past it
+    background: vec4<f32>,
+    padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_text: texture_2d<f32>;
+@group(0) @binding(2) var s_text: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let box_min = u.rect.xy - u.padding;
+    let box_max = u.rect.xy + u.rect.zw + u.padding;
+    if (any(in.position.xy < box_min) || any(in.position.xy > box_max)) {
+        discard;
+    }
+
+    let uv = (in.position.xy - u.rect.xy) / u.rect.zw;
+    let sampled = textureSampleLevel(t_text, s_text, clamp(uv, vec2<f32>(0.0), vec2<f32>(1.0)), 0.0).a;
+    var alpha = 0.0;
+    if (all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0))) {
+        alpha = sampled * u.color.a;
+    }
+
+    // premultiplied, over the box
+    let background = u.background.a * (1.0 - alpha);
+    return vec4<f32>(u.color.rgb * alpha + u.background.rgb * background, alpha + background);
+}
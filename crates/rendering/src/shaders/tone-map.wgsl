@@ -0,0 +1,113 @@
+struct Uniforms {
+    // converts the source's primaries to BT.709, as rows
+    gamut0: vec4<f32>,
+    gamut1: vec4<f32>,
+    gamut2: vec4<f32>,
+    // 0 for SDR, 1 for PQ, 2 for HLG
+    transfer: u32,
+    // the source's peak brightness, relative to reference white
+    peak: f32,
+    _padding0: f32,
+    _padding1: f32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_screen: texture_2d<f32>;
+@group(0) @binding(2) var s_screen: sampler;
+
+const REFERENCE_WHITE_NITS: f32 = 203.0;
+const HLG_PEAK_NITS: f32 = 1000.0;
+// below this, relative to reference white, colors are left alone
+const KNEE: f32 = 0.75;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(
+        1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055,
+        c * 12.92,
+        c <= vec3<f32>(0.0031308)
+    );
+}
+
+// SMPTE ST 2084
+fn pq_to_nits(e: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let p = pow(e, vec3<f32>(1.0 / m2));
+    return 10000.0 * pow(max(p - c1, vec3<f32>(0.0)) / (c2 - c3 * p), vec3<f32>(1.0 / m1));
+}
+
+// BT.2100's inverse OETF followed by its OOTF, for a 1000 nit display
+fn hlg_to_nits(e: vec3<f32>) -> vec3<f32> {
+    let a = 0.17883277;
+    let b = 0.28466892;
+    let c = 0.55991073;
+
+    let scene = select(
+        (exp((e - c) / a) + b) / 12.0,
+        e * e / 3.0,
+        e <= vec3<f32>(0.5)
+    );
+    let luminance = dot(scene, vec3<f32>(0.2627, 0.6780, 0.0593));
+
+    return HLG_PEAK_NITS * pow(max(luminance, 1e-6), 0.2) * scene;
+}
+
+// Leaves luminance below the knee alone and rolls off what's above it so `peak` lands on
+// white, scaling all three channels together to keep hues
+fn tone_map(rgb: vec3<f32>, peak: f32) -> vec3<f32> {
+    let l = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if (l <= KNEE) {
+        return rgb;
+    }
+
+    // extended Reinhard over what's above the knee
+    let s = (l - KNEE) / (1.0 - KNEE);
+    let p = max((peak - KNEE) / (1.0 - KNEE), 1.0);
+    let mapped = KNEE + (1.0 - KNEE) * s * (1.0 + s / (p * p)) / (1.0 + s);
+
+    return rgb * (mapped / l);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t_screen));
+    let color = textureSampleLevel(t_screen, s_screen, in.position.xy / size, 0.0);
+
+    // the texture's sRGB decoding is undone to get back the recorded code values
+    let code = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+
+    var rgb = color.rgb;
+    if (u.transfer == 1u) {
+        rgb = pq_to_nits(code) / REFERENCE_WHITE_NITS;
+    } else if (u.transfer == 2u) {
+        rgb = hlg_to_nits(code) / REFERENCE_WHITE_NITS;
+    }
+
+    let gamut = mat3x3<f32>(u.gamut0.xyz, u.gamut1.xyz, u.gamut2.xyz);
+    // the rows were uploaded as columns
+    rgb = max(rgb * gamut, vec3<f32>(0.0));
+
+    if (u.transfer != 0u) {
+        rgb = tone_map(rgb, u.peak);
+    }
+
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}
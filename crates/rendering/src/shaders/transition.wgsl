@@ -0,0 +1,70 @@
+struct Uniforms {
+    // the color dips go through, linear
+    color: vec4<f32>,
+    // the way slides move the incoming frame, in fractions of the frame
+    direction: vec2<f32>,
+    progress: f32,
+    // 0 cross-fade, 1 dip, 2 slide
+    kind: u32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var t_from: texture_2d<f32>;
+@group(0) @binding(2) var t_to: texture_2d<f32>;
+@group(0) @binding(3) var s_frame: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index & 2u) * 2 - 1);
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+
+    return out;
+}
+
+fn inside(uv: vec2<f32>) -> bool {
+    return all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.position.xy / vec2<f32>(textureDimensions(t_to));
+    let t = smoothstep(0.0, 1.0, u.progress);
+
+    if (u.kind == 2u) {
+        // the outgoing frame moves out as the incoming one takes its place
+        let offset = u.direction * t;
+        let from_uv = uv - offset;
+        let to_uv = uv - offset + u.direction;
+
+        let outgoing = textureSampleLevel(t_from, s_frame, clamp(from_uv, vec2<f32>(0.0), vec2<f32>(1.0)), 0.0);
+        let incoming = textureSampleLevel(t_to, s_frame, clamp(to_uv, vec2<f32>(0.0), vec2<f32>(1.0)), 0.0);
+
+        if (inside(to_uv)) {
+            return incoming;
+        }
+        if (inside(from_uv)) {
+            return outgoing;
+        }
+        return vec4<f32>(0.0);
+    }
+
+    let outgoing = textureSampleLevel(t_from, s_frame, uv, 0.0);
+    let incoming = textureSampleLevel(t_to, s_frame, uv, 0.0);
+
+    if (u.kind == 1u) {
+        // premultiplied, as the frames are
+        let color = vec4<f32>(u.color.rgb * u.color.a, u.color.a);
+        if (u.progress < 0.5) {
+            return mix(outgoing, color, smoothstep(0.0, 1.0, u.progress * 2.0));
+        }
+        return mix(color, incoming, smoothstep(0.0, 1.0, u.progress * 2.0 - 1.0));
+    }
+
+    return mix(outgoing, incoming, t);
+}
@@ -9,7 +9,7 @@ pub struct SpringMassDamperSimulation {
     pub target_position: XY<f32>,
 }
 
-const SIMULATION_TICK: f32 = 1000.0 / 60.0;
+pub const SIMULATION_TICK: f32 = 1000.0 / 60.0;
 
 impl SpringMassDamperSimulation {
     pub fn new(tension: f32, mass: f32, friction: f32) -> Self {
@@ -0,0 +1,224 @@
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use cap_project::TextFont;
+use image::{Rgba, RgbaImage};
+use std::sync::OnceLock;
+
+// fonts that ship with each OS, so no font has to be bundled
+#[cfg(target_os = "macos")]
+const FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/SFNS.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+    "/Library/Fonts/Arial.ttf",
+];
+#[cfg(windows)]
+const FONT_PATHS: &[&str] = &[
+    "C:\\Windows\\Fonts\\segoeui.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+];
+
+#[cfg(target_os = "macos")]
+const SERIF_FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/NewYork.ttf",
+    "/System/Library/Fonts/Supplemental/Georgia.ttf",
+    "/System/Library/Fonts/Supplemental/Times New Roman.ttf",
+];
+#[cfg(windows)]
+const SERIF_FONT_PATHS: &[&str] = &[
+    "C:\\Windows\\Fonts\\georgia.ttf",
+    "C:\\Windows\\Fonts\\times.ttf",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const SERIF_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSerif.ttf",
+    "/usr/share/fonts/TTF/DejaVuSerif.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSerif.ttf",
+];
+
+#[cfg(target_os = "macos")]
+const MONO_FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/SFNSMono.ttf",
+    "/System/Library/Fonts/Menlo.ttc",
+];
+#[cfg(windows)]
+const MONO_FONT_PATHS: &[&str] = &[
+    "C:\\Windows\\Fonts\\consola.ttf",
+    "C:\\Windows\\Fonts\\cour.ttf",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const MONO_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSansMono.ttf",
+];
+
+// tried in turn for characters the chosen font doesn't have, like CJK text and symbols.
+// Emoji come out in a single color.
+#[cfg(target_os = "macos")]
+const FALLBACK_FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/Apple Symbols.ttf",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+];
+#[cfg(windows)]
+const FALLBACK_FONT_PATHS: &[&str] = &[
+    "C:\\Windows\\Fonts\\seguisym.ttf",
+    "C:\\Windows\\Fonts\\seguiemj.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\malgun.ttf",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const FALLBACK_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+// loaded the first time a character's missing, since CJK fonts run to tens of megabytes
+static FALLBACK_FONTS: OnceLock<Vec<FontVec>> = OnceLock::new();
+
+fn fallback_fonts() -> &'static [FontVec] {
+    FALLBACK_FONTS.get_or_init(|| {
+        FALLBACK_FONT_PATHS
+            .iter()
+            .filter_map(|path| FontVec::try_from_vec_and_index(std::fs::read(path).ok()?, 0).ok())
+            .collect()
+    })
+}
+
+pub struct TextRasterizer {
+    font: FontVec,
+    fallback: bool,
+}
+
+impl TextRasterizer {
+    pub fn load_system_font() -> Option<Self> {
+        Self::load_from(FONT_PATHS)
+    }
+
+    // A system font of the kind asked for, or the regular one when there's none
+    pub fn load_system_font_like(font: TextFont) -> Option<Self> {
+        let paths = match font {
+            TextFont::Sans => FONT_PATHS,
+            TextFont::Serif => SERIF_FONT_PATHS,
+            TextFont::Mono => MONO_FONT_PATHS,
+        };
+
+        Self::load_from(paths).or_else(Self::load_system_font)
+    }
+
+    fn load_from(paths: &[&str]) -> Option<Self> {
+        paths
+            .iter()
+            .find_map(|path| FontVec::try_from_vec_and_index(std::fs::read(path).ok()?, 0).ok())
+            .map(|font| Self {
+                font,
+                fallback: true,
+            })
+    }
+
+    // Whether characters the font doesn't have are drawn with other fonts. Deterministic
+    // renders leave them as the font's missing glyph, since which fallback fonts are installed
+    // differs between machines. Glyphs are never hinted and coverage is rounded the same way
+    // everywhere, so the same font draws the same pixels on every machine.
+    pub fn with_fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    // The font to draw `c` with, falling back to others that have it
+    fn font_for(&self, c: char) -> &FontVec {
+        if !self.fallback || self.font.glyph_id(c).0 != 0 {
+            return &self.font;
+        }
+
+        fallback_fonts()
+            .iter()
+            .find(|font| font.glyph_id(c).0 != 0)
+            .unwrap_or(&self.font)
+    }
+
+    // Draws one line of white text at `px` per em, premultiplied, on a transparent
+    // image as tall as the font's line height
+    pub fn rasterize(&self, text: &str, px: f32) -> RgbaImage {
+        let scale = PxScale::from(px);
+        let ascent = self.font.as_scaled(scale).ascent();
+
+        let mut glyphs = vec![];
+        let mut x = 0.0;
+        let mut prev: Option<(&FontVec, _)> = None;
+
+        for c in text.chars() {
+            // joiners and variation selectors only shape emoji sequences, which are drawn
+            // one emoji at a time, so they're left out rather than drawn as boxes
+            if matches!(c, '\u{200D}' | '\u{FE0E}' | '\u{FE0F}') {
+                continue;
+            }
+
+            let font = self.font_for(c);
+            let scaled = font.as_scaled(scale);
+            let id = scaled.glyph_id(c);
+            // kerning only applies between glyphs of the same font
+            if let Some((_, prev_id)) = prev.filter(|(prev_font, _)| std::ptr::eq(*prev_font, font))
+            {
+                x += scaled.kern(prev_id, id);
+            }
+
+            glyphs.push((font, id.with_scale_and_position(scale, point(x, ascent))));
+            x += scaled.h_advance(id);
+            prev = Some((font, id));
+        }
+
+        let width = x.ceil().max(1.0) as u32;
+        let height = self.font.as_scaled(scale).height().ceil().max(1.0) as u32;
+        let mut image = RgbaImage::new(width, height);
+
+        for (font, glyph) in glyphs {
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+
+            outlined.draw(|gx, gy, coverage| {
+                let x = bounds.min.x as i32 + gx as i32;
+                let y = bounds.min.y as i32 + gy as i32;
+                if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                    return;
+                }
+
+                let pixel = image.get_pixel_mut(x as u32, y as u32);
+                let alpha = (pixel[3] as f32 + coverage * 255.0).min(255.0).round() as u8;
+                *pixel = Rgba([alpha; 4]);
+            });
+        }
+
+        image
+    }
+
+    // Like `rasterize`, with each line of `text` below the last, lined up on the left
+    pub fn rasterize_lines(&self, text: &str, px: f32) -> RgbaImage {
+        let lines = text
+            .lines()
+            .map(|line| self.rasterize(line, px))
+            .collect::<Vec<_>>();
+
+        let width = lines.iter().map(|l| l.width()).max().unwrap_or(1);
+        let height = lines.iter().map(|l| l.height()).sum::<u32>().max(1);
+        let mut image = RgbaImage::new(width, height);
+
+        let mut y = 0;
+        for line in lines {
+            image::imageops::replace(&mut image, &line, 0, y as i64);
+            y += line.height();
+        }
+
+        image
+    }
+}
@@ -1,12 +1,15 @@
-use cap_project::{ZoomSegment, XY};
+use cap_project::{AutoZoomConfiguration, ZoomEasing, ZoomMode, ZoomSegment, XY};
+use serde::Serialize;
+use specta::Type;
+use tracing::trace;
 
 pub const ZOOM_DURATION: f64 = 1.0;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SegmentsCursor<'a> {
     time: f64,
-    segment: Option<&'a ZoomSegment>,
-    prev_segment: Option<&'a ZoomSegment>,
+    pub segment: Option<&'a ZoomSegment>,
+    pub prev_segment: Option<&'a ZoomSegment>,
     segments: &'a [ZoomSegment],
 }
 
@@ -43,6 +46,41 @@ impl<'a> SegmentsCursor<'a> {
     }
 }
 
+pub fn duration_in(segment: &ZoomSegment) -> f64 {
+    segment
+        .transition_duration_in
+        .filter(|d| *d > 0.0)
+        .unwrap_or(ZOOM_DURATION)
+}
+
+pub fn duration_out(segment: &ZoomSegment) -> f64 {
+    segment
+        .transition_duration_out
+        .filter(|d| *d > 0.0)
+        .unwrap_or(ZOOM_DURATION)
+}
+
+// Zoom targets that move during the recording, resolved by the caller for the current frame
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZoomFocus {
+    // normalized display position Auto segments zoom towards
+    pub auto: XY<f64>,
+    // normalized [x, y, width, height] of the window tracked by a Window segment
+    pub segment_window: Option<[f64; 4]>,
+    pub prev_segment_window: Option<[f64; 4]>,
+    // the display amount at which it covers the whole output frame
+    pub cover_amount: f64,
+}
+
+impl ZoomFocus {
+    pub fn auto(auto: XY<f64>) -> Self {
+        Self {
+            auto,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SegmentBounds {
     pub top_left: XY<f64>,
@@ -50,27 +88,99 @@ pub struct SegmentBounds {
 }
 
 impl SegmentBounds {
-    fn from_segment(segment: &ZoomSegment) -> Self {
-        let position = match segment.mode {
-            cap_project::ZoomMode::Auto => (0.0, 0.0),
-            cap_project::ZoomMode::Manual { x, y } => (x, y),
+    // The segment's zoom at `time`, following its keyframes
+    fn from_segment(
+        segment: &ZoomSegment,
+        time: f64,
+        focus: &ZoomFocus,
+        window: Option<[f64; 4]>,
+    ) -> Self {
+        let base_position = match segment.mode {
+            ZoomMode::Auto => Some(focus.auto),
+            ZoomMode::Manual { x, y } | ZoomMode::Pan { x, y } => Some(XY::new(x as f64, y as f64)),
+            ZoomMode::Window { .. } => None,
         };
 
-        let scaled_center = [
-            position.0 as f64 * segment.amount,
-            position.1 as f64 * segment.amount,
-        ];
-        let center_diff = [
-            scaled_center[0] - position.0 as f64,
-            scaled_center[1] - position.1 as f64,
-        ];
+        let (amount, position) = keyframe_state(segment, time - segment.start, base_position);
+        let amount = match segment.mode {
+            ZoomMode::Pan { .. } => amount * focus.cover_amount.max(1.0),
+            _ => amount,
+        };
+
+        let position = match (position, window) {
+            (Some(position), _) => position,
+            (None, Some(window)) => return Self::fit_window(window, amount),
+            (None, None) => XY::new(0.5, 0.5),
+        };
+
+        let center_diff = position * amount - position;
 
         SegmentBounds::new(
-            XY::new(0.0 - center_diff[0], 0.0 - center_diff[1]),
-            XY::new(
-                segment.amount - center_diff[0],
-                segment.amount - center_diff[1],
+            XY::new(0.0, 0.0) - center_diff,
+            XY::new(amount, amount) - center_diff,
+        )
+    }
+
+    // Centers the window and scales it to fill the output, zooming in no further than
+    // max_amount and never past the display's edges.
+    fn fit_window([x, y, width, height]: [f64; 4], max_amount: f64) -> Self {
+        const PADDING: f64 = 0.9;
+
+        let amount = (PADDING / width.max(height)).min(max_amount).max(1.0);
+
+        let center = XY::new(x + width / 2.0, y + height / 2.0);
+        let top_left = (XY::new(0.5, 0.5) - center * amount).map(|v| v.clamp(1.0 - amount, 0.0));
+
+        SegmentBounds::new(top_left, top_left + XY::new(amount, amount))
+    }
+
+    // The viewport is the output frame in display-normalized coordinates, which extends past
+    // [0, 1] on axes with padding or letterboxing. Once zoomed far enough to fill an axis of
+    // the viewport the display is kept covering it, so no background shows inside the zoom;
+    // before that it's kept fully inside it. The scale is left untouched to preserve aspect.
+    pub fn clamp_to_viewport(self, viewport_start: XY<f64>, viewport_end: XY<f64>) -> Self {
+        let clamp_axis = |top_left: f64, bottom_right: f64, start: f64, end: f64| {
+            let amount = bottom_right - top_left;
+            let covering = end - amount;
+
+            top_left.clamp(covering.min(start), covering.max(start))
+        };
+
+        let top_left = XY::new(
+            clamp_axis(
+                self.top_left.x,
+                self.bottom_right.x,
+                viewport_start.x,
+                viewport_end.x,
+            ),
+            clamp_axis(
+                self.top_left.y,
+                self.bottom_right.y,
+                viewport_start.y,
+                viewport_end.y,
             ),
+        );
+
+        SegmentBounds::new(top_left, top_left + (self.bottom_right - self.top_left))
+    }
+
+    // Scales the bounds up about the viewport's center, not at all at `t` 0 and by just
+    // enough for the display to cover the viewport at `t` 1, so the padding around the
+    // display closes up as the zoom comes in and opens again as it goes out.
+    pub fn expand_into_padding(
+        self,
+        t: f64,
+        viewport_start: XY<f64>,
+        viewport_end: XY<f64>,
+    ) -> Self {
+        let viewport_size = viewport_end - viewport_start;
+        let cover = viewport_size.x.max(viewport_size.y).max(1.0);
+        let scale = 1.0 + (cover - 1.0) * t;
+        let center = (viewport_start + viewport_end) / 2.0;
+
+        SegmentBounds::new(
+            (self.top_left - center) * scale + center,
+            (self.bottom_right - center) * scale + center,
         )
     }
 
@@ -94,11 +204,21 @@ pub struct InterpolatedZoom {
 }
 
 impl InterpolatedZoom {
-    pub fn new(cursor: SegmentsCursor) -> Self {
+    #[tracing::instrument(level = "trace", skip_all, fields(time = cursor.time))]
+    pub fn new(cursor: SegmentsCursor, focus: ZoomFocus) -> Self {
         let ease_in = bezier_easing::bezier_easing(0.1, 0.0, 0.3, 1.0).unwrap();
         let ease_out = bezier_easing::bezier_easing(0.5, 0.0, 0.5, 1.0).unwrap();
 
-        Self::new_with_easing(cursor, ease_in, ease_out)
+        let zoom = Self::new_with_easing(cursor, focus, ease_in, ease_out);
+
+        trace!(
+            t = zoom.t,
+            top_left = ?zoom.bounds.top_left,
+            bottom_right = ?zoom.bounds.bottom_right,
+            "interpolated zoom"
+        );
+
+        zoom
     }
 
     // the multiplier applied to the display width/height
@@ -108,20 +228,26 @@ impl InterpolatedZoom {
 
     pub(self) fn new_with_easing(
         cursor: SegmentsCursor,
+        focus: ZoomFocus,
         ease_in: impl Fn(f32) -> f32,
         ease_out: impl Fn(f32) -> f32,
     ) -> InterpolatedZoom {
         let default = SegmentBounds::default();
         match (cursor.prev_segment, cursor.segment) {
             (Some(prev_segment), None) => {
-                let zoom_t =
-                    ease_out(t_clamp((cursor.time - prev_segment.end) / ZOOM_DURATION) as f32)
-                        as f64;
+                let zoom_t = ease_out(t_clamp(
+                    (cursor.time - prev_segment.end) / duration_out(prev_segment),
+                ) as f32) as f64;
 
                 Self {
                     t: 1.0 - zoom_t,
                     bounds: {
-                        let prev_segment_bounds = SegmentBounds::from_segment(prev_segment);
+                        let prev_segment_bounds = SegmentBounds::from_segment(
+                            prev_segment,
+                            prev_segment.end,
+                            &focus,
+                            focus.prev_segment_window,
+                        );
 
                         SegmentBounds::new(
                             prev_segment_bounds.top_left * (1.0 - zoom_t)
@@ -134,12 +260,18 @@ impl InterpolatedZoom {
             }
             (None, Some(segment)) => {
                 let t =
-                    ease_in(t_clamp((cursor.time - segment.start) / ZOOM_DURATION) as f32) as f64;
+                    ease_in(t_clamp((cursor.time - segment.start) / duration_in(segment)) as f32)
+                        as f64;
 
                 Self {
                     t,
                     bounds: {
-                        let segment_bounds = SegmentBounds::from_segment(segment);
+                        let segment_bounds = SegmentBounds::from_segment(
+                            segment,
+                            cursor.time,
+                            &focus,
+                            focus.segment_window,
+                        );
 
                         SegmentBounds::new(
                             default.top_left * (1.0 - t) + segment_bounds.top_left * t,
@@ -149,11 +281,18 @@ impl InterpolatedZoom {
                 }
             }
             (Some(prev_segment), Some(segment)) => {
-                let prev_segment_bounds = SegmentBounds::from_segment(prev_segment);
-                let segment_bounds = SegmentBounds::from_segment(segment);
+                let prev_segment_bounds = SegmentBounds::from_segment(
+                    prev_segment,
+                    prev_segment.end,
+                    &focus,
+                    focus.prev_segment_window,
+                );
+                let segment_bounds =
+                    SegmentBounds::from_segment(segment, cursor.time, &focus, focus.segment_window);
 
                 let zoom_t =
-                    ease_in(t_clamp((cursor.time - segment.start) / ZOOM_DURATION) as f32) as f64;
+                    ease_in(t_clamp((cursor.time - segment.start) / duration_in(segment)) as f32)
+                        as f64;
 
                 // no gap
                 if segment.start == prev_segment.end {
@@ -168,12 +307,13 @@ impl InterpolatedZoom {
                     }
                 }
                 // small gap
-                else if segment.start - prev_segment.end < ZOOM_DURATION {
+                else if segment.start - prev_segment.end < duration_out(prev_segment) {
                     // handling this is a bit funny, since we're not zooming in from 0 but rather
                     // from the previous value that the zoom out got interrupted at by the current segment
 
                     let min = InterpolatedZoom::new_with_easing(
                         SegmentsCursor::new(segment.start, cursor.segments),
+                        focus,
                         ease_in,
                         ease_out,
                     );
@@ -211,13 +351,164 @@ impl InterpolatedZoom {
     }
 }
 
+// Per-frame snapshot of the zoom state, for diagnostics overlays in the editor
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomDebugInfo {
+    pub time: f64,
+    pub segment_index: Option<u32>,
+    pub prev_segment_index: Option<u32>,
+    pub t: f64,
+    pub display_amount: f64,
+    pub top_left: XY<f64>,
+    pub bottom_right: XY<f64>,
+    pub auto_focus: XY<f64>,
+}
+
+impl ZoomDebugInfo {
+    pub fn new(cursor: &SegmentsCursor, zoom: &InterpolatedZoom, auto_focus: XY<f64>) -> Self {
+        let index_of = |segment: Option<&ZoomSegment>| {
+            segment.and_then(|segment| {
+                cursor
+                    .segments
+                    .iter()
+                    .position(|s| std::ptr::eq(s, segment))
+                    .map(|i| i as u32)
+            })
+        };
+
+        Self {
+            time: cursor.time,
+            segment_index: index_of(cursor.segment),
+            prev_segment_index: index_of(cursor.prev_segment),
+            t: zoom.t,
+            display_amount: zoom.display_amount(),
+            top_left: zoom.bounds.top_left,
+            bottom_right: zoom.bounds.bottom_right,
+            auto_focus,
+        }
+    }
+}
+
+const FOLLOW_STEP: f64 = 1.0 / 60.0;
+
+// The Auto zoom viewport center over a segment, simulated once from the segment's start
+// so each frame only has to look it up.
+// The center stays put while the target is inside the dead zone, and otherwise moves
+// just enough to bring the target back to the zone's edge, at no more than max_pan_velocity.
+#[derive(Debug)]
+pub struct AutoFocusTrack {
+    // (time, focus) every FOLLOW_STEP from the start, and at the end
+    samples: Vec<(f64, XY<f64>)>,
+}
+
+impl AutoFocusTrack {
+    pub fn simulate(
+        start: f64,
+        end: f64,
+        config: &AutoZoomConfiguration,
+        target_at: impl Fn(f64) -> Option<XY<f64>>,
+    ) -> Option<Self> {
+        let mut focus = target_at(start)?;
+        let mut t = start;
+        let mut samples = vec![(t, focus)];
+
+        while t < end {
+            let dt = FOLLOW_STEP.min(end - t);
+            t += dt;
+
+            if let Some(target) = target_at(t) {
+                let delta = target - focus;
+                let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+
+                if distance > config.dead_zone_radius && distance > 0.0 {
+                    let mut step = distance - config.dead_zone_radius.max(0.0);
+                    if config.max_pan_velocity > 0.0 {
+                        step = step.min(config.max_pan_velocity * dt);
+                    }
+
+                    focus = focus + delta * (step / distance);
+                }
+            }
+
+            samples.push((t, focus));
+        }
+
+        Some(Self { samples })
+    }
+
+    // The focus at `time`, between the samples either side of it. Times outside the
+    // simulated span get the nearest end.
+    pub fn at(&self, time: f64) -> XY<f64> {
+        let index = self.samples.partition_point(|(t, _)| *t <= time);
+        let Some(&(next_time, next)) = self.samples.get(index) else {
+            return self.samples[self.samples.len() - 1].1;
+        };
+        let Some(&(prev_time, prev)) = index.checked_sub(1).map(|i| &self.samples[i]) else {
+            return next;
+        };
+
+        prev + (next - prev) * ((time - prev_time) / (next_time - prev_time))
+    }
+}
+
+// Amount and position `time` seconds into the segment. Keyframes are approached from the
+// previous one (or the segment's own amount and mode at its start) using their easing.
+fn keyframe_state(
+    segment: &ZoomSegment,
+    time: f64,
+    base_position: Option<XY<f64>>,
+) -> (f64, Option<XY<f64>>) {
+    let mut amount = segment.amount;
+    let mut position = base_position;
+    let mut prev_time = 0.0;
+
+    for keyframe in &segment.keyframes {
+        let target_position = keyframe.position.or(position);
+
+        if time >= keyframe.time {
+            amount = keyframe.amount;
+            position = target_position;
+            prev_time = keyframe.time;
+            continue;
+        }
+
+        let span = keyframe.time - prev_time;
+        let t = if span > 0.0 {
+            ease(keyframe.easing, t_clamp((time - prev_time) / span))
+        } else {
+            1.0
+        };
+
+        amount += (keyframe.amount - amount) * t;
+        position = match (position, target_position) {
+            (Some(from), Some(to)) => Some(from + (to - from) * t),
+            (from, to) => to.or(from),
+        };
+        break;
+    }
+
+    (amount, position)
+}
+
+fn ease(easing: ZoomEasing, t: f64) -> f64 {
+    let curve = match easing {
+        ZoomEasing::Linear => return t,
+        ZoomEasing::EaseIn => bezier_easing::bezier_easing(0.42, 0.0, 1.0, 1.0),
+        ZoomEasing::EaseOut => bezier_easing::bezier_easing(0.0, 0.0, 0.58, 1.0),
+        ZoomEasing::EaseInOut => bezier_easing::bezier_easing(0.42, 0.0, 0.58, 1.0),
+    };
+
+    curve.map(|f| f(t as f32) as f64).unwrap_or(t)
+}
+
 fn t_clamp(v: f64) -> f64 {
     v.clamp(0.0, 1.0)
 }
 
 #[cfg(test)]
 mod test {
-    use cap_project::ZoomMode;
+    use cap_project::{ZoomKeyframe, ZoomMode};
 
     use super::*;
 
@@ -244,7 +535,12 @@ mod test {
     }
 
     fn test_interp((time, segments): (f64, &[ZoomSegment]), expected: InterpolatedZoom) {
-        let actual = InterpolatedZoom::new_with_easing(c(time, segments), |t| t, |t| t);
+        let actual = InterpolatedZoom::new_with_easing(
+            c(time, segments),
+            ZoomFocus::default(),
+            |t| t,
+            |t| t,
+        );
 
         assert_f64_near!(actual.t, expected.t, "t");
 
@@ -264,6 +560,9 @@ mod test {
             end: 4.0,
             amount: 2.0,
             mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![],
         }];
 
         test_interp(
@@ -339,12 +638,18 @@ mod test {
                 end: 4.0,
                 amount: 2.0,
                 mode: ZoomMode::Manual { x: 0.0, y: 0.0 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
             ZoomSegment {
                 start: 4.0,
                 end: 6.0,
                 amount: 4.0,
                 mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
         ];
 
@@ -386,12 +691,18 @@ mod test {
                 end: 4.0,
                 amount: 2.0,
                 mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
             ZoomSegment {
                 start: 4.0 + ZOOM_DURATION * 0.75,
                 end: 6.0,
                 amount: 4.0,
                 mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
         ];
 
@@ -440,12 +751,18 @@ mod test {
                 end: 4.0,
                 amount: 2.0,
                 mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
             ZoomSegment {
                 start: 7.0,
                 end: 9.0,
                 amount: 4.0,
                 mode: ZoomMode::Manual { x: 0.0, y: 0.0 },
+                transition_duration_in: None,
+                transition_duration_out: None,
+                keyframes: vec![],
             },
         ];
 
@@ -492,4 +809,246 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn custom_transition_durations() {
+        let segments = vec![ZoomSegment {
+            start: 2.0,
+            end: 4.0,
+            amount: 2.0,
+            mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+            transition_duration_in: Some(0.5),
+            transition_duration_out: Some(2.0),
+            keyframes: vec![],
+        }];
+
+        test_interp(
+            (2.25, &segments),
+            InterpolatedZoom {
+                t: 0.5,
+                bounds: SegmentBounds::new(XY::new(-0.25, -0.25), XY::new(1.25, 1.25)),
+            },
+        );
+        test_interp(
+            (2.5, &segments),
+            InterpolatedZoom {
+                t: 1.0,
+                bounds: SegmentBounds::new(XY::new(-0.5, -0.5), XY::new(1.5, 1.5)),
+            },
+        );
+        test_interp(
+            (5.0, &segments),
+            InterpolatedZoom {
+                t: 0.5,
+                bounds: SegmentBounds::new(XY::new(-0.25, -0.25), XY::new(1.25, 1.25)),
+            },
+        );
+        test_interp(
+            (6.0, &segments),
+            InterpolatedZoom {
+                t: 0.0,
+                bounds: SegmentBounds::default(),
+            },
+        );
+    }
+
+    #[test]
+    fn window_segment_centers_window() {
+        let segments = vec![ZoomSegment {
+            start: 2.0,
+            end: 4.0,
+            amount: 4.0,
+            mode: ZoomMode::Window { window_id: 1 },
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![],
+        }];
+
+        let focus = ZoomFocus {
+            segment_window: Some([0.55, 0.25, 0.45, 0.45]),
+            ..Default::default()
+        };
+        let zoom = InterpolatedZoom::new_with_easing(c(3.0, &segments), focus, |t| t, |t| t);
+
+        // fitted to 2x and clamped so the display's right edge stays in frame
+        assert_f64_near!(zoom.display_amount(), 2.0, "amount");
+        assert_f64_near!(zoom.bounds.top_left.x, -1.0, "top_left.x");
+        assert_f64_near!(zoom.bounds.top_left.y, -0.45, "top_left.y");
+        assert_f64_near!(zoom.bounds.bottom_right.x, 1.0, "bottom_right.x");
+    }
+
+    #[test]
+    fn keyframes_change_amount_within_segment() {
+        let segments = vec![ZoomSegment {
+            start: 2.0,
+            end: 8.0,
+            amount: 2.0,
+            mode: ZoomMode::Manual { x: 0.5, y: 0.5 },
+            transition_duration_in: None,
+            transition_duration_out: None,
+            keyframes: vec![ZoomKeyframe {
+                time: 2.0,
+                amount: 3.0,
+                position: None,
+                easing: ZoomEasing::Linear,
+            }],
+        }];
+        let zoom_at = |time| {
+            InterpolatedZoom::new_with_easing(
+                c(time, &segments),
+                ZoomFocus::default(),
+                |t| t,
+                |t| t,
+            )
+        };
+
+        // halfway from the segment's own amount to the keyframe's
+        assert_f64_near!(zoom_at(3.0).display_amount(), 2.5, "ramping");
+        assert_f64_near!(zoom_at(5.0).display_amount(), 3.0, "held");
+        // zooms out from the last keyframe's amount
+        assert_f64_near!(zoom_at(8.5).display_amount(), 2.0, "zooming out");
+    }
+
+    #[test]
+    fn pan_covers_output_and_glides() {
+        let segments = vec![ZoomSegment {
+            start: 0.0,
+            end: 10.0,
+            amount: 1.0,
+            mode: ZoomMode::Pan { x: 0.0, y: 0.5 },
+            transition_duration_in: Some(0.5),
+            transition_duration_out: None,
+            keyframes: vec![ZoomKeyframe {
+                time: 8.0,
+                amount: 1.0,
+                position: Some(XY::new(1.0, 0.5)),
+                easing: ZoomEasing::Linear,
+            }],
+        }];
+        // a 32:9 display letterboxed into a 16:9 output
+        let focus = ZoomFocus {
+            cover_amount: 2.0,
+            ..Default::default()
+        };
+        let zoom_at = |time| {
+            InterpolatedZoom::new_with_easing(c(time, &segments), focus, |t| t, |t| t).bounds
+        };
+
+        // scaled to fill the output's height, starting from the left half
+        let bounds = zoom_at(1.0);
+        assert_f64_near!(bounds.top_left.x, -0.125, "start x");
+        assert_f64_near!(bounds.top_left.y, -0.5, "start y");
+        assert_f64_near!(
+            bounds.bottom_right.x - bounds.top_left.x,
+            2.0,
+            "start width"
+        );
+
+        // halfway across without changing scale
+        let bounds = zoom_at(4.0);
+        assert_f64_near!(bounds.top_left.x, -0.5, "halfway x");
+        assert_f64_near!(
+            bounds.bottom_right.x - bounds.top_left.x,
+            2.0,
+            "halfway width"
+        );
+
+        // ends on the right half
+        assert_f64_near!(zoom_at(9.0).top_left.x, -1.0, "end x");
+    }
+
+    #[test]
+    fn clamp_to_letterboxed_viewport() {
+        // a wide display letterboxed into a tall output: the viewport spans
+        // 1 display width horizontally but 3 display heights vertically
+        let start = XY::new(0.0, -1.0);
+        let end = XY::new(1.0, 2.0);
+
+        let bounds =
+            SegmentBounds::new(XY::new(0.5, 0.5), XY::new(2.5, 2.5)).clamp_to_viewport(start, end);
+
+        // horizontally the zoom fills the viewport, so it's pulled back to cover the left edge
+        assert_f64_near!(bounds.top_left.x, 0.0, "top_left.x");
+        assert_f64_near!(bounds.bottom_right.x, 2.0, "bottom_right.x");
+        // vertically it's still smaller than the viewport, so it's kept inside it
+        assert_f64_near!(bounds.top_left.y, 0.0, "top_left.y");
+        assert_f64_near!(bounds.bottom_right.y, 2.0, "bottom_right.y");
+
+        let bounds = SegmentBounds::new(XY::new(-1.5, -1.5), XY::new(1.5, 1.5))
+            .clamp_to_viewport(start, end);
+
+        // pushed back inside the viewport without changing scale
+        assert_f64_near!(bounds.top_left.y, -1.0, "top_left.y");
+        assert_f64_near!(bounds.bottom_right.y, 2.0, "bottom_right.y");
+    }
+
+    #[test]
+    fn expand_into_padding_covers_viewport_at_full_zoom() {
+        // a display with padding on every side, more of it vertically
+        let start = XY::new(-0.1, -0.2);
+        let end = XY::new(1.1, 1.2);
+
+        let unzoomed = SegmentBounds::default().expand_into_padding(0.0, start, end);
+        assert_f64_near!(unzoomed.top_left.x, 0.0, "unzoomed top_left.x");
+        assert_f64_near!(unzoomed.bottom_right.y, 1.0, "unzoomed bottom_right.y");
+
+        let halfway = SegmentBounds::default().expand_into_padding(0.5, start, end);
+        assert_f64_near!(halfway.top_left.y, -0.1, "halfway top_left.y");
+        assert_f64_near!(halfway.bottom_right.y, 1.1, "halfway bottom_right.y");
+
+        // covers the taller axis exactly and overflows the other
+        let zoomed = SegmentBounds::default().expand_into_padding(1.0, start, end);
+        assert_f64_near!(zoomed.top_left.y, -0.2, "zoomed top_left.y");
+        assert_f64_near!(zoomed.bottom_right.y, 1.2, "zoomed bottom_right.y");
+        assert_f64_near!(zoomed.top_left.x, -0.2, "zoomed top_left.x");
+    }
+
+    #[test]
+    fn follow_ignores_movement_inside_dead_zone() {
+        let config = AutoZoomConfiguration {
+            dead_zone_radius: 0.1,
+            max_pan_velocity: 0.0,
+            ..Default::default()
+        };
+
+        let focus = AutoFocusTrack::simulate(0.0, 1.0, &config, |t| {
+            Some(if t < 0.5 {
+                XY::new(0.5, 0.5)
+            } else {
+                XY::new(0.55, 0.45)
+            })
+        })
+        .unwrap()
+        .at(1.0);
+
+        assert_f64_near!(focus.x, 0.5);
+        assert_f64_near!(focus.y, 0.5);
+    }
+
+    #[test]
+    fn follow_limits_pan_velocity() {
+        let config = AutoZoomConfiguration {
+            dead_zone_radius: 0.0,
+            max_pan_velocity: 0.5,
+            ..Default::default()
+        };
+        let target = |t: f64| {
+            Some(if t <= 0.0 {
+                XY::new(0.0, 0.0)
+            } else {
+                XY::new(1.0, 0.0)
+            })
+        };
+
+        let track = AutoFocusTrack::simulate(0.0, 3.0, &config, target).unwrap();
+
+        let focus = track.at(1.0);
+        assert_f64_near!(focus.x, 0.5);
+        assert_f64_near!(focus.y, 0.0);
+
+        assert_f64_near!(track.at(0.5).x, 0.25);
+        assert_f64_near!(track.at(3.0).x, 1.0);
+        // past the end it holds the last focus
+        assert_f64_near!(track.at(10.0).x, 1.0);
+    }
 }
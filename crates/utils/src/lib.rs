@@ -1,4 +1,4 @@
-use std::future::Future;
+use std::{future::Future, path::Path};
 
 use tracing::Instrument;
 
@@ -33,6 +33,32 @@ pub fn format_error_message(error_code: u32) -> String {
     }
 }
 
+/// Bytes that can still be written to the disk `path` is on.
+#[cfg(unix)]
+pub fn available_disk_space(path: &Path) -> std::io::Result<u64> {
+    let stat = nix::sys::statvfs::statvfs(path)?;
+    Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+/// Bytes that can still be written to the disk `path` is on.
+#[cfg(windows)]
+pub fn available_disk_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::{core::PCWSTR, Win32::Storage::FileSystem::GetDiskFreeSpaceExW};
+
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<_>>();
+    let mut available = 0;
+
+    unsafe { GetDiskFreeSpaceExW(PCWSTR(path.as_ptr()), Some(&mut available), None, None) }
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    Ok(available)
+}
+
 /// Wrapper around tokio::spawn that inherits the current tracing subscriber and span.
 pub fn spawn_actor<F>(future: F) -> tokio::task::JoinHandle<F::Output>
 where